@@ -29,21 +29,155 @@ export interface DesktopAgentConfig {
 }
 
 const AGENT_PORT = 5111;
-const AGENT_URL = `http://127.0.0.1:${AGENT_PORT}`;
+const AGENT_TLS_PORT = 5443;
+const AGENT_HTTP_URL = `http://127.0.0.1:${AGENT_PORT}`;
+const AGENT_HTTPS_URL = `https://127.0.0.1:${AGENT_TLS_PORT}`;
 
-export async function detectDesktopAgent(): Promise<DesktopAgentStatus | null> {
+// Whichever endpoint answered detection; the rest of the calls follow it
+let AGENT_URL = AGENT_HTTP_URL;
+const TOKEN_STORAGE_KEY = "spk-desktop-agent-token";
+
+function getAgentToken(): string | null {
+  if (typeof window === "undefined") return null;
+  return window.localStorage.getItem(TOKEN_STORAGE_KEY);
+}
+
+function authHeaders(): Record<string, string> {
+  const token = getAgentToken();
+  return token ? { Authorization: `Bearer ${token}` } : {};
+}
+
+export function isPairedWithDesktopAgent(): boolean {
+  return getAgentToken() !== null;
+}
+
+/**
+ * Asks the agent for its API token. The agent shows a native approval
+ * dialog, so this resolves only after the user allows or denies it.
+ */
+export async function pairWithDesktopAgent(appName = "SPK Network"): Promise<boolean> {
+  try {
+    const response = await fetch(`${AGENT_URL}/api/v1/pair`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify({ appName }),
+    });
+    if (!response.ok) return false;
+
+    const { token } = await response.json();
+    window.localStorage.setItem(TOKEN_STORAGE_KEY, token);
+    return true;
+  } catch {
+    return false;
+  }
+}
+
+export type DesktopAgentErrorCode =
+  | "INVALID_REQUEST"
+  | "UNAUTHORIZED"
+  | "FORBIDDEN"
+  | "APPROVAL_PENDING"
+  | "NOT_FOUND"
+  | "CONFLICT"
+  | "UNSUPPORTED_MEDIA_TYPE"
+  | "RATE_LIMITED"
+  | "INTERNAL"
+  | "IPFS_ERROR"
+  | "DAEMON_UNAVAILABLE"
+  | "DAEMON_TIMEOUT"
+  | "SPK_API_UNAVAILABLE"
+  | "STORAGE_FULL";
+
+export interface DesktopAgentError {
+  code: DesktopAgentErrorCode;
+  message: string;
+  retryable: boolean;
+  details: Record<string, unknown> | null;
+}
+
+let lastError: DesktopAgentError | null = null;
+
+/**
+ * The error from the most recent failed call, for showing the user why a
+ * pin or upload didn't happen. Cleared by the next successful call.
+ */
+export function getLastDesktopAgentError(): DesktopAgentError | null {
+  return lastError;
+}
+
+export function describeDesktopAgentError(error: DesktopAgentError): string {
+  switch (error.code) {
+    case "UNAUTHORIZED":
+      return "The desktop agent no longer trusts this app. Pair with it again.";
+    case "APPROVAL_PENDING":
+      return "Approve this site in the SPK Desktop Agent window, then try again.";
+    case "STORAGE_FULL":
+      return "The desktop agent's storage quota is full. Raise the quota or free up space.";
+    case "DAEMON_UNAVAILABLE":
+    case "DAEMON_TIMEOUT":
+      return "The IPFS node in the desktop agent isn't responding. It may still be starting.";
+    case "RATE_LIMITED":
+      return "Too many requests to the desktop agent. Wait a moment and try again.";
+    default:
+      return error.message;
+  }
+}
+
+// Keeps the error envelope for the caller. A 401 means the token was revoked in the agent; pair again
+async function handleErrorResponse(response: Response): Promise<void> {
+  if (response.ok) {
+    lastError = null;
+    return;
+  }
+  if (response.status === 401 && typeof window !== "undefined") {
+    window.localStorage.removeItem(TOKEN_STORAGE_KEY);
+  }
+  try {
+    const body = await response.clone().json();
+    lastError = {
+      code: body.code ?? "INTERNAL",
+      message: body.message ?? body.error ?? response.statusText,
+      retryable: Boolean(body.retryable),
+      details: body.details ?? null,
+    };
+  } catch {
+    lastError = { code: "INTERNAL", message: response.statusText, retryable: false, details: null };
+  }
+}
+
+async function probeAgent(baseUrl: string): Promise<Response | null> {
   try {
     const controller = new AbortController();
     const timeout = setTimeout(() => controller.abort(), 2000);
-
-    const response = await fetch(`${AGENT_URL}/api/status`, {
+    const response = await fetch(`${baseUrl}/api/v1/status`, {
       method: "GET",
+      headers: authHeaders(),
       signal: controller.signal,
     });
-
     clearTimeout(timeout);
+    return response.ok ? response : null;
+  } catch {
+    return null;
+  }
+}
+
+export async function detectDesktopAgent(): Promise<DesktopAgentStatus | null> {
+  try {
+    // From an HTTPS page, prefer the agent's HTTPS listener; some browsers
+    // block plain-HTTP localhost requests as mixed content
+    const candidates = typeof window !== "undefined" && window.location.protocol === "https:"
+      ? [AGENT_HTTPS_URL, AGENT_HTTP_URL]
+      : [AGENT_HTTP_URL, AGENT_HTTPS_URL];
 
-    if (!response.ok) {
+    let response: Response | null = null;
+    for (const candidate of candidates) {
+      response = await probeAgent(candidate);
+      if (response) {
+        AGENT_URL = candidate;
+        break;
+      }
+    }
+    if (!response) {
       return null;
     }
 
@@ -65,10 +199,12 @@ export async function detectDesktopAgent(): Promise<DesktopAgentStatus | null> {
 
 export async function getDesktopAgentConfig(): Promise<DesktopAgentConfig | null> {
   try {
-    const response = await fetch(`${AGENT_URL}/api/config`, {
+    const response = await fetch(`${AGENT_URL}/api/v1/config`, {
       method: "GET",
+      headers: authHeaders(),
     });
 
+    await handleErrorResponse(response);
     if (!response.ok) return null;
     return response.json();
   } catch {
@@ -80,11 +216,12 @@ export async function updateDesktopAgentConfig(
   config: Partial<DesktopAgentConfig>
 ): Promise<boolean> {
   try {
-    const response = await fetch(`${AGENT_URL}/api/config`, {
+    const response = await fetch(`${AGENT_URL}/api/v1/config`, {
       method: "POST",
-      headers: { "Content-Type": "application/json" },
+      headers: { "Content-Type": "application/json", ...authHeaders() },
       body: JSON.stringify(config),
     });
+    await handleErrorResponse(response);
     return response.ok;
   } catch {
     return false;
@@ -93,42 +230,188 @@ export async function updateDesktopAgentConfig(
 
 export async function pinWithDesktopAgent(cid: string, name?: string): Promise<boolean> {
   try {
-    const response = await fetch(`${AGENT_URL}/api/pin`, {
+    const response = await fetch(`${AGENT_URL}/api/v1/pin`, {
       method: "POST",
-      headers: { "Content-Type": "application/json" },
+      headers: { "Content-Type": "application/json", ...authHeaders() },
       body: JSON.stringify({ cid, name }),
     });
+    await handleErrorResponse(response);
     return response.ok;
   } catch {
     return false;
   }
 }
 
+export interface DesktopAgentAddResult {
+  cid: string;
+  name: string;
+  size: number;
+  carSize: number;
+}
+
+/**
+ * Adds a file through the agent rather than the Kubo API, so it keeps
+ * working when the agent moves Kubo to other ports.
+ */
+export async function addWithDesktopAgent(file: File): Promise<DesktopAgentAddResult | null> {
+  try {
+    const body = new FormData();
+    body.append("file", file, file.name);
+    const response = await fetch(`${AGENT_URL}/api/v1/add`, {
+      method: "POST",
+      headers: authHeaders(),
+      body,
+    });
+    await handleErrorResponse(response);
+    if (!response.ok) return null;
+    return response.json();
+  } catch {
+    return null;
+  }
+}
+
 export async function unpinFromDesktopAgent(cid: string): Promise<boolean> {
   try {
-    const response = await fetch(`${AGENT_URL}/api/unpin`, {
+    const response = await fetch(`${AGENT_URL}/api/v1/unpin`, {
       method: "POST",
-      headers: { "Content-Type": "application/json" },
+      headers: { "Content-Type": "application/json", ...authHeaders() },
       body: JSON.stringify({ cid }),
     });
+    await handleErrorResponse(response);
     return response.ok;
   } catch {
     return false;
   }
 }
 
-export async function getDesktopAgentPins(): Promise<Array<{cid: string; name: string; size: number}>> {
+export interface DesktopAgentPin {
+  cid: string;
+  type: string;
+  instance: string;
+  name: string | null;
+  labels: string[];
+  pinnedAt: string | null;
+}
+
+export interface DesktopAgentPinPage {
+  pins: DesktopAgentPin[];
+  total: number;
+  offset: number;
+  limit: number;
+  // Pass as `cursor` to get the next page; null on the last one
+  nextCursor: string | null;
+}
+
+export async function getDesktopAgentPins(
+  options: {
+    type?: "recursive" | "direct" | "indirect" | "all";
+    label?: string;
+    sort?: "cid" | "-cid" | "name" | "-name" | "pinnedAt" | "-pinnedAt";
+    cursor?: string;
+    offset?: number;
+    limit?: number;
+  } = {}
+): Promise<DesktopAgentPinPage> {
+  const params = new URLSearchParams();
+  Object.entries(options).forEach(([key, value]) => {
+    if (value !== undefined) params.set(key, String(value));
+  });
+
   try {
-    const response = await fetch(`${AGENT_URL}/api/pins`, {
+    const response = await fetch(`${AGENT_URL}/api/v1/pins?${params}`, {
       method: "GET",
+      headers: authHeaders(),
     });
-    if (!response.ok) return [];
+
+    await handleErrorResponse(response);
+    if (!response.ok) return { pins: [], total: 0, offset: 0, limit: 0, nextCursor: null };
     return response.json();
   } catch {
-    return [];
+    return { pins: [], total: 0, offset: 0, limit: 0, nextCursor: null };
   }
 }
 
+export async function labelDesktopAgentPin(cid: string, name: string | null, labels: string[]): Promise<boolean> {
+  try {
+    const response = await fetch(`${AGENT_URL}/api/v1/pins`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json", ...authHeaders() },
+      body: JSON.stringify({ cid, name, labels }),
+    });
+    await handleErrorResponse(response);
+    return response.ok;
+  } catch {
+    return false;
+  }
+}
+
+export type DesktopAgentEventTopic = "status" | "challenge" | "pins" | "earnings";
+
+export interface DesktopAgentEvent {
+  id: number;
+  topic: DesktopAgentEventTopic;
+  event: string;
+  data: any;
+  at: string;
+}
+
+/**
+ * Streams agent events over a WebSocket instead of polling, falling back to
+ * Server-Sent Events when a proxy won't let the WebSocket open. `onResync`
+ * is called when the agent dropped messages because we fell behind, so
+ * callers can refetch state over REST. Returns a function that closes the
+ * stream.
+ */
+export function subscribeToDesktopAgentEvents(
+  topics: DesktopAgentEventTopic[],
+  onEvent: (event: DesktopAgentEvent) => void,
+  onResync?: () => void
+): () => void {
+  // Neither a WebSocket nor an EventSource can send headers, so the token rides in the query
+  const query = new URLSearchParams({ topics: topics.join(","), token: getAgentToken() ?? "" });
+  const socket = new WebSocket(`${AGENT_URL.replace(/^http/, "ws")}/ws/events?${query}`);
+  let close = () => socket.close();
+  let opened = false;
+  let closed = false;
+
+  socket.onopen = () => {
+    opened = true;
+  };
+
+  socket.onerror = () => {
+    if (opened || closed || typeof EventSource === "undefined") return;
+    const source = new EventSource(`${AGENT_URL}/api/v1/events?${query}`);
+    close = () => source.close();
+    source.onmessage = (message) => {
+      try {
+        onEvent(JSON.parse(message.data) as DesktopAgentEvent);
+      } catch {
+        // Ignore malformed frames
+      }
+    };
+    source.addEventListener("resync", () => onResync?.());
+  };
+
+  socket.onmessage = (message) => {
+    let payload: any;
+    try {
+      payload = JSON.parse(message.data);
+    } catch {
+      return;
+    }
+    if (payload.type === "dropped") {
+      onResync?.();
+    } else if (payload.topic) {
+      onEvent(payload as DesktopAgentEvent);
+    }
+  };
+
+  return () => {
+    closed = true;
+    close();
+  };
+}
+
 export function formatUptime(seconds: number): string {
   const days = Math.floor(seconds / 86400);
   const hours = Math.floor((seconds % 86400) / 3600);
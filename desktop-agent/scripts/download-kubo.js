@@ -8,6 +8,7 @@ const https = require('https');
 const fs = require('fs');
 const path = require('path');
 const { execSync } = require('child_process');
+const crypto = require('crypto');
 
 const KUBO_VERSION = 'v0.24.0';
 const BINARIES_DIR = path.join(__dirname, '..', 'kubo-bin');
@@ -103,6 +104,10 @@ async function main() {
     fs.chmodSync(binaryDest, 0o755);
   }
 
+  // The agent checks the bundled binary against this on every launch
+  const checksum = crypto.createHash('sha256').update(fs.readFileSync(binaryDest)).digest('hex');
+  fs.writeFileSync(`${binaryDest}.sha256`, `${checksum}\n`);
+
   console.log(`Kubo binary saved to: ${binaryDest}`);
   console.log('Done!');
 }
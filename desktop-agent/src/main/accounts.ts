@@ -0,0 +1,138 @@
+import { ConfigStore } from './config';
+
+export type NotificationKind = 'challenges' | 'rewards' | 'contracts';
+
+export type NotificationPreferences = Record<NotificationKind, boolean>;
+
+export const DEFAULT_NOTIFICATION_PREFERENCES: NotificationPreferences = {
+  challenges: true,
+  rewards: true,
+  contracts: true,
+};
+
+// A Hive account this node stores for; the active one is hiveUsername
+export interface LinkedAccount {
+  username: string;
+  // Shown in the switcher, e.g. whose account it is
+  label: string;
+  notifications: NotificationPreferences;
+  // Where the node's rewards go when not to the account itself, e.g. a cold wallet
+  payoutAccount?: string | null;
+  addedAt: string;
+}
+
+export function validateNotificationPreferences(prefs: Partial<NotificationPreferences>): string[] {
+  const errors: string[] = [];
+  for (const [kind, enabled] of Object.entries(prefs)) {
+    if (!(kind in DEFAULT_NOTIFICATION_PREFERENCES)) errors.push(`Unknown notification kind ${kind}`);
+    else if (typeof enabled !== 'boolean') errors.push(`${kind} must be a boolean`);
+  }
+  return errors;
+}
+
+/**
+ * The Hive accounts linked to this agent, for a server shared by several
+ * people. One is active at a time: it signs, registers the node and takes
+ * new contracts unless they name another. Contracts and earnings stay with
+ * the account they were taken for, and each account chooses which
+ * notifications it wants.
+ */
+export class AccountDirectory {
+  private config: ConfigStore;
+
+  constructor(config: ConfigStore) {
+    this.config = config;
+  }
+
+  list(): LinkedAccount[] {
+    const { linkedAccounts, hiveUsername } = this.config.getConfig();
+    // Configs from before multi-account only know hiveUsername
+    if (hiveUsername && !linkedAccounts.some((account) => account.username === hiveUsername)) {
+      return [this.newAccount(hiveUsername), ...linkedAccounts];
+    }
+    return linkedAccounts;
+  }
+
+  get(username: string): LinkedAccount | null {
+    return this.list().find((account) => account.username === username) ?? null;
+  }
+
+  getActive(): string | null {
+    return this.config.getConfig().hiveUsername;
+  }
+
+  // Adds the account if new, keeping an existing entry's label and preferences
+  link(username: string, label?: string): LinkedAccount {
+    const accounts = this.list();
+    const existing = accounts.find((account) => account.username === username);
+    if (existing) {
+      if (label !== undefined) existing.label = label;
+      this.config.setConfig({ linkedAccounts: accounts });
+      return existing;
+    }
+    const account = this.newAccount(username, label);
+    this.config.setConfig({ linkedAccounts: [...accounts, account] });
+    console.log(`[Hive] Linked @${username}`);
+    return account;
+  }
+
+  /**
+   * Forgets an account. Its contracts and earnings history stay on disk;
+   * the active account moves to the next linked one, if any.
+   */
+  unlink(username: string): LinkedAccount[] {
+    const accounts = this.list().filter((account) => account.username !== username);
+    const update: { linkedAccounts: LinkedAccount[]; hiveUsername?: string | null } = { linkedAccounts: accounts };
+    if (this.getActive() === username) update.hiveUsername = accounts[0]?.username ?? null;
+    this.config.setConfig(update);
+    console.log(`[Hive] Unlinked @${username}`);
+    return accounts;
+  }
+
+  switchTo(username: string): LinkedAccount {
+    const account = this.get(username);
+    if (!account) throw new Error(`@${username} is not linked to this agent`);
+    this.config.setConfig({ hiveUsername: username });
+    console.log(`[Hive] Active account is now @${username}`);
+    return account;
+  }
+
+  setNotifications(username: string, prefs: Partial<NotificationPreferences>): LinkedAccount {
+    const accounts = this.list();
+    const account = accounts.find((entry) => entry.username === username);
+    if (!account) throw new Error(`@${username} is not linked to this agent`);
+    account.notifications = { ...account.notifications, ...prefs };
+    this.config.setConfig({ linkedAccounts: accounts });
+    return account;
+  }
+
+  // Validate the payout account first; HiveAccount.setPayoutAccount does
+  setPayoutAccount(username: string, payoutAccount: string | null): LinkedAccount {
+    const accounts = this.list();
+    const account = accounts.find((entry) => entry.username === username);
+    if (!account) throw new Error(`@${username} is not linked to this agent`);
+    account.payoutAccount = payoutAccount;
+    this.config.setConfig({ linkedAccounts: accounts });
+    return account;
+  }
+
+  // Null when rewards go to the account itself
+  getPayoutAccount(username: string | null = this.getActive()): string | null {
+    return username ? this.get(username)?.payoutAccount ?? null : null;
+  }
+
+  // Notifications not tied to an account follow the active account's choice
+  wantsNotification(username: string | null, kind: NotificationKind): boolean {
+    const account = this.get(username ?? this.getActive() ?? '');
+    return account ? account.notifications[kind] : true;
+  }
+
+  private newAccount(username: string, label = ''): LinkedAccount {
+    return {
+      username,
+      label,
+      notifications: { ...DEFAULT_NOTIFICATION_PREFERENCES },
+      addedAt: new Date().toISOString(),
+    };
+  }
+}
@@ -0,0 +1,40 @@
+import { EventEmitter } from 'events';
+
+export type ActivityKind = 'challenge' | 'upload';
+
+/**
+ * Counts in-flight operations that must not be interrupted by maintenance
+ * work such as repo GC.
+ */
+export class ActivityTracker extends EventEmitter {
+  private active = new Map<ActivityKind, number>();
+
+  /**
+   * Marks an operation as started. Call the returned function when it ends.
+   */
+  begin(kind: ActivityKind): () => void {
+    this.active.set(kind, (this.active.get(kind) || 0) + 1);
+    let released = false;
+
+    return () => {
+      if (released) return;
+      released = true;
+      const remaining = (this.active.get(kind) || 1) - 1;
+      this.active.set(kind, remaining);
+      if (!this.isBusy()) {
+        this.emit('idle');
+      }
+    };
+  }
+
+  isBusy(kinds: ActivityKind[] = ['challenge', 'upload']): boolean {
+    return kinds.some((kind) => (this.active.get(kind) || 0) > 0);
+  }
+
+  getCounts(): Record<ActivityKind, number> {
+    return {
+      challenge: this.active.get('challenge') || 0,
+      upload: this.active.get('upload') || 0,
+    };
+  }
+}
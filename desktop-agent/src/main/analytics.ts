@@ -0,0 +1,240 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { app } from 'electron';
+import Database from 'better-sqlite3';
+import { StreamEvent } from './event-bus';
+import { DaemonRestartEvent } from './watchdog';
+import { MAX_PAGE_SIZE } from './query';
+
+const DAY_MS = 24 * 60 * 60 * 1000;
+const PRUNE_INTERVAL_MS = 6 * 60 * 60 * 1000;
+
+export type AnalyticsKind =
+  | 'agent.start' | 'agent.stop' | 'agent.crash' | 'agent.error'
+  | 'daemon.crash' | 'daemon.restart'
+  | 'challenge.passed' | 'challenge.failed';
+export const ANALYTICS_KINDS: AnalyticsKind[] = [
+  'agent.start', 'agent.stop', 'agent.crash', 'agent.error',
+  'daemon.crash', 'daemon.restart',
+  'challenge.passed', 'challenge.failed',
+];
+
+export interface AnalyticsSettings {
+  // Events are recorded locally while on
+  enabled: boolean;
+  retentionDays: number;
+  // Opt-in: nothing can be exported for the SPK developers until this is turned on
+  allowExport: boolean;
+}
+
+export const DEFAULT_ANALYTICS_SETTINGS: AnalyticsSettings = {
+  enabled: true,
+  retentionDays: 30,
+  allowExport: false,
+};
+
+export function validateAnalyticsSettings(settings: Partial<AnalyticsSettings>): string[] {
+  const errors: string[] = [];
+  for (const field of ['enabled', 'allowExport'] as const) {
+    if (settings[field] !== undefined && typeof settings[field] !== 'boolean') {
+      errors.push(`${field} must be a boolean`);
+    }
+  }
+  if (settings.retentionDays !== undefined
+    && (!Number.isInteger(settings.retentionDays) || settings.retentionDays < 1 || settings.retentionDays > 365)) {
+    errors.push('retentionDays must be between 1 and 365');
+  }
+  return errors;
+}
+
+export interface AnalyticsEvent {
+  id: number;
+  at: string;
+  kind: AnalyticsKind;
+  data: Record<string, unknown>;
+}
+
+export interface AnalyticsQuery {
+  kind?: AnalyticsKind;
+  limit?: number;
+}
+
+export interface AnalyticsStatus {
+  settings: AnalyticsSettings;
+  total: number;
+  oldest: string | null;
+  counts: Partial<Record<AnalyticsKind, number>>;
+}
+
+export function validateAnalyticsQuery(query: AnalyticsQuery): string[] {
+  const errors: string[] = [];
+  if (query.kind !== undefined && !ANALYTICS_KINDS.includes(query.kind)) {
+    errors.push(`kind must be one of ${ANALYTICS_KINDS.join(', ')}`);
+  }
+  if (query.limit !== undefined && (!Number.isInteger(query.limit) || query.limit < 1 || query.limit > MAX_PAGE_SIZE)) {
+    errors.push(`limit must be between 1 and ${MAX_PAGE_SIZE}`);
+  }
+  return errors;
+}
+
+// What an export keeps of each kind; CIDs, accounts, validators, addresses and error text never leave
+const EXPORTED_FIELDS: Record<AnalyticsKind, string[]> = {
+  'agent.start': ['version', 'platform', 'arch'],
+  'agent.stop': [],
+  'agent.crash': ['reason'],
+  'agent.error': ['name'],
+  'daemon.crash': ['code'],
+  'daemon.restart': ['reason', 'attempt', 'success'],
+  'challenge.passed': ['responseTime'],
+  'challenge.failed': ['responseTime'],
+};
+
+export interface AnalyticsExportResult {
+  path: string;
+  events: number;
+}
+
+/**
+ * Agent events for the diagnostics view, in ~/.spk-ipfs/analytics.db:
+ * starts and stops, crashes of the agent and the daemon, watchdog
+ * restarts and challenge outcomes. A start without a stop before it means
+ * the agent didn't exit cleanly, and is recorded as a crash. Events older
+ * than the retention are dropped. Nothing leaves the machine on its own;
+ * the anonymized export is off until the user opts in, and writes a file
+ * for them to send: fixed fields per kind only, times rounded to the hour,
+ * and a fresh random ID per export so two exports can't be linked.
+ */
+export class AnalyticsStore {
+  private db: Database.Database;
+  private getSettings: () => AnalyticsSettings;
+  private timer: NodeJS.Timeout | null = null;
+  private onError = (error: Error) => this.record('agent.error', { name: error.name, message: error.message });
+
+  constructor(dataDir: string, getSettings: () => AnalyticsSettings) {
+    this.getSettings = getSettings;
+    this.db = new Database(path.join(dataDir, 'analytics.db'));
+    this.db.pragma('journal_mode = WAL');
+    this.db.exec(`
+      CREATE TABLE IF NOT EXISTS events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        data TEXT NOT NULL
+      );
+      CREATE INDEX IF NOT EXISTS events_at ON events (at);
+      CREATE INDEX IF NOT EXISTS events_kind ON events (kind);
+    `);
+  }
+
+  start(): void {
+    if (this.timer) return;
+    const last = this.db.prepare(`
+      SELECT kind, at FROM events WHERE kind IN ('agent.start', 'agent.stop') ORDER BY id DESC LIMIT 1
+    `).get() as { kind: AnalyticsKind; at: string } | undefined;
+    if (last?.kind === 'agent.start') {
+      const { at } = this.db.prepare('SELECT MAX(at) AS at FROM events').get() as { at: string };
+      this.record('agent.crash', { reason: 'unclean-exit', lastEventAt: at });
+    }
+    this.record('agent.start', { version: app.getVersion(), platform: process.platform, arch: process.arch });
+    // Watches without changing how an uncaught exception ends the process
+    process.on('uncaughtExceptionMonitor', this.onError);
+    this.prune();
+    this.timer = setInterval(() => this.prune(), PRUNE_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+    process.off('uncaughtExceptionMonitor', this.onError);
+    this.record('agent.stop', {});
+  }
+
+  close(): void {
+    this.db.close();
+  }
+
+  record(kind: AnalyticsKind, data: Record<string, unknown>): void {
+    // A stop is always kept, or the next start would read as a crash
+    if (!this.getSettings().enabled && kind !== 'agent.stop') return;
+    try {
+      this.db.prepare('INSERT INTO events (at, kind, data) VALUES (?, ?, ?)')
+        .run(new Date().toISOString(), kind, JSON.stringify(data));
+    } catch (error: any) {
+      console.error('[SPK] Failed to record analytics event:', error.message);
+    }
+  }
+
+  // Challenge outcomes, from the event bus
+  observe(message: StreamEvent): void {
+    if (message.topic !== 'challenge' || !['passed', 'failed'].includes(message.event)) return;
+    const data = message.data as Record<string, unknown>;
+    this.record(message.event === 'passed' ? 'challenge.passed' : 'challenge.failed', data);
+  }
+
+  observeDaemonCrash(code: number | null): void {
+    this.record('daemon.crash', { code });
+  }
+
+  observeRestart(event: DaemonRestartEvent): void {
+    this.record('daemon.restart', { reason: event.reason, attempt: event.attempt, success: event.success, error: event.error ?? null });
+  }
+
+  // Newest first
+  list(query: AnalyticsQuery = {}): AnalyticsEvent[] {
+    const rows = this.db.prepare(`
+      SELECT * FROM events ${query.kind ? 'WHERE kind = @kind' : ''} ORDER BY id DESC LIMIT @limit
+    `).all({ ...(query.kind ? { kind: query.kind } : {}), limit: query.limit ?? 100 }) as any[];
+    return rows.map((row) => ({ id: row.id, at: row.at, kind: row.kind, data: JSON.parse(row.data) }));
+  }
+
+  getStatus(): AnalyticsStatus {
+    const rows = this.db.prepare('SELECT kind, COUNT(*) AS count FROM events GROUP BY kind').all() as Array<{ kind: AnalyticsKind; count: number }>;
+    const { oldest } = this.db.prepare('SELECT MIN(at) AS oldest FROM events').get() as { oldest: string | null };
+    return {
+      settings: this.getSettings(),
+      total: rows.reduce((sum, row) => sum + row.count, 0),
+      oldest,
+      counts: Object.fromEntries(rows.map((row) => [row.kind, row.count])),
+    };
+  }
+
+  purge(): number {
+    const { changes } = this.db.prepare('DELETE FROM events').run();
+    this.db.exec('VACUUM');
+    console.log(`[SPK] Purged ${changes} analytics events`);
+    return changes;
+  }
+
+  exportAnonymized(filePath: string): AnalyticsExportResult {
+    if (!this.getSettings().allowExport) {
+      throw new Error('Anonymized export is off; turn it on in the analytics settings first');
+    }
+    const rows = this.db.prepare('SELECT at, kind, data FROM events ORDER BY id').all() as Array<{ at: string; kind: AnalyticsKind; data: string }>;
+    const events = rows.map((row) => {
+      const data = JSON.parse(row.data);
+      const kept = Object.fromEntries((EXPORTED_FIELDS[row.kind] ?? [])
+        .filter((field) => data[field] !== undefined)
+        .map((field) => [field, data[field]]));
+      return { hour: `${row.at.slice(0, 13)}:00Z`, kind: row.kind, ...kept };
+    });
+    const body = {
+      exportId: crypto.randomUUID(),
+      exportedAt: `${new Date().toISOString().slice(0, 13)}:00Z`,
+      retentionDays: this.getSettings().retentionDays,
+      events,
+    };
+    fs.writeFileSync(filePath, JSON.stringify(body, null, 2));
+    console.log(`[SPK] Exported ${events.length} anonymized analytics events to ${filePath}`);
+    return { path: filePath, events: events.length };
+  }
+
+  private prune(): void {
+    const cutoff = new Date(Date.now() - this.getSettings().retentionDays * DAY_MS).toISOString();
+    try {
+      this.db.prepare('DELETE FROM events WHERE at < ?').run(cutoff);
+    } catch (error: any) {
+      console.error('[SPK] Failed to prune analytics events:', error.message);
+    }
+  }
+}
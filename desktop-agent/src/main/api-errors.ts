@@ -0,0 +1,102 @@
+import { Request, Response, NextFunction } from 'express';
+
+/**
+ * Every error the agent API can return. Clients switch on `code`; the
+ * message is for people and may change between versions.
+ */
+export const API_ERRORS = {
+  INVALID_REQUEST: { status: 400, retryable: false },
+  UNAUTHORIZED: { status: 401, retryable: false },
+  FORBIDDEN: { status: 403, retryable: false },
+  // The user hasn't answered the approval dialog yet
+  APPROVAL_PENDING: { status: 403, retryable: true },
+  NOT_FOUND: { status: 404, retryable: false },
+  CONFLICT: { status: 409, retryable: false },
+  UNSUPPORTED_MEDIA_TYPE: { status: 415, retryable: false },
+  RATE_LIMITED: { status: 429, retryable: true },
+  INTERNAL: { status: 500, retryable: false },
+  // Kubo answered, but with an error
+  IPFS_ERROR: { status: 502, retryable: false },
+  DAEMON_UNAVAILABLE: { status: 503, retryable: true },
+  DAEMON_TIMEOUT: { status: 504, retryable: true },
+  SPK_API_UNAVAILABLE: { status: 502, retryable: true },
+  STORAGE_FULL: { status: 507, retryable: false },
+} as const;
+
+export type ApiErrorCode = keyof typeof API_ERRORS;
+
+export interface ApiErrorBody {
+  code: ApiErrorCode;
+  message: string;
+  retryable: boolean;
+  details: Record<string, unknown> | null;
+  // Same as message; clients written before codes read this
+  error: string;
+}
+
+export class ApiError extends Error {
+  code: ApiErrorCode;
+  status: number;
+  retryable: boolean;
+  details: Record<string, unknown> | null;
+
+  constructor(code: ApiErrorCode, message: string, details: Record<string, unknown> | null = null) {
+    super(message);
+    this.name = 'ApiError';
+    this.code = code;
+    this.status = API_ERRORS[code].status;
+    this.retryable = API_ERRORS[code].retryable;
+    this.details = details;
+  }
+
+  toJSON(): ApiErrorBody {
+    return { code: this.code, message: this.message, retryable: this.retryable, details: this.details, error: this.message };
+  }
+}
+
+/**
+ * Maps whatever a handler caught onto an ApiError. Failures talking to
+ * Kubo are told apart so the web app can say "the node is down" rather
+ * than show an axios message.
+ */
+export function toApiError(error: any): ApiError {
+  if (error instanceof ApiError) return error;
+
+  if (error?.isAxiosError) {
+    if (error.response) {
+      const message = error.response.data?.Message || error.message;
+      return new ApiError('IPFS_ERROR', message, { ipfsStatus: error.response.status });
+    }
+    if (['ECONNABORTED', 'ETIMEDOUT'].includes(error.code)) {
+      return new ApiError('DAEMON_TIMEOUT', 'The IPFS daemon did not answer in time');
+    }
+    if (['ECONNREFUSED', 'ECONNRESET', 'EHOSTUNREACH'].includes(error.code)) {
+      return new ApiError('DAEMON_UNAVAILABLE', 'The IPFS daemon is not running');
+    }
+  }
+
+  return new ApiError('INTERNAL', error?.message || String(error));
+}
+
+export function sendError(res: Response, error: unknown, details?: Record<string, unknown>): Response {
+  const apiError = toApiError(error);
+  if (details) apiError.details = { ...apiError.details, ...details };
+  if (apiError.status >= 500 && apiError.code !== 'DAEMON_UNAVAILABLE') {
+    console.error(`[API] ${apiError.code}: ${apiError.message}`);
+  }
+  return res.status(apiError.status).json(apiError.toJSON());
+}
+
+// Last in the stack: malformed JSON bodies and anything a handler threw
+export function errorHandler() {
+  return (error: any, req: Request, res: Response, next: NextFunction) => {
+    if (res.headersSent) return next(error);
+    if (error?.type === 'entity.parse.failed') {
+      return sendError(res, new ApiError('INVALID_REQUEST', 'Request body is not valid JSON'));
+    }
+    if (error?.type === 'entity.too.large') {
+      return sendError(res, new ApiError('INVALID_REQUEST', 'Request body is too large'));
+    }
+    sendError(res, error);
+  };
+}
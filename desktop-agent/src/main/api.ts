@@ -1,53 +1,431 @@
-import express, { Express, Request, Response } from 'express';
+import express, { Express, Request, Response, Router, RequestHandler } from 'express';
+import * as fs from 'fs';
 import * as http from 'http';
+import * as https from 'https';
 import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
 import axios from 'axios';
+import busboy from 'busboy';
+import { Readable } from 'stream';
 import { KuboManager } from './kubo';
-import { ConfigStore } from './config';
+import { ConfigStore, EarningsHistoryEntry } from './config';
+import { ActivityTracker } from './activity';
+import { StorageMonitor } from './storage-monitor';
+import { ConnectivityMonitor } from './connectivity';
+import { KuboPool } from './kubo-pool';
+import { ApiAuth } from './auth';
+import { CorsPolicy } from './cors';
+import { EventStream } from './event-stream';
+import { EventBus } from './event-bus';
+import { SseStream } from './sse';
+import { ResumableUploads, UploadOffsetError, AddResult, addStream, getCarSize } from './uploads';
+import { PinIndex, validatePinMetadata, MAX_LABELS } from './pin-index';
+import { PinningService, PINNING_SERVICE_PREFIX } from './pinning-service';
+import { RateLimiter } from './rate-limit';
+import { AuditLog, AUDIT_LIST_SPEC } from './audit-log';
+import { ApiError, sendError, errorHandler } from './api-errors';
+import { HealthMonitor } from './health';
+import { ContractPinner, CONTRACT_LIST_SPEC } from './contracts';
+import { ContractPolicyEngine, ContractPolicy, validateContractPolicy } from './contract-policy';
+import { ContractExpiryWatcher } from './contract-expiry';
+import { ContractMarket } from './contract-market';
+import { TransactionQueue } from './tx-queue';
+import { HoneycombClient } from './honeycomb';
+import { PriceFeed } from './price-feed';
+import { UptimeTracker } from './uptime';
+import { NetworkRankTracker } from './network-rank';
+import { EarningsAnomalyDetector } from './earnings-anomaly';
+import { ANALYTICS_KINDS, AnalyticsKind, AnalyticsStore, validateAnalyticsQuery } from './analytics';
+import { BackupManager } from './backup';
+import { PinReconciler, obligatedCids, nodeAccounts } from './pin-reconciler';
+import { TrashCollector } from './trash';
+import { PinImporter } from './pin-import';
+import { CarArchiver } from './car-archive';
+import { StorageBreakdownIndexer } from './storage-breakdown';
+import { MfsMirror } from './mfs-mirror';
+import { GatewayPreview } from './preview';
+import { DiskHealthMonitor } from './disk-health';
+import { RepoMover } from './repo-move';
+import { ContractBandwidthMeter, MAX_REPORT_DAYS } from './contract-bandwidth';
+import { EarningsProjector, MAX_PROJECTED_GB } from './earnings-projection';
+import { CapacityPlanner } from './capacity-planner';
+import { ShardManager, MAX_SHARDS } from './shards';
+import { TierScheduler, isReplicationTier, REPLICATION_TIERS } from './replication-tiers';
+import { SlashingRiskMonitor } from './risk-monitor';
+import {
+  ChallengeHistory,
+  ChallengeRecord,
+  BlockIndexError,
+  ByteRangeError,
+  HBD_PER_CHALLENGE,
+  MAX_RANGE_BYTES,
+  POA_PROTOCOL,
+  generateProof,
+  generateRangeProof,
+  listBlocks,
+} from './poa';
+import { PoaResponder, checkChallengeSignature } from './poa-responder';
+import { PoaSigner, ReplayGuard } from './poa-signing';
+import { PoaValidator } from './validator';
+import { ProofCache } from './proof-cache';
+import { ChallengeLog, ChallengeLogQuery, validateChallengeLogQuery } from './challenge-log';
+import { EligibilityChecker } from './eligibility';
+import { PoaBenchmark, BenchmarkOptions, validateBenchmarkOptions } from './benchmark';
+import { MetricsExporter, METRICS_CONTENT_TYPE } from './metrics';
+import { DaemonWatchdog } from './watchdog';
+import { CertificateManager } from './tls';
+import { RemoteAccessGuard, getBindAddress } from './remote-access';
+import { GrpcGateway, AGENT_PROTO } from './grpc';
+import { writeDiscoveryFile, removeDiscoveryFile } from './discovery';
+import { ListSpec, parseListQuery, paginate, listQueryDoc, PAGE_RESPONSE_PROPERTIES, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE } from './query';
+import { API_PREFIX, HttpMethod, RegisteredRoute, RouteDoc, buildOpenApiDocument } from './openapi';
+import { EarningsFinality } from './earnings-finality';
+import {
+  EARNINGS_GRANULARITIES, EARNINGS_TOKENS, EarningsSeriesQuery, EarningsToken, MAX_DAILY_DAYS, validateEarningsSeriesQuery,
+  describeGoal,
+} from './earnings';
+import { showNotification } from './notifications';
 
-export class ApiServer {
+export interface ApiServices {
+  kubo: KuboManager;
+  config: ConfigStore;
+  activity: ActivityTracker;
+  storage: StorageMonitor;
+  connectivity: ConnectivityMonitor;
+  pool: KuboPool;
+  auth: ApiAuth;
+  cors: CorsPolicy;
+  events: EventBus;
+  eventStream: EventStream;
+  pins: PinIndex;
+  certificates: CertificateManager;
+  watchdog: DaemonWatchdog;
+  finality: EarningsFinality;
+  queue: TransactionQueue;
+  honeycomb: HoneycombClient;
+  prices: PriceFeed;
+  uptime: UptimeTracker;
+  networkRank: NetworkRankTracker;
+  anomaly: EarningsAnomalyDetector;
+  analytics: AnalyticsStore;
+  backups: BackupManager;
+}
+
+const STORAGE_FULL_MESSAGE = 'Storage quota reached, new content is paused';
+const PIN_TYPES = ['recursive', 'direct', 'indirect', 'all'];
+
+interface PinListing {
+  cid: string;
+  type: string;
+  instance: string;
+  name: string | null;
+  labels: string[];
+  pinnedAt: string | null;
+}
+
+const PIN_LIST_SPEC: ListSpec<PinListing> = {
+  sortFields: {
+    cid: (pin) => pin.cid,
+    name: (pin) => pin.name,
+    pinnedAt: (pin) => pin.pinnedAt,
+  },
+  defaultSort: 'cid',
+  filters: {
+    label: (pin, value) => pin.labels.includes(value),
+    name: (pin, value) => (pin.name || '').toLowerCase().includes(value.toLowerCase()),
+    instance: (pin, value) => pin.instance === value,
+  },
+  key: (pin) => pin.cid,
+};
+
+const EARNINGS_HISTORY_LIST_SPEC: ListSpec<EarningsHistoryEntry> = {
+  sortFields: {
+    at: (entry) => entry.at,
+    hbdEarned: (entry) => entry.hbdEarned,
+  },
+  defaultSort: '-at',
+  filters: {
+    passed: (entry, value) => String(entry.passed) === value,
+    cid: (entry, value) => entry.cid === value,
+    account: (entry, value) => entry.account === value,
+    status: (entry, value) => entry.status === value,
+    token: (entry, value) => entry.token === value,
+    since: (entry, value) => Date.parse(entry.at) >= Date.parse(value),
+    until: (entry, value) => Date.parse(entry.at) <= Date.parse(value),
+  },
+  // Entries are appended one per challenge, so the time is unique enough
+  key: (entry) => `${entry.at} ${entry.cid}`,
+};
+
+export interface ApiEndpoints {
+  host: string;
+  port: number;
+  httpsPort: number | null;
+  grpcPort: number | null;
+}
+
+export class ApiServer extends EventEmitter {
   private app: Express;
+  private router: Router = express.Router();
+  private routes: RegisteredRoute[] = [];
+  private uploads = new ResumableUploads();
+  private pinningService: PinningService;
+  private contracts: ContractPinner;
+  private contractPolicy: ContractPolicyEngine;
+  private planner: CapacityPlanner;
+  private shards: ShardManager;
+  private contractExpiry: ContractExpiryWatcher;
+  private trash: TrashCollector;
+  private contractMarket: ContractMarket;
+  private pinReconciler: PinReconciler;
+  private pinImporter: PinImporter;
+  private carArchiver: CarArchiver;
+  private storageBreakdown: StorageBreakdownIndexer;
+  private mfsMirror: MfsMirror;
+  private preview: GatewayPreview;
+  private diskHealth: DiskHealthMonitor;
+  private repoMover: RepoMover;
+  private bandwidth: ContractBandwidthMeter;
+  private projector: EarningsProjector;
+  private tiers: TierScheduler;
+  private riskMonitor: SlashingRiskMonitor;
+  private signer: PoaSigner;
+  private challengeReplay: ReplayGuard;
+  private rateLimiter = new RateLimiter();
+  private audit: AuditLog;
+  private health: HealthMonitor;
+  private challenges: ChallengeHistory;
+  private proofCache: ProofCache;
+  private challengeLog: ChallengeLog;
+  private eligibility: EligibilityChecker;
+  private benchmark: PoaBenchmark;
+  private responder: PoaResponder;
+  private validator: PoaValidator;
+  private metrics: MetricsExporter;
+  private remoteAccess: RemoteAccessGuard;
+  private grpc: GrpcGateway;
   private server: http.Server | null = null;
+  private httpsServer: https.Server | null = null;
+  private p2pServer: http.Server | null = null;
   private kubo: KuboManager;
   private config: ConfigStore;
+  private activity: ActivityTracker;
+  private storage: StorageMonitor;
+  private connectivity: ConnectivityMonitor;
+  private pool: KuboPool;
+  private auth: ApiAuth;
+  private cors: CorsPolicy;
+  private events: EventBus;
+  private eventStream: EventStream;
+  private sse: SseStream;
+  private pins: PinIndex;
+  private certificates: CertificateManager;
+  private finality: EarningsFinality;
+  private prices: PriceFeed;
+  private uptime: UptimeTracker;
+  private networkRank: NetworkRankTracker;
+  private anomaly: EarningsAnomalyDetector;
+  private analytics: AnalyticsStore;
+  private backups: BackupManager;
   private port: number;
 
-  constructor(kubo: KuboManager, config: ConfigStore) {
-    this.kubo = kubo;
-    this.config = config;
-    this.port = config.getConfig().apiPort;
+  constructor(services: ApiServices) {
+    super();
+    this.kubo = services.kubo;
+    this.config = services.config;
+    this.activity = services.activity;
+    this.storage = services.storage;
+    this.connectivity = services.connectivity;
+    this.pool = services.pool;
+    this.auth = services.auth;
+    this.cors = services.cors;
+    this.events = services.events;
+    this.eventStream = services.eventStream;
+    this.sse = new SseStream(this.events);
+    this.pins = services.pins;
+    this.certificates = services.certificates;
+    this.finality = services.finality;
+    this.prices = services.prices;
+    this.uptime = services.uptime;
+    this.networkRank = services.networkRank;
+    this.anomaly = services.anomaly;
+    this.analytics = services.analytics;
+    this.backups = services.backups;
+    this.port = this.config.getConfig().apiPort;
+    this.remoteAccess = new RemoteAccessGuard(this.config, this.auth);
+    this.grpc = new GrpcGateway(this.events, this.remoteAccess, this.auth, () => {
+      const host = this.getHost();
+      const local = ['0.0.0.0', '::'].includes(host) ? '127.0.0.1' : host;
+      return `http://${local.includes(':') ? `[${local}]` : local}:${this.port}`;
+    });
+    this.health = new HealthMonitor(this.kubo, this.config);
+    this.challenges = new ChallengeHistory(this.config.getDataDir());
+    this.proofCache = new ProofCache(this.config.getDataDir(), (cid) => this.contracts.tierFor(cid));
+    this.signer = new PoaSigner(this.config.getDataDir(), () => this.kubo.getRepoPath(), () => this.config.getConfig().poa.signingKey);
+    this.challengeReplay = new ReplayGuard();
+    this.challengeLog = new ChallengeLog(this.config.getDataDir());
+    this.responder = new PoaResponder(this.config, {
+      findNode: async (cid) => (await this.pool.findHolder(cid).catch(() => null)) || this.kubo,
+      proofCache: this.proofCache,
+      contractsFor: (cid) => this.contracts.contractsFor(cid),
+      shardFor: (cid, index) => this.contracts.shardFor(cid, index),
+      accountFor: (cid) => this.contracts.accountFor(cid),
+      deadlineFor: (cid) => this.tiers.challengeTargetFor(cid),
+      record: (record) => this.recordChallengeResult(record),
+      signer: this.signer,
+    });
+    this.responder.on('slow-stage', (warning) => this.events.publish('challenge', 'slow-stage', warning));
+    this.responder.on('at-risk', (count) => this.events.publish('challenge', 'at-risk', { count }));
+    this.benchmark = new PoaBenchmark({
+      pinnedCids: async () => (await this.listPins('recursive')).map((pin) => pin.cid),
+      findNode: async (cid) => (await this.pool.findHolder(cid).catch(() => null)) || this.kubo,
+      deadlineMs: () => this.config.getConfig().poa.budget.totalMs,
+    });
+    this.benchmark.on('progress', (progress) => this.events.publish('challenge', 'benchmark-progress', progress));
+    this.eligibility = new EligibilityChecker(this.config, this.connectivity, {
+      contracts: () => this.contracts.list(),
+      validatorConnections: () => this.responder.getStatus(),
+    });
+    this.validator = new PoaValidator(this.config, this.kubo, this.events, this.signer);
+    this.metrics = new MetricsExporter(this.config, this.pool, () => services.watchdog.getRestartCount());
+    this.audit = new AuditLog(this.config.getDataDir(), (req) => this.auth.isAuthorized(req));
+    this.pinningService = new PinningService(this.config.getDataDir(), this.pool, {
+      pin: async (cid) => {
+        if (!this.storage.isAcceptingContracts()) {
+          throw new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE);
+        }
+        return this.pinCid(cid);
+      },
+      unpin: (cid) => this.unpinCid(cid),
+    });
+    this.shards = new ShardManager(this.pool, this.pins);
+    this.contracts = new ContractPinner(this.config.getDataDir(), {
+      pin: async (cid, contractId) => {
+        if (!this.storage.isAcceptingContracts()) {
+          throw new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE);
+        }
+        const result = await this.pinCid(cid);
+        // Lets /pins?label=contract:<id> list a contract's content
+        const labels = this.pins.get(cid)?.labels ?? [];
+        this.pins.set(cid, { labels: [...labels, `contract:${contractId}`].slice(0, MAX_LABELS) });
+        return result;
+      },
+      getShardManifest: (manifestCid) => this.shards.getManifest(manifestCid),
+    }, this.events, () => this.config.getConfig().spkApiUrl, () => this.config.getConfig().hiveUsername);
+    this.tiers = new TierScheduler(this.config, this.contracts, {
+      reprovide: async (cids) => {
+        const byNode = new Map<KuboManager, string[]>();
+        for (const cid of cids) {
+          const node = (await this.pool.findHolder(cid).catch(() => null)) || this.kubo;
+          byNode.set(node, [...(byNode.get(node) ?? []), cid]);
+        }
+        for (const [node, group] of byNode) await node.reprovide(group);
+      },
+      precompute: (cids) => this.proofCache.precompute(
+        async (cid) => (await this.pool.findHolder(cid).catch(() => null)) || this.kubo, cids),
+    });
+    this.planner = new CapacityPlanner(this.pool);
+    this.contractPolicy = new ContractPolicyEngine(this.config.getDataDir(), this.config, this.kubo, this.contracts,
+      this.planner, () => this.storage.isAcceptingContracts());
+    this.contractMarket = new ContractMarket(this.config, this.kubo, this.contracts, services.queue,
+      () => this.storage.isAcceptingContracts());
+    this.contractExpiry = new ContractExpiryWatcher(this.config, this.contracts, {
+      unpin: (cid) => this.unpinCid(cid),
+      sizeOf: (cid) => this.sizeOf(cid),
+      heldOutsideContracts: this.isHeldOutsideContracts,
+    }, this.events);
+    this.trash = new TrashCollector(this.config, this.contracts, {
+      unpin: (cid) => this.unpinCid(cid),
+      heldOutsideContracts: this.isHeldOutsideContracts,
+      obligations: () => {
+        const contractState = services.honeycomb.getStatus().find((dataset) => dataset.dataset === 'contracts');
+        if (!contractState?.syncedAt) return null;
+        return new Set(obligatedCids(services.honeycomb.getContracts(), nodeAccounts(this.config, this.contracts)).keys());
+      },
+    }, this.events);
+    this.mfsMirror = new MfsMirror(this.pool, this.contracts, this.pins, this.events);
+    this.preview = new GatewayPreview(this.pool);
+    this.diskHealth = new DiskHealthMonitor(this.pool, this.config);
+    this.repoMover = new RepoMover(this.pool, this.config);
+    this.bandwidth = new ContractBandwidthMeter(this.config.getDataDir(), this.pool, this.contracts);
+    this.pinReconciler = new PinReconciler(this.config, services.honeycomb, this.contracts, {
+      listPinned: async () => (await this.listPins('recursive')).map((pin) => pin.cid),
+      unpin: (cid) => this.unpinCid(cid),
+      heldOutsideContracts: this.isHeldOutsideContracts,
+      isAcceptingContracts: () => this.storage.isAcceptingContracts(),
+      mirror: () => this.mfsMirror.sync(),
+    }, this.events);
+    this.pinImporter = new PinImporter(this.config, this.pool, this.pins, this.contracts, services.honeycomb, this.events,
+      () => this.storage.isAcceptingContracts());
+    this.carArchiver = new CarArchiver(this.pool, this.contracts, this.pins, this.events,
+      () => this.storage.isAcceptingContracts());
+    this.storageBreakdown = new StorageBreakdownIndexer(this.config.getDataDir(), {
+      listPinned: async () => (await this.listPins('recursive')).map((pin) => ({ cid: pin.cid, name: pin.name })),
+      sizeOf: (cid) => this.sizeOf(cid),
+    }, this.contracts, this.storage);
+    this.projector = new EarningsProjector({
+      breakdown: () => this.storageBreakdown.getBreakdown(),
+      challengeDays: (from) => this.challengeLog.summarizeByDay({ from }),
+      offers: () => this.contractMarket.list(),
+    });
+    this.riskMonitor = new SlashingRiskMonitor({
+      contracts: () => this.contracts.list(),
+      pinnedCids: async () => new Set((await this.listPins('recursive')).map((pin) => pin.cid)),
+      isOnline: () => this.kubo.getStartupState() === 'Online',
+      failingDisks: () => this.diskHealth.getFailing(),
+    });
+    this.diskHealth.on('changed', () => {
+      this.riskMonitor.check().catch((error) => console.error('[PoA] Risk check failed:', error.message));
+    });
+    this.riskMonitor.on('changed', (risks) => this.events.publish('status', 'slashing-risk', { risks }));
     this.app = express();
     this.setupMiddleware();
     this.setupRoutes();
   }
 
   private setupMiddleware(): void {
+    this.applyTrustProxy();
+    this.app.use(this.remoteAccess.middleware());
     this.app.use(express.json());
     
     // CORS for web app communication
-    this.app.use((req, res, next) => {
-      res.header('Access-Control-Allow-Origin', '*');
-      res.header('Access-Control-Allow-Methods', 'GET, POST, PUT, DELETE, OPTIONS');
-      res.header('Access-Control-Allow-Headers', 'Content-Type, Authorization');
-      if (req.method === 'OPTIONS') {
-        return res.sendStatus(200);
-      }
-      next();
-    });
+    this.app.use(this.cors.middleware());
+    // Ahead of auth so token guessing is throttled and rejected attempts are recorded
+    this.app.use(this.rateLimiter.middleware());
+    this.app.use(this.audit.middleware());
+    this.app.use(this.auth.middleware());
+  }
+
+  private route(method: HttpMethod, path: string, doc: RouteDoc, handler: RequestHandler): void {
+    this.routes.push({ method, path, doc });
+    if (doc.public) this.auth.allowPublic(method, `/api${path}`);
+    this.router[method](path, handler);
   }
 
   private setupRoutes(): void {
     // Health check - used by web app to detect desktop agent
-    this.app.get('/api/status', async (req: Request, res: Response) => {
+    this.route('get', '/status', {
+      summary: 'Agent and node status; used by the web app to detect the agent. Without the token only running, version and peerId',
+      tag: 'Agent',
+      public: true,
+    }, async (req: Request, res: Response) => {
       const peerId = await this.kubo.getPeerId();
-      const stats = await this.kubo.getStats();
+      // Detection needs no more than this; what the node holds and earns is for paired clients
+      if (!this.auth.isAuthorized(req)) {
+        return res.json({ running: this.kubo.isRunning(), peerId, version: '1.0.0' });
+      }
+      const stats = await this.pool.getAggregateStats();
       const configData = this.config.getConfig();
       const earnings = this.config.getEarnings();
 
       res.json({
         running: this.kubo.isRunning(),
+        connectionMode: configData.connectionMode.type,
         peerId,
         stats,
+        storage: this.storage.getUsage(),
+        connectivity: this.connectivity.getReport(),
+        instances: await this.pool.getStatus(),
         config: {
           hiveUsername: configData.hiveUsername,
           autoStart: configData.autoStart,
@@ -57,181 +435,1648 @@ export class ApiServer {
       });
     });
 
+    // Pairing - the web app asks for the API token, the user approves it
+    this.route('post', '/pair', {
+      summary: 'Request the API token; the user approves in a native dialog',
+      tag: 'Auth',
+      body: { appName: { type: 'string' } },
+      response: { type: 'object', properties: { token: { type: 'string' } } },
+      public: true,
+    }, (req: Request, res: Response) => this.auth.pair(req, res));
+
     // Get/Set configuration
-    this.app.get('/api/config', (req: Request, res: Response) => {
+    this.route('get', '/config', { summary: 'Full agent configuration', tag: 'Config' }, (req: Request, res: Response) => {
       res.json(this.config.getConfig());
     });
 
-    this.app.post('/api/config', (req: Request, res: Response) => {
+    this.route('post', '/config', {
+      summary: 'Update the Hive username and auto-start setting',
+      tag: 'Config',
+      body: { hiveUsername: { type: 'string' }, autoStart: { type: 'boolean' } },
+    }, (req: Request, res: Response) => {
       const { hiveUsername, autoStart } = req.body;
       this.config.setConfig({ hiveUsername, autoStart });
       res.json({ success: true, config: this.config.getConfig() });
     });
 
     // Pin content
-    this.app.post('/api/pin', async (req: Request, res: Response) => {
-      const { cid } = req.body;
+    this.route('post', '/pin', {
+      summary: 'Pin a CID on the instance with the most free space',
+      tag: 'Pins',
+      body: { cid: { type: 'string' }, name: { type: 'string' } },
+      required: ['cid'],
+    }, async (req: Request, res: Response) => {
+      const { cid, name } = req.body;
       if (!cid) {
-        return res.status(400).json({ error: 'CID required' });
+        return sendError(res, new ApiError('INVALID_REQUEST', 'CID required'));
+      }
+      if (!this.storage.isAcceptingContracts()) {
+        return sendError(res, new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE));
+      }
+
+      try {
+        const { pins, instance } = await this.pinCid(cid);
+        if (typeof name === 'string') this.pins.set(cid, { name });
+        res.json({ success: true, pins, instance });
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    // Upload a file in one multipart request
+    this.route('post', '/add', {
+      summary: 'Add a file (multipart/form-data, one file field) to IPFS and pin it',
+      tag: 'Pins',
+      response: {
+        type: 'object',
+        properties: {
+          cid: { type: 'string' },
+          name: { type: 'string' },
+          size: { type: 'integer' },
+          carSize: { type: 'integer' },
+        },
+      },
+    }, (req: Request, res: Response) => {
+      if (!req.is('multipart/form-data')) {
+        return sendError(res, new ApiError('UNSUPPORTED_MEDIA_TYPE', 'Expected multipart/form-data'));
+      }
+      if (!this.storage.isAcceptingContracts()) {
+        return sendError(res, new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE));
+      }
+
+      const parser = busboy({ headers: req.headers, limits: { files: 1 } });
+      let handled = false;
+      parser.on('file', (_field, file, info) => {
+        handled = true;
+        const uploadId = crypto.randomBytes(8).toString('hex');
+        const total = Number(req.headers['content-length']) || null;
+        this.addContent(uploadId, file, info.filename || 'file', total)
+          .then((result) => res.json(result))
+          .catch((error: any) => sendError(res, error));
+      });
+      parser.on('finish', () => {
+        if (!handled) sendError(res, new ApiError('INVALID_REQUEST', 'No file in request'));
+      });
+      parser.on('error', (error: any) => {
+        if (!res.headersSent) sendError(res, new ApiError('INVALID_REQUEST', error.message));
+      });
+      req.pipe(parser);
+    });
+
+    // Resumable uploads: create, then PATCH chunks until Upload-Length is reached
+    this.route('post', '/add/uploads', {
+      summary: 'Start a resumable upload; send Upload-Length and optionally Upload-Name headers',
+      tag: 'Pins',
+    }, (req: Request, res: Response) => {
+      const length = Number(req.headers['upload-length']);
+      if (!Number.isInteger(length) || length <= 0) {
+        return sendError(res, new ApiError('INVALID_REQUEST', 'Upload-Length header required'));
+      }
+      if (!this.storage.isAcceptingContracts()) {
+        return sendError(res, new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE));
+      }
+
+      const name = decodeURIComponent(String(req.headers['upload-name'] || 'file'));
+      const upload = this.uploads.create(length, name);
+      res.status(201)
+        .header('Location', `${API_PREFIX}/add/uploads/${upload.id}`)
+        .header('Upload-Offset', '0')
+        .json({ id: upload.id, offset: 0, length });
+    });
+
+    this.route('head', '/add/uploads/:id', {
+      summary: 'Current Upload-Offset of a resumable upload',
+      tag: 'Pins',
+    }, (req: Request, res: Response) => {
+      const upload = this.uploads.get(req.params.id);
+      if (!upload) return res.sendStatus(404);
+      res.header('Upload-Offset', String(upload.offset))
+        .header('Upload-Length', String(upload.length))
+        .header('Cache-Control', 'no-store')
+        .sendStatus(200);
+    });
+
+    this.route('patch', '/add/uploads/:id', {
+      summary: 'Append a chunk (application/offset+octet-stream) at Upload-Offset; the last chunk returns the CID',
+      tag: 'Pins',
+    }, async (req: Request, res: Response) => {
+      const upload = this.uploads.get(req.params.id);
+      if (!upload) return sendError(res, new ApiError('NOT_FOUND', 'Unknown upload'));
+
+      try {
+        await this.uploads.append(upload.id, Number(req.headers['upload-offset']), req);
+      } catch (error: any) {
+        res.header('Upload-Offset', String(upload.offset));
+        return sendError(res, new ApiError(error instanceof UploadOffsetError ? 'CONFLICT' : 'INVALID_REQUEST', error.message));
+      }
+
+      res.header('Upload-Offset', String(upload.offset));
+      if (!this.uploads.isComplete(upload)) {
+        this.events.publish('pins', 'upload-progress', {
+          uploadId: upload.id,
+          name: upload.name,
+          bytes: upload.offset,
+          total: upload.length,
+        });
+        return res.sendStatus(204);
       }
 
       try {
-        const response = await axios.post(
-          `${this.kubo.getApiUrl()}/api/v0/pin/add?arg=${cid}`,
-          null,
-          { timeout: 300000 }
-        );
-        res.json({ success: true, pins: response.data.Pins });
+        const file = fs.createReadStream(upload.filePath);
+        res.json(await this.addContent(upload.id, file, upload.name, upload.length));
+        this.uploads.remove(upload.id);
       } catch (error: any) {
-        res.status(500).json({ error: error.message });
+        // The staged file is kept; an empty PATCH at the final offset retries the add
+        sendError(res, error);
       }
     });
 
+    this.route('delete', '/add/uploads/:id', {
+      summary: 'Cancel a resumable upload',
+      tag: 'Pins',
+    }, (req: Request, res: Response) => {
+      this.uploads.remove(req.params.id);
+      res.sendStatus(204);
+    });
+
     // Unpin content
-    this.app.post('/api/unpin', async (req: Request, res: Response) => {
+    this.route('post', '/unpin', {
+      summary: 'Unpin a CID',
+      tag: 'Pins',
+      body: { cid: { type: 'string' } },
+      required: ['cid'],
+    }, async (req: Request, res: Response) => {
       const { cid } = req.body;
       if (!cid) {
-        return res.status(400).json({ error: 'CID required' });
+        return sendError(res, new ApiError('INVALID_REQUEST', 'CID required'));
       }
 
       try {
-        await axios.post(`${this.kubo.getApiUrl()}/api/v0/pin/rm?arg=${cid}`);
+        await this.unpinCid(cid);
         res.json({ success: true });
       } catch (error: any) {
-        res.status(500).json({ error: error.message });
+        sendError(res, error);
       }
     });
 
     // List pinned content
-    this.app.get('/api/pins', async (req: Request, res: Response) => {
+    this.route('get', '/pins', {
+      summary: 'List pins across all instances, with names and labels from the local index',
+      tag: 'Pins',
+      query: {
+        type: { type: 'string', enum: PIN_TYPES },
+        ...listQueryDoc(PIN_LIST_SPEC),
+      },
+      response: {
+        type: 'object',
+        properties: {
+          pins: { type: 'array', items: { type: 'object' } },
+          ...PAGE_RESPONSE_PROPERTIES,
+        },
+      },
+    }, async (req: Request, res: Response) => {
+      const type = String(req.query.type || 'recursive');
+      if (!PIN_TYPES.includes(type)) {
+        return sendError(res, new ApiError('INVALID_REQUEST', `type must be one of ${PIN_TYPES.join(', ')}`));
+      }
+
       try {
-        const response = await axios.post(`${this.kubo.getApiUrl()}/api/v0/pin/ls?type=recursive`);
-        const pins = Object.keys(response.data.Keys || {});
-        res.json({ pins });
+        const pins = await this.listPins(type);
+        // Older clients on the unversioned path expect a bare CID list
+        if (req.baseUrl !== API_PREFIX) {
+          return res.json({ pins: pins.map((pin) => pin.cid) });
+        }
+
+        const { items, ...page } = paginate(pins, parseListQuery(req.query, PIN_LIST_SPEC), PIN_LIST_SPEC);
+        res.json({ pins: items, ...page });
       } catch (error: any) {
-        res.status(500).json({ error: error.message });
+        sendError(res, error);
       }
     });
 
-    // PoA Challenge endpoint - validators call this
-    this.app.post('/api/challenge', async (req: Request, res: Response) => {
-      const { cid, blockIndex, salt, validatorId } = req.body;
+    this.route('post', '/pins', {
+      summary: 'Pin a CID, or update the name and labels of an existing pin',
+      tag: 'Pins',
+      body: {
+        cid: { type: 'string' },
+        name: { type: 'string' },
+        labels: { type: 'array', items: { type: 'string' } },
+      },
+      required: ['cid'],
+    }, async (req: Request, res: Response) => {
+      const { cid, name, labels } = req.body;
+      if (!cid) {
+        return sendError(res, new ApiError('INVALID_REQUEST', 'CID required'));
+      }
+      const errors = validatePinMetadata({ name, labels });
+      if (errors.length > 0) {
+        return sendError(res, new ApiError('INVALID_REQUEST', errors.join('; '), { errors }));
+      }
 
-      if (!cid || blockIndex === undefined || !salt) {
-        return res.status(400).json({ error: 'Missing required fields: cid, blockIndex, salt' });
+      try {
+        let instance = (await this.pool.findHolder(cid))?.getInstanceId();
+        if (!instance) {
+          if (!this.storage.isAcceptingContracts()) {
+            return sendError(res, new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE));
+          }
+          instance = (await this.pinCid(cid)).instance;
+        }
+        const metadata = this.pins.set(cid, { name, labels });
+        res.json({ cid, type: 'recursive', instance, ...metadata });
+      } catch (error: any) {
+        sendError(res, error);
       }
+    });
 
-      const startTime = Date.now();
+    this.route('delete', '/pins/:cid', {
+      summary: 'Unpin a CID and forget its name and labels',
+      tag: 'Pins',
+    }, async (req: Request, res: Response) => {
+      try {
+        await this.unpinCid(req.params.cid);
+        res.sendStatus(204);
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
 
+    this.route('post', '/contracts/pin', {
+      summary: 'Pin every CID of a storage contract, by contract ID or manifest; progress streams on the pins topic',
+      tag: 'Contracts',
+      body: {
+        contractId: { type: 'string' },
+        cids: { type: 'array', items: { type: 'string' } },
+        expiresAt: { type: 'string', format: 'date-time' },
+        account: { type: 'string', description: 'Linked Hive account to store it for; defaults to the active one' },
+        tier: { type: 'string', enum: [...REPLICATION_TIERS], description: 'Replication tier of a new contract; defaults to warm' },
+        shards: {
+          type: 'object',
+          description: 'Pin these shards of an erasure-coded file instead of cids',
+          properties: { manifestCid: { type: 'string' }, indexes: { type: 'array', items: { type: 'integer' } } },
+        },
+      },
+    }, async (req: Request, res: Response) => {
+      if (!this.storage.isAcceptingContracts()) {
+        return sendError(res, new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE));
+      }
       try {
-        // Get the file blocks
-        const blocksResponse = await axios.post(
-          `${this.kubo.getApiUrl()}/api/v0/refs?arg=${cid}`,
-          null,
-          { timeout: 2000 }
-        );
+        res.status(202).json(await this.contracts.pin(req.body || {}));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
 
-        const blocks = blocksResponse.data.split('\n')
-          .filter((line: string) => line.trim())
-          .map((line: string) => {
-            try { return JSON.parse(line).Ref; } catch { return null; }
-          })
-          .filter(Boolean);
+    this.route('post', '/shards/encode', {
+      summary: 'Split a pinned file into Reed–Solomon shards, add them and a manifest naming them',
+      tag: 'Contracts',
+      body: {
+        cid: { type: 'string' },
+        dataShards: { type: 'integer', minimum: 1 },
+        parityShards: { type: 'integer', minimum: 1 },
+      },
+      required: ['cid', 'dataShards', 'parityShards'],
+    }, async (req: Request, res: Response) => {
+      const { cid, dataShards, parityShards } = req.body || {};
+      if (typeof cid !== 'string' || !cid) return sendError(res, new ApiError('INVALID_REQUEST', 'cid is required'));
+      if (!this.storage.isAcceptingContracts()) {
+        return sendError(res, new ApiError('STORAGE_FULL', STORAGE_FULL_MESSAGE));
+      }
+      try {
+        res.json(await this.shards.encode(cid, dataShards, parityShards));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
 
-        if (blockIndex >= blocks.length) {
-          return res.status(400).json({ error: 'Block index out of range' });
-        }
+    this.route('get', '/shards/:manifestCid', {
+      summary: 'A shard manifest: the file, shard counts and each shard\'s CID',
+      tag: 'Contracts',
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(await this.shards.getManifest(req.params.manifestCid));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
 
-        const blockCid = blocks[blockIndex];
+    this.route('post', '/shards/:manifestCid/reconstruct', {
+      summary: `Rebuild a sharded file (up to ${MAX_SHARDS} shards) from whichever shards can be fetched, and add it`,
+      tag: 'Contracts',
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(await this.shards.reconstruct(req.params.manifestCid));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
 
-        // Fetch the actual block data
-        const blockResponse = await axios.post(
-          `${this.kubo.getApiUrl()}/api/v0/block/get?arg=${blockCid}`,
-          null,
-          { timeout: 2000, responseType: 'arraybuffer' }
-        );
+    this.route('get', '/contracts', {
+      summary: 'Contracts this node stores and their pin progress',
+      tag: 'Contracts',
+      query: listQueryDoc(CONTRACT_LIST_SPEC, {
+        status: { type: 'string', enum: ['pinning', 'pinned', 'partial'] },
+        source: { type: 'string', enum: ['spk', 'manifest'] },
+        account: { type: 'string' },
+        tier: { type: 'string', enum: [...REPLICATION_TIERS] },
+      }),
+      response: {
+        type: 'object',
+        properties: {
+          contracts: { type: 'array', items: { type: 'object' } },
+          ...PAGE_RESPONSE_PROPERTIES,
+        },
+      },
+    }, (req: Request, res: Response) => {
+      const query = parseListQuery(req.query, CONTRACT_LIST_SPEC);
+      const { items, ...page } = paginate(this.contracts.list(), query, CONTRACT_LIST_SPEC);
+      res.json({ contracts: items, ...page });
+    });
 
-        // Compute proof: SHA256(salt + blockData)
-        const hash = crypto.createHash('sha256');
-        hash.update(salt);
-        hash.update(Buffer.from(blockResponse.data));
-        const proof = hash.digest('hex');
+    this.route('get', '/contracts/policy/decisions', {
+      summary: 'Offers the auto-acceptance policy accepted or rejected, and why, newest first',
+      tag: 'Contracts',
+    }, (req: Request, res: Response) => {
+      res.json({ policy: this.config.getConfig().contractPolicy, decisions: this.contractPolicy.getDecisions() });
+    });
 
-        const responseTime = Date.now() - startTime;
+    this.route('post', '/contracts/policy/preview', {
+      summary: 'What the policy, optionally with changed rules, would decide for the open offers; pins nothing',
+      tag: 'Contracts',
+      body: {
+        maxContractGb: { type: 'number', nullable: true },
+        minHbdPerGbMonth: { type: 'number', nullable: true },
+        blockedAccounts: { type: 'array', items: { type: 'string' } },
+        maxCommittedGb: { type: 'number', nullable: true },
+        tier: { type: 'string', enum: [...REPLICATION_TIERS] },
+        hotMinHbdPerGbMonth: { type: 'number', nullable: true },
+        coldMinGb: { type: 'number', nullable: true },
+      },
+    }, async (req: Request, res: Response) => {
+      const overrides: Partial<ContractPolicy> = req.body || {};
+      const errors = validateContractPolicy(overrides);
+      if (errors.length > 0) return sendError(res, new ApiError('INVALID_REQUEST', errors.join('; ')));
+      try {
+        res.json({ decisions: await this.contractPolicy.preview(overrides) });
+      } catch (error: any) {
+        sendError(res, new ApiError('SPK_API_UNAVAILABLE', `Could not fetch offers: ${error.message}`));
+      }
+    });
 
-        // Record successful challenge
-        const hbdEarned = 0.001; // Base earnings per challenge
-        this.config.recordChallenge(true, hbdEarned);
+    this.route('get', '/contracts/market', {
+      summary: 'Open storage contracts still short of nodes, with size, this node\'s reward and duration',
+      tag: 'Contracts',
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json({ offers: await this.contractMarket.list() });
+      } catch (error: any) {
+        sendError(res, new ApiError('SPK_API_UNAVAILABLE', `Could not fetch offers: ${error.message}`));
+      }
+    });
+
+    this.route('post', '/contracts/market/:id/accept', {
+      summary: 'Pin an open contract and accept it on-chain; answers once the acceptance is included',
+      tag: 'Contracts',
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(await this.contractMarket.accept(req.params.id));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/storage/breakdown', {
+      summary: 'Repo disk use by contract and CID; ?refresh=true sizes new pins first',
+      tag: 'Pins',
+      query: { refresh: { type: 'string', enum: ['true', 'false'] } },
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(req.query.refresh === 'true' ? await this.storageBreakdown.index() : this.storageBreakdown.getBreakdown());
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/storage/disk-health', {
+      summary: 'SMART health of the disk under each repo; ?refresh=true checks again now',
+      tag: 'Pins',
+      query: { refresh: { type: 'string', enum: ['true', 'false'] } },
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json({ disks: req.query.refresh === 'true' ? await this.diskHealth.check() : this.diskHealth.getReports() });
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/preview/:cid', {
+      summary: 'Stream stored content from the local gateway, honouring Range; ?path= reaches into a directory',
+      tag: 'Pins',
+      query: { path: { type: 'string' } },
+    }, async (req: Request, res: Response) => {
+      try {
+        const subpath = typeof req.query.path === 'string' ? req.query.path : '';
+        const preview = await this.preview.open(req.params.cid, subpath, req.headers.range);
+        res.status(preview.status);
+        for (const [name, value] of Object.entries(preview.headers)) res.setHeader(name, value);
+        preview.stream.on('error', () => res.destroy());
+        res.on('close', () => preview.stream.destroy());
+        preview.stream.pipe(res);
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/storage/plan', {
+      summary: 'How many new bytes pinning a DAG would add, leaving out blocks already in the repo',
+      tag: 'Pins',
+      query: { cid: { type: 'string' } },
+    }, async (req: Request, res: Response) => {
+      if (typeof req.query.cid !== 'string' || !req.query.cid) return sendError(res, new ApiError('INVALID_REQUEST', 'cid is required'));
+      try {
+        res.json(await this.planner.plan(req.query.cid));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/storage/mfs', {
+      summary: 'The /spk MFS view of contract content: the last sync and a gateway link per instance',
+      tag: 'Pins',
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(await this.mfsMirror.getStatus());
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/pins/reconciliation', {
+      summary: 'The last comparison of local pins with on-chain contracts: missing CIDs and orphans',
+      tag: 'Contracts',
+    }, (req: Request, res: Response) => {
+      res.json(this.pinReconciler.getStatus());
+    });
+
+    this.route('post', '/pins/reconciliation', {
+      summary: 'Compare local pins with on-chain contracts now, pinning what is missing',
+      tag: 'Contracts',
+    }, async (req: Request, res: Response) => {
+      try {
+        const reconciliation = await this.pinReconciler.run('manual');
+        if (!reconciliation) return sendError(res, new ApiError('CONFLICT', 'A reconciliation is already running'));
+        res.json(reconciliation);
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/contracts/expiring', {
+      summary: 'Contracts by expiry, with when each will be unpinned, and the last expiry sweep',
+      tag: 'Contracts',
+    }, (req: Request, res: Response) => {
+      res.json(this.contractExpiry.getStatus());
+    });
+
+    this.route('get', '/contracts/bandwidth', {
+      summary: 'Estimated bytes served per contract and CID over the last ?days= (default 30), from bitswap sampling',
+      tag: 'Contracts',
+      query: { days: { type: 'integer', minimum: 1, maximum: MAX_REPORT_DAYS } },
+    }, (req: Request, res: Response) => {
+      const days = req.query.days === undefined ? 30 : Number(req.query.days);
+      if (!Number.isInteger(days) || days < 1 || days > MAX_REPORT_DAYS) {
+        return sendError(res, new ApiError('INVALID_REQUEST', `days must be between 1 and ${MAX_REPORT_DAYS}`));
+      }
+      res.json(this.bandwidth.getReport(days));
+    });
+
+    this.route('get', '/contracts/trash', {
+      summary: 'Contracts in the trash, with when their content is unpinned and any CIDs the chain still assigns',
+      tag: 'Contracts',
+    }, (req: Request, res: Response) => {
+      res.json({ settings: this.config.getConfig().trash, contracts: this.trash.list() });
+    });
+
+    this.route('post', '/contracts/trash/:id/restore', {
+      summary: 'Take a contract out of the trash and pin its CIDs again',
+      tag: 'Contracts',
+    }, (req: Request, res: Response) => {
+      try {
+        const record = this.contracts.restore(req.params.id);
+        if (!record) return sendError(res, new ApiError('NOT_FOUND', 'No such contract in the trash'));
+        res.json(record);
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('delete', '/contracts/trash/:id', {
+      summary: 'Empty one contract from the trash now, unpinning what nothing else holds',
+      tag: 'Contracts',
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(await this.trash.purge(req.params.id));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/contracts/tiers', {
+      summary: 'Replication tiers: their reprovide interval and challenge target, and the contracts and CIDs in each',
+      tag: 'Contracts',
+    }, (req: Request, res: Response) => {
+      res.json({ tiers: this.tiers.getStatus() });
+    });
+
+    this.route('post', '/contracts/:id/tier', {
+      summary: 'Move a contract to the hot, warm or cold replication tier',
+      tag: 'Contracts',
+      body: { tier: { type: 'string', enum: [...REPLICATION_TIERS] } },
+      required: ['tier'],
+    }, (req: Request, res: Response) => {
+      const tier = req.body?.tier;
+      if (!isReplicationTier(tier)) {
+        return sendError(res, new ApiError('INVALID_REQUEST', `tier must be one of: ${REPLICATION_TIERS.join(', ')}`));
+      }
+      const record = this.contracts.setTier(req.params.id, tier);
+      if (!record) return sendError(res, new ApiError('NOT_FOUND', 'Unknown contract'));
+      res.json(record);
+    });
+
+    this.route('get', '/contracts/:id/car', {
+      summary: 'Download a CID of a contract as a CAR archive; ?cid= picks one when the contract holds several',
+      tag: 'Contracts',
+      query: { cid: { type: 'string' } },
+    }, async (req: Request, res: Response) => {
+      const record = this.contracts.get(req.params.id);
+      if (!record) return sendError(res, new ApiError('NOT_FOUND', 'Unknown contract'));
+      const cids = Object.keys(record.cids);
+      const cid = typeof req.query.cid === 'string' ? req.query.cid : cids.length === 1 ? cids[0] : null;
+      if (!cid) return sendError(res, new ApiError('INVALID_REQUEST', `The contract holds ${cids.length} CIDs; pick one with ?cid=`));
+      if (record.cids[cid]?.status !== 'pinned') {
+        return sendError(res, new ApiError('NOT_FOUND', `${cid} is not pinned for this contract`));
+      }
+      try {
+        const stream = await this.carArchiver.exportStream(cid);
+        res.setHeader('Content-Type', 'application/vnd.ipld.car');
+        res.setHeader('Content-Disposition', `attachment; filename="${cid}.car"`);
+        stream.on('error', () => res.destroy());
+        stream.pipe(res);
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('delete', '/contracts/:id', {
+      summary: 'Move a contract to the trash; its content stays pinned for the retention window, or is unpinned now when it is 0',
+      tag: 'Contracts',
+    }, async (req: Request, res: Response) => {
+      const entry = this.contracts.trash(req.params.id, 'removed');
+      if (!entry) return sendError(res, new ApiError('NOT_FOUND', 'Unknown contract'));
+      if (this.trash.isEnabled()) return res.json({ trashed: this.trash.list().find(({ record }) => record.id === entry.record.id) });
+      try {
+        res.json({ purged: await this.trash.purge(entry.record.id) });
+      } catch (error: any) {
+        // Still owed on-chain, so it waits in the trash like any other
+        res.json({ trashed: this.trash.list().find(({ record }) => record.id === entry.record.id), error: error.message });
+      }
+    });
+
+    this.route('get', '/contracts/:id', { summary: 'Pin progress of one contract', tag: 'Contracts' }, (req: Request, res: Response) => {
+      const record = this.contracts.get(req.params.id);
+      if (!record) return sendError(res, new ApiError('NOT_FOUND', 'Unknown contract'));
+      res.json(record);
+    });
+
+    // PoA Challenge endpoint - validators call this
+    this.route('post', '/challenge', {
+      summary: 'Answer a proof-of-access challenge on a block, or on offset and length bytes of the file; called by validators',
+      tag: 'PoA',
+      body: {
+        cid: { type: 'string' },
+        blockIndex: { type: 'integer' },
+        offset: { type: 'integer' },
+        length: { type: 'integer', maximum: MAX_RANGE_BYTES },
+        salt: { type: 'string' },
+        validatorId: { type: 'string' },
+        shard: { type: 'integer', description: 'For a sharded file, which shard to prove; any one held otherwise' },
+      },
+      required: ['cid', 'salt'],
+      response: {
+        type: 'object',
+        properties: {
+          success: { type: 'boolean' },
+          proof: { type: 'string' },
+          blockCid: { type: 'string' },
+          leafCids: { type: 'array', items: { type: 'string' } },
+          fileSize: { type: 'integer' },
+          responseTime: { type: 'integer' },
+        },
+      },
+      public: true,
+    }, this.answerChallenge);
+
+    this.route('post', '/poa/selftest', {
+      summary: 'Prove access to a random pinned CID locally, without touching earnings',
+      tag: 'PoA',
+      body: { cid: { type: 'string' } },
+    }, async (req: Request, res: Response) => {
+      const startTime = Date.now();
+      try {
+        let cid: string | undefined = req.body?.cid;
+        if (!cid) {
+          const pins = await this.listPins('recursive');
+          if (pins.length === 0) {
+            return sendError(res, new ApiError('CONFLICT', 'Nothing is pinned yet'));
+          }
+          cid = pins[crypto.randomInt(pins.length)].cid;
+        }
+
+        const node = (await this.pool.findHolder(cid)) || this.kubo;
+        // Learn the block count first so the index is always in range
+        const blockCount = (await listBlocks(node, cid)).length;
+        if (blockCount === 0) {
+          return sendError(res, new ApiError('CONFLICT', `${cid} is a single block; challenges need a DAG with links`));
+        }
+        const blockIndex = crypto.randomInt(blockCount);
+        const salt = crypto.randomBytes(16).toString('hex');
+        const result = await generateProof(node, cid, blockIndex, salt);
 
         res.json({
           success: true,
-          proof,
-          blockCid,
-          responseTime,
+          cid,
+          blockIndex,
+          salt,
+          instance: node.getInstanceId(),
+          ...result,
+          responseTime: Date.now() - startTime,
         });
       } catch (error: any) {
-        // Record failed challenge
-        this.config.recordChallenge(false, 0);
+        sendError(res, error, { responseTime: Date.now() - startTime });
+      }
+    });
 
-        res.status(500).json({
-          success: false,
-          error: error.message,
-          responseTime: Date.now() - startTime,
-        });
+    this.route('get', '/poa/validators', {
+      summary: 'Validator connections challenges arrive on',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json({ ...this.config.getConfig().poa, connections: this.responder.getStatus() });
+    });
+
+    this.route('get', '/poa/pending', {
+      summary: 'Answers that could not be sent to their validator and are being retried',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json({ pending: this.responder.getPendingSubmissions() });
+    });
+
+    this.route('get', '/poa/risks', {
+      summary: 'Patterns that usually precede a penalty: missed challenges, downtime, unpinned contract content',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json({ risks: this.riskMonitor.getRisks() });
+    });
+
+    this.route('get', '/poa/diagnostics', {
+      summary: 'Per-stage timings of recent challenge answers against the latency budget',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json(this.responder.getDiagnostics().getReport());
+    });
+
+    const historyQueryDoc = {
+      from: { type: 'string', format: 'date-time' },
+      to: { type: 'string', format: 'date-time', description: 'Exclusive' },
+      validator: { type: 'string' },
+      cid: { type: 'string' },
+      passed: { type: 'boolean' },
+      protocol: { type: 'string', enum: ['rest', 'spk'] },
+    };
+
+    this.route('get', '/poa/history', {
+      summary: 'Every challenge received, newest first, with validator, latency and reward',
+      tag: 'PoA',
+      query: {
+        ...historyQueryDoc,
+        limit: { type: 'integer', minimum: 1, maximum: MAX_PAGE_SIZE, default: DEFAULT_PAGE_SIZE },
+        cursor: { type: 'string', description: 'nextCursor from the previous page' },
+      },
+    }, (req: Request, res: Response) => {
+      try {
+        res.json(this.challengeLog.query(this.parseHistoryQuery(req)));
+      } catch (error: any) {
+        sendError(res, error);
       }
     });
 
+    this.route('get', '/poa/history/days', {
+      summary: 'Challenges, passes, reward and average latency per UTC day',
+      tag: 'PoA',
+      query: historyQueryDoc,
+    }, (req: Request, res: Response) => {
+      try {
+        res.json({ days: this.challengeLog.summarizeByDay(this.parseHistoryQuery(req)) });
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('post', '/poa/benchmark', {
+      summary: 'Fire synthetic challenges at the local repo and report latency percentiles and failure causes',
+      tag: 'PoA',
+      body: {
+        count: { type: 'integer', minimum: 1, maximum: 10000, default: 100 },
+        ratePerSec: { type: 'number', maximum: 100, default: 5 },
+        protocol: { type: 'string', enum: ['rest', 'spk'], default: 'spk' },
+      },
+    }, async (req: Request, res: Response) => {
+      const options: Partial<BenchmarkOptions> = req.body || {};
+      const errors = validateBenchmarkOptions(options);
+      if (errors.length > 0) return sendError(res, new ApiError('INVALID_REQUEST', errors.join('; ')));
+      if (this.benchmark.isRunning()) return sendError(res, new ApiError('CONFLICT', 'A benchmark is already running'));
+      try {
+        res.json(await this.benchmark.run(options));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('delete', '/poa/benchmark', { summary: 'Stop the running benchmark early', tag: 'PoA' }, (req: Request, res: Response) => {
+      this.benchmark.cancel();
+      res.sendStatus(204);
+    });
+
+    this.route('get', '/poa/eligibility', {
+      summary: 'Whether this node receives challenges, and why not: stake, reachability, registration, contracts',
+      tag: 'PoA',
+      query: { refresh: { type: 'boolean', description: 'Skip the five-minute cache' } },
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(await this.eligibility.getReport(req.query.refresh === 'true'));
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/poa/cache', {
+      summary: 'How many hot CIDs have their block list cached, and how often challenges hit it',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json(this.proofCache.getStats());
+    });
+
+    this.route('get', '/poa/last', {
+      summary: 'Inputs, timing and result of the most recent real challenge',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      const last = this.challenges.getLast();
+      if (!last) {
+        return sendError(res, new ApiError('NOT_FOUND', 'No challenge received yet'));
+      }
+      res.json(last);
+    });
+
     // Get earnings
-    this.app.get('/api/earnings', (req: Request, res: Response) => {
+    this.route('get', '/earnings', { summary: 'Earnings and challenge counters', tag: 'PoA' }, (req: Request, res: Response) => {
       res.json(this.config.getEarnings());
     });
 
+    this.route('get', '/earnings/accounts', {
+      summary: 'Earnings split by the linked account each challenged CID is stored for',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json({ accounts: this.config.getAccountEarnings() });
+    });
+
+    this.route('get', '/earnings/history', {
+      summary: 'Every challenge answered and what it earned, newest first; provisional until its Hive block is irreversible',
+      tag: 'PoA',
+      query: listQueryDoc(EARNINGS_HISTORY_LIST_SPEC, {
+        passed: { type: 'string', enum: ['true', 'false'] },
+        account: { type: 'string' },
+        status: { type: 'string', enum: ['provisional', 'final'] },
+        token: { type: 'string', enum: EARNINGS_TOKENS },
+        since: { type: 'string', format: 'date-time' },
+        until: { type: 'string', format: 'date-time' },
+      }),
+      response: {
+        type: 'object',
+        properties: {
+          entries: { type: 'array', items: { type: 'object' } },
+          ...PAGE_RESPONSE_PROPERTIES,
+        },
+      },
+    }, (req: Request, res: Response) => {
+      const query = parseListQuery(req.query, EARNINGS_HISTORY_LIST_SPEC);
+      const { items, ...page } = paginate(this.config.getEarningsHistory(), query, EARNINGS_HISTORY_LIST_SPEC);
+      res.json({ entries: items, ...page });
+    });
+
+    this.route('get', '/earnings/daily', {
+      summary: 'Challenges, passes and amount earned per UTC day and token over the last ?days= (default 30), newest first',
+      tag: 'PoA',
+      query: {
+        days: { type: 'integer', minimum: 1, maximum: MAX_DAILY_DAYS },
+        token: { type: 'string', enum: EARNINGS_TOKENS },
+      },
+    }, (req: Request, res: Response) => {
+      const days = req.query.days === undefined ? 30 : Number(req.query.days);
+      if (!Number.isInteger(days) || days < 1 || days > MAX_DAILY_DAYS) {
+        return sendError(res, new ApiError('INVALID_REQUEST', `days must be between 1 and ${MAX_DAILY_DAYS}`));
+      }
+      const token = req.query.token === undefined ? undefined : String(req.query.token) as EarningsToken;
+      if (token !== undefined && !EARNINGS_TOKENS.includes(token)) {
+        return sendError(res, new ApiError('INVALID_REQUEST', `token must be one of ${EARNINGS_TOKENS.join(', ')}`));
+      }
+      res.json({ days: this.config.getEarningsStore().getDaily(days, token) });
+    });
+
+    this.route('get', '/earnings/series', {
+      summary: 'Challenges, pass rate and amount per token by day, week or month, for charting',
+      tag: 'PoA',
+      query: {
+        granularity: { type: 'string', enum: EARNINGS_GRANULARITIES },
+        from: { type: 'string', format: 'date' },
+        to: { type: 'string', format: 'date' },
+      },
+    }, (req: Request, res: Response) => {
+      const value = (name: string) => (typeof req.query[name] === 'string' && req.query[name] !== '' ? String(req.query[name]) : undefined);
+      const query = { granularity: value('granularity'), from: value('from'), to: value('to') } as EarningsSeriesQuery;
+      const errors = validateEarningsSeriesQuery(query);
+      if (errors.length > 0) {
+        return sendError(res, new ApiError('INVALID_REQUEST', errors.join(', ')));
+      }
+      res.json(this.config.getEarningsStore().getSeries(query));
+    });
+
+    this.route('get', '/earnings/projection', {
+      summary: 'Projected daily and monthly earnings today, at the quota and at ?capacityGb=, from challenge history and open offers',
+      tag: 'PoA',
+      query: { capacityGb: { type: 'number', minimum: 0, maximum: MAX_PROJECTED_GB } },
+    }, async (req: Request, res: Response) => {
+      const capacityGb = req.query.capacityGb === undefined ? undefined : Number(req.query.capacityGb);
+      if (capacityGb !== undefined && (!Number.isFinite(capacityGb) || capacityGb < 0 || capacityGb > MAX_PROJECTED_GB)) {
+        return sendError(res, new ApiError('INVALID_REQUEST', `capacityGb must be between 0 and ${MAX_PROJECTED_GB}`));
+      }
+      res.json(await this.projector.project(capacityGb));
+    });
+
+    this.route('get', '/earnings/goals', {
+      summary: 'Milestone thresholds, and progress towards each goal in the current period',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      const settings = this.config.getConfig().milestones;
+      const goals = this.config.getEarningsStore().getGoalProgress(this.config.getEarnings(), settings);
+      res.json({ thresholds: settings.thresholds, goals });
+    });
+
+    this.route('get', '/uptime', {
+      summary: 'Whether the daemon is online, and its availability over the last 7 and 30 days',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json(this.uptime.getStats());
+    });
+
+    this.route('get', '/earnings/anomaly', {
+      summary: 'The last 7 days of earnings and pass rate against the 4 weeks before, and likely causes of a drop',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json(this.anomaly.getLast());
+    });
+
+    this.route('get', '/network/rank', {
+      summary: 'This node\'s rank among SPK storage nodes by earnings, proof reliability and reputation',
+      tag: 'PoA',
+      query: { refresh: { type: 'boolean', description: 'Skip the 15-minute cache' } },
+    }, async (req: Request, res: Response) => {
+      try {
+        res.json(await this.networkRank.getRank(req.query.refresh === 'true'));
+      } catch (error: any) {
+        sendError(res, new ApiError('SPK_API_UNAVAILABLE', `Could not fetch the node list: ${error.message}`));
+      }
+    });
+
+    this.route('get', '/earnings/finality', {
+      summary: 'Irreversible block and the earnings still waiting on it',
+      tag: 'PoA',
+    }, (req: Request, res: Response) => {
+      res.json(this.finality.getStatus());
+    });
+
     // Autostart management
-    this.app.get('/api/autostart', (req: Request, res: Response) => {
+    this.route('get', '/autostart', { summary: 'Whether the agent starts at login', tag: 'Config' }, (req: Request, res: Response) => {
       const config = this.config.getConfig();
       res.json({ enabled: config.autoStart });
     });
 
-    this.app.post('/api/autostart', (req: Request, res: Response) => {
+    this.route('post', '/autostart', {
+      summary: 'Enable or disable starting at login',
+      tag: 'Config',
+      body: { enabled: { type: 'boolean' } },
+      required: ['enabled'],
+    }, (req: Request, res: Response) => {
       const { enabled } = req.body;
       this.config.setConfig({ autoStart: enabled });
       // TODO: Actually configure OS autostart
       res.json({ success: true, enabled });
     });
-  }
 
-  async start(): Promise<void> {
-    return new Promise((resolve, reject) => {
+    this.route('get', '/analytics/events', {
+      summary: 'Agent events recorded locally for diagnostics, newest first',
+      tag: 'Agent',
+      query: {
+        kind: { type: 'string', enum: ANALYTICS_KINDS },
+        limit: { type: 'integer', minimum: 1, maximum: MAX_PAGE_SIZE },
+      },
+    }, (req: Request, res: Response) => {
+      const query = {
+        kind: req.query.kind === undefined ? undefined : String(req.query.kind) as AnalyticsKind,
+        limit: req.query.limit === undefined ? undefined : Number(req.query.limit),
+      };
+      const errors = validateAnalyticsQuery(query);
+      if (errors.length > 0) {
+        return sendError(res, new ApiError('INVALID_REQUEST', errors.join('; ')));
+      }
+      res.json({ ...this.analytics.getStatus(), events: this.analytics.list(query) });
+    });
+
+    this.route('delete', '/analytics/events', {
+      summary: 'Delete every recorded agent event',
+      tag: 'Agent',
+    }, (req: Request, res: Response) => {
+      res.json({ purged: this.analytics.purge() });
+    });
+
+    this.route('get', '/backup', {
+      summary: 'Backup settings and the encrypted backups pinned to this node',
+      tag: 'Agent',
+    }, (req: Request, res: Response) => {
+      res.json(this.backups.getStatus());
+    });
+
+    this.route('post', '/backup/ipfs', {
+      summary: 'Add an encrypted backup of earnings and settings to IPFS now',
+      tag: 'Agent',
+    }, async (req: Request, res: Response) => {
       try {
-        this.server = this.app.listen(this.port, '127.0.0.1', () => {
-          console.log(`[API] Server listening on http://127.0.0.1:${this.port}`);
-          resolve();
+        res.json(await this.backups.backupToIpfs());
+      } catch (error: any) {
+        sendError(res, error);
+      }
+    });
+
+    this.route('get', '/audit', {
+      summary: 'Mutating API calls, newest first',
+      tag: 'Agent',
+      query: listQueryDoc(AUDIT_LIST_SPEC, {
+        since: { type: 'string', format: 'date-time' },
+        until: { type: 'string', format: 'date-time' },
+      }),
+      response: {
+        type: 'object',
+        properties: {
+          entries: { type: 'array', items: { type: 'object' } },
+          ...PAGE_RESPONSE_PROPERTIES,
+        },
+      },
+    }, (req: Request, res: Response) => {
+      const query = parseListQuery(req.query, AUDIT_LIST_SPEC);
+      const { items, ...page } = paginate(this.audit.list(), query, AUDIT_LIST_SPEC);
+      res.json({ entries: items, ...page });
+    });
+
+    this.route('get', '/events', {
+      summary: 'Server-Sent Events stream of agent events, for networks that block WebSockets',
+      tag: 'Agent',
+      query: {
+        topics: { type: 'string', description: 'Comma-separated: status, challenge, pins, earnings' },
+        lastEventId: { type: 'integer', description: 'Resume after this event id' },
+      },
+    }, this.sse.handler());
+
+    this.route('get', '/grpc/proto', {
+      summary: 'Protobuf definition of the gRPC interface',
+      tag: 'Agent',
+    }, (req: Request, res: Response) => {
+      res.type('text/plain').send(AGENT_PROTO);
+    });
+
+    this.auth.allowPublic('get', '/api/spec');
+    this.router.get('/spec', (req: Request, res: Response) => {
+      res.json(buildOpenApiDocument(this.routes, this.port));
+    });
+
+    // Probes, like the status route, answer without the token; /metrics needs it
+    this.auth.allowPublic('get', '/healthz');
+    this.auth.allowPublic('get', '/readyz');
+    // Liveness: the agent answers. 503 only once the daemon has given up for good
+    this.app.get('/healthz', async (req: Request, res: Response) => {
+      const report = await this.health.check();
+      res.status(report.daemon.state === 'Failed' ? 503 : 200).json(report);
+    });
+
+    // Readiness: the node can take pins and answer challenges right now
+    this.app.get('/readyz', async (req: Request, res: Response) => {
+      const report = await this.health.check();
+      res.status(report.status === 'ok' ? 200 : 503).json(report);
+    });
+
+    this.app.get('/metrics', async (req: Request, res: Response) => {
+      res.type(METRICS_CONTENT_TYPE).send(await this.metrics.render());
+    });
+
+    this.app.use(PINNING_SERVICE_PREFIX, this.pinningService.router(this.auth));
+    this.app.use(API_PREFIX, this.router);
+    // Unversioned paths predate v1; existing web app builds and validators still use them
+    this.app.use('/api', this.router);
+    this.app.use('/api', (req: Request, res: Response) => {
+      sendError(res, new ApiError('NOT_FOUND', `No such endpoint: ${req.method} ${req.originalUrl.split('?')[0]}`));
+    });
+    this.app.use(errorHandler());
+  }
+
+  private async pinCid(cid: string): Promise<{ pins: string[]; instance: string }> {
+    const done = this.activity.begin('upload');
+    try {
+      const target = await this.pool.selectForNewContent();
+      const response = await axios.post(
+        `${target.getApiUrl()}/api/v0/pin/add?arg=${cid}`,
+        null,
+        { timeout: 300000 }
+      );
+      this.events.publish('pins', 'pinned', { cid, instance: target.getInstanceId() });
+
+      // Announce now so the first challenge doesn't race the reprovider
+      target.reprovide([cid]).catch((error: any) => {
+        console.error(`[API] Failed to provide ${cid}:`, error.message);
+      });
+      return { pins: response.data.Pins, instance: target.getInstanceId() };
+    } finally {
+      done();
+    }
+  }
+
+  // The DAG's cumulative size, from the instance holding it
+  private async sizeOf(cid: string): Promise<number | null> {
+    const holder = await this.pool.findHolder(cid).catch(() => null);
+    const response = await axios.post(`${(holder || this.kubo).getApiUrl()}/api/v0/files/stat?arg=/ipfs/${cid}`, null, { timeout: 30000 })
+      .catch(() => null);
+    return typeof response?.data?.CumulativeSize === 'number' ? response.data.CumulativeSize : null;
+  }
+
+  private async unpinCid(cid: string): Promise<void> {
+    const holder = await this.pool.findHolder(cid);
+    await axios.post(`${(holder || this.kubo).getApiUrl()}/api/v0/pin/rm?arg=${cid}`);
+    this.pins.remove(cid);
+    this.proofCache.invalidate(cid);
+    // The MFS view would otherwise keep the blocks from garbage collection
+    if (this.config.getConfig().pinReconcile.mirrorMfs) this.mfsMirror.scheduleSync();
+    this.events.publish('pins', 'unpinned', { cid });
+  }
+
+  // Pinned by name or under a label of its own, not only for a storage contract
+  private isHeldOutsideContracts = (cid: string): boolean => {
+    const metadata = this.pins.get(cid);
+    return !!metadata && (metadata.name !== null || metadata.labels.some((label) => !label.startsWith('contract:')));
+  };
+
+  private async listPins(type: string): Promise<PinListing[]> {
+    const pins = new Map<string, PinListing>();
+    for (const kubo of this.pool.getInstances()) {
+      if (!kubo.isRunning()) continue;
+      const response = await axios.post(`${kubo.getApiUrl()}/api/v0/pin/ls?type=${type}`);
+      for (const [cid, info] of Object.entries<any>(response.data.Keys || {})) {
+        if (pins.has(cid)) continue;
+        const metadata = this.pins.get(cid);
+        pins.set(cid, {
+          cid,
+          type: info.Type,
+          instance: kubo.getInstanceId(),
+          name: metadata?.name ?? null,
+          labels: metadata?.labels ?? [],
+          pinnedAt: metadata?.pinnedAt ?? null,
         });
+      }
+    }
+    return Array.from(pins.values());
+  }
 
-        this.server.on('error', (error: any) => {
-          if (error.code === 'EADDRINUSE') {
-            console.log(`[API] Port ${this.port} in use, trying ${this.port + 1}`);
-            this.port++;
-            this.server = this.app.listen(this.port, '127.0.0.1', () => {
-              resolve();
-            });
-          } else {
-            reject(error);
-          }
+  /**
+   * Adds a stream on the instance with the most free space, publishing
+   * progress on the `pins` topic, and announces the result.
+   */
+  private async addContent(uploadId: string, source: Readable, name: string, total: number | null): Promise<AddResult> {
+    const done = this.activity.begin('upload');
+    try {
+      const target = await this.pool.selectForNewContent();
+      const { cid, size } = await addStream(target.getApiUrl(), source, name, (bytes) => {
+        this.events.publish('pins', 'upload-progress', { uploadId, name, bytes, total });
+      });
+      const carSize = await getCarSize(target.getApiUrl(), cid);
+      console.log(`[API] Added ${name} as ${cid} (${carSize} bytes as CAR)`);
+
+      this.pins.set(cid, { name });
+      this.events.publish('pins', 'pinned', { cid, instance: target.getInstanceId() });
+      target.reprovide([cid]).catch((error: any) => {
+        console.error(`[API] Failed to provide ${cid}:`, error.message);
+      });
+      return { cid, name, size, carSize };
+    } finally {
+      done();
+    }
+  }
+
+  // Shared by the API route and the libp2p challenge listener
+  private answerChallenge = async (req: Request, res: Response) => {
+    const { cid, blockIndex, salt, validatorId, offset, length, shard: shardIndex } = req.body;
+    // Byte-range challenges name a slice of the file instead of a block
+    const isRange = offset !== undefined || length !== undefined;
+
+    if (!cid || !salt || (isRange ? offset === undefined || length === undefined : blockIndex === undefined)) {
+      return sendError(res, new ApiError('INVALID_REQUEST', 'Missing required fields: cid, salt, and blockIndex or offset and length'));
+    }
+    const rejection = checkChallengeSignature(this.config.getConfig().poa, validatorId ?? null, req.body, this.challengeReplay);
+    if (rejection) {
+      return sendError(res, new ApiError('UNAUTHORIZED', `Challenge refused: ${rejection}`));
+    }
+
+    const startTime = Date.now();
+    const done = this.activity.begin('challenge');
+    // A file this node only holds shards of is proven through its shard
+    const shard = this.contracts.contractsFor(cid).length > 0
+      ? null
+      : this.contracts.shardFor(cid, Number.isInteger(shardIndex) ? shardIndex : undefined);
+    const target = shard?.cid ?? cid;
+    const shardFields = shard ? { shard: shard.index, shardCid: shard.cid } : {};
+    const node = (await this.pool.findHolder(target).catch(() => null)) || this.kubo;
+    const record = {
+      at: new Date(startTime).toISOString(),
+      protocol: 'rest' as const,
+      cid,
+      blockIndex: isRange ? null : blockIndex,
+      range: isRange ? { offset, length } : null,
+      shard: shard ? { index: shard.index, cid: shard.cid } : null,
+      salt,
+      validatorId: validatorId ?? null,
+      contractIds: this.contracts.contractsFor(target),
+      instance: node.getInstanceId(),
+    };
+
+    try {
+      if (isRange) {
+        const result = await generateRangeProof(node, target, offset, length, salt);
+        const responseTime = Date.now() - startTime;
+        this.recordChallengeResult({
+          ...record,
+          passed: true,
+          responseTime,
+          proof: result.proof,
+          blockCid: result.leafCids[0] ?? null,
+          blockCount: result.leafCids.length,
+          blockBytes: result.blockBytes,
+          refsMs: null,
+          blockReadMs: result.readMs,
+          error: null,
         });
-      } catch (error) {
-        reject(error);
+        const answer = {
+          success: true,
+          proof: result.proof,
+          leafCids: result.leafCids,
+          fileSize: result.fileSize,
+          responseTime,
+          cid,
+          salt,
+          offset,
+          length,
+          ...shardFields,
+        };
+        return res.json(this.config.getConfig().poa.signMessages ? this.signer.sign(answer) : answer);
+      }
+
+      const result = await generateProof(node, target, blockIndex, salt, { cache: this.proofCache });
+      const responseTime = Date.now() - startTime;
+      this.recordChallengeResult({ ...record, ...result, passed: true, responseTime, error: null });
+
+      const answer = { success: true, proof: result.proof, blockCid: result.blockCid, responseTime, cid, salt, ...shardFields };
+      res.json(this.config.getConfig().poa.signMessages ? this.signer.sign(answer) : answer);
+    } catch (error: any) {
+      if (error instanceof BlockIndexError) {
+        return sendError(res, new ApiError('INVALID_REQUEST', 'Block index out of range'));
       }
+      if (error instanceof ByteRangeError) {
+        return sendError(res, new ApiError('INVALID_REQUEST', error.message));
+      }
+
+      const responseTime = Date.now() - startTime;
+      this.recordChallengeResult({
+        ...record,
+        passed: false,
+        responseTime,
+        proof: null,
+        blockCid: null,
+        blockCount: null,
+        blockBytes: null,
+        refsMs: null,
+        blockReadMs: null,
+        error: error.message,
+      });
+
+      sendError(res, error, { responseTime });
+    } finally {
+      done();
+    }
+  };
+
+  /**
+   * Books a challenge answer, whichever way it came in: earnings and their
+   * history, the last-challenge record, and the event bus, which feeds
+   * clients and webhooks. Milestones and goals it reaches are recorded in
+   * earnings.db, so each is announced once across restarts.
+   */
+  private recordChallengeResult(record: ChallengeRecord): void {
+    const reward = record.passed ? HBD_PER_CHALLENGE : 0;
+    // Earnings go to whoever the CID is stored for; unknown CIDs to the active account
+    const account = this.contracts.accountFor(record.shard?.cid ?? record.cid) ?? this.config.getConfig().hiveUsername;
+    // Valued at the price of the moment, for the ledger export
+    const { quote, stale } = this.prices.getStatus();
+    const fiat = quote && !stale ? { value: reward * quote.hbd, currency: quote.currency } : null;
+    const earnings = this.config.recordChallenge(record.passed, reward, record.cid, account, this.finality.anchor(),
+      record.salt, fiat);
+    this.challenges.record(record);
+    this.challengeLog.record(record, reward);
+    this.riskMonitor.observe(record);
+    const { cid, blockIndex, validatorId, responseTime } = record;
+    if (record.passed) {
+      this.events.publish('challenge', 'passed', { cid, blockIndex, validatorId, responseTime });
+    } else {
+      this.events.publish('challenge', 'failed', { cid, blockIndex, validatorId, error: record.error });
+    }
+    this.events.publish('earnings', 'updated', earnings);
+    for (const milestone of this.config.getEarningsStore().reachMilestones(earnings, this.config.getConfig().milestones)) {
+      this.events.publish('earnings', 'milestone', { ...milestone, earnings });
+      if (milestone.goal) {
+        showNotification('Goal reached', `You reached your goal of ${describeGoal(milestone.goal)}.`, { kind: 'rewards', account: null });
+      }
+    }
+  }
+
+  private parseHistoryQuery(req: Request): ChallengeLogQuery {
+    const value = (name: string) => (typeof req.query[name] === 'string' && req.query[name] !== '' ? String(req.query[name]) : undefined);
+    const passed = value('passed');
+    const limit = value('limit');
+    const query: ChallengeLogQuery = {
+      from: value('from'),
+      to: value('to'),
+      validator: value('validator'),
+      cid: value('cid'),
+      passed: passed === undefined ? undefined : passed === 'true',
+      protocol: value('protocol') as ChallengeLogQuery['protocol'],
+      limit: limit === undefined ? undefined : Number(limit),
+      cursor: value('cursor'),
+    };
+    const errors = validateChallengeLogQuery(query);
+    if (errors.length > 0) throw new ApiError('INVALID_REQUEST', errors.join('; '));
+    return query;
+  }
+
+  getContractPolicy(): ContractPolicyEngine {
+    return this.contractPolicy;
+  }
+
+  getContractExpiry(): ContractExpiryWatcher {
+    return this.contractExpiry;
+  }
+
+  getContractMarket(): ContractMarket {
+    return this.contractMarket;
+  }
+
+  getPinReconciler(): PinReconciler {
+    return this.pinReconciler;
+  }
+
+  getPinImporter(): PinImporter {
+    return this.pinImporter;
+  }
+
+  getCarArchiver(): CarArchiver {
+    return this.carArchiver;
+  }
+
+  getMfsMirror(): MfsMirror {
+    return this.mfsMirror;
+  }
+
+  getStorageBreakdown(): StorageBreakdownIndexer {
+    return this.storageBreakdown;
+  }
+
+  getTierScheduler(): TierScheduler {
+    return this.tiers;
+  }
+
+  getContracts(): ContractPinner {
+    return this.contracts;
+  }
+
+  getSigner(): PoaSigner {
+    return this.signer;
+  }
+
+  getRiskMonitor(): SlashingRiskMonitor {
+    return this.riskMonitor;
+  }
+
+  getDiskHealth(): DiskHealthMonitor {
+    return this.diskHealth;
+  }
+
+  getRepoMover(): RepoMover {
+    return this.repoMover;
+  }
+
+  getTrash(): TrashCollector {
+    return this.trash;
+  }
+
+  getContractBandwidth(): ContractBandwidthMeter {
+    return this.bandwidth;
+  }
+
+  getEarningsProjector(): EarningsProjector {
+    return this.projector;
+  }
+
+  getBenchmark(): PoaBenchmark {
+    return this.benchmark;
+  }
+
+  getEligibility(): EligibilityChecker {
+    return this.eligibility;
+  }
+
+  getChallengeLog(): ChallengeLog {
+    return this.challengeLog;
+  }
+
+  getPoaResponder(): PoaResponder {
+    return this.responder;
+  }
+
+  getPoaValidator(): PoaValidator {
+    return this.validator;
+  }
+
+  // GC may have taken blocks of content that was unpinned elsewhere, so cached lists are rebuilt
+  async refreshProofCache(): Promise<void> {
+    this.proofCache.clear();
+    await this.proofCache.precompute(async (cid) => (await this.pool.findHolder(cid).catch(() => null)) || this.kubo);
+  }
+
+  /**
+   * Lets other nodes' validators reach /challenge over a libp2p stream, so
+   * no port has to be open. Streams arrive on loopback, which the main app
+   * treats as the local user, so they get a server of their own with the
+   * challenge route and nothing else. Kubo forgets listeners when it
+   * restarts; call this each time the daemon comes online.
+   */
+  async startP2pChallenges(): Promise<void> {
+    if (!this.p2pServer) {
+      const app = express();
+      // Same paths as the main API, so the challenge rate limit applies
+      app.use(this.rateLimiter.middleware());
+      app.use(express.json({ limit: '16kb' }));
+      app.get(`${API_PREFIX}/ping`, (req: Request, res: Response) => res.json({ ok: true }));
+      app.post(`${API_PREFIX}/challenge`, this.answerChallenge);
+      const server = http.createServer(app);
+      await new Promise<void>((resolve, reject) => {
+        server.once('error', reject);
+        server.listen(0, '127.0.0.1', () => {
+          server.off('error', reject);
+          resolve();
+        });
+      });
+      this.p2pServer = server;
+    }
+    const { port } = this.p2pServer.address() as { port: number };
+    await this.kubo.p2pListen(POA_PROTOCOL, port);
+    console.log(`[PoA] Answering challenges over libp2p on ${POA_PROTOCOL}`);
+  }
+
+  /**
+   * Picks up Pinning Service requests and contract batches interrupted by
+   * the last shutdown.
+   */
+  resumePinRequests(): void {
+    this.pinningService.resume();
+    this.contracts.resume();
+  }
+
+  async start(): Promise<void> {
+    await this.listenHttp();
+    try {
+      await this.startHttps();
+    } catch (error) {
+      // HTTP keeps working; the dashboard shows the TLS error when toggled
+      console.error('[API] Failed to start HTTPS server:', error);
+    }
+    try {
+      await this.startGrpc();
+    } catch (error) {
+      console.error('[gRPC] Failed to start gRPC server:', error);
+    }
+    this.responder.start();
+    this.validator.start();
+    this.contractPolicy.start();
+    this.contractExpiry.start();
+    this.trash.start();
+    this.pinReconciler.start();
+    this.storageBreakdown.start();
+    this.tiers.start();
+    this.bandwidth.start();
+    this.diskHealth.start();
+    this.riskMonitor.start();
+  }
+
+  private allowUpgrade = (req: http.IncomingMessage, token: string | null): boolean =>
+    this.remoteAccess.allowsUpgrade(req, token);
+
+  private applyTrustProxy(): void {
+    // Only a proxy on this machine may vouch for the client address
+    this.app.set('trust proxy', this.config.getConfig().remoteAccess.trustProxy ? 'loopback' : false);
+  }
+
+  private getHost(): string {
+    return getBindAddress(this.config.getConfig().remoteAccess);
+  }
+
+  /**
+   * Closes and reopens the listeners on the current bind address, after
+   * remote access was turned on or off.
+   */
+  async rebind(): Promise<void> {
+    this.applyTrustProxy();
+    await this.stopHttps();
+    await this.closeHttp();
+    await this.listenHttp();
+    await this.startHttps();
+    await this.startGrpc();
+  }
+
+  // May differ from apiPort if that was taken
+  getPort(): number {
+    return this.port;
+  }
+
+  getHttpsPort(): number | null {
+    const address = this.httpsServer?.address();
+    return address && typeof address === 'object' ? address.port : null;
+  }
+
+  /**
+   * Starts, restarts or stops the HTTPS listener to match the TLS settings.
+   * It serves the same app as HTTP, including /ws/events.
+   */
+  async startHttps(): Promise<void> {
+    const wasRunning = this.httpsServer !== null;
+    await this.stopHttps();
+    const settings = this.config.getConfig().tls;
+    if (!settings.enabled) {
+      if (wasRunning) this.announceEndpoints();
+      return;
+    }
+
+    const credentials = this.certificates.ensureCredentials();
+    const server = https.createServer(credentials, this.app);
+    await new Promise<void>((resolve, reject) => {
+      server.once('error', reject);
+      server.listen(settings.port, this.getHost(), () => {
+        server.off('error', reject);
+        resolve();
+      });
+    });
+    this.httpsServer = server;
+    this.eventStream.attach(server, this.allowUpgrade);
+    console.log(`[API] Server listening on https://${this.getHost()}:${settings.port}`);
+    this.announceEndpoints();
+  }
+
+  private async stopHttps(): Promise<void> {
+    const server = this.httpsServer;
+    if (!server) return;
+    this.httpsServer = null;
+    this.sse.disconnectAll();
+    await new Promise<void>((resolve) => {
+      server.close(() => resolve());
+      server.closeIdleConnections();
+    });
+  }
+
+  getGrpcPort(): number | null {
+    return this.grpc.getPort();
+  }
+
+  /**
+   * Starts, restarts or stops the gRPC listener to match its settings. It
+   * binds where HTTP does and uses the HTTPS certificate when TLS is on.
+   */
+  async startGrpc(): Promise<void> {
+    const wasRunning = this.grpc.getPort() !== null;
+    await this.grpc.stop();
+    const { grpc: settings, tls } = this.config.getConfig();
+    if (!settings.enabled) {
+      if (wasRunning) this.announceEndpoints();
+      return;
+    }
+
+    const credentials = tls.enabled ? this.certificates.ensureCredentials() : null;
+    await this.grpc.start(this.getHost(), settings.port, credentials);
+    this.announceEndpoints();
+  }
+
+  /**
+   * Listens on apiPort, or the first free port in the fallback range above
+   * it so a stale agent or another program doesn't leave us without an API.
+   */
+  private async listenHttp(): Promise<void> {
+    const { apiPort, apiPortFallbacks } = this.config.getConfig();
+    const lastPort = apiPort + Math.max(0, apiPortFallbacks);
+
+    for (let port = apiPort; port <= lastPort; port++) {
+      try {
+        this.server = await this.listenOn(port);
+      } catch (error: any) {
+        if (error.code !== 'EADDRINUSE') throw error;
+        console.log(`[API] Port ${port} in use${port < lastPort ? `, trying ${port + 1}` : ''}`);
+        continue;
+      }
+      this.port = port;
+      console.log(`[API] Server listening on http://${this.getHost()}:${port}`);
+      this.eventStream.attach(this.server, this.allowUpgrade);
+      this.announceEndpoints();
+      return;
+    }
+    throw new Error(`Ports ${apiPort}-${lastPort} are all in use`);
+  }
+
+  private listenOn(port: number): Promise<http.Server> {
+    return new Promise((resolve, reject) => {
+      const server = http.createServer(this.app);
+      server.once('error', reject);
+      server.listen(port, this.getHost(), () => {
+        server.off('error', reject);
+        resolve(server);
+      });
     });
   }
 
+  getEndpoints(): ApiEndpoints {
+    return { host: this.getHost(), port: this.port, httpsPort: this.getHttpsPort(), grpcPort: this.getGrpcPort() };
+  }
+
+  // Keeps the discovery file and anyone listening in step with the listeners
+  private announceEndpoints(): void {
+    const endpoints = this.getEndpoints();
+    writeDiscoveryFile(this.config.getDataDir(), endpoints);
+    this.emit('endpoints-changed', endpoints);
+  }
+
   async stop(): Promise<void> {
+    this.eventStream.close();
+    this.sse.close();
+    this.responder.stop();
+    this.validator.stop();
+    this.contractPolicy.stop();
+    this.contractExpiry.stop();
+    this.trash.stop();
+    this.pinReconciler.stop();
+    this.mfsMirror.stop();
+    this.storageBreakdown.stop();
+    this.tiers.stop();
+    this.bandwidth.stop();
+    this.diskHealth.stop();
+    this.riskMonitor.stop();
+    this.proofCache.flush();
+    this.challengeLog.close();
+    this.p2pServer?.close();
+    this.p2pServer = null;
+    await this.grpc.stop();
+    await this.stopHttps();
+    await this.closeHttp();
+    removeDiscoveryFile(this.config.getDataDir());
+  }
+
+  private closeHttp(): Promise<void> {
     return new Promise((resolve) => {
       if (this.server) {
-        this.server.close(() => {
+        const server = this.server;
+        this.server = null;
+        this.sse.disconnectAll();
+        server.close(() => {
           console.log('[API] Server stopped');
           resolve();
         });
+        // Idle keep-alive sockets would otherwise hold close() open
+        server.closeIdleConnections();
       } else {
         resolve();
       }
@@ -0,0 +1,117 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { Request, Response, NextFunction } from 'express';
+import { ListSpec } from './query';
+
+// Rotated to audit.log.1 past this size; two generations are kept
+const MAX_LOG_BYTES = 10 * 1024 * 1024;
+// Validator challenges are answers, not actions, and would drown everything else
+const UNAUDITED_ROUTES = [/^\/api\/(v\d+\/)?challenge$/];
+
+export interface AuditEntry {
+  at: string;
+  method: string;
+  path: string;
+  status: number;
+  durationMs: number;
+  // Who: the caller as far as the agent can tell
+  ip: string | null;
+  origin: string | null;
+  userAgent: string | null;
+  authenticated: boolean;
+  // What: the CID acted on, when the request names one
+  cid: string | null;
+}
+
+export const AUDIT_LIST_SPEC: ListSpec<AuditEntry> = {
+  sortFields: { at: (entry) => entry.at },
+  defaultSort: '-at',
+  filters: {
+    since: (entry, value) => Date.parse(entry.at) >= Date.parse(value),
+    until: (entry, value) => Date.parse(entry.at) <= Date.parse(value),
+    path: (entry, value) => entry.path.includes(value),
+    cid: (entry, value) => entry.cid === value,
+    method: (entry, value) => entry.method === value.toUpperCase(),
+  },
+  // Entries have no id; two identical requests in the same millisecond are one as far as cursors go
+  key: (entry) => `${entry.at} ${entry.method} ${entry.path} ${entry.ip}`,
+};
+
+/**
+ * Persistent record of every mutating API call, for operators who share a
+ * machine and need to know who pinned or unpinned what. Entries are NDJSON
+ * in ~/.spk-ipfs/audit.log, including rejected attempts.
+ */
+export class AuditLog {
+  private filePath: string;
+  private isAuthorized: (req: Request) => boolean;
+
+  constructor(dataDir: string, isAuthorized: (req: Request) => boolean) {
+    this.filePath = path.join(dataDir, 'audit.log');
+    this.isAuthorized = isAuthorized;
+  }
+
+  middleware() {
+    return (req: Request, res: Response, next: NextFunction) => {
+      if (['GET', 'HEAD', 'OPTIONS'].includes(req.method)) return next();
+
+      const startedAt = Date.now();
+      // Routers rewrite req.path while they run, so keep the full one
+      const requestPath = req.originalUrl.split('?')[0];
+      if (UNAUDITED_ROUTES.some((route) => route.test(requestPath))) return next();
+
+      res.on('finish', () => {
+        const pathCid = requestPath.match(/\/pins\/([^/]+)$/)?.[1] || null;
+        this.append({
+          at: new Date(startedAt).toISOString(),
+          method: req.method,
+          path: requestPath,
+          status: res.statusCode,
+          durationMs: Date.now() - startedAt,
+          ip: req.ip || null,
+          origin: (req.headers.origin as string) || null,
+          userAgent: ((req.headers['user-agent'] as string) || '').slice(0, 200) || null,
+          authenticated: this.isAuthorized(req),
+          cid: typeof req.body?.cid === 'string' ? req.body.cid : pathCid,
+        });
+      });
+      next();
+    };
+  }
+
+  append(entry: AuditEntry): void {
+    try {
+      this.rotateIfNeeded();
+      fs.appendFileSync(this.filePath, JSON.stringify(entry) + '\n');
+    } catch (error) {
+      console.error('[Audit] Failed to write audit log:', error);
+    }
+  }
+
+  /**
+   * Every entry across the current and rotated file, oldest first.
+   */
+  list(): AuditEntry[] {
+    return [`${this.filePath}.1`, this.filePath].flatMap((file) => this.readEntries(file));
+  }
+
+  private readEntries(file: string): AuditEntry[] {
+    if (!fs.existsSync(file)) return [];
+    return fs.readFileSync(file, 'utf-8')
+      .split('\n')
+      .filter((line) => line.trim().length > 0)
+      .map((line) => {
+        try {
+          return JSON.parse(line) as AuditEntry;
+        } catch {
+          return null;
+        }
+      })
+      .filter((entry): entry is AuditEntry => entry !== null);
+  }
+
+  private rotateIfNeeded(): void {
+    if (!fs.existsSync(this.filePath) || fs.statSync(this.filePath).size < MAX_LOG_BYTES) return;
+    fs.renameSync(this.filePath, `${this.filePath}.1`);
+  }
+}
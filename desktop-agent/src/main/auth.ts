@@ -0,0 +1,125 @@
+import * as crypto from 'crypto';
+import { Request, Response, NextFunction } from 'express';
+import { ConfigStore } from './config';
+import { ApiError, sendError } from './api-errors';
+
+// EventSource and <video>/<img> can't set headers, so the SSE stream and
+// content previews also take the token as `?token=`
+const QUERY_TOKEN_ROUTES = [/^\/api\/events$/, /^\/api\/preview\/[^/]+$/];
+
+// Versioned and legacy unversioned paths (`/api/v1/pins`, `/api/pins`) are the same route
+function normalizePath(path: string): string {
+  return path.replace(/^\/api\/v\d+(\/|$)/, '/api$1');
+}
+
+function routeKey(method: string, path: string): string {
+  const verb = method.toUpperCase() === 'HEAD' ? 'GET' : method.toUpperCase();
+  return `${verb} ${normalizePath(path)}`;
+}
+
+export type PairingApprover = (request: { origin: string | null; appName: string }) => Promise<boolean>;
+
+function tokensMatch(provided: string, expected: string): boolean {
+  const a = Buffer.from(provided);
+  const b = Buffer.from(expected);
+  return a.length === b.length && crypto.timingSafeEqual(a, b);
+}
+
+/**
+ * Bearer-token auth for the local API. Each install has one secret; the SPK
+ * web app gets it through a pairing request the user approves in a native
+ * dialog, since any local process or web page can reach 127.0.0.1.
+ */
+export class ApiAuth {
+  private config: ConfigStore;
+  private approve: PairingApprover;
+  private pairingInFlight = false;
+  // Everything else needs the token, reads included
+  private publicRoutes = new Set<string>();
+
+  constructor(config: ConfigStore, approve: PairingApprover) {
+    this.config = config;
+    this.approve = approve;
+  }
+
+  getToken(): string {
+    let token = this.config.getApiToken();
+    if (!token) {
+      token = this.regenerateToken();
+    }
+    return token;
+  }
+
+  /**
+   * Issues a new secret, which unpairs every client holding the old one.
+   */
+  regenerateToken(): string {
+    const token = crypto.randomBytes(32).toString('hex');
+    this.config.setApiToken(token);
+    console.log('[Auth] Generated new API token');
+    return token;
+  }
+
+  /**
+   * Lets a route be called without the token. Each route says so where it's
+   * registered; the ones that do are how the web app detects the agent, how
+   * validators challenge it, how a client pairs, and the API description.
+   */
+  allowPublic(method: string, path: string): void {
+    this.publicRoutes.add(routeKey(method, path));
+  }
+
+  requiresToken(method: string, path: string): boolean {
+    return method.toUpperCase() !== 'OPTIONS' && !this.publicRoutes.has(routeKey(method, path));
+  }
+
+  isAuthorized(req: Request): boolean {
+    const header = req.headers.authorization || '';
+    const match = header.match(/^Bearer\s+(\S+)$/i);
+    if (match) return this.isValidToken(match[1]);
+    const path = normalizePath(req.path);
+    return QUERY_TOKEN_ROUTES.some((route) => route.test(path))
+      && typeof req.query.token === 'string' && this.isValidToken(req.query.token);
+  }
+
+  isValidToken(token: string): boolean {
+    return tokensMatch(token, this.getToken());
+  }
+
+  middleware() {
+    return (req: Request, res: Response, next: NextFunction) => {
+      if (!this.requiresToken(req.method, req.path) || this.isAuthorized(req)) {
+        return next();
+      }
+      sendError(res, new ApiError('UNAUTHORIZED', 'Missing or invalid API token; pair with the agent first'));
+    };
+  }
+
+  /**
+   * Asks the user whether to hand the token to the requesting app. Only one
+   * prompt is shown at a time so a page can't stack dialogs.
+   */
+  async pair(req: Request, res: Response): Promise<void> {
+    if (this.pairingInFlight) {
+      sendError(res, new ApiError('RATE_LIMITED', 'A pairing request is already waiting for approval'));
+      return;
+    }
+
+    this.pairingInFlight = true;
+    try {
+      const origin = (req.headers.origin as string) || null;
+      const appName = String(req.body?.appName || 'Unknown app').slice(0, 100);
+      const approved = await this.approve({ origin, appName });
+      if (!approved) {
+        console.log(`[Auth] Pairing denied for ${origin || appName}`);
+        sendError(res, new ApiError('FORBIDDEN', 'Pairing was denied'));
+        return;
+      }
+
+      console.log(`[Auth] Paired ${origin || appName}`);
+      res.json({ token: this.getToken() });
+    } finally {
+      this.pairingInFlight = false;
+    }
+  }
+}
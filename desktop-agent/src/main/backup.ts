@@ -0,0 +1,448 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as zlib from 'zlib';
+import * as crypto from 'crypto';
+import axios from 'axios';
+import { app } from 'electron';
+import { AgentConfig, ConfigStore } from './config';
+import { ChallengeLog } from './challenge-log';
+import { KuboManager } from './kubo';
+import { SecretStore, SCRYPT, MIN_PASSPHRASE, deriveKey, validateVaultSettings } from './secrets';
+import { addStream } from './uploads';
+import { validateConnectionMode, validateKuboSettings } from './settings';
+import { validateBandwidthLimits } from './bandwidth';
+import { validatePeeringDnsaddrs } from './peering';
+import { validatePrivateNetwork } from './private-network';
+import { validateResourceLimits } from './resource-limits';
+import { validateTlsSettings } from './tls';
+import { validateRemoteAccess } from './remote-access';
+import { validateGrpcSettings } from './grpc';
+import { validatePoaSettings } from './poa-responder';
+import { validateValidatorSettings } from './validator';
+import { validateContractPolicy } from './contract-policy';
+import { validateContractExpirySettings } from './contract-expiry';
+import { validateHiveSigningSettings } from './hive-signing';
+import { validateChainRegistrationSettings } from './chain-registration';
+import { validateRewardClaimSettings } from './reward-claims';
+import { validateWalletSettings } from './wallet';
+import { validatePriceFeedSettings } from './price-feed';
+import { validateHiveNodeSettings } from './hive-rpc';
+import { validateHoneycombSettings } from './honeycomb';
+import { validateDelegationSettings } from './delegations';
+import { validateContractMarketSettings } from './contract-market';
+import { validatePinReconcileSettings } from './pin-reconciler';
+import { validateReplicationTierSettings } from './replication-tiers';
+import { validateDiskHealthSettings } from './disk-health';
+import { validateScrubSettings } from './scrubber';
+import { validateTrashSettings } from './trash';
+import { validateDailySummarySettings } from './daily-summary';
+import { validateMilestoneSettings } from './earnings';
+import { validateNetworkRankSettings } from './network-rank';
+import { validateAnomalySettings } from './earnings-anomaly';
+import { validateAnalyticsSettings } from './analytics';
+
+const FORMAT = 'spk-agent-backup';
+const CHECK_INTERVAL_MS = 60 * 60 * 1000;
+// Staged by a restore and swapped in at the next start, before anything opens them
+const RESTORE_DIR = 'restore-pending';
+const RESTORED_FILES = ['earnings.db', 'challenges.db', 'uptime.json'];
+// Kept as they are on this machine: where it keeps things, and webhooks whose secrets are sealed to it
+const MACHINE_SETTINGS: Array<keyof AgentConfig> = ['ipfsRepoPath', 'extraRepos', 'kuboVersion', 'webhooks'];
+
+export interface BackupSettings {
+  // Encrypted backups added and pinned to this node's IPFS repo
+  autoIpfs: boolean;
+  intervalHours: number;
+  // IPFS backups kept pinned; older ones are unpinned
+  keep: number;
+}
+
+export const DEFAULT_BACKUP_SETTINGS: BackupSettings = {
+  autoIpfs: false,
+  intervalHours: 24,
+  keep: 3,
+};
+
+export function validateBackupSettings(settings: Partial<BackupSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.autoIpfs !== undefined && typeof settings.autoIpfs !== 'boolean') {
+    errors.push('autoIpfs must be a boolean');
+  }
+  if (settings.intervalHours !== undefined
+    && (!Number.isInteger(settings.intervalHours) || settings.intervalHours < 1 || settings.intervalHours > 24 * 30)) {
+    errors.push('intervalHours must be between 1 and 720');
+  }
+  if (settings.keep !== undefined && (!Number.isInteger(settings.keep) || settings.keep < 1 || settings.keep > 30)) {
+    errors.push('keep must be between 1 and 30');
+  }
+  return errors;
+}
+
+// Sections of a restored config checked the way their settings handlers check an update
+const SECTION_VALIDATORS: Partial<Record<keyof AgentConfig, (value: any, current: any) => string[]>> = {
+  kubo: validateKuboSettings,
+  connectionMode: validateConnectionMode,
+  bandwidth: validateBandwidthLimits,
+  peeringDnsaddrs: validatePeeringDnsaddrs,
+  privateNetwork: (value) => (value === null ? [] : validatePrivateNetwork(value)),
+  resourceLimits: validateResourceLimits,
+  tls: validateTlsSettings,
+  remoteAccess: validateRemoteAccess,
+  grpc: validateGrpcSettings,
+  poa: validatePoaSettings,
+  validator: validateValidatorSettings,
+  contractPolicy: validateContractPolicy,
+  contractExpiry: validateContractExpirySettings,
+  hiveSigning: validateHiveSigningSettings,
+  chainRegistration: validateChainRegistrationSettings,
+  rewardClaims: validateRewardClaimSettings,
+  wallet: validateWalletSettings,
+  priceFeed: validatePriceFeedSettings,
+  hiveNodes: validateHiveNodeSettings,
+  honeycomb: validateHoneycombSettings,
+  delegations: validateDelegationSettings,
+  contractMarket: validateContractMarketSettings,
+  pinReconcile: validatePinReconcileSettings,
+  replicationTiers: validateReplicationTierSettings,
+  vault: validateVaultSettings,
+  diskHealth: validateDiskHealthSettings,
+  scrub: validateScrubSettings,
+  trash: validateTrashSettings,
+  dailySummary: validateDailySummarySettings,
+  milestones: validateMilestoneSettings,
+  networkRank: validateNetworkRankSettings,
+  anomaly: validateAnomalySettings,
+  analytics: validateAnalyticsSettings,
+  backup: validateBackupSettings,
+};
+
+function isPlainObject(value: unknown): value is Record<string, unknown> {
+  return typeof value === 'object' && value !== null && !Array.isArray(value);
+}
+
+/**
+ * The settings of a backup, fitted to this agent: fields it doesn't know
+ * are dropped, sections are merged over the current value so one written
+ * by an older agent keeps the fields it lacked, and every section is
+ * checked as its settings handler would check it.
+ */
+export function restorableSettings(restored: unknown, current: AgentConfig): { settings: Partial<AgentConfig>; errors: string[] } {
+  if (!isPlainObject(restored)) return { settings: {}, errors: ['The backup holds no settings'] };
+  const settings: Record<string, unknown> = {};
+  const errors: string[] = [];
+  for (const key of Object.keys(current) as Array<keyof AgentConfig>) {
+    if (MACHINE_SETTINGS.includes(key) || !(key in restored)) continue;
+    const value = restored[key];
+    const existing = current[key] as unknown;
+    let merged: unknown = value;
+    if (isPlainObject(existing) && isPlainObject(value)) {
+      merged = { ...existing, ...value };
+    } else if (value !== null && existing !== null
+      && (Array.isArray(value) !== Array.isArray(existing) || typeof value !== typeof existing)) {
+      errors.push(`${key} has the wrong type`);
+      continue;
+    }
+    const validate = SECTION_VALIDATORS[key];
+    if (validate) {
+      try {
+        errors.push(...validate(merged, existing).map((error) => `${key}: ${error}`));
+      } catch {
+        errors.push(`${key} is malformed`);
+      }
+    }
+    settings[key] = merged;
+  }
+  return { settings: settings as Partial<AgentConfig>, errors };
+}
+
+export function validatePassphrase(passphrase: unknown): string[] {
+  return typeof passphrase === 'string' && passphrase.length >= MIN_PASSPHRASE
+    ? []
+    : [`The passphrase must be at least ${MIN_PASSPHRASE} characters`];
+}
+
+interface ArchiveFile {
+  format: typeof FORMAT;
+  version: 1;
+  createdAt: string;
+  kdf: 'scrypt';
+  salt: string;
+  N: number;
+  r: number;
+  p: number;
+  // iv, auth tag and ciphertext of the gzipped payload
+  data: string;
+}
+
+interface BackupPayload {
+  createdAt: string;
+  agentVersion: string;
+  settings: AgentConfig;
+  // File name to contents
+  files: Record<string, string>;
+}
+
+export interface IpfsBackup {
+  cid: string;
+  at: string;
+  bytes: number;
+}
+
+export interface BackupStatus {
+  settings: BackupSettings;
+  // Whether automatic backups have a passphrase to encrypt with
+  passphraseSet: boolean;
+  ipfs: IpfsBackup[];
+  lastError: string | null;
+  // Staged by a restore, applied at the next start
+  restorePending: boolean;
+}
+
+export interface RestoreResult {
+  createdAt: string;
+  agentVersion: string;
+  files: string[];
+}
+
+/**
+ * Swaps in what a restore staged, before the stores open. Called at
+ * startup; the SQLite side files of the replaced databases go with them.
+ */
+export function applyStagedRestore(dataDir: string): void {
+  const staged = path.join(dataDir, RESTORE_DIR);
+  if (!fs.existsSync(staged)) return;
+  for (const file of fs.readdirSync(staged)) {
+    if (!RESTORED_FILES.includes(file)) continue;
+    const target = path.join(dataDir, file);
+    for (const side of ['', '-wal', '-shm']) fs.rmSync(`${target}${side}`, { force: true });
+    fs.renameSync(path.join(staged, file), target);
+  }
+  fs.rmSync(staged, { recursive: true, force: true });
+  console.log('[Config] Applied the restored backup');
+}
+
+/**
+ * Backs up what can't be rebuilt after a reinstall: earnings.db with its
+ * lifetime totals and milestones, the challenge log, uptime history and
+ * the agent settings. An archive is one file, AES-256-GCM under a key
+ * derived from the user's passphrase with scrypt, so it is safe to keep
+ * anywhere. Automatic backups add the archive to this node's IPFS repo and
+ * pin it; the passphrase for them is kept with the other secrets. Restoring
+ * applies the settings right away and stages the files for the next start,
+ * since the stores have them open.
+ */
+export class BackupManager {
+  private dataDir: string;
+  private config: ConfigStore;
+  private secrets: SecretStore;
+  private kubo: KuboManager;
+  private challengeLog: () => ChallengeLog;
+  private statePath: string;
+  private ipfs: IpfsBackup[];
+  private lastError: string | null = null;
+  private timer: NodeJS.Timeout | null = null;
+  private running = false;
+
+  constructor(dataDir: string, config: ConfigStore, secrets: SecretStore, kubo: KuboManager, challengeLog: () => ChallengeLog) {
+    this.dataDir = dataDir;
+    this.config = config;
+    this.secrets = secrets;
+    this.kubo = kubo;
+    this.challengeLog = challengeLog;
+    this.statePath = path.join(dataDir, 'backups.json');
+    this.ipfs = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.checkAuto().catch(() => undefined), CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getStatus(): BackupStatus {
+    return {
+      settings: this.config.getConfig().backup,
+      passphraseSet: this.config.getBackupPassphrase() !== null,
+      ipfs: this.ipfs,
+      lastError: this.lastError,
+      restorePending: fs.existsSync(path.join(this.dataDir, RESTORE_DIR)),
+    };
+  }
+
+  // Sealed with the other secrets; null clears it
+  setAutoPassphrase(passphrase: string | null): void {
+    this.config.setBackupPassphrase(passphrase === null ? null : this.secrets.encrypt(passphrase));
+  }
+
+  async exportToFile(filePath: string, passphrase: string): Promise<{ path: string; bytes: number }> {
+    const archive = await this.createArchive(passphrase);
+    const tempPath = `${filePath}.tmp`;
+    fs.writeFileSync(tempPath, archive, { mode: 0o600 });
+    fs.renameSync(tempPath, filePath);
+    console.log(`[Config] Wrote a backup to ${filePath}`);
+    return { path: filePath, bytes: archive.length };
+  }
+
+  async restoreFromFile(filePath: string, passphrase: string): Promise<RestoreResult> {
+    return this.restore(fs.readFileSync(filePath), passphrase);
+  }
+
+  async restoreFromIpfs(cid: string, passphrase: string): Promise<RestoreResult> {
+    const response = await axios.post(`${this.kubo.getApiUrl()}/api/v0/cat?arg=${encodeURIComponent(cid)}`, null, {
+      responseType: 'arraybuffer',
+      timeout: 5 * 60 * 1000,
+    });
+    return this.restore(Buffer.from(response.data), passphrase);
+  }
+
+  // Adds an archive to IPFS now; the automatic run calls this when due
+  async backupToIpfs(): Promise<IpfsBackup> {
+    if (this.running) throw new Error('A backup is already running');
+    this.running = true;
+    try {
+      const sealed = this.config.getBackupPassphrase();
+      if (!sealed) throw new Error('Set a passphrase for automatic backups first');
+      const archive = await this.createArchive(this.secrets.decrypt(sealed));
+      const tempPath = path.join(this.dataDir, 'backup-upload.tmp');
+      fs.writeFileSync(tempPath, archive, { mode: 0o600 });
+      let added: { cid: string; size: number };
+      try {
+        added = await addStream(this.kubo.getApiUrl(), fs.createReadStream(tempPath), 'spk-agent-backup', () => undefined);
+      } finally {
+        fs.rmSync(tempPath, { force: true });
+      }
+
+      const entry: IpfsBackup = { cid: added.cid, at: new Date().toISOString(), bytes: archive.length };
+      const { keep } = this.config.getConfig().backup;
+      const kept = [entry, ...this.ipfs.filter((backup) => backup.cid !== entry.cid)];
+      for (const old of kept.slice(keep)) {
+        await axios.post(`${this.kubo.getApiUrl()}/api/v0/pin/rm?arg=${old.cid}`).catch(() => undefined);
+      }
+      this.ipfs = kept.slice(0, keep);
+      this.lastError = null;
+      this.save();
+      console.log(`[Config] Backed up to IPFS as ${entry.cid}`);
+      return entry;
+    } catch (error: any) {
+      this.lastError = error.message;
+      throw error;
+    } finally {
+      this.running = false;
+    }
+  }
+
+  private async checkAuto(): Promise<void> {
+    const { autoIpfs, intervalHours } = this.config.getConfig().backup;
+    if (!autoIpfs || !this.kubo.isRunning() || !this.secrets.isWritable()) return;
+    const last = this.ipfs[0];
+    if (last && Date.now() - Date.parse(last.at) < intervalHours * 60 * 60 * 1000) return;
+    try {
+      await this.backupToIpfs();
+    } catch (error: any) {
+      console.error('[Config] Automatic backup failed:', error.message);
+    }
+  }
+
+  private async createArchive(passphrase: string): Promise<Buffer> {
+    const errors = validatePassphrase(passphrase);
+    if (errors.length > 0) throw new Error(errors[0]);
+
+    // Consistent copies of the databases, taken while they stay in use
+    const tempDir = fs.mkdtempSync(path.join(this.dataDir, 'backup-'));
+    const files: Record<string, string> = {};
+    try {
+      await this.config.getEarningsStore().backup(path.join(tempDir, 'earnings.db'));
+      await this.challengeLog().backup(path.join(tempDir, 'challenges.db'));
+      for (const file of ['earnings.db', 'challenges.db']) {
+        files[file] = fs.readFileSync(path.join(tempDir, file)).toString('base64');
+      }
+    } finally {
+      fs.rmSync(tempDir, { recursive: true, force: true });
+    }
+    const uptimePath = path.join(this.dataDir, 'uptime.json');
+    if (fs.existsSync(uptimePath)) files['uptime.json'] = fs.readFileSync(uptimePath).toString('base64');
+
+    const createdAt = new Date().toISOString();
+    const payload: BackupPayload = { createdAt, agentVersion: app.getVersion(), settings: this.config.getConfig(), files };
+    const salt = crypto.randomBytes(16);
+    const key = await deriveKey(passphrase, salt, SCRYPT);
+    const iv = crypto.randomBytes(12);
+    const cipher = crypto.createCipheriv('aes-256-gcm', key, iv);
+    const ciphertext = Buffer.concat([cipher.update(zlib.gzipSync(JSON.stringify(payload))), cipher.final()]);
+    const archive: ArchiveFile = {
+      format: FORMAT,
+      version: 1,
+      createdAt,
+      kdf: 'scrypt',
+      salt: salt.toString('base64'),
+      N: SCRYPT.N,
+      r: SCRYPT.r,
+      p: SCRYPT.p,
+      data: Buffer.concat([iv, cipher.getAuthTag(), ciphertext]).toString('base64'),
+    };
+    return Buffer.from(JSON.stringify(archive));
+  }
+
+  private async restore(raw: Buffer, passphrase: string): Promise<RestoreResult> {
+    let archive: ArchiveFile;
+    try {
+      archive = JSON.parse(raw.toString('utf-8'));
+    } catch {
+      throw new Error('Not an SPK agent backup');
+    }
+    if (archive?.format !== FORMAT || archive.version !== 1) throw new Error('Not an SPK agent backup');
+    // An archive doesn't get to make the agent spend more than its own backups cost to open
+    if (archive.kdf !== 'scrypt'
+      || ![archive.N, archive.r, archive.p].every((value) => Number.isInteger(value) && value >= 1)
+      || archive.N > SCRYPT.N || archive.r > SCRYPT.r || archive.p > SCRYPT.p) {
+      throw new Error('The backup uses key derivation parameters this agent won\'t accept');
+    }
+
+    let payload: BackupPayload;
+    try {
+      const key = await deriveKey(String(passphrase ?? ''), Buffer.from(archive.salt, 'base64'), archive);
+      const data = Buffer.from(archive.data, 'base64');
+      const decipher = crypto.createDecipheriv('aes-256-gcm', key, data.subarray(0, 12));
+      decipher.setAuthTag(data.subarray(12, 28));
+      payload = JSON.parse(zlib.gunzipSync(Buffer.concat([decipher.update(data.subarray(28)), decipher.final()])).toString('utf-8'));
+    } catch {
+      throw new Error('Wrong passphrase, or the backup is damaged');
+    }
+
+    const { settings, errors } = restorableSettings(payload.settings, this.config.getConfig());
+    if (errors.length > 0) throw new Error(`The backup's settings don't fit this agent: ${errors.join('; ')}`);
+    if (!isPlainObject(payload.files)) throw new Error('The backup holds no files');
+
+    const staged = path.join(this.dataDir, RESTORE_DIR);
+    fs.rmSync(staged, { recursive: true, force: true });
+    fs.mkdirSync(staged, { recursive: true });
+    const files = Object.keys(payload.files).filter((file) => RESTORED_FILES.includes(file));
+    for (const file of files) {
+      fs.writeFileSync(path.join(staged, file), Buffer.from(payload.files[file], 'base64'));
+    }
+
+    this.config.setConfig(settings);
+    console.log(`[Config] Restored settings from the backup of ${payload.createdAt}; files apply at the next start`);
+    return { createdAt: payload.createdAt, agentVersion: payload.agentVersion, files };
+  }
+
+  private load(): IpfsBackup[] {
+    try {
+      return JSON.parse(fs.readFileSync(this.statePath, 'utf-8')).ipfs ?? [];
+    } catch {
+      return [];
+    }
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.statePath, JSON.stringify({ ipfs: this.ipfs }, null, 2));
+    } catch (error: any) {
+      console.error('[Config] Failed to save backup state:', error.message);
+    }
+  }
+}
@@ -0,0 +1,72 @@
+/**
+ * Bandwidth limits for the embedded daemon.
+ *
+ * Kubo has no hard rate limiter, so the configured rates are translated into
+ * the libp2p / bitswap knobs that bound throughput: how many bytes we keep in
+ * flight per peer, how many workers serve blocks, and how many connections
+ * we hold open. The resulting caps are approximate but keep a capped home
+ * connection usable.
+ */
+
+export interface BandwidthLimits {
+  // 0 means unlimited
+  uploadKbps: number;
+  downloadKbps: number;
+}
+
+export const DEFAULT_BANDWIDTH_LIMITS: BandwidthLimits = {
+  uploadKbps: 0,
+  downloadKbps: 0,
+};
+
+// Kubo defaults we fall back to when a direction is unlimited
+const DEFAULT_TASK_WORKERS = 8;
+const DEFAULT_OUTSTANDING_BYTES = 1 << 20;
+const DEFAULT_HIGH_WATER = 200;
+const DEFAULT_LOW_WATER = 50;
+
+// Rough per-connection download budget used to size the connection manager
+const KBPS_PER_CONNECTION = 64;
+
+export function validateBandwidthLimits(limits: Partial<BandwidthLimits>): string[] {
+  const errors: string[] = [];
+  for (const key of ['uploadKbps', 'downloadKbps'] as const) {
+    const value = limits[key];
+    if (value !== undefined && (!Number.isFinite(value) || value < 0)) {
+      errors.push(`${key} must be 0 (unlimited) or a positive number`);
+    }
+  }
+  return errors;
+}
+
+function clamp(value: number, min: number, max: number): number {
+  return Math.max(min, Math.min(max, Math.round(value)));
+}
+
+export function applyBandwidthLimits(repoConfig: any, limits: BandwidthLimits): void {
+  const bitswap = { ...repoConfig.Internal?.Bitswap };
+
+  if (limits.uploadKbps > 0) {
+    const bytesPerSecond = (limits.uploadKbps * 1024) / 8;
+    bitswap.TaskWorkerCount = clamp(limits.uploadKbps / 1024, 1, DEFAULT_TASK_WORKERS);
+    bitswap.EngineTaskWorkerCount = bitswap.TaskWorkerCount;
+    bitswap.MaxOutstandingBytesPerPeer = clamp(bytesPerSecond / 4, 64 * 1024, DEFAULT_OUTSTANDING_BYTES);
+  } else {
+    delete bitswap.TaskWorkerCount;
+    delete bitswap.EngineTaskWorkerCount;
+    delete bitswap.MaxOutstandingBytesPerPeer;
+  }
+
+  repoConfig.Internal = { ...repoConfig.Internal, Bitswap: bitswap };
+
+  const connMgr = { ...repoConfig.Swarm?.ConnMgr };
+  if (limits.downloadKbps > 0) {
+    connMgr.HighWater = clamp(limits.downloadKbps / KBPS_PER_CONNECTION, 20, DEFAULT_HIGH_WATER);
+    connMgr.LowWater = clamp(connMgr.HighWater / 4, 5, DEFAULT_LOW_WATER);
+  } else {
+    connMgr.HighWater = DEFAULT_HIGH_WATER;
+    connMgr.LowWater = DEFAULT_LOW_WATER;
+  }
+
+  repoConfig.Swarm = { ...repoConfig.Swarm, ConnMgr: connMgr };
+}
@@ -0,0 +1,167 @@
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import { KuboManager } from './kubo';
+import { generateProof, generateSpkProof, listBlocks } from './poa';
+import { percentile } from './challenge-diagnostics';
+
+export interface BenchmarkOptions {
+  count: number;
+  ratePerSec: number;
+  // Which proof to compute; SPK reads up to five blocks, REST one
+  protocol: 'rest' | 'spk';
+}
+
+export const DEFAULT_BENCHMARK_OPTIONS: BenchmarkOptions = {
+  count: 100,
+  ratePerSec: 5,
+  protocol: 'spk',
+};
+
+export function validateBenchmarkOptions(options: Partial<BenchmarkOptions>): string[] {
+  const errors: string[] = [];
+  if (options.count !== undefined && (!Number.isInteger(options.count) || options.count < 1 || options.count > 10000)) {
+    errors.push('count must be between 1 and 10000');
+  }
+  if (options.ratePerSec !== undefined && (typeof options.ratePerSec !== 'number' || options.ratePerSec <= 0 || options.ratePerSec > 100)) {
+    errors.push('ratePerSec must be above 0 and at most 100');
+  }
+  if (options.protocol !== undefined && !['rest', 'spk'].includes(options.protocol)) {
+    errors.push('protocol must be rest or spk');
+  }
+  return errors;
+}
+
+export interface BenchmarkReport {
+  options: BenchmarkOptions;
+  startedAt: string;
+  finishedAt: string;
+  cancelled: boolean;
+  completed: number;
+  passed: number;
+  p50: number;
+  p95: number;
+  max: number;
+  deadlineMs: number;
+  // Failure cause to count; answers past the deadline are counted as failures
+  failures: Record<string, number>;
+}
+
+export interface BenchmarkOperations {
+  pinnedCids(): Promise<string[]>;
+  findNode(cid: string): Promise<KuboManager>;
+  deadlineMs(): number;
+}
+
+// Groups errors so the report reads as causes rather than a wall of messages
+function failureCause(error: any): string {
+  if (error.code === 'ECONNABORTED' || /timeout/i.test(error.message)) return 'Kubo request timed out';
+  if (error.code === 'ECONNREFUSED') return 'Kubo not running';
+  const kuboMessage = String(error.response?.data?.Message || '');
+  if (/not found/i.test(kuboMessage)) return 'Block missing from the repo';
+  return (kuboMessage || error.message || 'Unknown error').slice(0, 120);
+}
+
+/**
+ * Fires synthetic challenges at the local repo at a fixed rate and reports
+ * latency percentiles and failure causes, so disks and tuning can be sized
+ * before real stake depends on them. Results don't touch earnings or the
+ * challenge history. Emits `progress` as answers come in.
+ */
+export class PoaBenchmark extends EventEmitter {
+  private ops: BenchmarkOperations;
+  private running = false;
+  private cancelled = false;
+
+  constructor(ops: BenchmarkOperations) {
+    super();
+    this.ops = ops;
+  }
+
+  isRunning(): boolean {
+    return this.running;
+  }
+
+  cancel(): void {
+    this.cancelled = true;
+  }
+
+  async run(overrides: Partial<BenchmarkOptions> = {}): Promise<BenchmarkReport> {
+    if (this.running) throw new Error('A benchmark is already running');
+    const options = { ...DEFAULT_BENCHMARK_OPTIONS, ...overrides };
+    const cids = await this.ops.pinnedCids();
+    if (cids.length === 0) throw new Error('Nothing is pinned to benchmark against');
+
+    this.running = true;
+    this.cancelled = false;
+    const deadlineMs = this.ops.deadlineMs();
+    const startedAt = new Date().toISOString();
+    const latencies: number[] = [];
+    const failures: Record<string, number> = {};
+    let passed = 0;
+    const fail = (cause: string) => { failures[cause] = (failures[cause] || 0) + 1; };
+
+    // REST challenges need an index in range; learning block counts isn't part of the timing
+    const blockCounts = new Map<string, number>();
+
+    const challenge = async (cid: string) => {
+      const node = await this.ops.findNode(cid);
+      const salt = crypto.randomBytes(16).toString('hex');
+      if (options.protocol === 'rest' && !blockCounts.has(cid)) {
+        blockCounts.set(cid, (await listBlocks(node, cid, 60 * 1000)).length);
+      }
+      const start = Date.now();
+      try {
+        if (options.protocol === 'spk') {
+          await generateSpkProof(node, cid, salt);
+        } else {
+          const blockCount = blockCounts.get(cid)!;
+          if (blockCount === 0) return fail('Single-block CID; REST challenges need a DAG');
+          await generateProof(node, cid, crypto.randomInt(blockCount), salt);
+        }
+        const elapsed = Date.now() - start;
+        latencies.push(elapsed);
+        if (elapsed > deadlineMs) fail(`Over the ${deadlineMs}ms deadline`);
+        else passed++;
+      } catch (error: any) {
+        latencies.push(Date.now() - start);
+        fail(failureCause(error));
+      }
+    };
+
+    try {
+      const interval = 1000 / options.ratePerSec;
+      const begin = Date.now();
+      const inFlight: Promise<void>[] = [];
+      let completed = 0;
+      for (let i = 0; i < options.count && !this.cancelled; i++) {
+        const wait = begin + i * interval - Date.now();
+        if (wait > 0) await new Promise((resolve) => setTimeout(resolve, wait));
+        if (this.cancelled) break;
+        // Answers may overlap; that's the load being measured
+        inFlight.push(challenge(cids[crypto.randomInt(cids.length)])
+          .catch((error) => fail(failureCause(error)))
+          .finally(() => this.emit('progress', { completed: ++completed, total: options.count })));
+      }
+      await Promise.all(inFlight);
+
+      const sorted = [...latencies].sort((a, b) => a - b);
+      const report: BenchmarkReport = {
+        options,
+        startedAt,
+        finishedAt: new Date().toISOString(),
+        cancelled: this.cancelled,
+        completed,
+        passed,
+        p50: percentile(sorted, 0.5),
+        p95: percentile(sorted, 0.95),
+        max: sorted[sorted.length - 1] ?? 0,
+        deadlineMs,
+        failures,
+      };
+      console.log(`[PoA] Benchmark: ${passed}/${completed} within ${deadlineMs}ms, p50 ${report.p50}ms, p95 ${report.p95}ms`);
+      return report;
+    } finally {
+      this.running = false;
+    }
+  }
+}
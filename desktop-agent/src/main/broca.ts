@@ -0,0 +1,154 @@
+import { EventEmitter } from 'events';
+import { ConfigStore } from './config';
+import { HiveAccount } from './hive';
+import { HoneycombClient } from './honeycomb';
+import { fromMilli } from './eligibility';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 15 * 60 * 1000;
+const CACHE_MS = 60 * 1000;
+const BLOCK_MS = 3000;
+// Honeycomb refills spent BROCA linearly over this many Hive blocks (5 days)
+export const BROCA_REFILL_BLOCKS = 144000;
+// Honeycomb's cap: this much BROCA per SPK power staked
+export const BROCA_PER_SPK_POWER = 1000;
+// Below this share of the cap, storage ops are likely to start failing
+const LOW_FRACTION = 0.1;
+const CURVE_POINTS = 24;
+
+export interface BrocaPoint {
+  at: string;
+  broca: number;
+}
+
+export interface BrocaStatus {
+  username: string;
+  // As the chain last recorded it, at storedAtBlock
+  stored: number;
+  storedAtBlock: number;
+  headBlock: number;
+  // stored plus what has regenerated since
+  current: number;
+  max: number;
+  // When current reaches max; null when it already has, or nothing can regenerate
+  fullAt: string | null;
+  low: boolean;
+  // Forecast from now until full, for the dashboard chart
+  curve: BrocaPoint[];
+  checkedAt: string;
+}
+
+export interface BrocaCostCheck {
+  cost: number;
+  available: number;
+  ok: boolean;
+  // When enough will have regenerated; null when ok, or never (cost above the cap)
+  availableAt: string | null;
+}
+
+/**
+ * Tracks the account's BROCA, the SPK chain's resource credit for storage
+ * operations. The chain only records the balance as of the last block it
+ * was spent in; the rest is worked out here from the refill rate, so the
+ * dashboard sees the live figure and a forecast. A balance falling below a
+ * tenth of the cap raises a notification, once per dip.
+ */
+export class BrocaTracker extends EventEmitter {
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private honeycomb: HoneycombClient;
+  private timer: NodeJS.Timeout | null = null;
+  private last: BrocaStatus | null = null;
+  private warned = false;
+
+  constructor(config: ConfigStore, account: HiveAccount, honeycomb: HoneycombClient) {
+    super();
+    this.config = config;
+    this.account = account;
+    this.honeycomb = honeycomb;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.getStatus(true).catch((error) => console.error('[SPK] BROCA check failed:', error.message));
+    }, CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  async getStatus(force = false): Promise<BrocaStatus> {
+    const { hiveUsername } = this.config.getConfig();
+    if (!hiveUsername) throw new Error('Log in with a Hive account first');
+    if (!force && this.last?.username === hiveUsername && Date.now() - new Date(this.last.checkedAt).getTime() < CACHE_MS) {
+      return this.last;
+    }
+
+    const [data, props] = await Promise.all([
+      this.honeycomb.getAccount(hiveUsername, force),
+      this.account.getClient().database.getDynamicGlobalProperties(),
+    ]);
+    const [, block] = String(data.broca ?? '0,0').split(',');
+    const stored = fromMilli(data.broca);
+    const storedAtBlock = parseInt(block, 10) || 0;
+    const headBlock = props.head_block_number;
+    const max = fromMilli(data.spk_power) * BROCA_PER_SPK_POWER;
+
+    const perBlock = max / BROCA_REFILL_BLOCKS;
+    const current = Math.min(max, stored + Math.max(0, headBlock - storedAtBlock) * perBlock);
+    const blocksToFull = perBlock > 0 ? Math.ceil((max - current) / perBlock) : 0;
+    const now = Date.now();
+    const curve: BrocaPoint[] = [];
+    for (let i = 0; i <= CURVE_POINTS && blocksToFull > 0; i++) {
+      const blocks = (blocksToFull * i) / CURVE_POINTS;
+      curve.push({ at: new Date(now + blocks * BLOCK_MS).toISOString(), broca: Math.min(max, current + blocks * perBlock) });
+    }
+
+    const status: BrocaStatus = {
+      username: hiveUsername,
+      stored,
+      storedAtBlock,
+      headBlock,
+      current,
+      max,
+      fullAt: blocksToFull > 0 ? new Date(now + blocksToFull * BLOCK_MS).toISOString() : null,
+      low: max > 0 && current < max * LOW_FRACTION,
+      curve,
+      checkedAt: new Date(now).toISOString(),
+    };
+    this.last = status;
+    this.warnIfLow(status);
+    this.emit('status', status);
+    return status;
+  }
+
+  /**
+   * Whether an operation costing `cost` BROCA would go through now, and
+   * if not, when it would. Callers check this before broadcasting.
+   */
+  async checkCost(cost: number): Promise<BrocaCostCheck> {
+    const status = await this.getStatus();
+    if (status.current >= cost) return { cost, available: status.current, ok: true, availableAt: null };
+    const perBlock = status.max / BROCA_REFILL_BLOCKS;
+    const availableAt = cost <= status.max && perBlock > 0
+      ? new Date(Date.now() + Math.ceil((cost - status.current) / perBlock) * BLOCK_MS).toISOString()
+      : null;
+    return { cost, available: status.current, ok: false, availableAt };
+  }
+
+  private warnIfLow(status: BrocaStatus): void {
+    if (!status.low) {
+      this.warned = false;
+      return;
+    }
+    if (this.warned) return;
+    this.warned = true;
+    const full = status.fullAt ? ` It is full again ${new Date(status.fullAt).toLocaleString()}.` : '';
+    console.warn(`[SPK] BROCA low: ${status.current.toFixed(3)} of ${status.max}`);
+    showNotification('BROCA running low',
+      `Only ${status.current.toFixed(0)} of ${status.max.toFixed(0)} BROCA left; storage operations may fail until it regenerates.${full}`);
+  }
+}
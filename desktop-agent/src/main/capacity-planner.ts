@@ -0,0 +1,175 @@
+import axios from 'axios';
+import { KuboPool } from './kubo-pool';
+import { KuboManager } from './kubo';
+import { lookupContentSize } from './contract-policy';
+
+// Sub-DAGs this small that aren't here are counted whole rather than fetched to look inside
+const LEAF_GUESS_BYTES = 1024 * 1024;
+// Nodes fetched from the network per plan; past this, the rest is counted as new
+const MAX_FETCHED_NODES = 2000;
+const MAX_VISITED_NODES = 20000;
+const CONCURRENCY = 8;
+// Plans are reused across policy runs until the repo has had time to change
+const PLAN_TTL_MS = 60 * 60 * 1000;
+
+export interface CapacityPlan {
+  cid: string;
+  // The instance new content is pinned to, whose repo the DAG is compared with
+  instance: string;
+  // Cumulative size of the DAG; null when its root couldn't be reached
+  totalBytes: number | null;
+  // Blocks already in the repo, or repeated within the DAG
+  existingBytes: number;
+  // What pinning it would add to the repo
+  newBytes: number | null;
+  // Part of the DAG was sized from its parent links without being looked inside
+  estimated: boolean;
+  fetchedNodes: number;
+  plannedAt: string;
+}
+
+interface DagLink {
+  cid: string;
+  // Cumulative size of the linked sub-DAG, from the parent
+  size: number;
+}
+
+// Raw leaves (CIDv1, base32 'bafk…') are a single block with no links
+function isRawLeaf(cid: string): boolean {
+  return cid.startsWith('bafk');
+}
+
+/**
+ * Estimates how many bytes an offered contract's DAG would add to the
+ * repo, counting blocks that are already stored (shared media, an earlier
+ * upload of the same file) and blocks the DAG repeats. The walk starts
+ * at the root and only descends where it matters: everything local is
+ * read offline, while of the missing parts only interior nodes larger
+ * than a chunk are fetched, to see whether anything below them is here.
+ * Leaves are sized from their parents' links and never downloaded.
+ */
+export class CapacityPlanner {
+  private pool: KuboPool;
+  private plans = new Map<string, CapacityPlan>();
+
+  constructor(pool: KuboPool) {
+    this.pool = pool;
+  }
+
+  async plan(cid: string, knownSize: number | null = null): Promise<CapacityPlan> {
+    const kubo = await this.pool.selectForNewContent();
+    const key = `${kubo.getInstanceId()}:${cid}`;
+    const cached = this.plans.get(key);
+    if (cached && Date.now() - new Date(cached.plannedAt).getTime() < PLAN_TTL_MS) return cached;
+
+    const plan = await this.walk(kubo, cid, knownSize);
+    this.plans.set(key, plan);
+    for (const [planKey, { plannedAt }] of this.plans) {
+      if (Date.now() - new Date(plannedAt).getTime() >= PLAN_TTL_MS) this.plans.delete(planKey);
+    }
+    return plan;
+  }
+
+  private async walk(kubo: KuboManager, root: string, knownSize: number | null): Promise<CapacityPlan> {
+    const totalBytes = (await lookupContentSize(kubo, root)) ?? knownSize;
+    const plan: CapacityPlan = {
+      cid: root,
+      instance: kubo.getInstanceId(),
+      totalBytes,
+      existingBytes: 0,
+      newBytes: null,
+      estimated: false,
+      fetchedNodes: 0,
+      plannedAt: new Date().toISOString(),
+    };
+    if (totalBytes === null) return plan;
+
+    let newBytes = 0;
+    let visited = 0;
+    const seen = new Set<string>();
+    const queue: DagLink[] = [{ cid: root, size: totalBytes }];
+    let active = 0;
+
+    const visit = async ({ cid, size }: DagLink): Promise<void> => {
+      if (seen.has(cid)) {
+        plan.existingBytes += size;
+        return;
+      }
+      seen.add(cid);
+      visited++;
+      const local = await this.isLocal(kubo, cid);
+      if (isRawLeaf(cid)) {
+        if (local) plan.existingBytes += size;
+        else newBytes += size;
+        return;
+      }
+      if (!local && (size <= LEAF_GUESS_BYTES || plan.fetchedNodes >= MAX_FETCHED_NODES || visited >= MAX_VISITED_NODES)) {
+        newBytes += size;
+        plan.estimated = true;
+        return;
+      }
+      if (local && visited >= MAX_VISITED_NODES) {
+        // A local node is almost always under a recursive pin, so its sub-DAG is here too
+        plan.existingBytes += size;
+        plan.estimated = true;
+        return;
+      }
+
+      const links = await this.links(kubo, cid, local);
+      if (!local) plan.fetchedNodes++;
+      if (links === null) {
+        newBytes += size;
+        plan.estimated = true;
+        return;
+      }
+      const ownBytes = Math.max(0, size - links.reduce((sum, link) => sum + link.size, 0));
+      if (local) plan.existingBytes += ownBytes;
+      else newBytes += ownBytes;
+      queue.push(...links);
+    };
+
+    // A small worker pool over a shared queue, so wide directories don't walk one node at a time
+    await new Promise<void>((resolve) => {
+      const pump = () => {
+        while (active < CONCURRENCY && queue.length > 0) {
+          active++;
+          visit(queue.shift()!)
+            .catch(() => { plan.estimated = true; })
+            .finally(() => {
+              active--;
+              if (queue.length === 0 && active === 0) resolve();
+              else pump();
+            });
+        }
+      };
+      pump();
+    });
+
+    plan.newBytes = newBytes;
+    return plan;
+  }
+
+  private async isLocal(kubo: KuboManager, cid: string): Promise<boolean> {
+    try {
+      await axios.post(`${kubo.getApiUrl()}/api/v0/block/stat?arg=${cid}&offline=true`, null, { timeout: 5000 });
+      return true;
+    } catch {
+      return false;
+    }
+  }
+
+  // Links of a dag-pb node; other codecs are treated as having none
+  private async links(kubo: KuboManager, cid: string, offline: boolean): Promise<DagLink[] | null> {
+    try {
+      const response = await axios.post(
+        `${kubo.getApiUrl()}/api/v0/dag/get?arg=${cid}&output-codec=dag-json${offline ? '&offline=true' : ''}`,
+        null, { timeout: 30000 });
+      const links: any[] = Array.isArray(response.data?.Links) ? response.data.Links : [];
+      return links
+        .filter((link) => typeof link?.Hash?.['/'] === 'string')
+        .map((link) => ({ cid: link.Hash['/'], size: typeof link.Tsize === 'number' ? link.Tsize : 0 }));
+    } catch {
+      return null;
+    }
+  }
+}
@@ -0,0 +1,460 @@
+import { EventEmitter } from 'events';
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { Transform, TransformCallback, Readable } from 'stream';
+import { pipeline } from 'stream/promises';
+import axios from 'axios';
+import { KuboPool } from './kubo-pool';
+import { KuboManager } from './kubo';
+import { PinIndex, MAX_LABELS } from './pin-index';
+import { ContractPinner } from './contracts';
+import { ReplicationTier } from './replication-tiers';
+import { EventBus } from './event-bus';
+import { importCar } from './uploads';
+
+export const CAR_IMPORT_LABEL = 'car-import';
+const MANIFEST_FILE = 'manifest.json';
+// 'version: 2' as a CBOR map, prefixed by its length: how a CARv2 file starts
+const CARV2_PRAGMA = Buffer.from('0aa16776657273696f6e02', 'hex');
+const CARV2_HEADER_BYTES = 40;
+export const SHA2_256 = 0x12;
+export const IDENTITY = 0x00;
+
+// Written next to the CAR files of an export, so an import knows what it should find
+export interface CarManifest {
+  version: 1;
+  contractId: string;
+  account: string | null;
+  expiresAt: string | null;
+  tier: ReplicationTier;
+  exportedAt: string;
+  files: Array<{ cid: string; file: string; bytes: number; sha256: string }>;
+}
+
+export interface CarExportResult {
+  contractId: string;
+  dir: string;
+  files: CarManifest['files'];
+  // CIDs of the contract that aren't pinned here, so weren't exported
+  skipped: string[];
+}
+
+export interface CarFileCheck {
+  path: string;
+  roots: string[];
+  blocks: number;
+  bytes: number;
+  // Blocks hashed with something other than SHA2-256 or identity, which Kubo checks on use instead
+  uncheckedBlocks: number;
+  sha256: string | null;
+  error: string | null;
+}
+
+export interface CarImportRequest {
+  // .car files, or folders of them; a folder with a manifest.json is read by it
+  paths: string[];
+  // Registers the roots under this contract and only accepts roots it holds
+  contractId?: string;
+  account?: string;
+}
+
+export interface CarImportResult {
+  contractId: string | null;
+  files: CarFileCheck[];
+  // Roots that verified and are now pinned
+  imported: string[];
+  // Roots the contract or manifest expects that no file had
+  missingRoots: string[];
+  // Roots no file was expected to carry; unpinned again
+  unexpectedRoots: string[];
+  registered: boolean;
+}
+
+export interface CarImportProgress {
+  file: string;
+  done: number;
+  total: number;
+  bytes: number;
+}
+
+// Null until enough bytes have arrived
+function readVarint(buffer: Buffer, offset: number): [number, number] | null {
+  let value = 0;
+  let shift = 0;
+  let position = offset;
+  while (position < buffer.length) {
+    const byte = buffer[position++];
+    value += (byte & 0x7f) * 2 ** shift;
+    if ((byte & 0x80) === 0) return [value, position];
+    shift += 7;
+    if (shift > 63) throw new Error('Malformed varint in CAR file');
+  }
+  return null;
+}
+
+// The multihash of a CID, given as bytes at the start of a CAR section
+export function parseSectionCid(section: Buffer): { cidLength: number; code: number; digest: Buffer } {
+  // CIDv0 is a bare SHA2-256 multihash
+  if (section[0] === SHA2_256 && section[1] === 0x20) {
+    return { cidLength: 34, code: SHA2_256, digest: section.subarray(2, 34) };
+  }
+  const fields: number[] = [];
+  let position = 0;
+  for (let i = 0; i < 4; i++) {
+    const read = readVarint(section, position);
+    if (!read) throw new Error('Truncated CID in CAR file');
+    fields.push(read[0]);
+    position = read[1];
+  }
+  const [version, , code, digestLength] = fields;
+  if (version !== 1) throw new Error(`Unsupported CID version ${version} in CAR file`);
+  if (position + digestLength > section.length) throw new Error('Truncated CID in CAR file');
+  return { cidLength: position + digestLength, code, digest: section.subarray(position, position + digestLength) };
+}
+
+/**
+ * Passes a CAR file through unchanged while checking it: every block must
+ * hash to the CID it is stored under, and the file must not end mid-block.
+ * Reads CARv1 and the data section of CARv2. Kubo's own import trusts the
+ * CIDs it is given, so this is what catches a drive that flipped bits.
+ */
+class CarVerifier extends Transform {
+  blocks = 0;
+  uncheckedBlocks = 0;
+  private hash = crypto.createHash('sha256');
+  private chunks: Buffer[] = [];
+  private buffered = 0;
+  private position = 0;
+  private stage: 'start' | 'skip' | 'header' | 'sections' | 'trailer' = 'start';
+  private dataStart = 0;
+  private dataEnd: number | null = null;
+  private digest: string | null = null;
+
+  get sha256(): string | null {
+    return this.digest;
+  }
+
+  _transform(chunk: Buffer, _encoding: BufferEncoding, callback: TransformCallback): void {
+    this.hash.update(chunk);
+    this.chunks.push(chunk);
+    this.buffered += chunk.length;
+    try {
+      this.parse();
+      callback(null, chunk);
+    } catch (error: any) {
+      callback(error);
+    }
+  }
+
+  _flush(callback: TransformCallback): void {
+    this.digest = this.hash.digest('hex');
+    if (this.stage !== 'sections' && this.stage !== 'trailer') {
+      callback(new Error('CAR file ends before its header'));
+    } else if (this.stage === 'sections' && this.buffered > 0) {
+      callback(new Error('CAR file ends in the middle of a block'));
+    } else {
+      callback();
+    }
+  }
+
+  // Everything buffered as one buffer, or null while fewer than n bytes have arrived
+  private peek(n: number): Buffer | null {
+    if (this.buffered < n) return null;
+    if (this.chunks.length > 1) this.chunks = [Buffer.concat(this.chunks)];
+    return this.chunks[0] ?? Buffer.alloc(0);
+  }
+
+  private consume(n: number): void {
+    this.chunks = this.buffered > n ? [this.peek(this.buffered)!.subarray(n)] : [];
+    this.buffered -= n;
+    this.position += n;
+  }
+
+  private parse(): void {
+    for (;;) {
+      if (this.stage === 'trailer') {
+        // A CARv2 index follows the data; nothing in it needs checking
+        this.consume(this.buffered);
+        return;
+      }
+      if (this.stage === 'start') {
+        const head = this.peek(Math.min(this.buffered, CARV2_PRAGMA.length));
+        if (!head || head.length < CARV2_PRAGMA.length) return;
+        if (!head.subarray(0, CARV2_PRAGMA.length).equals(CARV2_PRAGMA)) {
+          this.stage = 'header';
+          continue;
+        }
+        const header = this.peek(CARV2_PRAGMA.length + CARV2_HEADER_BYTES);
+        if (!header) return;
+        // 16 bytes of characteristics, then the data offset and size
+        this.dataStart = Number(header.readBigUInt64LE(CARV2_PRAGMA.length + 16));
+        this.dataEnd = this.dataStart + Number(header.readBigUInt64LE(CARV2_PRAGMA.length + 24));
+        this.stage = 'skip';
+        continue;
+      }
+      if (this.stage === 'skip') {
+        const gap = this.dataStart - this.position;
+        if (gap < 0) throw new Error('CARv2 data offset points into its header');
+        if (this.buffered < gap) return;
+        this.consume(gap);
+        this.stage = 'header';
+        continue;
+      }
+      if (this.stage === 'sections' && this.dataEnd !== null && this.position >= this.dataEnd) {
+        this.stage = 'trailer';
+        continue;
+      }
+
+      if (this.buffered === 0) return;
+      // A varint is at most 10 bytes; joining chunks on every arrival would go quadratic on large blocks
+      const head = this.chunks[0].length >= 10 ? this.chunks[0] : this.peek(this.buffered)!;
+      const read = readVarint(head, 0);
+      if (!read) return;
+      const [length, start] = read;
+      const section = this.peek(start + length);
+      if (!section) return;
+      if (this.stage === 'header') {
+        this.stage = 'sections';
+      } else {
+        this.verifySection(section.subarray(start, start + length));
+      }
+      this.consume(start + length);
+    }
+  }
+
+  private verifySection(section: Buffer): void {
+    const { cidLength, code, digest } = parseSectionCid(section);
+    const block = section.subarray(cidLength);
+    this.blocks++;
+    if (code === SHA2_256) {
+      if (!crypto.createHash('sha256').update(block).digest().equals(digest)) {
+        throw new Error(`Block ${this.blocks} does not match its CID; the file is corrupt`);
+      }
+    } else if (code === IDENTITY) {
+      if (!block.equals(digest)) throw new Error(`Block ${this.blocks} does not match its identity CID`);
+    } else {
+      this.uncheckedBlocks++;
+    }
+  }
+}
+
+function safeFileName(name: string): string {
+  return name.replace(/[^a-zA-Z0-9_.-]/g, '_');
+}
+
+/**
+ * Moves contract content in and out of the repo as CAR archives, for
+ * seeding a node from a shipped drive rather than over the network. An
+ * export writes one CAR per CID of a contract, with a manifest of roots
+ * and file checksums. An import streams each CAR through a verifier into
+ * `dag import`, checks its roots against what the manifest or contract
+ * says it should hold, and registers the verified roots under the
+ * contract. Anything that doesn't verify is unpinned again.
+ */
+export class CarArchiver extends EventEmitter {
+  private pool: KuboPool;
+  private contracts: ContractPinner;
+  private pins: PinIndex;
+  private events: EventBus;
+  private isAcceptingContracts: () => boolean;
+  private busy = false;
+
+  constructor(pool: KuboPool, contracts: ContractPinner, pins: PinIndex, events: EventBus, isAcceptingContracts: () => boolean) {
+    super();
+    this.pool = pool;
+    this.contracts = contracts;
+    this.pins = pins;
+    this.events = events;
+    this.isAcceptingContracts = isAcceptingContracts;
+  }
+
+  // A CAR of one CID, from the instance holding it
+  async exportStream(cid: string): Promise<Readable> {
+    const holder = await this.holderOf(cid);
+    const response = await axios.post(`${holder.getApiUrl()}/api/v0/dag/export?arg=${cid}`, null, {
+      responseType: 'stream',
+      timeout: 0,
+    });
+    return response.data;
+  }
+
+  async exportContract(contractId: string, dir: string): Promise<CarExportResult> {
+    const record = this.contracts.get(contractId);
+    if (!record) throw new Error(`Unknown contract ${contractId}`);
+    const target = path.join(dir, safeFileName(contractId));
+    fs.mkdirSync(target, { recursive: true });
+
+    const result: CarExportResult = { contractId, dir: target, files: [], skipped: [] };
+    for (const [cid, entry] of Object.entries(record.cids)) {
+      if (entry.status !== 'pinned') {
+        result.skipped.push(cid);
+        continue;
+      }
+      const file = `${safeFileName(cid)}.car`;
+      const hash = crypto.createHash('sha256');
+      let bytes = 0;
+      const source = await this.exportStream(cid);
+      source.on('data', (chunk: Buffer) => {
+        hash.update(chunk);
+        bytes += chunk.length;
+      });
+      await pipeline(source, fs.createWriteStream(path.join(target, file)));
+      result.files.push({ cid, file, bytes, sha256: hash.digest('hex') });
+    }
+
+    const manifest: CarManifest = {
+      version: 1,
+      contractId,
+      account: record.account,
+      expiresAt: record.expiresAt,
+      tier: this.contracts.tierOf(record),
+      exportedAt: new Date().toISOString(),
+      files: result.files,
+    };
+    fs.writeFileSync(path.join(target, MANIFEST_FILE), JSON.stringify(manifest, null, 2));
+    console.log(`[Contracts] Exported ${result.files.length} CARs of ${contractId} to ${target}`);
+    return result;
+  }
+
+  async importCars(request: CarImportRequest): Promise<CarImportResult> {
+    if (this.busy) throw new Error('A CAR import is already running');
+    if (!Array.isArray(request.paths) || request.paths.length === 0) throw new Error('Choose CAR files or a folder of them');
+    this.busy = true;
+    try {
+      const { files, manifest, checksums } = this.collect(request.paths);
+      if (files.length === 0) throw new Error('No .car files found');
+      const contractId = request.contractId || manifest?.contractId || null;
+
+      // What the files may carry; with nothing to go by, any root is taken
+      const expected = new Set(manifest?.files.map((file) => file.cid) ?? []);
+      if (contractId) {
+        for (const cid of await this.contracts.expectedCids(contractId)) expected.add(cid);
+      }
+
+      const result: CarImportResult = {
+        contractId,
+        files: [],
+        imported: [],
+        missingRoots: [],
+        unexpectedRoots: [],
+        registered: false,
+      };
+      const target = await this.pool.selectForNewContent();
+      let bytes = 0;
+      for (let i = 0; i < files.length; i++) {
+        this.emit('progress', { file: files[i], done: i, total: files.length, bytes } as CarImportProgress);
+        if (!this.isAcceptingContracts()) {
+          result.files.push({ path: files[i], roots: [], blocks: 0, bytes: 0, uncheckedBlocks: 0, sha256: null, error: 'Storage quota reached' });
+          continue;
+        }
+        const check = await this.importFile(target, files[i], checksums.get(files[i]) ?? null);
+        bytes += check.bytes;
+        for (const root of check.roots) {
+          if (check.error) continue;
+          if (expected.size > 0 && !expected.has(root)) {
+            result.unexpectedRoots.push(root);
+            await this.unpin(target, root);
+          } else if (!result.imported.includes(root)) {
+            result.imported.push(root);
+          }
+        }
+        result.files.push(check);
+      }
+      this.emit('progress', { file: '', done: files.length, total: files.length, bytes } as CarImportProgress);
+      result.missingRoots = [...expected].filter((cid) => !result.imported.includes(cid));
+
+      for (const cid of result.imported) {
+        const labels = this.pins.get(cid)?.labels ?? [];
+        this.pins.set(cid, { labels: [...labels, CAR_IMPORT_LABEL].slice(0, MAX_LABELS) });
+        this.events.publish('pins', 'pinned', { cid, instance: target.getInstanceId() });
+      }
+      if (contractId && result.imported.length > 0) {
+        // The blocks are in the repo now, so these pins complete at once
+        await this.contracts.pin({
+          contractId,
+          cids: result.imported,
+          account: request.account || manifest?.account || undefined,
+          expiresAt: manifest?.expiresAt ?? undefined,
+          tier: manifest?.tier,
+        });
+        result.registered = true;
+      }
+      if (result.imported.length > 0) {
+        target.reprovide(result.imported).catch((error: any) => {
+          console.error('[Contracts] Failed to announce imported CAR roots:', error.message);
+        });
+      }
+
+      const failed = result.files.filter((file) => file.error).length;
+      console.log(`[Contracts] Imported ${result.imported.length} roots from ${files.length} CARs`
+        + `${contractId ? ` for ${contractId}` : ''}: ${failed} files failed, ${result.missingRoots.length} roots missing,`
+        + ` ${result.unexpectedRoots.length} unexpected`);
+      this.events.publish('pins', 'car-imported', result);
+      return result;
+    } finally {
+      this.busy = false;
+    }
+  }
+
+  private async importFile(target: KuboManager, file: string, expectedSha256: string | null): Promise<CarFileCheck> {
+    const verifier = new CarVerifier();
+    const check: CarFileCheck = { path: file, roots: [], blocks: 0, bytes: 0, uncheckedBlocks: 0, sha256: null, error: null };
+    try {
+      const source = fs.createReadStream(file);
+      source.on('error', (error) => verifier.destroy(error));
+      const { roots, bytes } = await importCar(target.getApiUrl(), source.pipe(verifier));
+      check.roots = roots;
+      check.bytes = bytes;
+    } catch (error: any) {
+      check.error = error.message;
+    }
+    check.blocks = verifier.blocks;
+    check.uncheckedBlocks = verifier.uncheckedBlocks;
+    check.sha256 = verifier.sha256;
+    if (!check.error && expectedSha256 && check.sha256 !== expectedSha256) {
+      check.error = 'Checksum differs from the manifest';
+    }
+    if (check.error) {
+      console.error(`[Contracts] CAR ${file} failed verification:`, check.error);
+      for (const root of check.roots) await this.unpin(target, root);
+    }
+    return check;
+  }
+
+  // Folders are expanded to their .car files; the first manifest found is used
+  private collect(paths: string[]): { files: string[]; manifest: CarManifest | null; checksums: Map<string, string> } {
+    const files: string[] = [];
+    const checksums = new Map<string, string>();
+    let manifest: CarManifest | null = null;
+    for (const entry of paths) {
+      const stat = fs.statSync(entry);
+      if (!stat.isDirectory()) {
+        files.push(path.resolve(entry));
+        continue;
+      }
+      const manifestPath = path.join(entry, MANIFEST_FILE);
+      if (!manifest && fs.existsSync(manifestPath)) {
+        manifest = JSON.parse(fs.readFileSync(manifestPath, 'utf-8')) as CarManifest;
+        for (const file of manifest.files) {
+          const filePath = path.resolve(entry, file.file);
+          files.push(filePath);
+          checksums.set(filePath, file.sha256);
+        }
+      } else {
+        files.push(...fs.readdirSync(entry).filter((name) => name.endsWith('.car')).map((name) => path.resolve(entry, name)));
+      }
+    }
+    return { files: Array.from(new Set(files)), manifest, checksums };
+  }
+
+  // Unless something else still holds it
+  private async unpin(target: KuboManager, cid: string): Promise<void> {
+    if (this.contracts.contractsFor(cid).length > 0 || this.pins.get(cid)) return;
+    await axios.post(`${target.getApiUrl()}/api/v0/pin/rm?arg=${cid}`).catch(() => undefined);
+  }
+
+  private async holderOf(cid: string): Promise<KuboManager> {
+    return (await this.pool.findHolder(cid).catch(() => null)) || this.pool.getPrimary();
+  }
+}
@@ -0,0 +1,285 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { EventEmitter } from 'events';
+import { Operation } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+import { HiveAccount } from './hive';
+import { TransactionQueue } from './tx-queue';
+import { HoneycombClient } from './honeycomb';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 6 * 60 * 60 * 1000;
+// Honeycomb picks a broadcast up a few blocks after Hive includes it
+const LISTING_GRACE_MS = 10 * 60 * 1000;
+
+export interface ChainRegistrationSettings {
+  // Broadcast again when the service drops off the SPK chain's listing
+  autoRenew: boolean;
+  // What validators call; defaults to the public address and API port
+  endpoint: string;
+  domain: string;
+  // Honeycomb op name; SPK testnet ops carry the spkccT_ prefix
+  customJsonId: string;
+}
+
+export const DEFAULT_CHAIN_REGISTRATION_SETTINGS: ChainRegistrationSettings = {
+  autoRenew: true,
+  endpoint: '',
+  domain: '',
+  customJsonId: 'spkccT_register_service',
+};
+
+export function validateChainRegistrationSettings(settings: Partial<ChainRegistrationSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.autoRenew !== undefined && typeof settings.autoRenew !== 'boolean') {
+    errors.push('autoRenew must be a boolean');
+  }
+  if (settings.endpoint !== undefined && settings.endpoint !== '' && !/^https?:\/\/[^\s/]+(\/\S*)?$/.test(settings.endpoint)) {
+    errors.push('endpoint must be an http:// or https:// URL');
+  }
+  if (settings.domain !== undefined && settings.domain !== ''
+    && !/^(?=.{1,253}$)([a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z]{2,}$/i.test(settings.domain)) {
+    errors.push('domain must be a host name such as node.example.com');
+  }
+  if (settings.customJsonId !== undefined && !/^[a-zA-Z0-9_.-]{1,32}$/.test(settings.customJsonId)) {
+    errors.push('customJsonId must be 1-32 letters, digits, dots, dashes or underscores');
+  }
+  return errors;
+}
+
+export type ChainRegistrationStage = 'broadcasting' | 'confirming' | 'listed' | 'failed';
+
+export interface ChainRegistrationState {
+  peerId: string | null;
+  endpoint: string | null;
+  domain: string | null;
+  // Reward recipient registered, when not the node's own account
+  payoutAccount: string | null;
+  txId: string | null;
+  blockNum: number | null;
+  broadcastAt: string | null;
+  includedAt: string | null;
+  // When the SPK chain last listed the service with this peer ID
+  listedAt: string | null;
+  lastCheckedAt: string | null;
+  lastError: string | null;
+}
+
+export interface ChainRegistrationPreview {
+  operation: Operation;
+  // Why broadcasting this would not work; empty when it's ready to go
+  issues: string[];
+}
+
+export interface ChainRegistrationOperations {
+  peerId(): Promise<string | null>;
+  // http://<public ip>:<api port>, or null before connectivity is known
+  suggestedEndpoint(): string | null;
+}
+
+const EMPTY_STATE: ChainRegistrationState = {
+  peerId: null,
+  endpoint: null,
+  domain: null,
+  payoutAccount: null,
+  txId: null,
+  blockNum: null,
+  broadcastAt: null,
+  includedAt: null,
+  listedAt: null,
+  lastCheckedAt: null,
+  lastError: null,
+};
+
+/**
+ * Registers the storage node as a service on the SPK chain with a Hive
+ * custom_json: the peer ID validators challenge, the endpoint they call and
+ * an optional domain. A broadcast counts once Hive includes it; after that
+ * the chain's service listing is read every few hours, and a registration
+ * that lapsed or went stale (new peer ID) is broadcast again.
+ *
+ * Progress is emitted as `progress` with the stage and current state.
+ */
+export class ChainRegistration extends EventEmitter {
+  private filePath: string;
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private queue: TransactionQueue;
+  private honeycomb: HoneycombClient;
+  private ops: ChainRegistrationOperations;
+  private timer: NodeJS.Timeout | null = null;
+  private busy = false;
+
+  constructor(dataDir: string, config: ConfigStore, account: HiveAccount, queue: TransactionQueue,
+    honeycomb: HoneycombClient, ops: ChainRegistrationOperations) {
+    super();
+    this.filePath = path.join(dataDir, 'chain-registration.json');
+    this.config = config;
+    this.account = account;
+    this.queue = queue;
+    this.honeycomb = honeycomb;
+    this.ops = ops;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.verify().catch((error) => console.error('[Registration] On-chain check failed:', error.message));
+    }, CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getState(): ChainRegistrationState {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return { ...EMPTY_STATE, ...JSON.parse(fs.readFileSync(this.filePath, 'utf-8')) };
+      }
+    } catch (error) {
+      console.error('[Registration] Failed to read on-chain registration:', error);
+    }
+    return { ...EMPTY_STATE };
+  }
+
+  // The wizard's review step: the exact operation, and anything in its way
+  async preview(overrides: Partial<ChainRegistrationSettings> = {}): Promise<ChainRegistrationPreview> {
+    const settings = { ...this.config.getConfig().chainRegistration, ...overrides };
+    const { hiveUsername } = this.config.getConfig();
+    const peerId = await this.ops.peerId();
+    const endpoint = this.endpointFor(settings);
+    const issues: string[] = [];
+    if (!hiveUsername) issues.push('Log in with a Hive account first');
+    if (!peerId) issues.push('The IPFS daemon is not running, so the peer ID is unknown');
+    if (!endpoint) issues.push('No public address is known yet; enter the endpoint validators should use');
+    if (this.config.getConfig().hiveSigning.method === 'keys' && !this.account.getKey('active')) {
+      issues.push('Registering needs the active key; store it at login, or sign with Hive Keychain or HAS');
+    }
+    issues.push(...validateChainRegistrationSettings(settings));
+
+    const json: Record<string, string> = { type: 'IPFS', id: peerId || '', api: endpoint || '' };
+    if (settings.domain) json.domain = settings.domain;
+    const { payoutAccount } = this.account.whoami();
+    if (payoutAccount) json.payout = payoutAccount;
+    return {
+      operation: ['custom_json', {
+        required_auths: hiveUsername ? [hiveUsername] : [],
+        required_posting_auths: [],
+        id: settings.customJsonId,
+        json: JSON.stringify(json),
+      }],
+      issues,
+    };
+  }
+
+  /**
+   * Broadcasts the registration and waits for Hive to include it. The
+   * settings used are saved, so renewals repeat what the user confirmed.
+   */
+  async register(overrides: Partial<ChainRegistrationSettings> = {}): Promise<ChainRegistrationState> {
+    if (this.busy) throw new Error('A registration is already in progress');
+    this.busy = true;
+    try {
+      const { operation, issues } = await this.preview(overrides);
+      if (issues.length > 0) throw new Error(issues.join('; '));
+      this.config.setConfig({ chainRegistration: { ...this.config.getConfig().chainRegistration, ...overrides } });
+      return await this.broadcast(operation);
+    } finally {
+      this.busy = false;
+    }
+  }
+
+  /**
+   * Reads the SPK chain's listing for the service. When it's missing past
+   * the grace period, or under another peer ID, or the payout account was
+   * changed since, and auto-renew is on, the registration is broadcast again.
+   */
+  async verify(): Promise<ChainRegistrationState> {
+    const state = this.getState();
+    const { hiveUsername, chainRegistration } = this.config.getConfig();
+    // Nothing to keep up until the user has registered once through the wizard
+    if (!state.broadcastAt || !hiveUsername || this.busy) return state;
+
+    const peerId = await this.ops.peerId();
+    if (!peerId) return state;
+    const listed = await this.isListed(hiveUsername, peerId);
+    const now = new Date().toISOString();
+    const payoutChanged = (state.payoutAccount ?? null) !== this.account.whoami().payoutAccount;
+    if (listed && !payoutChanged) {
+      return this.save({ ...state, listedAt: now, lastCheckedAt: now, lastError: null });
+    }
+
+    const recent = state.peerId === peerId && !payoutChanged
+      && Date.now() - new Date(state.broadcastAt).getTime() < LISTING_GRACE_MS;
+    const saved = this.save({ ...state, lastCheckedAt: now });
+    if (recent) return saved;
+    const reason = state.peerId !== peerId ? 'the peer ID changed'
+      : payoutChanged ? 'the payout account changed' : 'it is no longer listed on the SPK chain';
+    console.warn(`[Registration] On-chain registration lapsed: ${reason}`);
+    if (!chainRegistration.autoRenew) {
+      showNotification('Node registration lapsed', `Your storage node's on-chain registration is out of date: ${reason}. Register again from the dashboard.`);
+      return saved;
+    }
+    showNotification('Renewing node registration', `Broadcasting the registration again because ${reason}.`);
+    return this.register().catch((error) => {
+      console.error('[Registration] Renewal failed:', error.message);
+      return this.getState();
+    });
+  }
+
+  private async broadcast(operation: Operation): Promise<ChainRegistrationState> {
+    const json = JSON.parse((operation[1] as { json: string }).json);
+    let state: ChainRegistrationState = {
+      ...this.getState(),
+      peerId: json.id,
+      endpoint: json.api,
+      domain: json.domain ?? null,
+      payoutAccount: json.payout ?? null,
+      txId: null,
+      blockNum: null,
+      includedAt: null,
+      lastError: null,
+    };
+    this.emit('progress', { stage: 'broadcasting', state });
+    try {
+      // Retries re-sign, so the ID can change between broadcasts
+      const { txId, blockNum } = await this.queue.submit({
+        source: 'registration',
+        description: 'Register storage node on the SPK chain',
+        operations: [operation],
+        role: 'active',
+      }, (broadcastId) => {
+        state = this.save({ ...state, txId: broadcastId, broadcastAt: new Date().toISOString() });
+        this.emit('progress', { stage: 'confirming', state });
+      });
+      state = this.save({ ...state, txId, blockNum, includedAt: new Date().toISOString() });
+      console.log(`[Registration] Registered on-chain in block ${blockNum ?? 'unknown'} (${txId})`);
+      this.emit('progress', { stage: 'listed', state });
+      return state;
+    } catch (error: any) {
+      state = this.save({ ...state, lastError: error.message });
+      console.error('[Registration] On-chain registration failed:', error.message);
+      this.emit('progress', { stage: 'failed', state });
+      throw error;
+    }
+  }
+
+  private async isListed(username: string, peerId: string): Promise<boolean> {
+    // Service listings are keyed by account and service ID; both must match
+    const listing = JSON.stringify(await this.honeycomb.getServices('IPFS'));
+    return listing.includes(`"${username}"`) && listing.includes(`"${peerId}"`);
+  }
+
+  private endpointFor(settings: ChainRegistrationSettings): string | null {
+    if (settings.endpoint) return settings.endpoint.replace(/\/$/, '');
+    if (settings.domain) return `https://${settings.domain}`;
+    return this.ops.suggestedEndpoint();
+  }
+
+  private save(state: ChainRegistrationState): ChainRegistrationState {
+    fs.writeFileSync(this.filePath, JSON.stringify(state, null, 2));
+    return state;
+  }
+}
@@ -0,0 +1,144 @@
+import { EventEmitter } from 'events';
+
+// From receipt to sent: finding the holding instance, reading the blocks
+// from Kubo (disk), hashing them, and writing the answer to the validator
+export const CHALLENGE_STAGES = ['lookup', 'fetch', 'hash', 'submit'] as const;
+export type ChallengeStage = typeof CHALLENGE_STAGES[number];
+
+export type StageTimings = Record<ChallengeStage, number>;
+
+export interface LatencyBudget {
+  // End to end; an answer past this is failed
+  totalMs: number;
+  stages: StageTimings;
+}
+
+export const DEFAULT_LATENCY_BUDGET: LatencyBudget = {
+  totalMs: 2000,
+  stages: { lookup: 200, fetch: 1000, hash: 100, submit: 300 },
+};
+
+export function validateLatencyBudget(budget: Partial<LatencyBudget>): string[] {
+  const errors: string[] = [];
+  if (budget.totalMs !== undefined && (!Number.isInteger(budget.totalMs) || budget.totalMs < 100 || budget.totalMs > 60000)) {
+    errors.push('totalMs must be between 100 and 60000');
+  }
+  if (budget.stages !== undefined) {
+    for (const stage of CHALLENGE_STAGES) {
+      const value = budget.stages[stage];
+      if (value !== undefined && (!Number.isInteger(value) || value < 1)) {
+        errors.push(`stages.${stage} must be a positive number of milliseconds`);
+      }
+    }
+  }
+  return errors;
+}
+
+export interface ChallengeTiming {
+  at: string;
+  cid: string;
+  validator: string;
+  passed: boolean;
+  stages: StageTimings;
+  totalMs: number;
+  // Stages that went over their share of the budget
+  overBudget: ChallengeStage[];
+}
+
+export interface StageSummary {
+  p50: number;
+  p95: number;
+  max: number;
+  budgetMs: number;
+  overBudget: number;
+}
+
+export interface ChallengeDiagnosticsReport {
+  budget: LatencyBudget;
+  samples: number;
+  stages: Record<ChallengeStage, StageSummary>;
+  total: StageSummary;
+  recent: ChallengeTiming[];
+  // Plain-language hints drawn from the slow stages
+  warnings: string[];
+}
+
+const MAX_SAMPLES = 200;
+const RECENT_SHOWN = 20;
+
+const STAGE_HINTS: Record<ChallengeStage, string> = {
+  lookup: 'Finding which repo holds the CID is slow; with several repos, each is asked in turn',
+  fetch: 'Reading blocks from the repo is slow; a spinning disk or a busy Kubo is the usual cause',
+  hash: 'Hashing is slow; the machine may be under heavy CPU load',
+  submit: 'Sending answers to the validator is slow; check the upload bandwidth limit and the network',
+};
+
+export function percentile(sorted: number[], fraction: number): number {
+  if (sorted.length === 0) return 0;
+  return sorted[Math.min(sorted.length - 1, Math.floor(sorted.length * fraction))];
+}
+
+function summarize(values: number[], budgetMs: number): StageSummary {
+  const sorted = [...values].sort((a, b) => a - b);
+  return {
+    p50: percentile(sorted, 0.5),
+    p95: percentile(sorted, 0.95),
+    max: sorted[sorted.length - 1] ?? 0,
+    budgetMs,
+    overBudget: values.filter((value) => value > budgetMs).length,
+  };
+}
+
+/**
+ * Per-stage timings of recent challenge answers, checked against the
+ * latency budget. Emits `slow-stage` whenever a stage goes over its
+ * share, so slow disks and links show up before challenges start failing.
+ */
+export class ChallengeDiagnostics extends EventEmitter {
+  private getBudget: () => LatencyBudget;
+  private samples: ChallengeTiming[] = [];
+
+  constructor(getBudget: () => LatencyBudget) {
+    super();
+    this.getBudget = getBudget;
+  }
+
+  record(sample: Omit<ChallengeTiming, 'overBudget' | 'at'>): ChallengeTiming {
+    const budget = this.getBudget();
+    const timing: ChallengeTiming = {
+      ...sample,
+      at: new Date().toISOString(),
+      overBudget: CHALLENGE_STAGES.filter((stage) => sample.stages[stage] > budget.stages[stage]),
+    };
+    this.samples.push(timing);
+    if (this.samples.length > MAX_SAMPLES) this.samples.shift();
+
+    for (const stage of timing.overBudget) {
+      console.warn(`[PoA] ${stage} took ${timing.stages[stage]}ms for ${timing.cid} (budget ${budget.stages[stage]}ms)`);
+      this.emit('slow-stage', { stage, ms: timing.stages[stage], budgetMs: budget.stages[stage], cid: timing.cid });
+    }
+    return timing;
+  }
+
+  getReport(): ChallengeDiagnosticsReport {
+    const budget = this.getBudget();
+    const stages = Object.fromEntries(CHALLENGE_STAGES.map((stage) => [
+      stage,
+      summarize(this.samples.map((sample) => sample.stages[stage]), budget.stages[stage]),
+    ])) as Record<ChallengeStage, StageSummary>;
+
+    // A stage is worth a warning once it's over budget in a tenth of answers
+    const warnings = CHALLENGE_STAGES
+      .filter((stage) => this.samples.length > 0 && stages[stage].overBudget / this.samples.length >= 0.1)
+      .map((stage) => `${STAGE_HINTS[stage]} (p95 ${stages[stage].p95}ms, budget ${budget.stages[stage]}ms)`);
+
+    return {
+      budget,
+      samples: this.samples.length,
+      stages,
+      total: summarize(this.samples.map((sample) => sample.totalMs), budget.totalMs),
+      recent: this.samples.slice(-RECENT_SHOWN).reverse(),
+      warnings,
+    };
+  }
+}
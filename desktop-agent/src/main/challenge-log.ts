@@ -0,0 +1,216 @@
+import * as path from 'path';
+import Database from 'better-sqlite3';
+import { ChallengeRecord } from './poa';
+import { DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE } from './query';
+
+export interface ChallengeLogEntry {
+  id: number;
+  at: string;
+  protocol: ChallengeRecord['protocol'];
+  validator: string | null;
+  cid: string;
+  passed: boolean;
+  latencyMs: number;
+  rewardHbd: number;
+  error: string | null;
+  contractIds: string[];
+  instance: string;
+}
+
+export interface ChallengeLogQuery {
+  // ISO timestamps or YYYY-MM-DD; `to` is exclusive
+  from?: string;
+  to?: string;
+  validator?: string;
+  cid?: string;
+  passed?: boolean;
+  protocol?: ChallengeRecord['protocol'];
+  limit?: number;
+  // nextCursor of the previous page
+  cursor?: string;
+}
+
+export interface ChallengeLogPage {
+  entries: ChallengeLogEntry[];
+  total: number;
+  limit: number;
+  nextCursor: string | null;
+}
+
+export interface ChallengeDaySummary {
+  day: string;
+  challenges: number;
+  passed: number;
+  rewardHbd: number;
+  avgLatencyMs: number;
+}
+
+export function validateChallengeLogQuery(query: ChallengeLogQuery): string[] {
+  const errors: string[] = [];
+  for (const field of ['from', 'to'] as const) {
+    if (query[field] !== undefined && Number.isNaN(Date.parse(query[field]!))) {
+      errors.push(`${field} must be a date`);
+    }
+  }
+  if (query.passed !== undefined && typeof query.passed !== 'boolean') {
+    errors.push('passed must be a boolean');
+  }
+  if (query.protocol !== undefined && !['rest', 'spk'].includes(query.protocol)) {
+    errors.push('protocol must be rest or spk');
+  }
+  if (query.limit !== undefined && (!Number.isInteger(query.limit) || query.limit < 1 || query.limit > MAX_PAGE_SIZE)) {
+    errors.push(`limit must be between 1 and ${MAX_PAGE_SIZE}`);
+  }
+  if (query.cursor !== undefined && !/^\d+$/.test(query.cursor)) {
+    errors.push('cursor is not valid');
+  }
+  return errors;
+}
+
+function toEntry(row: any): ChallengeLogEntry {
+  return {
+    id: row.id,
+    at: row.at,
+    protocol: row.protocol,
+    validator: row.validator,
+    cid: row.cid,
+    passed: row.passed === 1,
+    latencyMs: row.latency_ms,
+    rewardHbd: row.reward_hbd,
+    error: row.error,
+    contractIds: JSON.parse(row.contract_ids),
+    instance: row.instance,
+  };
+}
+
+/**
+ * Every challenge received, in ~/.spk-ipfs/challenges.db. Unlike the
+ * earnings history it keeps the validator, latency and error, so a dip in
+ * payouts can be traced to a validator, a CID or a slow disk.
+ */
+export class ChallengeLog {
+  private db: Database.Database;
+
+  constructor(dataDir: string) {
+    this.db = new Database(path.join(dataDir, 'challenges.db'));
+    // Challenges are written while the dashboard reads
+    this.db.pragma('journal_mode = WAL');
+    this.db.exec(`
+      CREATE TABLE IF NOT EXISTS challenges (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        protocol TEXT NOT NULL,
+        validator TEXT,
+        cid TEXT NOT NULL,
+        passed INTEGER NOT NULL,
+        latency_ms INTEGER NOT NULL,
+        reward_hbd REAL NOT NULL,
+        error TEXT,
+        contract_ids TEXT NOT NULL,
+        instance TEXT NOT NULL
+      );
+      CREATE INDEX IF NOT EXISTS challenges_at ON challenges (at);
+      CREATE INDEX IF NOT EXISTS challenges_cid ON challenges (cid);
+      CREATE INDEX IF NOT EXISTS challenges_validator ON challenges (validator);
+    `);
+  }
+
+  record(record: ChallengeRecord, rewardHbd: number): void {
+    try {
+      this.db.prepare(`
+        INSERT INTO challenges (at, protocol, validator, cid, passed, latency_ms, reward_hbd, error, contract_ids, instance)
+        VALUES (@at, @protocol, @validator, @cid, @passed, @latencyMs, @rewardHbd, @error, @contractIds, @instance)
+      `).run({
+        at: record.at,
+        protocol: record.protocol,
+        validator: record.validatorId,
+        cid: record.cid,
+        passed: record.passed ? 1 : 0,
+        latencyMs: record.responseTime,
+        rewardHbd,
+        error: record.error,
+        contractIds: JSON.stringify(record.contractIds),
+        instance: record.instance,
+      });
+    } catch (error) {
+      console.error('[PoA] Failed to log challenge:', error);
+    }
+  }
+
+  /**
+   * Newest first. The cursor is the id of the last entry returned, so new
+   * challenges arriving between pages don't shift what comes next.
+   */
+  query(query: ChallengeLogQuery = {}): ChallengeLogPage {
+    const { where, params } = this.buildWhere(query);
+    const limit = query.limit ?? DEFAULT_PAGE_SIZE;
+    const pageParams: Record<string, unknown> = { ...params, limit: limit + 1 };
+    let pageWhere = where;
+    if (query.cursor) {
+      pageWhere = `${where ? `${where} AND` : 'WHERE'} id < @cursor`;
+      pageParams.cursor = Number(query.cursor);
+    }
+
+    // One extra row tells whether there's a next page
+    const rows = this.db.prepare(`SELECT * FROM challenges ${pageWhere} ORDER BY id DESC LIMIT @limit`).all(pageParams);
+    const { total } = this.db.prepare(`SELECT COUNT(*) AS total FROM challenges ${where}`).get(params) as { total: number };
+
+    const entries = rows.slice(0, limit).map(toEntry);
+    return {
+      entries,
+      total,
+      limit,
+      nextCursor: rows.length > limit ? String(entries[entries.length - 1].id) : null,
+    };
+  }
+
+  // Per UTC day, for spotting the day payouts dropped
+  summarizeByDay(query: ChallengeLogQuery = {}): ChallengeDaySummary[] {
+    const { where, params } = this.buildWhere(query);
+    const rows = this.db.prepare(`
+      SELECT substr(at, 1, 10) AS day, COUNT(*) AS challenges, SUM(passed) AS passed,
+        SUM(reward_hbd) AS rewardHbd, AVG(latency_ms) AS avgLatencyMs
+      FROM challenges ${where}
+      GROUP BY day ORDER BY day DESC
+    `).all(params) as ChallengeDaySummary[];
+    return rows.map((row) => ({ ...row, avgLatencyMs: Math.round(row.avgLatencyMs) }));
+  }
+
+  backup(destination: string): Promise<unknown> {
+    return this.db.backup(destination);
+  }
+
+  close(): void {
+    this.db.close();
+  }
+
+  private buildWhere(query: ChallengeLogQuery): { where: string; params: Record<string, unknown> } {
+    const clauses: string[] = [];
+    const params: Record<string, unknown> = {};
+    if (query.from) {
+      clauses.push('at >= @from');
+      params.from = new Date(query.from).toISOString();
+    }
+    if (query.to) {
+      clauses.push('at < @to');
+      params.to = new Date(query.to).toISOString();
+    }
+    if (query.validator) {
+      clauses.push('validator = @validator');
+      params.validator = query.validator;
+    }
+    if (query.cid) {
+      clauses.push('cid = @cid');
+      params.cid = query.cid;
+    }
+    if (query.passed !== undefined) {
+      clauses.push('passed = @passed');
+      params.passed = query.passed ? 1 : 0;
+    }
+    if (query.protocol) {
+      clauses.push('protocol = @protocol');
+      params.protocol = query.protocol;
+    }
+    return { where: clauses.length > 0 ? `WHERE ${clauses.join(' AND ')}` : '', params };
+  }
+}
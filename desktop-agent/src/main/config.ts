@@ -2,12 +2,129 @@ import Store from 'electron-store';
 import * as path from 'path';
 import * as os from 'os';
 import * as fs from 'fs';
+import { KuboSettings, DEFAULT_KUBO_SETTINGS, ConnectionMode, DEFAULT_CONNECTION_MODE } from './settings';
+import { BandwidthLimits, DEFAULT_BANDWIDTH_LIMITS } from './bandwidth';
+import { PeeringPeer } from './peering';
+import { PowerPolicy, DEFAULT_POWER_POLICY, PowerOverlayBackup } from './power';
+import { PrivateNetwork } from './private-network';
+import { ResourceLimits, DEFAULT_RESOURCE_LIMITS } from './resource-limits';
+import { CorsSettings, DEFAULT_CORS_SETTINGS } from './cors';
+import { TlsSettings, DEFAULT_TLS_SETTINGS } from './tls';
+import { DiscoverySettings, DEFAULT_DISCOVERY_SETTINGS } from './discovery';
+import { RemoteAccessSettings, DEFAULT_REMOTE_ACCESS } from './remote-access';
+import { GrpcSettings, DEFAULT_GRPC_SETTINGS } from './grpc';
+import { Webhook } from './webhooks';
+import { PoaSettings, DEFAULT_POA_SETTINGS } from './poa-responder';
+import { ValidatorSettings, DEFAULT_VALIDATOR_SETTINGS } from './validator';
+import { ContractPolicy, DEFAULT_CONTRACT_POLICY } from './contract-policy';
+import { ContractExpirySettings, DEFAULT_CONTRACT_EXPIRY_SETTINGS } from './contract-expiry';
+import { EarningsFiat, EarningsStore, EarningsToken, MilestoneSettings, DEFAULT_MILESTONE_SETTINGS } from './earnings';
+import { HiveSigningSettings, DEFAULT_HIVE_SIGNING_SETTINGS } from './hive-signing';
+import { ChainRegistrationSettings, DEFAULT_CHAIN_REGISTRATION_SETTINGS } from './chain-registration';
+import { RewardClaimSettings, DEFAULT_REWARD_CLAIM_SETTINGS } from './reward-claims';
+import { WalletSettings, DEFAULT_WALLET_SETTINGS } from './wallet';
+import { PriceFeedSettings, DEFAULT_PRICE_FEED_SETTINGS } from './price-feed';
+import { HiveNodeSettings, DEFAULT_HIVE_NODE_SETTINGS } from './hive-rpc';
+import { HoneycombSettings, DEFAULT_HONEYCOMB_SETTINGS } from './honeycomb';
+import { DelegationSettings, DEFAULT_DELEGATION_SETTINGS } from './delegations';
+import { ContractMarketSettings, DEFAULT_CONTRACT_MARKET_SETTINGS } from './contract-market';
+import { PinReconcileSettings, DEFAULT_PIN_RECONCILE_SETTINGS } from './pin-reconciler';
+import { ReplicationTierSettings, DEFAULT_REPLICATION_TIER_SETTINGS } from './replication-tiers';
+import { VaultSettings, DEFAULT_VAULT_SETTINGS } from './secrets';
+import { DiskHealthSettings, DEFAULT_DISK_HEALTH_SETTINGS } from './disk-health';
+import { ScrubSettings, DEFAULT_SCRUB_SETTINGS } from './scrubber';
+import { TrashSettings, DEFAULT_TRASH_SETTINGS } from './trash';
+import { DailySummarySettings, DEFAULT_DAILY_SUMMARY_SETTINGS } from './daily-summary';
+import { NetworkRankSettings, DEFAULT_NETWORK_RANK_SETTINGS } from './network-rank';
+import { AnomalySettings, DEFAULT_ANOMALY_SETTINGS } from './earnings-anomaly';
+import { AnalyticsSettings, DEFAULT_ANALYTICS_SETTINGS } from './analytics';
+import { BackupSettings, DEFAULT_BACKUP_SETTINGS, applyStagedRestore } from './backup';
+import { LinkedAccount } from './accounts';
 
 export interface AgentConfig {
+  // The active account; others the node stores for are in linkedAccounts
   hiveUsername: string | null;
+  linkedAccounts: LinkedAccount[];
+  // SPK API that storage contract IDs are resolved against
+  spkApiUrl: string;
+  // Honeycomb node serving SPK account balances
+  spkChainUrl: string;
   ipfsRepoPath: string;
   apiPort: number;
+  // Ports above apiPort tried in turn when it's taken
+  apiPortFallbacks: number;
   autoStart: boolean;
+  kuboVersion: string | null;
+  kubo: KuboSettings;
+  connectionMode: ConnectionMode;
+  watchdogIntervalSec: number;
+  gcIntervalHours: number;
+  pauseContractsWhenFull: boolean;
+  bandwidth: BandwidthLimits;
+  customPeers: PeeringPeer[];
+  // Resolved into Peering.Peers at daemon start; empty until the user adds some
+  peeringDnsaddrs: string[];
+  connectivityCheckUrl: string | null;
+  powerPolicy: PowerPolicy;
+  extraRepos: ExtraRepoConfig[];
+  privateNetwork: PrivateNetwork | null;
+  resourceLimits: ResourceLimits;
+  cors: CorsSettings;
+  tls: TlsSettings;
+  discovery: DiscoverySettings;
+  remoteAccess: RemoteAccessSettings;
+  grpc: GrpcSettings;
+  webhooks: Webhook[];
+  poa: PoaSettings;
+  validator: ValidatorSettings;
+  contractPolicy: ContractPolicy;
+  contractExpiry: ContractExpirySettings;
+  hiveSigning: HiveSigningSettings;
+  chainRegistration: ChainRegistrationSettings;
+  rewardClaims: RewardClaimSettings;
+  wallet: WalletSettings;
+  priceFeed: PriceFeedSettings;
+  hiveNodes: HiveNodeSettings;
+  honeycomb: HoneycombSettings;
+  delegations: DelegationSettings;
+  contractMarket: ContractMarketSettings;
+  pinReconcile: PinReconcileSettings;
+  replicationTiers: ReplicationTierSettings;
+  vault: VaultSettings;
+  diskHealth: DiskHealthSettings;
+  scrub: ScrubSettings;
+  trash: TrashSettings;
+  dailySummary: DailySummarySettings;
+  milestones: MilestoneSettings;
+  networkRank: NetworkRankSettings;
+  anomaly: AnomalySettings;
+  analytics: AnalyticsSettings;
+  backup: BackupSettings;
+}
+
+// Additional repo on another drive, run as its own daemon
+export interface ExtraRepoConfig {
+  id: string;
+  repoPath: string;
+  storageMaxGb: number;
+}
+
+export interface GcStatus {
+  lastRunAt: string | null;
+  reclaimedBytes: number;
+  removedBlocks: number;
+  durationMs: number;
+}
+
+// What the SPK network last accepted for this node
+export interface RegistrationState {
+  peerId: string | null;
+  hiveUsername: string | null;
+  // Registered reward recipient when not hiveUsername
+  payoutAccount?: string | null;
+  publicIps: string[];
+  registeredAt: string | null;
+  lastError: string | null;
 }
 
 export interface EarningsData {
@@ -16,12 +133,57 @@ export interface EarningsData {
   challengesFailed: number;
   consecutivePasses: number;
   lastChallengeTime: string | null;
+  lastPassedChallengeTime: string | null;
+  // Part of totalHbd whose entries aren't final yet
+  provisionalHbd: number;
+  // Earned per reward token; HBD is totalHbd
+  totals: Partial<Record<EarningsToken, number>>;
+}
+
+// The Hive block an earnings entry is booked against
+export interface EarningsAnchor {
+  block: number;
+  blockId: string;
+}
+
+// Provisional until the entry's block is irreversible
+export type EarningsStatus = 'provisional' | 'final';
+
+// One row of earnings.db per challenge answered
+export interface EarningsHistoryEntry {
+  at: string;
+  passed: boolean;
+  // amount when the reward was paid in HBD, else 0
+  hbdEarned: number;
+  token?: EarningsToken;
+  amount?: number;
+  cid: string | null;
+  // The account the CID was stored for; missing on entries from older versions
+  account?: string | null;
+  block?: number | null;
+  blockId?: string | null;
+  // Entries from older versions have none and count as final
+  status?: EarningsStatus;
+  // The salt of the challenge answered
+  reference?: string | null;
+  // Worth of the amount when booked; null without a current price
+  fiatValue?: number | null;
+  fiatCurrency?: string | null;
+}
+
+// Earnings split by the account each challenged CID was stored for
+export interface AccountEarnings {
+  totalHbd: number;
+  challengesPassed: number;
+  challengesFailed: number;
+  lastChallengeTime: string | null;
 }
 
 export class ConfigStore {
   private store: Store;
+  private dataDir: string;
   private configPath: string;
-  private earningsPath: string;
+  private earnings: EarningsStore;
 
   constructor() {
     this.store = new Store({
@@ -33,16 +195,73 @@ export class ConfigStore {
       fs.mkdirSync(spkDir, { recursive: true });
     }
 
+    this.dataDir = spkDir;
     this.configPath = path.join(spkDir, 'agent-config.json');
-    this.earningsPath = path.join(spkDir, 'earnings.json');
+    // A restore stages its files for here, before the databases are opened
+    applyStagedRestore(spkDir);
+    this.earnings = new EarningsStore(spkDir);
+  }
+
+  // ~/.spk-ipfs, where the agent keeps its own files beside the IPFS repo
+  getDataDir(): string {
+    return this.dataDir;
   }
 
   getConfig(): AgentConfig {
     return {
       hiveUsername: this.store.get('hiveUsername', null) as string | null,
+      linkedAccounts: this.store.get('linkedAccounts', []) as LinkedAccount[],
+      spkApiUrl: this.store.get('spkApiUrl', 'http://localhost:5000') as string,
+      spkChainUrl: this.store.get('spkChainUrl', 'https://spktest.dlux.io') as string,
       ipfsRepoPath: this.store.get('ipfsRepoPath', path.join(os.homedir(), '.spk-ipfs', 'repo')) as string,
       apiPort: this.store.get('apiPort', 5111) as number,
+      apiPortFallbacks: this.store.get('apiPortFallbacks', 9) as number,
       autoStart: this.store.get('autoStart', false) as boolean,
+      kuboVersion: this.store.get('kuboVersion', null) as string | null,
+      kubo: { ...DEFAULT_KUBO_SETTINGS, ...(this.store.get('kubo', {}) as Partial<KuboSettings>) },
+      connectionMode: this.store.get('connectionMode', DEFAULT_CONNECTION_MODE) as ConnectionMode,
+      watchdogIntervalSec: this.store.get('watchdogIntervalSec', 15) as number,
+      gcIntervalHours: this.store.get('gcIntervalHours', 24) as number,
+      pauseContractsWhenFull: this.store.get('pauseContractsWhenFull', false) as boolean,
+      bandwidth: { ...DEFAULT_BANDWIDTH_LIMITS, ...(this.store.get('bandwidth', {}) as Partial<BandwidthLimits>) },
+      customPeers: this.store.get('customPeers', []) as PeeringPeer[],
+      peeringDnsaddrs: this.store.get('peeringDnsaddrs', []) as string[],
+      connectivityCheckUrl: this.store.get('connectivityCheckUrl', null) as string | null,
+      powerPolicy: { ...DEFAULT_POWER_POLICY, ...(this.store.get('powerPolicy', {}) as Partial<PowerPolicy>) },
+      extraRepos: this.store.get('extraRepos', []) as ExtraRepoConfig[],
+      privateNetwork: this.store.get('privateNetwork', null) as PrivateNetwork | null,
+      resourceLimits: { ...DEFAULT_RESOURCE_LIMITS, ...(this.store.get('resourceLimits', {}) as Partial<ResourceLimits>) },
+      cors: { ...DEFAULT_CORS_SETTINGS, ...(this.store.get('cors', {}) as Partial<CorsSettings>) },
+      tls: { ...DEFAULT_TLS_SETTINGS, ...(this.store.get('tls', {}) as Partial<TlsSettings>) },
+      discovery: { ...DEFAULT_DISCOVERY_SETTINGS, ...(this.store.get('discovery', {}) as Partial<DiscoverySettings>) },
+      remoteAccess: { ...DEFAULT_REMOTE_ACCESS, ...(this.store.get('remoteAccess', {}) as Partial<RemoteAccessSettings>) },
+      grpc: { ...DEFAULT_GRPC_SETTINGS, ...(this.store.get('grpc', {}) as Partial<GrpcSettings>) },
+      webhooks: this.getWebhooks(),
+      poa: { ...DEFAULT_POA_SETTINGS, ...(this.store.get('poa', {}) as Partial<PoaSettings>) },
+      validator: { ...DEFAULT_VALIDATOR_SETTINGS, ...(this.store.get('validator', {}) as Partial<ValidatorSettings>) },
+      contractPolicy: { ...DEFAULT_CONTRACT_POLICY, ...(this.store.get('contractPolicy', {}) as Partial<ContractPolicy>) },
+      contractExpiry: { ...DEFAULT_CONTRACT_EXPIRY_SETTINGS, ...(this.store.get('contractExpiry', {}) as Partial<ContractExpirySettings>) },
+      hiveSigning: { ...DEFAULT_HIVE_SIGNING_SETTINGS, ...(this.store.get('hiveSigning', {}) as Partial<HiveSigningSettings>) },
+      chainRegistration: { ...DEFAULT_CHAIN_REGISTRATION_SETTINGS, ...(this.store.get('chainRegistration', {}) as Partial<ChainRegistrationSettings>) },
+      rewardClaims: { ...DEFAULT_REWARD_CLAIM_SETTINGS, ...(this.store.get('rewardClaims', {}) as Partial<RewardClaimSettings>) },
+      wallet: { ...DEFAULT_WALLET_SETTINGS, ...(this.store.get('wallet', {}) as Partial<WalletSettings>) },
+      priceFeed: { ...DEFAULT_PRICE_FEED_SETTINGS, ...(this.store.get('priceFeed', {}) as Partial<PriceFeedSettings>) },
+      hiveNodes: { ...DEFAULT_HIVE_NODE_SETTINGS, ...(this.store.get('hiveNodes', {}) as Partial<HiveNodeSettings>) },
+      honeycomb: { ...DEFAULT_HONEYCOMB_SETTINGS, ...(this.store.get('honeycomb', {}) as Partial<HoneycombSettings>) },
+      delegations: { ...DEFAULT_DELEGATION_SETTINGS, ...(this.store.get('delegations', {}) as Partial<DelegationSettings>) },
+      contractMarket: { ...DEFAULT_CONTRACT_MARKET_SETTINGS, ...(this.store.get('contractMarket', {}) as Partial<ContractMarketSettings>) },
+      pinReconcile: { ...DEFAULT_PIN_RECONCILE_SETTINGS, ...(this.store.get('pinReconcile', {}) as Partial<PinReconcileSettings>) },
+      replicationTiers: { ...DEFAULT_REPLICATION_TIER_SETTINGS, ...(this.store.get('replicationTiers', {}) as Partial<ReplicationTierSettings>) },
+      vault: { ...DEFAULT_VAULT_SETTINGS, ...(this.store.get('vault', {}) as Partial<VaultSettings>) },
+      diskHealth: { ...DEFAULT_DISK_HEALTH_SETTINGS, ...(this.store.get('diskHealth', {}) as Partial<DiskHealthSettings>) },
+      scrub: { ...DEFAULT_SCRUB_SETTINGS, ...(this.store.get('scrub', {}) as Partial<ScrubSettings>) },
+      trash: { ...DEFAULT_TRASH_SETTINGS, ...(this.store.get('trash', {}) as Partial<TrashSettings>) },
+      dailySummary: { ...DEFAULT_DAILY_SUMMARY_SETTINGS, ...(this.store.get('dailySummary', {}) as Partial<DailySummarySettings>) },
+      milestones: this.getMilestoneSettings(),
+      networkRank: { ...DEFAULT_NETWORK_RANK_SETTINGS, ...(this.store.get('networkRank', {}) as Partial<NetworkRankSettings>) },
+      anomaly: { ...DEFAULT_ANOMALY_SETTINGS, ...(this.store.get('anomaly', {}) as Partial<AnomalySettings>) },
+      analytics: { ...DEFAULT_ANALYTICS_SETTINGS, ...(this.store.get('analytics', {}) as Partial<AnalyticsSettings>) },
+      backup: { ...DEFAULT_BACKUP_SETTINGS, ...(this.store.get('backup', {}) as Partial<BackupSettings>) },
     };
   }
 
@@ -56,46 +275,143 @@ export class ConfigStore {
     fs.writeFileSync(this.configPath, JSON.stringify(fullConfig, null, 2));
   }
 
-  getEarnings(): EarningsData {
-    try {
-      if (fs.existsSync(this.earningsPath)) {
-        return JSON.parse(fs.readFileSync(this.earningsPath, 'utf-8'));
-      }
-    } catch (error) {
-      console.error('[Config] Failed to read earnings:', error);
+  // Profile last applied to each repo, so a changed setting is only applied once
+  getAppliedProfile(instanceId: string = 'primary'): string | null {
+    return this.store.get(this.appliedProfileKey(instanceId), null) as string | null;
+  }
+
+  setAppliedProfile(profile: string, instanceId: string = 'primary'): void {
+    this.store.set(this.appliedProfileKey(instanceId), profile);
+  }
+
+  private appliedProfileKey(instanceId: string): string {
+    return instanceId === 'primary' ? 'kuboAppliedProfile' : `kuboAppliedProfile_${instanceId}`;
+  }
+
+  // Kept across restarts so a crash while on battery still restores the user's values
+  getPowerOverlayBackup(instanceId: string = 'primary'): PowerOverlayBackup | null {
+    return this.store.get(`powerOverlayBackup_${instanceId}`, null) as PowerOverlayBackup | null;
+  }
+
+  setPowerOverlayBackup(backup: PowerOverlayBackup | null, instanceId: string = 'primary'): void {
+    if (backup === null) {
+      this.store.delete(`powerOverlayBackup_${instanceId}`);
+    } else {
+      this.store.set(`powerOverlayBackup_${instanceId}`, backup);
     }
+  }
 
+  // Thresholds are merged per field, so one left unset keeps its defaults
+  private getMilestoneSettings(): MilestoneSettings {
+    const stored = this.store.get('milestones', {}) as Partial<MilestoneSettings>;
     return {
-      totalHbd: 0,
-      challengesPassed: 0,
-      challengesFailed: 0,
-      consecutivePasses: 0,
-      lastChallengeTime: null,
+      ...DEFAULT_MILESTONE_SETTINGS,
+      ...stored,
+      thresholds: { ...DEFAULT_MILESTONE_SETTINGS.thresholds, ...stored.thresholds },
     };
   }
 
-  updateEarnings(update: Partial<EarningsData>): EarningsData {
-    const current = this.getEarnings();
-    const updated = { ...current, ...update };
-    fs.writeFileSync(this.earningsPath, JSON.stringify(updated, null, 2));
-    return updated;
+  private getWebhooks(): Webhook[] {
+    const stored = this.store.get('webhooks', []) as Array<Webhook & { secret?: string }>;
+    return stored.map(({ secret: _secret, ...webhook }) => webhook);
   }
 
-  recordChallenge(passed: boolean, hbdEarned: number): EarningsData {
-    const current = this.getEarnings();
-    
-    if (passed) {
-      current.challengesPassed++;
-      current.consecutivePasses++;
-      current.totalHbd += hbdEarned;
-    } else {
-      current.challengesFailed++;
-      current.consecutivePasses = 0;
-    }
-    
-    current.lastChallengeTime = new Date().toISOString();
-    
-    fs.writeFileSync(this.earningsPath, JSON.stringify(current, null, 2));
-    return current;
+  // Kept out of AgentConfig so it never appears in agent-config.json or /api/config
+  getApiToken(): string | null {
+    return this.store.get('apiToken', null) as string | null;
+  }
+
+  setApiToken(token: string): void {
+    this.store.set('apiToken', token);
+  }
+
+  // Signing secrets by webhook ID, sealed by the secret store and kept out of AgentConfig like the token
+  getWebhookSecrets(): Record<string, string> {
+    return this.store.get('webhookSecrets', {}) as Record<string, string>;
+  }
+
+  setWebhookSecrets(sealed: Record<string, string>): void {
+    this.store.set('webhookSecrets', sealed);
+  }
+
+  // Plaintext secrets webhooks were stored with before they were sealed
+  getLegacyWebhookSecrets(): Record<string, string> {
+    const stored = this.store.get('webhooks', []) as Array<Webhook & { secret?: string }>;
+    return Object.fromEntries(stored.filter((webhook) => webhook.secret).map((webhook) => [webhook.id, webhook.secret!]));
+  }
+
+  clearLegacyWebhookSecrets(): void {
+    this.setConfig({ webhooks: this.getWebhooks() });
+  }
+
+  // Sealed by the secret store; kept out of AgentConfig like the token, and out of backups with it
+  getBackupPassphrase(): string | null {
+    return this.store.get('backupPassphrase', null) as string | null;
+  }
+
+  setBackupPassphrase(sealed: string | null): void {
+    if (sealed === null) this.store.delete('backupPassphrase');
+    else this.store.set('backupPassphrase', sealed);
+  }
+
+  getGcStatus(): GcStatus {
+    return this.store.get('gcStatus', {
+      lastRunAt: null,
+      reclaimedBytes: 0,
+      removedBlocks: 0,
+      durationMs: 0,
+    }) as GcStatus;
+  }
+
+  setGcStatus(status: GcStatus): void {
+    this.store.set('gcStatus', status);
+  }
+
+  getRegistration(): RegistrationState {
+    return this.store.get('registration', {
+      peerId: null,
+      hiveUsername: null,
+      publicIps: [],
+      registeredAt: null,
+      lastError: null,
+    }) as RegistrationState;
+  }
+
+  setRegistration(state: RegistrationState): void {
+    this.store.set('registration', state);
+  }
+
+  getEarnings(): EarningsData {
+    return this.earnings.getEarnings();
+  }
+
+  getEarningsStore(): EarningsStore {
+    return this.earnings;
+  }
+
+  recordChallenge(passed: boolean, hbdEarned: number, cid: string | null = null, account: string | null = null,
+    anchor: EarningsAnchor | null = null, reference: string | null = null, fiat: EarningsFiat | null = null): EarningsData {
+    return this.earnings.record(passed, hbdEarned, cid, account, anchor, reference, fiat);
+  }
+
+  getAccountEarnings(): Record<string, AccountEarnings> {
+    return this.earnings.getAccountEarnings();
+  }
+
+  // Oldest first
+  getEarningsHistory(): EarningsHistoryEntry[] {
+    return this.earnings.getHistory();
+  }
+
+  getProvisionalEarnings(): EarningsHistoryEntry[] {
+    return this.earnings.getProvisional();
+  }
+
+  /**
+   * Rewrites every provisional entry through `update`; provisionalHbd
+   * follows from what is still provisional afterwards.
+   */
+  updateEarningsHistory(update: (entry: EarningsHistoryEntry) => EarningsHistoryEntry): void {
+    this.earnings.updateProvisional(update);
   }
 }
@@ -0,0 +1,162 @@
+import { EventEmitter } from 'events';
+import axios from 'axios';
+import { KuboManager } from './kubo';
+import { ConfigStore } from './config';
+
+const CHECK_INTERVAL_MS = 10 * 60 * 1000;
+
+export type Reachability = 'Reachable' | 'Relayed' | 'Unreachable' | 'Unknown';
+
+export interface ConnectivityReport {
+  reachability: Reachability;
+  publicAddrs: string[];
+  relayAddrs: string[];
+  portMapped: boolean;
+  dialBack: boolean | null;
+  hint: string | null;
+  checkedAt: string;
+}
+
+function isPrivateIp(ip: string): boolean {
+  if (ip.includes(':')) {
+    const lower = ip.toLowerCase();
+    return lower === '::1' || lower.startsWith('fc') || lower.startsWith('fd') || lower.startsWith('fe80');
+  }
+
+  const [a, b] = ip.split('.').map((n) => parseInt(n, 10));
+  return a === 10 || a === 127 || a === 0
+    || (a === 172 && b >= 16 && b <= 31)
+    || (a === 192 && b === 168)
+    || (a === 169 && b === 254)
+    || (a === 100 && b >= 64 && b <= 127);
+}
+
+export function extractIp(multiaddr: string): string | null {
+  const match = multiaddr.match(/^\/ip[46]\/([^/]+)/);
+  return match ? match[1] : null;
+}
+
+/**
+ * Lets libp2p try UPnP / NAT-PMP port mapping, hole punching and relays.
+ */
+export function applyNatTraversal(repoConfig: any): void {
+  repoConfig.Swarm = {
+    ...repoConfig.Swarm,
+    DisableNatPortMap: false,
+    EnableHolePunching: true,
+    RelayClient: { ...repoConfig.Swarm?.RelayClient, Enabled: true },
+  };
+}
+
+/**
+ * Works out whether validators can dial this node. Uses the addresses the
+ * daemon announces (public, port-mapped or relayed) and, when configured,
+ * a remote dial-back service that tries to connect to us from outside.
+ */
+export class ConnectivityMonitor extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private firstCheck: NodeJS.Timeout | null = null;
+  private report: ConnectivityReport | null = null;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.check(), CHECK_INTERVAL_MS);
+    // Give libp2p a moment to discover its external addresses
+    this.firstCheck = setTimeout(() => {
+      this.firstCheck = null;
+      this.check();
+    }, 30000);
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+    if (this.firstCheck) {
+      clearTimeout(this.firstCheck);
+      this.firstCheck = null;
+    }
+  }
+
+  getReport(): ConnectivityReport | null {
+    return this.report;
+  }
+
+  async check(): Promise<ConnectivityReport> {
+    const previous = this.report?.reachability;
+    const report = await this.diagnose();
+    this.report = report;
+
+    if (report.reachability !== previous) {
+      console.log(`[Connectivity] ${report.reachability}${report.hint ? ` - ${report.hint}` : ''}`);
+      this.emit('connectivity-changed', report);
+    }
+    return report;
+  }
+
+  private async diagnose(): Promise<ConnectivityReport> {
+    const report: ConnectivityReport = {
+      reachability: 'Unknown',
+      publicAddrs: [],
+      relayAddrs: [],
+      portMapped: false,
+      dialBack: null,
+      hint: null,
+      checkedAt: new Date().toISOString(),
+    };
+
+    let id: { ID: string; Addresses: string[] | null };
+    try {
+      const response = await axios.post(`${this.kubo.getApiUrl()}/api/v0/id`, null, { timeout: 5000 });
+      id = response.data;
+    } catch {
+      report.reachability = 'Unreachable';
+      report.hint = 'The IPFS daemon is not responding.';
+      return report;
+    }
+
+    const addrs = id.Addresses || [];
+    report.relayAddrs = addrs.filter((addr) => addr.includes('/p2p-circuit'));
+    report.publicAddrs = addrs.filter((addr) => {
+      if (addr.includes('/p2p-circuit')) return false;
+      const ip = extractIp(addr);
+      return ip !== null && !isPrivateIp(ip);
+    });
+
+    const swarmPort = String(this.kubo.getSettings().swarmPort);
+    report.portMapped = report.publicAddrs.some((addr) => addr.includes(`/tcp/${swarmPort}`) || addr.includes(`/udp/${swarmPort}`));
+
+    const dialBackUrl = this.config.getConfig().connectivityCheckUrl;
+    if (dialBackUrl) {
+      try {
+        const response = await axios.post(dialBackUrl, { peerId: id.ID, addrs: report.publicAddrs }, { timeout: 15000 });
+        report.dialBack = Boolean(response.data?.reachable);
+      } catch (error: any) {
+        console.error('[Connectivity] Dial-back check failed:', error.message);
+      }
+    }
+
+    if (report.dialBack === true || (report.dialBack === null && report.publicAddrs.length > 0)) {
+      report.reachability = 'Reachable';
+    } else if (report.relayAddrs.length > 0) {
+      report.reachability = 'Relayed';
+      report.hint = 'Only reachable through a relay. Enable UPnP on your router or forward '
+        + `port ${swarmPort} (TCP and UDP) to this machine for reliable PoA challenges.`;
+    } else {
+      report.reachability = 'Unreachable';
+      report.hint = `Validators cannot dial this node. Forward port ${swarmPort} (TCP and UDP) `
+        + 'on your router or enable UPnP; PoA challenges will fail until then.';
+    }
+
+    return report;
+  }
+}
@@ -0,0 +1,291 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import axios from 'axios';
+import { KuboPool } from './kubo-pool';
+import { KuboManager } from './kubo';
+import { ContractPinner } from './contracts';
+import { cidMultihash } from './scrubber';
+
+const SAMPLE_INTERVAL_MS = 30 * 1000;
+// Contract DAGs are walked again this often, or sooner when the contracts change
+const INDEX_TTL_MS = 60 * 60 * 1000;
+const MAX_INDEXED_BLOCKS = 500000;
+const MAX_PEERS_PER_SAMPLE = 100;
+const SAVE_INTERVAL_MS = 5 * 60 * 1000;
+const RETENTION_DAYS = 90;
+export const MAX_REPORT_DAYS = RETENTION_DAYS;
+
+export interface CidBandwidth {
+  cid: string;
+  bytes: number;
+  // Blocks of it seen on peers' wantlists
+  requests: number;
+  lastRequestedAt: string | null;
+}
+
+export interface ContractBandwidth {
+  contractId: string;
+  account: string | null;
+  bytes: number;
+  requests: number;
+  cids: CidBandwidth[];
+}
+
+export interface BandwidthReport {
+  since: string;
+  days: number;
+  // Everything bitswap sent over the period
+  sentBytes: number;
+  // The part of it put down to a contract CID
+  attributedBytes: number;
+  contracts: ContractBandwidth[];
+  // Per day, oldest first, for charting
+  daily: Array<{ day: string; sentBytes: number; attributedBytes: number }>;
+}
+
+interface DayLedger {
+  sent: number;
+  attributed: number;
+  cids: Record<string, { bytes: number; requests: number; last: string }>;
+}
+
+function dayOf(time: number): string {
+  return new Date(time).toISOString().slice(0, 10);
+}
+
+// Wantlists and refs may name a block by CIDv0 or v1; the multihash is the same
+function blockKey(cid: string): string {
+  const multihash = cidMultihash(cid);
+  return multihash ? `${multihash.code}:${multihash.digest.toString('hex')}` : cid;
+}
+
+/**
+ * Estimates how much of what the node uploads goes to each contract CID.
+ * Kubo only counts bytes sent per peer, so every 30 seconds the bitswap
+ * ledger of each partner is sampled together with its wantlist: the bytes
+ * sent to a peer since the last sample are split across the contract
+ * blocks it was asking for. Blocks are matched to the contract CID
+ * they belong to through an index of every contract DAG. Wants served
+ * between two samples are missed, so the split is an estimate; bytes that
+ * can't be placed count towards the total only. Daily counts are kept
+ * for 90 days.
+ */
+export class ContractBandwidthMeter {
+  private pool: KuboPool;
+  private contracts: ContractPinner;
+  private filePath: string;
+  private days: Record<string, DayLedger>;
+  private index = new Map<string, string>();
+  private indexedAt = 0;
+  private indexedCids = '';
+  private sentByPeer = new Map<string, number>();
+  private sentByInstance = new Map<string, number>();
+  private timer: NodeJS.Timeout | null = null;
+  private saveTimer: NodeJS.Timeout | null = null;
+  private sampling = false;
+  private dirty = false;
+
+  constructor(dataDir: string, pool: KuboPool, contracts: ContractPinner) {
+    this.pool = pool;
+    this.contracts = contracts;
+    this.filePath = path.join(dataDir, 'contract-bandwidth.json');
+    this.days = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.sample().catch((error) => console.error('[Contracts] Bandwidth sample failed:', error.message));
+    }, SAMPLE_INTERVAL_MS);
+    this.saveTimer = setInterval(() => this.save(), SAVE_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    if (this.saveTimer) clearInterval(this.saveTimer);
+    this.timer = null;
+    this.saveTimer = null;
+    this.save();
+  }
+
+  getReport(days = 30): BandwidthReport {
+    const now = Date.now();
+    const range = Array.from({ length: days }, (_, i) => dayOf(now - (days - 1 - i) * 24 * 60 * 60 * 1000));
+    const perCid = new Map<string, CidBandwidth>();
+    const report: BandwidthReport = {
+      since: `${range[0]}T00:00:00.000Z`,
+      days,
+      sentBytes: 0,
+      attributedBytes: 0,
+      contracts: [],
+      daily: [],
+    };
+
+    for (const day of range) {
+      const ledger = this.days[day];
+      report.daily.push({ day, sentBytes: ledger?.sent ?? 0, attributedBytes: ledger?.attributed ?? 0 });
+      if (!ledger) continue;
+      report.sentBytes += ledger.sent;
+      report.attributedBytes += ledger.attributed;
+      for (const [cid, entry] of Object.entries(ledger.cids)) {
+        const total = perCid.get(cid) ?? { cid, bytes: 0, requests: 0, lastRequestedAt: null };
+        total.bytes += entry.bytes;
+        total.requests += entry.requests;
+        if (!total.lastRequestedAt || entry.last > total.lastRequestedAt) total.lastRequestedAt = entry.last;
+        perCid.set(cid, total);
+      }
+    }
+
+    for (const contract of this.contracts.list()) {
+      // Bytes are split across wanted blocks, so totals come out fractional
+      const cids = Object.keys(contract.cids)
+        .map((cid) => {
+          const total = perCid.get(cid);
+          return total ? { ...total, bytes: Math.round(total.bytes) } : { cid, bytes: 0, requests: 0, lastRequestedAt: null };
+        })
+        .sort((a, b) => b.bytes - a.bytes);
+      report.contracts.push({
+        contractId: contract.id,
+        account: contract.account,
+        bytes: cids.reduce((sum, cid) => sum + cid.bytes, 0),
+        requests: cids.reduce((sum, cid) => sum + cid.requests, 0),
+        cids,
+      });
+    }
+    report.contracts.sort((a, b) => b.bytes - a.bytes);
+    return report;
+  }
+
+  async sample(): Promise<void> {
+    if (this.sampling) return;
+    this.sampling = true;
+    try {
+      await this.refreshIndex();
+      for (const kubo of this.pool.getInstances()) {
+        if (kubo.isRunning()) await this.sampleInstance(kubo);
+      }
+    } finally {
+      this.sampling = false;
+    }
+  }
+
+  private async sampleInstance(kubo: KuboManager): Promise<void> {
+    const apiUrl = kubo.getApiUrl();
+    const instance = kubo.getInstanceId();
+    const stat = (await axios.post(`${apiUrl}/api/v0/bitswap/stat`, null, { timeout: 10000 })).data;
+    const now = Date.now();
+    const ledger = this.ledgerFor(dayOf(now));
+
+    const dataSent = Number(stat.DataSent) || 0;
+    const previous = this.sentByInstance.get(instance);
+    this.sentByInstance.set(instance, dataSent);
+    // A restarted daemon counts from zero again
+    if (previous !== undefined) ledger.sent += dataSent >= previous ? dataSent - previous : dataSent;
+
+    const peers: string[] = (Array.isArray(stat.Peers) ? stat.Peers : []).slice(0, MAX_PEERS_PER_SAMPLE);
+    for (const peer of peers) {
+      try {
+        const sent = Number((await axios.post(`${apiUrl}/api/v0/bitswap/ledger?arg=${peer}`, null, { timeout: 5000 })).data?.Sent) || 0;
+        const key = `${instance}:${peer}`;
+        const before = this.sentByPeer.get(key);
+        this.sentByPeer.set(key, sent);
+        const delta = before === undefined ? 0 : sent >= before ? sent - before : sent;
+
+        const wantlist = (await axios.post(`${apiUrl}/api/v0/bitswap/wantlist?peer=${peer}`, null, { timeout: 5000 })).data;
+        const wanted = (Array.isArray(wantlist?.Keys) ? wantlist.Keys : [])
+          .map((entry: any) => this.index.get(blockKey(entry?.['/'] ?? '')))
+          .filter((cid: string | undefined): cid is string => !!cid);
+        if (wanted.length === 0) continue;
+
+        const share = delta / wanted.length;
+        for (const cid of wanted) {
+          const entry = ledger.cids[cid] ?? (ledger.cids[cid] = { bytes: 0, requests: 0, last: '' });
+          entry.bytes += share;
+          entry.requests++;
+          entry.last = new Date(now).toISOString();
+        }
+        ledger.attributed += delta;
+        this.dirty = true;
+      } catch {
+        // The peer disconnected between the stat and the ledger
+      }
+    }
+
+    // Peers that went away would otherwise be kept forever
+    const current = new Set(peers.map((peer) => `${instance}:${peer}`));
+    for (const key of this.sentByPeer.keys()) {
+      if (key.startsWith(`${instance}:`) && !current.has(key)) this.sentByPeer.delete(key);
+    }
+  }
+
+  // Maps every block of every pinned contract CID to that CID
+  private async refreshIndex(): Promise<void> {
+    const cids = new Set<string>();
+    for (const contract of this.contracts.list()) {
+      for (const [cid, entry] of Object.entries(contract.cids)) {
+        if (entry.status === 'pinned') cids.add(cid);
+      }
+    }
+    const signature = Array.from(cids).sort().join(',');
+    if (signature === this.indexedCids && Date.now() - this.indexedAt < INDEX_TTL_MS) return;
+
+    const index = new Map<string, string>();
+    for (const cid of cids) {
+      if (index.size >= MAX_INDEXED_BLOCKS) break;
+      index.set(blockKey(cid), cid);
+      const holder = (await this.pool.findHolder(cid)) || this.pool.getPrimary();
+      try {
+        const response = await axios.post(
+          `${holder.getApiUrl()}/api/v0/refs?arg=${cid}&recursive=true&unique=true&offline=true`,
+          null,
+          { responseType: 'text', timeout: 5 * 60 * 1000 }
+        );
+        for (const line of String(response.data).split('\n')) {
+          if (index.size >= MAX_INDEXED_BLOCKS) break;
+          try {
+            const ref = JSON.parse(line).Ref;
+            // A block shared by several contracts goes to the first
+            if (ref && !index.has(blockKey(ref))) index.set(blockKey(ref), cid);
+          } catch {
+            // Blank line
+          }
+        }
+      } catch {
+        // Only the root is matched until the next rebuild
+      }
+    }
+    this.index = index;
+    this.indexedCids = signature;
+    this.indexedAt = Date.now();
+  }
+
+  private ledgerFor(day: string): DayLedger {
+    if (!this.days[day]) {
+      this.days[day] = { sent: 0, attributed: 0, cids: {} };
+      const cutoff = dayOf(Date.now() - RETENTION_DAYS * 24 * 60 * 60 * 1000);
+      for (const old of Object.keys(this.days)) {
+        if (old < cutoff) delete this.days[old];
+      }
+    }
+    this.dirty = true;
+    return this.days[day];
+  }
+
+  private load(): Record<string, DayLedger> {
+    try {
+      return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+    } catch {
+      return {};
+    }
+  }
+
+  private save(): void {
+    if (!this.dirty) return;
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.days));
+      this.dirty = false;
+    } catch (error: any) {
+      console.error('[Contracts] Failed to save bandwidth ledger:', error.message);
+    }
+  }
+}
@@ -0,0 +1,217 @@
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { ContractPinner, ContractRecord } from './contracts';
+import { EventBus } from './event-bus';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 60 * 60 * 1000;
+const HOUR_MS = 60 * 60 * 1000;
+
+export interface ContractExpirySettings {
+  // Unpin a contract's content once it has been expired for the grace period
+  enabled: boolean;
+  // Time for a renewal to land on the SPK API before content is dropped
+  graceHours: number;
+}
+
+export const DEFAULT_CONTRACT_EXPIRY_SETTINGS: ContractExpirySettings = {
+  enabled: true,
+  graceHours: 72,
+};
+
+export function validateContractExpirySettings(settings: Partial<ContractExpirySettings>): string[] {
+  const errors: string[] = [];
+  if (settings.enabled !== undefined && typeof settings.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  if (settings.graceHours !== undefined
+    && (!Number.isInteger(settings.graceHours) || settings.graceHours < 0 || settings.graceHours > 24 * 365)) {
+    errors.push('graceHours must be between 0 and 8760');
+  }
+  return errors;
+}
+
+export interface ContractExpiryOperations {
+  unpin(cid: string): Promise<void>;
+  sizeOf(cid: string): Promise<number | null>;
+  // True when the CID was also pinned or named by the user, outside any contract
+  heldOutsideContracts(cid: string): boolean;
+}
+
+export interface ExpiringContract {
+  contractId: string;
+  expiresAt: string;
+  // When the content will be unpinned unless the contract is renewed
+  unpinAt: string;
+  cids: number;
+}
+
+export interface ExpirySweep {
+  at: string;
+  contractIds: string[];
+  unpinned: number;
+  // Contracts moved to the trash instead, their content still pinned
+  trashed: number;
+  // CIDs still held for another contract or by the user
+  kept: number;
+  reclaimedBytes: number;
+  errors: string[];
+}
+
+export interface ContractExpiryStatus {
+  settings: ContractExpirySettings;
+  upcoming: ExpiringContract[];
+  lastSweep: ExpirySweep | null;
+}
+
+/**
+ * Drops storage contracts whose expiry passed more than the grace period
+ * ago, unpinning their CIDs and reclaiming the disk. Contracts from the SPK
+ * API are looked up again first, so one renewed in the meantime is kept.
+ * With a trash retention set, they are moved to the trash instead and
+ * their content goes when it is emptied.
+ */
+export class ContractExpiryWatcher {
+  private config: ConfigStore;
+  private contracts: ContractPinner;
+  private ops: ContractExpiryOperations;
+  private events: EventBus;
+  private timer: NodeJS.Timeout | null = null;
+  private lastSweep: ExpirySweep | null = null;
+  private sweeping = false;
+
+  constructor(config: ConfigStore, contracts: ContractPinner, ops: ContractExpiryOperations, events: EventBus) {
+    this.config = config;
+    this.contracts = contracts;
+    this.ops = ops;
+    this.events = events;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.sweep().catch((error) => console.error('[Contracts] Expiry sweep failed:', error.message));
+    }, CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getStatus(): ContractExpiryStatus {
+    const settings = this.config.getConfig().contractExpiry;
+    // Content of a trashed contract is only unpinned once the trash is emptied
+    const holdHours = settings.graceHours + this.config.getConfig().trash.retentionHours;
+    const upcoming = this.contracts.list()
+      .filter((record): record is ContractRecord & { expiresAt: string } => record.expiresAt !== null)
+      .map((record) => ({
+        contractId: record.id,
+        expiresAt: record.expiresAt,
+        unpinAt: new Date(new Date(record.expiresAt).getTime() + holdHours * HOUR_MS).toISOString(),
+        cids: Object.keys(record.cids).length,
+      }))
+      .sort((a, b) => a.expiresAt.localeCompare(b.expiresAt));
+    return { settings, upcoming, lastSweep: this.lastSweep };
+  }
+
+  async sweep(): Promise<ExpirySweep | null> {
+    const { enabled, graceHours } = this.config.getConfig().contractExpiry;
+    if (!enabled || this.sweeping) return null;
+    this.sweeping = true;
+    try {
+      const cutoff = Date.now() - graceHours * HOUR_MS;
+      const due: ContractRecord[] = [];
+      for (const record of this.contracts.list()) {
+        if (!record.expiresAt || new Date(record.expiresAt).getTime() > cutoff) continue;
+        if (record.source === 'spk' && await this.wasRenewed(record, cutoff)) continue;
+        due.push(record);
+      }
+      if (due.length === 0) return null;
+
+      const sweep: ExpirySweep = {
+        at: new Date().toISOString(),
+        contractIds: due.map((record) => record.id),
+        unpinned: 0,
+        trashed: 0,
+        kept: 0,
+        reclaimedBytes: 0,
+        errors: [],
+      };
+
+      const { retentionHours } = this.config.getConfig().trash;
+      if (retentionHours > 0) {
+        for (const record of due) {
+          if (this.contracts.trash(record.id, 'expired')) sweep.trashed++;
+        }
+        this.lastSweep = sweep;
+        console.log(`[Contracts] Moved ${sweep.trashed} expired contracts to the trash`);
+        this.events.publish('pins', 'contracts-expired', sweep);
+        showNotification('Expired contracts moved to the trash',
+          `${sweep.trashed} expired contracts were moved to the trash. Their content is unpinned in `
+          + `${Math.round(retentionHours / 24) || 1} days unless you restore them.`, { kind: 'contracts' });
+        return sweep;
+      }
+
+      const dropping = new Set(sweep.contractIds);
+      const cids = new Set(due.flatMap((record) => Object.keys(record.cids)));
+      const failed = new Set<string>();
+      for (const cid of cids) {
+        const stillWanted = this.contracts.contractsFor(cid).some((id) => !dropping.has(id))
+          || this.ops.heldOutsideContracts(cid);
+        if (stillWanted) {
+          sweep.kept++;
+          continue;
+        }
+        try {
+          // Sized first; the root can't be statted cheaply once unpinned and collected
+          const size = await this.ops.sizeOf(cid);
+          await this.ops.unpin(cid);
+          sweep.unpinned++;
+          sweep.reclaimedBytes += size ?? 0;
+        } catch (error: any) {
+          failed.add(cid);
+          sweep.errors.push(`${cid}: ${error.message}`);
+        }
+      }
+
+      // A contract whose unpin failed stays, so the next sweep retries it
+      for (const record of due) {
+        if (Object.keys(record.cids).some((cid) => failed.has(cid))) continue;
+        this.contracts.remove(record.id);
+      }
+
+      this.lastSweep = sweep;
+      const gb = (sweep.reclaimedBytes / 1e9).toFixed(2);
+      console.log(`[Contracts] Dropped ${due.length} expired contracts, unpinned ${sweep.unpinned} CIDs (${gb} GB)`);
+      this.events.publish('pins', 'contracts-expired', sweep);
+      if (sweep.unpinned > 0) {
+        showNotification('Expired contracts removed',
+          `Unpinned ${sweep.unpinned} CIDs from ${due.length} expired contracts, freeing about ${gb} GB. `
+          + 'Space is reclaimed at the next garbage collection.', { kind: 'contracts' });
+      }
+      return sweep;
+    } finally {
+      this.sweeping = false;
+    }
+  }
+
+  // Renewals extend expiresAt on the SPK API; those are taken over rather than dropped
+  private async wasRenewed(record: ContractRecord, cutoff: number): Promise<boolean> {
+    const url = `${this.config.getConfig().spkApiUrl.replace(/\/$/, '')}/api/contracts/${encodeURIComponent(record.id)}`;
+    try {
+      const response = await axios.get(url, { timeout: 10000 });
+      const expiresAt: unknown = response.data?.expiresAt;
+      if (typeof expiresAt === 'string' && expiresAt !== record.expiresAt) {
+        this.contracts.setExpiry(record.id, expiresAt);
+        return new Date(expiresAt).getTime() > cutoff;
+      }
+      return false;
+    } catch (error: any) {
+      if (error.response?.status === 404) return false;
+      // Unreachable API says nothing about a renewal; try again next sweep
+      console.error(`[Contracts] Could not check ${record.id} for renewal:`, error.message);
+      return true;
+    }
+  }
+}
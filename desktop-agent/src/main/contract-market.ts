@@ -0,0 +1,161 @@
+import { ConfigStore } from './config';
+import { KuboManager } from './kubo';
+import { ContractPinner, ContractRecord } from './contracts';
+import { ContractOffer, fetchOpenOffers, lookupContentSize, offerTerms } from './contract-policy';
+import { TransactionQueue } from './tx-queue';
+import { ApiError } from './api-errors';
+
+// Sizing asks Kubo for each root; a long list of offers shouldn't tie it up
+const SIZE_CONCURRENCY = 4;
+
+export interface ContractMarketSettings {
+  // Honeycomb op a storage node accepts contracts with; SPK testnet ops carry the spkccT_ prefix
+  storeOpId: string;
+}
+
+export const DEFAULT_CONTRACT_MARKET_SETTINGS: ContractMarketSettings = {
+  storeOpId: 'spkccT_store',
+};
+
+export function validateContractMarketSettings(settings: Partial<ContractMarketSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.storeOpId !== undefined && !/^[a-zA-Z0-9_.-]{1,32}$/.test(settings.storeOpId)) {
+    errors.push('storeOpId must be 1-32 letters, digits, dots, dashes or underscores');
+  }
+  return errors;
+}
+
+export interface MarketOffer {
+  id: string;
+  fileCid: string;
+  uploader: string;
+  sizeBytes: number | null;
+  // This node's share of the budget
+  rewardHbd: number;
+  durationDays: number;
+  hbdPerGbMonth: number | null;
+  expiresAt: string;
+  // Storage nodes the contract still wants
+  openSlots: number;
+  // Already pinned here; accepted is set once the on-chain acceptance went through
+  held: boolean;
+  accepted: boolean;
+}
+
+export interface MarketAcceptance {
+  contract: ContractRecord;
+  txId: string;
+}
+
+/**
+ * The open side of the SPK storage market: contracts still short of
+ * storage nodes, with their size, what they pay this node and for how
+ * long, so offers can be picked by hand rather than by the contract
+ * policy. Accepting one pins its content, then broadcasts the honeycomb
+ * store op through the transaction queue to take a slot on-chain.
+ */
+export class ContractMarket {
+  private config: ConfigStore;
+  private kubo: KuboManager;
+  private contracts: ContractPinner;
+  private queue: TransactionQueue;
+  private isAcceptingContracts: () => boolean;
+  private sizes = new Map<string, number>();
+
+  constructor(config: ConfigStore, kubo: KuboManager, contracts: ContractPinner, queue: TransactionQueue,
+    isAcceptingContracts: () => boolean) {
+    this.config = config;
+    this.kubo = kubo;
+    this.contracts = contracts;
+    this.queue = queue;
+    this.isAcceptingContracts = isAcceptingContracts;
+  }
+
+  // Best paying per GB first; offers of unknown size last
+  async list(): Promise<MarketOffer[]> {
+    const offers = await fetchOpenOffers(this.config.getConfig().spkApiUrl);
+    const sizes = await this.sizeAll(offers);
+    return offers
+      .map((offer, index) => this.describe(offer, sizes[index]))
+      .sort((a, b) => (b.hbdPerGbMonth ?? -1) - (a.hbdPerGbMonth ?? -1));
+  }
+
+  /**
+   * Pins the contract and accepts it on-chain. Resolves once the acceptance
+   * is included; if that fails, the content stays pinned and accepting again
+   * only redoes the broadcast.
+   */
+  async accept(contractId: string): Promise<MarketAcceptance> {
+    const { hiveUsername, contractMarket } = this.config.getConfig();
+    if (!hiveUsername) throw new ApiError('INVALID_REQUEST', 'Log in with a Hive account first');
+    if (this.contracts.get(contractId)?.acceptedTxId) {
+      throw new ApiError('CONFLICT', `Contract ${contractId} is already accepted`);
+    }
+    let offers: ContractOffer[];
+    try {
+      offers = await fetchOpenOffers(this.config.getConfig().spkApiUrl);
+    } catch (error: any) {
+      throw new ApiError('SPK_API_UNAVAILABLE', `Could not fetch offers: ${error.message}`);
+    }
+    const offer = offers.find((candidate) => candidate.id === contractId);
+    if (!offer) throw new ApiError('NOT_FOUND', `Contract ${contractId} is not open for storage nodes`);
+    if (!this.isAcceptingContracts()) throw new ApiError('STORAGE_FULL', 'Storage quota reached, new content is paused');
+
+    await this.contracts.pin({ contractId, expiresAt: offer.expiresAt });
+    let txId: string;
+    try {
+      ({ txId } = await this.queue.submit({
+        source: 'contracts',
+        description: `Accept storage contract ${contractId}`,
+        operations: [['custom_json', {
+          required_auths: [],
+          required_posting_auths: [hiveUsername],
+          id: contractMarket.storeOpId,
+          json: JSON.stringify({ items: [contractId] }),
+        }]],
+        role: 'posting',
+      }));
+    } catch (error: any) {
+      throw new Error(`Pinning ${contractId}, but accepting it on-chain failed: ${error.message}`);
+    }
+    this.contracts.markAccepted(contractId, txId);
+    console.log(`[Contracts] Accepted ${contractId} from @${offer.uploaderUsername}: ${txId}`);
+    return { contract: this.contracts.get(contractId)!, txId };
+  }
+
+  private describe(offer: ContractOffer, sizeBytes: number | null): MarketOffer {
+    const terms = offerTerms(offer, sizeBytes);
+    const held = this.contracts.get(offer.id);
+    return {
+      id: offer.id,
+      fileCid: offer.fileCid,
+      uploader: offer.uploaderUsername,
+      sizeBytes,
+      rewardHbd: terms.shareHbd,
+      // offerTerms counts 30-day months
+      durationDays: Math.max(0, terms.months * 30),
+      hbdPerGbMonth: terms.hbdPerGbMonth,
+      expiresAt: offer.expiresAt,
+      openSlots: offer.requestedReplication - offer.actualReplication,
+      held: !!held,
+      accepted: !!held?.acceptedTxId,
+    };
+  }
+
+  // A CID's size never changes, so each is only looked up once
+  private async sizeAll(offers: ContractOffer[]): Promise<Array<number | null>> {
+    const sizes: Array<number | null> = new Array(offers.length).fill(null);
+    let next = 0;
+    const worker = async () => {
+      while (next < offers.length) {
+        const index = next++;
+        const offer = offers[index];
+        const size = offer.size ?? this.sizes.get(offer.fileCid) ?? await lookupContentSize(this.kubo, offer.fileCid);
+        if (size !== null) this.sizes.set(offer.fileCid, size);
+        sizes[index] = size;
+      }
+    };
+    await Promise.all(Array.from({ length: SIZE_CONCURRENCY }, worker));
+    return sizes;
+  }
+}
@@ -0,0 +1,360 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { KuboManager } from './kubo';
+import { ContractPinner } from './contracts';
+import { ReplicationTier, REPLICATION_TIERS, isReplicationTier } from './replication-tiers';
+import { CapacityPlanner } from './capacity-planner';
+
+const GB = 1e9;
+const MS_PER_MONTH = 30 * 24 * 3600 * 1000;
+const MAX_DECISIONS = 500;
+
+export interface ContractPolicy {
+  // Off by default; offers are only taken by hand until the user sets rules
+  enabled: boolean;
+  // Decide and log, but don't pin
+  dryRun: boolean;
+  pollIntervalMin: number;
+  maxContractGb: number | null;
+  // This node's share of the budget, per GB per 30 days
+  minHbdPerGbMonth: number | null;
+  blockedAccounts: string[];
+  // Accepted contracts plus the offer may not exceed this
+  maxCommittedGb: number | null;
+  // Count only the bytes an offer would add to the repo against maxCommittedGb
+  dedupAware: boolean;
+  // The replication tier accepted contracts get, unless a rule below picks another
+  tier: ReplicationTier;
+  // Offers paying at least this per GB per month are kept hot
+  hotMinHbdPerGbMonth: number | null;
+  // Offers at least this large are kept cold; a well paying offer stays hot
+  coldMinGb: number | null;
+}
+
+export const DEFAULT_CONTRACT_POLICY: ContractPolicy = {
+  enabled: false,
+  dryRun: true,
+  pollIntervalMin: 15,
+  maxContractGb: null,
+  minHbdPerGbMonth: null,
+  blockedAccounts: [],
+  maxCommittedGb: null,
+  dedupAware: true,
+  tier: 'warm',
+  hotMinHbdPerGbMonth: null,
+  coldMinGb: null,
+};
+
+export function validateContractPolicy(policy: Partial<ContractPolicy>): string[] {
+  const errors: string[] = [];
+  for (const field of ['enabled', 'dryRun', 'dedupAware'] as const) {
+    if (policy[field] !== undefined && typeof policy[field] !== 'boolean') errors.push(`${field} must be a boolean`);
+  }
+  if (policy.pollIntervalMin !== undefined && (!Number.isInteger(policy.pollIntervalMin) || policy.pollIntervalMin < 1 || policy.pollIntervalMin > 1440)) {
+    errors.push('pollIntervalMin must be between 1 and 1440');
+  }
+  for (const field of ['maxContractGb', 'minHbdPerGbMonth', 'maxCommittedGb', 'hotMinHbdPerGbMonth', 'coldMinGb'] as const) {
+    const value = policy[field];
+    if (value !== undefined && value !== null && (typeof value !== 'number' || !(value >= 0))) {
+      errors.push(`${field} must be a non-negative number or null`);
+    }
+  }
+  if (policy.tier !== undefined && !isReplicationTier(policy.tier)) {
+    errors.push(`tier must be one of: ${REPLICATION_TIERS.join(', ')}`);
+  }
+  if (policy.blockedAccounts !== undefined
+    && (!Array.isArray(policy.blockedAccounts) || policy.blockedAccounts.some((account) => typeof account !== 'string'))) {
+    errors.push('blockedAccounts must be a list of Hive usernames');
+  }
+  return errors;
+}
+
+// A contract on the SPK API still looking for storage nodes
+export interface ContractOffer {
+  id: string;
+  fileCid: string;
+  uploaderUsername: string;
+  hbdBudget: string;
+  requestedReplication: number;
+  actualReplication: number;
+  status: string;
+  startsAt: string;
+  expiresAt: string;
+  size?: number;
+}
+
+export interface PolicyDecision {
+  at: string;
+  contractId: string;
+  uploader: string;
+  sizeBytes: number | null;
+  // What it would add to the repo once blocks already stored are left out; null when not planned
+  newBytes: number | null;
+  hbdPerGbMonth: number | null;
+  decision: 'accept' | 'reject';
+  // The replication tier an accepted contract is pinned with
+  tier: ReplicationTier;
+  // Every rule the offer broke; empty when accepted
+  reasons: string[];
+  dryRun: boolean;
+  // Whether the contract was actually pinned
+  applied: boolean;
+  error: string | null;
+}
+
+interface Evaluation {
+  decision: PolicyDecision['decision'];
+  reasons: string[];
+  hbdPerGbMonth: number | null;
+  tier: ReplicationTier;
+}
+
+export interface OfferTerms {
+  // Storage time left, from now or the start if later
+  months: number;
+  // This node's share of the budget
+  shareHbd: number;
+  hbdPerGbMonth: number | null;
+}
+
+export function offerTerms(offer: ContractOffer, sizeBytes: number | null): OfferTerms {
+  const months = (new Date(offer.expiresAt).getTime() - Math.max(Date.now(), new Date(offer.startsAt).getTime())) / MS_PER_MONTH;
+  const shareHbd = parseFloat(offer.hbdBudget) / Math.max(1, offer.requestedReplication);
+  return { months, shareHbd, hbdPerGbMonth: sizeBytes && months > 0 ? shareHbd / (sizeBytes / GB) / months : null };
+}
+
+// Contracts on the SPK API still short of the storage nodes they asked for
+export async function fetchOpenOffers(spkApiUrl: string): Promise<ContractOffer[]> {
+  const response = await axios.get(`${spkApiUrl.replace(/\/$/, '')}/api/contracts`, { timeout: 10000 });
+  const contracts: ContractOffer[] = Array.isArray(response.data) ? response.data : [];
+  return contracts.filter((contract) =>
+    ['pending', 'active'].includes(contract.status) && contract.actualReplication < contract.requestedReplication);
+}
+
+// From the UnixFS root alone, so sizing an offer doesn't download it
+export async function lookupContentSize(kubo: KuboManager, cid: string): Promise<number | null> {
+  try {
+    const response = await axios.post(`${kubo.getApiUrl()}/api/v0/files/stat?arg=/ipfs/${cid}`, null, { timeout: 30000 });
+    return typeof response.data?.CumulativeSize === 'number' ? response.data.CumulativeSize : null;
+  } catch {
+    return null;
+  }
+}
+
+// The repo space an offer takes: its new bytes when planned with dedupAware on, else its size
+export function addedBytes(sizeBytes: number | null, newBytes: number | null, policy: ContractPolicy): number | null {
+  return policy.dedupAware && newBytes !== null ? newBytes : sizeBytes;
+}
+
+/**
+ * The rules on their own, so a preview and the real run can't disagree.
+ * An unknown size fails every rule that needs one rather than letting the
+ * offer through.
+ */
+export function evaluateOffer(offer: ContractOffer, sizeBytes: number | null, committedBytes: number, policy: ContractPolicy,
+  newBytes: number | null = null): Evaluation {
+  const reasons: string[] = [];
+  const blocked = policy.blockedAccounts.map((account) => account.toLowerCase().replace(/^@/, ''));
+  if (blocked.includes(offer.uploaderUsername.toLowerCase())) {
+    reasons.push(`@${offer.uploaderUsername} is blocked`);
+  }
+
+  const { months, hbdPerGbMonth } = offerTerms(offer, sizeBytes);
+  if (months <= 0) reasons.push('Already expired');
+
+  const needsSize = policy.maxContractGb !== null || policy.minHbdPerGbMonth !== null || policy.maxCommittedGb !== null;
+  if (sizeBytes === null && needsSize) {
+    reasons.push('Size unknown');
+  } else if (sizeBytes !== null) {
+    if (policy.maxContractGb !== null && sizeBytes > policy.maxContractGb * GB) {
+      reasons.push(`${(sizeBytes / GB).toFixed(2)} GB is over the ${policy.maxContractGb} GB limit`);
+    }
+    const added = addedBytes(sizeBytes, newBytes, policy)!;
+    if (policy.maxCommittedGb !== null && committedBytes + added > policy.maxCommittedGb * GB) {
+      reasons.push(`Would commit ${((committedBytes + added) / GB).toFixed(2)} GB of the ${policy.maxCommittedGb} GB allowed`
+        + (added < sizeBytes ? ` (${(added / GB).toFixed(2)} GB of it new)` : ''));
+    }
+    if (policy.minHbdPerGbMonth !== null && (hbdPerGbMonth === null || hbdPerGbMonth < policy.minHbdPerGbMonth)) {
+      reasons.push(`Pays ${hbdPerGbMonth?.toFixed(4) ?? 0} HBD/GB/month, under the ${policy.minHbdPerGbMonth} minimum`);
+    }
+  }
+
+  return {
+    decision: reasons.length === 0 ? 'accept' : 'reject',
+    reasons,
+    hbdPerGbMonth,
+    tier: offerTier(sizeBytes, hbdPerGbMonth, policy),
+  };
+}
+
+// The tier rules only apply when the offer's size or pay is known
+export function offerTier(sizeBytes: number | null, hbdPerGbMonth: number | null, policy: ContractPolicy): ReplicationTier {
+  if (policy.hotMinHbdPerGbMonth !== null && hbdPerGbMonth !== null && hbdPerGbMonth >= policy.hotMinHbdPerGbMonth) {
+    return 'hot';
+  }
+  if (policy.coldMinGb !== null && sizeBytes !== null && sizeBytes >= policy.coldMinGb * GB) return 'cold';
+  return policy.tier;
+}
+
+/**
+ * Accepts or rejects open contract offers from the SPK API by the user's
+ * rules, on a timer. Every decision is logged to contract-decisions.json
+ * with the rules it broke and the replication tier an accept is pinned
+ * with; in dry-run mode decisions are logged but nothing is pinned.
+ */
+export class ContractPolicyEngine {
+  private filePath: string;
+  private config: ConfigStore;
+  private kubo: KuboManager;
+  private contracts: ContractPinner;
+  private planner: CapacityPlanner;
+  private isAcceptingContracts: () => boolean;
+  private decisions: PolicyDecision[];
+  private timer: NodeJS.Timeout | null = null;
+  private running = false;
+
+  constructor(dataDir: string, config: ConfigStore, kubo: KuboManager, contracts: ContractPinner, planner: CapacityPlanner,
+    isAcceptingContracts: () => boolean) {
+    this.filePath = path.join(dataDir, 'contract-decisions.json');
+    this.config = config;
+    this.kubo = kubo;
+    this.contracts = contracts;
+    this.planner = planner;
+    this.isAcceptingContracts = isAcceptingContracts;
+    this.decisions = this.load();
+  }
+
+  start(): void {
+    this.reload();
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  reload(): void {
+    this.stop();
+    const { enabled, pollIntervalMin } = this.config.getConfig().contractPolicy;
+    if (!enabled) return;
+    this.timer = setInterval(() => {
+      this.runOnce().catch((error) => console.error('[Contracts] Policy run failed:', error.message));
+    }, pollIntervalMin * 60 * 1000);
+  }
+
+  // Newest first
+  getDecisions(): PolicyDecision[] {
+    return [...this.decisions].reverse();
+  }
+
+  /**
+   * What the rules would decide for the current offers, without pinning or
+   * logging anything. Takes unsaved rules so they can be tried first.
+   */
+  async preview(overrides: Partial<ContractPolicy> = {}): Promise<PolicyDecision[]> {
+    const policy = { ...this.config.getConfig().contractPolicy, ...overrides };
+    return this.decide(await this.fetchOffers(), policy, true);
+  }
+
+  async runOnce(): Promise<PolicyDecision[]> {
+    if (this.running) return [];
+    this.running = true;
+    try {
+      const policy = this.config.getConfig().contractPolicy;
+      const latest = new Map(this.decisions.map((decision) => [decision.contractId, decision]));
+      // Offers are decided again each run, so loosened rules can take a rejected
+      // one later; only a changed outcome, or an accept to act on, is logged
+      const decisions = (await this.decide(await this.fetchOffers(), policy, policy.dryRun)).filter((decision) => {
+        const previous = latest.get(decision.contractId);
+        if (decision.decision === 'accept' && !decision.dryRun) return true;
+        return !previous || previous.decision !== decision.decision || previous.reasons.join() !== decision.reasons.join();
+      });
+
+      for (const decision of decisions) {
+        if (decision.decision === 'accept' && !decision.dryRun) {
+          try {
+            await this.contracts.pin({ contractId: decision.contractId, tier: decision.tier });
+            decision.applied = true;
+          } catch (error: any) {
+            decision.error = error.message;
+          }
+        }
+        console.log(`[Contracts] ${decision.dryRun ? 'Would ' : ''}${decision.decision} ${decision.contractId}`
+          + `${decision.decision === 'accept' ? ` (${decision.tier})` : ''}`
+          + `${decision.reasons.length > 0 ? `: ${decision.reasons.join('; ')}` : ''}`);
+      }
+      this.decisions.push(...decisions);
+      if (this.decisions.length > MAX_DECISIONS) this.decisions.splice(0, this.decisions.length - MAX_DECISIONS);
+      if (decisions.length > 0) this.save();
+      return decisions;
+    } finally {
+      this.running = false;
+    }
+  }
+
+  private async decide(offers: ContractOffer[], policy: ContractPolicy, dryRun: boolean): Promise<PolicyDecision[]> {
+    let committed = this.committedBytes();
+    const decisions: PolicyDecision[] = [];
+    for (const offer of offers) {
+      const sizeBytes = offer.size ?? await lookupContentSize(this.kubo, offer.fileCid);
+      const newBytes = policy.dedupAware && sizeBytes !== null
+        ? (await this.planner.plan(offer.fileCid, sizeBytes).catch(() => null))?.newBytes ?? null
+        : null;
+      const evaluation = evaluateOffer(offer, sizeBytes, committed, policy, newBytes);
+      if (evaluation.decision === 'accept' && !this.isAcceptingContracts()) {
+        evaluation.decision = 'reject';
+        evaluation.reasons.push('Storage is full');
+      }
+      // Later offers in the same run see the capacity this one takes
+      if (evaluation.decision === 'accept') committed += addedBytes(sizeBytes, newBytes, policy) ?? 0;
+      decisions.push({
+        at: new Date().toISOString(),
+        contractId: offer.id,
+        uploader: offer.uploaderUsername,
+        sizeBytes,
+        newBytes,
+        hbdPerGbMonth: evaluation.hbdPerGbMonth,
+        decision: evaluation.decision,
+        tier: evaluation.tier,
+        reasons: evaluation.reasons,
+        dryRun,
+        applied: false,
+        error: null,
+      });
+    }
+    return decisions;
+  }
+
+  private async fetchOffers(): Promise<ContractOffer[]> {
+    const offers = await fetchOpenOffers(this.config.getConfig().spkApiUrl);
+    return offers.filter((offer) => !this.contracts.get(offer.id));
+  }
+
+  // Contracts accepted by policy that this node still holds
+  private committedBytes(): number {
+    return this.decisions
+      .filter((decision) => decision.applied && decision.sizeBytes && this.contracts.get(decision.contractId))
+      .reduce((total, decision) => total + (decision.newBytes ?? decision.sizeBytes!), 0);
+  }
+
+  private load(): PolicyDecision[] {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Contracts] Failed to read policy decisions:', error);
+    }
+    return [];
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.decisions, null, 2));
+    } catch (error) {
+      console.error('[Contracts] Failed to save policy decisions:', error);
+    }
+  }
+}
@@ -0,0 +1,538 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import axios from 'axios';
+import { EventBus } from './event-bus';
+import { ApiError } from './api-errors';
+import { ListSpec } from './query';
+import { ReplicationTier, highestTier, isReplicationTier, REPLICATION_TIERS } from './replication-tiers';
+import { ShardManifest } from './shards';
+
+// Low enough that a big manifest doesn't starve challenges of bitswap
+const PIN_CONCURRENCY = 3;
+const MAX_MANIFEST_CIDS = 10000;
+const CONTRACT_LOOKUP_TIMEOUT_MS = 10 * 1000;
+
+export type ContractCidStatus = 'queued' | 'pinning' | 'pinned' | 'failed';
+export type ContractStatus = 'pinning' | 'pinned' | 'partial';
+
+export interface ContractCid {
+  status: ContractCidStatus;
+  error: string | null;
+  pinnedAt: string | null;
+}
+
+export interface ContractRecord {
+  id: string;
+  // 'spk' when the manifest came from the SPK API, 'manifest' when posted
+  source: 'spk' | 'manifest';
+  // The linked Hive account it is stored for, whose earnings it adds to
+  account: string | null;
+  status: ContractStatus;
+  expiresAt: string | null;
+  cids: Record<string, ContractCid>;
+  // The transaction that accepted it on-chain, when the agent broadcast one
+  acceptedTxId?: string | null;
+  // Missing on contracts from before tiers, which are warm
+  tier?: ReplicationTier;
+  // Set when the node stores Reed–Solomon shards of the file rather than the file
+  shards?: ContractShards;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface ContractShards {
+  manifestCid: string;
+  fileCid: string;
+  dataShards: number;
+  parityShards: number;
+  // CID of each shard this node was assigned, to its index
+  assigned: Record<string, number>;
+}
+
+export type TrashReason = 'expired' | 'orphaned' | 'removed';
+
+// A contract taken off the node whose content stays pinned until the trash is emptied
+export interface TrashedContract {
+  record: ContractRecord;
+  reason: TrashReason;
+  trashedAt: string;
+}
+
+// The shard of a file this node answers challenges with
+export interface HeldShard {
+  cid: string;
+  index: number;
+  manifestCid: string;
+}
+
+export interface ContractPinOperations {
+  pin(cid: string, contractId: string): Promise<unknown>;
+  getShardManifest(manifestCid: string): Promise<ShardManifest>;
+}
+
+export interface ContractPinRequest {
+  contractId?: unknown;
+  cids?: unknown;
+  expiresAt?: unknown;
+  // Defaults to the active account
+  account?: unknown;
+  // Only applied to a new contract; defaults to warm
+  tier?: unknown;
+  // { manifestCid, indexes }: pin these shards of a sharded file instead of CIDs
+  shards?: unknown;
+}
+
+export const CONTRACT_LIST_SPEC: ListSpec<ContractRecord> = {
+  sortFields: {
+    createdAt: (record) => record.createdAt,
+    updatedAt: (record) => record.updatedAt,
+    expiresAt: (record) => record.expiresAt,
+    id: (record) => record.id,
+  },
+  defaultSort: '-createdAt',
+  filters: {
+    status: (record, value) => record.status === value,
+    source: (record, value) => record.source === value,
+    account: (record, value) => record.account === value,
+    tier: (record, value) => (record.tier ?? 'warm') === value,
+    cid: (record, value) => record.cids[value] !== undefined,
+  },
+  key: (record) => record.id,
+};
+
+function summarize(record: ContractRecord): { total: number; pinned: number; failed: number } {
+  const entries = Object.values(record.cids);
+  return {
+    total: entries.length,
+    pinned: entries.filter((entry) => entry.status === 'pinned').length,
+    failed: entries.filter((entry) => entry.status === 'failed').length,
+  };
+}
+
+// Posted manifests without an id get one derived from their contents, so reposting is idempotent
+function manifestId(cids: string[]): string {
+  const hash = crypto.createHash('sha256').update([...cids].sort().join('\n')).digest('hex');
+  return `manifest-${hash.slice(0, 16)}`;
+}
+
+/**
+ * Pins every CID of a storage contract and remembers which contract each
+ * CID belongs to, for PoA bookkeeping. A contract is given either by ID,
+ * resolved against the SPK API, or as a manifest of CIDs. Pinning runs in
+ * the background; progress goes out on the `pins` event topic and the
+ * records persist in contracts.json so an interrupted batch resumes.
+ */
+export class ContractPinner {
+  private filePath: string;
+  private ops: ContractPinOperations;
+  private events: EventBus;
+  private getSpkApiUrl: () => string;
+  private getActiveAccount: () => string | null;
+  private records: Record<string, ContractRecord>;
+  private trashPath: string;
+  private trashed: Record<string, TrashedContract>;
+  private queue: Array<{ contractId: string; cid: string }> = [];
+  private active = 0;
+
+  constructor(dataDir: string, ops: ContractPinOperations, events: EventBus, getSpkApiUrl: () => string,
+    getActiveAccount: () => string | null) {
+    this.filePath = path.join(dataDir, 'contracts.json');
+    this.trashPath = path.join(dataDir, 'contract-trash.json');
+    this.ops = ops;
+    this.events = events;
+    this.getSpkApiUrl = getSpkApiUrl;
+    this.getActiveAccount = getActiveAccount;
+    this.records = this.load();
+    this.trashed = this.loadTrash();
+  }
+
+  list(): ContractRecord[] {
+    return Object.values(this.records).sort((a, b) => b.createdAt.localeCompare(a.createdAt));
+  }
+
+  get(id: string): ContractRecord | null {
+    return this.records[id] || null;
+  }
+
+  // The CIDs a contract holds: from the local record, or the SPK API for one not stored yet
+  async expectedCids(contractId: string): Promise<string[]> {
+    const record = this.records[contractId];
+    if (record) return Object.keys(record.cids);
+    return (await this.resolve({ contractId })).cids;
+  }
+
+  // Contracts a CID is stored for; a CID can back several
+  contractsFor(cid: string): string[] {
+    return Object.values(this.records)
+      .filter((record) => record.cids[cid] !== undefined)
+      .map((record) => record.id);
+  }
+
+  /**
+   * A pinned shard of `fileCid` held for a contract, or null when none
+   * is. Challenges on the file are answered with it; `index` asks for a
+   * particular shard.
+   */
+  shardFor(fileCid: string, index?: number): HeldShard | null {
+    for (const record of Object.values(this.records)) {
+      const shards = record.shards;
+      if (!shards || (shards.fileCid !== fileCid && shards.manifestCid !== fileCid)) continue;
+      for (const [cid, shardIndex] of Object.entries(shards.assigned)) {
+        if (index !== undefined && shardIndex !== index) continue;
+        if (record.cids[cid]?.status !== 'pinned') continue;
+        return { cid, index: shardIndex, manifestCid: shards.manifestCid };
+      }
+    }
+    return null;
+  }
+
+  // Who a CID earns for: the account of the oldest contract holding it
+  accountFor(cid: string): string | null {
+    const holders = Object.values(this.records)
+      .filter((record) => record.cids[cid] !== undefined && record.account)
+      .sort((a, b) => a.createdAt.localeCompare(b.createdAt));
+    return holders[0]?.account ?? null;
+  }
+
+  tierOf(record: ContractRecord): ReplicationTier {
+    return record.tier ?? 'warm';
+  }
+
+  // A CID backing several contracts is kept to the most demanding of their tiers
+  tierFor(cid: string): ReplicationTier {
+    const tiers = Object.values(this.records)
+      .filter((record) => record.cids[cid] !== undefined)
+      .map((record) => this.tierOf(record));
+    return tiers.length > 0 ? highestTier(tiers) : 'warm';
+  }
+
+  setTier(id: string, tier: ReplicationTier): ContractRecord | null {
+    const record = this.records[id];
+    if (!record) return null;
+    record.tier = tier;
+    record.updatedAt = new Date().toISOString();
+    this.save();
+    this.events.publish('pins', 'contract-tier', { contractId: id, tier });
+    return record;
+  }
+
+  setExpiry(id: string, expiresAt: string | null): void {
+    const record = this.records[id];
+    if (!record) return;
+    record.expiresAt = expiresAt;
+    record.updatedAt = new Date().toISOString();
+    this.save();
+  }
+
+  markAccepted(id: string, txId: string): void {
+    const record = this.records[id];
+    if (!record) return;
+    record.acceptedTxId = txId;
+    record.updatedAt = new Date().toISOString();
+    this.save();
+  }
+
+  // Forgets a contract; its CIDs are left pinned for the caller to deal with
+  remove(id: string): void {
+    if (!this.records[id]) return;
+    delete this.records[id];
+    this.queue = this.queue.filter((entry) => entry.contractId !== id);
+    this.save();
+    this.events.publish('pins', 'contract-removed', { contractId: id });
+  }
+
+  listTrash(): TrashedContract[] {
+    return Object.values(this.trashed).sort((a, b) => a.trashedAt.localeCompare(b.trashedAt));
+  }
+
+  // Trashed contracts a CID is stored for
+  trashedFor(cid: string): string[] {
+    return Object.values(this.trashed)
+      .filter(({ record }) => record.cids[cid] !== undefined)
+      .map(({ record }) => record.id);
+  }
+
+  /**
+   * Takes a contract off the node without touching its content, which
+   * stays pinned until the trash is emptied or the contract restored.
+   */
+  trash(id: string, reason: TrashReason): TrashedContract | null {
+    const record = this.records[id];
+    if (!record) return null;
+    const entry: TrashedContract = { record, reason, trashedAt: new Date().toISOString() };
+    this.trashed[id] = entry;
+    this.saveTrash();
+    this.remove(id);
+    console.log(`[Contracts] Moved ${id} to the trash (${reason})`);
+    this.events.publish('pins', 'contract-trashed', { contractId: id, reason });
+    return entry;
+  }
+
+  /**
+   * Brings a trashed contract back. Every CID is pinned again, which is
+   * instant for what is still in the repo and refetches anything that was
+   * unpinned by hand in the meantime.
+   */
+  restore(id: string): ContractRecord | null {
+    const entry = this.trashed[id];
+    if (!entry) return null;
+    if (this.records[id]) throw new ApiError('CONFLICT', `Contract ${id} was stored again since it was trashed`);
+    const record = entry.record;
+    for (const cid of Object.values(record.cids)) {
+      cid.status = 'queued';
+      cid.error = null;
+    }
+    record.status = 'pinning';
+    record.updatedAt = new Date().toISOString();
+    this.records[id] = record;
+    delete this.trashed[id];
+    this.save();
+    this.saveTrash();
+
+    Object.keys(record.cids).forEach((cid) => this.queue.push({ contractId: id, cid }));
+    this.drain();
+    console.log(`[Contracts] Restored ${id} from the trash`);
+    this.events.publish('pins', 'contract-restored', { contractId: id });
+    return record;
+  }
+
+  // Forgets a trashed contract; its CIDs are left for the caller to unpin first
+  purge(id: string): void {
+    if (!this.trashed[id]) return;
+    delete this.trashed[id];
+    this.saveTrash();
+  }
+
+  async pin(request: ContractPinRequest): Promise<ContractRecord> {
+    if (request.account !== undefined && (typeof request.account !== 'string' || !request.account)) {
+      throw new ApiError('INVALID_REQUEST', 'account must be a Hive username');
+    }
+    if (request.tier !== undefined && !isReplicationTier(request.tier)) {
+      throw new ApiError('INVALID_REQUEST', `tier must be one of: ${REPLICATION_TIERS.join(', ')}`);
+    }
+    const { id, source, cids, expiresAt, shards } = await this.resolve(request);
+    const now = new Date().toISOString();
+    const existing = this.records[id];
+    const record: ContractRecord = existing || {
+      id,
+      source,
+      account: (request.account as string | undefined) ?? this.getActiveAccount(),
+      status: 'pinning',
+      expiresAt,
+      cids: {},
+      tier: (request.tier as ReplicationTier | undefined) ?? 'warm',
+      createdAt: now,
+      updatedAt: now,
+    };
+
+    const queued: string[] = [];
+    for (const cid of cids) {
+      const current = record.cids[cid];
+      // Re-posting a contract retries what failed and leaves what's done
+      if (current && current.status !== 'failed') continue;
+      record.cids[cid] = { status: 'queued', error: null, pinnedAt: null };
+      queued.push(cid);
+    }
+    record.expiresAt = expiresAt ?? record.expiresAt;
+    if (shards) {
+      record.shards = { ...shards, assigned: { ...record.shards?.assigned, ...shards.assigned } };
+    }
+    record.updatedAt = now;
+    if (queued.length > 0) record.status = 'pinning';
+    this.records[id] = record;
+    this.save();
+
+    console.log(`[Contracts] Pinning ${queued.length} of ${cids.length} CIDs for ${id}`);
+    queued.forEach((cid) => this.queue.push({ contractId: id, cid }));
+    this.drain();
+    return record;
+  }
+
+  /**
+   * Requeues CIDs left queued or mid-pin when the agent last stopped.
+   */
+  resume(): void {
+    for (const record of Object.values(this.records)) {
+      for (const [cid, entry] of Object.entries(record.cids)) {
+        if (entry.status === 'queued' || entry.status === 'pinning') {
+          entry.status = 'queued';
+          this.queue.push({ contractId: record.id, cid });
+        }
+      }
+    }
+    if (this.queue.length > 0) {
+      console.log(`[Contracts] Resuming ${this.queue.length} contract pins`);
+      this.drain();
+    }
+  }
+
+  private async resolve(request: ContractPinRequest): Promise<{
+    id: string;
+    source: ContractRecord['source'];
+    cids: string[];
+    expiresAt: string | null;
+    shards: ContractShards | null;
+  }> {
+    const contractId = typeof request.contractId === 'string' && request.contractId.trim()
+      ? request.contractId.trim()
+      : null;
+    const expiresAt = typeof request.expiresAt === 'string' ? request.expiresAt : null;
+
+    if (request.shards !== undefined) {
+      const shards = await this.resolveShards(request.shards);
+      const cids = Object.keys(shards.assigned);
+      return { id: contractId || manifestId(cids), source: 'manifest', cids, expiresAt, shards };
+    }
+
+    if (request.cids !== undefined) {
+      if (!Array.isArray(request.cids) || request.cids.length === 0
+        || request.cids.some((cid) => typeof cid !== 'string' || !cid)) {
+        throw new ApiError('INVALID_REQUEST', 'cids must be a non-empty array of CIDs');
+      }
+      if (request.cids.length > MAX_MANIFEST_CIDS) {
+        throw new ApiError('INVALID_REQUEST', `A manifest can list at most ${MAX_MANIFEST_CIDS} CIDs`);
+      }
+      const cids = Array.from(new Set(request.cids as string[]));
+      return { id: contractId || manifestId(cids), source: 'manifest', cids, expiresAt, shards: null };
+    }
+
+    if (!contractId) {
+      throw new ApiError('INVALID_REQUEST', 'Send a contractId, a cids manifest, or both');
+    }
+
+    const url = `${this.getSpkApiUrl().replace(/\/$/, '')}/api/contracts/${encodeURIComponent(contractId)}`;
+    try {
+      const response = await axios.get(url, { timeout: CONTRACT_LOOKUP_TIMEOUT_MS });
+      const contract = response.data;
+      if (!contract?.fileCid) {
+        throw new ApiError('INVALID_REQUEST', `Contract ${contractId} has no file CID`);
+      }
+      // Sharded contracts assign each storage node its shard indexes
+      const account = (request.account as string | undefined) ?? this.getActiveAccount();
+      const assignment = contract.shards?.manifestCid && account ? contract.shards.assignments?.[account] : undefined;
+      if (Array.isArray(assignment)) {
+        const shards = await this.resolveShards({ manifestCid: contract.shards.manifestCid, indexes: assignment });
+        return { id: contractId, source: 'spk', cids: Object.keys(shards.assigned), expiresAt: contract.expiresAt ?? expiresAt, shards };
+      }
+      return {
+        id: contractId,
+        source: 'spk',
+        cids: [contract.fileCid],
+        expiresAt: contract.expiresAt ?? expiresAt,
+        shards: null,
+      };
+    } catch (error: any) {
+      if (error instanceof ApiError) throw error;
+      if (error.response?.status === 404) {
+        throw new ApiError('NOT_FOUND', `Contract ${contractId} not found`);
+      }
+      throw new ApiError('SPK_API_UNAVAILABLE', `Could not reach the SPK API: ${error.message}`);
+    }
+  }
+
+  private async resolveShards(value: unknown): Promise<ContractShards> {
+    const request = value as { manifestCid?: unknown; indexes?: unknown };
+    if (!request || typeof request.manifestCid !== 'string' || !request.manifestCid) {
+      throw new ApiError('INVALID_REQUEST', 'shards needs a manifestCid');
+    }
+    if (!Array.isArray(request.indexes) || request.indexes.length === 0
+      || request.indexes.some((index) => !Number.isInteger(index) || index < 0)) {
+      throw new ApiError('INVALID_REQUEST', 'shards.indexes must be a non-empty list of shard indexes');
+    }
+    const manifest = await this.ops.getShardManifest(request.manifestCid);
+    const assigned: Record<string, number> = {};
+    for (const index of request.indexes as number[]) {
+      if (index >= manifest.shards.length) {
+        throw new ApiError('INVALID_REQUEST', `Shard ${index} is out of range (${manifest.shards.length} shards)`);
+      }
+      assigned[manifest.shards[index]] = index;
+    }
+    return {
+      manifestCid: request.manifestCid,
+      fileCid: manifest.fileCid,
+      dataShards: manifest.dataShards,
+      parityShards: manifest.parityShards,
+      assigned,
+    };
+  }
+
+  private drain(): void {
+    while (this.active < PIN_CONCURRENCY && this.queue.length > 0) {
+      const next = this.queue.shift()!;
+      this.active++;
+      this.pinOne(next.contractId, next.cid).finally(() => {
+        this.active--;
+        this.drain();
+      });
+    }
+  }
+
+  private async pinOne(contractId: string, cid: string): Promise<void> {
+    const record = this.records[contractId];
+    const entry = record?.cids[cid];
+    if (!entry || entry.status !== 'queued') return;
+
+    // Not saved: a restart requeues 'pinning' the same as 'queued'
+    entry.status = 'pinning';
+    try {
+      await this.ops.pin(cid, contractId);
+      entry.status = 'pinned';
+      entry.pinnedAt = new Date().toISOString();
+    } catch (error: any) {
+      console.error(`[Contracts] Failed to pin ${cid} for ${contractId}:`, error.message);
+      entry.status = 'failed';
+      entry.error = error.message;
+    }
+
+    record.updatedAt = new Date().toISOString();
+    const counts = summarize(record);
+    const finished = counts.pinned + counts.failed === counts.total;
+    if (finished) record.status = counts.failed === 0 ? 'pinned' : 'partial';
+    this.save();
+
+    this.events.publish('pins', 'contract-progress', {
+      contractId,
+      cid,
+      status: entry.status,
+      error: entry.error,
+      ...counts,
+    });
+    if (finished) {
+      this.events.publish('pins', 'contract-finished', { contractId, status: record.status, ...counts });
+    }
+  }
+
+  private load(): Record<string, ContractRecord> {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        const records: Record<string, ContractRecord> = JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+        // Contracts from before multi-account were all the one account's
+        for (const record of Object.values(records)) {
+          if (record.account === undefined) record.account = this.getActiveAccount();
+        }
+        return records;
+      }
+    } catch (error) {
+      console.error('[Contracts] Failed to read contracts:', error);
+    }
+    return {};
+  }
+
+  private save(): void {
+    fs.writeFileSync(this.filePath, JSON.stringify(this.records, null, 2));
+  }
+
+  private loadTrash(): Record<string, TrashedContract> {
+    try {
+      if (fs.existsSync(this.trashPath)) return JSON.parse(fs.readFileSync(this.trashPath, 'utf-8'));
+    } catch (error) {
+      console.error('[Contracts] Failed to read contract trash:', error);
+    }
+    return {};
+  }
+
+  private saveTrash(): void {
+    fs.writeFileSync(this.trashPath, JSON.stringify(this.trashed, null, 2));
+  }
+}
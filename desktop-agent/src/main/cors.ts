@@ -0,0 +1,124 @@
+import { Request, Response, NextFunction } from 'express';
+import { ConfigStore } from './config';
+import { ApiAuth } from './auth';
+import { ApiError, sendError } from './api-errors';
+
+export interface CorsSettings {
+  // Origins the user approved on top of the official ones
+  allowedOrigins: string[];
+  deniedOrigins: string[];
+  // Developer escape hatch: accept every origin without prompting
+  allowAnyOrigin: boolean;
+}
+
+export const DEFAULT_CORS_SETTINGS: CorsSettings = {
+  allowedOrigins: [],
+  deniedOrigins: [],
+  allowAnyOrigin: false,
+};
+
+// The SPK web app, hosted and run locally
+export const OFFICIAL_ORIGINS = [
+  'https://3speak.tv',
+  'https://spk.network',
+  'http://localhost:5000',
+  'http://127.0.0.1:5000',
+];
+
+export type OriginApprover = (origin: string) => Promise<boolean>;
+
+export function normalizeOrigin(origin: string): string | null {
+  try {
+    const url = new URL(origin);
+    if (url.protocol !== 'http:' && url.protocol !== 'https:') return null;
+    return url.origin;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Decides which web pages may talk to the local API. Official SPK origins
+ * are allowed; any other origin is refused until the user approves it in a
+ * prompt, and a denial is remembered so a page can't keep asking.
+ *
+ * Pages with an opaque origin ('null': the file:// dashboard, sandboxed
+ * iframes) are only served when they present the API token.
+ */
+export class CorsPolicy {
+  private config: ConfigStore;
+  private auth: ApiAuth;
+  private approve: OriginApprover;
+  private pending = new Set<string>();
+  private prompts: Promise<void> = Promise.resolve();
+
+  constructor(config: ConfigStore, auth: ApiAuth, approve: OriginApprover) {
+    this.config = config;
+    this.auth = auth;
+    this.approve = approve;
+  }
+
+  isAllowed(origin: string): boolean {
+    const settings = this.config.getConfig().cors;
+    return settings.allowAnyOrigin || OFFICIAL_ORIGINS.includes(origin) || settings.allowedOrigins.includes(origin);
+  }
+
+  middleware() {
+    return (req: Request, res: Response, next: NextFunction) => {
+      const origin = req.headers.origin as string | undefined;
+      // Non-browser clients (validators, the CLI) don't send Origin
+      if (!origin) return next();
+
+      if (origin === 'null') {
+        this.setHeaders(res, 'null');
+        if (req.method === 'OPTIONS') return res.sendStatus(204);
+        if (this.auth.isAuthorized(req)) return next();
+        return sendError(res, new ApiError('FORBIDDEN', 'Requests from an opaque origin need the API token'));
+      }
+
+      const normalized = normalizeOrigin(origin);
+      if (normalized && this.isAllowed(normalized)) {
+        this.setHeaders(res, origin);
+        if (req.method === 'OPTIONS') return res.sendStatus(204);
+        return next();
+      }
+
+      if (normalized && !this.config.getConfig().cors.deniedOrigins.includes(normalized)) {
+        this.requestApproval(normalized);
+        return sendError(res, new ApiError('APPROVAL_PENDING', 'This origin is waiting for approval in the SPK Desktop Agent', { origin: normalized }));
+      }
+      sendError(res, new ApiError('FORBIDDEN', 'Origin not allowed'));
+    };
+  }
+
+  private setHeaders(res: Response, origin: string): void {
+    res.header('Access-Control-Allow-Origin', origin);
+    res.header('Vary', 'Origin');
+    res.header('Access-Control-Allow-Methods', 'GET, HEAD, POST, PUT, PATCH, DELETE, OPTIONS');
+    res.header('Access-Control-Allow-Headers', 'Content-Type, Authorization, Upload-Length, Upload-Offset, Upload-Name');
+    res.header('Access-Control-Expose-Headers', 'Location, Upload-Offset, Upload-Length');
+  }
+
+  // Prompts are shown one at a time so a burst of pages can't stack dialogs
+  private requestApproval(origin: string): void {
+    if (this.pending.has(origin)) return;
+    this.pending.add(origin);
+    this.prompts = this.prompts.then(() => this.prompt(origin)).catch(() => undefined);
+  }
+
+  private async prompt(origin: string): Promise<void> {
+    try {
+      const approved = await this.approve(origin);
+      const cors = this.config.getConfig().cors;
+      if (approved) {
+        this.config.setConfig({ cors: { ...cors, allowedOrigins: [...cors.allowedOrigins, origin] } });
+        console.log(`[CORS] Allowed ${origin}`);
+      } else {
+        this.config.setConfig({ cors: { ...cors, deniedOrigins: [...cors.deniedOrigins, origin] } });
+        console.log(`[CORS] Denied ${origin}`);
+      }
+    } finally {
+      this.pending.delete(origin);
+    }
+  }
+}
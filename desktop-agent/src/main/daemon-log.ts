@@ -0,0 +1,90 @@
+export const LOG_LEVELS = ['debug', 'info', 'warn', 'error'] as const;
+export type LogLevel = typeof LOG_LEVELS[number];
+
+export interface LogEntry {
+  seq: number;
+  timestamp: string;
+  level: LogLevel;
+  source: 'stdout' | 'stderr';
+  message: string;
+}
+
+const DEFAULT_CAPACITY = 5000;
+
+// go-log prefixes structured lines with e.g. "2024-01-01T12:00:00.000Z\tERROR\tbitswap"
+const GO_LOG_LEVEL = /\b(DEBUG|INFO|WARN|WARNING|ERROR|DPANIC|PANIC|FATAL)\b/;
+
+function parseLevel(line: string, source: LogEntry['source']): LogLevel {
+  const match = line.match(GO_LOG_LEVEL);
+  if (match) {
+    switch (match[1]) {
+      case 'DEBUG': return 'debug';
+      case 'INFO': return 'info';
+      case 'WARN':
+      case 'WARNING': return 'warn';
+      default: return 'error';
+    }
+  }
+  // Unstructured stderr is mostly warnings and startup errors
+  return source === 'stderr' ? 'warn' : 'info';
+}
+
+export function meetsLevel(level: LogLevel, minLevel: LogLevel): boolean {
+  return LOG_LEVELS.indexOf(level) >= LOG_LEVELS.indexOf(minLevel);
+}
+
+/**
+ * Keeps the most recent daemon output lines in a fixed-size ring buffer so
+ * the dashboard can show what Kubo was doing without a terminal.
+ */
+export class DaemonLog {
+  private entries: Array<LogEntry | undefined>;
+  private next = 0;
+  private seq = 0;
+  private partial: Record<LogEntry['source'], string> = { stdout: '', stderr: '' };
+
+  constructor(capacity: number = DEFAULT_CAPACITY) {
+    this.entries = new Array(capacity);
+  }
+
+  /**
+   * Splits a chunk of process output into lines; a trailing partial line is
+   * held until the rest of it arrives.
+   */
+  write(source: LogEntry['source'], chunk: string): LogEntry[] {
+    const lines = (this.partial[source] + chunk).split(/\r?\n/);
+    this.partial[source] = lines.pop() || '';
+
+    return lines
+      .filter((line) => line.trim().length > 0)
+      .map((line) => this.push(source, line));
+  }
+
+  getEntries(minLevel: LogLevel = 'debug', limit?: number): LogEntry[] {
+    const capacity = this.entries.length;
+    const ordered: LogEntry[] = [];
+    for (let i = 0; i < capacity; i++) {
+      const entry = this.entries[(this.next + i) % capacity];
+      if (entry && meetsLevel(entry.level, minLevel)) ordered.push(entry);
+    }
+    return limit !== undefined ? ordered.slice(-limit) : ordered;
+  }
+
+  clear(): void {
+    this.entries = new Array(this.entries.length);
+    this.next = 0;
+  }
+
+  private push(source: LogEntry['source'], line: string): LogEntry {
+    const entry: LogEntry = {
+      seq: ++this.seq,
+      timestamp: new Date().toISOString(),
+      level: parseLevel(line, source),
+      source,
+      message: line,
+    };
+    this.entries[this.next] = entry;
+    this.next = (this.next + 1) % this.entries.length;
+    return entry;
+  }
+}
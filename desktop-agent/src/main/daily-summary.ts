@@ -0,0 +1,183 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { powerMonitor } from 'electron';
+import { ConfigStore } from './config';
+import { PriceFeed } from './price-feed';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 60 * 1000;
+const DAY_MS = 24 * 60 * 60 * 1000;
+// A summary after a long absence covers at most this much
+const MAX_CATCH_UP_MS = 7 * DAY_MS;
+
+export interface DailySummarySettings {
+  enabled: boolean;
+  // Local wall-clock time, HH:MM
+  time: string;
+}
+
+export const DEFAULT_DAILY_SUMMARY_SETTINGS: DailySummarySettings = {
+  enabled: true,
+  time: '20:00',
+};
+
+export function validateDailySummarySettings(settings: Partial<DailySummarySettings>): string[] {
+  const errors: string[] = [];
+  if (settings.time !== undefined && !/^([01]\d|2[0-3]):[0-5]\d$/.test(settings.time)) {
+    errors.push('time must be HH:MM, 00:00 to 23:59');
+  }
+  return errors;
+}
+
+export interface DailySummary {
+  from: string;
+  to: string;
+  challenges: number;
+  passed: number;
+  amounts: Record<string, number>;
+  sentAt: string;
+}
+
+export interface DailySummaryStatus {
+  settings: DailySummarySettings;
+  nextAt: string | null;
+  last: DailySummary | null;
+}
+
+interface SummaryState {
+  // The scheduled time of the last summary sent, or passed over when the schedule was set up or changed
+  lastDueAt: string | null;
+  last: DailySummary | null;
+}
+
+/**
+ * Sends a notification with the day's earnings at a local time of the
+ * user's choosing. Times are local wall-clock times, so the summary stays
+ * at 20:00 across a DST change and that day's summary covers 23 or 25
+ * hours. The clock is checked every minute rather than trusting a
+ * day-long timer, so a machine that slept through the time sends the
+ * summary once it wakes, covering everything since the last one (up to a
+ * week). Each summary is only sent once, across restarts.
+ */
+export class DailySummaryScheduler {
+  private config: ConfigStore;
+  private prices: PriceFeed;
+  private filePath: string;
+  private state: SummaryState;
+  private timer: NodeJS.Timeout | null = null;
+  private onResume = () => this.check();
+
+  constructor(dataDir: string, config: ConfigStore, prices: PriceFeed) {
+    this.config = config;
+    this.prices = prices;
+    this.filePath = path.join(dataDir, 'daily-summary.json');
+    this.state = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.check();
+    this.timer = setInterval(() => this.check(), CHECK_INTERVAL_MS);
+    powerMonitor.on('resume', this.onResume);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+    powerMonitor.off('resume', this.onResume);
+  }
+
+  getStatus(): DailySummaryStatus {
+    const settings = this.config.getConfig().dailySummary;
+    const now = new Date();
+    let next = this.scheduledOn(now, settings.time);
+    if (next <= now) next = this.scheduledOn(new Date(now.getFullYear(), now.getMonth(), now.getDate() + 1), settings.time);
+    return { settings, nextAt: settings.enabled ? next.toISOString() : null, last: this.state.last };
+  }
+
+  // After the time changes, a time already past today waits for tomorrow instead of firing now
+  reschedule(): void {
+    const settings = this.config.getConfig().dailySummary;
+    const due = this.lastDue(new Date(), settings.time).toISOString();
+    if (this.state.lastDueAt && this.state.lastDueAt >= due) return;
+    this.state.lastDueAt = due;
+    this.save();
+  }
+
+  // Sends the summary now if a scheduled time has passed since the last one
+  check(): DailySummary | null {
+    const settings = this.config.getConfig().dailySummary;
+    if (!settings.enabled) return null;
+    const now = new Date();
+    const due = this.lastDue(now, settings.time);
+    const lastDue = this.state.lastDueAt ? new Date(this.state.lastDueAt) : null;
+    if (lastDue && lastDue >= due) return null;
+
+    if (!lastDue) {
+      // A fresh schedule starts from the next time rather than sending for one already past
+      this.state.lastDueAt = due.toISOString();
+      this.save();
+      return null;
+    }
+
+    // Picks up where the last summary ended, else from the previous scheduled time; on a DST day that is 23 or 25 hours
+    const previous = this.scheduledOn(new Date(due.getFullYear(), due.getMonth(), due.getDate() - 1), settings.time);
+    const start = this.state.last ? new Date(this.state.last.to) : previous;
+    const from = new Date(Math.max(start.getTime(), due.getTime() - MAX_CATCH_UP_MS));
+    const totals = this.config.getEarningsStore().summarize(from.toISOString(), now.toISOString());
+    const summary: DailySummary = {
+      from: from.toISOString(),
+      to: now.toISOString(),
+      challenges: totals.challenges,
+      passed: totals.passed,
+      amounts: totals.amounts as Record<string, number>,
+      sentAt: now.toISOString(),
+    };
+    this.state = { lastDueAt: due.toISOString(), last: summary };
+    this.save();
+    this.notify(summary, now.getTime() - from.getTime() > DAY_MS + 2 * 60 * 60 * 1000);
+    return summary;
+  }
+
+  private notify(summary: DailySummary, catchUp: boolean): void {
+    const earned = Object.entries(summary.amounts)
+      .filter(([, amount]) => amount > 0)
+      .map(([token, amount]) => (token === 'HBD' ? this.prices.format(amount, 'HBD') : `${amount.toFixed(3)} ${token}`));
+    const body = summary.challenges === 0
+      ? 'No challenges answered. Check that the node is online and reachable.'
+      : `${summary.passed} of ${summary.challenges} challenges passed`
+        + (earned.length > 0 ? `, earning ${earned.join(', ')}.` : '.');
+    const title = catchUp
+      ? `Earnings since ${new Date(summary.from).toLocaleDateString()}`
+      : 'Today\'s earnings';
+    showNotification(title, body, { kind: 'rewards', account: null });
+  }
+
+  // The most recent scheduled time at or before `now`
+  private lastDue(now: Date, time: string): Date {
+    const today = this.scheduledOn(now, time);
+    return today <= now ? today : this.scheduledOn(new Date(now.getFullYear(), now.getMonth(), now.getDate() - 1), time);
+  }
+
+  // Local time on that calendar day; a time skipped by a DST jump lands just after it
+  private scheduledOn(day: Date, time: string): Date {
+    const [hours, minutes] = time.split(':').map(Number);
+    return new Date(day.getFullYear(), day.getMonth(), day.getDate(), hours, minutes);
+  }
+
+  private load(): SummaryState {
+    try {
+      return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+    } catch {
+      return { lastDueAt: null, last: null };
+    }
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.state, null, 2));
+    } catch (error: any) {
+      console.error('[Notify] Failed to save daily summary state:', error.message);
+    }
+  }
+}
@@ -0,0 +1,197 @@
+import { execFileSync, spawn } from 'child_process';
+import { EventEmitter } from 'events';
+import * as fs from 'fs';
+import * as path from 'path';
+import { KuboManager } from './kubo';
+import { ConfigStore } from './config';
+import { DATASTORE_PROFILES, DatastoreType } from './settings';
+
+export interface DatastoreMigrationProgress {
+  done: number;
+  total: number;
+  cid?: string;
+}
+
+export interface DatastoreMigrationResult {
+  success: boolean;
+  from: DatastoreType | null;
+  to: DatastoreType;
+  migratedPins: number;
+  // Old repo, kept until the user deletes it
+  backupPath?: string;
+  error?: string;
+}
+
+/**
+ * Reads which datastore an existing repo was initialised with.
+ */
+export function detectDatastore(repoPath: string): DatastoreType | null {
+  try {
+    const spec = fs.readFileSync(path.join(repoPath, 'datastore_spec'), 'utf-8');
+    if (spec.includes('badgerds')) return 'badger';
+    if (spec.includes('flatfs')) return 'flatfs';
+  } catch {
+    // No repo yet
+  }
+  return null;
+}
+
+function ipfs(binary: string, repoPath: string, args: string[]): string {
+  return execFileSync(binary, args, {
+    env: { ...process.env, IPFS_PATH: repoPath },
+    encoding: 'utf-8',
+    maxBuffer: 64 * 1024 * 1024,
+  });
+}
+
+// Streams one DAG from the old repo into the new one without holding it in memory
+function copyDag(binary: string, fromRepo: string, toRepo: string, cid: string): Promise<void> {
+  return new Promise((resolve, reject) => {
+    const exporter = spawn(binary, ['dag', 'export', cid], {
+      env: { ...process.env, IPFS_PATH: fromRepo },
+      stdio: ['ignore', 'pipe', 'pipe'],
+    });
+    const importer = spawn(binary, ['dag', 'import', '--pin-roots=true', '--stats=false'], {
+      env: { ...process.env, IPFS_PATH: toRepo },
+      stdio: ['pipe', 'ignore', 'pipe'],
+    });
+
+    let stderr = '';
+    exporter.stderr?.on('data', (data) => { stderr += data.toString(); });
+    importer.stderr?.on('data', (data) => { stderr += data.toString(); });
+    exporter.stdout!.pipe(importer.stdin!);
+
+    let exportCode: number | null = null;
+    exporter.on('exit', (code) => { exportCode = code; });
+    exporter.on('error', reject);
+    importer.on('error', reject);
+    importer.on('exit', (code) => {
+      if (code === 0 && exportCode === 0) {
+        resolve();
+      } else {
+        reject(new Error(`Failed to copy ${cid}: ${stderr.trim() || `exit code ${code}`}`));
+      }
+    });
+  });
+}
+
+/**
+ * Moves a repo between flatfs and badger. Kubo can't convert a datastore in
+ * place, so a fresh repo is initialised with the target profile, given the
+ * old identity and config, and every recursive pin is copied across with
+ * `dag export | dag import`. Unpinned cached blocks are not carried over.
+ * The old repo is only renamed aside once every pin has been copied.
+ */
+export class DatastoreMigrator extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private migrating = false;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  async migrate(target: DatastoreType): Promise<DatastoreMigrationResult> {
+    if (this.kubo.isExternal()) {
+      throw new Error('The datastore of an external node is managed outside the agent');
+    }
+    if (this.migrating) {
+      throw new Error('Datastore migration already in progress');
+    }
+    this.migrating = true;
+
+    const repoPath = this.kubo.getRepoPath();
+    const tempPath = `${repoPath}.migrating`;
+    const from = detectDatastore(repoPath);
+    const result: DatastoreMigrationResult = { success: false, from, to: target, migratedPins: 0 };
+
+    try {
+      if (from === target) {
+        return { ...result, success: true };
+      }
+      if (from === null) {
+        // Nothing to migrate; the next init will use the new datastore
+        this.saveDatastore(target);
+        return { ...result, success: true };
+      }
+
+      const binary = await this.kubo.ensureBinary();
+      const wasRunning = this.kubo.isRunning();
+      if (wasRunning) {
+        this.emitStage('Stopping IPFS daemon...');
+        await this.kubo.stop();
+      }
+
+      try {
+        await this.checkFreeSpace(binary, repoPath);
+
+        const pins = ipfs(binary, repoPath, ['pin', 'ls', '--type=recursive', '--quiet'])
+          .split('\n')
+          .map((line) => line.trim())
+          .filter(Boolean);
+
+        this.emitStage(`Creating ${target} repository...`);
+        fs.rmSync(tempPath, { recursive: true, force: true });
+        fs.mkdirSync(tempPath, { recursive: true });
+        ipfs(binary, tempPath, ['init', '--empty-repo', `--profile=${DATASTORE_PROFILES[target]}`]);
+
+        // Keep the peer identity and all other settings, only swap the spec
+        const oldConfig = JSON.parse(fs.readFileSync(path.join(repoPath, 'config'), 'utf-8'));
+        const newConfig = JSON.parse(fs.readFileSync(path.join(tempPath, 'config'), 'utf-8'));
+        oldConfig.Datastore = { ...oldConfig.Datastore, Spec: newConfig.Datastore.Spec };
+        fs.writeFileSync(path.join(tempPath, 'config'), JSON.stringify(oldConfig, null, 2));
+
+        this.emitStage(`Copying ${pins.length} pinned items...`);
+        for (let i = 0; i < pins.length; i++) {
+          this.emit('progress', { done: i, total: pins.length, cid: pins[i] } as DatastoreMigrationProgress);
+          await copyDag(binary, repoPath, tempPath, pins[i]);
+        }
+        this.emit('progress', { done: pins.length, total: pins.length } as DatastoreMigrationProgress);
+
+        const backupPath = `${repoPath}.${from}-backup-${Date.now()}`;
+        fs.renameSync(repoPath, backupPath);
+        fs.renameSync(tempPath, repoPath);
+        this.saveDatastore(target);
+
+        result.success = true;
+        result.migratedPins = pins.length;
+        result.backupPath = backupPath;
+        console.log(`[Kubo] Migrated datastore from ${from} to ${target}, old repo kept at ${backupPath}`);
+      } catch (error: any) {
+        console.error('[Kubo] Datastore migration failed:', error.message);
+        fs.rmSync(tempPath, { recursive: true, force: true });
+        result.error = error.message;
+      }
+
+      if (wasRunning) {
+        this.emitStage('Starting IPFS daemon...');
+        await this.kubo.start();
+      }
+      return result;
+    } finally {
+      this.migrating = false;
+    }
+  }
+
+  private saveDatastore(datastore: DatastoreType): void {
+    this.config.setConfig({ kubo: { ...this.config.getConfig().kubo, datastore } });
+  }
+
+  // The old repo stays on disk until the copy is done, so we need room for both
+  private async checkFreeSpace(binary: string, repoPath: string): Promise<void> {
+    const repoSize = parseInt(ipfs(binary, repoPath, ['repo', 'stat', '--size-only', '--human=false'])
+      .match(/RepoSize:\s*(\d+)/)?.[1] || '0', 10);
+    const stats = await fs.promises.statfs(path.dirname(repoPath));
+    const free = stats.bavail * stats.bsize;
+    if (free < repoSize) {
+      throw new Error(`Migration needs about ${(repoSize / 1024 / 1024 / 1024).toFixed(1)} GB free next to the repo`);
+    }
+  }
+
+  private emitStage(message: string): void {
+    console.log(`[Kubo] ${message}`);
+    this.emit('status', message);
+  }
+}
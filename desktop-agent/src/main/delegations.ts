@@ -0,0 +1,162 @@
+import { Operation } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+import { HiveAccount } from './hive';
+import { HoneycombClient } from './honeycomb';
+import { TransactionQueue, QueuedTransactionResult } from './tx-queue';
+import { fromMilli } from './eligibility';
+
+export type DelegationToken = 'LARYNX' | 'SPK';
+const TOKENS: DelegationToken[] = ['LARYNX', 'SPK'];
+
+// Where honeycomb keeps each token's power and delegations on an account
+const FIELDS: Record<DelegationToken, { power: string; granting: string; granted: string }> = {
+  LARYNX: { power: 'poweredUp', granting: 'granting', granted: 'granted' },
+  SPK: { power: 'spk_power', granting: 'spk_granting', granted: 'spk_granted' },
+};
+
+export interface DelegationSettings {
+  // Honeycomb ops that set a delegation; SPK testnet ops carry the spkccT_ prefix
+  larynxGrantId: string;
+  spkGrantId: string;
+}
+
+export const DEFAULT_DELEGATION_SETTINGS: DelegationSettings = {
+  larynxGrantId: 'spkccT_power_grant',
+  spkGrantId: 'spkccT_spk_power_grant',
+};
+
+export function validateDelegationSettings(settings: Partial<DelegationSettings>): string[] {
+  const errors: string[] = [];
+  for (const key of ['larynxGrantId', 'spkGrantId'] as const) {
+    if (settings[key] !== undefined && !/^[a-zA-Z0-9_.-]{1,32}$/.test(settings[key]!)) {
+      errors.push(`${key} must be 1-32 letters, digits, dots, dashes or underscores`);
+    }
+  }
+  return errors;
+}
+
+export interface Delegation {
+  account: string;
+  amount: number;
+}
+
+export interface TokenDelegations {
+  token: DelegationToken;
+  // Powered up by the account itself, delegations out included
+  power: number;
+  // What is left to delegate
+  available: number;
+  outgoing: Delegation[];
+  incoming: Delegation[];
+}
+
+export interface DelegationRequest {
+  token: DelegationToken;
+  to: string;
+  // Whole tokens; 0 removes the delegation
+  amount: number;
+}
+
+export interface DelegationPreview {
+  operation: Operation;
+  // Why broadcasting this would not work; empty when it's ready to go
+  issues: string[];
+}
+
+// Honeycomb keeps the total under "t" next to the per-account amounts
+function toDelegations(map: unknown): Delegation[] {
+  if (!map || typeof map !== 'object') return [];
+  return Object.entries(map as Record<string, unknown>)
+    .filter(([account]) => account !== 't')
+    .map(([account, amount]) => ({ account, amount: fromMilli(amount) }))
+    .filter((delegation) => delegation.amount > 0)
+    .sort((a, b) => b.amount - a.amount);
+}
+
+/**
+ * LARYNX and SPK power delegations for the active account, so stake can be
+ * lent to (or borrowed for) the node without leaving the agent for a wallet
+ * site. Delegating is a honeycomb custom_json with active authority; it
+ * goes through the transaction queue and so through whichever signing
+ * method is set up, Keychain included. Setting an amount replaces the
+ * existing delegation to that account, and 0 removes it.
+ */
+export class DelegationManager {
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private honeycomb: HoneycombClient;
+  private queue: TransactionQueue;
+
+  constructor(config: ConfigStore, account: HiveAccount, honeycomb: HoneycombClient, queue: TransactionQueue) {
+    this.config = config;
+    this.account = account;
+    this.honeycomb = honeycomb;
+    this.queue = queue;
+  }
+
+  async list(force = false): Promise<TokenDelegations[]> {
+    const { hiveUsername } = this.config.getConfig();
+    if (!hiveUsername) throw new Error('Log in with a Hive account first');
+    const data = await this.honeycomb.getAccount(hiveUsername, force);
+    return TOKENS.map((token) => {
+      const fields = FIELDS[token];
+      const outgoing = toDelegations(data[fields.granting]);
+      const power = fromMilli(data[fields.power]);
+      return {
+        token,
+        power,
+        available: Math.max(0, power - outgoing.reduce((sum, delegation) => sum + delegation.amount, 0)),
+        outgoing,
+        incoming: toDelegations(data[fields.granted]),
+      };
+    });
+  }
+
+  async preview(request: DelegationRequest): Promise<DelegationPreview> {
+    const { hiveUsername, delegations: settings } = this.config.getConfig();
+    const to = String(request.to ?? '').trim().toLowerCase().replace(/^@/, '');
+    const issues: string[] = [];
+    if (!hiveUsername) issues.push('Log in with a Hive account first');
+    if (!TOKENS.includes(request.token)) issues.push(`token must be one of: ${TOKENS.join(', ')}`);
+    if (!/^[a-z][a-z0-9.-]{2,15}$/.test(to)) issues.push('That is not a valid Hive username');
+    else if (to === hiveUsername) issues.push('An account cannot delegate to itself');
+    if (!Number.isFinite(request.amount) || request.amount < 0) issues.push('amount must be zero or more');
+
+    if (issues.length === 0) {
+      const [recipient] = await this.account.getClient().database.getAccounts([to]);
+      if (!recipient) issues.push(`@${to} does not exist on Hive`);
+      const current = (await this.list(true)).find((entry) => entry.token === request.token)!;
+      const existing = current.outgoing.find((delegation) => delegation.account === to)?.amount ?? 0;
+      // The current delegation to this account is freed by replacing it
+      if (request.amount > current.available + existing) {
+        issues.push(`Only ${(current.available + existing).toFixed(3)} ${request.token} power can be delegated to @${to}`);
+      }
+      if (request.amount === 0 && existing === 0) issues.push(`Nothing is delegated to @${to} to remove`);
+    }
+    if (this.config.getConfig().hiveSigning.method === 'keys' && !this.account.getKey('active')) {
+      issues.push('Delegating needs the active key; store it at login, or sign with Hive Keychain or HAS');
+    }
+
+    return {
+      operation: ['custom_json', {
+        required_auths: hiveUsername ? [hiveUsername] : [],
+        required_posting_auths: [],
+        id: request.token === 'SPK' ? settings.spkGrantId : settings.larynxGrantId,
+        json: JSON.stringify({ to, amount: Math.round((request.amount || 0) * 1000) }),
+      }],
+      issues,
+    };
+  }
+
+  async delegate(request: DelegationRequest): Promise<QueuedTransactionResult> {
+    const { operation, issues } = await this.preview(request);
+    if (issues.length > 0) throw new Error(issues.join('; '));
+    const to = JSON.parse((operation[1] as { json: string }).json).to;
+    const description = request.amount === 0
+      ? `Remove ${request.token} power delegation to @${to}`
+      : `Delegate ${request.amount} ${request.token} power to @${to}`;
+    const result = await this.queue.submit({ source: 'delegation', description, operations: [operation], role: 'active' });
+    console.log(`[SPK] ${description}: ${result.txId}`);
+    return result;
+  }
+}
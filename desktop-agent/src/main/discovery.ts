@@ -0,0 +1,122 @@
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { Bonjour, Service } from 'bonjour-service';
+import { API_PREFIX, API_VERSION } from './openapi';
+
+export interface DiscoverySettings {
+  enabled: boolean;
+}
+
+export const DEFAULT_DISCOVERY_SETTINGS: DiscoverySettings = {
+  enabled: true,
+};
+
+// Browsed as _spkagent._tcp.local
+const SERVICE_TYPE = 'spkagent';
+
+export interface Advertisement {
+  port: number;
+  httpsPort: number | null;
+  peerId: string | null;
+}
+
+// Local tools read this instead of assuming the API got its configured port
+const DISCOVERY_FILE = 'agent-api.json';
+
+export interface DiscoveryFile {
+  pid: number;
+  host: string;
+  port: number;
+  httpsPort: number | null;
+  grpcPort: number | null;
+  url: string;
+  path: string;
+  version: string;
+  updatedAt: string;
+}
+
+export function getDiscoveryFilePath(dataDir: string): string {
+  return path.join(dataDir, DISCOVERY_FILE);
+}
+
+export function writeDiscoveryFile(dataDir: string, endpoint: { host: string; port: number; httpsPort: number | null; grpcPort: number | null }): void {
+  // Wildcard binds still answer on loopback, which is what local tools want
+  const host = ['0.0.0.0', '::'].includes(endpoint.host) ? '127.0.0.1' : endpoint.host;
+  const contents: DiscoveryFile = {
+    pid: process.pid,
+    host,
+    port: endpoint.port,
+    httpsPort: endpoint.httpsPort,
+    grpcPort: endpoint.grpcPort,
+    url: `http://${host.includes(':') ? `[${host}]` : host}:${endpoint.port}`,
+    path: API_PREFIX,
+    version: API_VERSION,
+    updatedAt: new Date().toISOString(),
+  };
+  try {
+    fs.writeFileSync(getDiscoveryFilePath(dataDir), JSON.stringify(contents, null, 2));
+  } catch (error) {
+    console.error('[Discovery] Failed to write discovery file:', error);
+  }
+}
+
+export function removeDiscoveryFile(dataDir: string): void {
+  try {
+    fs.rmSync(getDiscoveryFilePath(dataDir), { force: true });
+  } catch (error) {
+    console.error('[Discovery] Failed to remove discovery file:', error);
+  }
+}
+
+/**
+ * Announces the agent API over mDNS so companion apps on the LAN (or on
+ * this machine, when the port had to move) can find it without assuming
+ * localhost:5111. TXT records carry what a client needs to connect.
+ */
+export class AgentAdvertiser {
+  private bonjour: Bonjour | null = null;
+  private service: Service | null = null;
+  private current: Advertisement | null = null;
+
+  isAdvertising(): boolean {
+    return this.service !== null;
+  }
+
+  /**
+   * Publishes the service, or republishes it if anything in it changed.
+   */
+  advertise(advertisement: Advertisement): void {
+    if (this.service && JSON.stringify(advertisement) === JSON.stringify(this.current)) return;
+    this.stop();
+
+    this.bonjour = new Bonjour();
+    this.current = advertisement;
+    this.service = this.bonjour.publish({
+      name: `SPK Agent on ${os.hostname()}`,
+      type: SERVICE_TYPE,
+      protocol: 'tcp',
+      port: advertisement.port,
+      txt: {
+        v: API_VERSION,
+        path: API_PREFIX,
+        ...(advertisement.httpsPort ? { https: String(advertisement.httpsPort) } : {}),
+        ...(advertisement.peerId ? { peer: advertisement.peerId } : {}),
+      },
+    });
+    this.service.on('error', (error: Error) => {
+      console.error('[Discovery] mDNS advertisement failed:', error.message);
+    });
+    console.log(`[Discovery] Advertising _${SERVICE_TYPE}._tcp on port ${advertisement.port}`);
+  }
+
+  stop(): void {
+    if (!this.bonjour) return;
+    const bonjour = this.bonjour;
+    this.bonjour = null;
+    this.service = null;
+    this.current = null;
+    // Sends goodbye packets so browsers drop us right away
+    bonjour.unpublishAll(() => bonjour.destroy());
+  }
+}
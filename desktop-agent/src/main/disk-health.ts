@@ -0,0 +1,289 @@
+import { EventEmitter } from 'events';
+import { execFile } from 'child_process';
+import * as path from 'path';
+import { ConfigStore } from './config';
+import { KuboPool } from './kubo-pool';
+import { KuboManager } from './kubo';
+
+const HOUR_MS = 60 * 60 * 1000;
+const COMMAND_TIMEOUT_MS = 30000;
+// ATA attributes whose raw count climbs before a drive dies
+const PREFAIL_ATTRIBUTES: Record<number, string> = {
+  5: 'reallocated sectors',
+  187: 'reported uncorrectable errors',
+  197: 'sectors pending reallocation',
+  198: 'offline uncorrectable sectors',
+};
+// Where smartmontools installs when it isn't on the PATH the app was started with
+const SMARTCTL_CANDIDATES: Record<string, string[]> = {
+  linux: ['smartctl', '/usr/sbin/smartctl', '/usr/local/sbin/smartctl'],
+  darwin: ['smartctl', '/usr/local/sbin/smartctl', '/opt/homebrew/sbin/smartctl', '/opt/homebrew/bin/smartctl'],
+  win32: ['smartctl', 'C:\\Program Files\\smartmontools\\bin\\smartctl.exe'],
+};
+
+export interface DiskHealthSettings {
+  enabled: boolean;
+  intervalHours: number;
+}
+
+export const DEFAULT_DISK_HEALTH_SETTINGS: DiskHealthSettings = {
+  enabled: true,
+  intervalHours: 6,
+};
+
+export function validateDiskHealthSettings(settings: Partial<DiskHealthSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.enabled !== undefined && typeof settings.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  if (settings.intervalHours !== undefined
+    && (!Number.isInteger(settings.intervalHours) || settings.intervalHours < 1 || settings.intervalHours > 168)) {
+    errors.push('intervalHours must be between 1 and 168');
+  }
+  return errors;
+}
+
+// 'unknown' when the platform, the device or missing permissions keep SMART out of reach
+export type DiskHealthState = 'healthy' | 'prefail' | 'unknown';
+
+export interface DiskHealthReport {
+  instance: string;
+  repoPath: string;
+  device: string | null;
+  model: string | null;
+  state: DiskHealthState;
+  // What reported it: smartctl, diskutil or Windows storage health
+  source: 'smartctl' | 'diskutil' | 'windows' | null;
+  // Pre-failure signs found, e.g. '8 reallocated sectors'
+  indicators: string[];
+  // Why the state is unknown
+  reason: string | null;
+  checkedAt: string;
+}
+
+interface CommandResult {
+  code: number;
+  stdout: string;
+  missing: boolean;
+}
+
+function run(command: string, args: string[]): Promise<CommandResult> {
+  return new Promise((resolve) => {
+    execFile(command, args, { timeout: COMMAND_TIMEOUT_MS, windowsHide: true }, (error: any, stdout) => {
+      resolve({
+        code: error ? (typeof error.code === 'number' ? error.code : -1) : 0,
+        stdout: String(stdout || ''),
+        missing: error?.code === 'ENOENT',
+      });
+    });
+  });
+}
+
+/**
+ * The whole disk behind a path, in the form smartctl expects: the parent
+ * of the partition on Linux, diskN on macOS, the drive letter on Windows.
+ */
+async function resolveDevice(repoPath: string): Promise<string | null> {
+  if (process.platform === 'win32') {
+    const root = path.parse(path.resolve(repoPath)).root;
+    return /^[a-zA-Z]:/.test(root) ? root.slice(0, 2).toUpperCase() : null;
+  }
+
+  const df = process.platform === 'linux'
+    ? await run('df', ['--output=source', repoPath])
+    : await run('df', [repoPath]);
+  if (df.code !== 0) return null;
+  const lines = df.stdout.trim().split('\n');
+  const source = lines[lines.length - 1]?.trim().split(/\s+/)[0] || '';
+  if (!source.startsWith('/dev/')) return null;
+
+  if (process.platform === 'darwin') {
+    return source.match(/^\/dev\/disk\d+/)?.[0] ?? source;
+  }
+  const parent = await run('lsblk', ['-no', 'pkname', source]);
+  const name = parent.code === 0 ? parent.stdout.trim().split('\n')[0]?.trim() : '';
+  return name ? `/dev/${name}` : source;
+}
+
+/**
+ * Pre-failure signs in `smartctl --json` output: a failed overall
+ * health check, a climbing ATA prefail counter, or an NVMe critical
+ * warning, media errors or spare capacity below its threshold.
+ */
+export function smartIndicators(smart: any): string[] {
+  const indicators: string[] = [];
+  if (smart?.smart_status?.passed === false) indicators.push('SMART overall health check failed');
+
+  const attributes: any[] = Array.isArray(smart?.ata_smart_attributes?.table) ? smart.ata_smart_attributes.table : [];
+  for (const attribute of attributes) {
+    const raw = Number(attribute?.raw?.value);
+    const label = PREFAIL_ATTRIBUTES[attribute?.id];
+    if (label && raw > 0) indicators.push(`${raw} ${label}`);
+    else if (attribute?.when_failed === 'now') indicators.push(`${attribute.name} below its failure threshold`);
+  }
+
+  const nvme = smart?.nvme_smart_health_information_log;
+  if (nvme) {
+    if (nvme.critical_warning) indicators.push(`NVMe critical warning 0x${Number(nvme.critical_warning).toString(16)}`);
+    if (nvme.media_errors > 0) indicators.push(`${nvme.media_errors} NVMe media errors`);
+    if (typeof nvme.available_spare === 'number' && typeof nvme.available_spare_threshold === 'number'
+      && nvme.available_spare < nvme.available_spare_threshold) {
+      indicators.push(`spare capacity at ${nvme.available_spare}%, below the ${nvme.available_spare_threshold}% threshold`);
+    }
+  }
+  return indicators;
+}
+
+/**
+ * Checks SMART health of the disk under each repo every few hours. A
+ * dead disk takes every stored CID with it and every challenge after
+ * that fails, so pre-failure signs are the moment to move the repo, not
+ * after the daemon starts erroring. Uses smartctl where installed, and
+ * falls back to what the OS exposes (diskutil on macOS, the storage
+ * health status on Windows). Reading SMART often needs elevated rights;
+ * when it can't be read the report says why instead of claiming health.
+ */
+export class DiskHealthMonitor extends EventEmitter {
+  private pool: KuboPool;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private reports = new Map<string, DiskHealthReport>();
+  private smartctl: string | null | undefined;
+
+  constructor(pool: KuboPool, config: ConfigStore) {
+    super();
+    this.pool = pool;
+    this.config = config;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    // Re-read each hour so a changed interval applies without a restart
+    this.timer = setInterval(() => {
+      const last = Math.min(...Array.from(this.reports.values()).map((report) => new Date(report.checkedAt).getTime()));
+      const due = this.reports.size === 0 || Date.now() - last >= this.config.getConfig().diskHealth.intervalHours * HOUR_MS;
+      if (due && this.config.getConfig().diskHealth.enabled) {
+        this.check().catch((error) => console.error('[Storage] Disk health check failed:', error.message));
+      }
+    }, HOUR_MS);
+    if (this.config.getConfig().diskHealth.enabled) {
+      this.check().catch((error) => console.error('[Storage] Disk health check failed:', error.message));
+    }
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getReports(): DiskHealthReport[] {
+    return Array.from(this.reports.values());
+  }
+
+  // Reports of disks showing pre-failure signs
+  getFailing(): DiskHealthReport[] {
+    return this.getReports().filter((report) => report.state === 'prefail');
+  }
+
+  async check(): Promise<DiskHealthReport[]> {
+    const instances = this.pool.getInstances().filter((kubo) => !kubo.isExternal());
+    const reports = await Promise.all(instances.map((kubo) => this.checkInstance(kubo)));
+
+    const before = JSON.stringify(this.getReports().map(({ checkedAt, ...rest }) => rest));
+    this.reports = new Map(reports.map((report) => [report.instance, report]));
+    for (const report of reports) {
+      if (report.state === 'prefail') {
+        console.warn(`[Storage] Disk ${report.device} under ${report.repoPath} shows pre-failure signs: ${report.indicators.join(', ')}`);
+      }
+    }
+    if (JSON.stringify(reports.map(({ checkedAt, ...rest }) => rest)) !== before) this.emit('changed', reports);
+    return reports;
+  }
+
+  private async checkInstance(kubo: KuboManager): Promise<DiskHealthReport> {
+    const repoPath = kubo.getRepoPath();
+    const report: DiskHealthReport = {
+      instance: kubo.getInstanceId(),
+      repoPath,
+      device: null,
+      model: null,
+      state: 'unknown',
+      source: null,
+      indicators: [],
+      reason: null,
+      checkedAt: new Date().toISOString(),
+    };
+
+    report.device = await resolveDevice(repoPath);
+    if (!report.device) {
+      report.reason = 'Could not find the disk holding the repo';
+      return report;
+    }
+
+    const smartctl = await this.findSmartctl();
+    if (smartctl) {
+      const result = await run(smartctl, ['--json', '-H', '-A', '-i', report.device]);
+      // Bits 0-1 of the exit status mean smartctl couldn't talk to the device at all
+      let smart: any = null;
+      try {
+        smart = JSON.parse(result.stdout);
+      } catch {
+        // Not JSON: a very old smartctl
+      }
+      if (smart && (result.code & 0x3) === 0) {
+        report.source = 'smartctl';
+        report.model = smart.model_name ?? null;
+        report.indicators = smartIndicators(smart);
+        report.state = report.indicators.length > 0 ? 'prefail' : 'healthy';
+        return report;
+      }
+      const message = smart?.smartctl?.messages?.[0]?.string;
+      report.reason = message ? `smartctl: ${message}` : 'smartctl could not read the disk';
+    }
+
+    const fallback = await this.osHealth(report.device);
+    if (fallback) {
+      report.source = fallback.source;
+      report.reason = null;
+      report.indicators = fallback.indicators;
+      report.state = fallback.indicators.length > 0 ? 'prefail' : 'healthy';
+      return report;
+    }
+    if (!report.reason) {
+      report.reason = 'SMART is not available; install smartmontools to check this disk';
+    }
+    return report;
+  }
+
+  private async findSmartctl(): Promise<string | null> {
+    if (this.smartctl !== undefined) return this.smartctl;
+    this.smartctl = null;
+    for (const candidate of SMARTCTL_CANDIDATES[process.platform] || []) {
+      if (!(await run(candidate, ['--version'])).missing) {
+        this.smartctl = candidate;
+        break;
+      }
+    }
+    return this.smartctl;
+  }
+
+  // The coarse pass/fail some platforms expose without smartctl
+  private async osHealth(device: string): Promise<{ source: 'diskutil' | 'windows'; indicators: string[] } | null> {
+    if (process.platform === 'darwin') {
+      const result = await run('diskutil', ['info', device]);
+      const status = result.stdout.match(/SMART Status:\s*(.+)/)?.[1]?.trim();
+      if (result.code !== 0 || !status || /not supported/i.test(status)) return null;
+      return { source: 'diskutil', indicators: /verified/i.test(status) ? [] : [`SMART status ${status}`] };
+    }
+    if (process.platform === 'win32') {
+      const letter = device.slice(0, 1);
+      const result = await run('powershell', ['-NoProfile', '-Command',
+        `$n = (Get-Partition -DriveLetter ${letter} | Get-Disk).Number; `
+        + '(Get-PhysicalDisk | Where-Object DeviceId -eq $n).HealthStatus']);
+      const status = result.stdout.trim();
+      if (result.code !== 0 || !status) return null;
+      return { source: 'windows', indicators: status === 'Healthy' ? [] : [`Windows reports the disk as ${status}`] };
+    }
+    return null;
+  }
+}
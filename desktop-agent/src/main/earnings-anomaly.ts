@@ -0,0 +1,223 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { ConfigStore } from './config';
+import { ConnectivityMonitor } from './connectivity';
+import { UptimeTracker } from './uptime';
+import { showNotification } from './notifications';
+
+const DAY_MS = 24 * 60 * 60 * 1000;
+const CHECK_INTERVAL_MS = 60 * 60 * 1000;
+// The last week against the four weeks before it
+const RECENT_MS = 7 * DAY_MS;
+const BASELINE_WEEKS = 4;
+// Fewer challenges a week than this in the baseline is too little to compare against
+const MIN_BASELINE_CHALLENGES = 20;
+// A drop still going on is announced again after this
+const REALERT_MS = DAY_MS;
+
+export interface AnomalySettings {
+  enabled: boolean;
+  // Percent earnings may fall below the baseline before it is flagged
+  dropPercent: number;
+  // Percentage points the pass rate may fall
+  passRateDropPoints: number;
+}
+
+export const DEFAULT_ANOMALY_SETTINGS: AnomalySettings = {
+  enabled: true,
+  dropPercent: 50,
+  passRateDropPoints: 20,
+};
+
+export function validateAnomalySettings(settings: Partial<AnomalySettings>): string[] {
+  const errors: string[] = [];
+  if (settings.enabled !== undefined && typeof settings.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  if (settings.dropPercent !== undefined
+    && (!Number.isFinite(settings.dropPercent) || settings.dropPercent < 10 || settings.dropPercent > 100)) {
+    errors.push('dropPercent must be between 10 and 100');
+  }
+  if (settings.passRateDropPoints !== undefined
+    && (!Number.isFinite(settings.passRateDropPoints) || settings.passRateDropPoints < 5 || settings.passRateDropPoints > 100)) {
+    errors.push('passRateDropPoints must be between 5 and 100');
+  }
+  return errors;
+}
+
+export interface WeekFigures {
+  challenges: number;
+  passRate: number | null;
+  hbd: number;
+}
+
+export interface EarningsAnomaly {
+  // Null while there's too little history to compare against
+  baseline: WeekFigures | null;
+  recent: WeekFigures;
+  // How far earnings fell against the baseline, in percent; negative is a rise
+  earningsDropPercent: number | null;
+  passRateDropPoints: number | null;
+  anomalous: boolean;
+  // Likely causes, most telling first
+  causes: string[];
+  message: string | null;
+  checkedAt: string;
+}
+
+interface AnomalyState {
+  lastAlertAt: string | null;
+}
+
+function sinceLabel(at: string): string {
+  const date = new Date(at);
+  return Date.now() - date.getTime() < 6 * DAY_MS
+    ? date.toLocaleDateString(undefined, { weekday: 'long' })
+    : date.toLocaleDateString();
+}
+
+/**
+ * Watches for earnings falling away from what the node usually makes: the
+ * last 7 days of challenges from earnings.db against the weekly average of
+ * the 4 weeks before. When HBD earned or the pass rate dropped past the
+ * thresholds it sends a notification naming what it can see of the cause
+ * (the daemon down, the node unreachable, no challenges coming in) and
+ * opens the connectivity diagnostics when clicked. A drop that goes on is
+ * announced again once a day, not every hour.
+ */
+export class EarningsAnomalyDetector {
+  private config: ConfigStore;
+  private uptime: UptimeTracker;
+  private connectivity: ConnectivityMonitor;
+  private openDiagnostics: () => void;
+  private filePath: string;
+  private state: AnomalyState;
+  private last: EarningsAnomaly | null = null;
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(dataDir: string, config: ConfigStore, uptime: UptimeTracker, connectivity: ConnectivityMonitor,
+    openDiagnostics: () => void) {
+    this.config = config;
+    this.uptime = uptime;
+    this.connectivity = connectivity;
+    this.openDiagnostics = openDiagnostics;
+    this.filePath = path.join(dataDir, 'earnings-anomaly.json');
+    this.state = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.check(), CHECK_INTERVAL_MS);
+    this.check();
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getLast(): EarningsAnomaly {
+    return this.last ?? this.evaluate();
+  }
+
+  check(): EarningsAnomaly {
+    const anomaly = this.evaluate();
+    this.last = anomaly;
+    const settings = this.config.getConfig().anomaly;
+    if (!anomaly.anomalous) {
+      if (this.state.lastAlertAt) {
+        this.state.lastAlertAt = null;
+        this.save();
+      }
+      return anomaly;
+    }
+    if (!settings.enabled) return anomaly;
+    if (this.state.lastAlertAt && Date.now() - Date.parse(this.state.lastAlertAt) < REALERT_MS) return anomaly;
+
+    console.warn(`[PoA] ${anomaly.message}`);
+    this.state.lastAlertAt = anomaly.checkedAt;
+    this.save();
+    showNotification('Earnings dropped', `${anomaly.message} Click to open the connectivity diagnostics.`,
+      { kind: 'challenges', account: null })?.on('click', () => this.openDiagnostics());
+    return anomaly;
+  }
+
+  private evaluate(): EarningsAnomaly {
+    const settings = this.config.getConfig().anomaly;
+    const store = this.config.getEarningsStore();
+    const now = Date.now();
+    const week = (from: number, to: number, weeks = 1): WeekFigures => {
+      const totals = store.summarize(new Date(from).toISOString(), new Date(to).toISOString());
+      return {
+        challenges: totals.challenges / weeks,
+        passRate: totals.challenges > 0 ? totals.passed / totals.challenges : null,
+        hbd: (totals.amounts.HBD ?? 0) / weeks,
+      };
+    };
+    const recent = week(now - RECENT_MS, now);
+    const baselineFigures = week(now - RECENT_MS * (BASELINE_WEEKS + 1), now - RECENT_MS, BASELINE_WEEKS);
+    const baseline = baselineFigures.challenges >= MIN_BASELINE_CHALLENGES ? baselineFigures : null;
+
+    const earningsDropPercent = baseline && baseline.hbd > 0 ? (1 - recent.hbd / baseline.hbd) * 100 : null;
+    // No challenges at all this week says nothing about the pass rate; the earnings drop covers it
+    const passRateDropPoints = baseline && baseline.passRate !== null && recent.passRate !== null
+      ? (baseline.passRate - recent.passRate) * 100
+      : null;
+    const anomalous = (earningsDropPercent !== null && earningsDropPercent >= settings.dropPercent)
+      || (passRateDropPoints !== null && passRateDropPoints >= settings.passRateDropPoints);
+
+    const causes = anomalous ? this.causes(recent) : [];
+    const headline = earningsDropPercent !== null && earningsDropPercent >= settings.dropPercent
+      ? `Earnings down ${Math.round(earningsDropPercent)}% against the last ${BASELINE_WEEKS} weeks`
+      : `Pass rate down ${Math.round(passRateDropPoints ?? 0)} points against the last ${BASELINE_WEEKS} weeks`;
+    return {
+      baseline,
+      recent,
+      earningsDropPercent,
+      passRateDropPoints,
+      anomalous,
+      causes,
+      message: anomalous ? `${headline}${causes.length > 0 ? ` - ${causes[0]}?` : '.'}` : null,
+      checkedAt: new Date(now).toISOString(),
+    };
+  }
+
+  private causes(recent: WeekFigures): string[] {
+    const causes: string[] = [];
+    const uptime = this.uptime.getStats();
+    if (!uptime.online && uptime.offlineSince) {
+      causes.push(`IPFS daemon down since ${sinceLabel(uptime.offlineSince)}`);
+    }
+    const report = this.connectivity.getReport();
+    if (report?.reachability === 'Unreachable') {
+      causes.push(`node unreachable${report.hint ? `: ${report.hint}` : ''}`);
+    }
+    const { lastChallengeTime } = this.config.getEarnings();
+    if (recent.challenges === 0) {
+      causes.push(lastChallengeTime ? `no challenges since ${sinceLabel(lastChallengeTime)}` : 'no challenges this week');
+    } else if (recent.passRate !== null && recent.passRate < 0.5) {
+      causes.push('most challenges failing; see the challenge diagnostics');
+    }
+    const weekWindow = uptime.windows.find((window) => window.days === 7);
+    if (weekWindow && weekWindow.availability < 0.8) {
+      causes.push(`online only ${(weekWindow.availability * 100).toFixed(0)}% of the last week`);
+    }
+    return causes;
+  }
+
+  private load(): AnomalyState {
+    try {
+      return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+    } catch {
+      return { lastAlertAt: null };
+    }
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.state, null, 2));
+    } catch (error: any) {
+      console.error('[PoA] Failed to save earnings anomaly state:', error.message);
+    }
+  }
+}
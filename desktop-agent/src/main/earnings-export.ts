@@ -0,0 +1,162 @@
+import * as fs from 'fs';
+import { once } from 'events';
+import { ConfigStore } from './config';
+import { ClaimRecord, RewardClaimer } from './reward-claims';
+
+export type EarningsExportFormat = 'csv' | 'json';
+
+export interface EarningsExportQuery {
+  format?: EarningsExportFormat;
+  // ISO timestamps or YYYY-MM-DD; `to` is exclusive
+  from?: string;
+  to?: string;
+}
+
+// One line of the ledger: a challenge reward booked, or a reward claim paid out on-chain
+export interface LedgerRow {
+  timestamp: string;
+  kind: 'challenge' | 'claim';
+  token: string;
+  amount: number;
+  fiatValue: number | null;
+  fiatCurrency: string | null;
+  // The challenge salt, or the claim's transaction ID
+  reference: string | null;
+  cid: string | null;
+  account: string | null;
+  // provisional or final for challenges; claims are in a broadcast transaction
+  status: string;
+}
+
+export interface EarningsExportResult {
+  path: string;
+  format: EarningsExportFormat;
+  rows: number;
+}
+
+const CSV_COLUMNS: Array<keyof LedgerRow> = [
+  'timestamp', 'kind', 'token', 'amount', 'fiatValue', 'fiatCurrency', 'reference', 'cid', 'account', 'status',
+];
+
+export function validateEarningsExportQuery(query: EarningsExportQuery): string[] {
+  const errors: string[] = [];
+  if (query.format !== undefined && !['csv', 'json'].includes(query.format)) {
+    errors.push('format must be csv or json');
+  }
+  for (const field of ['from', 'to'] as const) {
+    if (query[field] !== undefined && Number.isNaN(Date.parse(query[field]!))) {
+      errors.push(`${field} must be a date`);
+    }
+  }
+  return errors;
+}
+
+function csvField(value: unknown): string {
+  if (value === null || value === undefined) return '';
+  const text = String(value);
+  return /[",\r\n]/.test(text) ? `"${text.replace(/"/g, '""')}"` : text;
+}
+
+function claimRows(record: ClaimRecord, account: string | null): LedgerRow[] {
+  return Object.entries(record.amounts)
+    .filter(([, amount]) => (amount ?? 0) > 0)
+    .map(([token, amount]) => {
+      const fiat = record.fiat?.amounts[token as 'HBD' | 'HIVE'];
+      return {
+        timestamp: record.at,
+        kind: 'claim' as const,
+        token,
+        amount: amount!,
+        fiatValue: fiat ?? null,
+        fiatCurrency: fiat !== undefined ? record.fiat!.currency : null,
+        reference: record.txId,
+        cid: null,
+        account,
+        status: 'broadcast',
+      };
+    });
+}
+
+/**
+ * Writes the earnings ledger for bookkeeping: every challenge that paid,
+ * from earnings.db, and every reward claim that was broadcast, oldest
+ * first. Fiat values are the price feed's quote when the entry was
+ * booked, never today's; entries booked without a fresh quote leave it
+ * blank rather than guess. Challenge rewards accrue on-chain until a
+ * claim pays them out, so the two kinds can be told apart by `kind`.
+ */
+export class EarningsExporter {
+  private config: ConfigStore;
+  private claims: RewardClaimer;
+
+  constructor(config: ConfigStore, claims: RewardClaimer) {
+    this.config = config;
+    this.claims = claims;
+  }
+
+  *rows(query: EarningsExportQuery = {}): Generator<LedgerRow> {
+    const from = query.from ? new Date(query.from).toISOString() : null;
+    const to = query.to ? new Date(query.to).toISOString() : null;
+    const account = this.config.getConfig().hiveUsername;
+    // The claim history is short; it is merged into the challenges by time
+    const claims = this.claims.getStatus().history
+      .filter((record) => !record.error && record.txId)
+      .filter((record) => (!from || record.at >= from) && (!to || record.at < to))
+      .sort((a, b) => a.at.localeCompare(b.at))
+      .flatMap((record) => claimRows(record, account));
+
+    let next = 0;
+    for (const entry of this.config.getEarningsStore().iterateEarned(query.from, query.to)) {
+      while (next < claims.length && claims[next].timestamp <= entry.at) yield claims[next++];
+      yield {
+        timestamp: entry.at,
+        kind: 'challenge',
+        token: entry.token ?? 'HBD',
+        amount: entry.amount ?? entry.hbdEarned,
+        fiatValue: entry.fiatValue ?? null,
+        fiatCurrency: entry.fiatCurrency ?? null,
+        reference: entry.reference ?? null,
+        cid: entry.cid,
+        account: entry.account ?? null,
+        status: entry.status ?? 'final',
+      };
+    }
+    while (next < claims.length) yield claims[next++];
+  }
+
+  async exportToFile(filePath: string, query: EarningsExportQuery = {}): Promise<EarningsExportResult> {
+    const format = query.format ?? 'csv';
+    const tempPath = `${filePath}.tmp`;
+    const out = fs.createWriteStream(tempPath);
+    const write = async (chunk: string) => {
+      if (!out.write(chunk)) await once(out, 'drain');
+    };
+
+    let rows = 0;
+    try {
+      if (format === 'csv') {
+        await write(CSV_COLUMNS.join(',') + '\n');
+      } else {
+        await write(`{"exportedAt":${JSON.stringify(new Date().toISOString())},"entries":[`);
+      }
+      for (const row of this.rows(query)) {
+        if (format === 'csv') {
+          await write(CSV_COLUMNS.map((column) => csvField(row[column])).join(',') + '\n');
+        } else {
+          await write(`${rows > 0 ? ',' : ''}\n${JSON.stringify(row)}`);
+        }
+        rows++;
+      }
+      if (format === 'json') await write('\n]}\n');
+      out.end();
+      await once(out, 'finish');
+      fs.renameSync(tempPath, filePath);
+    } catch (error) {
+      out.destroy();
+      fs.rmSync(tempPath, { force: true });
+      throw error;
+    }
+    console.log(`[SPK] Exported ${rows} earnings entries to ${filePath}`);
+    return { path: filePath, format, rows };
+  }
+}
@@ -0,0 +1,140 @@
+import { EventEmitter } from 'events';
+import { ConfigStore, EarningsAnchor, EarningsHistoryEntry } from './config';
+import { HiveRpcPool } from './hive-rpc';
+
+// Hive makes a block every 3s; irreversibility trails the head by about 45s
+const CHECK_MS = 15 * 1000;
+// An anchor older than this no longer says where the chain was when an entry was booked
+const ANCHOR_MAX_AGE_MS = 60 * 1000;
+
+export interface EarningsFinalityStatus {
+  headBlock: number | null;
+  irreversibleBlock: number | null;
+  provisionalEntries: number;
+  provisionalHbd: number;
+  // Entries whose block was dropped in a micro-fork and that wait again on a new one
+  reanchored: number;
+  checkedAt: string | null;
+  lastError: string | null;
+}
+
+export interface EarningsFinalized {
+  finalized: number;
+  reanchored: number;
+  irreversibleBlock: number;
+}
+
+/**
+ * Holds earnings back from being final until the chain can no longer drop
+ * them. Each earnings history entry is booked against the Hive head block of
+ * the moment, by number and ID, and starts out provisional. Once the last
+ * irreversible block passes it, the block at that height is looked up: if
+ * it is the one the entry was booked against, the entry becomes final. If
+ * another block took its place, the entry sat on a micro-fork; it is booked
+ * against the new head and waits again rather than being counted twice or
+ * lost.
+ *
+ * Emits `finalized` after a check that changed anything.
+ */
+export class EarningsFinality extends EventEmitter {
+  private config: ConfigStore;
+  private rpc: HiveRpcPool;
+  private head: EarningsAnchor | null = null;
+  private headAt = 0;
+  private irreversibleBlock: number | null = null;
+  private reanchored = 0;
+  private checkedAt: string | null = null;
+  private lastError: string | null = null;
+  private timer: NodeJS.Timeout | null = null;
+  private checking = false;
+
+  constructor(config: ConfigStore, rpc: HiveRpcPool) {
+    super();
+    this.config = config;
+    this.rpc = rpc;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.check().catch(() => undefined);
+    this.timer = setInterval(() => this.check().catch(() => undefined), CHECK_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  // Null when the chain hasn't been read lately; the next check books the entry instead
+  anchor(): EarningsAnchor | null {
+    return this.head && Date.now() - this.headAt < ANCHOR_MAX_AGE_MS ? this.head : null;
+  }
+
+  getStatus(): EarningsFinalityStatus {
+    const provisional = this.config.getProvisionalEarnings();
+    return {
+      headBlock: this.head?.block ?? null,
+      irreversibleBlock: this.irreversibleBlock,
+      provisionalEntries: provisional.length,
+      provisionalHbd: provisional.reduce((sum, entry) => sum + entry.hbdEarned, 0),
+      reanchored: this.reanchored,
+      checkedAt: this.checkedAt,
+      lastError: this.lastError,
+    };
+  }
+
+  async check(): Promise<EarningsFinalized | null> {
+    if (this.checking) return null;
+    this.checking = true;
+    try {
+      const client = this.rpc.getClient();
+      const props = await client.database.getDynamicGlobalProperties();
+      this.head = { block: props.head_block_number, blockId: props.head_block_id };
+      this.headAt = Date.now();
+      this.irreversibleBlock = props.last_irreversible_block_num;
+      this.checkedAt = new Date().toISOString();
+      this.lastError = null;
+
+      const pending = this.config.getProvisionalEarnings();
+      if (pending.length === 0) return null;
+
+      // One lookup per irreversible block some entry was booked against
+      const heights = [...new Set(pending.map((entry) => entry.block)
+        .filter((block): block is number => typeof block === 'number' && block <= this.irreversibleBlock!))];
+      const irreversibleIds = new Map<number, string>();
+      for (const block of heights) {
+        const signed = await client.database.getBlock(block).catch(() => null);
+        if (signed?.block_id) irreversibleIds.set(block, signed.block_id);
+      }
+
+      let finalized = 0;
+      let reanchored = 0;
+      const head = this.head;
+      this.config.updateEarningsHistory((entry): EarningsHistoryEntry => {
+        if (typeof entry.block !== 'number') return { ...entry, block: head.block, blockId: head.blockId };
+        const id = irreversibleIds.get(entry.block);
+        // Not irreversible yet, or the lookup failed: try again next time
+        if (id === undefined) return entry;
+        if (id === entry.blockId) {
+          finalized++;
+          return { ...entry, status: 'final' };
+        }
+        reanchored++;
+        console.warn(`[SPK] Block ${entry.block} was replaced in a micro-fork; `
+          + `earnings from ${entry.at} now wait on block ${head.block}`);
+        return { ...entry, block: head.block, blockId: head.blockId };
+      });
+      this.reanchored += reanchored;
+      if (finalized + reanchored === 0) return null;
+      if (finalized > 0) console.log(`[SPK] ${finalized} earnings entries final as of block ${this.irreversibleBlock}`);
+      const result = { finalized, reanchored, irreversibleBlock: this.irreversibleBlock };
+      this.emit('finalized', result);
+      return result;
+    } catch (error: any) {
+      this.lastError = error.message;
+      throw error;
+    } finally {
+      this.checking = false;
+    }
+  }
+}
@@ -0,0 +1,136 @@
+import { ChallengeDaySummary } from './challenge-log';
+import { MarketOffer } from './contract-market';
+import { HBD_PER_CHALLENGE } from './poa';
+import { StorageBreakdown } from './storage-breakdown';
+
+const GB = 1e9;
+// Days of challenge history the rates are taken from
+const WINDOW_DAYS = 14;
+// Open offers are fetched again after this
+const OFFERS_TTL_MS = 60 * 60 * 1000;
+export const MAX_PROJECTED_GB = 100000;
+
+export interface ProjectionSources {
+  breakdown(): StorageBreakdown;
+  // Per UTC day since `from`
+  challengeDays(from: string): ChallengeDaySummary[];
+  offers(): Promise<MarketOffer[]>;
+}
+
+export interface ProjectionBasis {
+  windowDays: number;
+  challengesPerDay: number;
+  // Null before any challenge came in
+  passRate: number | null;
+  storedGb: number;
+  quotaGb: number | null;
+  // Challenges a day per GB of contract content; null while nothing is stored
+  challengesPerGbDay: number | null;
+  hbdPerChallenge: number;
+  // Median of open offers this node could still take; null when the market couldn't be read
+  marketHbdPerGbMonth: number | null;
+  marketGb: number;
+}
+
+export interface ProjectionScenario {
+  capacityGb: number;
+  // What's stored at that capacity, given the open offers there are to fill it
+  storedGb: number;
+  daily: { challengeHbd: number; contractHbd: number; totalHbd: number };
+  monthly: { challengeHbd: number; contractHbd: number; totalHbd: number };
+}
+
+export interface EarningsProjection {
+  basis: ProjectionBasis;
+  // Today's storage, the quota, and the capacity asked about
+  current: ProjectionScenario;
+  quota: ProjectionScenario | null;
+  requested: ProjectionScenario | null;
+  projectedAt: string;
+}
+
+function median(values: number[]): number | null {
+  if (values.length === 0) return null;
+  const sorted = [...values].sort((a, b) => a - b);
+  const middle = Math.floor(sorted.length / 2);
+  return sorted.length % 2 === 1 ? sorted[middle] : (sorted[middle - 1] + sorted[middle]) / 2;
+}
+
+/**
+ * Projects what the node would earn per day and month at a given
+ * capacity. Challenge income comes from the last 14 days: challenges per
+ * GB of contract content, the pass rate and the reward per passed
+ * challenge, on the assumption that validators challenge in proportion to
+ * what's stored. Contract income is what open market offers pay per GB,
+ * for new storage only and only as far as there are offers to fill it. A
+ * node without challenge history projects no challenge income; it is an
+ * estimate to size the repo by, not a promise.
+ */
+export class EarningsProjector {
+  private sources: ProjectionSources;
+  private offers: { at: number; offers: MarketOffer[] } | null = null;
+
+  constructor(sources: ProjectionSources) {
+    this.sources = sources;
+  }
+
+  async project(capacityGb?: number): Promise<EarningsProjection> {
+    const basis = await this.basis();
+    return {
+      basis,
+      current: this.scenario(basis, basis.storedGb),
+      quota: basis.quotaGb !== null ? this.scenario(basis, basis.quotaGb) : null,
+      requested: capacityGb !== undefined ? this.scenario(basis, capacityGb) : null,
+      projectedAt: new Date().toISOString(),
+    };
+  }
+
+  private async basis(): Promise<ProjectionBasis> {
+    const breakdown = this.sources.breakdown();
+    const from = new Date(Date.now() - WINDOW_DAYS * 24 * 60 * 60 * 1000).toISOString();
+    const days = this.sources.challengeDays(from);
+    const challenges = days.reduce((sum, day) => sum + day.challenges, 0);
+    const passed = days.reduce((sum, day) => sum + day.passed, 0);
+    const storedGb = breakdown.contractBytes / GB;
+
+    // Offers already held here don't add storage
+    const open = (await this.openOffers())?.filter((offer) => !offer.held && offer.openSlots > 0) ?? null;
+    const priced = open?.filter((offer) => offer.hbdPerGbMonth !== null && offer.sizeBytes) ?? [];
+
+    return {
+      windowDays: WINDOW_DAYS,
+      challengesPerDay: challenges / WINDOW_DAYS,
+      passRate: challenges > 0 ? passed / challenges : null,
+      storedGb,
+      quotaGb: breakdown.maxBytes ? breakdown.maxBytes / GB : null,
+      challengesPerGbDay: storedGb > 0 ? challenges / WINDOW_DAYS / storedGb : null,
+      hbdPerChallenge: HBD_PER_CHALLENGE,
+      marketHbdPerGbMonth: open ? median(priced.map((offer) => offer.hbdPerGbMonth!)) : null,
+      marketGb: priced.reduce((sum, offer) => sum + offer.sizeBytes! / GB, 0),
+    };
+  }
+
+  private scenario(basis: ProjectionBasis, capacityGb: number): ProjectionScenario {
+    const fillable = Math.min(Math.max(0, capacityGb - basis.storedGb), basis.marketGb);
+    const storedGb = Math.min(capacityGb, basis.storedGb) + fillable;
+    const challengeHbd = (basis.challengesPerGbDay ?? 0) * storedGb * (basis.passRate ?? 0) * basis.hbdPerChallenge;
+    const contractHbd = fillable * (basis.marketHbdPerGbMonth ?? 0) / 30;
+    return {
+      capacityGb,
+      storedGb,
+      daily: { challengeHbd, contractHbd, totalHbd: challengeHbd + contractHbd },
+      monthly: { challengeHbd: challengeHbd * 30, contractHbd: contractHbd * 30, totalHbd: (challengeHbd + contractHbd) * 30 },
+    };
+  }
+
+  // Null when the SPK API can't be reached and nothing was fetched before
+  private async openOffers(): Promise<MarketOffer[] | null> {
+    if (this.offers && Date.now() - this.offers.at < OFFERS_TTL_MS) return this.offers.offers;
+    try {
+      this.offers = { at: Date.now(), offers: await this.sources.offers() };
+    } catch (error: any) {
+      console.warn('[SPK] Open offers unavailable for the earnings projection:', error.message);
+    }
+    return this.offers?.offers ?? null;
+  }
+}
@@ -0,0 +1,638 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import Database from 'better-sqlite3';
+import { AccountEarnings, EarningsAnchor, EarningsData, EarningsHistoryEntry } from './config';
+
+// The token a challenge reward is paid in
+export type EarningsToken = 'HBD' | 'LARYNX' | 'SPK';
+export const EARNINGS_TOKENS: EarningsToken[] = ['HBD', 'LARYNX', 'SPK'];
+
+export const MAX_DAILY_DAYS = 365;
+
+export type MilestoneField = 'challengesPassed' | 'consecutivePasses' | 'totalHbd';
+export const MILESTONE_FIELDS: MilestoneField[] = ['challengesPassed', 'consecutivePasses', 'totalHbd'];
+const MAX_THRESHOLDS = 20;
+export const MAX_GOALS = 20;
+
+// A goal of the user's: an amount of a token or a number of passes, over all time or each day, week or month
+export interface EarningsGoal {
+  id: string;
+  metric: 'amount' | 'challengesPassed';
+  // For `amount`; HBD when not given
+  token?: EarningsToken;
+  target: number;
+  period: 'total' | EarningsGranularity;
+}
+
+export interface MilestoneSettings {
+  // Counts that are reached once; a passing streak reaches its own again after every break
+  thresholds: Record<MilestoneField, number[]>;
+  goals: EarningsGoal[];
+}
+
+export const DEFAULT_MILESTONE_SETTINGS: MilestoneSettings = {
+  thresholds: {
+    challengesPassed: [1, 10, 100, 1000, 10000, 100000],
+    consecutivePasses: [10, 100, 1000, 10000],
+    totalHbd: [1, 10, 100, 1000],
+  },
+  goals: [],
+};
+
+export function validateMilestoneSettings(settings: Partial<MilestoneSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.thresholds !== undefined) {
+    for (const [field, thresholds] of Object.entries(settings.thresholds)) {
+      if (!(MILESTONE_FIELDS as string[]).includes(field)) {
+        errors.push(`Unknown milestone: ${field}`);
+      } else if (!Array.isArray(thresholds) || thresholds.length > MAX_THRESHOLDS
+        || thresholds.some((value) => typeof value !== 'number' || !Number.isFinite(value) || value <= 0)) {
+        errors.push(`${field} must list up to ${MAX_THRESHOLDS} positive numbers`);
+      }
+    }
+  }
+  if (settings.goals !== undefined) {
+    if (!Array.isArray(settings.goals) || settings.goals.length > MAX_GOALS) {
+      errors.push(`goals must list up to ${MAX_GOALS} goals`);
+      return errors;
+    }
+    settings.goals.forEach((goal, index) => {
+      const name = `Goal ${index + 1}`;
+      if (!goal.id || typeof goal.id !== 'string') errors.push(`${name} needs an id`);
+      if (!['amount', 'challengesPassed'].includes(goal.metric)) errors.push(`${name}: metric must be amount or challengesPassed`);
+      if (goal.token !== undefined && !EARNINGS_TOKENS.includes(goal.token)) errors.push(`${name}: token must be one of ${EARNINGS_TOKENS.join(', ')}`);
+      if (typeof goal.target !== 'number' || !Number.isFinite(goal.target) || goal.target <= 0) errors.push(`${name}: target must be a positive number`);
+      if (!['total', ...EARNINGS_GRANULARITIES].includes(goal.period)) errors.push(`${name}: period must be total, day, week or month`);
+    });
+    const ids = settings.goals.map((goal) => goal.id);
+    if (new Set(ids).size !== ids.length) errors.push('Goal ids must be unique');
+  }
+  return errors;
+}
+
+const GOAL_PERIODS: Record<EarningsGoal['period'], string> = { total: 'in total', day: 'today', week: 'this week', month: 'this month' };
+
+// "5 HBD this month", "1000 challenges passed in total"
+export function describeGoal(goal: EarningsGoal): string {
+  const what = goal.metric === 'amount' ? `${goal.target} ${goal.token ?? 'HBD'}` : `${goal.target} challenges passed`;
+  return `${what} ${GOAL_PERIODS[goal.period]}`;
+}
+
+export interface Milestone {
+  milestone: MilestoneField | 'goal';
+  value: number;
+  // For a goal; `period` is the first day of the day, week or month it was reached in
+  goal?: EarningsGoal;
+  period?: string;
+}
+
+export interface GoalProgress {
+  goal: EarningsGoal;
+  // So far, in the current period for a per-period goal
+  current: number;
+  period: string | null;
+  reached: boolean;
+}
+
+// What an amount was worth when it was booked, in the price feed's currency
+export interface EarningsFiat {
+  value: number;
+  currency: string;
+}
+
+export interface EarningsDay {
+  day: string;
+  token: EarningsToken;
+  challenges: number;
+  passed: number;
+  amount: number;
+}
+
+export type EarningsGranularity = 'day' | 'week' | 'month';
+export const EARNINGS_GRANULARITIES: EarningsGranularity[] = ['day', 'week', 'month'];
+// Periods one series may cover
+const MAX_SERIES_PERIODS = 400;
+// Periods covered when the range isn't given
+const DEFAULT_SERIES_PERIODS: Record<EarningsGranularity, number> = { day: 30, week: 12, month: 12 };
+
+export interface EarningsSeriesQuery {
+  granularity?: EarningsGranularity;
+  // YYYY-MM-DD, both inclusive; each is widened to the period it falls in
+  from?: string;
+  to?: string;
+}
+
+export interface EarningsPeriod {
+  // First day of the period; weeks start on Monday
+  start: string;
+  challenges: number;
+  passed: number;
+  // Null for a period without challenges
+  passRate: number | null;
+  amounts: Partial<Record<EarningsToken, number>>;
+}
+
+export interface EarningsSeries {
+  granularity: EarningsGranularity;
+  from: string;
+  to: string;
+  // Oldest first, with empty periods included so charts keep their spacing
+  periods: EarningsPeriod[];
+}
+
+const DAY_MS = 24 * 60 * 60 * 1000;
+
+function isDay(value: string): boolean {
+  return /^\d{4}-\d{2}-\d{2}$/.test(value) && !Number.isNaN(Date.parse(`${value}T00:00:00Z`));
+}
+
+function periodStart(day: string, granularity: EarningsGranularity): string {
+  if (granularity === 'month') return `${day.slice(0, 7)}-01`;
+  if (granularity === 'day') return day;
+  const date = new Date(`${day}T00:00:00Z`);
+  // getUTCDay is 0 on Sunday
+  return new Date(date.getTime() - ((date.getUTCDay() + 6) % 7) * DAY_MS).toISOString().slice(0, 10);
+}
+
+function nextPeriod(start: string, granularity: EarningsGranularity): string {
+  const date = new Date(`${start}T00:00:00Z`);
+  if (granularity === 'month') date.setUTCMonth(date.getUTCMonth() + 1);
+  else date.setUTCDate(date.getUTCDate() + (granularity === 'week' ? 7 : 1));
+  return date.toISOString().slice(0, 10);
+}
+
+function seriesRange(query: EarningsSeriesQuery): { granularity: EarningsGranularity; starts: string[]; from: string; to: string } {
+  const granularity = query.granularity ?? 'day';
+  const to = query.to ?? new Date().toISOString().slice(0, 10);
+  let from = query.from;
+  if (!from) {
+    from = periodStart(to, granularity);
+    for (let i = 1; i < DEFAULT_SERIES_PERIODS[granularity]; i++) {
+      // Step back through the day before each period's start
+      from = periodStart(new Date(Date.parse(`${from}T00:00:00Z`) - DAY_MS).toISOString().slice(0, 10), granularity);
+    }
+  }
+  const starts: string[] = [];
+  for (let start = periodStart(from, granularity); start <= to && starts.length <= MAX_SERIES_PERIODS; start = nextPeriod(start, granularity)) {
+    starts.push(start);
+  }
+  return { granularity, starts, from: starts[0] ?? from, to };
+}
+
+export function validateEarningsSeriesQuery(query: EarningsSeriesQuery): string[] {
+  const errors: string[] = [];
+  if (query.granularity !== undefined && !EARNINGS_GRANULARITIES.includes(query.granularity)) {
+    errors.push(`granularity must be one of ${EARNINGS_GRANULARITIES.join(', ')}`);
+  }
+  for (const field of ['from', 'to'] as const) {
+    if (query[field] !== undefined && !isDay(query[field]!)) errors.push(`${field} must be a YYYY-MM-DD date`);
+  }
+  if (errors.length > 0) return errors;
+  if (query.from && query.to && query.from > query.to) {
+    errors.push('from must not be after to');
+  } else if (seriesRange(query).starts.length > MAX_SERIES_PERIODS) {
+    errors.push(`The range covers more than ${MAX_SERIES_PERIODS} periods; use a coarser granularity`);
+  }
+  return errors;
+}
+
+// Files from before the database, imported once and then renamed
+const LEGACY_EARNINGS = 'earnings.json';
+const LEGACY_HISTORY = 'earnings-history.ndjson';
+const LEGACY_ACCOUNTS = 'earnings-accounts.json';
+
+interface Baseline {
+  totalHbd: number;
+  challengesPassed: number;
+  challengesFailed: number;
+}
+
+function toEntry(row: any): EarningsHistoryEntry {
+  return {
+    at: row.at,
+    passed: row.passed === 1,
+    hbdEarned: row.token === 'HBD' ? row.amount : 0,
+    token: row.token,
+    amount: row.amount,
+    cid: row.cid,
+    account: row.account,
+    block: row.block,
+    blockId: row.block_id,
+    status: row.status,
+    reference: row.reference,
+    fiatValue: row.fiat_value,
+    fiatCurrency: row.fiat_currency,
+  };
+}
+
+function readJson(filePath: string): any {
+  try {
+    return fs.existsSync(filePath) ? JSON.parse(fs.readFileSync(filePath, 'utf-8')) : null;
+  } catch (error) {
+    console.error(`[Config] Failed to read ${path.basename(filePath)}:`, error);
+    return null;
+  }
+}
+
+/**
+ * Earnings in ~/.spk-ipfs/earnings.db: one row per challenge answered,
+ * with the token it paid and its finality, and a rollup per UTC day and
+ * token that the totals are summed from. The passing streak, the last
+ * challenge times and the milestones already reached are kept alongside,
+ * so nothing is rebuilt from memory after a restart and a milestone
+ * crossed once isn't announced again.
+ *
+ * The JSON files of older versions are imported on first start. Their
+ * totals can predate the history file; the difference is kept as a
+ * baseline the totals start from.
+ */
+export class EarningsStore {
+  private db: Database.Database;
+
+  constructor(dataDir: string) {
+    this.db = new Database(path.join(dataDir, 'earnings.db'));
+    this.db.pragma('journal_mode = WAL');
+    this.db.exec(`
+      CREATE TABLE IF NOT EXISTS earnings (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        passed INTEGER NOT NULL,
+        token TEXT NOT NULL,
+        amount REAL NOT NULL,
+        cid TEXT,
+        account TEXT,
+        block INTEGER,
+        block_id TEXT,
+        status TEXT NOT NULL,
+        reference TEXT,
+        fiat_value REAL,
+        fiat_currency TEXT
+      );
+      CREATE INDEX IF NOT EXISTS earnings_status ON earnings (status);
+      CREATE INDEX IF NOT EXISTS earnings_account ON earnings (account);
+      CREATE INDEX IF NOT EXISTS earnings_at ON earnings (at);
+      CREATE TABLE IF NOT EXISTS earnings_daily (
+        day TEXT NOT NULL,
+        token TEXT NOT NULL,
+        challenges INTEGER NOT NULL,
+        passed INTEGER NOT NULL,
+        amount REAL NOT NULL,
+        PRIMARY KEY (day, token)
+      );
+      CREATE TABLE IF NOT EXISTS earnings_state (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+      );
+      CREATE TABLE IF NOT EXISTS milestones (
+        field TEXT NOT NULL,
+        threshold REAL NOT NULL,
+        streak TEXT NOT NULL,
+        reached_at TEXT NOT NULL,
+        PRIMARY KEY (field, threshold, streak)
+      );
+    `);
+    // Databases from before the ledger export lack the columns it reads
+    const columns = (this.db.prepare('PRAGMA table_info(earnings)').all() as Array<{ name: string }>).map((column) => column.name);
+    for (const [column, type] of [['reference', 'TEXT'], ['fiat_value', 'REAL'], ['fiat_currency', 'TEXT']]) {
+      if (!columns.includes(column)) this.db.exec(`ALTER TABLE earnings ADD COLUMN ${column} ${type}`);
+    }
+    this.importLegacy(dataDir);
+  }
+
+  getEarnings(): EarningsData {
+    const baseline = this.getBaseline();
+    const counts = this.db.prepare(`
+      SELECT COALESCE(SUM(challenges), 0) AS challenges, COALESCE(SUM(passed), 0) AS passed FROM earnings_daily
+    `).get() as { challenges: number; passed: number };
+    const totals: Partial<Record<EarningsToken, number>> = {};
+    for (const row of this.db.prepare('SELECT token, SUM(amount) AS amount FROM earnings_daily GROUP BY token').all() as any[]) {
+      totals[row.token as EarningsToken] = row.amount;
+    }
+    totals.HBD = (totals.HBD ?? 0) + baseline.totalHbd;
+    const { provisional } = this.db.prepare(`
+      SELECT COALESCE(SUM(amount), 0) AS provisional FROM earnings WHERE status = 'provisional' AND token = 'HBD'
+    `).get() as { provisional: number };
+
+    return {
+      totalHbd: totals.HBD,
+      challengesPassed: counts.passed + baseline.challengesPassed,
+      challengesFailed: counts.challenges - counts.passed + baseline.challengesFailed,
+      consecutivePasses: Number(this.getState('consecutivePasses') ?? 0),
+      lastChallengeTime: this.getState('lastChallengeTime'),
+      lastPassedChallengeTime: this.getState('lastPassedChallengeTime'),
+      provisionalHbd: provisional,
+      totals,
+    };
+  }
+
+  // `reference` names the challenge, its salt
+  record(passed: boolean, amount: number, cid: string | null, account: string | null,
+    anchor: EarningsAnchor | null, reference: string | null = null, fiat: EarningsFiat | null = null,
+    token: EarningsToken = 'HBD'): EarningsData {
+    const at = new Date().toISOString();
+    this.db.transaction(() => {
+      this.insert({
+        at,
+        passed,
+        token,
+        amount: passed ? amount : 0,
+        cid,
+        account,
+        block: anchor?.block ?? null,
+        blockId: anchor?.blockId ?? null,
+        status: 'provisional',
+        reference,
+        fiatValue: passed ? fiat?.value ?? null : null,
+        fiatCurrency: passed ? fiat?.currency ?? null : null,
+      });
+      const streak = Number(this.getState('consecutivePasses') ?? 0);
+      if (passed) {
+        // A new streak reaches the streak milestones afresh
+        if (streak === 0) this.setState('streakStartedAt', at);
+        this.setState('consecutivePasses', String(streak + 1));
+        this.setState('lastPassedChallengeTime', at);
+      } else {
+        this.setState('consecutivePasses', '0');
+      }
+      this.setState('lastChallengeTime', at);
+    })();
+    return this.getEarnings();
+  }
+
+  getAccountEarnings(): Record<string, AccountEarnings> {
+    const accounts: Record<string, AccountEarnings> = JSON.parse(this.getState('accountBaselines') ?? '{}');
+    const rows = this.db.prepare(`
+      SELECT account, SUM(CASE WHEN token = 'HBD' THEN amount ELSE 0 END) AS totalHbd,
+        SUM(passed) AS challengesPassed, SUM(1 - passed) AS challengesFailed, MAX(at) AS lastChallengeTime
+      FROM earnings WHERE account IS NOT NULL GROUP BY account
+    `).all() as Array<AccountEarnings & { account: string }>;
+    for (const { account, ...row } of rows) {
+      const base = accounts[account];
+      accounts[account] = base
+        ? {
+          totalHbd: base.totalHbd + row.totalHbd,
+          challengesPassed: base.challengesPassed + row.challengesPassed,
+          challengesFailed: base.challengesFailed + row.challengesFailed,
+          lastChallengeTime: row.lastChallengeTime,
+        }
+        : row;
+    }
+    return accounts;
+  }
+
+  // Oldest first
+  getHistory(): EarningsHistoryEntry[] {
+    return this.db.prepare('SELECT * FROM earnings ORDER BY id').all().map(toEntry);
+  }
+
+  // Entries that paid something, oldest first, read one at a time for exports
+  *iterateEarned(from?: string, to?: string): Generator<EarningsHistoryEntry> {
+    const clauses = ['amount > 0'];
+    const params: Record<string, string> = {};
+    if (from) {
+      clauses.push('at >= @from');
+      params.from = new Date(from).toISOString();
+    }
+    if (to) {
+      clauses.push('at < @to');
+      params.to = new Date(to).toISOString();
+    }
+    for (const row of this.db.prepare(`SELECT * FROM earnings WHERE ${clauses.join(' AND ')} ORDER BY id`).iterate(params)) {
+      yield toEntry(row);
+    }
+  }
+
+  // Challenges and amounts from `from` up to `to`, both ISO timestamps
+  summarize(from: string, to: string): { challenges: number; passed: number; amounts: Partial<Record<EarningsToken, number>> } {
+    const rows = this.db.prepare(`
+      SELECT token, COUNT(*) AS challenges, SUM(passed) AS passed, SUM(amount) AS amount
+      FROM earnings WHERE at >= @from AND at < @to GROUP BY token
+    `).all({ from, to }) as Array<{ token: EarningsToken; challenges: number; passed: number; amount: number }>;
+    return {
+      challenges: rows.reduce((sum, row) => sum + row.challenges, 0),
+      passed: rows.reduce((sum, row) => sum + row.passed, 0),
+      amounts: Object.fromEntries(rows.map((row) => [row.token, row.amount])),
+    };
+  }
+
+  getProvisional(): EarningsHistoryEntry[] {
+    return this.db.prepare(`SELECT * FROM earnings WHERE status = 'provisional' ORDER BY id`).all().map(toEntry);
+  }
+
+  // Rewrites provisional entries through `update`; the block and status are what it may change
+  updateProvisional(update: (entry: EarningsHistoryEntry) => EarningsHistoryEntry): void {
+    const rows = this.db.prepare(`SELECT * FROM earnings WHERE status = 'provisional'`).all() as any[];
+    const write = this.db.prepare('UPDATE earnings SET block = @block, block_id = @blockId, status = @status WHERE id = @id');
+    this.db.transaction(() => {
+      for (const row of rows) {
+        const before = toEntry(row);
+        const after = update(before);
+        if (after.status === before.status && after.block === before.block && after.blockId === before.blockId) continue;
+        write.run({ id: row.id, block: after.block ?? null, blockId: after.blockId ?? null, status: after.status ?? 'final' });
+      }
+    })();
+  }
+
+  // Newest day first
+  getDaily(days = 30, token?: EarningsToken): EarningsDay[] {
+    const since = new Date(Date.now() - (days - 1) * 24 * 60 * 60 * 1000).toISOString().slice(0, 10);
+    return this.db.prepare(`
+      SELECT day, token, challenges, passed, amount FROM earnings_daily
+      WHERE day >= @since ${token ? 'AND token = @token' : ''}
+      ORDER BY day DESC, token
+    `).all(token ? { since, token } : { since }) as EarningsDay[];
+  }
+
+  /**
+   * Challenges, pass rate and amount per token for each day, week or month
+   * of the range, summed from the daily rollup. Totals imported from
+   * before the history have no date and aren't in any period.
+   */
+  getSeries(query: EarningsSeriesQuery = {}): EarningsSeries {
+    const { granularity, starts, from, to } = seriesRange(query);
+    const periods = new Map<string, EarningsPeriod>(starts.map((start) => [
+      start,
+      { start, challenges: 0, passed: 0, passRate: null, amounts: {} },
+    ]));
+    const rows = this.db.prepare(`
+      SELECT day, token, challenges, passed, amount FROM earnings_daily WHERE day >= @from AND day <= @to
+    `).all({ from, to }) as EarningsDay[];
+    for (const row of rows) {
+      const period = periods.get(periodStart(row.day, granularity));
+      if (!period) continue;
+      period.challenges += row.challenges;
+      period.passed += row.passed;
+      period.amounts[row.token] = (period.amounts[row.token] ?? 0) + row.amount;
+    }
+    for (const period of periods.values()) {
+      if (period.challenges > 0) period.passRate = period.passed / period.challenges;
+    }
+    return { granularity, from, to, periods: Array.from(periods.values()) };
+  }
+
+  /**
+   * Milestones and goals `earnings` has reached that weren't recorded yet,
+   * recorded as it returns them. A per-period goal is recorded under the
+   * period it was reached in, so it can be reached again in the next one.
+   */
+  reachMilestones(earnings: EarningsData, settings: MilestoneSettings): Milestone[] {
+    const streak = this.getState('streakStartedAt') ?? '';
+    const insert = this.db.prepare(`
+      INSERT OR IGNORE INTO milestones (field, threshold, streak, reached_at) VALUES (@field, @threshold, @streak, @at)
+    `);
+    const reached: Milestone[] = [];
+    const at = new Date().toISOString();
+    this.db.transaction(() => {
+      for (const field of MILESTONE_FIELDS) {
+        for (const threshold of settings.thresholds[field]) {
+          if (earnings[field] < threshold) continue;
+          const { changes } = insert.run({ field, threshold, streak: field === 'consecutivePasses' ? streak : '', at });
+          if (changes > 0) reached.push({ milestone: field, value: threshold });
+        }
+      }
+      for (const progress of this.getGoalProgress(earnings, settings)) {
+        if (!progress.reached) continue;
+        const { goal, period } = progress;
+        const { changes } = insert.run({ field: `goal:${goal.id}`, threshold: goal.target, streak: period ?? '', at });
+        if (changes > 0) reached.push({ milestone: 'goal', value: goal.target, goal, period: period ?? undefined });
+      }
+    })();
+    return reached;
+  }
+
+  // Per-period goals count from the start of the current UTC day, week or month
+  getGoalProgress(earnings: EarningsData, settings: MilestoneSettings): GoalProgress[] {
+    const today = new Date().toISOString().slice(0, 10);
+    const periods = new Map<string, { passed: number; amounts: Partial<Record<EarningsToken, number>> }>();
+    const periodTotals = (start: string) => {
+      if (!periods.has(start)) {
+        const rows = this.db.prepare(`
+          SELECT token, SUM(passed) AS passed, SUM(amount) AS amount FROM earnings_daily WHERE day >= ? GROUP BY token
+        `).all(start) as Array<{ token: EarningsToken; passed: number; amount: number }>;
+        periods.set(start, {
+          passed: rows.reduce((sum, row) => sum + row.passed, 0),
+          amounts: Object.fromEntries(rows.map((row) => [row.token, row.amount])),
+        });
+      }
+      return periods.get(start)!;
+    };
+
+    return settings.goals.map((goal) => {
+      const token = goal.token ?? 'HBD';
+      let current: number;
+      let period: string | null = null;
+      if (goal.period === 'total') {
+        current = goal.metric === 'amount' ? earnings.totals[token] ?? 0 : earnings.challengesPassed;
+      } else {
+        period = periodStart(today, goal.period);
+        const totals = periodTotals(period);
+        current = goal.metric === 'amount' ? totals.amounts[token] ?? 0 : totals.passed;
+      }
+      return { goal, current, period, reached: current >= goal.target };
+    });
+  }
+
+  // A consistent copy while the database stays in use, for backups
+  backup(destination: string): Promise<unknown> {
+    return this.db.backup(destination);
+  }
+
+  close(): void {
+    this.db.close();
+  }
+
+  private insert(entry: Required<Omit<EarningsHistoryEntry, 'hbdEarned'>>): void {
+    this.db.prepare(`
+      INSERT INTO earnings (at, passed, token, amount, cid, account, block, block_id, status, reference, fiat_value, fiat_currency)
+      VALUES (@at, @passed, @token, @amount, @cid, @account, @block, @blockId, @status, @reference, @fiatValue, @fiatCurrency)
+    `).run({ ...entry, passed: entry.passed ? 1 : 0 });
+    this.db.prepare(`
+      INSERT INTO earnings_daily (day, token, challenges, passed, amount) VALUES (@day, @token, 1, @passed, @amount)
+      ON CONFLICT (day, token) DO UPDATE SET
+        challenges = challenges + 1, passed = passed + excluded.passed, amount = amount + excluded.amount
+    `).run({ day: entry.at.slice(0, 10), token: entry.token, passed: entry.passed ? 1 : 0, amount: entry.amount });
+  }
+
+  private getBaseline(): Baseline {
+    return { totalHbd: 0, challengesPassed: 0, challengesFailed: 0, ...JSON.parse(this.getState('baseline') ?? '{}') };
+  }
+
+  private getState(key: string): string | null {
+    const row = this.db.prepare('SELECT value FROM earnings_state WHERE key = ?').get(key) as { value: string } | undefined;
+    return row ? row.value : null;
+  }
+
+  private setState(key: string, value: string): void {
+    this.db.prepare('INSERT INTO earnings_state (key, value) VALUES (?, ?) ON CONFLICT (key) DO UPDATE SET value = excluded.value')
+      .run(key, value);
+  }
+
+  private importLegacy(dataDir: string): void {
+    if (this.getState('importedAt')) return;
+    const files = [LEGACY_EARNINGS, LEGACY_HISTORY, LEGACY_ACCOUNTS].map((name) => path.join(dataDir, name));
+    const [earningsPath, historyPath, accountsPath] = files;
+    const legacy = readJson(earningsPath);
+    const legacyAccounts: Record<string, AccountEarnings> = readJson(accountsPath) ?? {};
+
+    this.db.transaction(() => {
+      const history = fs.existsSync(historyPath) ? fs.readFileSync(historyPath, 'utf-8').split('\n') : [];
+      for (const line of history) {
+        if (line.trim().length === 0) continue;
+        let entry: EarningsHistoryEntry;
+        try {
+          entry = JSON.parse(line);
+        } catch {
+          continue;
+        }
+        this.insert({
+          at: entry.at,
+          passed: entry.passed,
+          token: 'HBD',
+          amount: entry.hbdEarned,
+          cid: entry.cid ?? null,
+          account: entry.account ?? null,
+          block: entry.block ?? null,
+          blockId: entry.blockId ?? null,
+          status: entry.status ?? 'final',
+          reference: null,
+          fiatValue: null,
+          fiatCurrency: null,
+        });
+      }
+
+      if (legacy) {
+        const imported = this.getEarnings();
+        this.setState('baseline', JSON.stringify({
+          totalHbd: Math.max(0, (legacy.totalHbd ?? 0) - imported.totalHbd),
+          challengesPassed: Math.max(0, (legacy.challengesPassed ?? 0) - imported.challengesPassed),
+          challengesFailed: Math.max(0, (legacy.challengesFailed ?? 0) - imported.challengesFailed),
+        }));
+        this.setState('consecutivePasses', String(legacy.consecutivePasses ?? 0));
+        if (legacy.lastChallengeTime) this.setState('lastChallengeTime', legacy.lastChallengeTime);
+        if (legacy.lastPassedChallengeTime) this.setState('lastPassedChallengeTime', legacy.lastPassedChallengeTime);
+      }
+
+      const importedAccounts = this.getAccountEarnings();
+      const accountBaselines: Record<string, AccountEarnings> = {};
+      for (const [account, totals] of Object.entries(legacyAccounts)) {
+        const seen = importedAccounts[account];
+        accountBaselines[account] = {
+          totalHbd: Math.max(0, totals.totalHbd - (seen?.totalHbd ?? 0)),
+          challengesPassed: Math.max(0, totals.challengesPassed - (seen?.challengesPassed ?? 0)),
+          challengesFailed: Math.max(0, totals.challengesFailed - (seen?.challengesFailed ?? 0)),
+          lastChallengeTime: totals.lastChallengeTime,
+        };
+      }
+      this.setState('accountBaselines', JSON.stringify(accountBaselines));
+
+      // What was reached before the upgrade was announced then
+      this.reachMilestones(this.getEarnings(), DEFAULT_MILESTONE_SETTINGS);
+      this.setState('importedAt', new Date().toISOString());
+    })();
+
+    for (const file of files) {
+      if (fs.existsSync(file)) fs.renameSync(file, `${file}.imported`);
+    }
+    if (legacy) console.log('[Config] Imported earnings into earnings.db');
+  }
+}
@@ -0,0 +1,171 @@
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { ConnectivityMonitor } from './connectivity';
+import { ContractRecord } from './contracts';
+import { ValidatorConnectionStatus } from './poa-responder';
+
+// Validators skip storage nodes with less SPK power than this staked
+export const MIN_SPK_POWER = 1;
+const CACHE_MS = 5 * 60 * 1000;
+
+export type EligibilityCheck = 'account' | 'stake' | 'reachability' | 'registration' | 'contracts' | 'validators';
+
+export interface EligibilityItem {
+  check: EligibilityCheck;
+  status: 'ok' | 'warning' | 'blocking';
+  message: string;
+  // What the user can do about it; null when nothing is needed
+  fix: string | null;
+}
+
+// Chain balances, in whole tokens
+export interface SpkAccount {
+  spkPower: number;
+  liquidSpk: number;
+  broca: number;
+}
+
+export interface EligibilityReport {
+  // False when any item is blocking
+  eligible: boolean;
+  account: SpkAccount | null;
+  items: EligibilityItem[];
+  checkedAt: string;
+}
+
+export interface EligibilitySources {
+  contracts(): ContractRecord[];
+  validatorConnections(): ValidatorConnectionStatus[];
+}
+
+// The chain keeps amounts as integer thousandths; BROCA as "amount,block"
+export function fromMilli(value: unknown): number {
+  const amount = typeof value === 'string' ? parseInt(value.split(',')[0], 10) : Number(value);
+  return Number.isFinite(amount) ? amount / 1000 : 0;
+}
+
+export async function fetchSpkAccount(chainUrl: string, username: string): Promise<SpkAccount> {
+  const response = await axios.get(`${chainUrl}/@${encodeURIComponent(username)}`, { timeout: 10000 });
+  const data = response.data || {};
+  return {
+    spkPower: fromMilli(data.spk_power),
+    liquidSpk: fromMilli(data.spk),
+    broca: fromMilli(data.broca),
+  };
+}
+
+/**
+ * Answers "why am I (not) getting challenges": stake on the SPK chain,
+ * whether validators can reach the node, registration, and whether there
+ * is anything pinned under contract to be challenged on.
+ */
+export class EligibilityChecker {
+  private config: ConfigStore;
+  private connectivity: ConnectivityMonitor;
+  private sources: EligibilitySources;
+  private last: EligibilityReport | null = null;
+
+  constructor(config: ConfigStore, connectivity: ConnectivityMonitor, sources: EligibilitySources) {
+    this.config = config;
+    this.connectivity = connectivity;
+    this.sources = sources;
+  }
+
+  // Reuses the last report for a few minutes; the chain doesn't move that fast
+  async getReport(force = false): Promise<EligibilityReport> {
+    if (!force && this.last && Date.now() - new Date(this.last.checkedAt).getTime() < CACHE_MS) {
+      return this.last;
+    }
+    this.last = await this.check();
+    return this.last;
+  }
+
+  private async check(): Promise<EligibilityReport> {
+    const items: EligibilityItem[] = [];
+    const { hiveUsername, spkChainUrl } = this.config.getConfig();
+
+    let account: SpkAccount | null = null;
+    if (!hiveUsername) {
+      items.push({
+        check: 'account',
+        status: 'blocking',
+        message: 'No Hive account is linked',
+        fix: 'Enter your Hive username under Hive Account',
+      });
+    } else {
+      try {
+        account = await fetchSpkAccount(spkChainUrl, hiveUsername);
+      } catch (error: any) {
+        items.push({
+          check: 'stake',
+          status: 'warning',
+          message: `Could not read @${hiveUsername} from the SPK chain: ${error.message}`,
+          fix: 'Check the SPK chain URL and your internet connection',
+        });
+      }
+    }
+    if (account) {
+      items.push(account.spkPower >= MIN_SPK_POWER
+        ? { check: 'stake', status: 'ok', message: `${account.spkPower} SPK power staked`, fix: null }
+        : {
+          check: 'stake',
+          status: 'blocking',
+          message: `Only ${account.spkPower} SPK power staked; validators need at least ${MIN_SPK_POWER}`,
+          fix: account.liquidSpk > 0
+            ? `Power up some of your ${account.liquidSpk} liquid SPK`
+            : 'Acquire SPK and power it up',
+        });
+    }
+
+    const report = this.connectivity.getReport();
+    if (!report || report.reachability === 'Unknown') {
+      items.push({ check: 'reachability', status: 'warning', message: 'Reachability not checked yet', fix: null });
+    } else if (report.reachability === 'Unreachable') {
+      items.push({ check: 'reachability', status: 'blocking', message: 'Validators cannot reach this node', fix: report.hint });
+    } else if (report.reachability === 'Relayed') {
+      items.push({ check: 'reachability', status: 'warning', message: 'Only reachable through a relay; answers may miss the deadline', fix: report.hint });
+    } else {
+      items.push({ check: 'reachability', status: 'ok', message: 'Reachable from the internet', fix: null });
+    }
+
+    const registration = this.config.getRegistration();
+    items.push(registration.registeredAt && !registration.lastError
+      ? { check: 'registration', status: 'ok', message: 'Registered with the SPK network', fix: null }
+      : {
+        check: 'registration',
+        status: 'blocking',
+        message: registration.lastError ? `Registration failed: ${registration.lastError}` : 'Not registered with the SPK network',
+        fix: 'Use Re-register Now under Hive Account',
+      });
+
+    const pinned = this.sources.contracts().filter((contract) => contract.status !== 'pinning');
+    items.push(pinned.length > 0
+      ? { check: 'contracts', status: 'ok', message: `${pinned.length} storage contracts pinned`, fix: null }
+      : {
+        check: 'contracts',
+        status: 'blocking',
+        message: 'No storage contracts pinned; challenges are only issued for contracted content',
+        fix: 'Accept a storage contract from the SPK network',
+      });
+
+    const { enabled, validators } = this.config.getConfig().poa;
+    const connected = this.sources.validatorConnections().filter((connection) => connection.state === 'connected').length;
+    if (enabled && validators.length > 0) {
+      items.push(connected > 0
+        ? { check: 'validators', status: 'ok', message: `Connected to ${connected} of ${validators.length} validators`, fix: null }
+        : {
+          check: 'validators',
+          status: 'warning',
+          message: 'Not connected to any validator',
+          fix: 'Check the validator URLs under Proof of Access',
+        });
+    }
+
+    return {
+      eligible: !items.some((item) => item.status === 'blocking'),
+      account,
+      items,
+      checkedAt: new Date().toISOString(),
+    };
+  }
+}
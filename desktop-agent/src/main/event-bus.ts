@@ -0,0 +1,52 @@
+import { EventEmitter } from 'events';
+
+export const EVENT_TOPICS = ['status', 'challenge', 'pins', 'earnings'] as const;
+export type EventTopic = typeof EVENT_TOPICS[number];
+
+// Enough for a client that lost its connection for a few seconds
+const REPLAY_SIZE = 200;
+
+export interface StreamEvent {
+  id: number;
+  topic: EventTopic;
+  event: string;
+  data: unknown;
+  at: string;
+}
+
+export function parseTopics(value: unknown): EventTopic[] {
+  const list = Array.isArray(value) ? value : String(value || '').split(',');
+  return list
+    .map((topic) => String(topic).trim())
+    .filter((topic): topic is EventTopic => (EVENT_TOPICS as readonly string[]).includes(topic));
+}
+
+/**
+ * Single source of agent events. Transports (WebSocket, Server-Sent Events)
+ * subscribe to `event` and decide per client what to forward, so producers
+ * publish once no matter how clients are connected.
+ */
+export class EventBus extends EventEmitter {
+  private nextId = 1;
+  private recent: StreamEvent[] = [];
+
+  publish(topic: EventTopic, event: string, data: unknown): void {
+    const message: StreamEvent = { id: this.nextId++, topic, event, data, at: new Date().toISOString() };
+    this.recent.push(message);
+    if (this.recent.length > REPLAY_SIZE) this.recent.shift();
+    this.emit('event', message);
+  }
+
+  /**
+   * Events published after `id`, or null when some of them have already
+   * been forgotten (or `id` is from before an agent restart) and the
+   * client has to resync over REST.
+   */
+  since(id: number): StreamEvent[] | null {
+    const lastId = this.nextId - 1;
+    if (id === lastId) return [];
+    if (id > lastId) return null;
+    const missed = this.recent.filter((event) => event.id > id);
+    return missed.length > 0 && missed[0].id === id + 1 ? missed : null;
+  }
+}
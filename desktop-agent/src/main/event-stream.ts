@@ -0,0 +1,183 @@
+import * as http from 'http';
+import { WebSocketServer, WebSocket } from 'ws';
+import { ApiAuth } from './auth';
+import { CorsPolicy, normalizeOrigin } from './cors';
+import { EventBus, EventTopic, EVENT_TOPICS, StreamEvent, parseTopics } from './event-bus';
+
+const EVENTS_PATH = '/ws/events';
+const HEARTBEAT_MS = 30 * 1000;
+// Above this we drop messages for the client instead of queueing them
+const MAX_BUFFERED_BYTES = 1024 * 1024;
+// A client this far behind isn't reading at all
+const DISCONNECT_BUFFERED_BYTES = 8 * 1024 * 1024;
+
+export type UpgradeCheck = (req: http.IncomingMessage, token: string | null) => boolean;
+
+interface Subscriber {
+  socket: WebSocket;
+  topics: Set<EventTopic>;
+  alive: boolean;
+  dropped: number;
+}
+
+/**
+ * Pushes agent events from the bus to web clients over `/ws/events?token=…` so
+ * they don't have to poll. Clients pick topics with `?topics=status,pins` and can change
+ * them later by sending `{ type: 'subscribe' | 'unsubscribe', topics }`.
+ *
+ * Slow readers don't stall the agent: once a socket has too much queued,
+ * messages for it are dropped and counted, and the client gets a `dropped`
+ * notice when it catches up so it knows to refetch state over REST.
+ */
+export class EventStream {
+  private bus: EventBus;
+  private auth: ApiAuth;
+  private cors: CorsPolicy;
+  private onEvent = (message: StreamEvent) => this.forward(message);
+  private wss = new WebSocketServer({ noServer: true });
+  private subscribers = new Set<Subscriber>();
+  private heartbeat: NodeJS.Timeout | null = null;
+
+  constructor(bus: EventBus, auth: ApiAuth, cors: CorsPolicy) {
+    this.bus = bus;
+    this.auth = auth;
+    this.cors = cors;
+    this.bus.on('event', this.onEvent);
+  }
+
+  /**
+   * Takes over WebSocket upgrades on `/ws/events` for an HTTP server.
+   * Every client needs the API token; `allowClient` can still refuse a
+   * connection before the origin check.
+   */
+  attach(server: http.Server, allowClient: UpgradeCheck = () => true): void {
+    server.on('upgrade', (req, socket, head) => {
+      const url = new URL(req.url || '/', 'http://127.0.0.1');
+      if (url.pathname !== EVENTS_PATH) {
+        socket.destroy();
+        return;
+      }
+      // Browsers can't set headers on a WebSocket, so the token comes as `?token=`
+      const token = url.searchParams.get('token');
+      if (token === null || !this.auth.isValidToken(token)) {
+        socket.write('HTTP/1.1 401 Unauthorized\r\nConnection: close\r\n\r\n');
+        socket.destroy();
+        return;
+      }
+      if (!allowClient(req, token) || !this.isOriginAllowed(req.headers.origin)) {
+        socket.write('HTTP/1.1 403 Forbidden\r\nConnection: close\r\n\r\n');
+        socket.destroy();
+        return;
+      }
+
+      this.wss.handleUpgrade(req, socket, head, (ws) => {
+        const requested = parseTopics(url.searchParams.get('topics'));
+        this.addSubscriber(ws, requested.length > 0 ? requested : [...EVENT_TOPICS]);
+      });
+    });
+
+    if (!this.heartbeat) {
+      this.heartbeat = setInterval(() => this.checkAlive(), HEARTBEAT_MS);
+    }
+  }
+
+  private forward(message: StreamEvent): void {
+    if (this.subscribers.size === 0) return;
+    const payload = JSON.stringify(message);
+
+    for (const subscriber of this.subscribers) {
+      if (subscriber.topics.has(message.topic)) {
+        this.send(subscriber, payload);
+      }
+    }
+  }
+
+  getClientCount(): number {
+    return this.subscribers.size;
+  }
+
+  close(): void {
+    this.bus.off('event', this.onEvent);
+    if (this.heartbeat) {
+      clearInterval(this.heartbeat);
+      this.heartbeat = null;
+    }
+    for (const subscriber of this.subscribers) {
+      subscriber.socket.close(1001, 'Agent shutting down');
+    }
+    this.subscribers.clear();
+  }
+
+  // Opaque origins (file://, sandboxed frames) have already shown the token
+  private isOriginAllowed(origin: string | undefined): boolean {
+    if (!origin || origin === 'null') return true;
+    const normalized = normalizeOrigin(origin);
+    return normalized !== null && this.cors.isAllowed(normalized);
+  }
+
+  private addSubscriber(socket: WebSocket, topics: EventTopic[]): void {
+    const subscriber: Subscriber = { socket, topics: new Set(topics), alive: true, dropped: 0 };
+    this.subscribers.add(subscriber);
+    console.log(`[Events] Client connected (${topics.join(', ')})`);
+
+    socket.on('pong', () => { subscriber.alive = true; });
+    socket.on('message', (raw) => this.handleMessage(subscriber, raw.toString()));
+    socket.on('close', () => this.subscribers.delete(subscriber));
+    socket.on('error', () => this.subscribers.delete(subscriber));
+
+    this.send(subscriber, JSON.stringify({ type: 'subscribed', topics }));
+  }
+
+  private handleMessage(subscriber: Subscriber, raw: string): void {
+    let message: any;
+    try {
+      message = JSON.parse(raw);
+    } catch {
+      return;
+    }
+
+    const topics = parseTopics(message?.topics);
+    if (message?.type === 'subscribe') {
+      topics.forEach((topic) => subscriber.topics.add(topic));
+    } else if (message?.type === 'unsubscribe') {
+      topics.forEach((topic) => subscriber.topics.delete(topic));
+    } else {
+      return;
+    }
+    this.send(subscriber, JSON.stringify({ type: 'subscribed', topics: Array.from(subscriber.topics) }));
+  }
+
+  private send(subscriber: Subscriber, payload: string): void {
+    const { socket } = subscriber;
+    if (socket.readyState !== WebSocket.OPEN) return;
+
+    if (socket.bufferedAmount > DISCONNECT_BUFFERED_BYTES) {
+      console.warn('[Events] Disconnecting client that stopped reading');
+      socket.terminate();
+      this.subscribers.delete(subscriber);
+      return;
+    }
+    if (socket.bufferedAmount > MAX_BUFFERED_BYTES) {
+      subscriber.dropped++;
+      return;
+    }
+
+    if (subscriber.dropped > 0) {
+      socket.send(JSON.stringify({ type: 'dropped', count: subscriber.dropped }));
+      subscriber.dropped = 0;
+    }
+    socket.send(payload);
+  }
+
+  private checkAlive(): void {
+    for (const subscriber of this.subscribers) {
+      if (!subscriber.alive) {
+        subscriber.socket.terminate();
+        this.subscribers.delete(subscriber);
+        continue;
+      }
+      subscriber.alive = false;
+      subscriber.socket.ping();
+    }
+  }
+}
@@ -0,0 +1,118 @@
+import { EventEmitter } from 'events';
+import axios from 'axios';
+import { KuboManager } from './kubo';
+import { ConfigStore, GcStatus } from './config';
+import { ActivityTracker } from './activity';
+
+// How long to wait before retrying when GC is due but the node is busy
+const BUSY_RETRY_MS = 60 * 1000;
+const CHECK_INTERVAL_MS = 5 * 60 * 1000;
+
+/**
+ * Runs `repo gc` on the configured cadence, deferring while a PoA challenge
+ * is being answered or content is being added. Emits `collected` after
+ * each run.
+ */
+export class GcScheduler extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private activity: ActivityTracker;
+  private timer: NodeJS.Timeout | null = null;
+  private running = false;
+
+  constructor(kubo: KuboManager, config: ConfigStore, activity: ActivityTracker) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+    this.activity = activity;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.tick(), CHECK_INTERVAL_MS);
+    void this.tick();
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getStatus(): GcStatus & { running: boolean; nextRunAt: string | null } {
+    const status = this.config.getGcStatus();
+    const intervalHours = this.config.getConfig().gcIntervalHours;
+    let nextRunAt: string | null = null;
+    if (intervalHours > 0) {
+      const last = status.lastRunAt ? new Date(status.lastRunAt).getTime() : Date.now();
+      nextRunAt = new Date(last + intervalHours * 3600 * 1000).toISOString();
+    }
+    return { ...status, running: this.running, nextRunAt };
+  }
+
+  private async tick(): Promise<void> {
+    const intervalHours = this.config.getConfig().gcIntervalHours;
+    if (intervalHours <= 0 || this.running || !this.kubo.isRunning()) return;
+
+    const { lastRunAt } = this.config.getGcStatus();
+    const due = !lastRunAt || Date.now() - new Date(lastRunAt).getTime() >= intervalHours * 3600 * 1000;
+    if (!due) return;
+
+    if (this.activity.isBusy()) {
+      console.log('[GC] Node busy, deferring scheduled GC');
+      setTimeout(() => this.tick(), BUSY_RETRY_MS);
+      return;
+    }
+
+    try {
+      await this.runNow();
+    } catch (error: any) {
+      console.error('[GC] Scheduled GC failed:', error.message);
+    }
+  }
+
+  /**
+   * Runs GC immediately unless a challenge or upload is in progress.
+   */
+  async runNow(): Promise<GcStatus> {
+    if (this.running) {
+      throw new Error('Garbage collection already running');
+    }
+    if (this.activity.isBusy()) {
+      throw new Error('Cannot run garbage collection while a challenge or upload is in progress');
+    }
+
+    this.running = true;
+    const startedAt = Date.now();
+    try {
+      const before = await this.getRepoSize();
+      console.log('[GC] Running repo gc...');
+
+      const response = await axios.post(`${this.kubo.getApiUrl()}/api/v0/repo/gc?quiet=true`, null, {
+        timeout: 30 * 60 * 1000,
+        responseType: 'text',
+      });
+      const removedBlocks = String(response.data).split('\n').filter((line) => line.trim()).length;
+
+      const after = await this.getRepoSize();
+      const status: GcStatus = {
+        lastRunAt: new Date().toISOString(),
+        reclaimedBytes: Math.max(0, before - after),
+        removedBlocks,
+        durationMs: Date.now() - startedAt,
+      };
+      this.config.setGcStatus(status);
+      console.log(`[GC] Reclaimed ${status.reclaimedBytes} bytes (${removedBlocks} blocks) in ${status.durationMs}ms`);
+      this.emit('collected', status);
+      return status;
+    } finally {
+      this.running = false;
+    }
+  }
+
+  private async getRepoSize(): Promise<number> {
+    const stats = await this.kubo.getStats();
+    return stats?.repoSize || 0;
+  }
+}
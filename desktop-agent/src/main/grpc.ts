@@ -0,0 +1,414 @@
+import * as net from 'net';
+import axios from 'axios';
+import * as grpc from '@grpc/grpc-js';
+import * as protoLoader from '@grpc/proto-loader';
+import { parse } from 'protobufjs';
+import { EventBus, EVENT_TOPICS, StreamEvent, parseTopics } from './event-bus';
+import { ApiAuth } from './auth';
+import { RemoteAccessGuard } from './remote-access';
+import { ApiErrorCode } from './api-errors';
+import { API_PREFIX } from './openapi';
+import { TlsCredentials } from './tls';
+
+export interface GrpcSettings {
+  enabled: boolean;
+  port: number;
+}
+
+export const DEFAULT_GRPC_SETTINGS: GrpcSettings = {
+  enabled: false,
+  port: 5150,
+};
+
+export function validateGrpcSettings(settings: Partial<GrpcSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.enabled !== undefined && typeof settings.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  if (settings.port !== undefined && (!Number.isInteger(settings.port) || settings.port < 1024 || settings.port > 65535)) {
+    errors.push('port must be between 1024 and 65535');
+  }
+  return errors;
+}
+
+// Served at /api/v1/grpc/proto so clients can generate stubs from the running agent
+export const AGENT_PROTO = `syntax = "proto3";
+
+package spk.agent.v1;
+
+service Agent {
+  rpc GetStatus (Empty) returns (Status);
+  rpc GetEarnings (Empty) returns (Earnings);
+  rpc ListPins (ListPinsRequest) returns (PinPage);
+  rpc Pin (PinRequest) returns (PinResult);
+  rpc Unpin (UnpinRequest) returns (Empty);
+  rpc PinContract (PinContractRequest) returns (Contract);
+  rpc GetContract (GetContractRequest) returns (Contract);
+  rpc Challenge (ChallengeRequest) returns (ChallengeResult);
+  rpc SelfTest (SelfTestRequest) returns (SelfTestResult);
+  // Same events as /ws/events; an empty topic list means all topics
+  rpc StreamEvents (StreamEventsRequest) returns (stream Event);
+}
+
+message Empty {}
+
+message NodeStats {
+  uint64 repo_size = 1;
+  uint64 storage_max = 2;
+  uint64 num_objects = 3;
+  uint64 bandwidth_in = 4;
+  uint64 bandwidth_out = 5;
+}
+
+message Earnings {
+  double total_hbd = 1;
+  uint32 challenges_passed = 2;
+  uint32 challenges_failed = 3;
+  uint32 consecutive_passes = 4;
+  string last_challenge_time = 5;
+  string last_passed_challenge_time = 6;
+  double provisional_hbd = 7;
+}
+
+message Status {
+  bool running = 1;
+  string connection_mode = 2;
+  string peer_id = 3;
+  NodeStats stats = 4;
+  Earnings earnings = 5;
+  string version = 6;
+}
+
+message ListPinsRequest {
+  string type = 1;
+  string label = 2;
+  uint32 offset = 3;
+  uint32 limit = 4;
+  string cursor = 5;
+  string sort = 6;
+}
+
+message PinInfo {
+  string cid = 1;
+  string type = 2;
+  string instance = 3;
+  string name = 4;
+  repeated string labels = 5;
+  string pinned_at = 6;
+}
+
+message PinPage {
+  repeated PinInfo pins = 1;
+  uint32 total = 2;
+  uint32 offset = 3;
+  uint32 limit = 4;
+  string next_cursor = 5;
+}
+
+message PinRequest {
+  string cid = 1;
+  string name = 2;
+}
+
+message PinResult {
+  repeated string pins = 1;
+  string instance = 2;
+}
+
+message UnpinRequest {
+  string cid = 1;
+}
+
+message PinContractRequest {
+  string contract_id = 1;
+  repeated string cids = 2;
+  string expires_at = 3;
+}
+
+message GetContractRequest {
+  string id = 1;
+}
+
+message ContractCid {
+  string status = 1;
+  string error = 2;
+  string pinned_at = 3;
+}
+
+message Contract {
+  string id = 1;
+  string source = 2;
+  string status = 3;
+  string expires_at = 4;
+  map<string, ContractCid> cids = 5;
+  string created_at = 6;
+  string updated_at = 7;
+}
+
+message ChallengeRequest {
+  string cid = 1;
+  uint32 block_index = 2;
+  string salt = 3;
+  string validator_id = 4;
+}
+
+message ChallengeResult {
+  bool success = 1;
+  string proof = 2;
+  string block_cid = 3;
+  uint32 response_time = 4;
+}
+
+message SelfTestRequest {
+  string cid = 1;
+}
+
+message SelfTestResult {
+  bool success = 1;
+  string cid = 2;
+  uint32 block_index = 3;
+  string salt = 4;
+  string instance = 5;
+  string proof = 6;
+  string block_cid = 7;
+  uint32 block_count = 8;
+  uint32 block_bytes = 9;
+  uint32 refs_ms = 10;
+  uint32 block_read_ms = 11;
+  uint32 response_time = 12;
+}
+
+message StreamEventsRequest {
+  repeated string topics = 1;
+}
+
+message Event {
+  uint64 id = 1;
+  string topic = 2;
+  string event = 3;
+  // JSON, shaped as on /ws/events
+  string data = 4;
+  string at = 5;
+}
+`;
+
+const STATUS_FOR_CODE: Record<ApiErrorCode, grpc.status> = {
+  INVALID_REQUEST: grpc.status.INVALID_ARGUMENT,
+  UNAUTHORIZED: grpc.status.UNAUTHENTICATED,
+  FORBIDDEN: grpc.status.PERMISSION_DENIED,
+  APPROVAL_PENDING: grpc.status.PERMISSION_DENIED,
+  NOT_FOUND: grpc.status.NOT_FOUND,
+  CONFLICT: grpc.status.FAILED_PRECONDITION,
+  UNSUPPORTED_MEDIA_TYPE: grpc.status.INVALID_ARGUMENT,
+  RATE_LIMITED: grpc.status.RESOURCE_EXHAUSTED,
+  INTERNAL: grpc.status.INTERNAL,
+  IPFS_ERROR: grpc.status.INTERNAL,
+  DAEMON_UNAVAILABLE: grpc.status.UNAVAILABLE,
+  DAEMON_TIMEOUT: grpc.status.DEADLINE_EXCEEDED,
+  SPK_API_UNAVAILABLE: grpc.status.UNAVAILABLE,
+  STORAGE_FULL: grpc.status.RESOURCE_EXHAUSTED,
+};
+
+interface Route {
+  method: 'get' | 'post' | 'delete';
+  // Reachable from other machines without the token, like its REST route
+  tokenless?: boolean;
+  path: (request: any) => string;
+  body?: (request: any) => unknown;
+  query?: (request: any) => Record<string, unknown>;
+}
+
+// Each RPC is the REST route it mirrors, so both stay one implementation
+const ROUTES: Record<string, Route> = {
+  GetStatus: { method: 'get', path: () => '/status' },
+  GetEarnings: { method: 'get', path: () => '/earnings' },
+  ListPins: {
+    method: 'get',
+    path: () => '/pins',
+    query: (r) => ({
+      type: r.type || undefined,
+      label: r.label || undefined,
+      offset: r.offset || undefined,
+      limit: r.limit || undefined,
+      cursor: r.cursor || undefined,
+      sort: r.sort || undefined,
+    }),
+  },
+  Pin: { method: 'post', path: () => '/pin', body: (r) => ({ cid: r.cid, name: r.name || undefined }) },
+  Unpin: { method: 'delete', path: (r) => `/pins/${encodeURIComponent(r.cid)}` },
+  PinContract: {
+    method: 'post',
+    path: () => '/contracts/pin',
+    body: (r) => ({
+      contractId: r.contractId || undefined,
+      cids: r.cids.length > 0 ? r.cids : undefined,
+      expiresAt: r.expiresAt || undefined,
+    }),
+  },
+  GetContract: { method: 'get', path: (r) => `/contracts/${encodeURIComponent(r.id)}` },
+  Challenge: {
+    method: 'post',
+    tokenless: true,
+    path: () => '/challenge',
+    body: (r) => ({ cid: r.cid, blockIndex: r.blockIndex, salt: r.salt, validatorId: r.validatorId || undefined }),
+  },
+  SelfTest: { method: 'post', path: () => '/poa/selftest', body: (r) => ({ cid: r.cid || undefined }) },
+};
+
+// grpc-js peers look like ipv4:10.0.0.5:51234 or ipv6:[::1]:51234
+function peerAddress(peer: string): string {
+  const match = peer.match(/^ipv[46]:\[?([^\]]+?)\]?:\d+$/);
+  return match && net.isIP(match[1]) ? match[1] : peer;
+}
+
+// proto3 has no null; empty strings stand in for absent values
+function withoutNulls(value: any): any {
+  if (value === null) return undefined;
+  if (Array.isArray(value)) return value.map(withoutNulls);
+  if (typeof value === 'object') {
+    return Object.fromEntries(Object.entries(value).map(([key, entry]) => [key, withoutNulls(entry)]));
+  }
+  return value;
+}
+
+/**
+ * gRPC front end for validators and automation that prefer typed clients
+ * and streaming over polling. Unary RPCs are forwarded to the REST API on
+ * this machine with the caller's token, so auth, rate limits, the audit log
+ * and error codes behave exactly as over HTTP. Events stream straight from
+ * the bus.
+ */
+export class GrpcGateway {
+  private bus: EventBus;
+  private remoteAccess: RemoteAccessGuard;
+  private auth: ApiAuth;
+  private getApiBase: () => string;
+  private server: grpc.Server | null = null;
+  private port: number | null = null;
+  // Open event streams and how to unsubscribe each from the bus
+  private streams = new Map<grpc.ServerWritableStream<any, any>, () => void>();
+
+  constructor(bus: EventBus, remoteAccess: RemoteAccessGuard, auth: ApiAuth, getApiBase: () => string) {
+    this.bus = bus;
+    this.remoteAccess = remoteAccess;
+    this.auth = auth;
+    this.getApiBase = getApiBase;
+  }
+
+  getPort(): number | null {
+    return this.port;
+  }
+
+  async start(host: string, port: number, tls: TlsCredentials | null): Promise<void> {
+    await this.stop();
+
+    const root = parse(AGENT_PROTO, { keepCase: false }).root;
+    const definition = protoLoader.fromJSON(root.toJSON(), { longs: Number, defaults: true });
+    const packageObject = grpc.loadPackageDefinition(definition) as any;
+    const service = packageObject.spk.agent.v1.Agent.service;
+
+    const server = new grpc.Server();
+    const handlers: grpc.UntypedServiceImplementation = {};
+    for (const [name, route] of Object.entries(ROUTES)) {
+      handlers[name] = (call: grpc.ServerUnaryCall<any, any>, callback: grpc.sendUnaryData<any>) => {
+        this.forward(call, route).then((response) => callback(null, response), (error) => callback(error));
+      };
+    }
+    handlers.StreamEvents = (call: grpc.ServerWritableStream<any, any>) => this.streamEvents(call);
+    server.addService(service, handlers);
+
+    const credentials = tls
+      ? grpc.ServerCredentials.createSsl(null, [{ private_key: Buffer.from(tls.key), cert_chain: Buffer.from(tls.cert) }])
+      : grpc.ServerCredentials.createInsecure();
+    const address = net.isIPv6(host) ? `[${host}]:${port}` : `${host}:${port}`;
+    this.port = await new Promise<number>((resolve, reject) => {
+      server.bindAsync(address, credentials, (error, boundPort) => (error ? reject(error) : resolve(boundPort)));
+    });
+    this.server = server;
+    console.log(`[gRPC] Listening on ${address}${tls ? ' (TLS)' : ''}`);
+  }
+
+  async stop(): Promise<void> {
+    const server = this.server;
+    if (!server) return;
+    this.server = null;
+    this.port = null;
+    for (const [call, unsubscribe] of this.streams) {
+      unsubscribe();
+      call.end();
+    }
+    this.streams.clear();
+    await new Promise<void>((resolve) => server.tryShutdown(() => resolve()));
+  }
+
+  private token(call: grpc.ServerSurfaceCall): string | null {
+    const header = String(call.metadata.get('authorization')[0] || '');
+    return header.match(/^Bearer\s+(\S+)$/i)?.[1] || null;
+  }
+
+  private denied(message: string): Error {
+    return Object.assign(new Error(message), { code: grpc.status.PERMISSION_DENIED, details: message });
+  }
+
+  private async forward(call: grpc.ServerUnaryCall<any, any>, route: Route): Promise<unknown> {
+    const token = this.token(call);
+    // Forwarded requests look local to the REST guard, so remote callers are checked here
+    if (!this.remoteAccess.allowsPeer(peerAddress(call.getPeer()), route.tokenless ? null : token, route.tokenless)) {
+      throw this.denied('This address or token is not allowed to reach the agent');
+    }
+    const response = await axios.request({
+      method: route.method,
+      url: `${this.getApiBase()}${API_PREFIX}${route.path(call.request)}`,
+      params: route.query?.(call.request),
+      data: route.body?.(call.request),
+      headers: {
+        'User-Agent': `spk-agent-grpc (${call.getPeer()})`,
+        ...(token ? { Authorization: `Bearer ${token}` } : {}),
+      },
+      validateStatus: () => true,
+    });
+
+    if (response.status >= 400) {
+      const code: ApiErrorCode = response.data?.code || 'INTERNAL';
+      const metadata = new grpc.Metadata();
+      metadata.set('spk-error-code', code);
+      throw Object.assign(new Error(response.data?.message || `HTTP ${response.status}`), {
+        code: STATUS_FOR_CODE[code] ?? grpc.status.UNKNOWN,
+        details: response.data?.message || `HTTP ${response.status}`,
+        metadata,
+      });
+    }
+    return withoutNulls(response.data) ?? {};
+  }
+
+  private streamEvents(call: grpc.ServerWritableStream<any, any>): void {
+    // Unlike the unary RPCs there's no REST request behind this to check the token, so local callers need it too
+    const token = this.token(call);
+    if (token === null || !this.auth.isValidToken(token)) {
+      call.emit('error', Object.assign(new Error('StreamEvents requires the API token'), {
+        code: grpc.status.UNAUTHENTICATED,
+        details: 'StreamEvents requires the API token',
+      }));
+      return;
+    }
+    if (!this.remoteAccess.allowsPeer(peerAddress(call.getPeer()), token)) {
+      call.emit('error', this.denied('This address or token is not allowed to reach the agent'));
+      return;
+    }
+
+    const requested = parseTopics(call.request.topics);
+    const topics = new Set(requested.length > 0 ? requested : EVENT_TOPICS);
+    const onEvent = (message: StreamEvent) => {
+      if (!topics.has(message.topic)) return;
+      call.write({ ...message, data: JSON.stringify(message.data ?? null) });
+    };
+
+    const unsubscribe = () => this.bus.off('event', onEvent);
+    this.streams.set(call, unsubscribe);
+    this.bus.on('event', onEvent);
+    call.on('cancelled', () => {
+      unsubscribe();
+      this.streams.delete(call);
+    });
+  }
+}
@@ -0,0 +1,106 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { KuboManager } from './kubo';
+import { ConfigStore } from './config';
+import { StartupState } from './startup';
+import { MIN_FREE_BYTES, freeBytes } from './preflight';
+
+const READY_STATES: StartupState[] = ['DaemonUp', 'Online'];
+
+export interface HealthCheck {
+  ok: boolean;
+  detail: string;
+}
+
+export interface HealthReport {
+  status: 'ok' | 'degraded' | 'down';
+  checkedAt: string;
+  uptimeSec: number;
+  daemon: HealthCheck & { state: StartupState; running: boolean; external: boolean };
+  repoLock: HealthCheck & { locked: boolean | null };
+  disk: HealthCheck & { freeBytes: number | null; minFreeBytes: number };
+  peers: HealthCheck & { count: number | null };
+  poa: HealthCheck & { lastSuccessAt: string | null; lastChallengeAt: string | null };
+}
+
+/**
+ * Backs `/healthz` and `/readyz` for supervisors (systemd, Docker, uptime
+ * monitors). Liveness only says the agent process answers; readiness says
+ * the node behind it can actually serve pins and challenges.
+ */
+export class HealthMonitor {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  async check(): Promise<HealthReport> {
+    const external = this.kubo.isExternal();
+    const state = this.kubo.getStartupState();
+    const running = this.kubo.isRunning();
+    const [peerCount, free] = await Promise.all([
+      running ? this.kubo.getPeerCount() : Promise.resolve(null),
+      external ? Promise.resolve(null) : freeBytes(this.kubo.getRepoPath()),
+    ]);
+    const earnings = this.config.getEarnings();
+
+    const daemon = {
+      ok: running && (external || READY_STATES.includes(state)),
+      detail: running ? `Daemon is ${external ? 'reachable' : state}` : 'Daemon is not running',
+      state,
+      running,
+      external,
+    };
+    const repoLock = this.checkRepoLock(external, running);
+    const disk = {
+      ok: free === null || free >= MIN_FREE_BYTES,
+      detail: free === null
+        ? 'Free space could not be determined'
+        : `${(free / 1024 / 1024 / 1024).toFixed(1)} GB free on the repo drive`,
+      freeBytes: free,
+      minFreeBytes: MIN_FREE_BYTES,
+    };
+    const peers = {
+      ok: (peerCount || 0) > 0,
+      detail: peerCount === null ? 'Peer list unavailable' : `${peerCount} connected peers`,
+      count: peerCount,
+    };
+    // Informational: a new node has simply not been challenged yet
+    const poa = {
+      ok: true,
+      detail: earnings.lastPassedChallengeTime ? 'Has passed a challenge' : 'No challenge passed yet',
+      lastSuccessAt: earnings.lastPassedChallengeTime,
+      lastChallengeAt: earnings.lastChallengeTime,
+    };
+
+    const ready = daemon.ok && repoLock.ok && disk.ok && peers.ok;
+    return {
+      status: ready ? 'ok' : daemon.ok ? 'degraded' : 'down',
+      checkedAt: new Date().toISOString(),
+      uptimeSec: Math.round(process.uptime()),
+      daemon,
+      repoLock,
+      disk,
+      peers,
+      poa,
+    };
+  }
+
+  // A lock with no daemon of ours means a crashed or foreign process holds the repo
+  private checkRepoLock(external: boolean, running: boolean): HealthReport['repoLock'] {
+    if (external) {
+      return { ok: true, detail: 'Repo is managed by the external node', locked: null };
+    }
+    const locked = fs.existsSync(path.join(this.kubo.getRepoPath(), 'repo.lock'));
+    if (locked && !running) {
+      return { ok: false, detail: 'Repo is locked but our daemon is not running', locked };
+    }
+    if (!locked && running) {
+      return { ok: false, detail: 'Daemon is running without holding the repo lock', locked };
+    }
+    return { ok: true, detail: locked ? 'Held by our daemon' : 'Unlocked', locked };
+  }
+}
@@ -0,0 +1,272 @@
+import axios from 'axios';
+import { Client } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+
+// Public API nodes run by well-known witnesses, tried in this order until health checks say otherwise
+export const DEFAULT_HIVE_NODES = [
+  'https://api.hive.blog',
+  'https://api.deathwing.me',
+  'https://api.openhive.network',
+  'https://hive-api.arcange.eu',
+  'https://techcoderx.com',
+  'https://rpc.mahdiyari.info',
+  'https://anyx.io',
+];
+
+const REQUEST_TIMEOUT_MS = 8000;
+const CHECK_TIMEOUT_MS = 5000;
+// How many nodes one call tries before giving up
+const MAX_ATTEMPTS = 3;
+// Consecutive failures after which a node goes to the back of the list
+const FAILURE_LIMIT = 3;
+// A node this far behind the best head block serves stale state (one minute)
+const MAX_LAG_BLOCKS = 20;
+// Assumed for nodes that haven't answered yet
+const UNKNOWN_LATENCY_MS = 1000;
+// Weight of the newest sample in the latency average
+const LATENCY_SMOOTHING = 0.3;
+
+export interface HiveNodeSettings {
+  // Tried ahead of the defaults, in this order
+  customNodes: string[];
+  // Off to use only the custom nodes, e.g. a private node
+  includeDefaults: boolean;
+  checkMinutes: number;
+}
+
+export const DEFAULT_HIVE_NODE_SETTINGS: HiveNodeSettings = {
+  customNodes: [],
+  includeDefaults: true,
+  checkMinutes: 10,
+};
+
+export function validateHiveNodeSettings(settings: Partial<HiveNodeSettings>, current: HiveNodeSettings): string[] {
+  const errors: string[] = [];
+  if (settings.customNodes !== undefined) {
+    if (!Array.isArray(settings.customNodes)) {
+      errors.push('customNodes must be a list of URLs');
+    } else {
+      for (const url of settings.customNodes) {
+        if (typeof url !== 'string' || !/^https?:\/\/[^\s/]+/.test(url)) errors.push(`${url} is not an http(s) URL`);
+      }
+    }
+  }
+  if (settings.includeDefaults !== undefined && typeof settings.includeDefaults !== 'boolean') {
+    errors.push('includeDefaults must be a boolean');
+  }
+  if (settings.checkMinutes !== undefined
+    && (!Number.isInteger(settings.checkMinutes) || settings.checkMinutes < 1 || settings.checkMinutes > 24 * 60)) {
+    errors.push('checkMinutes must be between 1 and 1440');
+  }
+  const merged = { ...current, ...settings };
+  if (errors.length === 0 && !merged.includeDefaults && merged.customNodes.length === 0) {
+    errors.push('Add a custom node before turning off the default nodes');
+  }
+  return errors;
+}
+
+export interface HiveNodeHealth {
+  url: string;
+  custom: boolean;
+  // Smoothed over recent calls and checks; null until the node has answered
+  latencyMs: number | null;
+  headBlock: number | null;
+  // Blocks behind the best head seen at the last check
+  lagBlocks: number | null;
+  consecutiveFailures: number;
+  lastError: string | null;
+  checkedAt: string | null;
+  healthy: boolean;
+  // Lower is better; the list is tried in this order
+  score: number;
+}
+
+// Calls go through the pool, so every dhive helper (database, broadcast, ...) fails over
+class FailoverClient extends Client {
+  constructor(private pool: HiveRpcPool) {
+    super(pool.ranked(), { timeout: REQUEST_TIMEOUT_MS });
+  }
+
+  call(api: string, method: string, params: any = []): Promise<any> {
+    return this.pool.call(api, method, params);
+  }
+}
+
+/**
+ * The Hive API nodes the agent talks to, ranked by health. Every node is
+ * checked on an interval for latency and how far its head block trails the
+ * others; calls also feed the figures. A call that fails to reach a node,
+ * or times out, moves on to the next one, and a node that keeps failing or
+ * falls behind drops to the back. Errors the chain itself returns (a bad
+ * key, a rejected transaction) come from a working node and don't fail over.
+ */
+export class HiveRpcPool {
+  private config: ConfigStore;
+  private client: FailoverClient;
+  private nodeClients = new Map<string, Client>();
+  private health = new Map<string, HiveNodeHealth>();
+  private timer: NodeJS.Timeout | null = null;
+  private preferred: string | null = null;
+
+  constructor(config: ConfigStore) {
+    this.config = config;
+    this.client = new FailoverClient(this);
+  }
+
+  getClient(): Client {
+    return this.client;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.check().catch(() => undefined);
+    const { checkMinutes } = this.config.getConfig().hiveNodes;
+    this.timer = setInterval(() => this.check().catch(() => undefined), checkMinutes * 60 * 1000);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  // Picks up new nodes or a new check interval
+  restart(): void {
+    this.stop();
+    this.start();
+  }
+
+  // Best first
+  getHealth(): HiveNodeHealth[] {
+    const nodes = this.nodes();
+    return nodes
+      .map((url, index) => this.healthOf(url, index))
+      .sort((a, b) => a.score - b.score);
+  }
+
+  ranked(): string[] {
+    return this.getHealth().map((node) => node.url);
+  }
+
+  async call(api: string, method: string, params: any): Promise<any> {
+    const failures: string[] = [];
+    for (const url of this.ranked().slice(0, MAX_ATTEMPTS)) {
+      const started = Date.now();
+      try {
+        const result = await this.nodeClient(url).call(api, method, params);
+        this.observe(url, Date.now() - started, null);
+        this.notePreferred();
+        return result;
+      } catch (error: any) {
+        if (error.name === 'RPCError') {
+          this.observe(url, Date.now() - started, null);
+          throw error;
+        }
+        this.observe(url, null, error.message);
+        failures.push(`${new URL(url).host}: ${error.message}`);
+        console.warn(`[Hive] ${url} failed ${api}.${method} (${error.message}); trying the next node`);
+      }
+    }
+    throw new Error(`No Hive API node answered: ${failures.join('; ')}`);
+  }
+
+  // Asks every node for the head block at once, then re-ranks
+  async check(): Promise<HiveNodeHealth[]> {
+    const nodes = this.nodes();
+    const results = await Promise.all(nodes.map(async (url) => {
+      const started = Date.now();
+      try {
+        const response = await axios.post(url, {
+          jsonrpc: '2.0',
+          method: 'condenser_api.get_dynamic_global_properties',
+          params: [],
+          id: 1,
+        }, { timeout: CHECK_TIMEOUT_MS });
+        const headBlock = Number(response.data?.result?.head_block_number);
+        if (!(headBlock > 0)) throw new Error(response.data?.error?.message || 'No head block in the response');
+        return { url, latency: Date.now() - started, headBlock, error: null };
+      } catch (error: any) {
+        return { url, latency: null, headBlock: null, error: error.message as string };
+      }
+    }));
+
+    const bestHead = Math.max(0, ...results.map((result) => result.headBlock ?? 0));
+    const now = new Date().toISOString();
+    for (const result of results) {
+      this.observe(result.url, result.latency, result.error);
+      const health = this.healthOf(result.url, nodes.indexOf(result.url));
+      health.checkedAt = now;
+      if (result.headBlock !== null) {
+        health.headBlock = result.headBlock;
+        health.lagBlocks = bestHead - result.headBlock;
+      }
+    }
+    this.notePreferred();
+    return this.getHealth();
+  }
+
+  private nodes(): string[] {
+    const { customNodes, includeDefaults } = this.config.getConfig().hiveNodes;
+    const nodes = [...customNodes, ...(includeDefaults ? DEFAULT_HIVE_NODES : [])];
+    return nodes.filter((url, index) => nodes.indexOf(url) === index);
+  }
+
+  private nodeClient(url: string): Client {
+    let client = this.nodeClients.get(url);
+    if (!client) {
+      client = new Client(url, { timeout: REQUEST_TIMEOUT_MS });
+      this.nodeClients.set(url, client);
+    }
+    return client;
+  }
+
+  private healthOf(url: string, index: number): HiveNodeHealth {
+    let health = this.health.get(url);
+    if (!health) {
+      health = {
+        url,
+        custom: false,
+        latencyMs: null,
+        headBlock: null,
+        lagBlocks: null,
+        consecutiveFailures: 0,
+        lastError: null,
+        checkedAt: null,
+        healthy: true,
+        score: 0,
+      };
+      this.health.set(url, health);
+    }
+    health.custom = this.config.getConfig().hiveNodes.customNodes.includes(url);
+    health.healthy = health.consecutiveFailures < FAILURE_LIMIT && (health.lagBlocks ?? 0) <= MAX_LAG_BLOCKS;
+    // Unhealthy nodes stay last resorts; the list order breaks ties
+    health.score = (health.latencyMs ?? UNKNOWN_LATENCY_MS)
+      + health.consecutiveFailures * REQUEST_TIMEOUT_MS
+      + (health.healthy ? 0 : 1e6)
+      + index;
+    return health;
+  }
+
+  private observe(url: string, latency: number | null, error: string | null): void {
+    const health = this.healthOf(url, this.nodes().indexOf(url));
+    if (error !== null) {
+      health.consecutiveFailures++;
+      health.lastError = error;
+      return;
+    }
+    health.consecutiveFailures = 0;
+    health.lastError = null;
+    if (latency !== null) {
+      health.latencyMs = health.latencyMs === null
+        ? latency
+        : Math.round(health.latencyMs * (1 - LATENCY_SMOOTHING) + latency * LATENCY_SMOOTHING);
+    }
+  }
+
+  private notePreferred(): void {
+    const [best] = this.ranked();
+    if (best && best !== this.preferred) {
+      if (this.preferred) console.log(`[Hive] Preferring ${best} over ${this.preferred}`);
+      this.preferred = best;
+    }
+  }
+}
@@ -0,0 +1,538 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as http from 'http';
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import { shell } from 'electron';
+import WebSocket from 'ws';
+import QRCode from 'qrcode';
+import { Operation, SignedTransaction } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+import { HiveAccount, HiveKeyRole } from './hive';
+import { SecretStore, VaultLockedError } from './secrets';
+
+// Time the user has to approve a request in Keychain or their HAS wallet
+const SIGN_TIMEOUT_MS = 5 * 60 * 1000;
+// How long a locally signed transaction stays valid; Hive allows up to an hour
+export const TX_EXPIRATION_MS = 60 * 1000;
+const APP_NAME = 'SPK Desktop Agent';
+
+// 'keys' signs with keys stored at login; the others never see a private key
+export type HiveSigningMethod = 'keys' | 'keychain' | 'has';
+const SIGNING_METHODS: HiveSigningMethod[] = ['keys', 'keychain', 'has'];
+
+export interface HiveSigningSettings {
+  method: HiveSigningMethod;
+  // Hive Authentication Services relay the wallet app is reached through
+  hasServer: string;
+}
+
+export const DEFAULT_HIVE_SIGNING_SETTINGS: HiveSigningSettings = {
+  method: 'keys',
+  hasServer: 'wss://hive-auth.arcange.eu',
+};
+
+export function validateHiveSigningSettings(settings: Partial<HiveSigningSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.method !== undefined && !SIGNING_METHODS.includes(settings.method)) {
+    errors.push(`method must be one of: ${SIGNING_METHODS.join(', ')}`);
+  }
+  if (settings.hasServer !== undefined
+    && (typeof settings.hasServer !== 'string' || !/^wss?:\/\/\S+$/.test(settings.hasServer))) {
+    errors.push('hasServer must be a ws:// or wss:// URL');
+  }
+  return errors;
+}
+
+export interface SigningRequest {
+  id: string;
+  description: string;
+  role: HiveKeyRole;
+  method: HiveSigningMethod;
+  createdAt: string;
+  // Keychain: the page to open in a browser with the extension
+  handoffUrl: string | null;
+  // HAS: the link a wallet app takes, and the same as a QR code image
+  hasUri: string | null;
+  qrCode: string | null;
+}
+
+export interface BroadcastResult {
+  txId: string;
+  method: HiveSigningMethod;
+}
+
+export interface HasSessionStatus {
+  username: string;
+  expiresAt: string;
+}
+
+// Persisted HAS pairing; the auth key is sealed, except in sessions paired before that was required
+interface StoredHasSession {
+  username: string;
+  server: string;
+  token: string;
+  expire: number;
+  authKey: string;
+  encrypted: boolean;
+}
+
+interface HandoffPayload {
+  username: string;
+  operations: Operation[];
+  role: HiveKeyRole;
+}
+
+interface PendingSign {
+  request: SigningRequest;
+  settle: (error: Error | null, txId?: string) => void;
+}
+
+/**
+ * CryptoJS-compatible AES, which HAS wallets use for every payload:
+ * OpenSSL's "Salted__" format with the key and IV derived from the
+ * passphrase by EVP_BytesToKey over MD5.
+ */
+function deriveKeyIv(passphrase: string, salt: Buffer): { key: Buffer; iv: Buffer } {
+  let derived = Buffer.alloc(0);
+  let block = Buffer.alloc(0);
+  while (derived.length < 48) {
+    block = crypto.createHash('md5').update(Buffer.concat([block, Buffer.from(passphrase), salt])).digest();
+    derived = Buffer.concat([derived, block]);
+  }
+  return { key: derived.subarray(0, 32), iv: derived.subarray(32, 48) };
+}
+
+export function hasEncrypt(plaintext: string, passphrase: string): string {
+  const salt = crypto.randomBytes(8);
+  const { key, iv } = deriveKeyIv(passphrase, salt);
+  const cipher = crypto.createCipheriv('aes-256-cbc', key, iv);
+  return Buffer.concat([Buffer.from('Salted__'), salt, cipher.update(plaintext, 'utf-8'), cipher.final()]).toString('base64');
+}
+
+export function hasDecrypt(ciphertext: string, passphrase: string): string {
+  const raw = Buffer.from(ciphertext, 'base64');
+  if (raw.subarray(0, 8).toString() !== 'Salted__') throw new Error('Not a HAS-encrypted payload');
+  const { key, iv } = deriveKeyIv(passphrase, raw.subarray(8, 16));
+  const decipher = crypto.createDecipheriv('aes-256-cbc', key, iv);
+  return Buffer.concat([decipher.update(raw.subarray(16)), decipher.final()]).toString('utf-8');
+}
+
+function escapeHtml(value: string): string {
+  return value.replace(/[&<>"']/g, (char) => `&#${char.charCodeAt(0)};`);
+}
+
+/**
+ * Broadcasts Hive transactions for the node's account. With stored keys it
+ * signs locally; with Hive Keychain it opens a one-time page on loopback in
+ * the user's browser, where the extension signs; with Hive Authentication
+ * Services it relays the transaction to the user's wallet app, paired once
+ * by QR code. Only 'keys' ever needs a private key in the agent.
+ *
+ * Each request waiting on the user is announced with a `request` event, so
+ * the dashboard can show the link or QR code, and a `resolved` event once
+ * it is signed, rejected or timed out.
+ */
+export class HiveSigner extends EventEmitter {
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private secrets: SecretStore;
+  private sessionPath: string;
+  private pending = new Map<string, PendingSign>();
+  private handoffServer: http.Server | null = null;
+  private handoffPayloads = new Map<string, HandoffPayload>();
+  private handoffSecret = crypto.randomBytes(16).toString('hex');
+
+  constructor(dataDir: string, config: ConfigStore, account: HiveAccount, secrets: SecretStore) {
+    super();
+    this.config = config;
+    this.account = account;
+    this.secrets = secrets;
+    this.sessionPath = path.join(dataDir, 'hive-auth.json');
+  }
+
+  stop(): void {
+    for (const { settle } of this.pending.values()) settle(new Error('The agent is shutting down'));
+    this.handoffServer?.close();
+    this.handoffServer = null;
+  }
+
+  getPending(): SigningRequest[] {
+    return Array.from(this.pending.values()).map(({ request }) => request);
+  }
+
+  cancel(id: string): boolean {
+    const entry = this.pending.get(id);
+    if (!entry) return false;
+    entry.settle(new Error('Cancelled'));
+    return true;
+  }
+
+  // The current HAS pairing, or null when the wallet app must be paired again (or the vault is locked)
+  getHasSession(): HasSessionStatus | null {
+    let session: StoredHasSession | null;
+    try {
+      session = this.readSession();
+    } catch {
+      return null;
+    }
+    return session ? { username: session.username, expiresAt: new Date(session.expire).toISOString() } : null;
+  }
+
+  forgetHasSession(): void {
+    if (fs.existsSync(this.sessionPath)) fs.unlinkSync(this.sessionPath);
+  }
+
+  async broadcast(description: string, operations: Operation[], role: HiveKeyRole): Promise<BroadcastResult> {
+    const username = this.config.getConfig().hiveUsername;
+    if (!username) throw new Error('Log in with a Hive account first');
+    const { method } = this.config.getConfig().hiveSigning;
+    let txId: string;
+    if (method === 'keychain') {
+      txId = await this.signWithKeychain(username, description, operations, role);
+    } else if (method === 'has') {
+      txId = await this.signWithHas(username, description, operations, role);
+    } else {
+      txId = (await this.account.getClient().broadcast.send(await this.sign(operations, role))).id;
+    }
+    console.log(`[Hive] Broadcast ${description} (${method}): ${txId}`);
+    return { txId, method };
+  }
+
+  /**
+   * Signs with the stored key without broadcasting, so the same transaction
+   * (and ID) can be sent again after a failed broadcast until it expires.
+   */
+  async sign(operations: Operation[], role: HiveKeyRole): Promise<SignedTransaction> {
+    const key = this.account.getKey(role);
+    if (!key) {
+      throw new Error(`No ${role} key is stored; log in with it, or sign with Hive Keychain or HAS instead`);
+    }
+    const client = this.account.getClient();
+    const props = await client.database.getDynamicGlobalProperties();
+    return client.broadcast.sign({
+      ref_block_num: props.head_block_number & 0xffff,
+      ref_block_prefix: Buffer.from(props.head_block_id, 'hex').readUInt32LE(4),
+      expiration: new Date(new Date(`${props.time}Z`).getTime() + TX_EXPIRATION_MS).toISOString().slice(0, -5),
+      operations,
+      extensions: [],
+    }, key);
+  }
+
+  /**
+   * Pairs with the user's HAS wallet app ahead of the first transaction, so
+   * that one only needs approving rather than a scan as well.
+   */
+  async pairHas(): Promise<HasSessionStatus> {
+    const username = this.config.getConfig().hiveUsername;
+    if (!username) throw new Error('Log in with a Hive account first');
+    const socket = await this.connectHas();
+    try {
+      await this.authenticateHas(socket, username);
+    } finally {
+      socket.close();
+    }
+    return this.getHasSession()!;
+  }
+
+  private track(request: SigningRequest, onSettle?: () => void): Promise<string> {
+    return new Promise<string>((resolve, reject) => {
+      let timer: NodeJS.Timeout;
+      const pending: PendingSign = {
+        request,
+        settle: (error, txId) => {
+          if (!this.pending.delete(request.id)) return;
+          clearTimeout(timer);
+          onSettle?.();
+          this.emit('resolved', { id: request.id, error: error?.message ?? null, txId: txId ?? null });
+          if (error) reject(error);
+          else resolve(txId!);
+        },
+      };
+      timer = setTimeout(() => pending.settle(new Error('Timed out waiting for approval')), SIGN_TIMEOUT_MS);
+      this.pending.set(request.id, pending);
+      this.emit('request', request);
+    });
+  }
+
+  private newRequest(description: string, role: HiveKeyRole, method: HiveSigningMethod): SigningRequest {
+    return {
+      id: crypto.randomUUID(),
+      description,
+      role,
+      method,
+      createdAt: new Date().toISOString(),
+      handoffUrl: null,
+      hasUri: null,
+      qrCode: null,
+    };
+  }
+
+  private async signWithKeychain(username: string, description: string, operations: Operation[],
+    role: HiveKeyRole): Promise<string> {
+    const port = await this.startHandoffServer();
+    const request = this.newRequest(description, role, 'keychain');
+    request.handoffUrl = `http://127.0.0.1:${port}/sign/${request.id}?t=${this.handoffSecret}`;
+    this.handoffPayloads.set(request.id, { username, operations, role });
+    const signed = this.track(request, () => this.handoffPayloads.delete(request.id));
+    shell.openExternal(request.handoffUrl).catch((error) => {
+      console.error('[Hive] Could not open the browser for Keychain:', error.message);
+    });
+    return signed;
+  }
+
+  /**
+   * Loopback server for the Keychain page and its result. Every URL carries
+   * a per-run secret, so other local pages can't read pending transactions
+   * or post fake results.
+   */
+  private async startHandoffServer(): Promise<number> {
+    if (!this.handoffServer) {
+      const server = http.createServer((req, res) => this.handleHandoff(req, res));
+      await new Promise<void>((resolve, reject) => {
+        server.once('error', reject);
+        server.listen(0, '127.0.0.1', () => {
+          server.off('error', reject);
+          resolve();
+        });
+      });
+      this.handoffServer = server;
+    }
+    return (this.handoffServer.address() as { port: number }).port;
+  }
+
+  private handleHandoff(req: http.IncomingMessage, res: http.ServerResponse): void {
+    const url = new URL(req.url || '/', 'http://127.0.0.1');
+    const match = url.pathname.match(/^\/sign\/([0-9a-f-]{36})(\/result)?$/);
+    const payload = match ? this.handoffPayloads.get(match[1]) : undefined;
+    if (!match || !payload || url.searchParams.get('t') !== this.handoffSecret) {
+      res.writeHead(404, { 'Content-Type': 'text/plain' }).end('This signing request has expired or does not exist');
+      return;
+    }
+    const entry = this.pending.get(match[1])!;
+
+    if (!match[2] && req.method === 'GET') {
+      res.writeHead(200, {
+        'Content-Type': 'text/html; charset=utf-8',
+        'Cache-Control': 'no-store',
+        'Content-Security-Policy': "default-src 'none'; script-src 'unsafe-inline'; style-src 'unsafe-inline'; connect-src 'self'",
+      }).end(this.renderHandoffPage(entry.request, payload));
+      return;
+    }
+    if (match[2] && req.method === 'POST') {
+      let body = '';
+      req.on('data', (chunk) => {
+        body += chunk;
+        if (body.length > 64 * 1024) req.destroy();
+      });
+      req.on('end', () => {
+        let response: any;
+        try {
+          response = JSON.parse(body);
+        } catch {
+          res.writeHead(400, { 'Content-Type': 'text/plain' }).end('Invalid result');
+          return;
+        }
+        res.writeHead(204).end();
+        const txId = response?.result?.id || response?.result?.tx_id;
+        if (response?.success && typeof txId === 'string') entry.settle(null, txId);
+        else entry.settle(new Error(`Keychain: ${response?.message || response?.error || 'request was rejected'}`));
+      });
+      return;
+    }
+    res.writeHead(405, { 'Content-Type': 'text/plain' }).end('Method not allowed');
+  }
+
+  private renderHandoffPage(request: SigningRequest, payload: HandoffPayload): string {
+    // Embedded as JSON in a script, so '<' is escaped against breaking out of the tag
+    const data = JSON.stringify({
+      username: payload.username,
+      operations: payload.operations,
+      keyType: payload.role === 'active' ? 'Active' : 'Posting',
+      resultUrl: `/sign/${request.id}/result?t=${this.handoffSecret}`,
+    }).replace(/</g, '\\u003c');
+    return `<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>${APP_NAME}: sign with Hive Keychain</title>
+<style>body { font-family: sans-serif; max-width: 560px; margin: 60px auto; color: #222; } pre { background: #f4f4f4; padding: 12px; overflow: auto; }</style>
+</head>
+<body>
+<h2>${escapeHtml(request.description)}</h2>
+<p id="state">Waiting for Hive Keychain…</p>
+<pre>${escapeHtml(JSON.stringify(payload.operations, null, 2))}</pre>
+<script>
+const data = ${data};
+const state = document.getElementById('state');
+function report(response) {
+  fetch(data.resultUrl, { method: 'POST', headers: { 'Content-Type': 'application/json' }, body: JSON.stringify(response) })
+    .then(() => { state.textContent = response.success ? 'Signed. You can close this tab.' : 'Not signed: ' + (response.message || 'rejected'); })
+    .catch(() => { state.textContent = 'Could not reach the desktop agent.'; });
+}
+function sign() {
+  if (!window.hive_keychain) {
+    state.textContent = 'Hive Keychain was not found in this browser. Install it, or choose another signing method in the agent.';
+    return;
+  }
+  state.textContent = 'Approve the request in Hive Keychain as @' + data.username + '.';
+  window.hive_keychain.requestBroadcast(data.username, data.operations, data.keyType, report);
+}
+// The extension injects itself after the page loads
+setTimeout(sign, 500);
+</script>
+</body>
+</html>`;
+  }
+
+  private async signWithHas(username: string, description: string, operations: Operation[],
+    role: HiveKeyRole): Promise<string> {
+    const socket = await this.connectHas();
+    try {
+      const session = this.readSession() ?? await this.authenticateHas(socket, username, description);
+      const request = this.newRequest(description, role, 'has');
+      const signed = this.track(request);
+      // The relay names this request in sign_wait; answers that don't carry that uuid are someone else's
+      let uuid: string | null = null;
+      const onMessage = (raw: WebSocket.RawData) => {
+        let message: any;
+        try {
+          message = JSON.parse(raw.toString());
+        } catch {
+          return;
+        }
+        if (message.cmd === 'sign_wait') {
+          if (uuid === null && typeof message.uuid === 'string') uuid = message.uuid;
+          return;
+        }
+        if (uuid === null || message.uuid !== uuid) return;
+        if (message.cmd === 'sign_ack') {
+          this.pending.get(request.id)?.settle(null, String(message.data));
+        } else if (message.cmd === 'sign_nack') {
+          this.pending.get(request.id)?.settle(new Error('Rejected in the wallet app'));
+        } else if (message.cmd === 'sign_err') {
+          let reason = message.error;
+          try {
+            reason = hasDecrypt(message.error, session.authKey);
+          } catch {
+            // Some relays send it unencrypted
+          }
+          this.pending.get(request.id)?.settle(new Error(`Wallet app: ${reason}`));
+        }
+      };
+      socket.on('message', onMessage);
+      socket.once('close', () => this.pending.get(request.id)?.settle(new Error('Lost the connection to the HAS relay')));
+      socket.send(JSON.stringify({
+        cmd: 'sign_req',
+        account: username,
+        token: session.token,
+        data: hasEncrypt(JSON.stringify({ key_type: role, ops: operations, broadcast: true, nonce: Date.now() }), session.authKey),
+      }));
+      return await signed;
+    } finally {
+      socket.close();
+    }
+  }
+
+  private connectHas(): Promise<WebSocket> {
+    const { hasServer } = this.config.getConfig().hiveSigning;
+    return new Promise((resolve, reject) => {
+      const socket = new WebSocket(hasServer, { handshakeTimeout: 10000 });
+      socket.once('open', () => {
+        socket.off('error', reject);
+        // Errors after opening surface as a close on the pending request
+        socket.on('error', (error) => console.error('[Hive] HAS relay error:', error.message));
+        resolve(socket);
+      });
+      socket.once('error', (error) => reject(new Error(`Could not reach the HAS relay ${hasServer}: ${error.message}`)));
+    });
+  }
+
+  /**
+   * Pairs the agent with the wallet app: the relay returns a request ID,
+   * which goes into the has:// link with a fresh auth key the relay never
+   * sees. The wallet answers with a session token encrypted under that key.
+   */
+  private async authenticateHas(socket: WebSocket, username: string, description = 'Link your wallet app'): Promise<StoredHasSession> {
+    // The auth key is as good as a key to the wallet session, so it's only kept sealed
+    if (!this.secrets.isWritable()) {
+      throw new Error(this.secrets.getStatus().backend === 'vault'
+        ? 'Unlock the secrets vault to pair a wallet app'
+        : 'No OS keyring is available; set up the secrets vault to pair a wallet app');
+    }
+    const { hasServer } = this.config.getConfig().hiveSigning;
+    const authKey = crypto.randomUUID();
+    const request = this.newRequest(description, 'posting', 'has');
+    // Announced again with the link once the relay has handed out an ID
+    const paired = this.track(request);
+    let session: StoredHasSession | null = null;
+
+    const onMessage = async (raw: WebSocket.RawData) => {
+      let message: any;
+      try {
+        message = JSON.parse(raw.toString());
+      } catch {
+        return;
+      }
+      if (message.cmd === 'auth_wait') {
+        const payload = Buffer.from(JSON.stringify({ account: username, uuid: message.uuid, key: authKey, host: hasServer }))
+          .toString('base64');
+        request.hasUri = `has://auth_req/${payload}`;
+        request.qrCode = await QRCode.toDataURL(request.hasUri, { margin: 1, width: 240 });
+        this.emit('request', request);
+      } else if (message.cmd === 'auth_ack') {
+        try {
+          const data = JSON.parse(hasDecrypt(message.data, authKey));
+          session = { username, server: hasServer, token: data.token, expire: data.expire, authKey, encrypted: true };
+          this.pending.get(request.id)?.settle(null, data.token);
+        } catch (error: any) {
+          this.pending.get(request.id)?.settle(new Error(`Unreadable answer from the wallet app: ${error.message}`));
+        }
+      } else if (message.cmd === 'auth_nack') {
+        this.pending.get(request.id)?.settle(new Error('Pairing was rejected in the wallet app'));
+      }
+    };
+    const onClose = () => this.pending.get(request.id)?.settle(new Error('Lost the connection to the HAS relay'));
+    socket.on('message', onMessage);
+    socket.once('close', onClose);
+    socket.send(JSON.stringify({
+      cmd: 'auth_req',
+      account: username,
+      data: hasEncrypt(JSON.stringify({ app: { name: APP_NAME, description: 'SPK Network storage node' } }), authKey),
+    }));
+
+    try {
+      await paired;
+    } finally {
+      socket.off('message', onMessage);
+      socket.off('close', onClose);
+    }
+    this.writeSession(session!);
+    console.log(`[Hive] Paired with the HAS wallet app for @${username}`);
+    return session!;
+  }
+
+  private readSession(): StoredHasSession | null {
+    try {
+      if (!fs.existsSync(this.sessionPath)) return null;
+      const stored: StoredHasSession = JSON.parse(fs.readFileSync(this.sessionPath, 'utf-8'));
+      const { hiveUsername, hiveSigning } = this.config.getConfig();
+      if (stored.username !== hiveUsername || stored.server !== hiveSigning.hasServer || stored.expire <= Date.now()) {
+        return null;
+      }
+      const authKey = stored.encrypted ? this.secrets.decrypt(stored.authKey) : stored.authKey;
+      const session = { ...stored, authKey, encrypted: true };
+      // Sessions paired before auth keys had to be sealed are sealed once they can be
+      if (!stored.encrypted && this.secrets.isWritable()) this.writeSession(session);
+      return session;
+    } catch (error) {
+      // A locked vault isn't a broken session; signing says to unlock rather than re-pair
+      if (error instanceof VaultLockedError) throw error;
+      console.error('[Hive] Failed to read the HAS session:', error);
+      return null;
+    }
+  }
+
+  private writeSession(session: StoredHasSession): void {
+    const stored: StoredHasSession = { ...session, authKey: this.secrets.encrypt(session.authKey), encrypted: true };
+    fs.writeFileSync(this.sessionPath, JSON.stringify(stored, null, 2), { mode: 0o600 });
+  }
+}
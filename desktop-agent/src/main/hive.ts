@@ -0,0 +1,174 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { Client, PrivateKey } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+import { AccountDirectory } from './accounts';
+import { HiveRpcPool } from './hive-rpc';
+import { SecretBackend, SecretStore } from './secrets';
+
+export type HiveKeyRole = 'posting' | 'active';
+const KEY_ROLES: HiveKeyRole[] = ['posting', 'active'];
+
+export interface HiveLoginRequest {
+  username: string;
+  postingKey?: string;
+  activeKey?: string;
+}
+
+export interface HiveIdentity {
+  username: string | null;
+  keys: Record<HiveKeyRole, boolean>;
+  // Where new keys are kept; null until there is a keyring or a vault
+  keyStorage: SecretBackend | null;
+  // Vault only: keys can't be used or stored until it is unlocked
+  locked: boolean;
+  // Where the node's rewards go; null for the account itself
+  payoutAccount: string | null;
+}
+
+// Lowercased, without a leading @; throws unless it is a well-formed Hive name
+export function normalizeUsername(input: string): string {
+  const username = String(input ?? '').trim().toLowerCase().replace(/^@/, '');
+  if (!/^[a-z][a-z0-9.-]{2,15}$/.test(username)) throw new Error('That is not a valid Hive username');
+  return username;
+}
+
+// Per linked account; on disk only as keyring- or vault-encrypted blobs, never plaintext
+type StoredKeys = Record<string, Partial<Record<HiveKeyRole, string>>>;
+
+/**
+ * The Hive accounts this node works for, and their keys. Keys are encrypted
+ * by the SecretStore, with the OS keyring (Keychain, DPAPI, libsecret) or
+ * else the passphrase vault, and written to hive-keys.json. Logging in checks each
+ * key against the account's on-chain authorities, so a typo'd or wrong key
+ * is caught before anything is signed with it.
+ */
+export class HiveAccount {
+  private filePath: string;
+  private config: ConfigStore;
+  private accounts: AccountDirectory;
+  private rpc: HiveRpcPool;
+  private secrets: SecretStore;
+
+  constructor(dataDir: string, config: ConfigStore, accounts: AccountDirectory, rpc: HiveRpcPool, secrets: SecretStore) {
+    this.filePath = path.join(dataDir, 'hive-keys.json');
+    this.config = config;
+    this.accounts = accounts;
+    this.rpc = rpc;
+    this.secrets = secrets;
+  }
+
+  // Fails over between the configured API nodes
+  getClient(): Client {
+    return this.rpc.getClient();
+  }
+
+  /**
+   * Links the account, validates and stores any keys given, and makes it
+   * the active account. Logging in again without keys keeps the stored ones.
+   */
+  async login(request: HiveLoginRequest, label?: string): Promise<HiveIdentity> {
+    const username = normalizeUsername(request.username);
+    const provided = KEY_ROLES.filter((role) => request[`${role}Key`]);
+    if (provided.length > 0 && !this.secrets.isWritable()) {
+      throw new Error(this.secrets.getStatus().backend === 'vault'
+        ? 'Unlock the secrets vault to store keys'
+        : 'No OS keyring is available; set up the secrets vault to store keys');
+    }
+
+    const [account] = await this.getClient().database.getAccounts([username]);
+    if (!account) throw new Error(`@${username} does not exist on Hive`);
+
+    const keys: Partial<Record<HiveKeyRole, string>> = {};
+    for (const role of provided) {
+      let key: PrivateKey;
+      try {
+        key = PrivateKey.fromString(request[`${role}Key`]!.trim());
+      } catch {
+        throw new Error(`The ${role} key is not a valid private key`);
+      }
+      const publicKey = key.createPublic().toString();
+      const authority = account[role];
+      if (!authority.key_auths.some(([authorized]) => authorized.toString() === publicKey)) {
+        throw new Error(`That is not the ${role} key of @${username}`);
+      }
+      keys[role] = this.secrets.encrypt(key.toString());
+    }
+
+    const stored = this.read();
+    if (provided.length > 0) stored[username] = { ...stored[username], ...keys };
+    this.write(stored);
+    this.accounts.link(username, label);
+    this.config.setConfig({ hiveUsername: username });
+    console.log(`[Hive] Logged in as @${username}${provided.length > 0 ? ` with ${provided.join(' and ')} keys` : ''}`);
+    return this.whoami();
+  }
+
+  // Unlinks an account (the active one by default) and deletes its keys
+  logout(username = this.config.getConfig().hiveUsername): HiveIdentity {
+    if (!username) return this.whoami();
+    const stored = this.read();
+    delete stored[username];
+    this.write(stored);
+    this.accounts.unlink(username);
+    console.log(`[Hive] Logged out @${username}`);
+    return this.whoami();
+  }
+
+  whoami(): HiveIdentity {
+    const username = this.config.getConfig().hiveUsername;
+    const keys = username ? this.read()[username] ?? {} : {};
+    return {
+      username,
+      keys: { posting: !!keys.posting, active: !!keys.active },
+      keyStorage: this.secrets.getStatus().backend,
+      locked: this.secrets.getStatus().backend === 'vault' && !this.secrets.getStatus().unlocked,
+      payoutAccount: this.accounts.getPayoutAccount(username),
+    };
+  }
+
+  /**
+   * Routes the active account's node rewards elsewhere, e.g. to a cold
+   * wallet. The target must exist on Hive; null (or the account itself)
+   * pays the account again. Takes effect at the next registration.
+   */
+  async setPayoutAccount(target: string | null): Promise<string | null> {
+    const username = this.config.getConfig().hiveUsername;
+    if (!username) throw new Error('Log in with a Hive account first');
+    let payout = target ? normalizeUsername(target) : null;
+    if (payout === username) payout = null;
+    if (payout) {
+      const [account] = await this.getClient().database.getAccounts([payout]);
+      if (!account) throw new Error(`@${payout} does not exist on Hive`);
+    }
+    this.accounts.setPayoutAccount(username, payout);
+    console.log(payout ? `[Hive] Rewards for @${username} now go to @${payout}` : `[Hive] Rewards for @${username} go to itself`);
+    return payout;
+  }
+
+  // For signing as the active account; null when that key isn't stored
+  getKey(role: HiveKeyRole): PrivateKey | null {
+    const username = this.config.getConfig().hiveUsername;
+    const blob = username ? this.read()[username]?.[role] : undefined;
+    if (!blob) return null;
+    return PrivateKey.fromString(this.secrets.decrypt(blob));
+  }
+
+  private read(): StoredKeys {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        const stored = JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+        // Single-account files: { username, keys }
+        if (typeof stored.username === 'string' && stored.keys) return { [stored.username]: stored.keys };
+        return stored;
+      }
+    } catch (error) {
+      console.error('[Hive] Failed to read stored keys:', error);
+    }
+    return {};
+  }
+
+  private write(stored: StoredKeys): void {
+    fs.writeFileSync(this.filePath, JSON.stringify(stored, null, 2), { mode: 0o600 });
+  }
+}
@@ -0,0 +1,266 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import axios from 'axios';
+import { ConfigStore } from './config';
+
+// Accounts change with every spend, so they are only reused briefly
+const ACCOUNT_CACHE_MS = 30 * 1000;
+
+export type HoneycombDataset = 'protocol' | 'validators' | 'rankings' | 'contracts';
+const DATASETS: HoneycombDataset[] = ['protocol', 'validators', 'rankings', 'contracts'];
+
+// Honeycomb routes each dataset is read from, and how to find its entries in the response
+const SOURCES: Record<HoneycombDataset, { route: string; entries: (data: any) => Record<string, unknown> }> = {
+  // Protocol parameters: one entry per parameter
+  protocol: { route: '/stats', entries: (data) => data?.result ?? data?.stats ?? {} },
+  // Accounts running consensus
+  validators: { route: '/runners', entries: (data) => data?.result ?? data?.runners ?? {} },
+  // Every storage node with its consensus record
+  rankings: { route: '/markets', entries: (data) => data?.markets?.node ?? {} },
+  contracts: { route: '/api/fileContracts', entries: (data) => data?.result ?? data?.contracts ?? {} },
+};
+
+export interface HoneycombSettings {
+  syncMinutes: number;
+}
+
+export const DEFAULT_HONEYCOMB_SETTINGS: HoneycombSettings = {
+  syncMinutes: 5,
+};
+
+export function validateHoneycombSettings(settings: Partial<HoneycombSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.syncMinutes !== undefined
+    && (!Number.isInteger(settings.syncMinutes) || settings.syncMinutes < 1 || settings.syncMinutes > 24 * 60)) {
+    errors.push('syncMinutes must be between 1 and 1440');
+  }
+  return errors;
+}
+
+interface CachedEntry {
+  hash: string;
+  // SPK block the entry last changed at
+  changedAt: number;
+  value: any;
+}
+
+interface CachedDataset {
+  headBlock: number;
+  syncedAt: string | null;
+  entries: Record<string, CachedEntry>;
+}
+
+export interface HoneycombDelta {
+  dataset: HoneycombDataset;
+  headBlock: number;
+  added: string[];
+  changed: string[];
+  removed: string[];
+}
+
+export interface HoneycombDatasetStatus {
+  dataset: HoneycombDataset;
+  headBlock: number;
+  syncedAt: string | null;
+  entries: number;
+  lastError: string | null;
+}
+
+export interface NodeRanking {
+  account: string;
+  rank: number;
+  domain: string | null;
+  // Consensus rounds the node took part in, and how many it agreed with
+  attempts: number;
+  yays: number;
+  lastGood: number | null;
+}
+
+/**
+ * The agent's view of the SPK chain, read from a honeycomb node: protocol
+ * parameters, the validator set, storage node rankings and storage
+ * contracts. Everything is cached in honeycomb-cache.json and synced on an
+ * interval. A sync is skipped while the chain's head block hasn't moved, and
+ * otherwise only the entries that differ are kept and announced, as a
+ * `delta` event per dataset. Reads answer from the cache, so features built
+ * on it still work while the honeycomb node is down.
+ */
+export class HoneycombClient extends EventEmitter {
+  private filePath: string;
+  private config: ConfigStore;
+  private cache: Record<HoneycombDataset, CachedDataset>;
+  private errors: Partial<Record<HoneycombDataset, string>> = {};
+  private accounts = new Map<string, { at: number; data: any }>();
+  private timer: NodeJS.Timeout | null = null;
+  private syncing: Promise<HoneycombDelta[]> | null = null;
+
+  constructor(dataDir: string, config: ConfigStore) {
+    super();
+    this.filePath = path.join(dataDir, 'honeycomb-cache.json');
+    this.config = config;
+    this.cache = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.sync().catch(() => undefined);
+    const { syncMinutes } = this.config.getConfig().honeycomb;
+    this.timer = setInterval(() => this.sync().catch(() => undefined), syncMinutes * 60 * 1000);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  // Picks up a new sync interval
+  restart(): void {
+    this.stop();
+    this.start();
+  }
+
+  getStatus(): HoneycombDatasetStatus[] {
+    return DATASETS.map((dataset) => ({
+      dataset,
+      headBlock: this.cache[dataset].headBlock,
+      syncedAt: this.cache[dataset].syncedAt,
+      entries: Object.keys(this.cache[dataset].entries).length,
+      lastError: this.errors[dataset] ?? null,
+    }));
+  }
+
+  // Concurrent callers share one sync
+  sync(force = false): Promise<HoneycombDelta[]> {
+    if (!this.syncing) {
+      this.syncing = this.syncAll(force).finally(() => {
+        this.syncing = null;
+      });
+    }
+    return this.syncing;
+  }
+
+  getProtocol(): Record<string, any> {
+    return this.values('protocol');
+  }
+
+  getValidators(): Record<string, any> {
+    return this.values('validators');
+  }
+
+  getContracts(): Record<string, any> {
+    return this.values('contracts');
+  }
+
+  // Ranked by how often the node agreed with consensus, then by participation
+  getRankings(): NodeRanking[] {
+    return Object.entries(this.values('rankings'))
+      .map(([account, node]) => ({
+        account,
+        domain: typeof node?.domain === 'string' ? node.domain : null,
+        attempts: Number(node?.attempts) || 0,
+        yays: Number(node?.yays) || 0,
+        lastGood: Number.isFinite(Number(node?.lastGood)) ? Number(node.lastGood) : null,
+      }))
+      .sort((a, b) => (b.yays / (b.attempts || 1)) - (a.yays / (a.attempts || 1)) || b.attempts - a.attempts)
+      .map((node, index) => ({ ...node, rank: index + 1 }));
+  }
+
+  // The account as honeycomb stores it (balances in milli-units)
+  async getAccount(username: string, force = false): Promise<any> {
+    const cached = this.accounts.get(username);
+    if (!force && cached && Date.now() - cached.at < ACCOUNT_CACHE_MS) return cached.data;
+    const data = (await this.get(`/@${encodeURIComponent(username)}`)) || {};
+    this.accounts.set(username, { at: Date.now(), data });
+    return data;
+  }
+
+  // Registered services of one type, e.g. IPFS storage nodes
+  async getServices(type: string): Promise<any> {
+    return (await this.get(`/services/${encodeURIComponent(type)}`)) ?? {};
+  }
+
+  private async syncAll(force: boolean): Promise<HoneycombDelta[]> {
+    // The root route is small; it says whether the chain moved since the last sync
+    const head = await this.get('/').then((data) => Number(data?.head_block) || 0).catch(() => 0);
+    const deltas: HoneycombDelta[] = [];
+    for (const dataset of DATASETS) {
+      if (!force && head > 0 && this.cache[dataset].headBlock === head) {
+        this.cache[dataset].syncedAt = new Date().toISOString();
+        continue;
+      }
+      try {
+        const delta = await this.syncDataset(dataset);
+        delete this.errors[dataset];
+        if (delta) deltas.push(delta);
+      } catch (error: any) {
+        this.errors[dataset] = error.message;
+        console.warn(`[SPK] Syncing ${dataset} from honeycomb failed:`, error.message);
+      }
+    }
+    this.save();
+    return deltas;
+  }
+
+  private async syncDataset(dataset: HoneycombDataset): Promise<HoneycombDelta | null> {
+    const cached = this.cache[dataset];
+    const data = await this.get(SOURCES[dataset].route);
+    const headBlock = Number(data?.head_block) || 0;
+    const now = new Date().toISOString();
+    const fresh = SOURCES[dataset].entries(data);
+    const delta: HoneycombDelta = { dataset, headBlock, added: [], changed: [], removed: [] };
+    for (const [key, value] of Object.entries(fresh)) {
+      const hash = crypto.createHash('sha1').update(JSON.stringify(value)).digest('hex');
+      const existing = cached.entries[key];
+      if (existing?.hash === hash) continue;
+      (existing ? delta.changed : delta.added).push(key);
+      cached.entries[key] = { hash, changedAt: headBlock, value };
+    }
+    for (const key of Object.keys(cached.entries)) {
+      if (!(key in fresh)) {
+        delta.removed.push(key);
+        delete cached.entries[key];
+      }
+    }
+    cached.headBlock = headBlock;
+    cached.syncedAt = now;
+
+    if (delta.added.length + delta.changed.length + delta.removed.length === 0) return null;
+    console.log(`[SPK] ${dataset} at block ${headBlock}: ${delta.added.length} added, `
+      + `${delta.changed.length} changed, ${delta.removed.length} removed`);
+    this.emit('delta', delta);
+    return delta;
+  }
+
+  private async get(route: string): Promise<any> {
+    const base = this.config.getConfig().spkChainUrl.replace(/\/$/, '');
+    const response = await axios.get(`${base}${route}`, { timeout: 15000 });
+    return response.data;
+  }
+
+  private values(dataset: HoneycombDataset): Record<string, any> {
+    return Object.fromEntries(Object.entries(this.cache[dataset].entries).map(([key, entry]) => [key, entry.value]));
+  }
+
+  private load(): Record<HoneycombDataset, CachedDataset> {
+    const empty = Object.fromEntries(DATASETS.map((dataset) => [dataset, { headBlock: 0, syncedAt: null, entries: {} }])) as
+      Record<HoneycombDataset, CachedDataset>;
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return { ...empty, ...JSON.parse(fs.readFileSync(this.filePath, 'utf-8')) };
+      }
+    } catch (error) {
+      console.error('[SPK] Failed to read the honeycomb cache:', error);
+    }
+    return empty;
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.cache));
+    } catch (error) {
+      console.error('[SPK] Failed to save the honeycomb cache:', error);
+    }
+  }
+}
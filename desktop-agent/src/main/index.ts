@@ -1,9 +1,54 @@
 import { app, BrowserWindow, Tray, Menu, nativeImage, dialog } from 'electron';
 import * as path from 'path';
-import { KuboManager } from './kubo';
-import { ApiServer } from './api';
+import { KuboManager, BinaryIntegrityError } from './kubo';
+import { ApiServer, ApiEndpoints } from './api';
 import { ConfigStore } from './config';
 import { AutoUpdater } from './updater';
+import { KuboUpdater } from './kubo-updater';
+import { DatastoreMigrator } from './datastore';
+import { DaemonWatchdog } from './watchdog';
+import { ActivityTracker } from './activity';
+import { GcScheduler } from './gc-scheduler';
+import { StorageMonitor } from './storage-monitor';
+import { ConnectivityMonitor, ConnectivityReport, extractIp } from './connectivity';
+import { PowerManager, PowerStatus } from './power';
+import { NodeStatsCollector } from './node-stats';
+import { PinRepairer } from './pin-repair';
+import { PinScrubber } from './scrubber';
+import { KuboPool } from './kubo-pool';
+import { PreflightError } from './preflight';
+import { StartupState, StartupStateChange } from './startup';
+import { ApiAuth } from './auth';
+import { CorsPolicy } from './cors';
+import { EventStream } from './event-stream';
+import { EventBus } from './event-bus';
+import { PinIndex } from './pin-index';
+import { CertificateManager } from './tls';
+import { AgentAdvertiser } from './discovery';
+import { WebhookDispatcher } from './webhooks';
+import { NodeRegistration } from './registration';
+import { HiveAccount } from './hive';
+import { AccountDirectory } from './accounts';
+import { HiveRpcPool } from './hive-rpc';
+import { SecretStore } from './secrets';
+import { setNotificationFilter } from './notifications';
+import { HiveSigner } from './hive-signing';
+import { TransactionQueue } from './tx-queue';
+import { HoneycombClient } from './honeycomb';
+import { EarningsFinality } from './earnings-finality';
+import { DelegationManager } from './delegations';
+import { ChainRegistration } from './chain-registration';
+import { RewardClaimer } from './reward-claims';
+import { BrocaTracker } from './broca';
+import { WalletProvider } from './wallet';
+import { PriceFeed } from './price-feed';
+import { UptimeTracker } from './uptime';
+import { DailySummaryScheduler } from './daily-summary';
+import { NetworkRankTracker } from './network-rank';
+import { EarningsAnomalyDetector } from './earnings-anomaly';
+import { AnalyticsStore } from './analytics';
+import { BackupManager } from './backup';
+import { registerIpcHandlers, sendToWindow } from './ipc';
 
 let mainWindow: BrowserWindow | null = null;
 let tray: Tray | null = null;
@@ -11,8 +56,64 @@ let kuboManager: KuboManager;
 let apiServer: ApiServer;
 let configStore: ConfigStore;
 let autoUpdater: AutoUpdater;
+let kuboUpdater: KuboUpdater;
+let datastoreMigrator: DatastoreMigrator;
+let watchdog: DaemonWatchdog;
+let activity: ActivityTracker;
+let gcScheduler: GcScheduler;
+let storageMonitor: StorageMonitor;
+let connectivity: ConnectivityMonitor;
+let powerManager: PowerManager;
+let nodeStats: NodeStatsCollector;
+let pinRepairer: PinRepairer;
+let scrubber: PinScrubber;
+let kuboPool: KuboPool;
+let apiAuth: ApiAuth;
+let corsPolicy: CorsPolicy;
+let eventBus: EventBus;
+let eventStream: EventStream;
+let pinIndex: PinIndex;
+let certificates: CertificateManager;
+let advertiser: AgentAdvertiser;
+let webhooks: WebhookDispatcher;
+let registration: NodeRegistration;
+let accounts: AccountDirectory;
+let hiveRpc: HiveRpcPool;
+let secrets: SecretStore;
+let hiveAccount: HiveAccount;
+let hiveSigner: HiveSigner;
+let txQueue: TransactionQueue;
+let honeycomb: HoneycombClient;
+let earningsFinality: EarningsFinality;
+let delegations: DelegationManager;
+let chainRegistration: ChainRegistration;
+let rewardClaimer: RewardClaimer;
+let brocaTracker: BrocaTracker;
+let wallet: WalletProvider;
+let priceFeed: PriceFeed;
+let uptime: UptimeTracker;
+let dailySummary: DailySummaryScheduler;
+let networkRank: NetworkRankTracker;
+let earningsAnomaly: EarningsAnomalyDetector;
+let analytics: AnalyticsStore;
+let backups: BackupManager;
 
 const isDev = process.env.NODE_ENV === 'development';
+const SHUTDOWN_TIMEOUT_MS = 15000;
+let isQuitting = false;
+let trayStatus = 'Starting...';
+let trayNetwork: string | null = null;
+let trayAtRisk = 0;
+let trayEndpoints: ApiEndpoints | null = null;
+
+const STARTUP_TRAY_LABELS: Record<StartupState, string> = {
+  Starting: 'Starting...',
+  RepoReady: 'Starting daemon...',
+  DaemonUp: 'Connecting to peers...',
+  Online: 'Running',
+  Failed: 'Error',
+  Stopped: 'Stopped',
+};
 
 function createWindow(): void {
   mainWindow = new BrowserWindow({
@@ -34,6 +135,7 @@ function createWindow(): void {
   }
 
   mainWindow.on('close', (event) => {
+    if (isQuitting) return;
     event.preventDefault();
     mainWindow?.hide();
   });
@@ -57,11 +159,30 @@ function createTray(): void {
   });
 }
 
-function updateTrayMenu(status: string): void {
+// A moved port is easy to miss in the logs, so the tray shows where the API ended up, and how available the node has been
+function updateTrayTooltip(endpoints: ApiEndpoints | null = trayEndpoints): void {
+  trayEndpoints = endpoints;
+  const lines = ['SPK Desktop Agent'];
+  if (endpoints) {
+    const https = endpoints.httpsPort ? `, HTTPS ${endpoints.httpsPort}` : '';
+    const grpc = endpoints.grpcPort ? `, gRPC ${endpoints.grpcPort}` : '';
+    lines.push(`API on port ${endpoints.port}${https}${grpc}`);
+  }
+  if (uptime) {
+    const windows = uptime.getStats().windows.map((window) => `${(window.availability * 100).toFixed(1)}% (${window.days}d)`);
+    lines.push(`Availability ${windows.join(', ')}`);
+  }
+  tray?.setToolTip(lines.join('\n'));
+}
+
+function updateTrayMenu(status: string = trayStatus): void {
+  trayStatus = status;
   if (!tray) return;
 
   const contextMenu = Menu.buildFromTemplate([
     { label: `Status: ${status}`, enabled: false },
+    ...(trayNetwork ? [{ label: `Network: ${trayNetwork}`, enabled: false }] : []),
+    ...(trayAtRisk > 0 ? [{ label: `At risk: ${trayAtRisk} unsent proofs, retrying`, enabled: false }] : []),
     { type: 'separator' },
     { label: 'Show Dashboard', click: () => { mainWindow?.show(); mainWindow?.focus(); } },
     { label: 'Open Web App', click: () => { require('electron').shell.openExternal('http://localhost:5000'); } },
@@ -74,31 +195,319 @@ function updateTrayMenu(status: string): void {
   tray.setContextMenu(contextMenu);
 }
 
+async function offerBinaryRepair(error: BinaryIntegrityError): Promise<void> {
+  const { response } = await dialog.showMessageBox({
+    type: 'error',
+    title: 'SPK Desktop Agent',
+    message: 'The IPFS binary appears to be damaged or modified',
+    detail: `${error.binaryPath} does not match the checksum recorded when it was installed. `
+      + 'The agent will not run it. Download a fresh, verified copy?',
+    buttons: ['Download Again', 'Quit'],
+    defaultId: 0,
+    cancelId: 1,
+  });
+
+  if (response !== 0) {
+    app.quit();
+    return;
+  }
+
+  try {
+    await kuboManager.repairBinary();
+    await kuboManager.start();
+  } catch (repairError) {
+    console.error('[SPK] Failed to repair IPFS binary:', repairError);
+    dialog.showErrorBox('SPK Desktop Agent', `Failed to repair IPFS: ${repairError}`);
+  }
+}
+
+async function approvePairing({ origin, appName }: { origin: string | null; appName: string }): Promise<boolean> {
+  mainWindow?.show();
+  const options = {
+    type: 'question' as const,
+    title: 'SPK Desktop Agent',
+    message: `Allow ${origin || appName} to control this agent?`,
+    detail: `${appName} is asking for access to pin content and change settings on this node. `
+      + 'Only allow apps you opened yourself.',
+    buttons: ['Allow', 'Deny'],
+    defaultId: 1,
+    cancelId: 1,
+  };
+  const { response } = mainWindow
+    ? await dialog.showMessageBox(mainWindow, options)
+    : await dialog.showMessageBox(options);
+  return response === 0;
+}
+
+async function approveOrigin(origin: string): Promise<boolean> {
+  const options = {
+    type: 'question' as const,
+    title: 'SPK Desktop Agent',
+    message: `Allow ${origin} to connect to this agent?`,
+    detail: 'This website wants to talk to your local SPK node. Only allow sites you trust; '
+      + 'you can revoke this later in settings.',
+    buttons: ['Allow', 'Deny'],
+    defaultId: 1,
+    cancelId: 1,
+  };
+  const { response } = mainWindow
+    ? await dialog.showMessageBox(mainWindow, options)
+    : await dialog.showMessageBox(options);
+  return response === 0;
+}
+
+// A loopback-only API is no use to other machines, so mDNS follows remote access
+async function updateAdvertisement(): Promise<void> {
+  const { discovery, remoteAccess } = configStore.getConfig();
+  if (!discovery.enabled || !remoteAccess.enabled) {
+    advertiser.stop();
+    return;
+  }
+  advertiser.advertise({
+    port: apiServer.getPort(),
+    httpsPort: apiServer.getHttpsPort(),
+    peerId: await kuboManager.getPeerId(),
+  });
+}
+
 async function initialize(): Promise<void> {
   console.log('[SPK] Initializing desktop agent...');
 
   configStore = new ConfigStore();
+  // First, so a crash during startup is recorded too
+  analytics = new AnalyticsStore(configStore.getDataDir(), () => configStore.getConfig().analytics);
+  analytics.start();
   kuboManager = new KuboManager(configStore);
-  apiServer = new ApiServer(kuboManager, configStore);
+  kuboPool = new KuboPool(kuboManager, configStore);
+  pinRepairer = new PinRepairer(kuboPool);
+  kuboUpdater = new KuboUpdater(kuboManager, configStore);
+  datastoreMigrator = new DatastoreMigrator(kuboManager, configStore);
+  watchdog = new DaemonWatchdog(kuboManager, configStore);
+  activity = new ActivityTracker();
+  scrubber = new PinScrubber(configStore.getDataDir(), kuboPool, configStore, activity, pinRepairer);
+  gcScheduler = new GcScheduler(kuboManager, configStore, activity);
+  storageMonitor = new StorageMonitor(kuboManager, configStore);
+  connectivity = new ConnectivityMonitor(kuboManager, configStore);
+  accounts = new AccountDirectory(configStore);
+  registration = new NodeRegistration(kuboManager, configStore, connectivity, accounts);
+  setNotificationFilter((topic) => accounts.wantsNotification(topic.account ?? null, topic.kind));
+  hiveRpc = new HiveRpcPool(configStore);
+  secrets = new SecretStore(configStore.getDataDir(), configStore);
+  hiveAccount = new HiveAccount(configStore.getDataDir(), configStore, accounts, hiveRpc, secrets);
+  hiveSigner = new HiveSigner(configStore.getDataDir(), configStore, hiveAccount, secrets);
+  txQueue = new TransactionQueue(configStore.getDataDir(), configStore, hiveAccount, hiveSigner);
+  honeycomb = new HoneycombClient(configStore.getDataDir(), configStore);
+  earningsFinality = new EarningsFinality(configStore, hiveRpc);
+  uptime = new UptimeTracker(configStore.getDataDir());
+  networkRank = new NetworkRankTracker(configStore.getDataDir(), configStore, () => kuboManager.getPeerId());
+  earningsAnomaly = new EarningsAnomalyDetector(configStore.getDataDir(), configStore, uptime, connectivity, () => {
+    mainWindow?.show();
+    mainWindow?.focus();
+    sendToWindow(mainWindow, 'show-connectivity-diagnostics');
+  });
+  backups = new BackupManager(configStore.getDataDir(), configStore, secrets, kuboManager, () => apiServer.getChallengeLog());
+  chainRegistration = new ChainRegistration(configStore.getDataDir(), configStore, hiveAccount, txQueue, honeycomb, {
+    peerId: () => kuboManager.getPeerId(),
+    suggestedEndpoint: () => {
+      const ip = (connectivity.getReport()?.publicAddrs || []).map(extractIp).find((addr): addr is string => addr !== null);
+      return ip ? `http://${ip.includes(':') ? `[${ip}]` : ip}:${apiServer.getPort()}` : null;
+    },
+  });
+  priceFeed = new PriceFeed(configStore.getDataDir(), configStore, hiveAccount);
+  dailySummary = new DailySummaryScheduler(configStore.getDataDir(), configStore, priceFeed);
+  rewardClaimer = new RewardClaimer(configStore.getDataDir(), configStore, hiveAccount, txQueue, priceFeed,
+    honeycomb);
+  brocaTracker = new BrocaTracker(configStore, hiveAccount, honeycomb);
+  wallet = new WalletProvider(configStore, hiveAccount, brocaTracker, honeycomb);
+  delegations = new DelegationManager(configStore, hiveAccount, honeycomb, txQueue);
+  powerManager = new PowerManager(kuboPool, configStore);
+  nodeStats = new NodeStatsCollector(kuboManager);
+  apiAuth = new ApiAuth(configStore, approvePairing);
+  corsPolicy = new CorsPolicy(configStore, apiAuth, approveOrigin);
+  eventBus = new EventBus();
+  webhooks = new WebhookDispatcher(configStore, eventBus, secrets);
+  webhooks.start();
+  eventStream = new EventStream(eventBus, apiAuth, corsPolicy);
+  pinIndex = new PinIndex(configStore.getDataDir());
+  certificates = new CertificateManager(configStore.getDataDir());
+  advertiser = new AgentAdvertiser();
+  apiServer = new ApiServer({
+    kubo: kuboManager,
+    config: configStore,
+    activity,
+    storage: storageMonitor,
+    connectivity,
+    pool: kuboPool,
+    auth: apiAuth,
+    cors: corsPolicy,
+    events: eventBus,
+    eventStream,
+    pins: pinIndex,
+    certificates,
+    watchdog,
+    finality: earningsFinality,
+    queue: txQueue,
+    honeycomb,
+    prices: priceFeed,
+    uptime,
+    networkRank,
+    anomaly: earningsAnomaly,
+    analytics,
+    backups,
+  });
   autoUpdater = new AutoUpdater();
   autoUpdater.setMainWindow(mainWindow);
+  registerIpcHandlers({
+    kubo: kuboManager,
+    config: configStore,
+    kuboUpdater,
+    datastoreMigrator,
+    watchdog,
+    gcScheduler,
+    storageMonitor,
+    connectivity,
+    powerManager,
+    nodeStats,
+    kuboPool,
+    pinRepairer,
+    scrubber,
+    apiAuth,
+    apiServer,
+    certificates,
+    webhooks,
+    registration,
+    hive: hiveAccount,
+    accounts,
+    hiveRpc,
+    secrets,
+    hiveSigner,
+    txQueue,
+    honeycomb,
+    chainRegistration,
+    rewardClaimer,
+    broca: brocaTracker,
+    wallet,
+    delegations,
+    prices: priceFeed,
+    uptime,
+    dailySummary,
+    networkRank,
+    anomaly: earningsAnomaly,
+    analytics,
+    backups,
+    updateAdvertisement,
+    getMainWindow: () => mainWindow,
+  });
 
-  try {
-    await kuboManager.start();
-    updateTrayMenu('Running');
-    console.log('[SPK] IPFS daemon started successfully');
-  } catch (error) {
-    console.error('[SPK] Failed to start IPFS:', error);
-    updateTrayMenu('Error');
-    dialog.showErrorBox('SPK Desktop Agent', `Failed to start IPFS: ${error}`);
-  }
+  apiServer.on('endpoints-changed', (endpoints: ApiEndpoints) => {
+    updateTrayTooltip(endpoints);
+    updateAdvertisement().catch(() => undefined);
+  });
 
-  try {
-    await apiServer.start();
-    console.log('[SPK] API server started on port 5111');
-  } catch (error) {
-    console.error('[SPK] Failed to start API server:', error);
-  }
+  kuboManager.on('startup-state', (change: StartupStateChange) => {
+    updateTrayMenu(STARTUP_TRAY_LABELS[change.state]);
+  });
+  kuboManager.on('startup-state', (change: StartupStateChange) => {
+    uptime.observe(change.state === 'Online');
+    updateTrayTooltip();
+  });
+
+  // Web clients on /ws/events and /api/v1/events get the same lifecycle the tray shows
+  kuboManager.on('startup-state', (change) => eventBus.publish('status', 'startup-state', change));
+  // The peer ID in the mDNS record is only known once the daemon is up
+  kuboManager.on('startup-state', (change: StartupStateChange) => {
+    if (change.state === 'Online') updateAdvertisement().catch(() => undefined);
+  });
+  gcScheduler.on('collected', () => {
+    apiServer.refreshProofCache().catch((error) => console.error('[PoA] Failed to rebuild proof cache:', error.message));
+  });
+  // A re-init or a new identity shows up as a new peer ID once the daemon is back
+  kuboManager.on('startup-state', (change: StartupStateChange) => {
+    if (change.state === 'Online') registration.check().catch(() => undefined);
+  });
+  registration.on('registered', (state) => eventBus.publish('status', 'registration', state));
+  chainRegistration.on('progress', (progress) => eventBus.publish('status', 'chain-registration', progress));
+  rewardClaimer.on('claimed', (record) => eventBus.publish('earnings', 'reward-claim', record));
+  earningsFinality.on('finalized', (result) => eventBus.publish('earnings', 'finalized', { ...result, earnings: configStore.getEarnings() }));
+  // p2p listeners live in the daemon, so they're gone after every restart
+  kuboManager.on('startup-state', (change: StartupStateChange) => {
+    if (change.state !== 'Online') return;
+    apiServer.startP2pChallenges()
+      .catch((error) => console.error('[PoA] Failed to listen for libp2p challenges:', error.message));
+  });
+  watchdog.on('status', (status) => eventBus.publish('status', 'watchdog', { status }));
+  eventBus.on('event', (message) => analytics.observe(message));
+  kuboManager.on('crashed', (code: number | null) => analytics.observeDaemonCrash(code));
+  watchdog.on('daemon-restarted', (event) => analytics.observeRestart(event));
+  connectivity.on('connectivity-changed', (report) => eventBus.publish('status', 'connectivity', report));
+  powerManager.on('power-state-changed', (status) => eventBus.publish('status', 'power', status));
+  pinRepairer.on('progress', (progress) => eventBus.publish('pins', 'repair-progress', progress));
+  scrubber.on('scrubbed', (result) => eventBus.publish('pins', 'scrubbed', result));
+  watchdog.on('status', (status: string) => updateTrayMenu(status));
+  connectivity.on('connectivity-changed', (report: ConnectivityReport) => {
+    trayNetwork = report.reachability;
+    updateTrayMenu();
+  });
+  // Answers stuck on a flaky connection would otherwise fail without a trace
+  apiServer.getPoaResponder().on('at-risk', (count: number) => {
+    trayAtRisk = count;
+    updateTrayMenu();
+  });
+  powerManager.on('power-state-changed', (status: PowerStatus) => {
+    if (status.state === 'paused') updateTrayMenu('Paused (power saving)');
+    else if (status.state === 'lowpower') updateTrayMenu('Running (low power)');
+    else updateTrayMenu('Running');
+  });
+
+  // The API server doesn't need the daemon to bind its port, so it comes up
+  // alongside Kubo; routes that touch IPFS fail until DaemonUp.
+  const startApi = apiServer.start()
+    .then(() => console.log(`[SPK] API server started on port ${apiServer.getPort()}`))
+    .catch((error) => console.error('[SPK] Failed to start API server:', error));
+
+  const startKubo = kuboManager.start()
+    .then(() => console.log('[SPK] IPFS daemon started successfully'))
+    .catch(async (error) => {
+      console.error('[SPK] Failed to start IPFS:', error);
+      if (error instanceof BinaryIntegrityError) {
+        await offerBinaryRepair(error);
+      } else if (error instanceof PreflightError) {
+        const details = error.issues
+          .filter((issue) => issue.severity === 'error')
+          .map((issue) => `• ${issue.message}\n  ${issue.fix}`)
+          .join('\n\n');
+        dialog.showErrorBox('SPK Desktop Agent', `IPFS could not be started:\n\n${details}`);
+      } else {
+        dialog.showErrorBox('SPK Desktop Agent', `Failed to start IPFS: ${error}`);
+      }
+    });
+
+  await startKubo;
+  watchdog.start();
+  gcScheduler.start();
+  scrubber.start();
+  storageMonitor.start();
+  connectivity.start();
+  registration.start();
+  hiveRpc.start();
+  txQueue.start();
+  honeycomb.start();
+  earningsFinality.start();
+  chainRegistration.start();
+  rewardClaimer.start();
+  brocaTracker.start();
+  priceFeed.start();
+  powerManager.start();
+  nodeStats.start();
+  uptime.start();
+  dailySummary.start();
+  networkRank.start();
+  earningsAnomaly.start();
+  backups.start();
+  setInterval(() => updateTrayTooltip(), 5 * 60 * 1000);
+  await Promise.all([kuboPool.startExtras(), startApi]);
+  apiServer.resumePinRequests();
+  updateAdvertisement().catch((error) => console.error('[SPK] Failed to advertise agent:', error));
 
   // Check for updates after startup
   setTimeout(() => {
@@ -122,9 +531,78 @@ app.on('window-all-closed', () => {
   // Don't quit on macOS
 });
 
-app.on('before-quit', async () => {
+/**
+ * Stops everything in dependency order: the watchdog first so it doesn't
+ * resurrect the daemon, then the API server (which waits for in-flight
+ * challenge responses to finish recording earnings), then Kubo so the repo
+ * lock is released cleanly.
+ */
+async function shutdown(): Promise<void> {
   console.log('[SPK] Shutting down...');
-  await kuboManager?.stop();
-  await apiServer?.stop();
-  app.exit(0);
+  watchdog?.stop();
+  webhooks?.stop();
+  advertiser?.stop();
+  gcScheduler?.stop();
+  scrubber?.stop();
+  storageMonitor?.stop();
+  connectivity?.stop();
+  registration?.stop();
+  hiveRpc?.stop();
+  secrets?.lock();
+  txQueue?.stop();
+  honeycomb?.stop();
+  earningsFinality?.stop();
+  hiveSigner?.stop();
+  chainRegistration?.stop();
+  rewardClaimer?.stop();
+  brocaTracker?.stop();
+  priceFeed?.stop();
+  powerManager?.stop();
+  nodeStats?.stop();
+  uptime?.stop();
+  dailySummary?.stop();
+  networkRank?.stop();
+  earningsAnomaly?.stop();
+  backups?.stop();
+  analytics?.stop();
+
+  const steps = (async () => {
+    try {
+      await apiServer?.stop();
+    } catch (error) {
+      console.error('[SPK] Failed to stop API server:', error);
+    }
+    // Last challenge answers are booked by now
+    configStore?.getEarningsStore().close();
+    analytics?.close();
+    try {
+      await Promise.all([kuboManager?.stop(), kuboPool?.stopExtras()]);
+    } catch (error) {
+      console.error('[SPK] Failed to stop IPFS daemon:', error);
+    }
+  })();
+
+  const timeout = new Promise<void>((resolve) => {
+    setTimeout(() => {
+      console.warn(`[SPK] Shutdown did not finish within ${SHUTDOWN_TIMEOUT_MS}ms, exiting anyway`);
+      resolve();
+    }, SHUTDOWN_TIMEOUT_MS);
+  });
+
+  await Promise.race([steps, timeout]);
+  console.log('[SPK] Shutdown complete');
+}
+
+app.on('before-quit', (event) => {
+  if (isQuitting) return;
+  event.preventDefault();
+  isQuitting = true;
+  shutdown().finally(() => app.exit(0));
 });
+
+for (const signal of ['SIGINT', 'SIGTERM'] as const) {
+  process.on(signal, () => {
+    console.log(`[SPK] Received ${signal}`);
+    app.quit();
+  });
+}
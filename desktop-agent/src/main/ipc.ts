@@ -0,0 +1,1672 @@
+import * as crypto from 'crypto';
+import { app, ipcMain, BrowserWindow, dialog, shell } from 'electron';
+import { KuboManager } from './kubo';
+import { ConfigStore } from './config';
+import { KuboUpdater } from './kubo-updater';
+import { DatastoreMigrator, detectDatastore } from './datastore';
+import { DaemonWatchdog } from './watchdog';
+import { GcScheduler } from './gc-scheduler';
+import { StorageMonitor } from './storage-monitor';
+import { BandwidthLimits, validateBandwidthLimits } from './bandwidth';
+import { validatePeeringDnsaddrs } from './peering';
+import { ConnectivityMonitor } from './connectivity';
+import { PowerManager, PowerPolicy } from './power';
+import { KuboPool } from './kubo-pool';
+import { NodeStatsCollector } from './node-stats';
+import { PinRepairer } from './pin-repair';
+import { PinScrubber, ScrubSettings, validateScrubSettings } from './scrubber';
+import { PrivateNetwork, validatePrivateNetwork } from './private-network';
+import { ResourceLimits, validateResourceLimits } from './resource-limits';
+import { ApiAuth } from './auth';
+import { CorsSettings, normalizeOrigin, OFFICIAL_ORIGINS } from './cors';
+import { TlsSettings, CertificateManager, validateTlsSettings } from './tls';
+import { ApiServer } from './api';
+import { DiscoverySettings } from './discovery';
+import { RemoteAccessSettings, validateRemoteAccess } from './remote-access';
+import { GrpcSettings, validateGrpcSettings } from './grpc';
+import { Webhook, WebhookDispatcher } from './webhooks';
+import { PoaSettings, validatePoaSettings } from './poa-responder';
+import { ValidatorSettings, validateValidatorSettings } from './validator';
+import { ChallengeLogQuery, validateChallengeLogQuery } from './challenge-log';
+import { NodeRegistration } from './registration';
+import { HiveAccount, HiveLoginRequest } from './hive';
+import { AccountDirectory, NotificationPreferences, validateNotificationPreferences } from './accounts';
+import { HiveRpcPool, HiveNodeSettings, validateHiveNodeSettings, DEFAULT_HIVE_NODES } from './hive-rpc';
+import { SecretStore, VaultSettings, validateVaultSettings } from './secrets';
+import { HiveSigner, HiveSigningSettings, validateHiveSigningSettings } from './hive-signing';
+import { TransactionQueue } from './tx-queue';
+import { HoneycombClient, HoneycombSettings, validateHoneycombSettings } from './honeycomb';
+import { DelegationManager, DelegationRequest, DelegationSettings, validateDelegationSettings } from './delegations';
+import { ChainRegistration, ChainRegistrationSettings, validateChainRegistrationSettings } from './chain-registration';
+import { RewardClaimer, RewardClaimSettings, validateRewardClaimSettings } from './reward-claims';
+import { BrocaTracker } from './broca';
+import { WalletProvider, WalletSettings, validateWalletSettings } from './wallet';
+import { PriceFeed, PriceFeedSettings, validatePriceFeedSettings, SUPPORTED_CURRENCIES } from './price-feed';
+import { BenchmarkOptions, validateBenchmarkOptions } from './benchmark';
+import { ContractPolicy, validateContractPolicy } from './contract-policy';
+import { ContractExpirySettings, validateContractExpirySettings } from './contract-expiry';
+import { TrashSettings, validateTrashSettings } from './trash';
+import { EarningsGoal, EarningsSeriesQuery, MilestoneSettings, validateEarningsSeriesQuery, validateMilestoneSettings } from './earnings';
+import { EarningsExporter, EarningsExportQuery, validateEarningsExportQuery } from './earnings-export';
+import { MAX_PROJECTED_GB } from './earnings-projection';
+import { UptimeTracker } from './uptime';
+import { DailySummaryScheduler, DailySummarySettings, validateDailySummarySettings } from './daily-summary';
+import { NetworkRankSettings, NetworkRankTracker, validateNetworkRankSettings } from './network-rank';
+import { AnomalySettings, EarningsAnomalyDetector, validateAnomalySettings } from './earnings-anomaly';
+import { AnalyticsQuery, AnalyticsSettings, AnalyticsStore, validateAnalyticsQuery, validateAnalyticsSettings } from './analytics';
+import { BackupManager, BackupSettings, validateBackupSettings, validatePassphrase } from './backup';
+import { ContractMarketSettings, validateContractMarketSettings } from './contract-market';
+import { ImportSource } from './pin-import';
+import { CarImportRequest } from './car-archive';
+import {
+  ReplicationTier,
+  TierSettings,
+  isReplicationTier,
+  validateReplicationTierSettings,
+  REPLICATION_TIERS,
+} from './replication-tiers';
+import { PinReconcileSettings, validatePinReconcileSettings } from './pin-reconciler';
+import { DiskHealthSettings, validateDiskHealthSettings } from './disk-health';
+import { LOG_LEVELS, LogLevel, meetsLevel } from './daemon-log';
+import {
+  KuboSettings,
+  validateKuboSettings,
+  ConnectionMode,
+  validateConnectionMode,
+  DATASTORE_TYPES,
+  DatastoreType,
+} from './settings';
+
+export interface IpcContext {
+  kubo: KuboManager;
+  config: ConfigStore;
+  kuboUpdater: KuboUpdater;
+  datastoreMigrator: DatastoreMigrator;
+  watchdog: DaemonWatchdog;
+  gcScheduler: GcScheduler;
+  storageMonitor: StorageMonitor;
+  connectivity: ConnectivityMonitor;
+  powerManager: PowerManager;
+  nodeStats: NodeStatsCollector;
+  kuboPool: KuboPool;
+  pinRepairer: PinRepairer;
+  scrubber: PinScrubber;
+  apiAuth: ApiAuth;
+  apiServer: ApiServer;
+  certificates: CertificateManager;
+  webhooks: WebhookDispatcher;
+  registration: NodeRegistration;
+  hive: HiveAccount;
+  accounts: AccountDirectory;
+  hiveRpc: HiveRpcPool;
+  secrets: SecretStore;
+  hiveSigner: HiveSigner;
+  txQueue: TransactionQueue;
+  honeycomb: HoneycombClient;
+  chainRegistration: ChainRegistration;
+  rewardClaimer: RewardClaimer;
+  broca: BrocaTracker;
+  wallet: WalletProvider;
+  delegations: DelegationManager;
+  prices: PriceFeed;
+  uptime: UptimeTracker;
+  dailySummary: DailySummaryScheduler;
+  networkRank: NetworkRankTracker;
+  anomaly: EarningsAnomalyDetector;
+  analytics: AnalyticsStore;
+  backups: BackupManager;
+  updateAdvertisement: () => Promise<void>;
+  getMainWindow: () => BrowserWindow | null;
+}
+
+export function sendToWindow(window: BrowserWindow | null, channel: string, payload?: unknown): void {
+  if (window && !window.isDestroyed()) {
+    window.webContents.send(channel, payload);
+  }
+}
+
+/**
+ * Registers the commands the dashboard can invoke via ipcRenderer.invoke()
+ * and forwards main-process events to the renderer.
+ */
+export function registerIpcHandlers(ctx: IpcContext): void {
+  // Lines below this level aren't sent to the window; the buffer keeps them all
+  let logStreamLevel: LogLevel = 'info';
+  ctx.kubo.on('log', (entry) => {
+    if (meetsLevel(entry.level, logStreamLevel)) {
+      sendToWindow(ctx.getMainWindow(), 'daemon-log', entry);
+    }
+  });
+
+  ctx.kubo.on('startup-state', (change) => {
+    sendToWindow(ctx.getMainWindow(), 'startup-state-changed', change);
+  });
+
+  ctx.apiServer.getRiskMonitor().on('changed', (risks) => {
+    sendToWindow(ctx.getMainWindow(), 'slashing-risk', risks);
+  });
+
+  // Keychain links and HAS QR codes the user has to act on
+  ctx.hiveSigner.on('request', (request) => {
+    sendToWindow(ctx.getMainWindow(), 'hive-signing-request', request);
+  });
+
+  ctx.hiveSigner.on('resolved', (outcome) => {
+    sendToWindow(ctx.getMainWindow(), 'hive-signing-resolved', outcome);
+  });
+
+  ctx.chainRegistration.on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'chain-registration-progress', progress);
+  });
+
+  ctx.rewardClaimer.on('claimed', (record) => {
+    sendToWindow(ctx.getMainWindow(), 'reward-claimed', record);
+  });
+
+  for (const event of ['locked', 'unlocked']) {
+    ctx.secrets.on(event, () => sendToWindow(ctx.getMainWindow(), 'vault-state', ctx.secrets.getStatus()));
+  }
+
+  ctx.txQueue.on('update', (entry) => {
+    // Signed transactions stay in the main process
+    sendToWindow(ctx.getMainWindow(), 'tx-queue-update', { ...entry, signed: null });
+  });
+
+  ctx.kubo.on('preflight', (result) => {
+    sendToWindow(ctx.getMainWindow(), 'kubo-preflight', result);
+  });
+
+  ctx.kubo.on('download-progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'kubo-download-progress', progress);
+  });
+
+  ctx.kuboUpdater.on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'kubo-download-progress', progress);
+  });
+
+  ctx.kuboUpdater.on('status', (message) => {
+    sendToWindow(ctx.getMainWindow(), 'kubo-update-status', message);
+  });
+
+  ctx.datastoreMigrator.on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'datastore-migration-progress', progress);
+  });
+
+  ctx.datastoreMigrator.on('status', (message) => {
+    sendToWindow(ctx.getMainWindow(), 'datastore-migration-status', message);
+  });
+
+  ctx.pinRepairer.on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'pin-repair-progress', progress);
+  });
+
+  ctx.scrubber.on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'scrub-progress', progress);
+  });
+
+  ctx.scrubber.on('scrubbed', (result) => {
+    sendToWindow(ctx.getMainWindow(), 'scrubbed', result);
+  });
+
+  ctx.apiServer.getPinImporter().on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'pin-import-progress', progress);
+  });
+
+  ctx.apiServer.getCarArchiver().on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'car-import-progress', progress);
+  });
+
+  ctx.watchdog.on('daemon-restarted', (event) => {
+    sendToWindow(ctx.getMainWindow(), 'daemon-restarted', event);
+  });
+
+  ctx.storageMonitor.on('storage-warning', (usage) => {
+    sendToWindow(ctx.getMainWindow(), 'storage-warning', usage);
+  });
+
+  ctx.apiServer.getDiskHealth().on('changed', (reports) => {
+    sendToWindow(ctx.getMainWindow(), 'disk-health', reports);
+  });
+
+  ctx.apiServer.getRepoMover().on('progress', (progress) => {
+    sendToWindow(ctx.getMainWindow(), 'repo-move-progress', progress);
+  });
+
+  ctx.apiServer.getRepoMover().on('status', (message) => {
+    sendToWindow(ctx.getMainWindow(), 'repo-move-status', message);
+  });
+
+  ctx.connectivity.on('connectivity-changed', (report) => {
+    sendToWindow(ctx.getMainWindow(), 'connectivity-changed', report);
+  });
+
+  ctx.powerManager.on('power-state-changed', (status) => {
+    sendToWindow(ctx.getMainWindow(), 'power-state-changed', status);
+  });
+
+  ipcMain.handle('ensure-kubo-binary', async () => {
+    return ctx.kubo.ensureBinary();
+  });
+
+  ipcMain.handle('repair-kubo-binary', async () => {
+    try {
+      const binaryPath = await ctx.kubo.repairBinary();
+      if (!ctx.kubo.isRunning()) await ctx.kubo.start();
+      return { success: true, binaryPath };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('check-kubo-update', async () => {
+    return ctx.kuboUpdater.checkForUpdate();
+  });
+
+  ipcMain.handle('apply-kubo-update', async () => {
+    return ctx.kuboUpdater.applyUpdate();
+  });
+
+  ipcMain.handle('get-kubo-settings', () => {
+    return ctx.config.getConfig().kubo;
+  });
+
+  // Saves the settings and restarts the daemon so they take effect
+  ipcMain.handle('set-kubo-settings', async (_event, update: Partial<KuboSettings>) => {
+    const errors = validateKuboSettings({ ...ctx.config.getConfig().kubo, ...update });
+    const currentDatastore = detectDatastore(ctx.kubo.getRepoPath());
+    if (update.datastore && currentDatastore && update.datastore !== currentDatastore) {
+      errors.push(`The repo uses ${currentDatastore}; use the datastore migration to switch to ${update.datastore}`);
+    }
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    ctx.config.setConfig({ kubo: { ...ctx.config.getConfig().kubo, ...update } });
+    if (ctx.kubo.isRunning()) {
+      await ctx.kubo.restart();
+    }
+    return { success: true, settings: ctx.config.getConfig().kubo };
+  });
+
+  ipcMain.handle('get-connection-mode', () => {
+    return ctx.config.getConfig().connectionMode;
+  });
+
+  // Stops whatever node we're attached to, switches mode and reconnects
+  ipcMain.handle('set-connection-mode', async (_event, mode: ConnectionMode) => {
+    const errors = validateConnectionMode(mode);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    await ctx.kubo.stop();
+    ctx.config.setConfig({ connectionMode: mode });
+    try {
+      await ctx.kubo.start();
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+    return { success: true, connectionMode: mode };
+  });
+
+  ipcMain.handle('get-gc-status', () => {
+    return ctx.gcScheduler.getStatus();
+  });
+
+  ipcMain.handle('run-gc', async () => {
+    return ctx.gcScheduler.runNow();
+  });
+
+  ipcMain.handle('get-storage-usage', async () => {
+    return ctx.storageMonitor.check();
+  });
+
+  // Sizes any new pins first on the first call, or when asked to
+  ipcMain.handle('get-storage-breakdown', async (_event, refresh = false) => {
+    const indexer = ctx.apiServer.getStorageBreakdown();
+    try {
+      const breakdown = refresh || !indexer.getBreakdown().indexedAt ? await indexer.index() : indexer.getBreakdown();
+      return { success: true, breakdown };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-bandwidth-limits', () => {
+    return ctx.config.getConfig().bandwidth;
+  });
+
+  // Bitswap and connection manager limits are only read at daemon start
+  ipcMain.handle('set-bandwidth-limits', async (_event, update: Partial<BandwidthLimits>) => {
+    const errors = validateBandwidthLimits(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    ctx.config.setConfig({ bandwidth: { ...ctx.config.getConfig().bandwidth, ...update } });
+    if (ctx.kubo.isRunning() && !ctx.kubo.isExternal()) {
+      await ctx.kubo.restart();
+    }
+    return { success: true, bandwidth: ctx.config.getConfig().bandwidth };
+  });
+
+  ipcMain.handle('get-peering-peers', () => {
+    return { ...ctx.kubo.getPeeringPeers(), dnsaddrs: ctx.config.getConfig().peeringDnsaddrs };
+  });
+
+  // dnsaddrs are resolved into Peering.Peers when the daemon starts
+  ipcMain.handle('set-peering-dnsaddrs', async (_event, dnsaddrs: string[]) => {
+    const errors = validatePeeringDnsaddrs(dnsaddrs);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    ctx.config.setConfig({ peeringDnsaddrs: dnsaddrs });
+    if (ctx.kubo.isRunning() && !ctx.kubo.isExternal()) {
+      await ctx.kubo.restart();
+    }
+    return { success: true, dnsaddrs };
+  });
+
+  ipcMain.handle('add-peering-peer', async (_event, multiaddr: string) => {
+    return ctx.kubo.addPeeringPeer(multiaddr);
+  });
+
+  ipcMain.handle('remove-peering-peer', async (_event, peerId: string) => {
+    await ctx.kubo.removePeeringPeer(peerId);
+    return ctx.kubo.getPeeringPeers();
+  });
+
+  ipcMain.handle('check-connectivity', async () => {
+    return ctx.connectivity.check();
+  });
+
+  ipcMain.handle('get-power-status', () => {
+    return ctx.powerManager.getStatus();
+  });
+
+  ipcMain.handle('set-power-policy', (_event, update: Partial<PowerPolicy>) => {
+    ctx.config.setConfig({ powerPolicy: { ...ctx.config.getConfig().powerPolicy, ...update } });
+    ctx.powerManager.evaluate();
+    return ctx.powerManager.getStatus();
+  });
+
+  ipcMain.handle('get-kubo-instances', async () => {
+    return ctx.kuboPool.getStatus();
+  });
+
+  ipcMain.handle('get-kubo-config', async () => {
+    return ctx.kubo.getRepoConfig();
+  });
+
+  ipcMain.handle('set-kubo-config-key', async (_event, keyPath: string, value: any) => {
+    try {
+      const result = await ctx.kubo.setRepoConfigKey(keyPath, value);
+      return { success: true, ...result };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('run-preflight', async () => {
+    if (ctx.kubo.isExternal()) return { ok: true, issues: [] };
+    return ctx.kubo.preflight();
+  });
+
+  ipcMain.handle('get-datastore', () => {
+    return detectDatastore(ctx.kubo.getRepoPath()) || ctx.config.getConfig().kubo.datastore;
+  });
+
+  // Copies all pins into a repo with the other datastore; can take a while
+  ipcMain.handle('migrate-datastore', async (_event, target: DatastoreType) => {
+    if (!DATASTORE_TYPES.includes(target)) {
+      return { success: false, errors: [`datastore must be one of: ${DATASTORE_TYPES.join(', ')}`] };
+    }
+    return ctx.datastoreMigrator.migrate(target);
+  });
+
+  ipcMain.handle('get-daemon-logs', (_event, minLevel: LogLevel = 'debug', limit?: number) => {
+    return ctx.kubo.getLogs(minLevel, limit);
+  });
+
+  ipcMain.handle('set-daemon-log-level', (_event, level: LogLevel) => {
+    if (LOG_LEVELS.includes(level)) logStreamLevel = level;
+    return logStreamLevel;
+  });
+
+  ipcMain.handle('clear-daemon-logs', () => {
+    ctx.kubo.clearLogs();
+  });
+
+  ipcMain.handle('get-node-stats', (_event, since?: string) => {
+    return { latest: ctx.nodeStats.getLatest(), series: ctx.nodeStats.getSeries(since) };
+  });
+
+  ipcMain.handle('reprovide-now', async (_event, cids?: string[]) => {
+    try {
+      await ctx.kubo.reprovide(cids);
+      return { success: true };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('repair-pins', async () => {
+    return ctx.pinRepairer.repairAll();
+  });
+
+  ipcMain.handle('get-scrub-status', () => {
+    return { settings: ctx.config.getConfig().scrub, ...ctx.scrubber.getStatus() };
+  });
+
+  ipcMain.handle('set-scrub-settings', (_event, update: Partial<ScrubSettings>) => {
+    const errors = validateScrubSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ scrub: { ...ctx.config.getConfig().scrub, ...update } });
+    return { success: true, scrub: ctx.config.getConfig().scrub };
+  });
+
+  // Runs the next slice of the rotation now instead of waiting for the schedule
+  ipcMain.handle('run-scrub', async () => {
+    try {
+      return { success: true, result: await ctx.scrubber.scrub() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-pin-import', async () => {
+    const importer = ctx.apiServer.getPinImporter();
+    return { ...importer.getStatus(), candidates: await importer.detect() };
+  });
+
+  ipcMain.handle('list-import-pins', async (_event, source: ImportSource) => {
+    try {
+      return { success: true, pins: await ctx.apiServer.getPinImporter().listPins(source) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  // Copies every pin, or the CIDs given, from another node; can take a while
+  ipcMain.handle('import-pins', async (_event, source: ImportSource, cids?: string[]) => {
+    try {
+      return { success: true, result: await ctx.apiServer.getPinImporter().import(source, cids) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('cancel-pin-import', () => {
+    ctx.apiServer.getPinImporter().cancel();
+    return { success: true };
+  });
+
+  // Writes one CAR per pinned CID and a manifest into a folder the user picks
+  ipcMain.handle('export-contract-car', async (_event, contractId: string) => {
+    const window = ctx.getMainWindow();
+    const options: Electron.OpenDialogOptions = { title: 'Export contract as CAR', properties: ['openDirectory', 'createDirectory'] };
+    const choice = window ? await dialog.showOpenDialog(window, options) : await dialog.showOpenDialog(options);
+    if (choice.canceled || choice.filePaths.length === 0) return { success: false, errors: [] };
+    try {
+      return { success: true, result: await ctx.apiServer.getCarArchiver().exportContract(contractId, choice.filePaths[0]) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('choose-car-files', async (_event, folder: boolean) => {
+    const window = ctx.getMainWindow();
+    const options: Electron.OpenDialogOptions = folder
+      ? { title: 'Choose a folder of CAR files', properties: ['openDirectory'] }
+      : { title: 'Choose CAR files', properties: ['openFile', 'multiSelections'], filters: [{ name: 'CAR archives', extensions: ['car'] }] };
+    const choice = window ? await dialog.showOpenDialog(window, options) : await dialog.showOpenDialog(options);
+    return choice.canceled ? [] : choice.filePaths;
+  });
+
+  ipcMain.handle('import-car-files', async (_event, request: CarImportRequest) => {
+    try {
+      return { success: true, result: await ctx.apiServer.getCarArchiver().importCars(request) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  // Never hand the key itself back to the renderer
+  ipcMain.handle('get-private-network', () => {
+    const network = ctx.config.getConfig().privateNetwork;
+    return network ? { enabled: true, bootstrap: network.bootstrap } : { enabled: false, bootstrap: [] };
+  });
+
+  ipcMain.handle('set-private-network', async (_event, network: PrivateNetwork | null) => {
+    const errors = network ? validatePrivateNetwork(network) : [];
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    try {
+      await ctx.kubo.setPrivateNetwork(network);
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+    return { success: true };
+  });
+
+  ipcMain.handle('get-resource-limits', () => {
+    return ctx.config.getConfig().resourceLimits;
+  });
+
+  // The resource manager is built once at daemon start
+  ipcMain.handle('set-resource-limits', async (_event, update: Partial<ResourceLimits>) => {
+    const limits = { ...ctx.config.getConfig().resourceLimits, ...update };
+    const errors = validateResourceLimits(limits);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    ctx.config.setConfig({ resourceLimits: limits });
+    if (ctx.kubo.isRunning() && !ctx.kubo.isExternal()) {
+      await ctx.kubo.restart();
+    }
+    return { success: true, resourceLimits: limits };
+  });
+
+  ipcMain.handle('get-resource-usage', async () => {
+    try {
+      return await ctx.kubo.getResourceUsage();
+    } catch {
+      return [];
+    }
+  });
+
+  ipcMain.handle('get-startup-state', () => {
+    return ctx.kubo.getStartupState();
+  });
+
+  // The dashboard is trusted and calls the local API with the same token
+  ipcMain.handle('get-api-token', () => {
+    return ctx.apiAuth.getToken();
+  });
+
+  ipcMain.handle('regenerate-api-token', () => {
+    ctx.apiAuth.regenerateToken();
+    return { success: true };
+  });
+
+  ipcMain.handle('get-cors-settings', () => {
+    return { ...ctx.config.getConfig().cors, officialOrigins: OFFICIAL_ORIGINS };
+  });
+
+  ipcMain.handle('set-cors-settings', (_event, update: Partial<CorsSettings>) => {
+    const errors: string[] = [];
+    const normalize = (origins: string[] | undefined) => origins?.map((origin) => {
+      const normalized = normalizeOrigin(origin);
+      if (!normalized) errors.push(`Invalid origin: ${origin}`);
+      return normalized || origin;
+    });
+
+    const cors = {
+      ...ctx.config.getConfig().cors,
+      ...update,
+      ...(update.allowedOrigins ? { allowedOrigins: normalize(update.allowedOrigins)! } : {}),
+      ...(update.deniedOrigins ? { deniedOrigins: normalize(update.deniedOrigins)! } : {}),
+    };
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    ctx.config.setConfig({ cors });
+    return { success: true, cors };
+  });
+
+  ipcMain.handle('get-tls-settings', () => {
+    return {
+      ...ctx.config.getConfig().tls,
+      listening: ctx.apiServer.getHttpsPort() !== null,
+      caPath: ctx.certificates.getCaPath(),
+      caFingerprint: ctx.certificates.getCaFingerprint(),
+    };
+  });
+
+  ipcMain.handle('set-tls-settings', async (_event, update: Partial<TlsSettings>) => {
+    const errors = validateTlsSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    const previous = ctx.config.getConfig().tls;
+    ctx.config.setConfig({ tls: { ...previous, ...update } });
+    try {
+      await ctx.apiServer.startHttps();
+      // gRPC shares the certificate, so it follows TLS on and off
+      await ctx.apiServer.startGrpc();
+      await ctx.updateAdvertisement();
+      return { success: true, tls: ctx.config.getConfig().tls };
+    } catch (error: any) {
+      ctx.config.setConfig({ tls: previous });
+      await ctx.apiServer.startHttps().catch(() => undefined);
+      await ctx.apiServer.startGrpc().catch(() => undefined);
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('trust-tls-certificate', () => {
+    return ctx.certificates.trustCa();
+  });
+
+  ipcMain.handle('get-discovery-settings', () => {
+    return ctx.config.getConfig().discovery;
+  });
+
+  ipcMain.handle('set-discovery-settings', async (_event, update: Partial<DiscoverySettings>) => {
+    if (update.enabled !== undefined && typeof update.enabled !== 'boolean') {
+      return { success: false, errors: ['enabled must be a boolean'] };
+    }
+    ctx.config.setConfig({ discovery: { ...ctx.config.getConfig().discovery, ...update } });
+    await ctx.updateAdvertisement();
+    return { success: true, discovery: ctx.config.getConfig().discovery };
+  });
+
+  ipcMain.handle('get-remote-access', () => {
+    return ctx.config.getConfig().remoteAccess;
+  });
+
+  ipcMain.handle('set-remote-access', async (_event, update: Partial<RemoteAccessSettings>) => {
+    const errors = validateRemoteAccess(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    const previous = ctx.config.getConfig().remoteAccess;
+    ctx.config.setConfig({ remoteAccess: { ...previous, ...update } });
+    try {
+      await ctx.apiServer.rebind();
+    } catch (error: any) {
+      // Most likely the address isn't on this machine; go back to what worked
+      ctx.config.setConfig({ remoteAccess: previous });
+      await ctx.apiServer.rebind().catch(() => undefined);
+      return { success: false, errors: [error.message] };
+    }
+    await ctx.updateAdvertisement();
+
+    const remoteAccess = ctx.config.getConfig().remoteAccess;
+    const warnings: string[] = [];
+    if (remoteAccess.enabled && !ctx.config.getConfig().tls.enabled && !remoteAccess.trustProxy) {
+      warnings.push('The API token will cross the network unencrypted; enable HTTPS or put a TLS reverse proxy in front');
+    }
+    if (remoteAccess.enabled && remoteAccess.allowedIps.length === 0) {
+      warnings.push('Any address that can reach this machine may try the API; consider an IP allowlist');
+    }
+    return { success: true, remoteAccess, warnings };
+  });
+
+  ipcMain.handle('get-grpc-settings', () => {
+    return {
+      ...ctx.config.getConfig().grpc,
+      listening: ctx.apiServer.getGrpcPort() !== null,
+      tls: ctx.config.getConfig().tls.enabled,
+    };
+  });
+
+  ipcMain.handle('set-grpc-settings', async (_event, update: Partial<GrpcSettings>) => {
+    const errors = validateGrpcSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+
+    const previous = ctx.config.getConfig().grpc;
+    ctx.config.setConfig({ grpc: { ...previous, ...update } });
+    try {
+      await ctx.apiServer.startGrpc();
+      return { success: true, grpc: ctx.config.getConfig().grpc };
+    } catch (error: any) {
+      ctx.config.setConfig({ grpc: previous });
+      await ctx.apiServer.startGrpc().catch(() => undefined);
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-webhooks', () => {
+    return { webhooks: ctx.webhooks.list(), deliveries: ctx.webhooks.getDeliveries() };
+  });
+
+  ipcMain.handle('add-webhook', (_event, webhook: Partial<Webhook>) => {
+    return ctx.webhooks.add(webhook);
+  });
+
+  ipcMain.handle('update-webhook', (_event, id: string, update: Partial<Webhook>) => {
+    return ctx.webhooks.update(id, update);
+  });
+
+  ipcMain.handle('remove-webhook', (_event, id: string) => {
+    return ctx.webhooks.remove(id);
+  });
+
+  ipcMain.handle('get-webhook-secret', (_event, id: string) => {
+    try {
+      const secret = ctx.webhooks.getSecret(id);
+      return secret ? { success: true, secret } : { success: false, errors: ['Unknown webhook'] };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('test-webhook', (_event, id: string) => {
+    return ctx.webhooks.test(id);
+  });
+
+  ipcMain.handle('get-poa-settings', () => {
+    const responder = ctx.apiServer.getPoaResponder();
+    return {
+      ...ctx.config.getConfig().poa,
+      connections: responder.getStatus(),
+      atRisk: responder.getPendingSubmissions().length,
+      signer: { source: ctx.apiServer.getSigner().getKeySource(), publicKey: ctx.apiServer.getSigner().getPublicKey() },
+    };
+  });
+
+  ipcMain.handle('set-poa-settings', (_event, update: Partial<PoaSettings>) => {
+    const errors = validatePoaSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const previous = ctx.config.getConfig().poa;
+    const budget = update.budget
+      ? { ...previous.budget, ...update.budget, stages: { ...previous.budget.stages, ...update.budget.stages } }
+      : previous.budget;
+    ctx.config.setConfig({ poa: { ...previous, ...update, budget } });
+    ctx.apiServer.getPoaResponder().reload();
+    return { success: true, poa: ctx.config.getConfig().poa };
+  });
+
+  ipcMain.handle('get-challenge-diagnostics', () => {
+    return ctx.apiServer.getPoaResponder().getDiagnostics().getReport();
+  });
+
+  ipcMain.handle('get-validator-settings', () => {
+    return { ...ctx.config.getConfig().validator, status: ctx.apiServer.getPoaValidator().getStatus() };
+  });
+
+  ipcMain.handle('set-validator-settings', (_event, update: Partial<ValidatorSettings>) => {
+    const errors = validateValidatorSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ validator: { ...ctx.config.getConfig().validator, ...update } });
+    ctx.apiServer.getPoaValidator().reload();
+    return { success: true, validator: ctx.config.getConfig().validator };
+  });
+
+  ipcMain.handle('run-validation-round', async () => {
+    try {
+      const results = await ctx.apiServer.getPoaValidator().runRound();
+      return { success: true, results };
+    } catch (error: any) {
+      return { success: false, error: error.message };
+    }
+  });
+
+  ipcMain.handle('get-challenge-history', (_event, query: ChallengeLogQuery = {}) => {
+    const errors = validateChallengeLogQuery(query);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const log = ctx.apiServer.getChallengeLog();
+    return { success: true, ...log.query(query), days: log.summarizeByDay(query) };
+  });
+
+  ipcMain.handle('get-registration-status', () => {
+    return ctx.registration.getStatus();
+  });
+
+  ipcMain.handle('refresh-registration', () => {
+    return ctx.registration.check(true);
+  });
+
+  ipcMain.handle('get-eligibility', (_event, refresh: boolean = false) => {
+    return ctx.apiServer.getEligibility().getReport(refresh);
+  });
+
+  ipcMain.handle('run-poa-benchmark', async (_event, options: Partial<BenchmarkOptions> = {}) => {
+    const errors = validateBenchmarkOptions(options);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    try {
+      return { success: true, report: await ctx.apiServer.getBenchmark().run(options) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('cancel-poa-benchmark', () => {
+    ctx.apiServer.getBenchmark().cancel();
+  });
+
+  ipcMain.handle('get-contract-policy', () => {
+    return { ...ctx.config.getConfig().contractPolicy, decisions: ctx.apiServer.getContractPolicy().getDecisions() };
+  });
+
+  ipcMain.handle('set-contract-policy', (_event, update: Partial<ContractPolicy>) => {
+    const errors = validateContractPolicy(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ contractPolicy: { ...ctx.config.getConfig().contractPolicy, ...update } });
+    ctx.apiServer.getContractPolicy().reload();
+    return { success: true, contractPolicy: ctx.config.getConfig().contractPolicy };
+  });
+
+  ipcMain.handle('preview-contract-policy', async (_event, overrides: Partial<ContractPolicy> = {}) => {
+    const errors = validateContractPolicy(overrides);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    try {
+      return { success: true, decisions: await ctx.apiServer.getContractPolicy().preview(overrides) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('run-contract-policy', async () => {
+    try {
+      return { success: true, decisions: await ctx.apiServer.getContractPolicy().runOnce() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-contract-bandwidth', (_event, days = 30) => {
+    return ctx.apiServer.getContractBandwidth().getReport(days);
+  });
+
+  ipcMain.handle('get-contract-trash', () => {
+    return { settings: ctx.config.getConfig().trash, contracts: ctx.apiServer.getTrash().list() };
+  });
+
+  ipcMain.handle('set-trash-settings', (_event, update: Partial<TrashSettings>) => {
+    const errors = validateTrashSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ trash: { ...ctx.config.getConfig().trash, ...update } });
+    return { success: true, trash: ctx.config.getConfig().trash };
+  });
+
+  ipcMain.handle('restore-contract', (_event, contractId: string) => {
+    try {
+      const record = ctx.apiServer.getContracts().restore(contractId);
+      return record ? { success: true, record } : { success: false, errors: ['No such contract in the trash'] };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('purge-contract', async (_event, contractId: string) => {
+    try {
+      return { success: true, purge: await ctx.apiServer.getTrash().purge(contractId) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-contract-expiry', () => {
+    return ctx.apiServer.getContractExpiry().getStatus();
+  });
+
+  ipcMain.handle('set-contract-expiry-settings', (_event, update: Partial<ContractExpirySettings>) => {
+    const errors = validateContractExpirySettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ contractExpiry: { ...ctx.config.getConfig().contractExpiry, ...update } });
+    return { success: true, contractExpiry: ctx.config.getConfig().contractExpiry };
+  });
+
+  ipcMain.handle('sweep-expired-contracts', async () => {
+    try {
+      return { success: true, sweep: await ctx.apiServer.getContractExpiry().sweep() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-pin-reconciliation', () => {
+    return ctx.apiServer.getPinReconciler().getStatus();
+  });
+
+  ipcMain.handle('reconcile-pins', async () => {
+    try {
+      const reconciliation = await ctx.apiServer.getPinReconciler().run('manual');
+      if (!reconciliation) return { success: false, errors: ['A reconciliation is already running'] };
+      return { success: true, reconciliation };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('clean-orphan-pins', async (_event, cids?: string[]) => {
+    try {
+      return { success: true, cleanup: await ctx.apiServer.getPinReconciler().cleanOrphans(cids) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  // Where the dashboard loads stored media from, through the agent API rather than the gateway port
+  ipcMain.handle('get-preview-url', (_event, cid: string) => {
+    // Media elements can't send the bearer header, so the dashboard's previews carry the token in the URL
+    const token = encodeURIComponent(ctx.apiAuth.getToken());
+    return `http://127.0.0.1:${ctx.apiServer.getPort()}/api/v1/preview/${encodeURIComponent(cid)}?token=${token}`;
+  });
+
+  ipcMain.handle('get-mfs-mirror', () => {
+    return ctx.apiServer.getMfsMirror().getStatus();
+  });
+
+  ipcMain.handle('open-mfs-mirror', async () => {
+    const { roots } = await ctx.apiServer.getMfsMirror().getStatus();
+    if (roots.length === 0) return { success: false, errors: ['Nothing has been mirrored yet'] };
+    await shell.openExternal(roots[0].url);
+    return { success: true };
+  });
+
+  ipcMain.handle('set-pin-reconcile-settings', (_event, update: Partial<PinReconcileSettings>) => {
+    const errors = validatePinReconcileSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ pinReconcile: { ...ctx.config.getConfig().pinReconcile, ...update } });
+    ctx.apiServer.getPinReconciler().restart();
+    return { success: true, pinReconcile: ctx.config.getConfig().pinReconcile };
+  });
+
+  ipcMain.handle('get-contract-market', async () => {
+    try {
+      return { success: true, offers: await ctx.apiServer.getContractMarket().list(), settings: ctx.config.getConfig().contractMarket };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('accept-contract', async (_event, contractId: string) => {
+    try {
+      return { success: true, ...(await ctx.apiServer.getContractMarket().accept(contractId)) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('set-contract-market-settings', (_event, update: Partial<ContractMarketSettings>) => {
+    const errors = validateContractMarketSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ contractMarket: { ...ctx.config.getConfig().contractMarket, ...update } });
+    return { success: true, contractMarket: ctx.config.getConfig().contractMarket };
+  });
+
+  ipcMain.handle('get-replication-tiers', () => {
+    const contracts = ctx.apiServer.getContracts();
+    return {
+      tiers: ctx.apiServer.getTierScheduler().getStatus(),
+      contracts: contracts.list().map((record) => ({
+        id: record.id,
+        account: record.account,
+        cids: Object.keys(record.cids).length,
+        tier: contracts.tierOf(record),
+      })),
+    };
+  });
+
+  ipcMain.handle('set-contract-tier', (_event, contractId: string, tier: ReplicationTier) => {
+    if (!isReplicationTier(tier)) {
+      return { success: false, errors: [`tier must be one of: ${REPLICATION_TIERS.join(', ')}`] };
+    }
+    const record = ctx.apiServer.getContracts().setTier(contractId, tier);
+    if (!record) {
+      return { success: false, errors: [`Unknown contract ${contractId}`] };
+    }
+    return { success: true, contract: record };
+  });
+
+  ipcMain.handle('set-tier-settings', (_event, update: Partial<Record<ReplicationTier, Partial<TierSettings>>>) => {
+    const errors = validateReplicationTierSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const current = ctx.config.getConfig().replicationTiers;
+    const replicationTiers = { ...current };
+    for (const tier of REPLICATION_TIERS) {
+      if (update[tier]) replicationTiers[tier] = { ...current[tier], ...update[tier] };
+    }
+    ctx.config.setConfig({ replicationTiers });
+    return { success: true, replicationTiers };
+  });
+
+  ipcMain.handle('get-slashing-risks', () => {
+    return ctx.apiServer.getRiskMonitor().getRisks();
+  });
+
+  ipcMain.handle('get-disk-health', async (_event, refresh = false) => {
+    const monitor = ctx.apiServer.getDiskHealth();
+    return { settings: ctx.config.getConfig().diskHealth, disks: refresh ? await monitor.check() : monitor.getReports() };
+  });
+
+  ipcMain.handle('set-disk-health-settings', (_event, update: Partial<DiskHealthSettings>) => {
+    const errors = validateDiskHealthSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ diskHealth: { ...ctx.config.getConfig().diskHealth, ...update } });
+    return { success: true, diskHealth: ctx.config.getConfig().diskHealth };
+  });
+
+  // Copies an instance's repo into an empty folder the user picks on another drive
+  ipcMain.handle('move-repo', async (_event, instanceId: string) => {
+    const window = ctx.getMainWindow();
+    const options: Electron.OpenDialogOptions = {
+      title: 'Choose an empty folder on a healthy drive',
+      properties: ['openDirectory', 'createDirectory'],
+    };
+    const choice = window ? await dialog.showOpenDialog(window, options) : await dialog.showOpenDialog(options);
+    if (choice.canceled || choice.filePaths.length === 0) return { success: false, errors: [] };
+    try {
+      const result = await ctx.apiServer.getRepoMover().move(instanceId, choice.filePaths[0]);
+      if (result.success) ctx.apiServer.getDiskHealth().check().catch(() => undefined);
+      return result.success ? { success: true, result } : { success: false, errors: [result.error] };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('hive-login', async (_event, request: HiveLoginRequest, label?: string) => {
+    try {
+      const identity = await ctx.hive.login(request, label);
+      // The network keys nodes by account, so a new one needs registering
+      ctx.registration.check().catch(() => undefined);
+      return { success: true, identity };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('hive-logout', () => {
+    ctx.hiveSigner.forgetHasSession();
+    return { success: true, identity: ctx.hive.logout() };
+  });
+
+  ipcMain.handle('hive-whoami', () => {
+    return ctx.hive.whoami();
+  });
+
+  ipcMain.handle('get-hive-signing', () => {
+    return {
+      settings: ctx.config.getConfig().hiveSigning,
+      hasSession: ctx.hiveSigner.getHasSession(),
+      pending: ctx.hiveSigner.getPending(),
+    };
+  });
+
+  ipcMain.handle('set-hive-signing-settings', (_event, update: Partial<HiveSigningSettings>) => {
+    const errors = validateHiveSigningSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ hiveSigning: { ...ctx.config.getConfig().hiveSigning, ...update } });
+    return { success: true, hiveSigning: ctx.config.getConfig().hiveSigning };
+  });
+
+  ipcMain.handle('pair-hive-auth', async () => {
+    try {
+      return { success: true, hasSession: await ctx.hiveSigner.pairHas() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('forget-hive-auth', () => {
+    ctx.hiveSigner.forgetHasSession();
+    return { success: true };
+  });
+
+  ipcMain.handle('cancel-hive-signing', (_event, id: string) => {
+    return { success: ctx.hiveSigner.cancel(id) };
+  });
+
+  ipcMain.handle('get-chain-registration', () => {
+    return { settings: ctx.config.getConfig().chainRegistration, state: ctx.chainRegistration.getState() };
+  });
+
+  ipcMain.handle('set-chain-registration-settings', (_event, update: Partial<ChainRegistrationSettings>) => {
+    const errors = validateChainRegistrationSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ chainRegistration: { ...ctx.config.getConfig().chainRegistration, ...update } });
+    return { success: true, chainRegistration: ctx.config.getConfig().chainRegistration };
+  });
+
+  ipcMain.handle('preview-chain-registration', async (_event, overrides: Partial<ChainRegistrationSettings>) => {
+    const errors = validateChainRegistrationSettings(overrides);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    return { success: true, preview: await ctx.chainRegistration.preview(overrides) };
+  });
+
+  ipcMain.handle('broadcast-chain-registration', async (_event, overrides: Partial<ChainRegistrationSettings>) => {
+    const errors = validateChainRegistrationSettings(overrides);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    try {
+      return { success: true, state: await ctx.chainRegistration.register(overrides) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('verify-chain-registration', async () => {
+    try {
+      return { success: true, state: await ctx.chainRegistration.verify() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-reward-claims', () => {
+    return ctx.rewardClaimer.getStatus();
+  });
+
+  ipcMain.handle('set-reward-claim-settings', (_event, update: Partial<RewardClaimSettings>) => {
+    const errors = validateRewardClaimSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const current = ctx.config.getConfig().rewardClaims;
+    ctx.config.setConfig({
+      rewardClaims: { ...current, ...update, thresholds: { ...current.thresholds, ...(update.thresholds || {}) } },
+    });
+    return { success: true, rewardClaims: ctx.config.getConfig().rewardClaims };
+  });
+
+  ipcMain.handle('estimate-reward-claim', async () => {
+    try {
+      return { success: true, estimate: await ctx.rewardClaimer.estimate() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('claim-rewards-now', async () => {
+    try {
+      return { success: true, claim: await ctx.rewardClaimer.claimNow() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-broca-status', async (_event, force?: boolean) => {
+    try {
+      return { success: true, status: await ctx.broca.getStatus(force === true) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('check-broca-cost', async (_event, cost: number) => {
+    if (typeof cost !== 'number' || !Number.isFinite(cost) || cost < 0) {
+      return { success: false, errors: ['cost must be zero or more'] };
+    }
+    try {
+      return { success: true, check: await ctx.broca.checkCost(cost) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-wallet-overview', async (_event, force?: boolean) => {
+    const settings = ctx.config.getConfig().wallet;
+    try {
+      return { success: true, settings, overview: await ctx.wallet.getOverview(force === true) };
+    } catch (error: any) {
+      return { success: false, settings, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('set-wallet-settings', (_event, update: Partial<WalletSettings>) => {
+    const errors = validateWalletSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ wallet: { ...ctx.config.getConfig().wallet, ...update } });
+    return { success: true, wallet: ctx.config.getConfig().wallet };
+  });
+
+  ipcMain.handle('get-earnings-history', (_event, query: EarningsSeriesQuery = {}) => {
+    const errors = validateEarningsSeriesQuery(query);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    return { success: true, series: ctx.config.getEarningsStore().getSeries(query) };
+  });
+
+  ipcMain.handle('get-uptime-stats', () => {
+    return ctx.uptime.getStats();
+  });
+
+  ipcMain.handle('get-analytics', (_event, query: AnalyticsQuery = {}) => {
+    const errors = validateAnalyticsQuery(query);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    return { success: true, status: ctx.analytics.getStatus(), events: ctx.analytics.list(query) };
+  });
+
+  ipcMain.handle('set-analytics-settings', (_event, update: Partial<AnalyticsSettings>) => {
+    const errors = validateAnalyticsSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ analytics: { ...ctx.config.getConfig().analytics, ...update } });
+    return { success: true, analytics: ctx.config.getConfig().analytics };
+  });
+
+  ipcMain.handle('purge-analytics', () => {
+    return { success: true, purged: ctx.analytics.purge() };
+  });
+
+  ipcMain.handle('export-analytics', async () => {
+    if (!ctx.config.getConfig().analytics.allowExport) {
+      return { success: false, errors: ['Turn on the anonymized export first'] };
+    }
+    const window = ctx.getMainWindow();
+    const options: Electron.SaveDialogOptions = {
+      title: 'Export anonymized analytics',
+      defaultPath: `spk-agent-analytics-${new Date().toISOString().slice(0, 10)}.json`,
+      filters: [{ name: 'JSON', extensions: ['json'] }],
+    };
+    const choice = window ? await dialog.showSaveDialog(window, options) : await dialog.showSaveDialog(options);
+    if (choice.canceled || !choice.filePath) return { success: false, errors: [] };
+    try {
+      return { success: true, result: ctx.analytics.exportAnonymized(choice.filePath) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-backup-status', () => {
+    return ctx.backups.getStatus();
+  });
+
+  // A passphrase given here is the one automatic backups encrypt with; null forgets it
+  ipcMain.handle('set-backup-settings', (_event, update: Partial<BackupSettings>, passphrase?: string | null) => {
+    const errors = [
+      ...validateBackupSettings(update),
+      ...(typeof passphrase === 'string' ? validatePassphrase(passphrase) : []),
+    ];
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    try {
+      if (passphrase !== undefined) ctx.backups.setAutoPassphrase(passphrase);
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+    ctx.config.setConfig({ backup: { ...ctx.config.getConfig().backup, ...update } });
+    return { success: true, status: ctx.backups.getStatus() };
+  });
+
+  ipcMain.handle('export-backup', async (_event, passphrase: string) => {
+    const errors = validatePassphrase(passphrase);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const window = ctx.getMainWindow();
+    const options: Electron.SaveDialogOptions = {
+      title: 'Save an encrypted backup',
+      defaultPath: `spk-agent-backup-${new Date().toISOString().slice(0, 10)}.spkbackup`,
+      filters: [{ name: 'SPK agent backup', extensions: ['spkbackup'] }],
+    };
+    const choice = window ? await dialog.showSaveDialog(window, options) : await dialog.showSaveDialog(options);
+    if (choice.canceled || !choice.filePath) return { success: false, errors: [] };
+    try {
+      return { success: true, result: await ctx.backups.exportToFile(choice.filePath, passphrase) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('restore-backup', async (_event, passphrase: string) => {
+    const window = ctx.getMainWindow();
+    const options: Electron.OpenDialogOptions = {
+      title: 'Restore from a backup',
+      properties: ['openFile'],
+      filters: [{ name: 'SPK agent backup', extensions: ['spkbackup'] }],
+    };
+    const choice = window ? await dialog.showOpenDialog(window, options) : await dialog.showOpenDialog(options);
+    if (choice.canceled || choice.filePaths.length === 0) return { success: false, errors: [] };
+    try {
+      return { success: true, result: await ctx.backups.restoreFromFile(choice.filePaths[0], passphrase) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('restore-backup-cid', async (_event, cid: string, passphrase: string) => {
+    if (typeof cid !== 'string' || cid.trim() === '') {
+      return { success: false, errors: ['Enter the CID of a backup'] };
+    }
+    try {
+      return { success: true, result: await ctx.backups.restoreFromIpfs(cid.trim(), passphrase) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('backup-to-ipfs-now', async () => {
+    try {
+      return { success: true, backup: await ctx.backups.backupToIpfs() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  // The restored databases are swapped in as the agent starts
+  ipcMain.handle('restart-after-restore', () => {
+    app.relaunch();
+    app.quit();
+  });
+
+  ipcMain.handle('get-earnings-anomaly', () => {
+    return { settings: ctx.config.getConfig().anomaly, anomaly: ctx.anomaly.getLast() };
+  });
+
+  ipcMain.handle('set-anomaly-settings', (_event, update: Partial<AnomalySettings>) => {
+    const errors = validateAnomalySettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ anomaly: { ...ctx.config.getConfig().anomaly, ...update } });
+    return { success: true, anomaly: ctx.config.getConfig().anomaly };
+  });
+
+  ipcMain.handle('get-network-rank', async (_event, refresh = false) => {
+    try {
+      await ctx.networkRank.getRank(refresh);
+    } catch {
+      // The status carries the error, and the last rank fetched if any
+    }
+    return ctx.networkRank.getStatus();
+  });
+
+  ipcMain.handle('set-network-rank-settings', (_event, update: Partial<NetworkRankSettings>) => {
+    const errors = validateNetworkRankSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ networkRank: { ...ctx.config.getConfig().networkRank, ...update } });
+    return { success: true, networkRank: ctx.config.getConfig().networkRank };
+  });
+
+  ipcMain.handle('get-milestones', () => {
+    const settings = ctx.config.getConfig().milestones;
+    return { thresholds: settings.thresholds, goals: ctx.config.getEarningsStore().getGoalProgress(ctx.config.getEarnings(), settings) };
+  });
+
+  // New goals come without an id; one is given here
+  ipcMain.handle('set-milestone-settings', (_event, update: Partial<MilestoneSettings>) => {
+    if (Array.isArray(update.goals)) {
+      update = { ...update, goals: update.goals.map((goal: EarningsGoal) => ({ ...goal, id: goal.id || crypto.randomUUID() })) };
+    }
+    const errors = validateMilestoneSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const current = ctx.config.getConfig().milestones;
+    ctx.config.setConfig({
+      milestones: { ...current, ...update, thresholds: { ...current.thresholds, ...update.thresholds } },
+    });
+    return { success: true, milestones: ctx.config.getConfig().milestones };
+  });
+
+  ipcMain.handle('get-daily-summary', () => {
+    return ctx.dailySummary.getStatus();
+  });
+
+  ipcMain.handle('set-daily-summary-settings', (_event, update: Partial<DailySummarySettings>) => {
+    const errors = validateDailySummarySettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ dailySummary: { ...ctx.config.getConfig().dailySummary, ...update } });
+    ctx.dailySummary.reschedule();
+    return { success: true, dailySummary: ctx.dailySummary.getStatus() };
+  });
+
+  ipcMain.handle('get-earnings-projection', async (_event, capacityGb?: number) => {
+    if (capacityGb !== undefined && (!Number.isFinite(capacityGb) || capacityGb < 0 || capacityGb > MAX_PROJECTED_GB)) {
+      return { success: false, errors: [`capacityGb must be between 0 and ${MAX_PROJECTED_GB}`] };
+    }
+    return { success: true, projection: await ctx.apiServer.getEarningsProjector().project(capacityGb) };
+  });
+
+  // Writes the earnings ledger to a file the user picks, for accounting
+  ipcMain.handle('export-earnings', async (_event, query: EarningsExportQuery = {}) => {
+    const errors = validateEarningsExportQuery(query);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const format = query.format ?? 'csv';
+    const window = ctx.getMainWindow();
+    const options: Electron.SaveDialogOptions = {
+      title: 'Export earnings',
+      defaultPath: `spk-earnings-${new Date().toISOString().slice(0, 10)}.${format}`,
+      filters: [format === 'csv' ? { name: 'CSV', extensions: ['csv'] } : { name: 'JSON', extensions: ['json'] }],
+    };
+    const choice = window ? await dialog.showSaveDialog(window, options) : await dialog.showSaveDialog(options);
+    if (choice.canceled || !choice.filePath) return { success: false, errors: [] };
+    try {
+      const exporter = new EarningsExporter(ctx.config, ctx.rewardClaimer);
+      return { success: true, result: await exporter.exportToFile(choice.filePath, { ...query, format }) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-price-feed', () => {
+    return { settings: ctx.config.getConfig().priceFeed, currencies: SUPPORTED_CURRENCIES, ...ctx.prices.getStatus() };
+  });
+
+  ipcMain.handle('set-price-feed-settings', async (_event, update: Partial<PriceFeedSettings>) => {
+    const errors = validatePriceFeedSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    const previous = ctx.config.getConfig().priceFeed;
+    ctx.config.setConfig({ priceFeed: { ...previous, ...update } });
+    if (update.refreshMinutes !== undefined && update.refreshMinutes !== previous.refreshMinutes) ctx.prices.restart();
+    // A new currency needs a quote of its own
+    else if (update.currency !== undefined && update.currency !== previous.currency) await ctx.prices.refresh();
+    return { success: true, priceFeed: ctx.config.getConfig().priceFeed, ...ctx.prices.getStatus() };
+  });
+
+  ipcMain.handle('list-hive-accounts', () => {
+    return {
+      active: ctx.accounts.getActive(),
+      accounts: ctx.accounts.list(),
+      earnings: ctx.config.getAccountEarnings(),
+    };
+  });
+
+  ipcMain.handle('switch-hive-account', (_event, username: string) => {
+    try {
+      const account = ctx.accounts.switchTo(username);
+      ctx.registration.check().catch(() => undefined);
+      return { success: true, account, identity: ctx.hive.whoami() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('unlink-hive-account', (_event, username: string) => {
+    const wasActive = ctx.accounts.getActive() === username;
+    if (wasActive) ctx.hiveSigner.forgetHasSession();
+    const identity = ctx.hive.logout(username);
+    if (wasActive && identity.username) ctx.registration.check().catch(() => undefined);
+    return { success: true, identity, accounts: ctx.accounts.list() };
+  });
+
+  ipcMain.handle('set-account-notifications', (_event, username: string, prefs: Partial<NotificationPreferences>) => {
+    const errors = validateNotificationPreferences(prefs);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    try {
+      return { success: true, account: ctx.accounts.setNotifications(username, prefs) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-hive-nodes', () => {
+    return { settings: ctx.config.getConfig().hiveNodes, defaults: DEFAULT_HIVE_NODES, nodes: ctx.hiveRpc.getHealth() };
+  });
+
+  ipcMain.handle('set-hive-node-settings', (_event, update: Partial<HiveNodeSettings>) => {
+    const errors = validateHiveNodeSettings(update, ctx.config.getConfig().hiveNodes);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ hiveNodes: { ...ctx.config.getConfig().hiveNodes, ...update } });
+    ctx.hiveRpc.restart();
+    return { success: true, hiveNodes: ctx.config.getConfig().hiveNodes, nodes: ctx.hiveRpc.getHealth() };
+  });
+
+  ipcMain.handle('check-hive-nodes', async () => {
+    return { success: true, nodes: await ctx.hiveRpc.check() };
+  });
+
+  ipcMain.handle('get-tx-queue', () => {
+    return ctx.txQueue.list().map((entry) => ({ ...entry, signed: null }));
+  });
+
+  ipcMain.handle('retry-transaction', (_event, id: string) => {
+    try {
+      return { success: true, entry: { ...ctx.txQueue.retry(id), signed: null } };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('cancel-transaction', (_event, id: string) => {
+    try {
+      return { success: true, entry: { ...ctx.txQueue.cancel(id), signed: null } };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-honeycomb-status', () => {
+    return {
+      settings: ctx.config.getConfig().honeycomb,
+      datasets: ctx.honeycomb.getStatus(),
+      rankings: ctx.honeycomb.getRankings(),
+      validators: Object.keys(ctx.honeycomb.getValidators()).length,
+    };
+  });
+
+  ipcMain.handle('sync-honeycomb', async () => {
+    const deltas = await ctx.honeycomb.sync(true);
+    return { success: true, deltas, datasets: ctx.honeycomb.getStatus() };
+  });
+
+  ipcMain.handle('set-honeycomb-settings', (_event, update: Partial<HoneycombSettings>) => {
+    const errors = validateHoneycombSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ honeycomb: { ...ctx.config.getConfig().honeycomb, ...update } });
+    ctx.honeycomb.restart();
+    return { success: true, honeycomb: ctx.config.getConfig().honeycomb };
+  });
+
+  ipcMain.handle('get-delegations', async (_event, force: boolean = false) => {
+    const settings = ctx.config.getConfig().delegations;
+    try {
+      return { success: true, settings, tokens: await ctx.delegations.list(force === true) };
+    } catch (error: any) {
+      return { success: false, settings, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('preview-delegation', async (_event, request: DelegationRequest) => {
+    return ctx.delegations.preview(request);
+  });
+
+  ipcMain.handle('delegate-power', async (_event, request: DelegationRequest) => {
+    try {
+      return { success: true, result: await ctx.delegations.delegate(request) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('set-delegation-settings', (_event, update: Partial<DelegationSettings>) => {
+    const errors = validateDelegationSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ delegations: { ...ctx.config.getConfig().delegations, ...update } });
+    return { success: true, delegations: ctx.config.getConfig().delegations };
+  });
+
+  ipcMain.handle('set-payout-account', async (_event, target: string | null) => {
+    try {
+      const payoutAccount = await ctx.hive.setPayoutAccount(target || null);
+      // Both registrations carry the payout account
+      ctx.registration.check().catch(() => undefined);
+      ctx.chainRegistration.verify().catch(() => undefined);
+      return { success: true, payoutAccount, identity: ctx.hive.whoami() };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('get-vault-status', () => {
+    return { settings: ctx.config.getConfig().vault, ...ctx.secrets.getStatus() };
+  });
+
+  ipcMain.handle('create-vault', async (_event, passphrase: string) => {
+    try {
+      return { success: true, status: await ctx.secrets.create(passphrase) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('unlock-vault', async (_event, passphrase: string) => {
+    try {
+      return { success: true, status: await ctx.secrets.unlock(passphrase) };
+    } catch (error: any) {
+      return { success: false, errors: [error.message] };
+    }
+  });
+
+  ipcMain.handle('lock-vault', () => {
+    return { success: true, status: ctx.secrets.lock() };
+  });
+
+  ipcMain.handle('set-vault-settings', (_event, update: Partial<VaultSettings>) => {
+    const errors = validateVaultSettings(update);
+    if (errors.length > 0) {
+      return { success: false, errors };
+    }
+    ctx.config.setConfig({ vault: { ...ctx.config.getConfig().vault, ...update } });
+    return { success: true, vault: ctx.config.getConfig().vault, ...ctx.secrets.getStatus() };
+  });
+}
@@ -0,0 +1,259 @@
+import * as https from 'https';
+import * as http from 'http';
+import * as path from 'path';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as crypto from 'crypto';
+import { execSync } from 'child_process';
+import { app } from 'electron';
+
+export const KUBO_VERSION = 'v0.24.0';
+const DIST_URL = 'https://dist.ipfs.tech/kubo';
+// Sidecar holding the SHA-256 of the binary as installed
+const CHECKSUM_SUFFIX = '.sha256';
+
+export type BinaryIntegrity = 'ok' | 'mismatch' | 'unrecorded';
+
+export interface DownloadProgress {
+  stage: 'manifest' | 'downloading' | 'verifying' | 'extracting' | 'done';
+  transferred: number;
+  total: number;
+  percent: number;
+}
+
+export type ProgressCallback = (progress: DownloadProgress) => void;
+
+interface DistArchive {
+  link: string;
+  sha512: string;
+  cid?: string;
+}
+
+const PLATFORM_NAMES: Record<string, string> = {
+  darwin: 'darwin',
+  linux: 'linux',
+  win32: 'windows',
+  freebsd: 'freebsd',
+};
+
+const ARCH_NAMES: Record<string, string> = {
+  x64: 'amd64',
+  arm64: 'arm64',
+  ia32: '386',
+  arm: 'arm',
+};
+
+export function getBinaryName(): string {
+  return process.platform === 'win32' ? 'ipfs.exe' : 'ipfs';
+}
+
+/**
+ * Directory where downloaded Kubo binaries are cached, per version.
+ */
+export function getCacheDir(version: string = KUBO_VERSION): string {
+  return path.join(app.getPath('userData'), 'kubo-bin', version);
+}
+
+export function getCachedBinaryPath(version: string = KUBO_VERSION): string {
+  return path.join(getCacheDir(version), getBinaryName());
+}
+
+function httpsGet(url: string): Promise<http.IncomingMessage> {
+  return new Promise((resolve, reject) => {
+    https.get(url, (response) => {
+      if (response.statusCode === 301 || response.statusCode === 302) {
+        response.resume();
+        httpsGet(response.headers.location!).then(resolve).catch(reject);
+        return;
+      }
+      if (response.statusCode !== 200) {
+        response.resume();
+        reject(new Error(`Request to ${url} failed with status ${response.statusCode}`));
+        return;
+      }
+      resolve(response);
+    }).on('error', reject);
+  });
+}
+
+export async function fetchText(url: string): Promise<string> {
+  const response = await httpsGet(url);
+  const chunks: Buffer[] = [];
+  for await (const chunk of response) {
+    chunks.push(chunk as Buffer);
+  }
+  return Buffer.concat(chunks).toString('utf-8');
+}
+
+async function fetchJson(url: string): Promise<any> {
+  return JSON.parse(await fetchText(url));
+}
+
+/**
+ * Latest stable Kubo release listed on dist.ipfs.tech (release candidates skipped).
+ */
+export async function fetchLatestVersion(): Promise<string> {
+  const versions = (await fetchText(`${DIST_URL}/versions`))
+    .split('\n')
+    .map((line) => line.trim())
+    .filter((line) => line && !line.includes('-'));
+  if (versions.length === 0) {
+    throw new Error('No Kubo versions listed on dist.ipfs.tech');
+  }
+  return versions[versions.length - 1];
+}
+
+/**
+ * Compares two 'vX.Y.Z' version strings. Returns <0, 0 or >0.
+ */
+export function compareVersions(a: string, b: string): number {
+  const pa = a.replace(/^v/, '').split('.').map((n) => parseInt(n, 10) || 0);
+  const pb = b.replace(/^v/, '').split('.').map((n) => parseInt(n, 10) || 0);
+  for (let i = 0; i < Math.max(pa.length, pb.length); i++) {
+    const diff = (pa[i] || 0) - (pb[i] || 0);
+    if (diff !== 0) return diff;
+  }
+  return 0;
+}
+
+async function resolveArchive(version: string): Promise<DistArchive> {
+  const platform = PLATFORM_NAMES[process.platform];
+  const arch = ARCH_NAMES[process.arch];
+  if (!platform || !arch) {
+    throw new Error(`Unsupported platform: ${process.platform}-${process.arch}`);
+  }
+
+  const manifest = await fetchJson(`${DIST_URL}/${version}/dist.json`);
+  const archive = manifest.platforms?.[platform]?.archs?.[arch];
+  if (!archive?.link || !archive?.sha512) {
+    throw new Error(`No Kubo ${version} release for ${platform}-${arch}`);
+  }
+
+  return archive;
+}
+
+/**
+ * Streams a file to disk while computing its SHA-512, reporting progress
+ * from the Content-Length header when the server provides it.
+ */
+async function downloadWithHash(url: string, dest: string, onProgress?: ProgressCallback): Promise<string> {
+  const response = await httpsGet(url);
+  const total = parseInt(response.headers['content-length'] || '0', 10);
+  const hash = crypto.createHash('sha512');
+  const file = fs.createWriteStream(dest);
+  let transferred = 0;
+
+  return new Promise((resolve, reject) => {
+    response.on('data', (chunk: Buffer) => {
+      hash.update(chunk);
+      transferred += chunk.length;
+      onProgress?.({
+        stage: 'downloading',
+        transferred,
+        total,
+        percent: total > 0 ? (transferred / total) * 100 : 0,
+      });
+    });
+    response.on('error', reject);
+    file.on('error', reject);
+    file.on('finish', () => resolve(hash.digest('hex')));
+    response.pipe(file);
+  });
+}
+
+function extractArchive(archivePath: string, destDir: string): void {
+  if (archivePath.endsWith('.tar.gz')) {
+    execSync(`tar -xzf "${archivePath}" -C "${destDir}"`);
+    return;
+  }
+
+  try {
+    execSync(`powershell -command "Expand-Archive -Path '${archivePath}' -DestinationPath '${destDir}' -Force"`);
+  } catch {
+    execSync(`unzip -o "${archivePath}" -d "${destDir}"`);
+  }
+}
+
+export function hashFile(filePath: string): Promise<string> {
+  return new Promise((resolve, reject) => {
+    const hash = crypto.createHash('sha256');
+    fs.createReadStream(filePath)
+      .on('data', (chunk) => hash.update(chunk))
+      .on('error', reject)
+      .on('end', () => resolve(hash.digest('hex')));
+  });
+}
+
+export async function recordChecksum(binaryPath: string): Promise<void> {
+  fs.writeFileSync(`${binaryPath}${CHECKSUM_SUFFIX}`, `${await hashFile(binaryPath)}\n`);
+}
+
+/**
+ * Compares a binary against the checksum recorded when it was installed.
+ */
+export async function verifyBinary(binaryPath: string): Promise<BinaryIntegrity> {
+  const checksumPath = `${binaryPath}${CHECKSUM_SUFFIX}`;
+  if (!fs.existsSync(checksumPath)) return 'unrecorded';
+
+  const expected = fs.readFileSync(checksumPath, 'utf-8').trim().split(/\s+/)[0].toLowerCase();
+  return (await hashFile(binaryPath)) === expected ? 'ok' : 'mismatch';
+}
+
+/**
+ * Drops a cached binary so the next ensureBinary() downloads it again.
+ */
+export function removeCachedBinary(version: string = KUBO_VERSION): void {
+  const binaryPath = getCachedBinaryPath(version);
+  fs.rmSync(binaryPath, { force: true });
+  fs.rmSync(`${binaryPath}${CHECKSUM_SUFFIX}`, { force: true });
+}
+
+/**
+ * Makes sure a verified Kubo binary for this OS/arch exists in the app data
+ * dir, downloading it from dist.ipfs.tech on first run. Returns its path.
+ */
+export async function ensureBinary(onProgress?: ProgressCallback, version: string = KUBO_VERSION): Promise<string> {
+  const binaryPath = getCachedBinaryPath(version);
+  if (fs.existsSync(binaryPath)) {
+    return binaryPath;
+  }
+
+  const cacheDir = getCacheDir(version);
+  fs.mkdirSync(cacheDir, { recursive: true });
+
+  onProgress?.({ stage: 'manifest', transferred: 0, total: 0, percent: 0 });
+  const archive = await resolveArchive(version);
+  const archiveUrl = `${DIST_URL}/${version}${archive.link}`;
+  const tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), 'spk-kubo-'));
+  const archivePath = path.join(tmpDir, path.basename(archive.link));
+
+  try {
+    console.log(`[Kubo] Downloading ${archiveUrl}`);
+    const digest = await downloadWithHash(archiveUrl, archivePath, onProgress);
+
+    onProgress?.({ stage: 'verifying', transferred: 0, total: 0, percent: 100 });
+    if (digest !== archive.sha512.toLowerCase()) {
+      throw new Error(`Checksum mismatch for ${path.basename(archive.link)}: expected ${archive.sha512}, got ${digest}`);
+    }
+
+    onProgress?.({ stage: 'extracting', transferred: 0, total: 0, percent: 100 });
+    extractArchive(archivePath, tmpDir);
+
+    const extracted = path.join(tmpDir, 'kubo', getBinaryName());
+    if (!fs.existsSync(extracted)) {
+      throw new Error('Kubo archive did not contain the ipfs binary');
+    }
+
+    fs.copyFileSync(extracted, binaryPath);
+    if (process.platform !== 'win32') {
+      fs.chmodSync(binaryPath, 0o755);
+    }
+    await recordChecksum(binaryPath);
+  } finally {
+    fs.rmSync(tmpDir, { recursive: true, force: true });
+  }
+
+  console.log(`[Kubo] Binary cached at ${binaryPath}`);
+  onProgress?.({ stage: 'done', transferred: 0, total: 0, percent: 100 });
+  return binaryPath;
+}
@@ -0,0 +1,95 @@
+// Keys the agent rewrites on every start; editing them directly would be
+// silently undone, so point the user at the matching setting instead. Null
+// marks the ones the agent always forces with no setting behind them.
+const MANAGED_KEYS: Record<string, string | null> = {
+  'Addresses.API': 'the API port setting',
+  'Addresses.Gateway': 'the gateway port setting',
+  'Addresses.Swarm': 'the swarm port setting',
+  'Datastore.StorageMax': 'the storage quota setting',
+  'Peering.Peers': 'the custom peers list',
+  'Reprovider': 'the reprovider settings',
+  'Swarm.ConnMgr': 'the bandwidth limits',
+  'Internal.Bitswap': 'the bandwidth limits',
+  'Swarm.EnableHolePunching': null,
+  'Swarm.RelayClient': null,
+  'Swarm.DisableNatPortMap': null,
+  'Routing.Type': 'the power policy',
+  'AutoNAT.ServiceMode': 'the power policy',
+  'Swarm.ResourceMgr': 'the resource limits',
+  'Experimental.Libp2pStreamMounting': null,
+};
+
+// Would reach Object.prototype instead of a config field
+const FORBIDDEN_SEGMENTS = ['__proto__', 'constructor', 'prototype'];
+
+const READ_ONLY_PREFIXES = ['Identity'];
+
+function splitPath(keyPath: string): string[] {
+  const parts = keyPath.split('.');
+  if (parts.some((part) => !/^[A-Za-z0-9_\-/]+$/.test(part) || FORBIDDEN_SEGMENTS.includes(part))) {
+    throw new Error(`Invalid config key: ${keyPath}`);
+  }
+  return parts;
+}
+
+function typeOf(value: any): string {
+  if (value === null) return 'null';
+  if (Array.isArray(value)) return 'array';
+  return typeof value;
+}
+
+/**
+ * Repo config as shown to the user, with the node's private key removed.
+ */
+export function redactRepoConfig(repoConfig: any): any {
+  const copy = JSON.parse(JSON.stringify(repoConfig));
+  if (copy.Identity) delete copy.Identity.PrivKey;
+  return copy;
+}
+
+export function getConfigKey(repoConfig: any, keyPath: string): any {
+  return splitPath(keyPath).reduce((node, part) => (node == null ? undefined : node[part]), repoConfig);
+}
+
+/**
+ * Checks that a key may be changed and that the new value has the same JSON
+ * type as the current one, so a typo can't leave the repo unbootable.
+ */
+export function validateConfigKey(repoConfig: any, keyPath: string, value: any): string | null {
+  try {
+    splitPath(keyPath);
+  } catch (error: any) {
+    return error.message;
+  }
+
+  if (READ_ONLY_PREFIXES.some((prefix) => keyPath === prefix || keyPath.startsWith(`${prefix}.`))) {
+    return `${keyPath} is read-only`;
+  }
+  const managedBy = Object.keys(MANAGED_KEYS).find((key) => keyPath === key || keyPath.startsWith(`${key}.`));
+  if (managedBy) {
+    const setting = MANAGED_KEYS[managedBy];
+    return setting
+      ? `${keyPath} is managed by the agent; change ${setting} instead`
+      : `${keyPath} is always set by the agent`;
+  }
+
+  const current = getConfigKey(repoConfig, keyPath);
+  if (current !== undefined && current !== null && value !== null && typeOf(current) !== typeOf(value)) {
+    return `${keyPath} expects a ${typeOf(current)}, got a ${typeOf(value)}`;
+  }
+  return null;
+}
+
+export function setConfigKey(repoConfig: any, keyPath: string, value: any): void {
+  const parts = splitPath(keyPath);
+  const last = parts.pop()!;
+  let node = repoConfig;
+  for (const part of parts) {
+    if (node[part] === undefined || node[part] === null) node[part] = {};
+    if (typeof node[part] !== 'object' || Array.isArray(node[part])) {
+      throw new Error(`Cannot set ${keyPath}: ${part} is not an object`);
+    }
+    node = node[part];
+  }
+  node[last] = value;
+}
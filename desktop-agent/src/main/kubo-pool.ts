@@ -0,0 +1,135 @@
+import axios from 'axios';
+import { KuboManager } from './kubo';
+import { ConfigStore, ExtraRepoConfig } from './config';
+import { DaemonWatchdog } from './watchdog';
+
+// Each extra instance shifts API/Gateway/Swarm ports by this much
+const PORT_STRIDE = 10;
+
+export interface InstanceStatus {
+  id: string;
+  repoPath: string;
+  apiUrl: string;
+  running: boolean;
+  repoSize: number;
+  storageMax: number;
+}
+
+/**
+ * All Kubo daemons run by the agent: the primary one plus one per extra
+ * drive the operator configured. New content goes to the instance with the
+ * most free quota; challenges are answered by whichever instance holds the CID.
+ */
+export class KuboPool {
+  private config: ConfigStore;
+  private instances: KuboManager[];
+  private watchdogs: DaemonWatchdog[] = [];
+
+  constructor(primary: KuboManager, config: ConfigStore) {
+    this.config = config;
+    this.instances = [primary];
+
+    config.getConfig().extraRepos.forEach((repo: ExtraRepoConfig, index: number) => {
+      this.instances.push(new KuboManager(config, {
+        id: repo.id,
+        repoPath: repo.repoPath,
+        portOffset: (index + 1) * PORT_STRIDE,
+        storageMaxGb: repo.storageMaxGb,
+      }));
+    });
+  }
+
+  getPrimary(): KuboManager {
+    return this.instances[0];
+  }
+
+  getInstances(): KuboManager[] {
+    return this.instances;
+  }
+
+  /**
+   * Starts the extra instances. The primary is started (and watched) by the
+   * main startup path; a failing extra drive doesn't block the others.
+   */
+  async startExtras(): Promise<void> {
+    await Promise.all(this.instances.slice(1).map(async (kubo) => {
+      try {
+        await kubo.start();
+        const watchdog = new DaemonWatchdog(kubo, this.config);
+        watchdog.start();
+        this.watchdogs.push(watchdog);
+        console.log(`[Pool] Instance ${kubo.getInstanceId()} started`);
+      } catch (error: any) {
+        console.error(`[Pool] Instance ${kubo.getInstanceId()} failed to start:`, error.message);
+      }
+    }));
+  }
+
+  async stopExtras(): Promise<void> {
+    this.watchdogs.forEach((watchdog) => watchdog.stop());
+    this.watchdogs = [];
+    await Promise.all(this.instances.slice(1).map((kubo) => kubo.stop()));
+  }
+
+  async getStatus(): Promise<InstanceStatus[]> {
+    return Promise.all(this.instances.map(async (kubo) => {
+      const stats = await kubo.getStats();
+      return {
+        id: kubo.getInstanceId(),
+        repoPath: kubo.getRepoPath(),
+        apiUrl: kubo.getApiUrl(),
+        running: kubo.isRunning(),
+        repoSize: stats?.repoSize || 0,
+        storageMax: stats?.storageMax || 0,
+      };
+    }));
+  }
+
+  /**
+   * Running instance with the most free quota, for pinning new content.
+   */
+  async selectForNewContent(): Promise<KuboManager> {
+    const statuses = await this.getStatus();
+    let best = this.getPrimary();
+    let bestFree = -Infinity;
+
+    statuses.forEach((status, index) => {
+      if (!status.running) return;
+      const free = status.storageMax - status.repoSize;
+      if (free > bestFree) {
+        bestFree = free;
+        best = this.instances[index];
+      }
+    });
+    return best;
+  }
+
+  /**
+   * Instance that has the CID pinned, or null if none do.
+   */
+  async findHolder(cid: string): Promise<KuboManager | null> {
+    if (this.instances.length === 1) return this.getPrimary();
+
+    for (const kubo of this.instances) {
+      if (!kubo.isRunning()) continue;
+      try {
+        await axios.post(`${kubo.getApiUrl()}/api/v0/pin/ls?arg=${cid}&type=recursive`, null, { timeout: 2000 });
+        return kubo;
+      } catch {
+        // Not pinned here
+      }
+    }
+    return null;
+  }
+
+  async getAggregateStats(): Promise<{ repoSize: number; storageMax: number; numObjects: number; bandwidthIn: number; bandwidthOut: number }> {
+    const all = await Promise.all(this.instances.map((kubo) => kubo.getStats()));
+    return all.reduce((total, stats) => ({
+      repoSize: total.repoSize + (stats?.repoSize || 0),
+      storageMax: total.storageMax + (stats?.storageMax || 0),
+      numObjects: total.numObjects + (stats?.numObjects || 0),
+      bandwidthIn: total.bandwidthIn + (stats?.bandwidthIn || 0),
+      bandwidthOut: total.bandwidthOut + (stats?.bandwidthOut || 0),
+    }), { repoSize: 0, storageMax: 0, numObjects: 0, bandwidthIn: 0, bandwidthOut: 0 });
+  }
+}
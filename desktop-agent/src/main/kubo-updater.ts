@@ -0,0 +1,168 @@
+import { execSync } from 'child_process';
+import { EventEmitter } from 'events';
+import * as path from 'path';
+import * as fs from 'fs';
+import { KuboManager } from './kubo';
+import { ConfigStore } from './config';
+import { ensureBinary, fetchLatestVersion, compareVersions, DownloadProgress } from './kubo-binary';
+
+// Repo files touched by fs-repo-migrations. Block data is never rewritten by
+// the migrations Kubo ships today, so these are enough to roll back.
+const REPO_METADATA_FILES = ['config', 'version', 'datastore_spec'];
+
+export interface KuboUpdateInfo {
+  currentVersion: string | null;
+  latestVersion: string;
+  updateAvailable: boolean;
+}
+
+export interface KuboUpdateResult {
+  success: boolean;
+  previousVersion: string | null;
+  version: string | null;
+  migrated: boolean;
+  rolledBack: boolean;
+  error?: string;
+}
+
+export class KuboUpdater extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private updating = false;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  async checkForUpdate(): Promise<KuboUpdateInfo> {
+    if (this.kubo.isExternal()) {
+      throw new Error('Kubo updates are managed outside the agent for external nodes');
+    }
+
+    const currentVersion = this.kubo.getVersion();
+    const latestVersion = await fetchLatestVersion();
+
+    return {
+      currentVersion,
+      latestVersion,
+      updateAvailable: !currentVersion || compareVersions(latestVersion, currentVersion) > 0,
+    };
+  }
+
+  /**
+   * Downloads the latest Kubo, stops the daemon, migrates the repo with the
+   * new binary and restarts. If the migration fails the repo metadata and
+   * the previous binary are restored and the old daemon is restarted.
+   */
+  async applyUpdate(): Promise<KuboUpdateResult> {
+    if (this.updating) {
+      throw new Error('Kubo update already in progress');
+    }
+    this.updating = true;
+
+    const previousPath = this.kubo.getBinaryPath();
+    const previousVersion = this.kubo.getVersion();
+    const result: KuboUpdateResult = {
+      success: false,
+      previousVersion,
+      version: previousVersion,
+      migrated: false,
+      rolledBack: false,
+    };
+
+    try {
+      const { latestVersion, updateAvailable } = await this.checkForUpdate();
+      if (!updateAvailable) {
+        return { ...result, success: true };
+      }
+
+      this.emitStage(`Downloading Kubo ${latestVersion}...`);
+      const newPath = await ensureBinary((progress: DownloadProgress) => {
+        this.emit('progress', progress);
+      }, latestVersion);
+
+      const wasRunning = this.kubo.isRunning();
+      if (wasRunning) {
+        this.emitStage('Stopping IPFS daemon...');
+        await this.kubo.stop();
+      }
+
+      const backupDir = this.backupRepoMetadata();
+      try {
+        if (this.needsMigration(newPath)) {
+          this.emitStage('Migrating IPFS repository...');
+          execSync(`"${newPath}" repo migrate`, {
+            env: { ...process.env, IPFS_PATH: this.kubo.getRepoPath() },
+            encoding: 'utf-8',
+          });
+          result.migrated = true;
+        }
+
+        this.kubo.setBinaryPath(newPath);
+        this.config.setConfig({ kuboVersion: latestVersion });
+        fs.rmSync(backupDir, { recursive: true, force: true });
+        result.version = latestVersion;
+        result.success = true;
+        console.log(`[Kubo] Upgraded from ${previousVersion} to ${latestVersion}`);
+      } catch (error: any) {
+        console.error('[Kubo] Repo migration failed, rolling back:', error.message);
+        this.restoreRepoMetadata(backupDir);
+        if (previousPath) this.kubo.setBinaryPath(previousPath);
+        result.rolledBack = true;
+        result.error = error.message;
+      }
+
+      if (wasRunning) {
+        this.emitStage('Starting IPFS daemon...');
+        await this.kubo.start();
+      }
+
+      return result;
+    } finally {
+      this.updating = false;
+    }
+  }
+
+  private emitStage(message: string): void {
+    console.log(`[Kubo] ${message}`);
+    this.emit('status', message);
+  }
+
+  private needsMigration(binaryPath: string): boolean {
+    const versionFile = path.join(this.kubo.getRepoPath(), 'version');
+    if (!fs.existsSync(versionFile)) return false;
+
+    const repoVersion = fs.readFileSync(versionFile, 'utf-8').trim();
+    const supported = execSync(`"${binaryPath}" repo version --quiet`, { encoding: 'utf-8' })
+      .trim()
+      .replace(/^fs-repo@/, '');
+    return repoVersion !== supported;
+  }
+
+  private backupRepoMetadata(): string {
+    const repoPath = this.kubo.getRepoPath();
+    const backupDir = path.join(repoPath, `.migration-backup-${Date.now()}`);
+    fs.mkdirSync(backupDir, { recursive: true });
+
+    for (const file of REPO_METADATA_FILES) {
+      const source = path.join(repoPath, file);
+      if (fs.existsSync(source)) {
+        fs.copyFileSync(source, path.join(backupDir, file));
+      }
+    }
+    return backupDir;
+  }
+
+  private restoreRepoMetadata(backupDir: string): void {
+    const repoPath = this.kubo.getRepoPath();
+    for (const file of REPO_METADATA_FILES) {
+      const backup = path.join(backupDir, file);
+      if (fs.existsSync(backup)) {
+        fs.copyFileSync(backup, path.join(repoPath, file));
+      }
+    }
+    fs.rmSync(backupDir, { recursive: true, force: true });
+  }
+}
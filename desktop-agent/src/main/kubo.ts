@@ -1,25 +1,127 @@
-import { spawn, ChildProcess, execSync } from 'child_process';
+import { spawn, ChildProcess, execSync, execFile } from 'child_process';
+import { EventEmitter } from 'events';
+import { promisify } from 'util';
 import * as path from 'path';
 import * as fs from 'fs';
 import * as os from 'os';
 import { app } from 'electron';
 import { ConfigStore } from './config';
+import {
+  ensureBinary,
+  getCachedBinaryPath,
+  recordChecksum,
+  removeCachedBinary,
+  verifyBinary,
+  DownloadProgress,
+  KUBO_VERSION,
+} from './kubo-binary';
+import { applyKuboSettings, DATASTORE_PROFILES, KuboSettings } from './settings';
+import { applyBandwidthLimits } from './bandwidth';
+import { PeeringPeer, applyPeering, parsePeerMultiaddr, resolveSpkPeers } from './peering';
+import { applyNatTraversal } from './connectivity';
+import { applyPowerSaving } from './power';
+import { redactRepoConfig, setConfigKey, validateConfigKey } from './kubo-config';
+import { PreflightError, PreflightResult, runPreflight } from './preflight';
+import { DaemonLog, LogEntry, LogLevel } from './daemon-log';
+import { StartupState, StartupStateMachine } from './startup';
+import {
+  PrivateNetwork,
+  applyPrivateNetwork,
+  clearPrivateNetwork,
+  removeSwarmKey,
+  writeSwarmKey,
+} from './private-network';
+import { applyResourceLimits, writeResourceLimitsFile, parseSystemUsage, ResourceUsage } from './resource-limits';
 
-export class KuboManager {
+const PEER_RESOLVE_TIMEOUT_MS = 5000;
+const ONLINE_TIMEOUT_MS = 2 * 60 * 1000;
+const ONLINE_POLL_MS = 2000;
+const execFileAsync = promisify(execFile);
+
+/**
+ * Identifies one daemon when the agent runs a repo per drive. The primary
+ * instance uses the main settings; extra instances shift every port by
+ * portOffset and may carry their own repo path and quota.
+ */
+export interface KuboInstanceOptions {
+  id: string;
+  repoPath?: string;
+  portOffset: number;
+  storageMaxGb?: number;
+}
+
+export const PRIMARY_INSTANCE: KuboInstanceOptions = { id: 'primary', portOffset: 0 };
+
+/**
+ * The Kubo binary on disk no longer matches the checksum recorded when it
+ * was installed (partial download, disk corruption or tampering).
+ */
+export class BinaryIntegrityError extends Error {
+  binaryPath: string;
+
+  constructor(binaryPath: string) {
+    super(`Kubo binary at ${binaryPath} failed its integrity check`);
+    this.name = 'BinaryIntegrityError';
+    this.binaryPath = binaryPath;
+  }
+}
+
+export class KuboManager extends EventEmitter {
   private process: ChildProcess | null = null;
   private config: ConfigStore;
-  private ipfsPath: string;
+  private ipfsPath: string | null;
   private repoPath: string;
+  private externalOnline = false;
+  private stopping = false;
+  private spkPeers: PeeringPeer[] = [];
+  private powerSaving = false;
+  private suspended = false;
+  private instance: KuboInstanceOptions;
+  private logs = new DaemonLog();
+  private verifiedBinary: string | null = null;
+  private startup: StartupStateMachine;
 
-  constructor(config: ConfigStore) {
+  constructor(config: ConfigStore, instance: KuboInstanceOptions = PRIMARY_INSTANCE) {
+    super();
     this.config = config;
-    this.repoPath = config.getConfig().ipfsRepoPath;
+    this.instance = instance;
+    this.startup = new StartupStateMachine(instance.id);
+    this.startup.on('state-changed', (change) => this.emit('startup-state', change));
+    this.repoPath = instance.repoPath || config.getConfig().ipfsRepoPath;
     this.ipfsPath = this.findIpfsBinary();
   }
 
-  private findIpfsBinary(): string {
+  getInstanceId(): string {
+    return this.instance.id;
+  }
+
+  /**
+   * The user's Kubo settings adjusted for this instance.
+   */
+  getSettings(): KuboSettings {
+    const settings = this.config.getConfig().kubo;
+    const offset = this.instance.portOffset;
+    return {
+      ...settings,
+      apiPort: settings.apiPort + offset,
+      gatewayPort: settings.gatewayPort + offset,
+      swarmPort: settings.swarmPort + offset,
+      storageMaxGb: this.instance.storageMaxGb ?? settings.storageMaxGb,
+    };
+  }
+
+  private findIpfsBinary(): string | null {
     const ext = process.platform === 'win32' ? '.exe' : '';
-    
+
+    // A version installed through the upgrade manager takes precedence
+    const upgradedVersion = this.config.getConfig().kuboVersion;
+    if (upgradedVersion) {
+      const upgradedPath = getCachedBinaryPath(upgradedVersion);
+      if (fs.existsSync(upgradedPath)) {
+        return upgradedPath;
+      }
+    }
+
     // In production, use the bundled binary from extraResources
     if (app.isPackaged) {
       const resourcePath = path.join(process.resourcesPath, 'kubo-bin', `ipfs${ext}`);
@@ -40,48 +142,224 @@ export class KuboManager {
       return devBinaryPath;
     }
 
-    throw new Error('IPFS binary not found. Run: npm run download-kubo');
+    // Previously downloaded into the app data dir by ensureBinary()
+    const cachedPath = getCachedBinaryPath();
+    if (fs.existsSync(cachedPath)) {
+      return cachedPath;
+    }
+
+    return null;
+  }
+
+  /**
+   * Resolves the Kubo binary, downloading and verifying it on first run when
+   * no bundled copy exists. Progress is emitted as 'download-progress'.
+   */
+  async ensureBinary(): Promise<string> {
+    if (this.ipfsPath) return this.ipfsPath;
+
+    console.log('[Kubo] No bundled binary found, downloading...');
+    const version = this.config.getConfig().kuboVersion || KUBO_VERSION;
+    this.ipfsPath = await ensureBinary((progress: DownloadProgress) => {
+      this.emit('download-progress', progress);
+    }, version);
+    return this.ipfsPath;
+  }
+
+  /**
+   * Hashes the binary once per launch. Binaries installed before checksums
+   * were recorded get one now, except bundled ones in a read-only location.
+   */
+  private async checkBinaryIntegrity(): Promise<void> {
+    const binaryPath = this.ipfsPath!;
+    if (this.verifiedBinary === binaryPath) return;
+
+    const integrity = await verifyBinary(binaryPath);
+    if (integrity === 'mismatch') {
+      console.error(`[Kubo] Checksum mismatch for ${binaryPath}`);
+      throw new BinaryIntegrityError(binaryPath);
+    }
+    if (integrity === 'unrecorded') {
+      try {
+        await recordChecksum(binaryPath);
+      } catch {
+        console.warn(`[Kubo] No checksum recorded for ${binaryPath}`);
+      }
+    }
+    this.verifiedBinary = binaryPath;
+  }
+
+  /**
+   * Replaces a damaged binary with a fresh verified download. A tampered
+   * bundled binary can't be overwritten, so the download is used instead.
+   */
+  async repairBinary(): Promise<string> {
+    const version = this.config.getConfig().kuboVersion || KUBO_VERSION;
+    removeCachedBinary(version);
+    this.ipfsPath = null;
+    this.verifiedBinary = null;
+
+    this.ipfsPath = await ensureBinary((progress: DownloadProgress) => {
+      this.emit('download-progress', progress);
+    }, version);
+    this.config.setConfig({ kuboVersion: version });
+    console.log(`[Kubo] Binary repaired: ${this.ipfsPath}`);
+    return this.ipfsPath;
+  }
+
+  getBinaryPath(): string | null {
+    return this.ipfsPath;
+  }
+
+  /**
+   * Switches to a different Kubo binary. Takes effect on the next start().
+   */
+  setBinaryPath(binaryPath: string): void {
+    this.ipfsPath = binaryPath;
+  }
+
+  getRepoPath(): string {
+    return this.repoPath;
+  }
+
+  /**
+   * Points the instance at a repo moved elsewhere. Takes effect on the next start().
+   */
+  setRepoPath(repoPath: string): void {
+    this.repoPath = repoPath;
   }
 
+  /**
+   * Version of the Kubo binary currently in use, e.g. 'v0.24.0'.
+   */
+  getVersion(binaryPath: string | null = this.ipfsPath): string | null {
+    if (!binaryPath) return null;
+    try {
+      const output = execSync(`"${binaryPath}" version --number`, { encoding: 'utf-8' });
+      return `v${output.trim()}`;
+    } catch {
+      return null;
+    }
+  }
+
+  isExternal(): boolean {
+    return this.instance.id === PRIMARY_INSTANCE.id && this.config.getConfig().connectionMode.type === 'external';
+  }
+
+  getStartupState(): StartupState {
+    return this.startup.getState();
+  }
+
+  /**
+   * Brings the node up through Starting → RepoReady → DaemonUp → Online.
+   * Resolving SPK peers only needs the network, so it overlaps binary and
+   * repo preparation; the promise resolves at DaemonUp and Online follows
+   * in the background once bootstrap dialing has connected us to peers.
+   */
   async start(): Promise<void> {
+    const run = this.startup.begin();
+
+    try {
+      if (this.isExternal()) {
+        this.startup.advance('RepoReady', run);
+        await this.connectExternal();
+      } else {
+        // Public SPK gateways can't reach us inside a private network
+        const peers = this.config.getConfig().privateNetwork ? Promise.resolve([]) : Promise.race([
+          resolveSpkPeers(this.config.getConfig().peeringDnsaddrs),
+          new Promise<PeeringPeer[]>((resolve) => setTimeout(() => resolve(this.spkPeers), PEER_RESOLVE_TIMEOUT_MS)),
+        ]);
+
+        await this.prepareRepo();
+        this.startup.advance('RepoReady', run);
+
+        this.spkPeers = await peers;
+        this.applySettings();
+
+        console.log('[Kubo] Starting IPFS daemon...');
+        await this.startDaemon();
+      }
+      this.startup.advance('DaemonUp', run);
+    } catch (error: any) {
+      this.startup.fail(error, run);
+      throw error;
+    }
+
+    void this.waitForPeers(run);
+  }
+
+  private async prepareRepo(): Promise<void> {
+    await this.ensureBinary();
+    await this.checkBinaryIntegrity();
     console.log(`[Kubo] Using binary: ${this.ipfsPath}`);
     console.log(`[Kubo] Repo path: ${this.repoPath}`);
 
+    const preflight = await this.preflight();
+    if (!preflight.ok) {
+      throw new PreflightError(preflight.issues);
+    }
+
     // Initialize repo if needed
     if (!fs.existsSync(path.join(this.repoPath, 'config'))) {
       console.log('[Kubo] Initializing IPFS repository...');
       await this.initRepo();
     }
-
-    // Start the daemon
-    console.log('[Kubo] Starting IPFS daemon...');
-    await this.startDaemon();
   }
 
-  private async initRepo(): Promise<void> {
-    return new Promise((resolve, reject) => {
+  // Online once bootstrap dialing has produced at least one connection
+  private async waitForPeers(run: number): Promise<void> {
+    const axios = require('axios');
+    const deadline = Date.now() + ONLINE_TIMEOUT_MS;
+
+    while (Date.now() < deadline && this.startup.getRun() === run) {
       try {
-        fs.mkdirSync(this.repoPath, { recursive: true });
-        
-        const result = execSync(`"${this.ipfsPath}" init`, {
-          env: { ...process.env, IPFS_PATH: this.repoPath },
-          encoding: 'utf-8',
-        });
-        
-        console.log('[Kubo] Init result:', result);
-        
-        // Configure for desktop use
-        this.configureForDesktop();
-        
-        resolve();
-      } catch (error: any) {
-        if (error.message.includes('already initialized')) {
-          resolve();
-        } else {
-          reject(error);
+        const response = await axios.post(`${this.getApiUrl()}/api/v0/swarm/peers`, null, { timeout: 5000 });
+        if ((response.data.Peers || []).length > 0) {
+          this.startup.advance('Online', run);
+          return;
         }
+      } catch {
+        // Daemon still warming up
       }
+      await new Promise((resolve) => setTimeout(resolve, ONLINE_POLL_MS));
+    }
+
+    if (this.startup.getRun() === run) {
+      console.warn('[Kubo] No peers connected yet; check the network connection');
+    }
+  }
+
+  async preflight(): Promise<PreflightResult> {
+    const result = await runPreflight(this.repoPath, this.getSettings());
+    result.issues.forEach((issue) => {
+      const log = issue.severity === 'error' ? console.error : console.warn;
+      log(`[Kubo] Preflight ${issue.check}: ${issue.message}`);
     });
+    this.emit('preflight', result);
+    return result;
+  }
+
+  private async initRepo(): Promise<void> {
+    fs.mkdirSync(this.repoPath, { recursive: true });
+
+    const { profile, datastore } = this.getSettings();
+    const profiles = [DATASTORE_PROFILES[datastore], ...(profile !== 'default' ? [profile] : [])];
+
+    try {
+      const { stdout } = await execFileAsync(this.ipfsPath!, ['init', `--profile=${profiles.join(',')}`], {
+        env: { ...process.env, IPFS_PATH: this.repoPath },
+        encoding: 'utf-8',
+      });
+      console.log('[Kubo] Init result:', stdout);
+    } catch (error: any) {
+      if (!`${error.message}${error.stderr || ''}`.includes('already initialized')) {
+        throw error;
+      }
+    }
+
+    // Configure for desktop use
+    this.configureForDesktop();
+    this.config.setAppliedProfile(profile, this.instance.id);
   }
 
   private configureForDesktop(): void {
@@ -91,13 +369,6 @@ export class KuboManager {
     try {
       const config = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
       
-      // Use default ports but make API accessible
-      config.Addresses = {
-        ...config.Addresses,
-        API: '/ip4/127.0.0.1/tcp/5001',
-        Gateway: '/ip4/127.0.0.1/tcp/8080',
-      };
-
       // Enable pubsub for real-time features
       config.Pubsub = { Enabled: true };
 
@@ -118,18 +389,70 @@ export class KuboManager {
     }
   }
 
+  /**
+   * Writes the user's port, quota, bandwidth and peering settings into the
+   * repo config and applies the init profile if it changed since last applied.
+   */
+  private applySettings(): void {
+    const configPath = path.join(this.repoPath, 'config');
+    const { bandwidth, customPeers, privateNetwork, resourceLimits } = this.config.getConfig();
+    const settings = this.getSettings();
+
+    const applied = this.config.getAppliedProfile(this.instance.id) || 'default';
+    if (settings.profile !== applied && settings.profile !== 'default') {
+      try {
+        execSync(`"${this.ipfsPath}" config profile apply ${settings.profile}`, {
+          env: { ...process.env, IPFS_PATH: this.repoPath },
+          encoding: 'utf-8',
+        });
+        console.log(`[Kubo] Applied ${settings.profile} profile`);
+        this.config.setAppliedProfile(settings.profile, this.instance.id);
+      } catch (error) {
+        console.error(`[Kubo] Failed to apply ${settings.profile} profile:`, error);
+      }
+    } else {
+      this.config.setAppliedProfile(settings.profile, this.instance.id);
+    }
+
+    // Our settings are written last so a profile can't override them
+    try {
+      const config = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
+      applyKuboSettings(config, settings);
+      applyBandwidthLimits(config, bandwidth);
+      applyPeering(config, [...this.spkPeers, ...customPeers]);
+      applyNatTraversal(config);
+      const powerBackup = applyPowerSaving(config, this.powerSaving, this.config.getPowerOverlayBackup(this.instance.id));
+      applyResourceLimits(config, resourceLimits);
+      writeResourceLimitsFile(this.repoPath, resourceLimits);
+      // PoA challenges between nodes travel as libp2p streams (ipfs p2p)
+      config.Experimental = { ...config.Experimental, Libp2pStreamMounting: true };
+      if (privateNetwork) {
+        writeSwarmKey(this.repoPath, privateNetwork.swarmKey);
+        applyPrivateNetwork(config, privateNetwork);
+      }
+      fs.writeFileSync(configPath, JSON.stringify(config, null, 2));
+      this.config.setPowerOverlayBackup(powerBackup, this.instance.id);
+    } catch (error) {
+      console.error('[Kubo] Failed to apply settings:', error);
+    }
+  }
+
   private async startDaemon(): Promise<void> {
     return new Promise((resolve, reject) => {
-      this.process = spawn(this.ipfsPath, ['daemon', '--enable-gc'], {
-        env: { ...process.env, IPFS_PATH: this.repoPath },
+      // Refuse to fall back to the public network if swarm.key goes missing
+      const pnetEnv = this.config.getConfig().privateNetwork ? { LIBP2P_FORCE_PNET: '1' } : {};
+      const child = spawn(this.ipfsPath!, ['daemon', '--enable-gc'], {
+        env: { ...process.env, IPFS_PATH: this.repoPath, ...pnetEnv },
         stdio: ['ignore', 'pipe', 'pipe'],
       });
+      this.process = child;
 
       let started = false;
 
-      this.process.stdout?.on('data', (data) => {
+      child.stdout?.on('data', (data) => {
         const output = data.toString();
         console.log('[Kubo]', output.trim());
+        this.recordLog('stdout', output);
         
         if (output.includes('Daemon is ready') && !started) {
           started = true;
@@ -137,18 +460,26 @@ export class KuboManager {
         }
       });
 
-      this.process.stderr?.on('data', (data) => {
-        console.error('[Kubo Error]', data.toString().trim());
+      child.stderr?.on('data', (data) => {
+        const output = data.toString();
+        console.error('[Kubo Error]', output.trim());
+        this.recordLog('stderr', output);
       });
 
-      this.process.on('error', (error) => {
+      child.on('error', (error) => {
         console.error('[Kubo] Process error:', error);
         if (!started) reject(error);
       });
 
-      this.process.on('exit', (code) => {
+      child.on('exit', (code) => {
         console.log(`[Kubo] Process exited with code ${code}`);
-        this.process = null;
+        if (this.process === child) {
+          this.process = null;
+        }
+        if (!this.stopping && started) {
+          this.startup.fail(new Error(`Daemon exited with code ${code}`));
+          this.emit('crashed', code);
+        }
       });
 
       // Timeout after 30 seconds
@@ -160,40 +491,333 @@ export class KuboManager {
     });
   }
 
+  private recordLog(source: LogEntry['source'], output: string): void {
+    this.logs.write(source, output).forEach((entry) => this.emit('log', entry));
+  }
+
+  getLogs(minLevel: LogLevel = 'debug', limit?: number): LogEntry[] {
+    return this.logs.getEntries(minLevel, limit);
+  }
+
+  clearLogs(): void {
+    this.logs.clear();
+  }
+
+  /**
+   * In external mode there is no process to manage; we only confirm the
+   * node's API answers before reporting it as running.
+   */
+  private async connectExternal(): Promise<void> {
+    console.log(`[Kubo] Using external node at ${this.getApiUrl()}`);
+    const peerId = await this.getPeerId();
+    if (!peerId) {
+      throw new Error(`External IPFS node at ${this.getApiUrl()} is not reachable`);
+    }
+    console.log(`[Kubo] Connected to external node ${peerId}`);
+  }
+
   async stop(): Promise<void> {
+    this.startup.stopped();
+    if (this.isExternal()) {
+      this.externalOnline = false;
+      return;
+    }
+
     if (this.process) {
       console.log('[Kubo] Stopping daemon...');
-      this.process.kill('SIGTERM');
+      const child = this.process;
+      this.stopping = true;
+
+      // Ask the daemon to shut itself down first so it releases repo.lock
+      // cleanly; fall back to signals if the API doesn't answer.
+      try {
+        const axios = require('axios');
+        await axios.post(`${this.getApiUrl()}/api/v0/shutdown`, null, { timeout: 2000 });
+      } catch {
+        child.kill('SIGTERM');
+      }
       
       await new Promise<void>((resolve) => {
+        if (child.exitCode !== null || child.signalCode !== null) {
+          resolve();
+          return;
+        }
+
         const timeout = setTimeout(() => {
-          this.process?.kill('SIGKILL');
+          child.kill('SIGKILL');
           resolve();
         }, 5000);
 
-        this.process?.on('exit', () => {
+        child.on('exit', () => {
           clearTimeout(timeout);
           resolve();
         });
       });
       
       this.process = null;
+      this.stopping = false;
     }
   }
 
+  async restart(): Promise<void> {
+    await this.stop();
+    await this.start();
+  }
+
+  /**
+   * Enables the low-power overlay. Takes effect on the next start().
+   */
+  setPowerSaving(enabled: boolean): void {
+    this.powerSaving = enabled;
+  }
+
+  isPowerSaving(): boolean {
+    return this.powerSaving;
+  }
+
+  /**
+   * Stops the daemon on purpose (e.g. on battery) so the watchdog leaves it alone.
+   */
+  async suspend(): Promise<void> {
+    this.suspended = true;
+    await this.stop();
+  }
+
+  async resume(): Promise<void> {
+    this.suspended = false;
+    await this.start();
+  }
+
+  isSuspended(): boolean {
+    return this.suspended;
+  }
+
   isRunning(): boolean {
+    if (this.isExternal()) return this.externalOnline;
     return this.process !== null;
   }
 
   getApiUrl(): string {
-    return 'http://127.0.0.1:5001';
+    const mode = this.config.getConfig().connectionMode;
+    if (this.isExternal() && mode.type === 'external') return mode.apiUrl.replace(/\/$/, '');
+    return `http://127.0.0.1:${this.getSettings().apiPort}`;
+  }
+
+  getGatewayUrl(): string {
+    const mode = this.config.getConfig().connectionMode;
+    if (this.isExternal() && mode.type === 'external' && mode.gatewayUrl) return mode.gatewayUrl.replace(/\/$/, '');
+    return `http://127.0.0.1:${this.getSettings().gatewayPort}`;
+  }
+
+  getPeeringPeers(): { spk: PeeringPeer[]; custom: PeeringPeer[] } {
+    return { spk: this.spkPeers, custom: this.config.getConfig().customPeers };
+  }
+
+  /**
+   * Adds a custom peer ('/ip4/.../tcp/4001/p2p/<id>') to Peering.Peers. The
+   * running daemon picks it up immediately; the repo config on next start.
+   */
+  async addPeeringPeer(multiaddr: string): Promise<PeeringPeer> {
+    const peer = parsePeerMultiaddr(multiaddr);
+    if (!peer || peer.Addrs.length === 0) {
+      throw new Error('Expected a multiaddr ending in /p2p/<peer-id>');
+    }
+
+    const custom = this.config.getConfig().customPeers.filter((p) => p.ID !== peer.ID);
+    this.config.setConfig({ customPeers: [...custom, peer] });
+
+    if (this.isRunning()) {
+      const axios = require('axios');
+      await axios.post(`${this.getApiUrl()}/api/v0/swarm/peering/add?arg=${encodeURIComponent(multiaddr.trim())}`);
+    }
+    return peer;
+  }
+
+  async removePeeringPeer(peerId: string): Promise<void> {
+    const custom = this.config.getConfig().customPeers.filter((p) => p.ID !== peerId);
+    this.config.setConfig({ customPeers: custom });
+
+    if (this.isRunning()) {
+      const axios = require('axios');
+      await axios.post(`${this.getApiUrl()}/api/v0/swarm/peering/rm?arg=${encodeURIComponent(peerId)}`);
+    }
+  }
+
+  /**
+   * The repo config with the private key stripped. Read over the API for an
+   * external node since we can't see its repo.
+   */
+  async getRepoConfig(): Promise<any> {
+    if (this.isExternal()) {
+      const axios = require('axios');
+      const response = await axios.post(`${this.getApiUrl()}/api/v0/config/show`);
+      return redactRepoConfig(response.data);
+    }
+
+    const configPath = path.join(this.repoPath, 'config');
+    return redactRepoConfig(JSON.parse(fs.readFileSync(configPath, 'utf-8')));
+  }
+
+  /**
+   * Patches one dotted key (e.g. 'Swarm.ConnMgr.HighWater') in the repo
+   * config. Kubo only reads its config at startup, so a running embedded
+   * daemon is restarted; an external node has to be restarted by its owner.
+   */
+  async setRepoConfigKey(keyPath: string, value: any): Promise<{ restarted: boolean }> {
+    if (this.isExternal()) {
+      const axios = require('axios');
+      const current = (await axios.post(`${this.getApiUrl()}/api/v0/config/show`)).data;
+      const error = validateConfigKey(current, keyPath, value);
+      if (error) throw new Error(error);
+
+      await axios.post(`${this.getApiUrl()}/api/v0/config?arg=${encodeURIComponent(keyPath)}`
+        + `&arg=${encodeURIComponent(JSON.stringify(value))}&json=true`);
+      return { restarted: false };
+    }
+
+    const configPath = path.join(this.repoPath, 'config');
+    const repoConfig = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
+    const error = validateConfigKey(repoConfig, keyPath, value);
+    if (error) throw new Error(error);
+
+    setConfigKey(repoConfig, keyPath, value);
+    fs.writeFileSync(configPath, JSON.stringify(repoConfig, null, 2));
+    console.log(`[Kubo] Set ${keyPath} in repo config`);
+
+    if (!this.isRunning()) return { restarted: false };
+    await this.restart();
+    return { restarted: true };
+  }
+
+  /**
+   * Joins a private network, or returns to the public one when given null.
+   * Leaving restores Kubo's default bootstrap peers and transports.
+   */
+  async setPrivateNetwork(network: PrivateNetwork | null): Promise<void> {
+    if (this.isExternal()) {
+      throw new Error('The network of an external node is configured outside the agent');
+    }
+
+    const previous = this.config.getConfig().privateNetwork;
+    const wasRunning = this.isRunning();
+    await this.stop();
+    this.config.setConfig({ privateNetwork: network });
+
+    if (!network && previous) {
+      removeSwarmKey(this.repoPath);
+      const configPath = path.join(this.repoPath, 'config');
+      if (fs.existsSync(configPath)) {
+        const repoConfig = JSON.parse(fs.readFileSync(configPath, 'utf-8'));
+        clearPrivateNetwork(repoConfig);
+        repoConfig.Bootstrap = [];
+        fs.writeFileSync(configPath, JSON.stringify(repoConfig, null, 2));
+        if (this.ipfsPath) {
+          execSync(`"${this.ipfsPath}" bootstrap add --default`, {
+            env: { ...process.env, IPFS_PATH: this.repoPath },
+            encoding: 'utf-8',
+          });
+        }
+      }
+      console.log('[Kubo] Left private network');
+    } else if (network) {
+      console.log('[Kubo] Joining private network');
+    }
+
+    if (wasRunning) {
+      await this.start();
+    }
+  }
+
+  /**
+   * Announces content to the DHT right away instead of waiting for the next
+   * reprovide run. With CIDs, each one and its children is provided; without,
+   * a full reprovide is triggered using the configured strategy.
+   */
+  async reprovide(cids?: string[]): Promise<void> {
+    const axios = require('axios');
+    if (!cids || cids.length === 0) {
+      await axios.post(`${this.getApiUrl()}/api/v0/routing/reprovide`, null, { timeout: 30 * 60 * 1000 });
+      return;
+    }
+    for (const cid of cids) {
+      await axios.post(`${this.getApiUrl()}/api/v0/routing/provide?arg=${cid}&recursive=true`, null, { timeout: 10 * 60 * 1000 });
+    }
+  }
+
+  async getResourceUsage(): Promise<ResourceUsage[]> {
+    const axios = require('axios');
+    const response = await axios.post(`${this.getApiUrl()}/api/v0/swarm/resources`, null, { timeout: 5000 });
+    return parseSystemUsage(response.data);
   }
 
   async getPeerId(): Promise<string | null> {
     try {
       const axios = require('axios');
-      const response = await axios.post(`${this.getApiUrl()}/api/v0/id`);
+      const response = await axios.post(`${this.getApiUrl()}/api/v0/id`, null, { timeout: 5000 });
+      this.externalOnline = true;
       return response.data.ID;
+    } catch {
+      this.externalOnline = false;
+      return null;
+    }
+  }
+
+  /**
+   * Peers that announce `cid` on the DHT, excluding this node. Stops at
+   * `max` or after the timeout, whichever comes first.
+   */
+  async findProviders(cid: string, max: number = 20, timeoutMs: number = 20000): Promise<string[]> {
+    const axios = require('axios');
+    const response = await axios.post(
+      `${this.getApiUrl()}/api/v0/routing/findprovs?arg=${cid}&num-providers=${max}`,
+      null,
+      { timeout: timeoutMs, responseType: 'text' }
+    );
+    const self = await this.getPeerId();
+    const providers = new Set<string>();
+    for (const line of String(response.data).split('\n')) {
+      try {
+        const event = JSON.parse(line);
+        // Type 4 is a provider record
+        if (event.Type !== 4) continue;
+        for (const entry of event.Responses || []) {
+          if (entry.ID && entry.ID !== self) providers.add(entry.ID);
+        }
+      } catch {
+        // Blank or partial line
+      }
+    }
+    return Array.from(providers);
+  }
+
+  /**
+   * Accepts libp2p streams for `protocol` and pipes them to a TCP port on
+   * loopback, replacing any earlier listener for the protocol.
+   */
+  async p2pListen(protocol: string, port: number): Promise<void> {
+    const axios = require('axios');
+    await axios.post(`${this.getApiUrl()}/api/v0/p2p/close?protocol=${encodeURIComponent(protocol)}`).catch(() => undefined);
+    await axios.post(`${this.getApiUrl()}/api/v0/p2p/listen?arg=${encodeURIComponent(protocol)}`
+      + `&arg=${encodeURIComponent(`/ip4/127.0.0.1/tcp/${port}`)}`);
+  }
+
+  // Opens a loopback port whose connections become `protocol` streams to the peer
+  async p2pForward(protocol: string, port: number, peerId: string): Promise<void> {
+    const axios = require('axios');
+    await axios.post(`${this.getApiUrl()}/api/v0/p2p/forward?arg=${encodeURIComponent(protocol)}`
+      + `&arg=${encodeURIComponent(`/ip4/127.0.0.1/tcp/${port}`)}&arg=${encodeURIComponent(`/p2p/${peerId}`)}`);
+  }
+
+  async p2pCloseForward(port: number): Promise<void> {
+    const axios = require('axios');
+    await axios.post(`${this.getApiUrl()}/api/v0/p2p/close?listen-address=${encodeURIComponent(`/ip4/127.0.0.1/tcp/${port}`)}`);
+  }
+
+  async getPeerCount(): Promise<number | null> {
+    try {
+      const axios = require('axios');
+      const response = await axios.post(`${this.getApiUrl()}/api/v0/swarm/peers`, null, { timeout: 3000 });
+      return (response.data.Peers || []).length;
     } catch {
       return null;
     }
@@ -209,6 +833,7 @@ export class KuboManager {
 
       return {
         repoSize: repoStats.data.RepoSize || 0,
+        storageMax: repoStats.data.StorageMax || 0,
         numObjects: repoStats.data.NumObjects || 0,
         bandwidthIn: bwStats.data.TotalIn || 0,
         bandwidthOut: bwStats.data.TotalOut || 0,
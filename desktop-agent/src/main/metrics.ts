@@ -0,0 +1,97 @@
+import { ConfigStore } from './config';
+import { KuboPool } from './kubo-pool';
+
+type MetricType = 'counter' | 'gauge';
+
+interface Sample {
+  labels?: Record<string, string>;
+  value: number;
+}
+
+export const METRICS_CONTENT_TYPE = 'text/plain; version=0.0.4; charset=utf-8';
+
+function escapeLabel(value: string): string {
+  return value.replace(/\\/g, '\\\\').replace(/\n/g, '\\n').replace(/"/g, '\\"');
+}
+
+function formatMetric(name: string, type: MetricType, help: string, samples: Sample[]): string {
+  const lines = [`# HELP ${name} ${help}`, `# TYPE ${name} ${type}`];
+  for (const sample of samples) {
+    const labels = Object.entries(sample.labels || {})
+      .map(([key, value]) => `${key}="${escapeLabel(value)}"`)
+      .join(',');
+    lines.push(`${name}${labels ? `{${labels}}` : ''} ${sample.value}`);
+  }
+  return lines.join('\n');
+}
+
+/**
+ * Renders `/metrics` in the Prometheus text exposition format so operators
+ * can scrape the agent into Grafana. Values are read on every scrape; the
+ * agent keeps no extra state for them.
+ */
+export class MetricsExporter {
+  private config: ConfigStore;
+  private pool: KuboPool;
+  private getRestartCount: () => number;
+
+  constructor(config: ConfigStore, pool: KuboPool, getRestartCount: () => number) {
+    this.config = config;
+    this.pool = pool;
+    this.getRestartCount = getRestartCount;
+  }
+
+  async render(): Promise<string> {
+    const earnings = this.config.getEarnings();
+    const instances = this.pool.getInstances();
+    const perInstance = await Promise.all(instances.map(async (kubo) => {
+      const running = kubo.isRunning();
+      const [stats, peers] = running
+        ? await Promise.all([kubo.getStats(), kubo.getPeerCount()])
+        : [null, null];
+      return { instance: kubo.getInstanceId(), running, stats, peers };
+    }));
+
+    const lastPassed = earnings.lastPassedChallengeTime ? Date.parse(earnings.lastPassedChallengeTime) / 1000 : 0;
+
+    return [
+      formatMetric('spk_challenges_total', 'counter', 'PoA challenges answered, by result', [
+        { labels: { result: 'passed' }, value: earnings.challengesPassed },
+        { labels: { result: 'failed' }, value: earnings.challengesFailed },
+      ]),
+      formatMetric('spk_challenge_consecutive_passes', 'gauge', 'Challenges passed since the last failure', [
+        { value: earnings.consecutivePasses },
+      ]),
+      formatMetric('spk_challenge_last_success_timestamp_seconds', 'gauge', 'Unix time of the last passed challenge, 0 if none', [
+        { value: lastPassed },
+      ]),
+      formatMetric('spk_earnings_hbd_total', 'counter', 'HBD earned from storage challenges', [
+        { value: earnings.totalHbd },
+      ]),
+      formatMetric('spk_earnings_hbd_provisional', 'gauge', 'Part of the HBD earned whose Hive block is not irreversible yet', [
+        { value: earnings.provisionalHbd },
+      ]),
+      formatMetric('spk_daemon_up', 'gauge', 'Whether the IPFS daemon is running', perInstance.map((entry) => ({
+        labels: { instance: entry.instance }, value: entry.running ? 1 : 0,
+      }))),
+      formatMetric('spk_daemon_restarts_total', 'counter', 'Daemon restarts performed by the watchdog', [
+        { value: this.getRestartCount() },
+      ]),
+      formatMetric('spk_pinned_bytes', 'gauge', 'Bytes stored in the IPFS repo', perInstance
+        .filter((entry) => entry.stats)
+        .map((entry) => ({ labels: { instance: entry.instance }, value: entry.stats.repoSize }))),
+      formatMetric('spk_storage_max_bytes', 'gauge', 'Configured repo size limit', perInstance
+        .filter((entry) => entry.stats)
+        .map((entry) => ({ labels: { instance: entry.instance }, value: entry.stats.storageMax }))),
+      formatMetric('spk_peers', 'gauge', 'Connected swarm peers', perInstance
+        .filter((entry) => entry.peers !== null)
+        .map((entry) => ({ labels: { instance: entry.instance }, value: entry.peers as number }))),
+      formatMetric('spk_bandwidth_bytes_total', 'counter', 'Bytes transferred by the daemon since it started', perInstance
+        .filter((entry) => entry.stats)
+        .flatMap((entry) => [
+          { labels: { instance: entry.instance, direction: 'in' }, value: entry.stats.bandwidthIn },
+          { labels: { instance: entry.instance, direction: 'out' }, value: entry.stats.bandwidthOut },
+        ])),
+    ].join('\n\n') + '\n';
+  }
+}
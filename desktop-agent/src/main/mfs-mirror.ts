@@ -0,0 +1,194 @@
+import axios from 'axios';
+import { KuboPool } from './kubo-pool';
+import { KuboManager } from './kubo';
+import { ContractPinner } from './contracts';
+import { PinIndex } from './pin-index';
+import { EventBus } from './event-bus';
+
+export const MFS_ROOT = '/spk';
+// Unpins come in bursts (expiry sweeps, orphan cleanup); one sync covers them all
+const RESYNC_DELAY_MS = 30 * 1000;
+
+export interface MfsMirrorResult {
+  at: string;
+  contracts: number;
+  entries: number;
+  added: number;
+  removed: number;
+  errors: string[];
+}
+
+export interface MfsMirrorRoot {
+  instance: string;
+  cid: string;
+  // The mirror on that instance's gateway
+  url: string;
+}
+
+export interface MfsMirrorStatus {
+  last: MfsMirrorResult | null;
+  roots: MfsMirrorRoot[];
+}
+
+interface MfsEntry {
+  name: string;
+  cid: string;
+}
+
+// MFS names can hold anything but a slash
+function mfsName(name: string): string {
+  return name.replace(/\//g, '_');
+}
+
+/**
+ * Mirrors pinned contract content into each Kubo instance's MFS under
+ * `/spk/<contract-id>/`, one entry per CID named after its pin, so what the
+ * node stores can be browsed through the local gateway. An MFS copy only
+ * links the pinned DAG, so it costs no extra space, but it does keep the
+ * blocks from garbage collection: entries are removed as soon as their CID
+ * is no longer pinned for a contract. Nothing outside `/spk` is touched.
+ */
+export class MfsMirror {
+  private pool: KuboPool;
+  private contracts: ContractPinner;
+  private pins: PinIndex;
+  private events: EventBus;
+  private last: MfsMirrorResult | null = null;
+  private resyncTimer: NodeJS.Timeout | null = null;
+  private running = false;
+
+  constructor(pool: KuboPool, contracts: ContractPinner, pins: PinIndex, events: EventBus) {
+    this.pool = pool;
+    this.contracts = contracts;
+    this.pins = pins;
+    this.events = events;
+  }
+
+  stop(): void {
+    if (this.resyncTimer) clearTimeout(this.resyncTimer);
+    this.resyncTimer = null;
+  }
+
+  // Syncs shortly, so content that was unpinned can be collected
+  scheduleSync(): void {
+    if (this.resyncTimer) return;
+    this.resyncTimer = setTimeout(() => {
+      this.resyncTimer = null;
+      this.sync().catch((error) => console.error('[Storage] MFS mirror sync failed:', error.message));
+    }, RESYNC_DELAY_MS);
+  }
+
+  async getStatus(): Promise<MfsMirrorStatus> {
+    const roots: MfsMirrorRoot[] = [];
+    for (const kubo of this.pool.getInstances()) {
+      if (!kubo.isRunning()) continue;
+      try {
+        const response = await axios.post(`${kubo.getApiUrl()}/api/v0/files/stat?arg=${encodeURIComponent(MFS_ROOT)}`,
+          null, { timeout: 5000 });
+        const cid: string = response.data.Hash;
+        roots.push({ instance: kubo.getInstanceId(), cid, url: `${kubo.getGatewayUrl()}/ipfs/${cid}/` });
+      } catch {
+        // Nothing mirrored on this instance yet
+      }
+    }
+    return { last: this.last, roots };
+  }
+
+  async sync(): Promise<MfsMirrorResult | null> {
+    if (this.running) return null;
+    this.running = true;
+    try {
+      const result: MfsMirrorResult = { at: new Date().toISOString(), contracts: 0, entries: 0, added: 0, removed: 0, errors: [] };
+      for (const kubo of this.pool.getInstances()) {
+        if (!kubo.isRunning()) continue;
+        try {
+          await this.syncInstance(kubo, result);
+        } catch (error: any) {
+          result.errors.push(`${kubo.getInstanceId()}: ${error.message}`);
+        }
+      }
+      this.last = result;
+      if (result.added > 0 || result.removed > 0) {
+        console.log(`[Storage] MFS mirror: ${result.added} entries added, ${result.removed} removed across ${result.contracts} contracts`);
+      }
+      this.events.publish('pins', 'mfs-mirrored', result);
+      return result;
+    } finally {
+      this.running = false;
+    }
+  }
+
+  private async syncInstance(kubo: KuboManager, result: MfsMirrorResult): Promise<void> {
+    const apiUrl = kubo.getApiUrl();
+    const pinned = new Set(Object.keys(
+      (await axios.post(`${apiUrl}/api/v0/pin/ls?type=recursive`, null, { timeout: 60000 })).data.Keys || {}));
+    const wanted = this.wantedEntries(pinned);
+
+    await this.files(apiUrl, 'mkdir', [MFS_ROOT], '&parents=true');
+    for (const dir of await this.list(apiUrl, MFS_ROOT)) {
+      if (wanted.has(dir.name)) continue;
+      await this.files(apiUrl, 'rm', [`${MFS_ROOT}/${dir.name}`], '&recursive=true');
+      result.removed++;
+    }
+
+    for (const [dirName, entries] of wanted) {
+      const dir = `${MFS_ROOT}/${dirName}`;
+      result.contracts++;
+      result.entries += entries.length;
+      try {
+        await this.files(apiUrl, 'mkdir', [dir], '&parents=true');
+        const existing = new Map((await this.list(apiUrl, dir)).map((entry) => [entry.name, entry.cid]));
+        const byName = new Map(entries.map((entry) => [entry.name, entry.cid]));
+        for (const [name, cid] of existing) {
+          if (byName.get(name) === cid) continue;
+          await this.files(apiUrl, 'rm', [`${dir}/${name}`], '&recursive=true');
+          existing.delete(name);
+          result.removed++;
+        }
+        for (const entry of entries) {
+          if (existing.has(entry.name)) continue;
+          await this.files(apiUrl, 'cp', [`/ipfs/${entry.cid}`, `${dir}/${entry.name}`]);
+          result.added++;
+        }
+      } catch (error: any) {
+        result.errors.push(`${dirName}: ${error.message}`);
+      }
+    }
+  }
+
+  // Directory name to entries, for the contract CIDs this instance has pinned
+  private wantedEntries(pinned: Set<string>): Map<string, MfsEntry[]> {
+    const wanted = new Map<string, MfsEntry[]>();
+    for (const record of this.contracts.list()) {
+      const cids = Object.entries(record.cids)
+        .filter(([cid, entry]) => entry.status === 'pinned' && pinned.has(cid))
+        .map(([cid]) => cid)
+        .sort();
+      if (cids.length === 0) continue;
+
+      // Pins sharing a name fall back to their CID, so every entry stays put between syncs
+      const named = cids.map((cid) => {
+        const name = this.pins.get(cid)?.name;
+        return { cid, name: name ? mfsName(name) : cid };
+      });
+      const counts = new Map<string, number>();
+      for (const { name } of named) counts.set(name, (counts.get(name) ?? 0) + 1);
+      wanted.set(mfsName(record.id), named.map(({ cid, name }) => ({ cid, name: counts.get(name)! > 1 ? cid : name })));
+    }
+    return wanted;
+  }
+
+  private async list(apiUrl: string, dir: string): Promise<MfsEntry[]> {
+    const response = await axios.post(`${apiUrl}/api/v0/files/ls?arg=${encodeURIComponent(dir)}&long=true`, null, { timeout: 30000 });
+    return (response.data.Entries || []).map((entry: any) => ({ name: entry.Name, cid: entry.Hash }));
+  }
+
+  private async files(apiUrl: string, command: string, args: string[], options = ''): Promise<void> {
+    const query = args.map((arg) => `arg=${encodeURIComponent(arg)}`).join('&');
+    try {
+      await axios.post(`${apiUrl}/api/v0/files/${command}?${query}${options}`, null, { timeout: 60000 });
+    } catch (error: any) {
+      throw new Error(error.response?.data?.Message || error.message);
+    }
+  }
+}
@@ -0,0 +1,203 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { showNotification } from './notifications';
+
+const DAY_MS = 24 * 60 * 60 * 1000;
+// The node list is fetched again after this
+const CACHE_MS = 15 * 60 * 1000;
+const CHECK_INTERVAL_MS = 60 * 60 * 1000;
+const WEEK_MS = 7 * DAY_MS;
+const MAX_SNAPSHOTS = 12;
+
+export type RankCriterion = 'earnings' | 'reliability' | 'reputation';
+export const RANK_CRITERIA: RankCriterion[] = ['earnings', 'reliability', 'reputation'];
+
+export interface NetworkRankSettings {
+  // A weekly notification when the earnings rank moved
+  notifyWeekly: boolean;
+}
+
+export const DEFAULT_NETWORK_RANK_SETTINGS: NetworkRankSettings = {
+  notifyWeekly: false,
+};
+
+export function validateNetworkRankSettings(settings: Partial<NetworkRankSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.notifyWeekly !== undefined && typeof settings.notifyWeekly !== 'boolean') {
+    errors.push('notifyWeekly must be a boolean');
+  }
+  return errors;
+}
+
+// A storage node as GET /api/nodes lists it
+interface NetworkNode {
+  peerId: string;
+  hiveUsername: string;
+  status: string;
+  reputation: number;
+  totalProofs: number;
+  failedProofs: number;
+  totalEarnedHbd: number;
+}
+
+export interface CriterionRank {
+  // 1 is best; nodes with the same value share a rank
+  rank: number;
+  value: number;
+}
+
+export interface NetworkRank {
+  // False when this node isn't in the list, e.g. before it registered
+  found: boolean;
+  peerId: string | null;
+  account: string | null;
+  // Nodes ranked; banned nodes aren't
+  totalNodes: number;
+  ranks: Partial<Record<RankCriterion, CriterionRank>>;
+  fetchedAt: string;
+}
+
+interface RankSnapshot {
+  at: string;
+  ranks: Partial<Record<RankCriterion, number>>;
+}
+
+export interface NetworkRankStatus {
+  settings: NetworkRankSettings;
+  rank: NetworkRank | null;
+  lastError: string | null;
+  // Weekly, oldest first
+  history: RankSnapshot[];
+}
+
+function rankOf(values: number[], value: number): number {
+  return 1 + values.filter((other) => other > value).length;
+}
+
+/**
+ * This node's rank among the SPK storage nodes the network API lists
+ * (`/api/nodes`): by HBD earned, by the share of proofs it passed, the
+ * network's view of whether it was there when challenged, and by
+ * reputation. The API doesn't publish capacity, so there is no rank by it.
+ * A snapshot is kept each week in network-rank.json, and when the earnings
+ * rank moved since the last one a notification can say by how much.
+ */
+export class NetworkRankTracker {
+  private config: ConfigStore;
+  private peerId: () => Promise<string | null>;
+  private filePath: string;
+  private history: RankSnapshot[];
+  private cached: NetworkRank | null = null;
+  private lastError: string | null = null;
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(dataDir: string, config: ConfigStore, peerId: () => Promise<string | null>) {
+    this.config = config;
+    this.peerId = peerId;
+    this.filePath = path.join(dataDir, 'network-rank.json');
+    this.history = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.checkWeekly().catch(() => undefined), CHECK_INTERVAL_MS);
+    this.checkWeekly().catch(() => undefined);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getStatus(): NetworkRankStatus {
+    return {
+      settings: this.config.getConfig().networkRank,
+      rank: this.cached,
+      lastError: this.lastError,
+      history: this.history,
+    };
+  }
+
+  async getRank(force = false): Promise<NetworkRank> {
+    if (!force && this.cached && Date.now() - Date.parse(this.cached.fetchedAt) < CACHE_MS) return this.cached;
+    const { spkApiUrl, hiveUsername } = this.config.getConfig();
+    let nodes: NetworkNode[];
+    try {
+      const response = await axios.get(`${spkApiUrl}/api/nodes`, { timeout: 15000 });
+      nodes = (Array.isArray(response.data) ? response.data : []).filter((node: NetworkNode) => node.status !== 'banned');
+      this.lastError = null;
+    } catch (error: any) {
+      this.lastError = error.message;
+      throw error;
+    }
+
+    const values: Record<RankCriterion, (node: NetworkNode) => number> = {
+      earnings: (node) => Number(node.totalEarnedHbd) || 0,
+      reliability: (node) => (node.totalProofs > 0 ? (node.totalProofs - node.failedProofs) / node.totalProofs : 0),
+      reputation: (node) => Number(node.reputation) || 0,
+    };
+    const peerId = await this.peerId().catch(() => null);
+    // The peer ID names this node; the account is the fallback before the daemon is up
+    const own = nodes.find((node) => peerId && node.peerId === peerId)
+      ?? nodes.find((node) => hiveUsername && node.hiveUsername === hiveUsername);
+    const ranks: Partial<Record<RankCriterion, CriterionRank>> = {};
+    if (own) {
+      for (const criterion of RANK_CRITERIA) {
+        const value = values[criterion](own);
+        ranks[criterion] = { rank: rankOf(nodes.map(values[criterion]), value), value };
+      }
+    }
+    this.cached = {
+      found: !!own,
+      peerId: own?.peerId ?? peerId,
+      account: own?.hiveUsername ?? hiveUsername,
+      totalNodes: nodes.length,
+      ranks,
+      fetchedAt: new Date().toISOString(),
+    };
+    return this.cached;
+  }
+
+  // Takes a snapshot once a week and says how far the earnings rank moved
+  private async checkWeekly(): Promise<void> {
+    const last = this.history[this.history.length - 1];
+    if (last && Date.now() - Date.parse(last.at) < WEEK_MS) return;
+    const rank = await this.getRank(true);
+    if (!rank.found) return;
+
+    const snapshot: RankSnapshot = {
+      at: rank.fetchedAt,
+      ranks: Object.fromEntries(Object.entries(rank.ranks).map(([criterion, entry]) => [criterion, entry!.rank])),
+    };
+    this.history = [...this.history, snapshot].slice(-MAX_SNAPSHOTS);
+    this.save();
+
+    const before = last?.ranks.earnings;
+    const now = snapshot.ranks.earnings;
+    if (!this.config.getConfig().networkRank.notifyWeekly || before === undefined || now === undefined || before === now) return;
+    const moved = Math.abs(before - now);
+    showNotification(
+      now < before ? `You moved up ${moved} places` : `You moved down ${moved} places`,
+      `Your node ranks #${now} of ${rank.totalNodes} storage nodes by earnings, from #${before} last week.`,
+      { kind: 'rewards', account: null },
+    );
+  }
+
+  private load(): RankSnapshot[] {
+    try {
+      return JSON.parse(fs.readFileSync(this.filePath, 'utf-8')).history ?? [];
+    } catch {
+      return [];
+    }
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify({ history: this.history }, null, 2));
+    } catch (error: any) {
+      console.error('[SPK] Failed to save network rank history:', error.message);
+    }
+  }
+}
@@ -0,0 +1,106 @@
+import axios from 'axios';
+import { KuboManager } from './kubo';
+
+const SAMPLE_INTERVAL_MS = 30 * 1000;
+// 24 hours at one sample every 30 seconds
+const MAX_SAMPLES = 2880;
+
+export interface NodeStatsSample {
+  timestamp: string;
+  repoSize: number;
+  storageMax: number;
+  numObjects: number;
+  blocksReceived: number;
+  blocksSent: number;
+  dataReceived: number;
+  dataSent: number;
+  // Bytes per second since the previous sample
+  rateIn: number;
+  rateOut: number;
+  wantlistLength: number;
+  swarmPeers: number;
+}
+
+/**
+ * Polls repo, bitswap and swarm stats and keeps a rolling in-memory series
+ * for the dashboard graphs. Nothing is persisted; the series starts over
+ * when the agent restarts.
+ */
+export class NodeStatsCollector {
+  private kubo: KuboManager;
+  private timer: NodeJS.Timeout | null = null;
+  private samples: NodeStatsSample[] = [];
+
+  constructor(kubo: KuboManager) {
+    this.kubo = kubo;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.collect(), SAMPLE_INTERVAL_MS);
+    void this.collect();
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  /**
+   * Samples newer than `since` (ISO timestamp), or the whole series.
+   */
+  getSeries(since?: string): NodeStatsSample[] {
+    if (!since) return this.samples;
+    const cutoff = new Date(since).getTime();
+    return this.samples.filter((sample) => new Date(sample.timestamp).getTime() > cutoff);
+  }
+
+  getLatest(): NodeStatsSample | null {
+    return this.samples[this.samples.length - 1] || null;
+  }
+
+  private async collect(): Promise<void> {
+    if (!this.kubo.isRunning() || this.kubo.isSuspended()) return;
+
+    const apiUrl = this.kubo.getApiUrl();
+    const [repo, bitswap, peers] = await Promise.all([
+      axios.post(`${apiUrl}/api/v0/repo/stat`, null, { timeout: 10000 }).catch(() => null),
+      axios.post(`${apiUrl}/api/v0/bitswap/stat`, null, { timeout: 10000 }).catch(() => null),
+      axios.post(`${apiUrl}/api/v0/swarm/peers`, null, { timeout: 10000 }).catch(() => null),
+    ]);
+    if (!repo && !bitswap && !peers) return;
+
+    const now = Date.now();
+    const previous = this.getLatest();
+    const sample: NodeStatsSample = {
+      timestamp: new Date(now).toISOString(),
+      repoSize: repo?.data.RepoSize || 0,
+      storageMax: repo?.data.StorageMax || 0,
+      numObjects: repo?.data.NumObjects || 0,
+      blocksReceived: bitswap?.data.BlocksReceived || 0,
+      blocksSent: bitswap?.data.BlocksSent || 0,
+      dataReceived: bitswap?.data.DataReceived || 0,
+      dataSent: bitswap?.data.DataSent || 0,
+      rateIn: 0,
+      rateOut: 0,
+      wantlistLength: bitswap?.data.Wantlist?.length || 0,
+      swarmPeers: peers?.data.Peers?.length || 0,
+    };
+
+    if (previous) {
+      const seconds = (now - new Date(previous.timestamp).getTime()) / 1000;
+      // Counters reset when the daemon restarts; don't report a negative rate
+      if (seconds > 0 && sample.dataReceived >= previous.dataReceived && sample.dataSent >= previous.dataSent) {
+        sample.rateIn = (sample.dataReceived - previous.dataReceived) / seconds;
+        sample.rateOut = (sample.dataSent - previous.dataSent) / seconds;
+      }
+    }
+
+    this.samples.push(sample);
+    if (this.samples.length > MAX_SAMPLES) {
+      this.samples.splice(0, this.samples.length - MAX_SAMPLES);
+    }
+  }
+}
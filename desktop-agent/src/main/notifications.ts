@@ -0,0 +1,32 @@
+import { Notification } from 'electron';
+import { NotificationKind } from './accounts';
+
+export interface NotificationTopic {
+  kind: NotificationKind;
+  // The account it concerns; null for the active one
+  account?: string | null;
+}
+
+type NotificationFilter = (topic: NotificationTopic) => boolean;
+
+let filter: NotificationFilter = () => true;
+
+// Lets per-account preferences mute a kind of notification
+export function setNotificationFilter(next: NotificationFilter): void {
+  filter = next;
+}
+
+// Urgent notifications stay on screen until dismissed, where the OS allows it. Null when it wasn't shown
+export function showNotification(title: string, body: string, topic?: NotificationTopic, urgent = false): Notification | null {
+  console.log(`[Notify] ${title}: ${body}`);
+  if (topic && !filter(topic)) return null;
+  if (!Notification.isSupported()) return null;
+
+  const notification = new Notification({
+    title,
+    body,
+    ...(urgent ? { urgency: 'critical' as const, timeoutType: 'never' as const } : {}),
+  });
+  notification.show();
+  return notification;
+}
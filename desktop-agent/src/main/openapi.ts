@@ -0,0 +1,118 @@
+import { API_ERRORS } from './api-errors';
+
+// Bumped on breaking changes to /api/v1; additive changes keep it
+export const API_VERSION = '1.0.0';
+export const API_PREFIX = '/api/v1';
+
+export type HttpMethod = 'get' | 'head' | 'post' | 'put' | 'patch' | 'delete';
+
+type JsonSchema = Record<string, unknown>;
+
+export interface RouteDoc {
+  summary: string;
+  tag: string;
+  // Properties of the JSON request body
+  body?: Record<string, JsonSchema>;
+  required?: string[];
+  query?: Record<string, JsonSchema>;
+  // Callable without the API token; every other route needs it
+  public?: boolean;
+  // Schema of the 200 response, if worth describing
+  response?: JsonSchema;
+}
+
+export interface RegisteredRoute {
+  method: HttpMethod;
+  // Relative to API_PREFIX, in Express syntax (`/pins/:cid`)
+  path: string;
+  doc: RouteDoc;
+}
+
+function toOpenApiPath(path: string): string {
+  return path.replace(/:(\w+)/g, '{$1}');
+}
+
+/**
+ * Builds the OpenAPI 3 document for the registered routes. It's generated
+ * from the same table the router is built from, so it can't drift from
+ * what the agent actually serves.
+ */
+export function buildOpenApiDocument(routes: RegisteredRoute[], port: number): object {
+  const paths: Record<string, Record<string, object>> = {};
+
+  for (const { method, path, doc } of routes) {
+    const fullPath = `${API_PREFIX}${path}`;
+    const parameters: object[] = [];
+    for (const match of path.matchAll(/:(\w+)/g)) {
+      parameters.push({ name: match[1], in: 'path', required: true, schema: { type: 'string' } });
+    }
+    for (const [name, schema] of Object.entries(doc.query || {})) {
+      parameters.push({ name, in: 'query', required: false, schema });
+    }
+
+    const operation: Record<string, unknown> = {
+      summary: doc.summary,
+      tags: [doc.tag],
+      responses: {
+        200: {
+          description: 'OK',
+          ...(doc.response ? { content: { 'application/json': { schema: doc.response } } } : {}),
+        },
+        ...(!doc.public ? { 401: { $ref: '#/components/responses/Error' } } : {}),
+        default: { $ref: '#/components/responses/Error' },
+      },
+    };
+    if (parameters.length > 0) operation.parameters = parameters;
+    if (doc.body) {
+      operation.requestBody = {
+        required: (doc.required || []).length > 0,
+        content: {
+          'application/json': {
+            schema: { type: 'object', properties: doc.body, required: doc.required || [] },
+          },
+        },
+      };
+    }
+    if (!doc.public) {
+      operation.security = [{ bearerAuth: [] }];
+    }
+
+    const key = toOpenApiPath(fullPath);
+    paths[key] = { ...paths[key], [method]: operation };
+  }
+
+  return {
+    openapi: '3.0.3',
+    info: {
+      title: 'SPK Desktop Agent API',
+      version: API_VERSION,
+      description: 'Local API of the SPK Network desktop agent. Mutating routes need a token from /api/v1/pair.',
+    },
+    servers: [{ url: `http://127.0.0.1:${port}` }],
+    components: {
+      securitySchemes: {
+        bearerAuth: { type: 'http', scheme: 'bearer' },
+      },
+      responses: {
+        Error: {
+          description: 'Error envelope; switch on `code`',
+          content: { 'application/json': { schema: { $ref: '#/components/schemas/Error' } } },
+        },
+      },
+      schemas: {
+        Error: {
+          type: 'object',
+          required: ['code', 'message', 'retryable'],
+          properties: {
+            code: { type: 'string', enum: Object.keys(API_ERRORS) },
+            message: { type: 'string' },
+            retryable: { type: 'boolean' },
+            details: { type: 'object', nullable: true },
+            error: { type: 'string', deprecated: true },
+          },
+        },
+      },
+    },
+    paths,
+  };
+}
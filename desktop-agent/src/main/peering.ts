@@ -0,0 +1,102 @@
+import * as dns from 'dns';
+
+/**
+ * Entry in Kubo's Peering.Peers config.
+ */
+export interface PeeringPeer {
+  ID: string;
+  Addrs: string[];
+}
+
+const MAX_DNSADDR_DEPTH = 3;
+export const MAX_PEERING_DNSADDRS = 10;
+
+/**
+ * The user's list of dnsaddrs for SPK gateways and validators to keep
+ * peered with. The agent ships none: no SPK operator publishes `_dnsaddr`
+ * records yet, so the list starts empty and holds what the user trusts.
+ * A curated default list is deferred until operators publish records.
+ */
+export function validatePeeringDnsaddrs(dnsaddrs: unknown): string[] {
+  if (!Array.isArray(dnsaddrs)) return ['dnsaddrs must be a list'];
+  const errors: string[] = [];
+  if (dnsaddrs.length > MAX_PEERING_DNSADDRS) {
+    errors.push(`At most ${MAX_PEERING_DNSADDRS} dnsaddrs`);
+  }
+  for (const addr of dnsaddrs) {
+    if (typeof addr !== 'string' || !/^\/dnsaddr\/[A-Za-z0-9.-]+$/.test(addr)) {
+      errors.push(`${addr} is not a /dnsaddr/<domain> address`);
+    }
+  }
+  return errors;
+}
+
+/**
+ * Splits '/ip4/1.2.3.4/tcp/4001/p2p/12D3Koo...' into a peering entry.
+ */
+export function parsePeerMultiaddr(multiaddr: string): PeeringPeer | null {
+  const match = multiaddr.trim().match(/^(.*)\/(?:p2p|ipfs)\/([A-Za-z0-9]+)$/);
+  if (!match) return null;
+
+  const [, addr, id] = match;
+  return { ID: id, Addrs: addr ? [addr] : [] };
+}
+
+/**
+ * Merges entries with the same peer ID, de-duplicating their addresses.
+ */
+export function mergePeers(peers: PeeringPeer[]): PeeringPeer[] {
+  const byId = new Map<string, Set<string>>();
+  for (const peer of peers) {
+    const addrs = byId.get(peer.ID) || new Set<string>();
+    peer.Addrs.forEach((addr) => addrs.add(addr));
+    byId.set(peer.ID, addrs);
+  }
+  return Array.from(byId.entries()).map(([ID, addrs]) => ({ ID, Addrs: Array.from(addrs) }));
+}
+
+async function resolveDnsaddr(multiaddr: string, depth = 0): Promise<string[]> {
+  const domain = multiaddr.replace(/^\/dnsaddr\//, '').split('/')[0];
+  let records: string[][];
+  try {
+    records = await dns.promises.resolveTxt(`_dnsaddr.${domain}`);
+  } catch {
+    return [];
+  }
+
+  const resolved: string[] = [];
+  for (const record of records) {
+    const value = record.join('');
+    if (!value.startsWith('dnsaddr=')) continue;
+    const addr = value.slice('dnsaddr='.length);
+    if (addr.startsWith('/dnsaddr/') && depth < MAX_DNSADDR_DEPTH) {
+      resolved.push(...await resolveDnsaddr(addr, depth + 1));
+    } else {
+      resolved.push(addr);
+    }
+  }
+  return resolved;
+}
+
+/**
+ * Resolves the configured SPK dnsaddrs into concrete peering entries.
+ * Domains that don't resolve are skipped so startup never blocks on DNS.
+ */
+export async function resolveSpkPeers(dnsaddrs: string[]): Promise<PeeringPeer[]> {
+  if (dnsaddrs.length === 0) return [];
+  const results = await Promise.all(dnsaddrs.map((addr) => resolveDnsaddr(addr)));
+  const peers = results
+    .flat()
+    .map(parsePeerMultiaddr)
+    .filter((peer): peer is PeeringPeer => peer !== null);
+
+  console.log(`[Peering] Resolved ${peers.length} SPK peer address(es)`);
+  return mergePeers(peers);
+}
+
+export function applyPeering(repoConfig: any, peers: PeeringPeer[]): void {
+  repoConfig.Peering = {
+    ...repoConfig.Peering,
+    Peers: mergePeers(peers),
+  };
+}
@@ -0,0 +1,358 @@
+import { execFile, spawn } from 'child_process';
+import { EventEmitter } from 'events';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { Readable } from 'stream';
+import { promisify } from 'util';
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { KuboPool } from './kubo-pool';
+import { KuboManager } from './kubo';
+import { PinIndex, MAX_LABELS } from './pin-index';
+import { ContractPinner } from './contracts';
+import { HoneycombClient } from './honeycomb';
+import { nodeAccounts, obligatedCids } from './pin-reconciler';
+import { EventBus } from './event-bus';
+import { showNotification } from './notifications';
+import { importCar } from './uploads';
+
+const execFileAsync = promisify(execFile);
+
+// Kubo's own default, which IPFS Desktop also uses
+const DEFAULT_API_URL = 'http://127.0.0.1:5001';
+const PROBE_TIMEOUT_MS = 2000;
+export const IMPORTED_LABEL = 'imported';
+
+export type ImportSourceKind = 'api' | 'repo';
+
+// A running node by its RPC API URL, or a repo no daemon has open
+export interface ImportSource {
+  kind: ImportSourceKind;
+  location: string;
+}
+
+export interface ImportCandidate extends ImportSource {
+  label: string;
+}
+
+export interface ImportablePin {
+  cid: string;
+  name: string | null;
+  // Already pinned on this node; importing skips it
+  present: boolean;
+}
+
+export interface PinImportProgress {
+  done: number;
+  total: number;
+  bytes: number;
+  cid?: string;
+}
+
+export interface PinImportResult {
+  source: ImportSource;
+  startedAt: string;
+  finishedAt: string;
+  imported: string[];
+  skipped: number;
+  failed: Array<{ cid: string; error: string }>;
+  bytes: number;
+  // Imported CIDs matched to an on-chain contract of this node's accounts
+  registered: number;
+  // Set when the run ended before every pin was tried
+  stoppedReason: string | null;
+}
+
+export interface PinImportStatus {
+  running: boolean;
+  source: ImportSource | null;
+  progress: PinImportProgress | null;
+  last: PinImportResult | null;
+}
+
+// '/ip4/127.0.0.1/tcp/5001' to 'http://127.0.0.1:5001'
+function multiaddrToUrl(multiaddr: string): string | null {
+  const match = multiaddr.trim().match(/^\/(ip4|ip6|dns|dns4|dns6)\/([^/]+)\/tcp\/(\d+)/);
+  if (!match) return null;
+  const host = match[1] === 'ip6' ? `[${match[2]}]` : match[2];
+  return `http://${host}:${match[3]}`;
+}
+
+function samePath(a: string, b: string): boolean {
+  return path.resolve(a) === path.resolve(b);
+}
+
+/**
+ * Brings the pins of another IPFS node into this one, so someone moving
+ * from IPFS Desktop or their own Kubo doesn't download it all again. The
+ * source is a running node's RPC API, or a repo on disk whose daemon is
+ * stopped, read with the agent's Kubo binary. Each recursive pin is copied
+ * as a `dag export | dag import` stream, which works whatever datastore
+ * either side uses; pins this node already has are skipped, so an import
+ * that stopped can simply be run again.
+ *
+ * Imported pins keep their names and get the `imported` label. Those that
+ * an on-chain contract of this node's accounts covers are registered under
+ * that contract, so their challenges earn for it. Every imported CID is
+ * announced right away so validators find it here.
+ */
+export class PinImporter extends EventEmitter {
+  private config: ConfigStore;
+  private pool: KuboPool;
+  private pins: PinIndex;
+  private contracts: ContractPinner;
+  private honeycomb: HoneycombClient;
+  private events: EventBus;
+  private isAcceptingContracts: () => boolean;
+  private source: ImportSource | null = null;
+  private progress: PinImportProgress | null = null;
+  private last: PinImportResult | null = null;
+  private cancelled = false;
+
+  constructor(config: ConfigStore, pool: KuboPool, pins: PinIndex, contracts: ContractPinner, honeycomb: HoneycombClient,
+    events: EventBus, isAcceptingContracts: () => boolean) {
+    super();
+    this.config = config;
+    this.pool = pool;
+    this.pins = pins;
+    this.contracts = contracts;
+    this.honeycomb = honeycomb;
+    this.events = events;
+    this.isAcceptingContracts = isAcceptingContracts;
+  }
+
+  getStatus(): PinImportStatus {
+    return { running: this.source !== null, source: this.source, progress: this.progress, last: this.last };
+  }
+
+  /**
+   * Nodes and repos on this machine that look like another IPFS install:
+   * $IPFS_PATH and ~/.ipfs, and a daemon on Kubo's default API port. The
+   * agent's own repos and APIs are left out.
+   */
+  async detect(): Promise<ImportCandidate[]> {
+    const instances = this.pool.getInstances();
+    const ownApis = new Set(instances.map((kubo) => kubo.getApiUrl().replace(/\/$/, '')));
+    const candidates: ImportCandidate[] = [];
+
+    const repos = [process.env.IPFS_PATH, path.join(os.homedir(), '.ipfs')]
+      .filter((repo): repo is string => !!repo)
+      .filter((repo, index, all) => all.findIndex((other) => samePath(other, repo)) === index)
+      .filter((repo) => !instances.some((kubo) => samePath(kubo.getRepoPath(), repo)))
+      .filter((repo) => fs.existsSync(path.join(repo, 'config')));
+    for (const repo of repos) {
+      const apiUrl = this.runningApiOf(repo);
+      if (apiUrl) {
+        if (!ownApis.has(apiUrl)) candidates.push({ kind: 'api', location: apiUrl, label: `${repo} (running)` });
+      } else {
+        candidates.push({ kind: 'repo', location: repo, label: `${repo} (stopped)` });
+      }
+    }
+
+    if (!ownApis.has(DEFAULT_API_URL) && !candidates.some((candidate) => candidate.location === DEFAULT_API_URL)) {
+      const reachable = await axios.post(`${DEFAULT_API_URL}/api/v0/id`, null, { timeout: PROBE_TIMEOUT_MS })
+        .then(() => true, () => false);
+      if (reachable) candidates.push({ kind: 'api', location: DEFAULT_API_URL, label: `IPFS node at ${DEFAULT_API_URL}` });
+    }
+    return candidates;
+  }
+
+  // A repo whose daemon is up is read through its API, since the daemon holds the repo lock
+  async listPins(source: ImportSource): Promise<ImportablePin[]> {
+    const resolved = this.resolve(source);
+    const keys: Record<string, { Name?: string }> = resolved.kind === 'api'
+      ? (await axios.post(`${resolved.location}/api/v0/pin/ls?type=recursive&names=true`, null, { timeout: 60000 })).data?.Keys ?? {}
+      : JSON.parse(await this.ipfs(resolved.location, ['pin', 'ls', '--type=recursive', '--names', '--enc=json'])).Keys ?? {};
+    const present = await this.localPins();
+    return Object.entries(keys).map(([cid, entry]) => ({ cid, name: entry?.Name || null, present: present.has(cid) }));
+  }
+
+  cancel(): void {
+    this.cancelled = true;
+  }
+
+  /**
+   * Copies the source's pins, or only the CIDs given. Resolves when the
+   * run ends; `progress` is emitted after every pin.
+   */
+  async import(source: ImportSource, cids?: string[]): Promise<PinImportResult> {
+    if (this.source) throw new Error('A pin import is already running');
+    const resolved = this.resolve(source);
+    this.source = resolved;
+    this.cancelled = false;
+    const result: PinImportResult = {
+      source: resolved,
+      startedAt: new Date().toISOString(),
+      finishedAt: '',
+      imported: [],
+      skipped: 0,
+      failed: [],
+      bytes: 0,
+      registered: 0,
+      stoppedReason: null,
+    };
+
+    try {
+      const available = await this.listPins(resolved);
+      const wanted = cids ? available.filter((pin) => cids.includes(pin.cid)) : available;
+      const todo = wanted.filter((pin) => !pin.present);
+      result.skipped = wanted.length - todo.length;
+      // One target for the whole run, so contract registration finds the content where it landed
+      const target = await this.pool.selectForNewContent();
+      console.log(`[Kubo] Importing ${todo.length} pins from ${resolved.location} (${result.skipped} already here)`);
+
+      for (let i = 0; i < todo.length; i++) {
+        if (this.cancelled) {
+          result.stoppedReason = 'Cancelled';
+          break;
+        }
+        if (!this.isAcceptingContracts()) {
+          result.stoppedReason = 'Storage quota reached';
+          break;
+        }
+        const { cid, name } = todo[i];
+        this.reportProgress({ done: i, total: todo.length, bytes: result.bytes, cid });
+        try {
+          const { bytes } = await importCar(target.getApiUrl(), await this.exportDag(resolved, cid));
+          result.bytes += bytes;
+          result.imported.push(cid);
+          const labels = this.pins.get(cid)?.labels ?? [];
+          this.pins.set(cid, {
+            name: this.pins.get(cid)?.name ?? name,
+            labels: [...labels, IMPORTED_LABEL].slice(0, MAX_LABELS),
+          });
+          this.events.publish('pins', 'pinned', { cid, instance: target.getInstanceId() });
+        } catch (error: any) {
+          console.error(`[Kubo] Failed to import ${cid}:`, error.message);
+          result.failed.push({ cid, error: error.message });
+        }
+      }
+      this.reportProgress({ done: result.imported.length + result.failed.length, total: todo.length, bytes: result.bytes });
+
+      if (result.imported.length > 0) {
+        result.registered = await this.register(result.imported);
+        target.reprovide(result.imported).catch((error: any) => {
+          console.error('[Kubo] Failed to announce imported pins:', error.message);
+        });
+      }
+    } finally {
+      this.source = null;
+      this.progress = null;
+    }
+
+    result.finishedAt = new Date().toISOString();
+    this.last = result;
+    console.log(`[Kubo] Imported ${result.imported.length} pins (${(result.bytes / 1e9).toFixed(2)} GB), `
+      + `${result.failed.length} failed, ${result.registered} registered under contracts`
+      + (result.stoppedReason ? `; stopped: ${result.stoppedReason}` : ''));
+    this.events.publish('pins', 'imported', result);
+    showNotification('Pins imported', `Copied ${result.imported.length} pins from ${resolved.location}`
+      + (result.failed.length > 0 ? `, ${result.failed.length} failed` : '')
+      + (result.stoppedReason ? ` before stopping: ${result.stoppedReason}` : ''), { kind: 'contracts' });
+    return result;
+  }
+
+  // Groups imported CIDs under the on-chain contracts that say this node stores them
+  private async register(cids: string[]): Promise<number> {
+    const contractState = this.honeycomb.getStatus().find((dataset) => dataset.dataset === 'contracts');
+    if (!contractState?.syncedAt) return 0;
+    const obligations = obligatedCids(this.honeycomb.getContracts(), nodeAccounts(this.config, this.contracts));
+    const byContract = new Map<string, { account: string; cids: string[] }>();
+    for (const cid of cids) {
+      const obligation = obligations.get(cid);
+      if (!obligation) continue;
+      const group = byContract.get(obligation.contractId) ?? { account: obligation.account, cids: [] };
+      group.cids.push(cid);
+      byContract.set(obligation.contractId, group);
+    }
+    let registered = 0;
+    for (const [contractId, { account, cids: held }] of byContract) {
+      try {
+        // The blocks are already here, so this pin returns at once
+        await this.contracts.pin({ contractId, cids: held, account });
+        registered += held.length;
+      } catch (error: any) {
+        console.error(`[Kubo] Failed to register imported pins under ${contractId}:`, error.message);
+      }
+    }
+    return registered;
+  }
+
+  private resolve(source: ImportSource): ImportSource {
+    if (source?.kind === 'api') {
+      if (!/^https?:\/\//.test(source.location)) throw new Error('The API URL must start with http:// or https://');
+      return { kind: 'api', location: source.location.replace(/\/$/, '') };
+    }
+    if (source?.kind !== 'repo' || !source.location) throw new Error('Choose a node API URL or a repo folder');
+    const repo = source.location.replace(/^~(?=$|[\\/])/, os.homedir());
+    if (!fs.existsSync(path.join(repo, 'config'))) throw new Error(`${repo} is not an IPFS repo`);
+    if (this.pool.getInstances().some((kubo) => samePath(kubo.getRepoPath(), repo))) {
+      throw new Error('That is one of this agent\'s own repos');
+    }
+    const apiUrl = this.runningApiOf(repo);
+    return apiUrl ? { kind: 'api', location: apiUrl } : { kind: 'repo', location: repo };
+  }
+
+  // The API a running daemon wrote into its repo; the file is removed when it stops
+  private runningApiOf(repo: string): string | null {
+    try {
+      return multiaddrToUrl(fs.readFileSync(path.join(repo, 'api'), 'utf-8'));
+    } catch {
+      return null;
+    }
+  }
+
+  private async exportDag(source: ImportSource, cid: string): Promise<Readable> {
+    if (source.kind === 'api') {
+      const response = await axios.post(`${source.location}/api/v0/dag/export?arg=${cid}`, null, {
+        responseType: 'stream',
+        timeout: 0,
+      });
+      return response.data;
+    }
+    const binary = await this.pool.getPrimary().ensureBinary();
+    const exporter = spawn(binary, ['--offline', 'dag', 'export', cid], {
+      env: { ...process.env, IPFS_PATH: source.location },
+      stdio: ['ignore', 'pipe', 'pipe'],
+    });
+    let stderr = '';
+    exporter.stderr?.on('data', (data) => { stderr += data.toString(); });
+    exporter.on('exit', (code) => {
+      if (code !== 0) exporter.stdout?.destroy(new Error(stderr.trim() || `dag export exited with code ${code}`));
+    });
+    return exporter.stdout!;
+  }
+
+  private async ipfs(repo: string, args: string[]): Promise<string> {
+    const binary = await this.pool.getPrimary().ensureBinary();
+    try {
+      const { stdout } = await execFileAsync(binary, args, {
+        env: { ...process.env, IPFS_PATH: repo },
+        maxBuffer: 64 * 1024 * 1024,
+      });
+      return stdout;
+    } catch (error: any) {
+      const message = (error.stderr || error.message || '').trim();
+      if (/repo.*version|migrat/i.test(message)) {
+        throw new Error(`The repo at ${repo} is for another Kubo version; start its node and import from its API instead`);
+      }
+      throw new Error(message);
+    }
+  }
+
+  private async localPins(): Promise<Set<string>> {
+    const present = new Set<string>();
+    await Promise.all(this.pool.getInstances().filter((kubo: KuboManager) => kubo.isRunning()).map(async (kubo) => {
+      const response = await axios.post(`${kubo.getApiUrl()}/api/v0/pin/ls?type=recursive`, null, { timeout: 60000 })
+        .catch(() => null);
+      for (const cid of Object.keys(response?.data?.Keys ?? {})) present.add(cid);
+    }));
+    return present;
+  }
+
+  private reportProgress(progress: PinImportProgress): void {
+    this.progress = progress;
+    this.emit('progress', progress);
+  }
+}
@@ -0,0 +1,81 @@
+import * as fs from 'fs';
+import * as path from 'path';
+
+export interface PinMetadata {
+  name: string | null;
+  labels: string[];
+  pinnedAt: string;
+}
+
+export const MAX_LABELS = 16;
+const MAX_LABEL_LENGTH = 64;
+const MAX_NAME_LENGTH = 255;
+
+export function validatePinMetadata(update: { name?: unknown; labels?: unknown }): string[] {
+  const errors: string[] = [];
+  if (update.name !== undefined && update.name !== null) {
+    if (typeof update.name !== 'string' || update.name.length > MAX_NAME_LENGTH) {
+      errors.push(`name must be a string of at most ${MAX_NAME_LENGTH} characters`);
+    }
+  }
+  if (update.labels !== undefined) {
+    if (!Array.isArray(update.labels) || update.labels.length > MAX_LABELS) {
+      errors.push(`labels must be an array of at most ${MAX_LABELS} strings`);
+    } else if (update.labels.some((label) => typeof label !== 'string' || !label || label.length > MAX_LABEL_LENGTH)) {
+      errors.push(`each label must be a non-empty string of at most ${MAX_LABEL_LENGTH} characters`);
+    }
+  }
+  return errors;
+}
+
+/**
+ * Names and labels for pinned CIDs. Kubo only knows CIDs, so the agent keeps
+ * what the user called things in pin-index.json next to the earnings file.
+ * Entries for content unpinned outside the agent are simply never matched.
+ */
+export class PinIndex {
+  private filePath: string;
+  private entries: Record<string, PinMetadata>;
+
+  constructor(dataDir: string) {
+    this.filePath = path.join(dataDir, 'pin-index.json');
+    this.entries = this.load();
+  }
+
+  get(cid: string): PinMetadata | null {
+    return this.entries[cid] || null;
+  }
+
+  set(cid: string, update: { name?: string | null; labels?: string[] }): PinMetadata {
+    const current = this.entries[cid];
+    const entry: PinMetadata = {
+      name: update.name !== undefined ? update.name : current?.name ?? null,
+      labels: update.labels !== undefined ? Array.from(new Set(update.labels)) : current?.labels ?? [],
+      pinnedAt: current?.pinnedAt ?? new Date().toISOString(),
+    };
+    this.entries[cid] = entry;
+    this.save();
+    return entry;
+  }
+
+  remove(cid: string): void {
+    if (!this.entries[cid]) return;
+    delete this.entries[cid];
+    this.save();
+  }
+
+  private load(): Record<string, PinMetadata> {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Pins] Failed to read pin index:', error);
+    }
+    return {};
+  }
+
+  private save(): void {
+    fs.writeFileSync(this.filePath, JSON.stringify(this.entries, null, 2));
+  }
+}
@@ -0,0 +1,325 @@
+import { ConfigStore } from './config';
+import { ContractPinner } from './contracts';
+import { HoneycombClient } from './honeycomb';
+import { EventBus } from './event-bus';
+import { showNotification } from './notifications';
+import { MfsMirrorResult } from './mfs-mirror';
+
+const HOUR_MS = 60 * 60 * 1000;
+// Gives the daemon time to come up and honeycomb time for a first sync
+const STARTUP_DELAY_MS = 2 * 60 * 1000;
+
+export interface PinReconcileSettings {
+  enabled: boolean;
+  intervalHours: number;
+  // Pin what the chain says this node stores but it doesn't have
+  autoPin: boolean;
+  // Mirror contract content into MFS under /spk after each run
+  mirrorMfs: boolean;
+}
+
+export const DEFAULT_PIN_RECONCILE_SETTINGS: PinReconcileSettings = {
+  enabled: true,
+  intervalHours: 6,
+  autoPin: true,
+  mirrorMfs: true,
+};
+
+export function validatePinReconcileSettings(settings: Partial<PinReconcileSettings>): string[] {
+  const errors: string[] = [];
+  for (const field of ['enabled', 'autoPin', 'mirrorMfs'] as const) {
+    if (settings[field] !== undefined && typeof settings[field] !== 'boolean') errors.push(`${field} must be a boolean`);
+  }
+  if (settings.intervalHours !== undefined
+    && (!Number.isInteger(settings.intervalHours) || settings.intervalHours < 1 || settings.intervalHours > 24 * 7)) {
+    errors.push('intervalHours must be between 1 and 168');
+  }
+  return errors;
+}
+
+export interface PinReconcileOperations {
+  // Recursively pinned CIDs across every Kubo instance
+  listPinned(): Promise<string[]>;
+  unpin(cid: string): Promise<void>;
+  // True when the CID was also pinned or named by the user, outside any contract
+  heldOutsideContracts(cid: string): boolean;
+  isAcceptingContracts(): boolean;
+  // Brings the /spk MFS view in line with the contract pins
+  mirror(): Promise<MfsMirrorResult | null>;
+}
+
+export type ReconcileTrigger = 'startup' | 'schedule' | 'manual';
+
+export interface OrphanPin {
+  cid: string;
+  // The local contracts it was pinned for, none of which the chain still lists
+  contractIds: string[];
+}
+
+export interface PinReconciliation {
+  at: string;
+  trigger: ReconcileTrigger;
+  // CIDs the chain has this node's accounts storing
+  obligated: number;
+  pinned: number;
+  missing: string[];
+  // Missing CIDs queued for pinning; 0 with autoPin off or storage full
+  queued: number;
+  orphans: OrphanPin[];
+  // Null with mirrorMfs off
+  mirror: MfsMirrorResult | null;
+  errors: string[];
+}
+
+export interface PinReconcileStatus {
+  settings: PinReconcileSettings;
+  last: PinReconciliation | null;
+  nextRunAt: string | null;
+}
+
+export interface OrphanCleanup {
+  unpinned: string[];
+  // Contracts moved to the trash, whose orphaned content stays pinned until it is emptied
+  trashed: string[];
+  // Still held outside contracts, or claimed again since the reconciliation
+  kept: string[];
+  errors: string[];
+}
+
+/**
+ * Honeycomb keeps storage contracts keyed by ID, with the files under `df`
+ * (CID to size) and the storage nodes holding them under `n`.
+ */
+export function obligatedCids(contracts: Record<string, any>, accounts: Set<string>): Map<string, { contractId: string; account: string }> {
+  const obligations = new Map<string, { contractId: string; account: string }>();
+  for (const [contractId, contract] of Object.entries(contracts)) {
+    const nodes = contract?.n && typeof contract.n === 'object' ? Object.values(contract.n as Record<string, unknown>) : [];
+    const account = nodes.find((node): node is string => typeof node === 'string' && accounts.has(node));
+    if (!account) continue;
+    for (const cid of Object.keys(contract?.df ?? {})) {
+      if (!obligations.has(cid)) obligations.set(cid, { contractId, account });
+    }
+  }
+  return obligations;
+}
+
+// The active account, and any other a local contract is stored for
+export function nodeAccounts(config: ConfigStore, contracts: ContractPinner): Set<string> {
+  return new Set([
+    config.getConfig().hiveUsername,
+    ...contracts.list().map((record) => record.account),
+  ].filter((account): account is string => !!account));
+}
+
+/**
+ * Keeps the local pinset in line with what the SPK chain says this node's
+ * accounts store. On startup and every few hours, the CIDs of on-chain
+ * contracts naming one of the node's accounts are compared with what Kubo
+ * has pinned. Missing CIDs are pinned again under their contract. Pins
+ * made for a contract the chain no longer lists are flagged as orphans;
+ * they are only unpinned when the user cleans them up. A run that finds
+ * either raises a notification.
+ */
+export class PinReconciler {
+  private config: ConfigStore;
+  private honeycomb: HoneycombClient;
+  private contracts: ContractPinner;
+  private ops: PinReconcileOperations;
+  private events: EventBus;
+  private startupTimer: NodeJS.Timeout | null = null;
+  private timer: NodeJS.Timeout | null = null;
+  private nextRunAt: number | null = null;
+  private last: PinReconciliation | null = null;
+  private running = false;
+
+  constructor(config: ConfigStore, honeycomb: HoneycombClient, contracts: ContractPinner, ops: PinReconcileOperations,
+    events: EventBus) {
+    this.config = config;
+    this.honeycomb = honeycomb;
+    this.contracts = contracts;
+    this.ops = ops;
+    this.events = events;
+  }
+
+  start(): void {
+    if (this.startupTimer || this.timer) return;
+    const { enabled, intervalHours } = this.config.getConfig().pinReconcile;
+    if (!enabled) return;
+    this.nextRunAt = Date.now() + STARTUP_DELAY_MS;
+    this.startupTimer = setTimeout(() => {
+      this.startupTimer = null;
+      this.run('startup').catch((error) => console.error('[Contracts] Pin reconciliation failed:', error.message));
+    }, STARTUP_DELAY_MS);
+    this.timer = setInterval(() => {
+      this.run('schedule').catch((error) => console.error('[Contracts] Pin reconciliation failed:', error.message));
+    }, intervalHours * HOUR_MS);
+  }
+
+  stop(): void {
+    if (this.startupTimer) clearTimeout(this.startupTimer);
+    if (this.timer) clearInterval(this.timer);
+    this.startupTimer = null;
+    this.timer = null;
+    this.nextRunAt = null;
+  }
+
+  // Picks up new settings
+  restart(): void {
+    this.stop();
+    this.start();
+  }
+
+  getStatus(): PinReconcileStatus {
+    return {
+      settings: this.config.getConfig().pinReconcile,
+      last: this.last,
+      nextRunAt: this.nextRunAt ? new Date(this.nextRunAt).toISOString() : null,
+    };
+  }
+
+  async run(trigger: ReconcileTrigger = 'manual'): Promise<PinReconciliation | null> {
+    if (this.running) return null;
+    this.running = true;
+    try {
+      if (trigger !== 'manual') {
+        this.nextRunAt = Date.now() + this.config.getConfig().pinReconcile.intervalHours * HOUR_MS;
+      }
+      const contractState = this.honeycomb.getStatus().find((dataset) => dataset.dataset === 'contracts');
+      // Without the chain's view, every contract pin would look orphaned
+      if (!contractState?.syncedAt) throw new Error('SPK contract state has not synced from honeycomb yet');
+
+      const obligations = obligatedCids(this.honeycomb.getContracts(), nodeAccounts(this.config, this.contracts));
+      const pinned = new Set(await this.ops.listPinned());
+
+      const reconciliation: PinReconciliation = {
+        at: new Date().toISOString(),
+        trigger,
+        obligated: obligations.size,
+        pinned: pinned.size,
+        // A sharded file is held through its assigned shards, never whole
+        missing: [...obligations.keys()].filter((cid) => !pinned.has(cid) && !this.contracts.shardFor(cid)),
+        queued: 0,
+        orphans: [...pinned]
+          .filter((cid) => !obligations.has(cid) && !this.ops.heldOutsideContracts(cid))
+          .map((cid) => ({ cid, contractIds: this.contracts.contractsFor(cid) }))
+          .filter((orphan) => orphan.contractIds.length > 0),
+        mirror: null,
+        errors: [],
+      };
+
+      if (reconciliation.missing.length > 0 && this.config.getConfig().pinReconcile.autoPin) {
+        if (!this.ops.isAcceptingContracts()) {
+          reconciliation.errors.push('Storage is full; missing CIDs were not pinned');
+        } else {
+          await this.pinMissing(reconciliation, obligations);
+        }
+      }
+      if (this.config.getConfig().pinReconcile.mirrorMfs) {
+        try {
+          reconciliation.mirror = await this.ops.mirror();
+        } catch (error: any) {
+          reconciliation.errors.push(`MFS mirror: ${error.message}`);
+        }
+      }
+
+      this.last = reconciliation;
+      this.report(reconciliation);
+      return reconciliation;
+    } finally {
+      this.running = false;
+    }
+  }
+
+  /**
+   * Unpins orphans from the last run (all of them, or the CIDs given),
+   * then forgets local contracts left with nothing pinned. With a trash
+   * retention set, contracts made up only of orphans are trashed instead.
+   */
+  async cleanOrphans(cids?: string[]): Promise<OrphanCleanup> {
+    const flagged = this.last?.orphans ?? [];
+    const targets = cids ? flagged.filter((orphan) => cids.includes(orphan.cid)) : flagged;
+    const obligations = obligatedCids(this.honeycomb.getContracts(), nodeAccounts(this.config, this.contracts));
+    const cleanup: OrphanCleanup = { unpinned: [], trashed: [], kept: [], errors: [] };
+    const removable = new Set(targets
+      .filter((orphan) => !obligations.has(orphan.cid) && !this.ops.heldOutsideContracts(orphan.cid))
+      .map((orphan) => orphan.cid));
+
+    // Contracts left with nothing but orphans go to the trash whole, content still pinned
+    const trashedCids = new Set<string>();
+    if (this.config.getConfig().trash.retentionHours > 0) {
+      for (const id of new Set(targets.flatMap((orphan) => orphan.contractIds))) {
+        const record = this.contracts.get(id);
+        if (!record || !Object.keys(record.cids).every((cid) => removable.has(cid))) continue;
+        this.contracts.trash(id, 'orphaned');
+        cleanup.trashed.push(id);
+        Object.keys(record.cids).forEach((cid) => trashedCids.add(cid));
+      }
+    }
+
+    for (const orphan of targets) {
+      if (!removable.has(orphan.cid)) {
+        cleanup.kept.push(orphan.cid);
+        continue;
+      }
+      if (trashedCids.has(orphan.cid)) continue;
+      try {
+        await this.ops.unpin(orphan.cid);
+        cleanup.unpinned.push(orphan.cid);
+      } catch (error: any) {
+        cleanup.errors.push(`${orphan.cid}: ${error.message}`);
+      }
+    }
+
+    const unpinned = new Set(cleanup.unpinned);
+    const contractIds = new Set(targets.flatMap((orphan) => orphan.contractIds));
+    for (const id of contractIds) {
+      const record = this.contracts.get(id);
+      if (record && Object.keys(record.cids).every((cid) => unpinned.has(cid))) this.contracts.remove(id);
+    }
+    if (this.last) this.last.orphans = this.last.orphans.filter((orphan) => !unpinned.has(orphan.cid) && !trashedCids.has(orphan.cid));
+    console.log(`[Contracts] Cleaned up ${cleanup.unpinned.length} orphaned pins and trashed ${cleanup.trashed.length} contracts,`
+      + ` kept ${cleanup.kept.length}`);
+    this.events.publish('pins', 'orphans-cleaned', cleanup);
+    return cleanup;
+  }
+
+  // Grouped by contract, so each missing CID is tracked under the contract that owes it
+  private async pinMissing(reconciliation: PinReconciliation,
+    obligations: Map<string, { contractId: string; account: string }>): Promise<void> {
+    const byContract = new Map<string, { account: string; cids: string[] }>();
+    for (const cid of reconciliation.missing) {
+      const { contractId, account } = obligations.get(cid)!;
+      const group = byContract.get(contractId) ?? { account, cids: [] };
+      group.cids.push(cid);
+      byContract.set(contractId, group);
+    }
+    for (const [contractId, { account, cids }] of byContract) {
+      try {
+        await this.contracts.pin({ contractId, cids, account });
+        reconciliation.queued += cids.length;
+      } catch (error: any) {
+        reconciliation.errors.push(`${contractId}: ${error.message}`);
+      }
+    }
+  }
+
+  private report(reconciliation: PinReconciliation): void {
+    const { missing, queued, orphans, errors } = reconciliation;
+    console.log(`[Contracts] Reconciled pins (${reconciliation.trigger}): ${reconciliation.obligated} under contract, `
+      + `${missing.length} missing, ${queued} queued, ${orphans.length} orphaned`
+      + (errors.length > 0 ? `; ${errors.join('; ')}` : ''));
+    this.events.publish('pins', 'reconciled', reconciliation);
+    if (missing.length === 0 && orphans.length === 0) return;
+
+    const parts: string[] = [];
+    if (missing.length > 0) {
+      parts.push(queued > 0
+        ? `Pinning ${queued} of ${missing.length} CIDs this node should store but didn't have`
+        : `${missing.length} CIDs this node should store are not pinned`);
+    }
+    if (orphans.length > 0) {
+      parts.push(`${orphans.length} pins belong to contracts no longer on chain; clean them up from the dashboard`);
+    }
+    showNotification('Pins reconciled', `${parts.join('. ')}.`, { kind: 'contracts' });
+  }
+}
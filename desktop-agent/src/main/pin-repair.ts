@@ -0,0 +1,187 @@
+import { EventEmitter } from 'events';
+import axios from 'axios';
+import { KuboManager } from './kubo';
+import { KuboPool } from './kubo-pool';
+
+const STAT_CONCURRENCY = 8;
+const FETCH_TIMEOUT_MS = 60 * 1000;
+
+export interface PinCheck {
+  cid: string;
+  instance: string;
+  missingBlocks: string[];
+  // False when the walk hit a missing block and couldn't see below it
+  complete: boolean;
+}
+
+export interface PinRepairProgress {
+  done: number;
+  total: number;
+  cid: string;
+  stage: 'verifying' | 'repairing';
+}
+
+export interface PinRepairResult {
+  checked: number;
+  healthy: number;
+  repaired: string[];
+  failed: Array<{ cid: string; missingBlocks: number; error?: string }>;
+  startedAt: string;
+  finishedAt: string;
+}
+
+function parseNdjson(body: string): any[] {
+  return body
+    .split('\n')
+    .filter((line) => line.trim().length > 0)
+    .map((line) => {
+      try {
+        return JSON.parse(line);
+      } catch {
+        return null;
+      }
+    })
+    .filter(Boolean);
+}
+
+/**
+ * Finds pinned DAGs with blocks missing from the local blockstore (usually
+ * after a disk error) and fetches them again from the network, so PoA
+ * challenges on those CIDs stop failing silently.
+ */
+export class PinRepairer extends EventEmitter {
+  private pool: KuboPool;
+  private running = false;
+
+  constructor(pool: KuboPool) {
+    super();
+    this.pool = pool;
+  }
+
+  isRunning(): boolean {
+    return this.running;
+  }
+
+  async repairAll(): Promise<PinRepairResult> {
+    if (this.running) {
+      throw new Error('Pin repair already in progress');
+    }
+    this.running = true;
+
+    const result: PinRepairResult = {
+      checked: 0,
+      healthy: 0,
+      repaired: [],
+      failed: [],
+      startedAt: new Date().toISOString(),
+      finishedAt: '',
+    };
+
+    try {
+      const targets: Array<{ kubo: KuboManager; cid: string }> = [];
+      for (const kubo of this.pool.getInstances()) {
+        if (!kubo.isRunning()) continue;
+        const response = await axios.post(`${kubo.getApiUrl()}/api/v0/pin/ls?type=recursive`);
+        Object.keys(response.data.Keys || {}).forEach((cid) => targets.push({ kubo, cid }));
+      }
+
+      for (let i = 0; i < targets.length; i++) {
+        const { kubo, cid } = targets[i];
+        this.emit('progress', { done: i, total: targets.length, cid, stage: 'verifying' } as PinRepairProgress);
+
+        try {
+          const check = await this.verifyPin(kubo, cid);
+          result.checked++;
+          if (check.complete && check.missingBlocks.length === 0) {
+            result.healthy++;
+            continue;
+          }
+
+          console.log(`[Repair] ${cid} is missing ${check.missingBlocks.length} block(s), re-fetching`);
+          this.emit('progress', { done: i, total: targets.length, cid, stage: 'repairing' } as PinRepairProgress);
+          const after = await this.repairPin(kubo, check);
+          if (after.complete && after.missingBlocks.length === 0) {
+            result.repaired.push(cid);
+          } else {
+            result.failed.push({ cid, missingBlocks: after.missingBlocks.length });
+          }
+        } catch (error: any) {
+          result.failed.push({ cid, missingBlocks: 0, error: error.message });
+        }
+      }
+
+      this.emit('progress', { done: targets.length, total: targets.length, cid: '', stage: 'verifying' } as PinRepairProgress);
+      result.finishedAt = new Date().toISOString();
+      console.log(`[Repair] Checked ${result.checked} pins: ${result.repaired.length} repaired, ${result.failed.length} failed`);
+      return result;
+    } finally {
+      this.running = false;
+    }
+  }
+
+  /**
+   * Walks the DAG without touching the network and checks each block is in
+   * the blockstore.
+   */
+  async verifyPin(kubo: KuboManager, cid: string): Promise<PinCheck> {
+    const apiUrl = kubo.getApiUrl();
+    const check: PinCheck = { cid, instance: kubo.getInstanceId(), missingBlocks: [], complete: true };
+
+    const refs = [cid];
+    try {
+      const response = await axios.post(
+        `${apiUrl}/api/v0/refs?arg=${cid}&recursive=true&unique=true&offline=true`,
+        null,
+        { responseType: 'text', timeout: 10 * 60 * 1000 }
+      );
+      parseNdjson(response.data).forEach((entry) => {
+        if (entry.Err) check.complete = false;
+        if (entry.Ref) refs.push(entry.Ref);
+      });
+    } catch {
+      check.complete = false;
+    }
+
+    for (let i = 0; i < refs.length; i += STAT_CONCURRENCY) {
+      const batch = refs.slice(i, i + STAT_CONCURRENCY);
+      const present = await Promise.all(batch.map((ref) =>
+        axios.post(`${apiUrl}/api/v0/block/stat?arg=${ref}&offline=true`, null, { timeout: 10000 })
+          .then(() => true)
+          .catch(() => false)
+      ));
+      batch.forEach((ref, index) => {
+        if (!present[index]) check.missingBlocks.push(ref);
+      });
+    }
+    return check;
+  }
+
+  /**
+   * Fetches the blocks a check found missing and walks the DAG again.
+   */
+  async repairPin(kubo: KuboManager, check: PinCheck): Promise<PinCheck> {
+    const apiUrl = kubo.getApiUrl();
+
+    for (const block of check.missingBlocks) {
+      await axios.post(`${apiUrl}/api/v0/block/get?arg=${block}`, null, {
+        responseType: 'arraybuffer',
+        timeout: FETCH_TIMEOUT_MS,
+      }).catch((error: any) => {
+        console.error(`[Repair] Could not fetch ${block}:`, error.message);
+      });
+    }
+
+    // Blocks below a missing parent only show up once the parent is back;
+    // an online walk fetches whatever is still absent.
+    if (!check.complete) {
+      await axios.post(`${apiUrl}/api/v0/refs?arg=${check.cid}&recursive=true&unique=true`, null, {
+        responseType: 'text',
+        timeout: 30 * 60 * 1000,
+      }).catch((error: any) => {
+        console.error(`[Repair] Online walk of ${check.cid} failed:`, error.message);
+      });
+    }
+
+    return this.verifyPin(kubo, check.cid);
+  }
+}
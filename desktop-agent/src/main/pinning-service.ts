@@ -0,0 +1,322 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import express, { Request, Response, Router } from 'express';
+import axios from 'axios';
+import { ApiAuth } from './auth';
+import { KuboPool } from './kubo-pool';
+
+/**
+ * IPFS Pinning Service API (https://ipfs.github.io/pinning-services-api-spec/)
+ * on top of the agent, so `ipfs pin remote`, ipfs-desktop and CI pipelines
+ * can use the node as a pinning backend:
+ *
+ *   ipfs pin remote service add spk http://127.0.0.1:5111/pinning/v1 <token>
+ *
+ * The access token is the agent API token. Requests are pinned in the
+ * background in the order they arrive and survive agent restarts.
+ */
+
+export const PINNING_SERVICE_PREFIX = '/pinning/v1';
+
+export type PinRequestStatus = 'queued' | 'pinning' | 'pinned' | 'failed';
+
+const STATUSES: PinRequestStatus[] = ['queued', 'pinning', 'pinned', 'failed'];
+const MATCH_MODES = ['exact', 'iexact', 'partial', 'ipartial'];
+const DEFAULT_LIMIT = 10;
+const MAX_LIMIT = 1000;
+const PIN_CONCURRENCY = 2;
+const ORIGIN_CONNECT_TIMEOUT_MS = 10 * 1000;
+const DELEGATES_TTL_MS = 5 * 60 * 1000;
+
+export interface Pin {
+  cid: string;
+  name?: string;
+  origins?: string[];
+  meta?: Record<string, string>;
+}
+
+export interface PinRequest {
+  requestid: string;
+  status: PinRequestStatus;
+  created: string;
+  pin: Pin;
+  info?: Record<string, string>;
+}
+
+export interface PinOperations {
+  pin(cid: string): Promise<unknown>;
+  unpin(cid: string): Promise<void>;
+}
+
+function validatePin(body: any): string | null {
+  if (!body || typeof body.cid !== 'string' || !body.cid) return 'cid is required';
+  if (body.name !== undefined && (typeof body.name !== 'string' || body.name.length > 255)) {
+    return 'name must be a string of at most 255 characters';
+  }
+  if (body.origins !== undefined && (!Array.isArray(body.origins) || body.origins.some((o: unknown) => typeof o !== 'string'))) {
+    return 'origins must be an array of multiaddrs';
+  }
+  if (body.meta !== undefined && (typeof body.meta !== 'object' || Array.isArray(body.meta))) {
+    return 'meta must be an object of strings';
+  }
+  return null;
+}
+
+function nameMatches(name: string | undefined, query: string, mode: string): boolean {
+  if (!name) return false;
+  switch (mode) {
+    case 'iexact': return name.toLowerCase() === query.toLowerCase();
+    case 'partial': return name.includes(query);
+    case 'ipartial': return name.toLowerCase().includes(query.toLowerCase());
+    default: return name === query;
+  }
+}
+
+function sendError(res: Response, status: number, reason: string, details?: string): void {
+  res.status(status).json({ error: { reason, ...(details ? { details } : {}) } });
+}
+
+export class PinningService {
+  private filePath: string;
+  private pool: KuboPool;
+  private ops: PinOperations;
+  private requests: Record<string, PinRequest>;
+  private queue: string[] = [];
+  private active = 0;
+  // Our own multiaddrs; clients connect to these to send us blocks
+  private delegates: string[] = [];
+  private delegatesFetchedAt = 0;
+
+  constructor(dataDir: string, pool: KuboPool, ops: PinOperations) {
+    this.filePath = path.join(dataDir, 'pin-requests.json');
+    this.pool = pool;
+    this.ops = ops;
+    this.requests = this.load();
+
+    // Anything interrupted by the last shutdown starts over
+    Object.values(this.requests)
+      .filter((request) => request.status === 'queued' || request.status === 'pinning')
+      .sort((a, b) => a.created.localeCompare(b.created))
+      .forEach((request) => {
+        request.status = 'queued';
+        this.queue.push(request.requestid);
+      });
+  }
+
+  /**
+   * Starts working through requests left over from the last run. Called
+   * once the daemon is up, since pinning needs it.
+   */
+  resume(): void {
+    this.drain();
+  }
+
+  router(auth: ApiAuth): Router {
+    const router = express.Router();
+
+    // Unlike the agent API, the spec requires the token on reads too
+    router.use((req, res, next) => {
+      if (auth.isAuthorized(req)) {
+        void this.refreshDelegates();
+        return next();
+      }
+      sendError(res, 401, 'UNAUTHORIZED', 'Access token is missing or invalid');
+    });
+
+    router.get('/pins', (req, res) => this.list(req, res));
+    router.post('/pins', (req, res) => {
+      const error = validatePin(req.body);
+      if (error) return sendError(res, 400, 'BAD_REQUEST', error);
+      res.status(202).json(this.withDelegates(this.add(req.body)));
+    });
+    router.get('/pins/:requestid', (req, res) => {
+      const request = this.requests[req.params.requestid];
+      if (!request) return sendError(res, 404, 'NOT_FOUND', 'The specified resource was not found');
+      res.json(this.withDelegates(request));
+    });
+    router.post('/pins/:requestid', async (req, res) => {
+      const existing = this.requests[req.params.requestid];
+      if (!existing) return sendError(res, 404, 'NOT_FOUND', 'The specified resource was not found');
+      const error = validatePin(req.body);
+      if (error) return sendError(res, 400, 'BAD_REQUEST', error);
+
+      // Replace = add the new pin, then drop the old request
+      const replacement = this.add(req.body);
+      await this.remove(existing.requestid, req.body.cid);
+      res.status(202).json(this.withDelegates(replacement));
+    });
+    router.delete('/pins/:requestid', async (req, res) => {
+      if (!this.requests[req.params.requestid]) {
+        return sendError(res, 404, 'NOT_FOUND', 'The specified resource was not found');
+      }
+      try {
+        await this.remove(req.params.requestid);
+        res.sendStatus(202);
+      } catch (error: any) {
+        sendError(res, 500, 'INTERNAL_SERVER_ERROR', error.message);
+      }
+    });
+
+    return router;
+  }
+
+  private list(req: Request, res: Response): void {
+    const cids = req.query.cid ? String(req.query.cid).split(',') : null;
+    const statuses = req.query.status ? String(req.query.status).split(',') : ['pinned'];
+    const match = String(req.query.match || 'exact');
+    const limit = req.query.limit !== undefined ? parseInt(String(req.query.limit), 10) : DEFAULT_LIMIT;
+
+    if (statuses.some((status) => !STATUSES.includes(status as PinRequestStatus))) {
+      return sendError(res, 400, 'BAD_REQUEST', `status must be a list of ${STATUSES.join(', ')}`);
+    }
+    if (!MATCH_MODES.includes(match)) {
+      return sendError(res, 400, 'BAD_REQUEST', `match must be one of ${MATCH_MODES.join(', ')}`);
+    }
+    if (!Number.isInteger(limit) || limit < 1 || limit > MAX_LIMIT) {
+      return sendError(res, 400, 'BAD_REQUEST', `limit must be between 1 and ${MAX_LIMIT}`);
+    }
+
+    let meta: Record<string, string> | null = null;
+    if (req.query.meta) {
+      try {
+        meta = JSON.parse(String(req.query.meta));
+      } catch {
+        return sendError(res, 400, 'BAD_REQUEST', 'meta must be a JSON object');
+      }
+    }
+
+    const before = req.query.before ? Date.parse(String(req.query.before)) : null;
+    const after = req.query.after ? Date.parse(String(req.query.after)) : null;
+    if (Number.isNaN(before) || Number.isNaN(after)) {
+      return sendError(res, 400, 'BAD_REQUEST', 'before and after must be ISO 8601 timestamps');
+    }
+    const name = req.query.name ? String(req.query.name) : null;
+
+    const matches = Object.values(this.requests)
+      .filter((request) => statuses.includes(request.status))
+      .filter((request) => !cids || cids.includes(request.pin.cid))
+      .filter((request) => !name || nameMatches(request.pin.name, name, match))
+      .filter((request) => before === null || Date.parse(request.created) < before)
+      .filter((request) => after === null || Date.parse(request.created) > after)
+      .filter((request) => !meta || Object.entries(meta).every(([key, value]) => request.pin.meta?.[key] === value))
+      .sort((a, b) => b.created.localeCompare(a.created));
+
+    res.json({ count: matches.length, results: matches.slice(0, limit).map((request) => this.withDelegates(request)) });
+  }
+
+  private add(pin: Pin): PinRequest {
+    const request: PinRequest = {
+      requestid: crypto.randomUUID(),
+      status: 'queued',
+      created: new Date().toISOString(),
+      pin: {
+        cid: pin.cid,
+        ...(pin.name ? { name: pin.name } : {}),
+        ...(pin.origins ? { origins: pin.origins } : {}),
+        ...(pin.meta ? { meta: pin.meta } : {}),
+      },
+    };
+    this.requests[request.requestid] = request;
+    this.save();
+
+    this.queue.push(request.requestid);
+    this.drain();
+    return request;
+  }
+
+  /**
+   * Drops a request, unpinning its CID unless another request (or the
+   * replacement, `keepCid`) still wants it.
+   */
+  private async remove(requestid: string, keepCid?: string): Promise<void> {
+    const request = this.requests[requestid];
+    delete this.requests[requestid];
+    this.queue = this.queue.filter((id) => id !== requestid);
+    this.save();
+
+    const cid = request.pin.cid;
+    const stillWanted = cid === keepCid || Object.values(this.requests).some((other) => other.pin.cid === cid);
+    if (request.status === 'pinned' && !stillWanted) {
+      await this.ops.unpin(cid);
+    }
+  }
+
+  private drain(): void {
+    while (this.active < PIN_CONCURRENCY && this.queue.length > 0) {
+      const request = this.requests[this.queue.shift()!];
+      if (!request) continue;
+      this.active++;
+      this.process(request).finally(() => {
+        this.active--;
+        this.drain();
+      });
+    }
+  }
+
+  private async process(request: PinRequest): Promise<void> {
+    request.status = 'pinning';
+    this.save();
+
+    try {
+      // Dialing the origins first lets Bitswap find the content without a DHT lookup
+      await Promise.all((request.pin.origins || []).map((origin) =>
+        axios.post(
+          `${this.pool.getPrimary().getApiUrl()}/api/v0/swarm/connect?arg=${encodeURIComponent(origin)}`,
+          null,
+          { timeout: ORIGIN_CONNECT_TIMEOUT_MS }
+        ).catch(() => undefined)
+      ));
+
+      await this.ops.pin(request.pin.cid);
+      request.status = 'pinned';
+      delete request.info;
+      console.log(`[Pinning] Pinned ${request.pin.cid} (${request.requestid})`);
+    } catch (error: any) {
+      request.status = 'failed';
+      request.info = { status_details: error.message };
+      console.error(`[Pinning] Failed to pin ${request.pin.cid}:`, error.message);
+    }
+
+    if (this.requests[request.requestid]) {
+      this.save();
+      return;
+    }
+
+    // Deleted while it was pinning; undo the pin unless someone else wants it
+    const stillWanted = Object.values(this.requests).some((other) => other.pin.cid === request.pin.cid);
+    if (request.status === 'pinned' && !stillWanted) {
+      await this.ops.unpin(request.pin.cid).catch(() => undefined);
+    }
+  }
+
+  private withDelegates(request: PinRequest): PinRequest & { delegates: string[] } {
+    return { ...request, delegates: this.delegates };
+  }
+
+  private async refreshDelegates(): Promise<void> {
+    if (Date.now() - this.delegatesFetchedAt < DELEGATES_TTL_MS) return;
+    try {
+      const response = await axios.post(`${this.pool.getPrimary().getApiUrl()}/api/v0/id`, null, { timeout: 5000 });
+      this.delegates = (response.data.Addresses || []).filter((addr: string) => !addr.includes('/127.0.0.1/') && !addr.includes('/::1/'));
+      this.delegatesFetchedAt = Date.now();
+    } catch {
+      // Keep the last known addresses
+    }
+  }
+
+  private load(): Record<string, PinRequest> {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Pinning] Failed to read pin requests:', error);
+    }
+    return {};
+  }
+
+  private save(): void {
+    fs.writeFileSync(this.filePath, JSON.stringify(this.requests, null, 2));
+  }
+}
@@ -0,0 +1,622 @@
+import { EventEmitter } from 'events';
+import { WebSocket } from 'ws';
+import { ConfigStore } from './config';
+import { KuboManager } from './kubo';
+import { ChallengeRecord, SpkProof, generateSpkProof, generateShardProof } from './poa';
+import { HeldShard } from './contracts';
+import { ProofCache } from './proof-cache';
+import { showNotification } from './notifications';
+import { SubmissionQueue, PendingSubmission } from './submission-queue';
+import { PoaSigner, ReplayGuard, SigningKeySource, verifyMessage } from './poa-signing';
+import {
+  ChallengeDiagnostics,
+  LatencyBudget,
+  DEFAULT_LATENCY_BUDGET,
+  validateLatencyBudget,
+} from './challenge-diagnostics';
+
+const INITIAL_RECONNECT_MS = 2000;
+const MAX_RECONNECT_MS = 60 * 1000;
+const HEARTBEAT_MS = 30 * 1000;
+const FAILURE_NOTIFY_INTERVAL_MS = 10 * 60 * 1000;
+const REBALANCE_MS = 60 * 1000;
+// A validator that dropped is passed over for this long when picking replacements
+const FAILOVER_COOLDOWN_MS = 5 * 60 * 1000;
+// Failures older than this no longer count against a validator's score
+const SCORE_WINDOW_MS = 60 * 60 * 1000;
+
+export interface PoaSettings {
+  enabled: boolean;
+  // Validator base URLs; the agent dials <url>/validate on each
+  validators: string[];
+  // SPK validators time a proof out after 2s, hence the default total
+  budget: LatencyBudget;
+  // How long an answer that couldn't be sent is retried; 0 gives up at once
+  resubmitWindowMs: number;
+  // Validators kept connected at once, best scored first; 0 connects to all
+  activeValidators: number;
+  // Always one of the active validators while it is up
+  preferredValidator: string | null;
+  // Sign answers so validators can tell them from spoofed or replayed ones
+  signMessages: boolean;
+  signingKey: SigningKeySource;
+  // Validator URL (or REST validatorId) to its base64 Ed25519 key; challenges from these must be signed by it
+  validatorKeys: Record<string, string>;
+  // Refuse challenges from validators without a key above
+  requireSignedChallenges: boolean;
+}
+
+export const DEFAULT_POA_SETTINGS: PoaSettings = {
+  enabled: true,
+  validators: [],
+  budget: DEFAULT_LATENCY_BUDGET,
+  resubmitWindowMs: 30 * 1000,
+  activeValidators: 0,
+  preferredValidator: null,
+  signMessages: true,
+  signingKey: 'libp2p',
+  validatorKeys: {},
+  requireSignedChallenges: false,
+};
+
+export function validatePoaSettings(settings: Partial<PoaSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.enabled !== undefined && typeof settings.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  if (settings.validators !== undefined) {
+    if (!Array.isArray(settings.validators)) {
+      errors.push('validators must be a list of URLs');
+    } else {
+      for (const url of settings.validators) {
+        try {
+          if (!['http:', 'https:', 'ws:', 'wss:'].includes(new URL(url).protocol)) throw new Error();
+        } catch {
+          errors.push(`${url} is not an http(s) or ws(s) URL`);
+        }
+      }
+    }
+  }
+  if (settings.budget !== undefined) {
+    errors.push(...validateLatencyBudget(settings.budget));
+  }
+  if (settings.resubmitWindowMs !== undefined
+    && (!Number.isInteger(settings.resubmitWindowMs) || settings.resubmitWindowMs < 0 || settings.resubmitWindowMs > 5 * 60 * 1000)) {
+    errors.push('resubmitWindowMs must be between 0 and 300000');
+  }
+  if (settings.activeValidators !== undefined
+    && (!Number.isInteger(settings.activeValidators) || settings.activeValidators < 0 || settings.activeValidators > 20)) {
+    errors.push('activeValidators must be between 0 and 20');
+  }
+  if (settings.preferredValidator !== undefined && settings.preferredValidator !== null) {
+    if (typeof settings.preferredValidator !== 'string') {
+      errors.push('preferredValidator must be a validator URL or null');
+    } else if (settings.validators && !settings.validators.includes(settings.preferredValidator)) {
+      errors.push('preferredValidator must be one of the validators');
+    }
+  }
+  for (const field of ['signMessages', 'requireSignedChallenges'] as const) {
+    if (settings[field] !== undefined && typeof settings[field] !== 'boolean') errors.push(`${field} must be a boolean`);
+  }
+  if (settings.signingKey !== undefined && !['libp2p', 'dedicated'].includes(settings.signingKey)) {
+    errors.push('signingKey must be libp2p or dedicated');
+  }
+  if (settings.validatorKeys !== undefined) {
+    if (typeof settings.validatorKeys !== 'object' || settings.validatorKeys === null || Array.isArray(settings.validatorKeys)) {
+      errors.push('validatorKeys must map validators to public keys');
+    } else {
+      for (const [validator, key] of Object.entries(settings.validatorKeys)) {
+        if (typeof key !== 'string' || Buffer.from(key, 'base64').length !== 32) {
+          errors.push(`The key for ${validator} is not a base64 Ed25519 public key`);
+        }
+      }
+    }
+  }
+  return errors;
+}
+
+// What a validator sends, in the SPK PoA wire format
+interface ProofRequest {
+  type: 'RequestProof';
+  Hash: string;
+  CID: string;
+  Status?: string;
+  User?: string;
+  // For a sharded file, which shard to prove; any one held otherwise
+  Shard?: number;
+}
+
+// 'standby' validators are scored but not connected, waiting to take over
+export type ValidatorConnectionState = 'connecting' | 'connected' | 'disconnected' | 'standby';
+
+export interface ValidatorConnectionStatus {
+  url: string;
+  state: ValidatorConnectionState;
+  preferred: boolean;
+  connectedAt: string | null;
+  lastChallengeAt: string | null;
+  answered: number;
+  lastError: string | null;
+  // Smoothed heartbeat round trip; null until one has come back
+  latencyMs: number | null;
+  // Dropped connections and failed dials in the last hour
+  recentFailures: number;
+  // Higher is better; see scoreOf
+  score: number;
+}
+
+export interface PoaResponderOperations {
+  findNode(cid: string): Promise<KuboManager>;
+  proofCache: ProofCache;
+  contractsFor(cid: string): string[];
+  // A shard of the file held for a contract, when the file itself isn't
+  shardFor(cid: string, index?: number): HeldShard | null;
+  // The linked account a CID is stored for, for its notification preferences
+  accountFor(cid: string): string | null;
+  // Deadline an answer about the CID is held to; its replication tier can loosen the latency budget
+  deadlineFor(cid: string): number;
+  // Books the result into earnings, history and events
+  record(record: ChallengeRecord): void;
+  signer: PoaSigner;
+}
+
+/**
+ * Why a challenge from this validator should be refused, or null. Shared
+ * with the HTTP challenge route, where validators are named by validatorId.
+ */
+export function checkChallengeSignature(
+  settings: PoaSettings,
+  validator: string | null,
+  message: Record<string, unknown>,
+  replay: ReplayGuard,
+): string | null {
+  const key = validator ? settings.validatorKeys[validator] : undefined;
+  if (!key) return settings.requireSignedChallenges ? 'No public key is configured for this validator' : null;
+  return verifyMessage(message, key) ?? replay.check(message);
+}
+
+interface ValidatorConnection {
+  status: ValidatorConnectionStatus;
+  socket: WebSocket | null;
+  reconnectMs: number;
+  reconnectTimer: NodeJS.Timeout | null;
+  heartbeat: NodeJS.Timeout | null;
+  alive: boolean;
+  pingSentAt: number | null;
+  failures: number[];
+  // Set when the socket is closed on purpose, so the close isn't a failure
+  closing: boolean;
+}
+
+/**
+ * Reliability first, then latency: each recent failure costs 25 points and
+ * every 20ms of round trip one. A validator not heard from yet scores as
+ * untested rather than bad, so standbys get a chance.
+ */
+function scoreOf(connection: ValidatorConnection): number {
+  const latencyPenalty = connection.status.latencyMs !== null ? Math.min(connection.status.latencyMs, 2000) / 20 : 0;
+  return Math.round(100 - connection.status.recentFailures * 25 - latencyPenalty);
+}
+
+export function toValidateUrl(url: string): string {
+  const parsed = new URL(url);
+  parsed.protocol = parsed.protocol.replace(/^http/, 'ws');
+  if (parsed.pathname === '/' || parsed.pathname === '') parsed.pathname = '/validate';
+  return parsed.toString();
+}
+
+/**
+ * Answers proof-of-access challenges as they arrive. The agent dials out to
+ * each configured validator and keeps the socket open, so it works behind
+ * NAT without an open port; validators push `RequestProof` messages down
+ * it and get the proof back on the same socket. With `activeValidators`
+ * set, only the best scored validators are kept connected and a standby
+ * takes over when one drops.
+ */
+export class PoaResponder extends EventEmitter {
+  private config: ConfigStore;
+  private ops: PoaResponderOperations;
+  private connections = new Map<string, ValidatorConnection>();
+  private diagnostics: ChallengeDiagnostics;
+  private submissions: SubmissionQueue;
+  private lastFailureNotice = 0;
+  private running = false;
+  private rebalanceTimer: NodeJS.Timeout | null = null;
+  private replay = new ReplayGuard();
+
+  constructor(config: ConfigStore, ops: PoaResponderOperations) {
+    super();
+    this.config = config;
+    this.ops = ops;
+    this.diagnostics = new ChallengeDiagnostics(() => this.config.getConfig().poa.budget);
+    this.diagnostics.on('slow-stage', (warning) => this.emit('slow-stage', warning));
+    this.submissions = new SubmissionQueue(config.getDataDir(), (url, message) => this.sendTo(url, message));
+    this.submissions.on('settled', (record: ChallengeRecord) => {
+      this.ops.record(record);
+      if (!record.passed) this.notifyFailure(record.cid, record.error!);
+    });
+    this.submissions.on('changed', (count: number) => this.emit('at-risk', count));
+  }
+
+  getDiagnostics(): ChallengeDiagnostics {
+    return this.diagnostics;
+  }
+
+  start(): void {
+    this.running = true;
+    this.submissions.start();
+    this.reload();
+    // Lets the preferred validator, or a recovered one, back in once its cooldown ends
+    this.rebalanceTimer = setInterval(() => this.reload(), REBALANCE_MS);
+  }
+
+  stop(): void {
+    this.running = false;
+    if (this.rebalanceTimer) clearInterval(this.rebalanceTimer);
+    this.rebalanceTimer = null;
+    for (const url of Array.from(this.connections.keys())) this.disconnect(url);
+    this.submissions.stop();
+  }
+
+  // Answers computed but not yet delivered; their rewards are at risk
+  getPendingSubmissions(): PendingSubmission[] {
+    return this.submissions.getPending();
+  }
+
+  /**
+   * Opens and closes connections to match the settings, and picks which
+   * validators are active when not all of them are.
+   */
+  reload(): void {
+    const { enabled, validators, activeValidators, preferredValidator } = this.config.getConfig().poa;
+    const wanted = this.running && enabled ? validators : [];
+    for (const url of Array.from(this.connections.keys())) {
+      if (!wanted.includes(url)) this.disconnect(url);
+    }
+    for (const url of wanted) {
+      const connection = this.connections.get(url) || this.track(url);
+      connection.status.preferred = url === preferredValidator;
+    }
+
+    const active = activeValidators === 0 || activeValidators >= wanted.length
+      ? new Set(wanted)
+      : this.pickActive(wanted, activeValidators, preferredValidator);
+    for (const url of wanted) {
+      const connection = this.connections.get(url)!;
+      if (active.has(url)) {
+        if (connection.status.state !== 'standby') continue;
+        if (active.size < wanted.length) console.log(`[PoA] Activating validator ${url}`);
+        this.connect(url);
+      } else if (connection.status.state !== 'standby') {
+        console.log(`[PoA] Moving validator ${url} to standby`);
+        this.standby(connection);
+      }
+    }
+  }
+
+  getStatus(): ValidatorConnectionStatus[] {
+    return Array.from(this.connections.values()).map((connection) => {
+      const cutoff = Date.now() - SCORE_WINDOW_MS;
+      connection.failures = connection.failures.filter((at) => at > cutoff);
+      connection.status.recentFailures = connection.failures.length;
+      connection.status.score = scoreOf(connection);
+      return { ...connection.status };
+    });
+  }
+
+  /**
+   * Active validators that are still up stay, so a slightly better score
+   * elsewhere doesn't cause churn. Free slots go to the preferred validator,
+   * then by score, skipping ones that failed recently unless nothing else is
+   * left.
+   */
+  private pickActive(urls: string[], count: number, preferred: string | null): Set<string> {
+    this.getStatus();
+    const coolingDown = (connection: ValidatorConnection) => {
+      const last = connection.failures[connection.failures.length - 1];
+      return last !== undefined && Date.now() - last < FAILOVER_COOLDOWN_MS;
+    };
+    const ranked = urls
+      .map((url) => this.connections.get(url)!)
+      .sort((a, b) => Number(b.status.url === preferred) - Number(a.status.url === preferred) || b.status.score - a.status.score);
+
+    const active: string[] = [];
+    const preferredConnection = ranked.find((connection) => connection.status.url === preferred);
+    if (preferredConnection && !coolingDown(preferredConnection)) active.push(preferredConnection.status.url);
+    for (const connection of ranked) {
+      if (['connected', 'connecting'].includes(connection.status.state) && !active.includes(connection.status.url)) {
+        active.push(connection.status.url);
+      }
+    }
+    for (const skipCoolingDown of [true, false]) {
+      for (const connection of ranked) {
+        if (active.includes(connection.status.url) || (skipCoolingDown && coolingDown(connection))) continue;
+        active.push(connection.status.url);
+      }
+    }
+    return new Set(active.slice(0, count));
+  }
+
+  private track(url: string): ValidatorConnection {
+    const connection: ValidatorConnection = {
+      status: {
+        url,
+        state: 'standby',
+        preferred: false,
+        connectedAt: null,
+        lastChallengeAt: null,
+        answered: 0,
+        lastError: null,
+        latencyMs: null,
+        recentFailures: 0,
+        score: 100,
+      },
+      socket: null,
+      reconnectMs: INITIAL_RECONNECT_MS,
+      reconnectTimer: null,
+      heartbeat: null,
+      alive: false,
+      pingSentAt: null,
+      failures: [],
+      closing: false,
+    };
+    this.connections.set(url, connection);
+    return connection;
+  }
+
+  // Closes the socket but keeps the validator's score for later
+  private standby(connection: ValidatorConnection): void {
+    if (connection.reconnectTimer) clearTimeout(connection.reconnectTimer);
+    connection.reconnectTimer = null;
+    connection.status.state = 'standby';
+    if (connection.socket) {
+      connection.closing = true;
+      connection.socket.close();
+    }
+  }
+
+  private connect(url: string): void {
+    const connection = this.connections.get(url) || this.track(url);
+    connection.status.state = 'connecting';
+    connection.closing = false;
+
+    let socket: WebSocket;
+    try {
+      socket = new WebSocket(toValidateUrl(url), {
+        headers: { 'X-SPK-User': this.config.getConfig().hiveUsername || '' },
+      });
+    } catch (error: any) {
+      connection.status.lastError = error.message;
+      connection.failures.push(Date.now());
+      this.scheduleReconnect(url);
+      return;
+    }
+    connection.socket = socket;
+
+    socket.on('open', () => {
+      console.log(`[PoA] Connected to validator ${url}`);
+      connection.status.state = 'connected';
+      connection.status.connectedAt = new Date().toISOString();
+      connection.status.lastError = null;
+      connection.reconnectMs = INITIAL_RECONNECT_MS;
+      connection.alive = true;
+      const ping = () => {
+        connection.pingSentAt = Date.now();
+        socket.ping();
+      };
+      connection.heartbeat = setInterval(() => {
+        // A validator that stopped answering pings is gone even if TCP isn't
+        if (!connection.alive) {
+          connection.status.lastError = 'Stopped answering heartbeats';
+          return socket.terminate();
+        }
+        connection.alive = false;
+        ping();
+      }, HEARTBEAT_MS);
+      // Scores the link right away instead of after the first heartbeat
+      ping();
+      this.emit('status', this.getStatus());
+      this.submissions.flush(url);
+    });
+    socket.on('pong', () => {
+      connection.alive = true;
+      if (connection.pingSentAt === null) return;
+      const rtt = Date.now() - connection.pingSentAt;
+      connection.pingSentAt = null;
+      connection.status.latencyMs = connection.status.latencyMs === null
+        ? rtt
+        : Math.round(connection.status.latencyMs * 0.7 + rtt * 0.3);
+    });
+    socket.on('message', (data) => this.handleMessage(connection, socket, data.toString()));
+    socket.on('error', (error) => {
+      connection.status.lastError = error.message;
+    });
+    socket.on('close', () => {
+      if (connection.heartbeat) clearInterval(connection.heartbeat);
+      connection.heartbeat = null;
+      connection.socket = null;
+      if (!this.connections.has(url)) return;
+      if (connection.closing) {
+        connection.closing = false;
+        this.emit('status', this.getStatus());
+        return;
+      }
+      connection.status.state = 'disconnected';
+      connection.failures.push(Date.now());
+      console.log(`[PoA] Validator ${url} disconnected${connection.status.lastError ? `: ${connection.status.lastError}` : ''}`);
+      // A standby may take its place; if not, keep redialing it
+      this.reload();
+      if (connection.status.state === 'disconnected') this.scheduleReconnect(url);
+      this.emit('status', this.getStatus());
+    });
+  }
+
+  private scheduleReconnect(url: string): void {
+    const connection = this.connections.get(url);
+    if (!connection || connection.reconnectTimer) return;
+    const delay = connection.reconnectMs;
+    connection.reconnectMs = Math.min(MAX_RECONNECT_MS, connection.reconnectMs * 2);
+    connection.reconnectTimer = setTimeout(() => {
+      connection.reconnectTimer = null;
+      if (this.connections.has(url) && connection.status.state !== 'standby') this.connect(url);
+    }, delay);
+  }
+
+  private disconnect(url: string): void {
+    const connection = this.connections.get(url);
+    if (!connection) return;
+    this.connections.delete(url);
+    if (connection.reconnectTimer) clearTimeout(connection.reconnectTimer);
+    if (connection.heartbeat) clearInterval(connection.heartbeat);
+    connection.closing = true;
+    connection.socket?.close();
+  }
+
+  private handleMessage(connection: ValidatorConnection, socket: WebSocket, raw: string): void {
+    let message: ProofRequest;
+    try {
+      message = JSON.parse(raw);
+    } catch {
+      console.warn(`[PoA] Ignoring malformed message from ${connection.status.url}`);
+      return;
+    }
+    if (message.type !== 'RequestProof' || typeof message.Hash !== 'string' || typeof message.CID !== 'string') return;
+
+    const rejection = checkChallengeSignature(this.config.getConfig().poa, connection.status.url, message as unknown as Record<string, unknown>, this.replay);
+    if (rejection) {
+      console.warn(`[PoA] Ignoring challenge from ${connection.status.url}: ${rejection}`);
+      return;
+    }
+
+    const username = this.config.getConfig().hiveUsername;
+    if (username && message.User && message.User !== username) {
+      console.warn(`[PoA] Ignoring challenge for ${message.User}; this node is ${username}`);
+      return;
+    }
+
+    connection.status.lastChallengeAt = new Date().toISOString();
+    this.answer(connection, socket, message, Date.now()).catch((error) => {
+      console.error('[PoA] Failed to answer challenge:', error);
+    });
+  }
+
+  private async answer(connection: ValidatorConnection, socket: WebSocket, request: ProofRequest, receivedAt: number): Promise<void> {
+    const { Hash: salt, CID: cid } = request;
+    const shard = this.ops.contractsFor(cid).length > 0
+      ? null
+      : this.ops.shardFor(cid, Number.isInteger(request.Shard) ? request.Shard : undefined);
+    // What is actually stored here: the file, or the shard of it
+    const target = shard?.cid ?? cid;
+    const deadlineMs = this.ops.deadlineFor(target);
+    const node = await this.ops.findNode(target);
+    const lookupMs = Date.now() - receivedAt;
+
+    let proof: SpkProof | null = null;
+    let error: string | null = null;
+    try {
+      proof = shard
+        ? await generateShardProof(node, shard, salt, { cache: this.ops.proofCache })
+        : await generateSpkProof(node, cid, salt, { cache: this.ops.proofCache });
+    } catch (caught: any) {
+      error = caught.message;
+    }
+
+    const elapsed = Date.now() - receivedAt;
+    const submitStart = Date.now();
+    const answer = {
+      Hash: salt,
+      CID: cid,
+      User: request.User ?? this.config.getConfig().hiveUsername,
+      Status: proof ? 'Success' : 'Fail',
+      proofHash: proof?.proof ?? '',
+      ...(shard ? { Shard: shard.index, ShardCID: shard.cid } : {}),
+      elapsed,
+      ...(proof ? {} : { error }),
+    };
+    const message = this.config.getConfig().poa.signMessages ? this.ops.signer.sign(answer) : answer;
+    let sendError: string | null = null;
+    try {
+      await this.send(socket, message);
+    } catch (caught: any) {
+      sendError = caught.message;
+    }
+    const submitMs = Date.now() - submitStart;
+    connection.status.answered++;
+
+    const totalMs = Date.now() - receivedAt;
+    // A good proof that only failed to send is worth retrying; anything else is final
+    const { resubmitWindowMs } = this.config.getConfig().poa;
+    const retry = sendError !== null && error === null && elapsed <= deadlineMs && resubmitWindowMs > 0;
+    if (sendError !== null && !retry) {
+      error = error ?? `Could not send the proof: ${sendError}`;
+    }
+    if (error === null && !retry && totalMs > deadlineMs) {
+      error = `Proof took ${totalMs}ms, past the ${deadlineMs}ms deadline`;
+    }
+    const passed = error === null;
+
+    this.diagnostics.record({
+      cid,
+      validator: connection.status.url,
+      passed,
+      stages: {
+        lookup: lookupMs,
+        fetch: proof ? proof.refsMs + proof.blockReadMs : 0,
+        hash: proof?.hashMs ?? 0,
+        submit: submitMs,
+      },
+      totalMs,
+    });
+
+    const record: ChallengeRecord = {
+      at: new Date(receivedAt).toISOString(),
+      protocol: 'spk',
+      cid,
+      blockIndex: null,
+      shard: shard ? { index: shard.index, cid: shard.cid } : null,
+      salt,
+      validatorId: connection.status.url,
+      contractIds: this.ops.contractsFor(target),
+      instance: node.getInstanceId(),
+      passed,
+      responseTime: totalMs,
+      proof: proof?.proof ?? null,
+      blockCid: null,
+      blockCount: proof?.blockCount ?? null,
+      blockBytes: proof?.blockBytes ?? null,
+      refsMs: proof?.refsMs ?? null,
+      blockReadMs: proof?.blockReadMs ?? null,
+      error,
+    };
+    if (retry) {
+      this.submissions.enqueue(connection.status.url, message, record, receivedAt + resubmitWindowMs, sendError!);
+      return;
+    }
+    this.ops.record(record);
+
+    if (!passed) this.notifyFailure(cid, error!);
+  }
+
+  // For retries, which go out on whatever socket the validator is on now
+  private sendTo(url: string, message: object): Promise<void> {
+    const socket = this.connections.get(url)?.socket;
+    if (!socket) return Promise.reject(new Error('Validator not connected'));
+    return this.send(socket, message);
+  }
+
+  // Resolves once the answer is handed to the socket, which is what submit time measures
+  private send(socket: WebSocket, message: object): Promise<void> {
+    return new Promise((resolve, reject) => {
+      if (socket.readyState !== WebSocket.OPEN) return reject(new Error('Validator connection closed'));
+      socket.send(JSON.stringify(message), (error) => (error ? reject(error) : resolve()));
+    });
+  }
+
+  // Once per interval, so a run of failures doesn't bury the desktop in toasts
+  private notifyFailure(cid: string, error: string): void {
+    if (Date.now() - this.lastFailureNotice < FAILURE_NOTIFY_INTERVAL_MS) return;
+    this.lastFailureNotice = Date.now();
+    showNotification('Challenge failed', `Could not prove ${cid.slice(0, 16)}…: ${error}`,
+      { kind: 'challenges', account: this.ops.accountFor(cid) });
+  }
+}
@@ -0,0 +1,192 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+
+// Messages older than this, or from further in the future, are refused
+export const MAX_MESSAGE_AGE_MS = 60 * 1000;
+
+// DER framing around a raw 32-byte Ed25519 key, so Node's crypto can load one
+const PKCS8_PREFIX = Buffer.from('302e020100300506032b657004220420', 'hex');
+const SPKI_PREFIX = Buffer.from('302a300506032b6570032100', 'hex');
+const BASE58_ALPHABET = '123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz';
+// Identity multihash of a 36-byte protobuf holding a 32-byte Ed25519 key
+const PEER_ID_PREFIX = Buffer.from('002408011220', 'hex');
+
+export type SigningKeySource = 'libp2p' | 'dedicated';
+
+// Fields added to every signed message
+export interface SignatureEnvelope {
+  ts: number;
+  nonce: string;
+  // Base64 raw Ed25519 public key
+  signer: string;
+  // Base64 signature over the canonical form of everything else
+  signature: string;
+}
+
+/**
+ * JSON with object keys sorted at every level and no whitespace, so the
+ * signer and the verifier hash the same bytes whatever order fields were
+ * built in. Undefined fields are dropped, as JSON.stringify does.
+ */
+export function canonicalize(value: unknown): string {
+  if (value === null || typeof value !== 'object') return JSON.stringify(value);
+  if (Array.isArray(value)) return `[${value.map((item) => canonicalize(item ?? null)).join(',')}]`;
+  const entries = Object.entries(value as Record<string, unknown>)
+    .filter(([, item]) => item !== undefined)
+    .sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0));
+  return `{${entries.map(([key, item]) => `${JSON.stringify(key)}:${canonicalize(item)}`).join(',')}}`;
+}
+
+function publicKeyFromRaw(raw: Buffer): crypto.KeyObject {
+  return crypto.createPublicKey({ key: Buffer.concat([SPKI_PREFIX, raw]), format: 'der', type: 'spki' });
+}
+
+/**
+ * The Ed25519 public key inside a libp2p peer ID ("12D3KooW…"), which is an
+ * identity multihash of the protobuf-encoded key. Null for RSA and other
+ * key types, whose IDs are hashes that can't be reversed.
+ */
+export function publicKeyFromPeerId(peerId: string): string | null {
+  let value = BigInt(0);
+  for (const char of peerId) {
+    const digit = BASE58_ALPHABET.indexOf(char);
+    if (digit < 0) return null;
+    value = value * BigInt(58) + BigInt(digit);
+  }
+  const hex = value.toString(16);
+  const bytes = Buffer.from(hex.length % 2 ? `0${hex}` : hex, 'hex');
+  // Leading '1's are zero bytes; an identity multihash starts with one
+  const leadingZeros = peerId.length - peerId.replace(/^1+/, '').length;
+  const decoded = Buffer.concat([Buffer.alloc(leadingZeros), bytes]);
+  if (decoded.length !== PEER_ID_PREFIX.length + 32 || !decoded.subarray(0, PEER_ID_PREFIX.length).equals(PEER_ID_PREFIX)) {
+    return null;
+  }
+  return decoded.subarray(PEER_ID_PREFIX.length).toString('base64');
+}
+
+/**
+ * Checks a signed message against the key it should come from. Returns why
+ * it was refused, or null when the signature is good; freshness and replay
+ * are the ReplayGuard's job.
+ */
+export function verifyMessage(message: Record<string, unknown>, trustedKey: string): string | null {
+  const { signature, ...signed } = message;
+  if (typeof signature !== 'string' || typeof signed.signer !== 'string') return 'Message is not signed';
+  if (signed.signer !== trustedKey) return 'Signed by an unexpected key';
+  try {
+    const ok = crypto.verify(null, Buffer.from(canonicalize(signed)), publicKeyFromRaw(Buffer.from(trustedKey, 'base64')),
+      Buffer.from(signature, 'base64'));
+    return ok ? null : 'Signature does not match';
+  } catch (error: any) {
+    return `Signature could not be checked: ${error.message}`;
+  }
+}
+
+/**
+ * Refuses signed messages that are stale or were seen before. Nonces are
+ * only remembered for the age window; anything older is refused by time.
+ */
+export class ReplayGuard {
+  private seen = new Map<string, number>();
+
+  check(message: Record<string, unknown>): string | null {
+    const { ts, nonce } = message;
+    if (typeof ts !== 'number' || typeof nonce !== 'string') return 'Message has no timestamp or nonce';
+    const now = Date.now();
+    if (Math.abs(now - ts) > MAX_MESSAGE_AGE_MS) return 'Message is too old or from the future; check the clock';
+    for (const [key, at] of this.seen) {
+      if (now - at > MAX_MESSAGE_AGE_MS * 2) this.seen.delete(key);
+    }
+    if (this.seen.has(nonce)) return 'Message was already received';
+    this.seen.set(nonce, now);
+    return null;
+  }
+}
+
+/**
+ * Signs outgoing PoA messages with the node's libp2p identity, so a
+ * validator can check an answer against the peer ID it challenged. Falls
+ * back to a dedicated key in poa-signing-key.pem when the repo key can't be
+ * read (an external daemon, or a non-Ed25519 identity), or when chosen.
+ */
+export class PoaSigner {
+  private getRepoPath: () => string;
+  private dedicatedPath: string;
+  private getSource: () => SigningKeySource;
+  private key: crypto.KeyObject | null = null;
+  private keySource: SigningKeySource | null = null;
+  private loadedFor: SigningKeySource | null = null;
+
+  constructor(dataDir: string, getRepoPath: () => string, getSource: () => SigningKeySource) {
+    this.dedicatedPath = path.join(dataDir, 'poa-signing-key.pem');
+    this.getRepoPath = getRepoPath;
+    this.getSource = getSource;
+  }
+
+  sign<T extends object>(message: T): T & SignatureEnvelope {
+    const key = this.loadKey();
+    const unsigned = {
+      ...message,
+      ts: Date.now(),
+      nonce: crypto.randomBytes(16).toString('hex'),
+      signer: this.getPublicKey(),
+    };
+    const signature = crypto.sign(null, Buffer.from(canonicalize(unsigned)), key).toString('base64');
+    return { ...unsigned, signature };
+  }
+
+  getPublicKey(): string {
+    const der = crypto.createPublicKey(this.loadKey()).export({ format: 'der', type: 'spki' });
+    return der.subarray(der.length - 32).toString('base64');
+  }
+
+  // The key actually in use, which differs from the setting after a fallback
+  getKeySource(): SigningKeySource {
+    this.loadKey();
+    return this.keySource!;
+  }
+
+  private loadKey(): crypto.KeyObject {
+    const wanted = this.getSource();
+    if (this.key && this.loadedFor === wanted) return this.key;
+    this.loadedFor = wanted;
+    if (wanted === 'libp2p') {
+      const key = this.readLibp2pKey();
+      if (key) {
+        this.key = key;
+        this.keySource = 'libp2p';
+        return key;
+      }
+    }
+    this.key = this.readDedicatedKey();
+    this.keySource = 'dedicated';
+    return this.key;
+  }
+
+  private readLibp2pKey(): crypto.KeyObject | null {
+    try {
+      const config = JSON.parse(fs.readFileSync(path.join(this.getRepoPath(), 'config'), 'utf-8'));
+      const raw = Buffer.from(config.Identity?.PrivKey || '', 'base64');
+      // protobuf { Type: Ed25519 (1), Data: 64 bytes of seed + public key }
+      if (raw.length !== 68 || !raw.subarray(0, 4).equals(Buffer.from('08011240', 'hex'))) {
+        console.warn('[PoA] Repo identity is not an Ed25519 key; signing with a dedicated key');
+        return null;
+      }
+      return crypto.createPrivateKey({ key: Buffer.concat([PKCS8_PREFIX, raw.subarray(4, 36)]), format: 'der', type: 'pkcs8' });
+    } catch (error: any) {
+      console.warn(`[PoA] Could not read the repo identity (${error.message}); signing with a dedicated key`);
+      return null;
+    }
+  }
+
+  private readDedicatedKey(): crypto.KeyObject {
+    if (fs.existsSync(this.dedicatedPath)) {
+      return crypto.createPrivateKey(fs.readFileSync(this.dedicatedPath, 'utf-8'));
+    }
+    const { privateKey } = crypto.generateKeyPairSync('ed25519');
+    fs.writeFileSync(this.dedicatedPath, privateKey.export({ format: 'pem', type: 'pkcs8' }), { mode: 0o600 });
+    console.log('[PoA] Created a dedicated PoA signing key');
+    return privateKey;
+  }
+}
@@ -0,0 +1,470 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import axios from 'axios';
+import { KuboManager } from './kubo';
+import { ProofCache } from './proof-cache';
+
+// Validators give up well before this; a slower answer is a failed one anyway
+const STEP_TIMEOUT_MS = 2000;
+// SPK validators check at most this many blocks per challenge
+const SPK_MAX_BLOCKS = 5;
+// Byte-range challenges prove a slice of the file, not a whole download
+export const MAX_RANGE_BYTES = 1024 * 1024;
+
+// libp2p protocol nodes answer POST /challenge on, via `ipfs p2p`
+export const POA_PROTOCOL = '/x/spk-poa/1.0';
+
+// Base earnings per passed challenge
+export const HBD_PER_CHALLENGE = 0.001;
+
+export class BlockIndexError extends Error {
+  constructor(blockIndex: number, blockCount: number) {
+    super(`Block index ${blockIndex} out of range (${blockCount} blocks)`);
+    this.name = 'BlockIndexError';
+  }
+}
+
+export class ByteRangeError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = 'ByteRangeError';
+  }
+}
+
+export interface Proof {
+  proof: string;
+  blockCid: string;
+  blockCount: number;
+  blockBytes: number;
+  // Time to list the DAG's blocks and to read the chosen one
+  refsMs: number;
+  blockReadMs: number;
+}
+
+export interface SpkProof {
+  proof: string;
+  blockCount: number;
+  // Which blocks the salt selected; empty for a single-block CID
+  blockIndexes: number[];
+  blockBytes: number;
+  refsMs: number;
+  blockReadMs: number;
+  hashMs: number;
+}
+
+export interface ShardProof extends SpkProof {
+  shardIndex: number;
+  shardCid: string;
+}
+
+export interface RangeProof {
+  proof: string;
+  fileSize: number;
+  // The leaf blocks the range covers, in file order
+  leafCids: string[];
+  // DAG nodes read to get there, leaves included
+  nodesVisited: number;
+  blockBytes: number;
+  readMs: number;
+  hashMs: number;
+}
+
+export interface ProofOptions {
+  timeoutMs?: number;
+  // Serves the block list of hot CIDs; real challenges only
+  cache?: ProofCache;
+}
+
+export interface ChallengeRecord {
+  at: string;
+  // 'rest' for POST /challenge, 'spk' for RequestProof over a validator connection
+  protocol: 'rest' | 'spk';
+  cid: string;
+  // null for SPK challenges, which pick several blocks from the salt
+  blockIndex: number | null;
+  // Set for byte-range challenges, which have no block index
+  range?: { offset: number; length: number } | null;
+  // The shard a challenge on a sharded file was answered with
+  shard?: { index: number; cid: string } | null;
+  salt: string;
+  validatorId: string | null;
+  // Storage contracts the CID is held for
+  contractIds: string[];
+  instance: string;
+  passed: boolean;
+  responseTime: number;
+  proof: string | null;
+  blockCid: string | null;
+  blockCount: number | null;
+  blockBytes: number | null;
+  refsMs: number | null;
+  blockReadMs: number | null;
+  error: string | null;
+}
+
+// Blocks a challenge can point at: the DAG's child links, in order
+export async function listBlocks(node: KuboManager, cid: string, timeoutMs: number = STEP_TIMEOUT_MS): Promise<string[]> {
+  const response = await axios.post(
+    `${node.getApiUrl()}/api/v0/refs?arg=${cid}`,
+    null,
+    // NDJSON; a single ref would otherwise be parsed as one JSON object
+    { timeout: timeoutMs, responseType: 'text' }
+  );
+
+  return response.data.split('\n')
+    .filter((line: string) => line.trim())
+    .map((line: string) => {
+      try { return JSON.parse(line).Ref; } catch { return null; }
+    })
+    .filter(Boolean);
+}
+
+/**
+ * Computes proof-of-access answers: SHA256(salt + block) for the
+ * blockIndex-th block of the CID's DAG, using the instance holding it.
+ */
+export async function generateProof(
+  node: KuboManager,
+  cid: string,
+  blockIndex: number,
+  salt: string,
+  options: ProofOptions = {}
+): Promise<Proof> {
+  const timeoutMs = options.timeoutMs ?? STEP_TIMEOUT_MS;
+  const refsStart = Date.now();
+  const blocks = options.cache
+    ? await options.cache.listBlocks(node, cid, timeoutMs)
+    : await listBlocks(node, cid, timeoutMs);
+  const refsMs = Date.now() - refsStart;
+
+  if (blockIndex >= blocks.length) {
+    throw new BlockIndexError(blockIndex, blocks.length);
+  }
+  const blockCid = blocks[blockIndex];
+
+  const readStart = Date.now();
+  const blockResponse = await axios.post(
+    `${node.getApiUrl()}/api/v0/block/get?arg=${blockCid}`,
+    null,
+    { timeout: timeoutMs, responseType: 'arraybuffer' }
+  );
+  const blockReadMs = Date.now() - readStart;
+  const block = Buffer.from(blockResponse.data);
+
+  const hash = crypto.createHash('sha256');
+  hash.update(salt);
+  hash.update(block);
+
+  return {
+    proof: hash.digest('hex'),
+    blockCid,
+    blockCount: blocks.length,
+    blockBytes: block.length,
+    refsMs,
+    blockReadMs,
+  };
+}
+
+function sha256Hex(data: Buffer | string): string {
+  return crypto.createHash('sha256').update(data).digest('hex');
+}
+
+/**
+ * Index into `length` blocks derived from a hash, as SPK validators do
+ * (FNV-1a). Short DAGs always start at block 1.
+ */
+export function getIntFromHash(hash: string, length: number): number {
+  if (length <= 7) return 1;
+  let h = 2166136261;
+  for (let i = 0; i < hash.length; i++) {
+    h ^= hash.charCodeAt(i);
+    h = Math.imul(h, 16777619) >>> 0;
+  }
+  return h % length;
+}
+
+async function cat(node: KuboManager, cid: string): Promise<Buffer> {
+  const response = await axios.post(
+    `${node.getApiUrl()}/api/v0/cat?arg=${cid}`,
+    null,
+    { timeout: STEP_TIMEOUT_MS, responseType: 'arraybuffer' }
+  );
+  return Buffer.from(response.data);
+}
+
+/**
+ * Answers an SPK `RequestProof`: the salt picks up to five of the DAG's
+ * blocks, each is hashed with the salt appended, and the proof is the hash
+ * of those hashes. Must match the validators' computation byte for byte.
+ */
+export async function generateSpkProof(node: KuboManager, cid: string, salt: string, options: ProofOptions = {}): Promise<SpkProof> {
+  const refsStart = Date.now();
+  const blocks = options.cache
+    ? await options.cache.listBlocks(node, cid, options.timeoutMs)
+    : await listBlocks(node, cid, options.timeoutMs);
+  const refsMs = Date.now() - refsStart;
+  const readStart = Date.now();
+
+  if (blocks.length === 0) {
+    const content = await cat(node, cid);
+    const blockReadMs = Date.now() - readStart;
+    const hashStart = Date.now();
+    return {
+      proof: sha256Hex(Buffer.concat([content, Buffer.from(salt)])),
+      blockCount: 0,
+      blockIndexes: [],
+      blockBytes: content.length,
+      refsMs,
+      blockReadMs,
+      hashMs: Date.now() - hashStart,
+    };
+  }
+
+  const indexes: number[] = [];
+  let seed = getIntFromHash(salt, blocks.length);
+  let progression = '';
+  for (let i = 0; i < Math.min(SPK_MAX_BLOCKS, blocks.length) && seed < blocks.length; i++) {
+    indexes.push(seed);
+    progression += sha256Hex(`block_${seed}_${salt}`);
+    seed += getIntFromHash(salt + progression, blocks.length);
+  }
+
+  const contents = await Promise.all(indexes.map((index) => cat(node, blocks[index])));
+  const blockReadMs = Date.now() - readStart;
+
+  const hashStart = Date.now();
+  const blockHashes = contents.map((content) => sha256Hex(Buffer.concat([content, Buffer.from(salt)])));
+  const proof = sha256Hex(blockHashes.join(''));
+
+  return {
+    proof,
+    blockCount: blocks.length,
+    blockIndexes: indexes,
+    blockBytes: contents.reduce((total, content) => total + content.length, 0),
+    refsMs,
+    blockReadMs,
+    hashMs: Date.now() - hashStart,
+  };
+}
+
+/**
+ * Answers an SPK challenge on a file this node only holds shards of. The
+ * proof is the usual one, computed over the DAG of the shard rather than
+ * the file, and names the shard so the validator checks it against that
+ * shard's CID in the manifest.
+ */
+export async function generateShardProof(
+  node: KuboManager,
+  shard: { cid: string; index: number },
+  salt: string,
+  options: ProofOptions = {}
+): Promise<ShardProof> {
+  const proof = await generateSpkProof(node, shard.cid, salt, options);
+  return { ...proof, shardIndex: shard.index, shardCid: shard.cid };
+}
+
+interface DagNode {
+  // The block's own bytes for raw leaves, or the UnixFS payload of a dag-pb node
+  data: Buffer;
+  links: string[];
+  // Bytes of file content under each link, from the UnixFS blocksizes
+  blockSizes: number[];
+  fileSize: number;
+}
+
+function readVarint(buffer: Buffer, offset: number): [number, number] {
+  let value = 0;
+  let shift = 0;
+  let position = offset;
+  for (;;) {
+    const byte = buffer[position++];
+    if (byte === undefined) throw new Error('Truncated UnixFS metadata');
+    // Multiplying rather than shifting keeps sizes above 2 GiB intact
+    value += (byte & 0x7f) * 2 ** shift;
+    if ((byte & 0x80) === 0) return [value, position];
+    shift += 7;
+  }
+}
+
+/**
+ * The UnixFS Data message: field 2 is inline file data, 3 the file size,
+ * 4 the content size under each link (packed or not).
+ */
+function parseUnixFs(buffer: Buffer): { data: Buffer; fileSize: number | null; blockSizes: number[] } {
+  let data = Buffer.alloc(0);
+  let fileSize: number | null = null;
+  const blockSizes: number[] = [];
+  let position = 0;
+  while (position < buffer.length) {
+    const [key, afterKey] = readVarint(buffer, position);
+    const field = Math.floor(key / 8);
+    const wireType = key & 7;
+    position = afterKey;
+    if (wireType === 0) {
+      const [value, next] = readVarint(buffer, position);
+      if (field === 3) fileSize = value;
+      if (field === 4) blockSizes.push(value);
+      position = next;
+    } else if (wireType === 2) {
+      const [length, start] = readVarint(buffer, position);
+      const bytes = buffer.subarray(start, start + length);
+      if (field === 2) data = bytes;
+      if (field === 4) {
+        for (let at = 0; at < bytes.length;) {
+          const [value, next] = readVarint(bytes, at);
+          blockSizes.push(value);
+          at = next;
+        }
+      }
+      position = start + length;
+    } else {
+      throw new Error(`Unexpected protobuf wire type ${wireType} in UnixFS metadata`);
+    }
+  }
+  return { data, fileSize, blockSizes };
+}
+
+// dag-json from Kubo: { Data, Links } for dag-pb, { "/": { bytes } } for raw blocks
+async function getDagNode(node: KuboManager, cid: string, timeoutMs: number): Promise<DagNode> {
+  const response = await axios.post(
+    `${node.getApiUrl()}/api/v0/dag/get?arg=${cid}&output-codec=dag-json`,
+    null,
+    { timeout: timeoutMs }
+  );
+  const body = response.data || {};
+  if (!Array.isArray(body.Links)) {
+    const data = Buffer.from(body['/']?.bytes ?? '', 'base64');
+    return { data, links: [], blockSizes: [], fileSize: data.length };
+  }
+  const unixfs = parseUnixFs(Buffer.from(body.Data?.['/']?.bytes ?? '', 'base64'));
+  const links: string[] = body.Links.map((link: any) => link.Hash['/']);
+  if (links.length > 0 && unixfs.blockSizes.length !== links.length) {
+    throw new ByteRangeError(`${cid} is not a UnixFS file`);
+  }
+  const fileSize = unixfs.fileSize ?? unixfs.data.length + unixfs.blockSizes.reduce((total, size) => total + size, 0);
+  return { data: unixfs.data, links, blockSizes: unixfs.blockSizes, fileSize };
+}
+
+/**
+ * Bytes [start, end) of the file under `cid`, reading only the nodes whose
+ * content overlaps the range. Children at the same level are read in
+ * parallel and put back in file order.
+ */
+async function readDagRange(
+  node: KuboManager,
+  cid: string,
+  dagNode: DagNode,
+  start: number,
+  end: number,
+  timeoutMs: number,
+  visit: () => void,
+): Promise<{ chunks: Buffer[]; leaves: string[] }> {
+  visit();
+  const chunks: Buffer[] = [];
+  const leaves: string[] = dagNode.links.length === 0 ? [cid] : [];
+  // A node's inline data comes before the content of its links
+  if (dagNode.data.length > 0 && start < dagNode.data.length) {
+    chunks.push(dagNode.data.subarray(start, Math.min(end, dagNode.data.length)));
+  }
+
+  const reads: Array<Promise<{ chunks: Buffer[]; leaves: string[] }>> = [];
+  let position = dagNode.data.length;
+  for (let i = 0; i < dagNode.links.length && position < end; i++) {
+    const childStart = position;
+    const childEnd = position + dagNode.blockSizes[i];
+    position = childEnd;
+    if (childEnd <= start) continue;
+    const child = dagNode.links[i];
+    reads.push(getDagNode(node, child, timeoutMs).then((childNode) =>
+      readDagRange(node, child, childNode, Math.max(start, childStart) - childStart, Math.min(end, childEnd) - childStart, timeoutMs, visit)));
+  }
+  for (const result of await Promise.all(reads)) {
+    chunks.push(...result.chunks);
+    leaves.push(...result.leaves);
+  }
+  return { chunks, leaves };
+}
+
+/**
+ * Answers a byte-range challenge: SHA256(salt + bytes offset..offset+length
+ * of the file), as the newer SPK spec asks. The DAG is walked from the root
+ * using the UnixFS block sizes, so only the leaves holding the range, and
+ * the nodes above them, are read.
+ */
+export async function generateRangeProof(
+  node: KuboManager,
+  cid: string,
+  offset: number,
+  length: number,
+  salt: string,
+  options: ProofOptions = {}
+): Promise<RangeProof> {
+  if (!Number.isInteger(offset) || offset < 0) throw new ByteRangeError('offset must be a non-negative integer');
+  if (!Number.isInteger(length) || length < 1 || length > MAX_RANGE_BYTES) {
+    throw new ByteRangeError(`length must be between 1 and ${MAX_RANGE_BYTES}`);
+  }
+  const timeoutMs = options.timeoutMs ?? STEP_TIMEOUT_MS;
+  const readStart = Date.now();
+  const root = await getDagNode(node, cid, timeoutMs);
+  if (offset + length > root.fileSize) {
+    throw new ByteRangeError(`Range ${offset}+${length} is past the end of the file (${root.fileSize} bytes)`);
+  }
+
+  let nodesVisited = 0;
+  const { chunks, leaves: leafCids } = await readDagRange(node, cid, root, offset, offset + length, timeoutMs, () => {
+    nodesVisited++;
+  });
+  const readMs = Date.now() - readStart;
+
+  const hashStart = Date.now();
+  const hash = crypto.createHash('sha256');
+  hash.update(salt);
+  for (const chunk of chunks) hash.update(chunk);
+  const blockBytes = chunks.reduce((total, chunk) => total + chunk.length, 0);
+  if (blockBytes !== length) throw new ByteRangeError(`Read ${blockBytes} bytes of the ${length} requested; the DAG's sizes are inconsistent`);
+
+  return {
+    proof: hash.digest('hex'),
+    fileSize: root.fileSize,
+    leafCids,
+    nodesVisited,
+    blockBytes,
+    readMs,
+    hashMs: Date.now() - hashStart,
+  };
+}
+
+/**
+ * Keeps the most recent real challenge, inputs included, in
+ * ~/.spk-ipfs/last-challenge.json so a failed validation can be
+ * reproduced after the fact, across restarts.
+ */
+export class ChallengeHistory {
+  private filePath: string;
+  private last: ChallengeRecord | null = null;
+
+  constructor(dataDir: string) {
+    this.filePath = path.join(dataDir, 'last-challenge.json');
+    try {
+      if (fs.existsSync(this.filePath)) {
+        this.last = JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[PoA] Failed to read last challenge:', error);
+    }
+  }
+
+  getLast(): ChallengeRecord | null {
+    return this.last;
+  }
+
+  record(entry: ChallengeRecord): void {
+    this.last = entry;
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(entry, null, 2));
+    } catch (error) {
+      console.error('[PoA] Failed to save last challenge:', error);
+    }
+  }
+}
@@ -0,0 +1,205 @@
+import { EventEmitter } from 'events';
+import { exec } from 'child_process';
+import { powerMonitor } from 'electron';
+import { KuboPool } from './kubo-pool';
+import { ConfigStore } from './config';
+import { getConfigKey, setConfigKey } from './kubo-config';
+
+const METERED_CHECK_INTERVAL_MS = 5 * 60 * 1000;
+
+export type PowerAction = 'none' | 'lowpower' | 'pause';
+
+export interface PowerPolicy {
+  onBattery: PowerAction;
+  onMetered: PowerAction;
+}
+
+export const DEFAULT_POWER_POLICY: PowerPolicy = {
+  onBattery: 'lowpower',
+  onMetered: 'none',
+};
+
+export type PowerState = 'normal' | 'lowpower' | 'paused';
+
+export interface PowerStatus {
+  onBattery: boolean;
+  metered: boolean | null;
+  state: PowerState;
+  policy: PowerPolicy;
+}
+
+// The parts of Kubo's lowpower profile that matter on a laptop
+const POWER_OVERLAY: Record<string, unknown> = {
+  'Routing.Type': 'dhtclient',
+  'AutoNAT.ServiceMode': 'disabled',
+  'Reprovider.Interval': '0',
+  'Swarm.ConnMgr.LowWater': 20,
+  'Swarm.ConnMgr.HighWater': 40,
+  'Swarm.ConnMgr.GracePeriod': '1m',
+};
+
+// What each overlaid key held before; null when it was unset
+export type PowerOverlayBackup = Record<string, unknown>;
+
+function deleteConfigKey(repoConfig: any, keyPath: string): void {
+  const parts = keyPath.split('.');
+  const last = parts.pop()!;
+  const parent = getConfigKey(repoConfig, parts.join('.'));
+  if (parent && typeof parent === 'object') delete parent[last];
+}
+
+/**
+ * Applies the lowpower overlay as a reversible change so we can switch back
+ * when AC power returns. Returns what the overlay replaced, to be passed back
+ * on the next call; switching off restores exactly those keys and returns null.
+ */
+export function applyPowerSaving(repoConfig: any, enabled: boolean, backup: PowerOverlayBackup | null): PowerOverlayBackup | null {
+  if (enabled) {
+    const replaced: PowerOverlayBackup = {};
+    for (const [key, value] of Object.entries(POWER_OVERLAY)) {
+      const current = getConfigKey(repoConfig, key);
+      // Still our value from an earlier start, so keep what it replaced back then
+      replaced[key] = backup && key in backup && current === value ? backup[key] : current ?? null;
+      setConfigKey(repoConfig, key, value);
+    }
+    return replaced;
+  }
+
+  if (!backup) return null;
+  for (const [key, previous] of Object.entries(backup)) {
+    // Rewritten by another setting since, so there's nothing of ours to undo
+    if (getConfigKey(repoConfig, key) !== POWER_OVERLAY[key]) continue;
+    if (previous === null) {
+      deleteConfigKey(repoConfig, key);
+    } else {
+      setConfigKey(repoConfig, key, previous);
+    }
+  }
+  return null;
+}
+
+const STATE_RANK: Record<PowerState, number> = { normal: 0, lowpower: 1, paused: 2 };
+
+function actionToState(action: PowerAction): PowerState {
+  if (action === 'pause') return 'paused';
+  if (action === 'lowpower') return 'lowpower';
+  return 'normal';
+}
+
+function detectMetered(): Promise<boolean | null> {
+  let command: string | null = null;
+  if (process.platform === 'linux') {
+    command = 'nmcli -t -f GENERAL.METERED dev show';
+  } else if (process.platform === 'win32') {
+    command = 'powershell -NoProfile -Command "[Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime] | Out-Null; '
+      + '[Windows.Networking.Connectivity.NetworkInformation]::GetInternetConnectionProfile().GetConnectionCost().NetworkCostType"';
+  }
+  if (!command) return Promise.resolve(null);
+
+  return new Promise((resolve) => {
+    exec(command!, { timeout: 10000 }, (error, stdout) => {
+      if (error) return resolve(null);
+      if (process.platform === 'linux') {
+        resolve(/GENERAL\.METERED:yes/.test(stdout));
+      } else {
+        const cost = stdout.trim();
+        resolve(cost === 'Fixed' || cost === 'Variable');
+      }
+    });
+  });
+}
+
+/**
+ * Switches the daemon to low-power settings or suspends it entirely while
+ * on battery or a metered connection, according to the user's policy.
+ */
+export class PowerManager extends EventEmitter {
+  private pool: KuboPool;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private metered: boolean | null = null;
+  private state: PowerState = 'normal';
+  private applying: Promise<void> = Promise.resolve();
+  private onPowerSourceChanged = () => this.evaluate();
+
+  constructor(pool: KuboPool, config: ConfigStore) {
+    super();
+    this.pool = pool;
+    this.config = config;
+  }
+
+  start(): void {
+    powerMonitor.on('on-battery', this.onPowerSourceChanged);
+    powerMonitor.on('on-ac', this.onPowerSourceChanged);
+
+    this.timer = setInterval(async () => {
+      this.metered = await detectMetered();
+      this.evaluate();
+    }, METERED_CHECK_INTERVAL_MS);
+
+    void detectMetered().then((metered) => {
+      this.metered = metered;
+      this.evaluate();
+    });
+  }
+
+  stop(): void {
+    powerMonitor.off('on-battery', this.onPowerSourceChanged);
+    powerMonitor.off('on-ac', this.onPowerSourceChanged);
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getStatus(): PowerStatus {
+    return {
+      onBattery: powerMonitor.isOnBatteryPower(),
+      metered: this.metered,
+      state: this.state,
+      policy: this.config.getConfig().powerPolicy,
+    };
+  }
+
+  /**
+   * Re-applies the policy, e.g. after the user changed it in settings.
+   */
+  evaluate(): void {
+    const policy = this.config.getConfig().powerPolicy;
+    const candidates: PowerState[] = ['normal'];
+    if (powerMonitor.isOnBatteryPower()) candidates.push(actionToState(policy.onBattery));
+    if (this.metered) candidates.push(actionToState(policy.onMetered));
+
+    const desired = candidates.reduce((a, b) => (STATE_RANK[b] > STATE_RANK[a] ? b : a));
+    if (desired === this.state) return;
+
+    // Serialize transitions so rapid plug/unplug can't interleave restarts
+    this.applying = this.applying.then(() => this.transition(desired));
+  }
+
+  private async transition(desired: PowerState): Promise<void> {
+    // A daemon we didn't start isn't ours to suspend or reconfigure
+    const instances = this.pool.getInstances().filter((kubo) => !kubo.isExternal());
+    if (desired === this.state || instances.length === 0) return;
+    console.log(`[Power] Switching from ${this.state} to ${desired}`);
+
+    try {
+      await Promise.all(instances.map(async (kubo) => {
+        if (desired === 'paused') {
+          await kubo.suspend();
+          return;
+        }
+        kubo.setPowerSaving(desired === 'lowpower');
+        if (kubo.isSuspended()) {
+          await kubo.resume();
+        } else if (kubo.isRunning()) {
+          await kubo.restart();
+        }
+      }));
+      this.state = desired;
+      this.emit('power-state-changed', this.getStatus());
+    } catch (error: any) {
+      console.error('[Power] Failed to switch power state:', error.message);
+    }
+  }
+}
@@ -0,0 +1,159 @@
+import * as fs from 'fs';
+import * as net from 'net';
+import * as path from 'path';
+import axios from 'axios';
+import { KuboSettings } from './settings';
+
+// Below this much free space the daemon can't even write its datastore
+export const MIN_FREE_BYTES = 1024 * 1024 * 1024;
+
+export type PreflightCheck = 'ports' | 'repo-lock' | 'disk-space' | 'running-daemon';
+
+export interface PreflightIssue {
+  check: PreflightCheck;
+  severity: 'error' | 'warning';
+  message: string;
+  // What the user can do about it
+  fix: string;
+}
+
+export interface PreflightResult {
+  ok: boolean;
+  issues: PreflightIssue[];
+}
+
+export class PreflightError extends Error {
+  issues: PreflightIssue[];
+
+  constructor(issues: PreflightIssue[]) {
+    super(issues.filter((issue) => issue.severity === 'error').map((issue) => issue.message).join('; '));
+    this.name = 'PreflightError';
+    this.issues = issues;
+  }
+}
+
+function isPortFree(port: number): Promise<boolean> {
+  return new Promise((resolve) => {
+    const server = net.createServer();
+    server.once('error', () => resolve(false));
+    server.once('listening', () => server.close(() => resolve(true)));
+    server.listen(port, '0.0.0.0');
+  });
+}
+
+async function isIpfsApi(apiUrl: string): Promise<boolean> {
+  try {
+    const response = await axios.post(`${apiUrl}/api/v0/id`, null, { timeout: 2000 });
+    return Boolean(response.data?.ID);
+  } catch {
+    return false;
+  }
+}
+
+// Kubo writes the API multiaddr of the daemon holding the repo to <repo>/api
+function readRepoApiUrl(repoPath: string): string | null {
+  try {
+    const multiaddr = fs.readFileSync(path.join(repoPath, 'api'), 'utf-8').trim();
+    const match = multiaddr.match(/^\/ip[46]\/([^/]+)\/tcp\/(\d+)/);
+    if (!match) return null;
+    const host = match[1] === '0.0.0.0' ? '127.0.0.1' : match[1];
+    return `http://${host.includes(':') ? `[${host}]` : host}:${match[2]}`;
+  } catch {
+    return null;
+  }
+}
+
+export async function freeBytes(dir: string): Promise<number | null> {
+  // The repo may not exist yet on first run; check the nearest parent
+  let target = dir;
+  while (!fs.existsSync(target) && path.dirname(target) !== target) {
+    target = path.dirname(target);
+  }
+  try {
+    const stats = await fs.promises.statfs(target);
+    return stats.bavail * stats.bsize;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Checks the things that most often make `ipfs daemon` fail to start, so the
+ * user gets an explanation instead of a startup timeout. A stale repo.lock
+ * left by a crashed daemon is removed here since it is safe to do so.
+ */
+export async function runPreflight(repoPath: string, settings: KuboSettings): Promise<PreflightResult> {
+  const issues: PreflightIssue[] = [];
+
+  const repoApiUrl = readRepoApiUrl(repoPath);
+  const lockPath = path.join(repoPath, 'repo.lock');
+  if (repoApiUrl && await isIpfsApi(repoApiUrl)) {
+    issues.push({
+      check: 'running-daemon',
+      severity: 'error',
+      message: `Another IPFS daemon is already using ${repoPath} (API at ${repoApiUrl})`,
+      fix: 'Stop the other daemon (e.g. `ipfs shutdown` or quit IPFS Desktop), or switch to external node mode to use it.',
+    });
+  } else if (fs.existsSync(lockPath)) {
+    try {
+      fs.unlinkSync(lockPath);
+      console.log('[Preflight] Removed stale repo.lock');
+    } catch (error: any) {
+      issues.push({
+        check: 'repo-lock',
+        severity: 'error',
+        message: `The repo is locked and the lock could not be removed: ${error.message}`,
+        fix: `Make sure no other ipfs process is running, then delete ${lockPath}.`,
+      });
+    }
+  }
+
+  const ports: Array<[string, number]> = [
+    ['API', settings.apiPort],
+    ['gateway', settings.gatewayPort],
+    ['swarm', settings.swarmPort],
+  ];
+  for (const [name, port] of ports) {
+    // A daemon already found holding our repo explains any busy ports
+    if (issues.some((issue) => issue.check === 'running-daemon')) break;
+    if (await isPortFree(port)) continue;
+
+    if (name === 'API' && await isIpfsApi(`http://127.0.0.1:${port}`)) {
+      issues.push({
+        check: 'running-daemon',
+        severity: 'error',
+        message: `An IPFS daemon is already listening on port ${port}`,
+        fix: 'Stop the other daemon, or switch to external node mode to use it instead.',
+      });
+    } else {
+      issues.push({
+        check: 'ports',
+        severity: 'error',
+        message: `The ${name} port ${port} is already in use by another program`,
+        fix: `Close the program using port ${port} or choose a different ${name} port in settings.`,
+      });
+    }
+  }
+
+  const free = await freeBytes(repoPath);
+  if (free !== null) {
+    const quota = settings.storageMaxGb * 1024 * 1024 * 1024;
+    if (free < MIN_FREE_BYTES) {
+      issues.push({
+        check: 'disk-space',
+        severity: 'error',
+        message: `Only ${(free / 1024 / 1024).toFixed(0)} MB free on the repo drive`,
+        fix: 'Free up disk space or move the repo to another drive.',
+      });
+    } else if (free < quota) {
+      issues.push({
+        check: 'disk-space',
+        severity: 'warning',
+        message: `The drive has ${(free / 1024 / 1024 / 1024).toFixed(1)} GB free, less than the ${settings.storageMaxGb} GB quota`,
+        fix: 'Lower the storage quota or free up disk space so contracts are not accepted beyond what fits.',
+      });
+    }
+  }
+
+  return { ok: !issues.some((issue) => issue.severity === 'error'), issues };
+}
@@ -0,0 +1,182 @@
+import { Readable } from 'stream';
+import axios from 'axios';
+import { KuboPool } from './kubo-pool';
+import { ApiError } from './api-errors';
+
+// Enough for two MPEG-TS packets
+const SNIFF_BYTES = 512;
+// Types are remembered so a seek into the middle of a file is served like its start
+const TYPE_CACHE_SIZE = 500;
+const GENERIC_TYPES = ['', 'application/octet-stream', 'text/plain'];
+// Headers of the gateway response passed on to the client
+const FORWARDED_HEADERS = ['content-length', 'content-range', 'accept-ranges', 'etag', 'last-modified', 'cache-control'];
+
+// Magic numbers of the media the dashboard previews; Kubo's own detection misses several
+const SIGNATURES: Array<{ type: string; offset: number; bytes: number[] }> = [
+  { type: 'image/png', offset: 0, bytes: [0x89, 0x50, 0x4e, 0x47] },
+  { type: 'image/jpeg', offset: 0, bytes: [0xff, 0xd8, 0xff] },
+  { type: 'image/gif', offset: 0, bytes: [0x47, 0x49, 0x46, 0x38] },
+  { type: 'application/pdf', offset: 0, bytes: [0x25, 0x50, 0x44, 0x46] },
+  { type: 'video/webm', offset: 0, bytes: [0x1a, 0x45, 0xdf, 0xa3] },
+  { type: 'audio/ogg', offset: 0, bytes: [0x4f, 0x67, 0x67, 0x53] },
+  { type: 'audio/flac', offset: 0, bytes: [0x66, 0x4c, 0x61, 0x43] },
+  { type: 'audio/mpeg', offset: 0, bytes: [0x49, 0x44, 0x33] },
+  { type: 'video/mp2t', offset: 0, bytes: [0x47] },
+  { type: 'video/quicktime', offset: 4, bytes: [0x66, 0x74, 0x79, 0x70, 0x71, 0x74] },
+  { type: 'video/mp4', offset: 4, bytes: [0x66, 0x74, 0x79, 0x70] },
+];
+
+export function sniffContentType(head: Buffer): string | null {
+  if (head.length >= 12 && head.toString('ascii', 0, 4) === 'RIFF') {
+    const format = head.toString('ascii', 8, 12);
+    if (format === 'WEBP') return 'image/webp';
+    if (format === 'WAVE') return 'audio/wav';
+    if (format === 'AVI ') return 'video/x-msvideo';
+  }
+  const utf8 = head.toString('utf8').trimStart();
+  if (utf8.startsWith('#EXTM3U')) return 'application/vnd.apple.mpegurl';
+  for (const { type, offset, bytes } of SIGNATURES) {
+    // A lone sync byte is too weak a signal for MPEG-TS without the next packet
+    if (type === 'video/mp2t' && (head.length < 189 || head[188] !== 0x47)) continue;
+    if (head.length < offset + bytes.length) continue;
+    if (bytes.every((byte, i) => head[offset + i] === byte)) return type;
+  }
+  return null;
+}
+
+export interface PreviewResponse {
+  status: number;
+  headers: Record<string, string>;
+  stream: Readable;
+}
+
+/**
+ * Streams stored content from the local gateway through the agent API,
+ * so the dashboard can play videos without the gateway port being
+ * reachable. Only blocks already on this node are served: the gateway is
+ * asked with `Cache-Control: only-if-cached` and never fetches from the
+ * network on a preview's behalf. Range requests go through unchanged, and
+ * a generic content type is replaced by one sniffed from the file's first
+ * bytes. Everything is sent with `nosniff` and a sandbox CSP, so stored
+ * HTML or SVG can't run script on the API's origin.
+ */
+export class GatewayPreview {
+  private pool: KuboPool;
+  private types = new Map<string, string>();
+
+  constructor(pool: KuboPool) {
+    this.pool = pool;
+  }
+
+  async open(cid: string, subpath: string, range: string | undefined): Promise<PreviewResponse> {
+    if (!/^[a-zA-Z0-9]+$/.test(cid)) throw new ApiError('INVALID_REQUEST', 'Not a CID');
+    const segments = subpath.split('/').filter((segment) => segment.length > 0);
+    if (segments.some((segment) => segment === '.' || segment === '..')) {
+      throw new ApiError('INVALID_REQUEST', 'path may not contain . or .. segments');
+    }
+    const holder = (await this.pool.findHolder(cid)) || this.pool.getPrimary();
+    const url = `${holder.getGatewayUrl()}/ipfs/${cid}${segments.map((segment) => `/${encodeURIComponent(segment)}`).join('')}`;
+
+    const response = await this.fetch(url, range);
+    const status = response.status;
+    if (status === 412 || status === 404) {
+      response.data.destroy();
+      throw new ApiError('NOT_FOUND', `${cid}${subpath ? `/${segments.join('/')}` : ''} is not stored on this node`);
+    }
+    if (status !== 200 && status !== 206 && status !== 416) {
+      response.data.destroy();
+      throw new ApiError('IPFS_ERROR', `Gateway answered ${status}`);
+    }
+
+    const headers: Record<string, string> = {
+      'X-Content-Type-Options': 'nosniff',
+      'Content-Security-Policy': 'sandbox',
+    };
+    for (const name of FORWARDED_HEADERS) {
+      const value = response.headers[name];
+      if (value !== undefined) headers[name] = String(value);
+    }
+    if (status === 416) return { status, headers, stream: response.data };
+
+    const reported = String(response.headers['content-type'] || '').split(';')[0].trim().toLowerCase();
+    let type = GENERIC_TYPES.includes(reported) ? this.types.get(url) ?? null : String(response.headers['content-type']);
+    let stream: Readable = response.data;
+    if (!type) {
+      const starts = status === 200 || /^bytes 0-/.test(String(response.headers['content-range'] || ''));
+      if (starts) {
+        const { head, rest } = await peek(response.data, SNIFF_BYTES);
+        type = sniffContentType(head);
+        stream = rest;
+      } else {
+        type = await this.sniffStart(url);
+      }
+      if (type) this.remember(url, type);
+    }
+    headers['content-type'] = type || reported || 'application/octet-stream';
+    return { status, headers, stream };
+  }
+
+  private async fetch(url: string, range: string | undefined) {
+    try {
+      return await axios.get<Readable>(url, {
+        responseType: 'stream',
+        headers: { 'Cache-Control': 'only-if-cached', ...(range ? { Range: range } : {}) },
+        validateStatus: () => true,
+        maxRedirects: 3,
+        timeout: 30000,
+      });
+    } catch (error: any) {
+      throw new ApiError('DAEMON_UNAVAILABLE', `Local gateway unreachable: ${error.message}`);
+    }
+  }
+
+  // A seek before the start was ever requested
+  private async sniffStart(url: string): Promise<string | null> {
+    const response = await this.fetch(url, `bytes=0-${SNIFF_BYTES - 1}`);
+    const { head, rest } = await peek(response.data, SNIFF_BYTES);
+    rest.destroy();
+    return response.status === 200 || response.status === 206 ? sniffContentType(head) : null;
+  }
+
+  private remember(key: string, type: string): void {
+    this.types.delete(key);
+    this.types.set(key, type);
+    if (this.types.size > TYPE_CACHE_SIZE) this.types.delete(this.types.keys().next().value as string);
+  }
+}
+
+/**
+ * Reads up to `bytes` from the front of a stream, returning them and a
+ * stream that yields everything again from the start.
+ */
+async function peek(source: Readable, bytes: number): Promise<{ head: Buffer; rest: Readable }> {
+  const chunks: Buffer[] = [];
+  let length = 0;
+  const iterator = source[Symbol.asyncIterator]();
+  let ended = false;
+  while (length < bytes) {
+    const next = await iterator.next();
+    if (next.done) {
+      ended = true;
+      break;
+    }
+    const chunk = Buffer.from(next.value);
+    chunks.push(chunk);
+    length += chunk.length;
+  }
+  const head = Buffer.concat(chunks);
+  async function* replay() {
+    if (head.length > 0) yield head;
+    if (ended) return;
+    for (;;) {
+      const next = await iterator.next();
+      if (next.done) return;
+      yield next.value;
+    }
+  }
+  const rest = Readable.from(replay(), { objectMode: false });
+  rest.on('close', () => {
+    if (!rest.readableEnded) source.destroy();
+  });
+  return { head, rest };
+}
@@ -0,0 +1,178 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { HiveAccount } from './hive';
+
+const COINGECKO_URL = 'https://api.coingecko.com/api/v3/simple/price';
+// Past this, a cached quote is still shown but flagged as old
+const STALE_MS = 24 * 60 * 60 * 1000;
+
+export const SUPPORTED_CURRENCIES = ['usd', 'eur', 'gbp', 'jpy', 'cad', 'aud', 'chf', 'cny', 'inr', 'krw', 'brl', 'php'] as const;
+export type FiatCurrency = typeof SUPPORTED_CURRENCIES[number];
+
+export interface PriceFeedSettings {
+  currency: FiatCurrency;
+  refreshMinutes: number;
+}
+
+export const DEFAULT_PRICE_FEED_SETTINGS: PriceFeedSettings = {
+  currency: 'usd',
+  refreshMinutes: 15,
+};
+
+export function validatePriceFeedSettings(settings: Partial<PriceFeedSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.currency !== undefined && !SUPPORTED_CURRENCIES.includes(settings.currency)) {
+    errors.push(`currency must be one of: ${SUPPORTED_CURRENCIES.join(', ')}`);
+  }
+  if (settings.refreshMinutes !== undefined
+    && (!Number.isInteger(settings.refreshMinutes) || settings.refreshMinutes < 1 || settings.refreshMinutes > 24 * 60)) {
+    errors.push('refreshMinutes must be between 1 and 1440');
+  }
+  return errors;
+}
+
+export interface PriceQuote {
+  currency: FiatCurrency;
+  // Fiat per token
+  hbd: number;
+  hive: number;
+  source: 'coingecko' | 'hive-market';
+  fetchedAt: string;
+}
+
+export interface PriceStatus {
+  quote: PriceQuote | null;
+  stale: boolean;
+  lastError: string | null;
+}
+
+/**
+ * HBD and HIVE prices in the chosen currency, so earnings can be shown as
+ * "0.0123 HBD (~$0.012)". CoinGecko is asked first. If it is down or
+ * rate-limited, the Hive internal market gives HIVE in HBD, which is
+ * converted with the last known HBD price, or the $1 peg for USD. Quotes are
+ * kept in price-cache.json, so figures still show after a restart offline.
+ */
+export class PriceFeed {
+  private filePath: string;
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private quotes: Partial<Record<FiatCurrency, PriceQuote>>;
+  private timer: NodeJS.Timeout | null = null;
+  private lastError: string | null = null;
+
+  constructor(dataDir: string, config: ConfigStore, account: HiveAccount) {
+    this.filePath = path.join(dataDir, 'price-cache.json');
+    this.config = config;
+    this.account = account;
+    this.quotes = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.refresh().catch(() => undefined);
+    const { refreshMinutes } = this.config.getConfig().priceFeed;
+    this.timer = setInterval(() => this.refresh().catch(() => undefined), refreshMinutes * 60 * 1000);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  // Picks up a new refresh interval
+  restart(): void {
+    this.stop();
+    this.start();
+  }
+
+  getStatus(): PriceStatus {
+    const quote = this.quotes[this.config.getConfig().priceFeed.currency] ?? null;
+    return {
+      quote,
+      stale: !!quote && Date.now() - new Date(quote.fetchedAt).getTime() > STALE_MS,
+      lastError: this.lastError,
+    };
+  }
+
+  async refresh(): Promise<PriceStatus> {
+    const { currency } = this.config.getConfig().priceFeed;
+    try {
+      this.quotes[currency] = await this.fromCoinGecko(currency).catch(async (error) => {
+        console.warn(`[Prices] CoinGecko unavailable (${error.message}); using the Hive internal market`);
+        return this.fromHiveMarket(currency);
+      });
+      this.lastError = null;
+      this.save();
+    } catch (error: any) {
+      this.lastError = error.message;
+      console.error('[Prices] Price refresh failed:', error.message);
+    }
+    return this.getStatus();
+  }
+
+  // Null without any quote yet for the chosen currency
+  toFiat(amount: number, token: 'HBD' | 'HIVE' = 'HBD'): number | null {
+    const { quote } = this.getStatus();
+    if (!quote) return null;
+    return amount * (token === 'HBD' ? quote.hbd : quote.hive);
+  }
+
+  /**
+   * "0.0123 HBD (~$0.012)"; just the token amount when no price is known.
+   * Small values keep two significant digits so they don't round to zero.
+   */
+  format(amount: number, token: 'HBD' | 'HIVE' = 'HBD'): string {
+    const base = `${amount.toFixed(amount !== 0 && Math.abs(amount) < 1 ? 4 : 3)} ${token}`;
+    const fiat = this.toFiat(amount, token);
+    if (fiat === null) return base;
+    const formatter = new Intl.NumberFormat(undefined, {
+      style: 'currency',
+      currency: this.config.getConfig().priceFeed.currency.toUpperCase(),
+      ...(fiat !== 0 && Math.abs(fiat) < 1 ? { maximumSignificantDigits: 2 } : {}),
+    });
+    return `${base} (~${formatter.format(fiat)})`;
+  }
+
+  private async fromCoinGecko(currency: FiatCurrency): Promise<PriceQuote> {
+    const response = await axios.get(COINGECKO_URL, {
+      params: { ids: 'hive_dollar,hive', vs_currencies: currency },
+      timeout: 10000,
+    });
+    const hbd = Number(response.data?.hive_dollar?.[currency]);
+    const hive = Number(response.data?.hive?.[currency]);
+    if (!(hbd > 0) || !(hive > 0)) throw new Error('CoinGecko returned no price');
+    return { currency, hbd, hive, source: 'coingecko', fetchedAt: new Date().toISOString() };
+  }
+
+  private async fromHiveMarket(currency: FiatCurrency): Promise<PriceQuote> {
+    const ticker = await this.account.getClient().call('condenser_api', 'get_ticker', []);
+    // HBD per HIVE at the last fill
+    const hiveInHbd = Number(ticker?.latest);
+    if (!(hiveInHbd > 0)) throw new Error('The Hive internal market returned no price');
+    const hbd = this.quotes[currency]?.hbd ?? (currency === 'usd' ? 1 : null);
+    if (hbd === null) throw new Error(`No HBD price in ${currency.toUpperCase()} is known yet`);
+    return { currency, hbd, hive: hiveInHbd * hbd, source: 'hive-market', fetchedAt: new Date().toISOString() };
+  }
+
+  private load(): Partial<Record<FiatCurrency, PriceQuote>> {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Prices] Failed to read price cache:', error);
+    }
+    return {};
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.quotes, null, 2));
+    } catch (error) {
+      console.error('[Prices] Failed to save price cache:', error);
+    }
+  }
+}
@@ -0,0 +1,77 @@
+import * as fs from 'fs';
+import * as path from 'path';
+
+/**
+ * A private libp2p network (e.g. an SPK testnet cluster). Only peers holding
+ * the same swarm key can connect, so the public bootstrap list is replaced
+ * with the cluster's own nodes.
+ */
+export interface PrivateNetwork {
+  // Contents of swarm.key: '/key/swarm/psk/1.0.0/\n/base16/\n<64 hex chars>'
+  swarmKey: string;
+  bootstrap: string[];
+}
+
+const SWARM_KEY_PATTERN = /^\/key\/swarm\/psk\/1\.0\.0\/\s*\n\/base16\/\s*\n([0-9a-fA-F]{64})\s*$/;
+
+export function validatePrivateNetwork(network: PrivateNetwork): string[] {
+  const errors: string[] = [];
+  if (!SWARM_KEY_PATTERN.test(network.swarmKey.trim())) {
+    errors.push('swarmKey must be a PSK v1 key: /key/swarm/psk/1.0.0/, /base16/ and 64 hex characters on separate lines');
+  }
+  if (!Array.isArray(network.bootstrap) || network.bootstrap.length === 0) {
+    errors.push('bootstrap must list at least one peer of the private network');
+  } else {
+    network.bootstrap
+      .filter((addr) => !/\/(p2p|ipfs)\/[A-Za-z0-9]+$/.test(addr.trim()))
+      .forEach((addr) => errors.push(`Bootstrap address must end in /p2p/<peer-id>: ${addr}`));
+  }
+  return errors;
+}
+
+export function writeSwarmKey(repoPath: string, swarmKey: string): void {
+  fs.writeFileSync(path.join(repoPath, 'swarm.key'), `${swarmKey.trim()}\n`, { mode: 0o600 });
+}
+
+export function removeSwarmKey(repoPath: string): void {
+  fs.rmSync(path.join(repoPath, 'swarm.key'), { force: true });
+}
+
+/**
+ * Points bootstrap at the cluster and turns off the transports libp2p can't
+ * protect with a pre-shared key (QUIC, WebTransport, WebRTC); Kubo refuses
+ * to start a private network with them enabled.
+ */
+export function applyPrivateNetwork(repoConfig: any, network: PrivateNetwork): void {
+  repoConfig.Bootstrap = network.bootstrap.map((addr) => addr.trim());
+
+  repoConfig.Swarm = {
+    ...repoConfig.Swarm,
+    Transports: {
+      ...repoConfig.Swarm?.Transports,
+      Network: {
+        ...repoConfig.Swarm?.Transports?.Network,
+        QUIC: false,
+        WebTransport: false,
+        WebRTCDirect: false,
+      },
+    },
+  };
+
+  repoConfig.Addresses = {
+    ...repoConfig.Addresses,
+    Swarm: (repoConfig.Addresses?.Swarm || []).filter((addr: string) => !addr.includes('/udp/')),
+  };
+}
+
+/**
+ * Re-enables the transports switched off for the private network. The
+ * bootstrap list is restored separately with `ipfs bootstrap add --default`.
+ */
+export function clearPrivateNetwork(repoConfig: any): void {
+  const network = repoConfig.Swarm?.Transports?.Network;
+  if (!network) return;
+  for (const transport of ['QUIC', 'WebTransport', 'WebRTCDirect']) {
+    if (network[transport] === false) delete network[transport];
+  }
+}
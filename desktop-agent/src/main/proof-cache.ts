@@ -0,0 +1,163 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { KuboManager } from './kubo';
+import { listBlocks } from './poa';
+import { ReplicationTier } from './replication-tiers';
+
+// Challenges a CID needs before its block list is kept
+const HOT_THRESHOLD = 2;
+const MAX_TRACKED = 2000;
+const SAVE_DELAY_MS = 5000;
+// Which entries go first when the cache is full
+const EVICTION_ORDER: ReplicationTier[] = ['cold', 'warm', 'hot'];
+
+interface CacheEntry {
+  challenges: number;
+  lastChallengedAt: string;
+  // The DAG's block CIDs in order; null until the CID is often challenged or in the hot tier, or after invalidation
+  blocks: string[] | null;
+}
+
+export interface ProofCacheStats {
+  tracked: number;
+  cached: number;
+  hits: number;
+  misses: number;
+}
+
+/**
+ * Block lists of frequently challenged CIDs, kept in
+ * ~/.spk-ipfs/proof-cache.json. Listing a DAG walks every node of it, which
+ * on a spinning disk can eat most of the 2s deadline; with the list cached
+ * a challenge only reads the blocks it asks for. Pinned DAGs never change,
+ * so entries only go stale when content is unpinned or collected. The
+ * replication tier of a CID's contracts decides what is kept: hot content
+ * is listed before anyone challenges it, and cold content is the first
+ * evicted.
+ */
+export class ProofCache {
+  private filePath: string;
+  private entries = new Map<string, CacheEntry>();
+  private hits = 0;
+  private misses = 0;
+  private saveTimer: NodeJS.Timeout | null = null;
+  private tierOf: (cid: string) => ReplicationTier;
+
+  constructor(dataDir: string, tierOf: (cid: string) => ReplicationTier) {
+    this.filePath = path.join(dataDir, 'proof-cache.json');
+    this.tierOf = tierOf;
+    try {
+      if (fs.existsSync(this.filePath)) {
+        const saved = JSON.parse(fs.readFileSync(this.filePath, 'utf-8')) as Record<string, CacheEntry>;
+        for (const [cid, entry] of Object.entries(saved)) this.entries.set(cid, entry);
+      }
+    } catch (error) {
+      console.error('[PoA] Failed to read proof cache:', error);
+    }
+  }
+
+  /**
+   * The block list for a challenge, from the cache when the CID is hot.
+   * Counts the challenge either way.
+   */
+  async listBlocks(node: KuboManager, cid: string, timeoutMs?: number): Promise<string[]> {
+    const entry = this.entries.get(cid) || { challenges: 0, lastChallengedAt: '', blocks: null };
+    entry.challenges++;
+    entry.lastChallengedAt = new Date().toISOString();
+    this.entries.delete(cid);
+    this.entries.set(cid, entry);
+    this.evict();
+
+    if (entry.blocks) {
+      this.hits++;
+      this.scheduleSave();
+      return entry.blocks;
+    }
+    this.misses++;
+    const blocks = await listBlocks(node, cid, timeoutMs);
+    if (entry.challenges >= HOT_THRESHOLD || this.tierOf(cid) === 'hot') entry.blocks = blocks;
+    this.scheduleSave();
+    return blocks;
+  }
+
+  invalidate(cid: string): void {
+    const entry = this.entries.get(cid);
+    if (!entry?.blocks) return;
+    entry.blocks = null;
+    this.scheduleSave();
+  }
+
+  // After GC; challenge counts are kept so precompute() knows what to rebuild
+  clear(): void {
+    for (const entry of this.entries.values()) entry.blocks = null;
+    this.scheduleSave();
+  }
+
+  /**
+   * Rebuilds the block lists of often challenged CIDs that lost them, and
+   * builds them for hot-tier CIDs not yet challenged, off the challenge
+   * path. CIDs that can no longer be listed are gone from the node and are
+   * forgotten.
+   */
+  async precompute(findNode: (cid: string) => Promise<KuboManager>, hotCids: string[] = []): Promise<number> {
+    const hot = new Set(hotCids);
+    for (const cid of hot) {
+      if (!this.entries.has(cid)) this.entries.set(cid, { challenges: 0, lastChallengedAt: '', blocks: null });
+    }
+    this.evict();
+    let built = 0;
+    for (const [cid, entry] of Array.from(this.entries)) {
+      if (entry.blocks || (entry.challenges < HOT_THRESHOLD && !hot.has(cid))) continue;
+      try {
+        const node = await findNode(cid);
+        // No deadline here; nobody is waiting on the answer
+        entry.blocks = await listBlocks(node, cid, 60 * 1000);
+        built++;
+      } catch {
+        this.entries.delete(cid);
+      }
+    }
+    if (built > 0) console.log(`[PoA] Precomputed block lists for ${built} CIDs`);
+    this.scheduleSave();
+    return built;
+  }
+
+  getStats(): ProofCacheStats {
+    let cached = 0;
+    for (const entry of this.entries.values()) if (entry.blocks) cached++;
+    return { tracked: this.entries.size, cached, hits: this.hits, misses: this.misses };
+  }
+
+  flush(): void {
+    if (this.saveTimer) clearTimeout(this.saveTimer);
+    this.saveTimer = null;
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(Object.fromEntries(this.entries)));
+    } catch (error) {
+      console.error('[PoA] Failed to save proof cache:', error);
+    }
+  }
+
+  // Entries are kept in challenge order, so within a tier the first one is the least recent
+  private evict(): void {
+    while (this.entries.size > MAX_TRACKED) {
+      let victim: string | null = null;
+      for (const tier of EVICTION_ORDER) {
+        for (const cid of this.entries.keys()) {
+          if (this.tierOf(cid) === tier) {
+            victim = cid;
+            break;
+          }
+        }
+        if (victim) break;
+      }
+      this.entries.delete(victim ?? (this.entries.keys().next().value as string));
+    }
+  }
+
+  // Challenges come in bursts; one write covers a burst
+  private scheduleSave(): void {
+    if (this.saveTimer) return;
+    this.saveTimer = setTimeout(() => this.flush(), SAVE_DELAY_MS);
+  }
+}
@@ -0,0 +1,160 @@
+import { Request } from 'express';
+import { ApiError } from './api-errors';
+
+export const DEFAULT_PAGE_SIZE = 100;
+export const MAX_PAGE_SIZE = 1000;
+
+type SortValue = string | number | null;
+type JsonSchema = Record<string, unknown>;
+
+/**
+ * What a list endpoint can be sorted and filtered by. Filters are read from
+ * `filter[name]=value`, or from a plain `name=value` as older clients send.
+ */
+export interface ListSpec<T> {
+  sortFields: Record<string, (item: T) => SortValue>;
+  // A field name, prefixed with '-' for descending
+  defaultSort: string;
+  filters?: Record<string, (item: T, value: string) => boolean>;
+  // Unique per item; breaks sort ties so a cursor always points at one place
+  key: (item: T) => string;
+}
+
+export interface ListQuery {
+  limit: number;
+  offset: number;
+  cursor: string | null;
+  sort: string;
+  filters: Record<string, string>;
+}
+
+export interface Page<T> {
+  items: T[];
+  total: number;
+  offset: number;
+  limit: number;
+  // Pass back as ?cursor= for the next page; null on the last one
+  nextCursor: string | null;
+}
+
+interface CursorPosition {
+  sort: string;
+  value: SortValue;
+  key: string;
+}
+
+function parseSort<T>(sort: string, spec: ListSpec<T>): { field: string; descending: boolean } {
+  const descending = sort.startsWith('-');
+  const field = descending ? sort.slice(1) : sort;
+  if (!spec.sortFields[field]) {
+    throw new ApiError('INVALID_REQUEST', `sort must be one of ${Object.keys(spec.sortFields).join(', ')}, optionally prefixed with -`);
+  }
+  return { field, descending };
+}
+
+function encodeCursor(position: CursorPosition): string {
+  return Buffer.from(JSON.stringify(position)).toString('base64url');
+}
+
+function decodeCursor(cursor: string, sort: string): CursorPosition {
+  let position: CursorPosition;
+  try {
+    position = JSON.parse(Buffer.from(cursor, 'base64url').toString('utf-8'));
+  } catch {
+    throw new ApiError('INVALID_REQUEST', 'cursor is not valid');
+  }
+  if (typeof position?.key !== 'string' || position.sort !== sort) {
+    throw new ApiError('INVALID_REQUEST', 'cursor belongs to a different sort order; start again without it');
+  }
+  return position;
+}
+
+// Nulls sort first ascending, so they end up last in the usual newest-first views
+function compareValues(a: SortValue, b: SortValue): number {
+  if (a === b) return 0;
+  if (a === null) return -1;
+  if (b === null) return 1;
+  return a < b ? -1 : 1;
+}
+
+export function parseListQuery<T>(query: Request['query'], spec: ListSpec<T>): ListQuery {
+  const limit = Math.min(MAX_PAGE_SIZE, Math.max(1, parseInt(String(query.limit || DEFAULT_PAGE_SIZE), 10) || DEFAULT_PAGE_SIZE));
+  const offset = Math.max(0, parseInt(String(query.offset || '0'), 10) || 0);
+  const sort = query.sort ? String(query.sort) : spec.defaultSort;
+  parseSort(sort, spec);
+
+  const nested = query.filter && typeof query.filter === 'object' ? query.filter as Record<string, unknown> : {};
+  const known = Object.keys(spec.filters || {});
+  const unknown = Object.keys(nested).filter((name) => !known.includes(name));
+  if (unknown.length > 0) {
+    throw new ApiError('INVALID_REQUEST', `Unknown filter ${unknown.join(', ')}; filters here are ${known.join(', ') || 'none'}`);
+  }
+
+  const filters: Record<string, string> = {};
+  for (const name of known) {
+    const value = nested[name] ?? query[name];
+    if (typeof value === 'string' && value !== '') filters[name] = value;
+  }
+
+  return { limit, offset, cursor: query.cursor ? String(query.cursor) : null, sort, filters };
+}
+
+/**
+ * Filters, sorts and cuts one page out of `items`. A cursor resumes right
+ * after the last item of the previous page, so pins added or removed in
+ * between don't shift or repeat entries the way an offset would.
+ */
+export function paginate<T>(items: T[], query: ListQuery, spec: ListSpec<T>): Page<T> {
+  const { field, descending } = parseSort(query.sort, spec);
+  const valueOf = spec.sortFields[field];
+  const compare = (a: { value: SortValue; key: string }, b: { value: SortValue; key: string }) => {
+    const order = compareValues(a.value, b.value) || (a.key < b.key ? -1 : a.key > b.key ? 1 : 0);
+    return descending ? -order : order;
+  };
+
+  const matches = items
+    .filter((item) => Object.entries(query.filters).every(([name, value]) => spec.filters![name](item, value)))
+    .map((item) => ({ item, value: valueOf(item), key: spec.key(item) }))
+    .sort(compare);
+
+  let start = query.offset;
+  if (query.cursor) {
+    const position = decodeCursor(query.cursor, query.sort);
+    start = matches.findIndex((entry) => compare(entry, position) > 0);
+    if (start === -1) start = matches.length;
+  }
+
+  const page = matches.slice(start, start + query.limit);
+  const last = page[page.length - 1];
+  return {
+    items: page.map((entry) => entry.item),
+    total: matches.length,
+    offset: start,
+    limit: query.limit,
+    nextCursor: last && start + page.length < matches.length
+      ? encodeCursor({ sort: query.sort, value: last.value, key: last.key })
+      : null,
+  };
+}
+
+// The query parameters of a list endpoint, for its RouteDoc
+export function listQueryDoc<T>(spec: ListSpec<T>, filterDocs: Record<string, JsonSchema> = {}): Record<string, JsonSchema> {
+  const sorts = Object.keys(spec.sortFields).flatMap((field) => [field, `-${field}`]);
+  const filters = Object.fromEntries(Object.keys(spec.filters || {})
+    .map((name) => [`filter[${name}]`, filterDocs[name] || { type: 'string' }]));
+  return {
+    limit: { type: 'integer', minimum: 1, maximum: MAX_PAGE_SIZE, default: DEFAULT_PAGE_SIZE },
+    cursor: { type: 'string', description: 'nextCursor from the previous page' },
+    offset: { type: 'integer', minimum: 0, description: 'Ignored when cursor is set' },
+    sort: { type: 'string', enum: sorts, default: spec.defaultSort },
+    ...filters,
+  };
+}
+
+// Page fields shared by every list response, next to the endpoint's own array
+export const PAGE_RESPONSE_PROPERTIES: Record<string, JsonSchema> = {
+  total: { type: 'integer' },
+  offset: { type: 'integer' },
+  limit: { type: 'integer' },
+  nextCursor: { type: 'string', nullable: true },
+};
@@ -0,0 +1,88 @@
+import { Request, Response, NextFunction } from 'express';
+import { ApiError, sendError } from './api-errors';
+
+export interface RateLimitRule {
+  name: string;
+  // Matched against the path with any /api/vN prefix folded to /api
+  pattern: RegExp;
+  methods: string[] | null;
+  perMinute: number;
+}
+
+/**
+ * First match wins. Pairing is tight because each request pops a dialog;
+ * challenges are generous because several validators may probe at once.
+ */
+export const RATE_LIMIT_RULES: RateLimitRule[] = [
+  { name: 'pair', pattern: /^\/api\/pair$/, methods: ['POST'], perMinute: 5 },
+  { name: 'challenge', pattern: /^\/api\/challenge$/, methods: ['POST'], perMinute: 300 },
+  { name: 'add', pattern: /^\/api\/add(\/|$)/, methods: ['POST', 'PATCH'], perMinute: 600 },
+  { name: 'pins', pattern: /^\/(api\/(pin|unpin|pins)|pinning\/v1\/pins)(\/|$)/, methods: ['POST', 'DELETE'], perMinute: 120 },
+  { name: 'write', pattern: /./, methods: ['POST', 'PUT', 'PATCH', 'DELETE'], perMinute: 120 },
+  { name: 'read', pattern: /./, methods: null, perMinute: 1200 },
+];
+
+const SWEEP_EVERY = 1000;
+
+interface Bucket {
+  tokens: number;
+  updatedAt: number;
+}
+
+function matchRule(method: string, path: string): RateLimitRule | null {
+  const route = path.replace(/^\/api\/v\d+\//, '/api/');
+  return RATE_LIMIT_RULES.find((rule) =>
+    rule.pattern.test(route) && (!rule.methods || rule.methods.includes(method))
+  ) || null;
+}
+
+/**
+ * Token-bucket limiting per client and rule. Every local caller shares
+ * 127.0.0.1, so the browser origin is part of the key; one misbehaving tab
+ * can't starve the validators or the dashboard.
+ */
+export class RateLimiter {
+  private buckets = new Map<string, Bucket>();
+  private requests = 0;
+
+  middleware() {
+    return (req: Request, res: Response, next: NextFunction) => {
+      if (req.method === 'OPTIONS') return next();
+      const rule = matchRule(req.method, req.path);
+      if (!rule) return next();
+
+      const key = `${req.ip}|${req.headers.origin || '-'}|${rule.name}`;
+      const { allowed, remaining, retryAfterSec } = this.take(key, rule.perMinute);
+      res.header('RateLimit-Limit', String(rule.perMinute));
+      res.header('RateLimit-Remaining', String(remaining));
+
+      if (allowed) return next();
+      res.header('Retry-After', String(retryAfterSec));
+      sendError(res, new ApiError('RATE_LIMITED', `Too many ${rule.name} requests, retry in ${retryAfterSec}s`, { retryAfterSec }));
+    };
+  }
+
+  private take(key: string, perMinute: number): { allowed: boolean; remaining: number; retryAfterSec: number } {
+    const now = Date.now();
+    const refillPerMs = perMinute / 60000;
+    const bucket = this.buckets.get(key) || { tokens: perMinute, updatedAt: now };
+
+    bucket.tokens = Math.min(perMinute, bucket.tokens + (now - bucket.updatedAt) * refillPerMs);
+    bucket.updatedAt = now;
+    this.buckets.set(key, bucket);
+    if (++this.requests % SWEEP_EVERY === 0) this.sweep(now);
+
+    if (bucket.tokens < 1) {
+      return { allowed: false, remaining: 0, retryAfterSec: Math.ceil((1 - bucket.tokens) / refillPerMs / 1000) };
+    }
+    bucket.tokens -= 1;
+    return { allowed: true, remaining: Math.floor(bucket.tokens), retryAfterSec: 0 };
+  }
+
+  // Buckets idle for a minute are full again, so they can be forgotten
+  private sweep(now: number): void {
+    for (const [key, bucket] of this.buckets) {
+      if (now - bucket.updatedAt > 60000) this.buckets.delete(key);
+    }
+  }
+}
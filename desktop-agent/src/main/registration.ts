@@ -0,0 +1,157 @@
+import { EventEmitter } from 'events';
+import axios from 'axios';
+import { KuboManager } from './kubo';
+import { ConfigStore, RegistrationState } from './config';
+import { ConnectivityMonitor, extractIp } from './connectivity';
+import { AccountDirectory } from './accounts';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 5 * 60 * 1000;
+// Waits before each retry of a failed registration; the last one repeats
+const RETRY_DELAYS_MS = [60 * 1000, 5 * 60 * 1000, 15 * 60 * 1000];
+
+export interface RegistrationStatus extends RegistrationState {
+  // Why the next check would register, or null when up to date
+  pendingReason: string | null;
+}
+
+/**
+ * Keeps the node's registration with the SPK network current. Validators
+ * pick whom to challenge from it, so a node whose peer ID (repo re-init) or
+ * public IP (ISP reassignment) changed would otherwise stop getting
+ * challenges without any error on our side.
+ */
+export class NodeRegistration extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private connectivity: ConnectivityMonitor;
+  private accounts: AccountDirectory;
+  private timer: NodeJS.Timeout | null = null;
+  private retryTimer: NodeJS.Timeout | null = null;
+  private failures = 0;
+  private checking = false;
+
+  constructor(kubo: KuboManager, config: ConfigStore, connectivity: ConnectivityMonitor, accounts: AccountDirectory) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+    this.connectivity = connectivity;
+    this.accounts = accounts;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.check().catch(() => undefined), CHECK_INTERVAL_MS);
+    this.connectivity.on('connectivity-changed', () => this.check().catch(() => undefined));
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    if (this.retryTimer) clearTimeout(this.retryTimer);
+    this.timer = null;
+    this.retryTimer = null;
+  }
+
+  async getStatus(): Promise<RegistrationStatus> {
+    const current = await this.observe();
+    return { ...this.config.getRegistration(), pendingReason: current ? this.changeReason(current) : null };
+  }
+
+  /**
+   * Registers if the peer ID, Hive account, payout account or public IPs differ from what
+   * was last accepted, or always when forced.
+   */
+  async check(force = false): Promise<RegistrationStatus> {
+    if (this.checking) return this.getStatus();
+    this.checking = true;
+    try {
+      const current = await this.observe();
+      // The network keys nodes by Hive account; there's nothing to register without one
+      if (!current || !this.config.getConfig().hiveUsername) return this.getStatus();
+      const reason = force ? 'Requested' : this.changeReason(current);
+      if (reason) await this.register(current.peerId, current.publicIps, reason);
+      return this.getStatus();
+    } finally {
+      this.checking = false;
+    }
+  }
+
+  // The node as validators would see it now; null while the daemon is down
+  private async observe(): Promise<{ peerId: string; publicIps: string[] } | null> {
+    const peerId = await this.kubo.getPeerId();
+    if (!peerId) return null;
+    const report = this.connectivity.getReport();
+    const publicIps = Array.from(new Set((report?.publicAddrs || [])
+      .map(extractIp)
+      .filter((ip): ip is string => ip !== null)))
+      .sort();
+    return { peerId, publicIps };
+  }
+
+  private changeReason(current: { peerId: string; publicIps: string[] }): string | null {
+    const saved = this.config.getRegistration();
+    const { hiveUsername } = this.config.getConfig();
+    if (!hiveUsername) return null;
+    if (!saved.registeredAt) return 'Not registered yet';
+    if (saved.peerId !== current.peerId) return 'Peer ID changed';
+    if (saved.hiveUsername !== hiveUsername) return 'Hive account changed';
+    if ((saved.payoutAccount ?? null) !== this.accounts.getPayoutAccount()) return 'Payout account changed';
+    // No public address this round says nothing about the old one being gone
+    if (current.publicIps.length > 0 && current.publicIps.join(',') !== saved.publicIps.join(',')) {
+      return 'Public IP changed';
+    }
+    return null;
+  }
+
+  private async register(peerId: string, publicIps: string[], reason: string): Promise<void> {
+    const { spkApiUrl, hiveUsername } = this.config.getConfig();
+    const payoutAccount = this.accounts.getPayoutAccount();
+    const saved = this.config.getRegistration();
+    const report = this.connectivity.getReport();
+    if (this.retryTimer) clearTimeout(this.retryTimer);
+    this.retryTimer = null;
+
+    try {
+      await axios.post(`${spkApiUrl}/api/nodes/register`, {
+        peerId,
+        hiveUsername,
+        // Rewards go here instead of hiveUsername when set
+        payoutAccount,
+        addresses: [...(report?.publicAddrs || []), ...(report?.relayAddrs || [])],
+        // Lets the network move reputation and contracts over to the new ID
+        previousPeerId: saved.peerId && saved.peerId !== peerId ? saved.peerId : null,
+      }, { timeout: 15000 });
+    } catch (error: any) {
+      const message = error.response?.data?.error || error.message;
+      this.config.setRegistration({ ...saved, lastError: message });
+      console.error(`[Registration] ${reason}; registering failed: ${message}`);
+      if (this.failures === 0) {
+        showNotification('Registration failed', `${reason}, but the SPK network could not be updated. Retrying in the background.`);
+      }
+      const delay = RETRY_DELAYS_MS[Math.min(this.failures, RETRY_DELAYS_MS.length - 1)];
+      this.failures++;
+      this.retryTimer = setTimeout(() => {
+        this.retryTimer = null;
+        this.check().catch(() => undefined);
+      }, delay);
+      return;
+    }
+
+    const state: RegistrationState = {
+      peerId,
+      hiveUsername,
+      payoutAccount,
+      publicIps,
+      registeredAt: new Date().toISOString(),
+      lastError: null,
+    };
+    this.config.setRegistration(state);
+    this.failures = 0;
+    console.log(`[Registration] Registered ${peerId} (${reason})`);
+    // The first registration isn't news; a change that would have cost challenges is
+    if (saved.registeredAt) {
+      showNotification('Registration updated', `${reason}; the SPK network now has this node's new details.`);
+    }
+    this.emit('registered', { ...state, reason });
+  }
+}
@@ -0,0 +1,144 @@
+import * as net from 'net';
+import * as os from 'os';
+import * as http from 'http';
+import { Request, Response, NextFunction } from 'express';
+import { ConfigStore } from './config';
+import { ApiAuth } from './auth';
+import { ApiError, sendError } from './api-errors';
+
+export interface RemoteAccessSettings {
+  enabled: boolean;
+  // 0.0.0.0 / :: for every interface, or one interface's address
+  bindAddress: string;
+  // CIDRs allowed to connect from other machines; empty means any
+  allowedIps: string[];
+  // Honor X-Forwarded-For from a reverse proxy on this machine
+  trustProxy: boolean;
+}
+
+export const DEFAULT_REMOTE_ACCESS: RemoteAccessSettings = {
+  enabled: false,
+  bindAddress: '0.0.0.0',
+  allowedIps: [],
+  trustProxy: false,
+};
+
+export const LOOPBACK_ADDRESS = '127.0.0.1';
+
+// Validators answer from wherever they run and have no token, and neither
+// do health probes from a supervisor
+const TOKENLESS_REMOTE_ROUTES = [/^\/api\/(v\d+\/)?challenge$/, /^\/(healthz|readyz)$/];
+
+function parseCidr(cidr: string): { address: string; prefix: number; family: 'ipv4' | 'ipv6' } | null {
+  const [address, prefixText] = cidr.trim().split('/');
+  const version = net.isIP(address);
+  if (version === 0) return null;
+  const maxPrefix = version === 4 ? 32 : 128;
+  const prefix = prefixText === undefined ? maxPrefix : Number(prefixText);
+  if (!Number.isInteger(prefix) || prefix < 0 || prefix > maxPrefix) return null;
+  return { address, prefix, family: version === 4 ? 'ipv4' : 'ipv6' };
+}
+
+// Dual-stack sockets report IPv4 clients as ::ffff:a.b.c.d
+function normalizeIp(ip: string): string {
+  return ip.startsWith('::ffff:') && net.isIPv4(ip.slice(7)) ? ip.slice(7) : ip;
+}
+
+export function isLoopback(ip: string): boolean {
+  const normalized = normalizeIp(ip);
+  return normalized === '::1' || normalized.startsWith('127.');
+}
+
+// Loopback, or one of this machine's own interface addresses
+export function isLocalAddress(ip: string): boolean {
+  if (isLoopback(ip)) return true;
+  const normalized = normalizeIp(ip);
+  return Object.values(os.networkInterfaces())
+    .some((addresses) => (addresses || []).some((address) => address.address === normalized));
+}
+
+export function validateRemoteAccess(settings: Partial<RemoteAccessSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.bindAddress !== undefined && net.isIP(settings.bindAddress) === 0) {
+    errors.push('bindAddress must be an IP address');
+  }
+  if (settings.allowedIps !== undefined) {
+    if (!Array.isArray(settings.allowedIps)) {
+      errors.push('allowedIps must be a list of IP addresses or CIDR ranges');
+    } else {
+      settings.allowedIps
+        .filter((cidr) => !parseCidr(cidr))
+        .forEach((cidr) => errors.push(`Invalid IP or CIDR range: ${cidr}`));
+    }
+  }
+  return errors;
+}
+
+export function getBindAddress(settings: RemoteAccessSettings): string {
+  return settings.enabled ? settings.bindAddress : LOOPBACK_ADDRESS;
+}
+
+/**
+ * Guards the API when it listens beyond loopback, e.g. on a NAS controlled
+ * from another machine. Requests from this machine behave as before; every
+ * other caller must come from an allowed range and present the API token
+ * on every route, reads included, except the validator challenge.
+ */
+export class RemoteAccessGuard {
+  private config: ConfigStore;
+  private auth: ApiAuth;
+
+  constructor(config: ConfigStore, auth: ApiAuth) {
+    this.config = config;
+    this.auth = auth;
+  }
+
+  isIpAllowed(ip: string, allowedIps: string[]): boolean {
+    if (allowedIps.length === 0) return true;
+    const list = new net.BlockList();
+    allowedIps.map(parseCidr).forEach((cidr) => {
+      if (cidr) list.addSubnet(cidr.address, cidr.prefix, cidr.family);
+    });
+    const normalized = normalizeIp(ip);
+    return list.check(normalized, net.isIPv6(normalized) ? 'ipv6' : 'ipv4');
+  }
+
+  /**
+   * Same rule for WebSocket upgrades, which bypass the Express stack.
+   * Browsers can't send headers there, so the token comes as a parameter.
+   */
+  allowsUpgrade(req: http.IncomingMessage, token: string | null): boolean {
+    return this.allowsPeer(req.socket.remoteAddress || '', token);
+  }
+
+  /**
+   * The remote rule for a caller outside Express (WebSocket, gRPC): local
+   * callers always pass, others need remote access on, an allowed IP and,
+   * unless `tokenless`, the token.
+   */
+  allowsPeer(ip: string, token: string | null, tokenless = false): boolean {
+    if (isLocalAddress(ip)) return true;
+    const settings = this.config.getConfig().remoteAccess;
+    return settings.enabled
+      && this.isIpAllowed(ip, settings.allowedIps)
+      && (tokenless || (token !== null && this.auth.isValidToken(token)));
+  }
+
+  middleware() {
+    return (req: Request, res: Response, next: NextFunction) => {
+      const settings = this.config.getConfig().remoteAccess;
+      const ip = req.ip || req.socket.remoteAddress || '';
+      if (!settings.enabled || isLocalAddress(ip)) return next();
+
+      if (!this.isIpAllowed(ip, settings.allowedIps)) {
+        console.warn(`[Remote] Refused request from ${ip}`);
+        return sendError(res, new ApiError('FORBIDDEN', 'This address is not allowed to reach the agent'));
+      }
+      const tokenless = TOKENLESS_REMOTE_ROUTES.some((route) => route.test(req.path));
+      if (req.method !== 'OPTIONS' && !tokenless && !this.auth.isAuthorized(req)) {
+        return sendError(res, new ApiError('UNAUTHORIZED', 'Remote access requires the API token'));
+      }
+      next();
+    };
+  }
+}
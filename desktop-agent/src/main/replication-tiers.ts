@@ -0,0 +1,161 @@
+import { ConfigStore } from './config';
+import { ContractPinner } from './contracts';
+
+const TICK_MS = 15 * 60 * 1000;
+const HOUR_MS = 60 * 60 * 1000;
+
+export const REPLICATION_TIERS = ['hot', 'warm', 'cold'] as const;
+export type ReplicationTier = typeof REPLICATION_TIERS[number];
+
+// A CID several contracts hold gets the most demanding of their tiers
+const TIER_RANK: Record<ReplicationTier, number> = { hot: 2, warm: 1, cold: 0 };
+
+export function highestTier(tiers: ReplicationTier[]): ReplicationTier {
+  return tiers.reduce<ReplicationTier>((best, tier) => (TIER_RANK[tier] > TIER_RANK[best] ? tier : best), 'cold');
+}
+
+export interface TierSettings {
+  // How often the agent announces the tier's CIDs itself; 0 leaves them to Kubo's reprovider
+  reprovideHours: number;
+  // Deadline an answer for the tier's content is held to; null uses the PoA latency budget
+  challengeTargetMs: number | null;
+}
+
+export type ReplicationTierSettings = Record<ReplicationTier, TierSettings>;
+
+export const DEFAULT_REPLICATION_TIER_SETTINGS: ReplicationTierSettings = {
+  hot: { reprovideHours: 4, challengeTargetMs: null },
+  warm: { reprovideHours: 12, challengeTargetMs: null },
+  cold: { reprovideHours: 0, challengeTargetMs: 6000 },
+};
+
+export function isReplicationTier(value: unknown): value is ReplicationTier {
+  return typeof value === 'string' && (REPLICATION_TIERS as readonly string[]).includes(value);
+}
+
+export function validateReplicationTierSettings(settings: Partial<Record<ReplicationTier, Partial<TierSettings>>>): string[] {
+  const errors: string[] = [];
+  for (const [tier, update] of Object.entries(settings)) {
+    if (!isReplicationTier(tier)) {
+      errors.push(`Unknown tier ${tier}; use one of: ${REPLICATION_TIERS.join(', ')}`);
+      continue;
+    }
+    if (update?.reprovideHours !== undefined
+      && (!Number.isInteger(update.reprovideHours) || update.reprovideHours < 0 || update.reprovideHours > 168)) {
+      errors.push(`${tier}.reprovideHours must be between 0 and 168`);
+    }
+    const target = update?.challengeTargetMs;
+    if (target !== undefined && target !== null && (!Number.isInteger(target) || target < 500 || target > 60000)) {
+      errors.push(`${tier}.challengeTargetMs must be between 500 and 60000, or null`);
+    }
+  }
+  return errors;
+}
+
+export interface TierOperations {
+  // Announces the CIDs to the DHT from the instances holding them
+  reprovide(cids: string[]): Promise<void>;
+  // Builds block lists for the CIDs ahead of their first challenge
+  precompute(cids: string[]): Promise<number>;
+}
+
+export interface TierStatus {
+  tier: ReplicationTier;
+  settings: TierSettings;
+  contracts: number;
+  cids: number;
+  lastReprovidedAt: string | null;
+  nextReprovideAt: string | null;
+}
+
+/**
+ * Storage contracts carry a replication tier. Hot content is announced
+ * every few hours and has its proof block lists built before any
+ * challenge asks for them, and keeps them longest in the proof cache. Warm
+ * content, the default, is announced twice a day. Cold content is left to
+ * Kubo's own reprovider, is the first out of the proof cache, and answers
+ * are held to a looser deadline.
+ */
+export class TierScheduler {
+  private config: ConfigStore;
+  private contracts: ContractPinner;
+  private ops: TierOperations;
+  private timer: NodeJS.Timeout | null = null;
+  private lastReprovided: Partial<Record<ReplicationTier, number>> = {};
+  private running = false;
+
+  constructor(config: ConfigStore, contracts: ContractPinner, ops: TierOperations) {
+    this.config = config;
+    this.contracts = contracts;
+    this.ops = ops;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    // Kubo announces everything when it starts, so the first round can wait a full interval
+    const now = Date.now();
+    for (const tier of REPLICATION_TIERS) this.lastReprovided[tier] ??= now;
+    this.timer = setInterval(() => {
+      this.tick().catch((error) => console.error('[Contracts] Tier maintenance failed:', error.message));
+    }, TICK_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getStatus(): TierStatus[] {
+    const settings = this.config.getConfig().replicationTiers;
+    return REPLICATION_TIERS.map((tier) => {
+      const last = this.lastReprovided[tier] ?? null;
+      const { reprovideHours } = settings[tier];
+      return {
+        tier,
+        settings: settings[tier],
+        contracts: this.contracts.list().filter((record) => this.contracts.tierOf(record) === tier).length,
+        cids: this.cidsOf(tier).length,
+        lastReprovidedAt: last ? new Date(last).toISOString() : null,
+        nextReprovideAt: last && reprovideHours > 0 ? new Date(last + reprovideHours * HOUR_MS).toISOString() : null,
+      };
+    });
+  }
+
+  // The PoA deadline for an answer about this CID
+  challengeTargetFor(cid: string): number {
+    const target = this.config.getConfig().replicationTiers[this.contracts.tierFor(cid)].challengeTargetMs;
+    return target ?? this.config.getConfig().poa.budget.totalMs;
+  }
+
+  async tick(): Promise<void> {
+    if (this.running) return;
+    this.running = true;
+    try {
+      const settings = this.config.getConfig().replicationTiers;
+      for (const tier of REPLICATION_TIERS) {
+        const { reprovideHours } = settings[tier];
+        if (reprovideHours === 0) continue;
+        if (Date.now() - (this.lastReprovided[tier] ?? 0) < reprovideHours * HOUR_MS) continue;
+        const cids = this.cidsOf(tier);
+        this.lastReprovided[tier] = Date.now();
+        if (cids.length === 0) continue;
+        await this.ops.reprovide(cids);
+        console.log(`[Contracts] Announced ${cids.length} ${tier} CIDs`);
+      }
+      await this.ops.precompute(this.cidsOf('hot'));
+    } finally {
+      this.running = false;
+    }
+  }
+
+  // Pinned CIDs whose most demanding contract is in this tier
+  private cidsOf(tier: ReplicationTier): string[] {
+    const cids = new Set<string>();
+    for (const record of this.contracts.list()) {
+      for (const [cid, entry] of Object.entries(record.cids)) {
+        if (entry.status === 'pinned' && this.contracts.tierFor(cid) === tier) cids.add(cid);
+      }
+    }
+    return Array.from(cids);
+  }
+}
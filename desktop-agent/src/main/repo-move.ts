@@ -0,0 +1,146 @@
+import { EventEmitter } from 'events';
+import * as fs from 'fs';
+import * as path from 'path';
+import { ConfigStore } from './config';
+import { KuboPool } from './kubo-pool';
+
+// Files the daemon leaves behind while running; a fresh start recreates them
+const SKIPPED_FILES = ['repo.lock', 'api', 'gateway'];
+
+export interface RepoMoveProgress {
+  doneBytes: number;
+  totalBytes: number;
+  file?: string;
+}
+
+export interface RepoMoveResult {
+  success: boolean;
+  instance: string;
+  from: string;
+  to: string;
+  copiedBytes: number;
+  error?: string;
+}
+
+interface RepoFile {
+  relative: string;
+  size: number;
+}
+
+function listFiles(root: string, relative = ''): RepoFile[] {
+  const files: RepoFile[] = [];
+  for (const entry of fs.readdirSync(path.join(root, relative), { withFileTypes: true })) {
+    const child = path.join(relative, entry.name);
+    if (entry.isDirectory()) {
+      files.push(...listFiles(root, child));
+    } else if (entry.isFile() && !(relative === '' && SKIPPED_FILES.includes(entry.name))) {
+      files.push({ relative: child, size: fs.statSync(path.join(root, child)).size });
+    }
+  }
+  return files;
+}
+
+// statfs needs a path that exists; the target usually doesn't yet
+function existingParent(target: string): string {
+  let dir = path.resolve(target);
+  while (!fs.existsSync(dir) && path.dirname(dir) !== dir) dir = path.dirname(dir);
+  return dir;
+}
+
+/**
+ * Moves a repo to another drive, for when the disk under it is failing.
+ * The daemon is stopped so the datastore is consistent, the repo is
+ * copied file by file, and the instance is started again from the new
+ * path. Unlike a datastore migration the repo is copied as-is, cached
+ * blocks included. The old repo is left where it was: it may be the only
+ * copy left if the new one turns out bad, and deleting it is the user's
+ * call.
+ */
+export class RepoMover extends EventEmitter {
+  private pool: KuboPool;
+  private config: ConfigStore;
+  private moving = false;
+
+  constructor(pool: KuboPool, config: ConfigStore) {
+    super();
+    this.pool = pool;
+    this.config = config;
+  }
+
+  async move(instanceId: string, targetPath: string): Promise<RepoMoveResult> {
+    const kubo = this.pool.getInstances().find((instance) => instance.getInstanceId() === instanceId);
+    if (!kubo) throw new Error(`No instance ${instanceId}`);
+    if (kubo.isExternal()) throw new Error('The repo of an external node is managed outside the agent');
+    if (this.moving) throw new Error('A repo move is already in progress');
+
+    const from = kubo.getRepoPath();
+    const to = path.resolve(targetPath);
+    if (to === path.resolve(from) || to.startsWith(path.resolve(from) + path.sep)) {
+      throw new Error('The new location must be outside the current repo');
+    }
+    if (fs.existsSync(to) && fs.readdirSync(to).length > 0) {
+      throw new Error(`${to} is not empty`);
+    }
+    this.moving = true;
+
+    const result: RepoMoveResult = { success: false, instance: instanceId, from, to, copiedBytes: 0 };
+    const wasRunning = kubo.isRunning();
+    try {
+      if (wasRunning) {
+        this.emitStage('Stopping IPFS daemon...');
+        await kubo.stop();
+      }
+
+      try {
+        const files = listFiles(from);
+        const totalBytes = files.reduce((sum, file) => sum + file.size, 0);
+        const stats = await fs.promises.statfs(existingParent(to));
+        if (stats.bavail * stats.bsize < totalBytes) {
+          throw new Error(`The new location needs ${(totalBytes / 1024 / 1024 / 1024).toFixed(1)} GB free`);
+        }
+
+        this.emitStage(`Copying ${files.length} files to ${to}...`);
+        for (const file of files) {
+          this.emit('progress', { doneBytes: result.copiedBytes, totalBytes, file: file.relative } as RepoMoveProgress);
+          const destination = path.join(to, file.relative);
+          await fs.promises.mkdir(path.dirname(destination), { recursive: true });
+          await fs.promises.copyFile(path.join(from, file.relative), destination);
+          result.copiedBytes += file.size;
+        }
+        this.emit('progress', { doneBytes: totalBytes, totalBytes } as RepoMoveProgress);
+
+        kubo.setRepoPath(to);
+        this.saveRepoPath(instanceId, to);
+        result.success = true;
+        console.log(`[Kubo] Moved repo of ${instanceId} from ${from} to ${to}; the old repo was left in place`);
+      } catch (error: any) {
+        console.error('[Kubo] Repo move failed:', error.message);
+        fs.rmSync(to, { recursive: true, force: true });
+        result.error = error.message;
+      }
+
+      if (wasRunning) {
+        this.emitStage('Starting IPFS daemon...');
+        await kubo.start();
+      }
+      return result;
+    } finally {
+      this.moving = false;
+    }
+  }
+
+  private saveRepoPath(instanceId: string, repoPath: string): void {
+    if (instanceId === this.pool.getPrimary().getInstanceId()) {
+      this.config.setConfig({ ipfsRepoPath: repoPath });
+      return;
+    }
+    this.config.setConfig({
+      extraRepos: this.config.getConfig().extraRepos.map((repo) => (repo.id === instanceId ? { ...repo, repoPath } : repo)),
+    });
+  }
+
+  private emitStage(message: string): void {
+    console.log(`[Kubo] ${message}`);
+    this.emit('status', message);
+  }
+}
@@ -0,0 +1,105 @@
+import * as fs from 'fs';
+import * as path from 'path';
+
+/**
+ * libp2p resource manager limits for the embedded daemon.
+ *
+ * Kubo scales its defaults from system memory, which leaves large storage
+ * nodes short on connections and file descriptors. Memory and FDs map to
+ * Swarm.ResourceMgr; connection caps go into the repo's user limits file,
+ * which Kubo merges over its computed defaults at startup.
+ */
+
+export interface ResourceLimits {
+  // 0 means let Kubo compute the limit
+  maxMemoryMb: number;
+  maxFileDescriptors: number;
+  maxConnections: number;
+  maxInboundConnections: number;
+}
+
+export const DEFAULT_RESOURCE_LIMITS: ResourceLimits = {
+  maxMemoryMb: 0,
+  maxFileDescriptors: 0,
+  maxConnections: 0,
+  maxInboundConnections: 0,
+};
+
+const LIMITS_FILE = 'libp2p-resource-manager-limits.json';
+
+export interface ResourceUsage {
+  name: string;
+  usage: number;
+  limit: number | 'unlimited' | null;
+}
+
+export function validateResourceLimits(limits: Partial<ResourceLimits>): string[] {
+  const errors: string[] = [];
+  for (const key of Object.keys(DEFAULT_RESOURCE_LIMITS) as Array<keyof ResourceLimits>) {
+    const value = limits[key];
+    if (value !== undefined && (!Number.isInteger(value) || value < 0)) {
+      errors.push(`${key} must be 0 (automatic) or a positive integer`);
+    }
+  }
+
+  if (limits.maxMemoryMb !== undefined && limits.maxMemoryMb > 0 && limits.maxMemoryMb < 128) {
+    errors.push('maxMemoryMb must be at least 128');
+  }
+  if (limits.maxConnections && limits.maxInboundConnections && limits.maxInboundConnections > limits.maxConnections) {
+    errors.push('maxInboundConnections cannot exceed maxConnections');
+  }
+  return errors;
+}
+
+export function applyResourceLimits(repoConfig: any, limits: ResourceLimits): void {
+  const resourceMgr = { ...repoConfig.Swarm?.ResourceMgr };
+
+  if (limits.maxMemoryMb > 0) {
+    resourceMgr.MaxMemory = `${limits.maxMemoryMb}MB`;
+  } else {
+    delete resourceMgr.MaxMemory;
+  }
+  if (limits.maxFileDescriptors > 0) {
+    resourceMgr.MaxFileDescriptors = limits.maxFileDescriptors;
+  } else {
+    delete resourceMgr.MaxFileDescriptors;
+  }
+
+  repoConfig.Swarm = { ...repoConfig.Swarm, ResourceMgr: resourceMgr };
+}
+
+/**
+ * Writes the System scope connection caps, or removes the file when every
+ * connection limit is automatic.
+ */
+export function writeResourceLimitsFile(repoPath: string, limits: ResourceLimits): void {
+  const file = path.join(repoPath, LIMITS_FILE);
+  const system: Record<string, number> = {};
+  if (limits.maxConnections > 0) system.Conns = limits.maxConnections;
+  if (limits.maxInboundConnections > 0) system.ConnsInbound = limits.maxInboundConnections;
+
+  if (Object.keys(system).length === 0) {
+    fs.rmSync(file, { force: true });
+    return;
+  }
+  fs.writeFileSync(file, JSON.stringify({ System: system }, null, 2));
+}
+
+/**
+ * Flattens the System scope of `swarm/resources` into usage/limit pairs.
+ */
+export function parseSystemUsage(resources: any): ResourceUsage[] {
+  const system = resources?.System;
+  if (!system) return [];
+
+  return ['Memory', 'FD', 'Conns', 'ConnsInbound', 'ConnsOutbound', 'Streams', 'StreamsInbound', 'StreamsOutbound']
+    .filter((name) => system[`${name}Usage`] !== undefined)
+    .map((name) => {
+      const limit = system[name];
+      return {
+        name,
+        usage: system[`${name}Usage`],
+        limit: typeof limit === 'number' ? limit : (limit === 'unlimited' ? 'unlimited' : null),
+      };
+    });
+}
@@ -0,0 +1,283 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import { Asset, Operation } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+import { HiveAccount } from './hive';
+import { HiveSigningMethod } from './hive-signing';
+import { TransactionQueue } from './tx-queue';
+import { fromMilli } from './eligibility';
+import { PriceFeed } from './price-feed';
+import { HoneycombClient } from './honeycomb';
+import { showNotification } from './notifications';
+
+// How often threshold mode looks at the balances
+const THRESHOLD_CHECK_MS = 60 * 60 * 1000;
+const HOUR_MS = 60 * 60 * 1000;
+const MAX_HISTORY = 500;
+
+// HBD covers Hive's pending author/curation rewards; HIVE and VESTS come along in the same claim
+export type RewardToken = 'HBD' | 'HIVE' | 'VESTS' | 'LARYNX' | 'SPK';
+export type ThresholdToken = 'HBD' | 'LARYNX' | 'SPK';
+export type ClaimTrigger = 'schedule' | 'threshold' | 'manual';
+
+export interface RewardClaimSettings {
+  mode: 'off' | 'schedule' | 'threshold';
+  // schedule: claim whatever has accrued this often
+  intervalHours: number;
+  // threshold: claim a token once this much of it has accrued
+  thresholds: Record<ThresholdToken, number>;
+  // Honeycomb op that pays out the node's accrued LARYNX and SPK
+  tokenClaimId: string;
+}
+
+export const DEFAULT_REWARD_CLAIM_SETTINGS: RewardClaimSettings = {
+  mode: 'off',
+  intervalHours: 24,
+  thresholds: { HBD: 1, LARYNX: 10, SPK: 1 },
+  tokenClaimId: 'spkccT_shares_claim',
+};
+
+export function validateRewardClaimSettings(settings: Partial<RewardClaimSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.mode !== undefined && !['off', 'schedule', 'threshold'].includes(settings.mode)) {
+    errors.push('mode must be off, schedule or threshold');
+  }
+  if (settings.intervalHours !== undefined
+    && (!Number.isInteger(settings.intervalHours) || settings.intervalHours < 1 || settings.intervalHours > 24 * 30)) {
+    errors.push('intervalHours must be between 1 and 720');
+  }
+  if (settings.thresholds !== undefined) {
+    for (const [token, amount] of Object.entries(settings.thresholds)) {
+      if (!['HBD', 'LARYNX', 'SPK'].includes(token)) errors.push(`thresholds has an unknown token ${token}`);
+      else if (typeof amount !== 'number' || !Number.isFinite(amount) || amount < 0) {
+        errors.push(`thresholds.${token} must be zero or more`);
+      }
+    }
+  }
+  if (settings.tokenClaimId !== undefined && !/^[a-zA-Z0-9_.-]{1,32}$/.test(settings.tokenClaimId)) {
+    errors.push('tokenClaimId must be 1-32 letters, digits, dots, dashes or underscores');
+  }
+  return errors;
+}
+
+export interface ClaimEstimate {
+  accrued: Record<RewardToken, number>;
+  // What a claim now would take, given the trigger; empty when nothing is due
+  claiming: RewardToken[];
+  operations: Operation[];
+  checkedAt: string;
+}
+
+export interface ClaimRecord {
+  id: string;
+  at: string;
+  trigger: ClaimTrigger;
+  amounts: Partial<Record<RewardToken, number>>;
+  txId: string | null;
+  method: HiveSigningMethod;
+  error: string | null;
+  // What the HBD and HIVE claimed were worth at the time; missing on older records
+  fiat?: { currency: string; amounts: Partial<Record<'HBD' | 'HIVE', number>> } | null;
+}
+
+export interface RewardClaimStatus {
+  settings: RewardClaimSettings;
+  lastClaimAt: string | null;
+  nextScheduledAt: string | null;
+  history: ClaimRecord[];
+}
+
+/**
+ * Claims the node account's accrued rewards: Hive's pending HBD (with the
+ * HIVE and VESTS that come with it) through claim_reward_balance, and the
+ * node's LARYNX and SPK through the honeycomb claim op. Both go in one
+ * posting-authority transaction. Runs on a schedule or whenever a balance
+ * passes its threshold; every claim, good or failed, is kept in
+ * reward-claims.json and raises a notification.
+ */
+export class RewardClaimer extends EventEmitter {
+  private filePath: string;
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private queue: TransactionQueue;
+  private prices: PriceFeed;
+  private honeycomb: HoneycombClient;
+  private timer: NodeJS.Timeout | null = null;
+  private claiming = false;
+
+  constructor(dataDir: string, config: ConfigStore, account: HiveAccount, queue: TransactionQueue, prices: PriceFeed,
+    honeycomb: HoneycombClient) {
+    super();
+    this.filePath = path.join(dataDir, 'reward-claims.json');
+    this.config = config;
+    this.account = account;
+    this.queue = queue;
+    this.prices = prices;
+    this.honeycomb = honeycomb;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.tick().catch((error) => console.error('[Rewards] Claim check failed:', error.message));
+    }, THRESHOLD_CHECK_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getStatus(): RewardClaimStatus {
+    const settings = this.config.getConfig().rewardClaims;
+    const history = this.readHistory();
+    const lastClaimAt = history.find((record) => !record.error)?.at ?? null;
+    return {
+      settings,
+      lastClaimAt,
+      // Without an earlier claim, the first one is due straight away
+      nextScheduledAt: settings.mode === 'schedule'
+        ? new Date(lastClaimAt ? new Date(lastClaimAt).getTime() + settings.intervalHours * HOUR_MS : Date.now()).toISOString()
+        : null,
+      history,
+    };
+  }
+
+  /**
+   * The dry run: what has accrued and what a claim for this trigger would
+   * broadcast. Manual and scheduled claims take everything above zero;
+   * threshold claims only the tokens past their threshold.
+   */
+  async estimate(trigger: ClaimTrigger = 'manual'): Promise<ClaimEstimate> {
+    const { hiveUsername, rewardClaims } = this.config.getConfig();
+    if (!hiveUsername) throw new Error('Log in with a Hive account first');
+
+    const [hiveAccount] = await this.account.getClient().database.getAccounts([hiveUsername]);
+    if (!hiveAccount) throw new Error(`@${hiveUsername} does not exist on Hive`);
+    // Claims must see what has accrued right now, not a cached balance
+    const spkAccount = await this.honeycomb.getAccount(hiveUsername, true);
+    const accrued: Record<RewardToken, number> = {
+      HBD: Asset.from(hiveAccount.reward_hbd_balance).amount,
+      HIVE: Asset.from(hiveAccount.reward_hive_balance).amount,
+      VESTS: Asset.from(hiveAccount.reward_vesting_balance).amount,
+      LARYNX: fromMilli(spkAccount.claim),
+      SPK: fromMilli(spkAccount.spk_claim),
+    };
+
+    const due = (token: ThresholdToken, amount: number) => amount > 0
+      && (trigger !== 'threshold' || amount >= rewardClaims.thresholds[token]);
+    const hiveDue = due('HBD', accrued.HBD) || (trigger !== 'threshold' && (accrued.HIVE > 0 || accrued.VESTS > 0));
+    const tokensDue = due('LARYNX', accrued.LARYNX) || due('SPK', accrued.SPK);
+
+    const claiming: RewardToken[] = [];
+    const operations: Operation[] = [];
+    if (hiveDue) {
+      claiming.push(...(['HBD', 'HIVE', 'VESTS'] as const).filter((token) => accrued[token] > 0));
+      operations.push(['claim_reward_balance', {
+        account: hiveUsername,
+        reward_hive: hiveAccount.reward_hive_balance,
+        reward_hbd: hiveAccount.reward_hbd_balance,
+        reward_vests: hiveAccount.reward_vesting_balance,
+      }]);
+    }
+    if (tokensDue) {
+      // The op pays out both tokens, so both are claimed once either is due
+      claiming.push(...(['LARYNX', 'SPK'] as const).filter((token) => accrued[token] > 0));
+      operations.push(['custom_json', {
+        required_auths: [],
+        required_posting_auths: [hiveUsername],
+        id: rewardClaims.tokenClaimId,
+        json: JSON.stringify({}),
+      }]);
+    }
+    return { accrued, claiming, operations, checkedAt: new Date().toISOString() };
+  }
+
+  // Null when nothing had accrued; a failed broadcast is recorded, then thrown
+  async claimNow(trigger: ClaimTrigger = 'manual'): Promise<ClaimRecord | null> {
+    if (this.claiming) throw new Error('A claim is already in progress');
+    this.claiming = true;
+    try {
+      const estimate = await this.estimate(trigger);
+      if (estimate.operations.length === 0) return null;
+      const amounts = Object.fromEntries(estimate.claiming.map((token) => [token, estimate.accrued[token]]));
+      const { quote, stale } = this.prices.getStatus();
+      const record: ClaimRecord = {
+        id: crypto.randomUUID(),
+        at: new Date().toISOString(),
+        trigger,
+        amounts,
+        txId: null,
+        method: this.config.getConfig().hiveSigning.method,
+        error: null,
+        fiat: quote && !stale
+          ? {
+            currency: quote.currency,
+            amounts: Object.fromEntries((['HBD', 'HIVE'] as const)
+              .filter((token) => amounts[token] !== undefined)
+              .map((token) => [token, amounts[token] * (token === 'HBD' ? quote.hbd : quote.hive)])),
+          }
+          : null,
+      };
+      const topic = { kind: 'rewards' as const, account: this.config.getConfig().hiveUsername };
+      const summary = estimate.claiming.map((token) => `${estimate.accrued[token]} ${token}`).join(', ');
+      // Notifications carry the fiat value of what has one
+      const shown = estimate.claiming.map((token) => (token === 'HBD' || token === 'HIVE'
+        ? this.prices.format(estimate.accrued[token], token)
+        : `${estimate.accrued[token]} ${token}`)).join(', ');
+      try {
+        const result = await this.queue.submit({
+          source: 'rewards',
+          description: `Claim rewards: ${summary}`,
+          operations: estimate.operations,
+          role: 'posting',
+        });
+        record.txId = result.txId;
+        record.method = result.method;
+      } catch (error: any) {
+        record.error = error.message;
+      }
+      this.append(record);
+      this.emit('claimed', record);
+      if (record.error) {
+        console.error(`[Rewards] Claiming ${summary} failed: ${record.error}`);
+        showNotification('Reward claim failed', `Could not claim ${summary}: ${record.error}`, topic);
+        throw new Error(record.error);
+      }
+      console.log(`[Rewards] Claimed ${summary} (${trigger}): ${record.txId}`);
+      showNotification('Rewards claimed', `Claimed ${shown}.`, topic);
+      return record;
+    } finally {
+      this.claiming = false;
+    }
+  }
+
+  private async tick(): Promise<void> {
+    const { mode } = this.config.getConfig().rewardClaims;
+    if (mode === 'threshold') {
+      await this.claimNow('threshold');
+    } else if (mode === 'schedule') {
+      const { nextScheduledAt } = this.getStatus();
+      if (nextScheduledAt && Date.now() >= new Date(nextScheduledAt).getTime()) await this.claimNow('schedule');
+    }
+  }
+
+  private readHistory(): ClaimRecord[] {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Rewards] Failed to read claim history:', error);
+    }
+    return [];
+  }
+
+  // Newest first
+  private append(record: ClaimRecord): void {
+    const history = [record, ...this.readHistory()].slice(0, MAX_HISTORY);
+    fs.writeFileSync(this.filePath, JSON.stringify(history, null, 2));
+  }
+}
@@ -0,0 +1,163 @@
+import { EventEmitter } from 'events';
+import { ChallengeRecord } from './poa';
+import { ContractRecord } from './contracts';
+import { DiskHealthReport } from './disk-health';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 60 * 1000;
+// Listing every pin is too heavy to do each minute
+const PIN_CHECK_INTERVAL_MS = 15 * 60 * 1000;
+const MISSED_WARNING = 3;
+const MISSED_CRITICAL = 5;
+const OFFLINE_WARNING_MS = 10 * 60 * 1000;
+const OFFLINE_CRITICAL_MS = 30 * 60 * 1000;
+
+export type RiskKind = 'missed-challenges' | 'offline' | 'unpinned-contract-cids' | 'failing-disk';
+export type RiskLevel = 'warning' | 'critical';
+
+export interface SlashingRisk {
+  kind: RiskKind;
+  level: RiskLevel;
+  message: string;
+  since: string;
+  // CIDs behind an unpinned-contract-cids risk
+  cids?: string[];
+}
+
+export interface RiskMonitorOperations {
+  contracts(): ContractRecord[];
+  pinnedCids(): Promise<Set<string>>;
+  isOnline(): boolean;
+  // Repo disks whose SMART data shows pre-failure signs
+  failingDisks(): DiskHealthReport[];
+}
+
+/**
+ * Watches for what usually comes before the network penalizes a storage
+ * node: a run of missed challenges, the daemon being offline, contract
+ * content that is no longer pinned, or a repo disk about to fail. A new or worse risk raises a
+ * notification and a `changed` event with every current risk, for the
+ * dashboard banner; a cleared one only the event.
+ */
+export class SlashingRiskMonitor extends EventEmitter {
+  private ops: RiskMonitorOperations;
+  private risks = new Map<RiskKind, SlashingRisk>();
+  private consecutiveMisses = 0;
+  private firstMissAt: string | null = null;
+  private offlineSince: number | null = null;
+  private lastPinCheck = 0;
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(ops: RiskMonitorOperations) {
+    super();
+    this.ops = ops;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.check().catch((error) => console.error('[PoA] Risk check failed:', error.message));
+    }, CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  getRisks(): SlashingRisk[] {
+    return Array.from(this.risks.values());
+  }
+
+  observe(record: ChallengeRecord): void {
+    if (record.passed) {
+      this.consecutiveMisses = 0;
+      this.firstMissAt = null;
+      this.update('missed-challenges', null);
+      return;
+    }
+    this.consecutiveMisses++;
+    this.firstMissAt = this.firstMissAt ?? record.at;
+    if (this.consecutiveMisses < MISSED_WARNING) return;
+    this.update('missed-challenges', {
+      kind: 'missed-challenges',
+      level: this.consecutiveMisses >= MISSED_CRITICAL ? 'critical' : 'warning',
+      message: `${this.consecutiveMisses} challenges failed in a row; the latest: ${record.error}`,
+      since: this.firstMissAt,
+    });
+  }
+
+  async check(): Promise<SlashingRisk[]> {
+    if (this.ops.isOnline()) {
+      this.offlineSince = null;
+      this.update('offline', null);
+    } else {
+      this.offlineSince = this.offlineSince ?? Date.now();
+      const down = Date.now() - this.offlineSince;
+      this.update('offline', down < OFFLINE_WARNING_MS ? null : {
+        kind: 'offline',
+        level: down >= OFFLINE_CRITICAL_MS ? 'critical' : 'warning',
+        message: `The IPFS daemon has been offline for ${Math.round(down / 60000)} minutes; challenges can't be answered`,
+        since: new Date(this.offlineSince).toISOString(),
+      });
+    }
+
+    const disks = this.ops.failingDisks();
+    this.update('failing-disk', disks.length === 0 ? null : {
+      kind: 'failing-disk',
+      // Everything on it is lost when it dies, so there's no warning stage
+      level: 'critical',
+      message: disks
+        .map((disk) => `The disk holding ${disk.repoPath} (${disk.model || disk.device}) shows pre-failure signs: ${disk.indicators.join(', ')}`)
+        .join('; ') + '. Migrate the repo to a healthy drive.',
+      since: this.risks.get('failing-disk')?.since ?? new Date().toISOString(),
+    });
+
+    if (this.ops.isOnline() && Date.now() - this.lastPinCheck >= PIN_CHECK_INTERVAL_MS) {
+      this.lastPinCheck = Date.now();
+      await this.checkPins();
+    }
+    return this.getRisks();
+  }
+
+  private async checkPins(): Promise<void> {
+    const pinned = await this.ops.pinnedCids();
+    const missing = new Set<string>();
+    for (const contract of this.ops.contracts()) {
+      for (const [cid, entry] of Object.entries(contract.cids)) {
+        // Still being pinned isn't a risk yet; failed or vanished content is
+        if (entry.status === 'failed' || (entry.status === 'pinned' && !pinned.has(cid))) missing.add(cid);
+      }
+    }
+    if (missing.size === 0) return this.update('unpinned-contract-cids', null);
+    const cids = Array.from(missing);
+    this.update('unpinned-contract-cids', {
+      kind: 'unpinned-contract-cids',
+      // Every challenge on these fails, so it's critical from the start
+      level: 'critical',
+      message: `${cids.length} CIDs under contract are not pinned; use Repair Pins or re-pin the contracts`,
+      since: this.risks.get('unpinned-contract-cids')?.since ?? new Date().toISOString(),
+      cids,
+    });
+  }
+
+  private update(kind: RiskKind, risk: SlashingRisk | null): void {
+    const previous = this.risks.get(kind);
+    if (!risk) {
+      if (!previous) return;
+      this.risks.delete(kind);
+      console.log(`[PoA] Risk cleared: ${kind}`);
+      this.emit('changed', this.getRisks());
+      return;
+    }
+
+    this.risks.set(kind, risk);
+    const escalated = !previous || (previous.level === 'warning' && risk.level === 'critical');
+    if (escalated) {
+      console.warn(`[PoA] Slashing risk (${risk.level}): ${risk.message}`);
+      showNotification(risk.level === 'critical' ? 'Penalty risk: act now' : 'Penalty risk', risk.message, undefined,
+        risk.kind === 'failing-disk');
+    }
+    if (escalated || previous?.message !== risk.message) this.emit('changed', this.getRisks());
+  }
+}
@@ -0,0 +1,437 @@
+import { EventEmitter } from 'events';
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { KuboPool } from './kubo-pool';
+import { KuboManager } from './kubo';
+import { ActivityTracker } from './activity';
+import { PinRepairer } from './pin-repair';
+import { detectDatastore } from './datastore';
+import { parseSectionCid, SHA2_256, IDENTITY } from './car-archive';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 60 * 60 * 1000;
+const HOUR_MS = 60 * 60 * 1000;
+// Breathing room between blocks so a scrub never competes with challenges for the disk
+const BLOCK_PAUSE_MS = 10;
+const BUSY_WAIT_MS = 5000;
+const SHA2_512 = 0x13;
+const BASE32 = 'abcdefghijklmnopqrstuvwxyz234567';
+const BASE58 = '123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz';
+
+export interface ScrubSettings {
+  enabled: boolean;
+  intervalHours: number;
+  // Read from each instance per run; the rotation picks up where it stopped
+  megabytesPerRun: number;
+}
+
+export const DEFAULT_SCRUB_SETTINGS: ScrubSettings = {
+  enabled: true,
+  intervalHours: 6,
+  megabytesPerRun: 1024,
+};
+
+export function validateScrubSettings(settings: Partial<ScrubSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.enabled !== undefined && typeof settings.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  if (settings.intervalHours !== undefined
+    && (!Number.isInteger(settings.intervalHours) || settings.intervalHours < 1 || settings.intervalHours > 24 * 30)) {
+    errors.push('intervalHours must be between 1 and 720');
+  }
+  if (settings.megabytesPerRun !== undefined
+    && (!Number.isInteger(settings.megabytesPerRun) || settings.megabytesPerRun < 16 || settings.megabytesPerRun > 1024 * 1024)) {
+    errors.push('megabytesPerRun must be between 16 and 1048576');
+  }
+  return errors;
+}
+
+export interface DamagedBlock {
+  block: string;
+  // The pin it was reached through
+  pin: string;
+  instance: string;
+  problem: 'corrupt' | 'missing';
+  repaired: boolean;
+  error?: string;
+}
+
+export interface ScrubProgress {
+  instance: string;
+  pin: string;
+  checkedBytes: number;
+  budgetBytes: number;
+}
+
+export interface ScrubResult {
+  startedAt: string;
+  finishedAt: string;
+  checkedBlocks: number;
+  checkedBytes: number;
+  // Hashed with a function the scrubber can't recompute
+  uncheckedBlocks: number;
+  damaged: DamagedBlock[];
+}
+
+export interface ScrubStatus {
+  running: boolean;
+  progress: ScrubProgress | null;
+  last: ScrubResult | null;
+  // When the rotation last got through every pin of each instance
+  cycleCompletedAt: Record<string, string>;
+  nextRunAt: string | null;
+}
+
+// Where the rotation stopped in an instance's pins, sorted by CID
+interface ScrubCursor {
+  pin: string | null;
+  offset: number;
+}
+
+interface ScrubState {
+  cursors: Record<string, ScrubCursor>;
+  cycleCompletedAt: Record<string, string>;
+  last: ScrubResult | null;
+}
+
+function decodeBase32(text: string): Buffer {
+  const bytes: number[] = [];
+  let buffer = 0;
+  let bits = 0;
+  for (const char of text) {
+    const value = BASE32.indexOf(char);
+    if (value < 0) throw new Error(`Invalid character ${char}`);
+    buffer = ((buffer << 5) | value) & 0xfff;
+    bits += 5;
+    if (bits >= 8) {
+      bits -= 8;
+      bytes.push((buffer >> bits) & 0xff);
+    }
+  }
+  return Buffer.from(bytes);
+}
+
+function decodeBase58(text: string): Buffer {
+  let value = 0n;
+  for (const char of text) {
+    const digit = BASE58.indexOf(char);
+    if (digit < 0) throw new Error(`Invalid character ${char}`);
+    value = value * 58n + BigInt(digit);
+  }
+  const hex = value === 0n ? '' : value.toString(16);
+  const body = Buffer.from(hex.length % 2 ? `0${hex}` : hex, 'hex');
+  const zeros = text.match(/^1*/)![0].length;
+  return Buffer.concat([Buffer.alloc(zeros), body]);
+}
+
+/**
+ * The multihash a CID string names. Handles CIDv0 and the multibases
+ * Kubo prints CIDv1 in; null for anything else.
+ */
+export function cidMultihash(cid: string): { code: number; digest: Buffer } | null {
+  try {
+    let bytes: Buffer;
+    if (cid.startsWith('Qm')) bytes = decodeBase58(cid);
+    else if (cid.startsWith('b')) bytes = decodeBase32(cid.slice(1));
+    else if (cid.startsWith('z')) bytes = decodeBase58(cid.slice(1));
+    else if (cid.startsWith('f')) bytes = Buffer.from(cid.slice(1), 'hex');
+    else return null;
+    const { code, digest } = parseSectionCid(bytes);
+    return { code, digest };
+  } catch {
+    return null;
+  }
+}
+
+// Null when the hash function isn't one we can recompute
+function matchesCid(code: number, digest: Buffer, block: Buffer): boolean | null {
+  if (code === SHA2_256) return crypto.createHash('sha256').update(block).digest().equals(digest);
+  if (code === SHA2_512) return crypto.createHash('sha512').update(block).digest().equals(digest);
+  if (code === IDENTITY) return block.equals(digest);
+  return null;
+}
+
+/**
+ * File a flatfs datastore keeps a block in: the multihash in unpadded
+ * upper-case base32, under the shard directory the repo's SHARDING file
+ * names.
+ */
+function flatfsBlockPath(repoPath: string, cid: string): string | null {
+  const multihash = cidMultihash(cid);
+  if (!multihash) return null;
+  const bytes = Buffer.concat([Buffer.from([multihash.code, multihash.digest.length]), multihash.digest]);
+  let key = '';
+  let buffer = 0;
+  let bits = 0;
+  for (const byte of bytes) {
+    buffer = ((buffer << 8) | byte) & 0xfff;
+    bits += 8;
+    while (bits >= 5) {
+      bits -= 5;
+      key += BASE32[(buffer >> bits) & 0x1f];
+    }
+  }
+  if (bits > 0) key += BASE32[(buffer << (5 - bits)) & 0x1f];
+  key = key.toUpperCase();
+
+  const blocksDir = path.join(repoPath, 'blocks');
+  let sharding = '/repo/flatfs/shard/v1/next-to-last/2';
+  try {
+    sharding = fs.readFileSync(path.join(blocksDir, 'SHARDING'), 'utf-8').trim();
+  } catch {
+    // Kubo's default
+  }
+  const match = sharding.match(/\/(prefix|suffix|next-to-last)\/(\d+)$/);
+  if (!match) return null;
+  const length = parseInt(match[2], 10);
+  const padded = key.padStart(length + 1, '_');
+  const shard = match[1] === 'prefix' ? padded.slice(0, length)
+    : match[1] === 'suffix' ? padded.slice(-length)
+      : padded.slice(padded.length - length - 1, padded.length - 1);
+  return path.join(blocksDir, shard, `${key}.data`);
+}
+
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+/**
+ * Re-hashes stored blocks against their CIDs in the background, to catch
+ * bit rot before a challenge lands on it. Each run reads a slice of every
+ * instance's pins, so a large repo is covered over several runs instead
+ * of saturating the disk; where the rotation stopped is kept across
+ * restarts. Work pauses while a challenge is answered or content added.
+ *
+ * A block that doesn't match its CID is dropped from a flatfs repo and
+ * fetched again through the pin repairer; missing blocks go the same way.
+ * Kubo won't remove a pinned block through its API and badger has no
+ * file per block, so corruption elsewhere is reported but left in place.
+ */
+export class PinScrubber extends EventEmitter {
+  private pool: KuboPool;
+  private config: ConfigStore;
+  private activity: ActivityTracker;
+  private repairer: PinRepairer;
+  private filePath: string;
+  private state: ScrubState;
+  private timer: NodeJS.Timeout | null = null;
+  private running = false;
+  private stopping = false;
+  private progress: ScrubProgress | null = null;
+
+  constructor(dataDir: string, pool: KuboPool, config: ConfigStore, activity: ActivityTracker, repairer: PinRepairer) {
+    super();
+    this.pool = pool;
+    this.config = config;
+    this.activity = activity;
+    this.repairer = repairer;
+    this.filePath = path.join(dataDir, 'scrub-state.json');
+    this.state = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.stopping = false;
+    this.timer = setInterval(() => this.tick(), CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+    this.stopping = true;
+  }
+
+  getStatus(): ScrubStatus {
+    const { enabled, intervalHours } = this.config.getConfig().scrub;
+    const last = this.state.last ? new Date(this.state.last.finishedAt).getTime() : Date.now();
+    return {
+      running: this.running,
+      progress: this.progress,
+      last: this.state.last,
+      cycleCompletedAt: this.state.cycleCompletedAt,
+      nextRunAt: enabled ? new Date(last + intervalHours * HOUR_MS).toISOString() : null,
+    };
+  }
+
+  private tick(): void {
+    const { enabled, intervalHours } = this.config.getConfig().scrub;
+    if (!enabled || this.running || this.repairer.isRunning()) return;
+    const last = this.state.last ? new Date(this.state.last.finishedAt).getTime() : 0;
+    if (Date.now() - last < intervalHours * HOUR_MS) return;
+    this.scrub().catch((error) => console.error('[Repair] Scrub failed:', error.message));
+  }
+
+  async scrub(): Promise<ScrubResult> {
+    if (this.running) throw new Error('A scrub is already running');
+    if (this.repairer.isRunning()) throw new Error('Pin repair is running');
+    this.running = true;
+    this.stopping = false;
+
+    const result: ScrubResult = {
+      startedAt: new Date().toISOString(),
+      finishedAt: '',
+      checkedBlocks: 0,
+      checkedBytes: 0,
+      uncheckedBlocks: 0,
+      damaged: [],
+    };
+    try {
+      const budget = this.config.getConfig().scrub.megabytesPerRun * 1024 * 1024;
+      for (const kubo of this.pool.getInstances()) {
+        if (this.stopping) break;
+        if (!kubo.isRunning()) continue;
+        await this.scrubInstance(kubo, budget, result);
+      }
+
+      result.finishedAt = new Date().toISOString();
+      this.state.last = result;
+      this.save();
+      const repaired = result.damaged.filter((block) => block.repaired).length;
+      console.log(`[Repair] Scrubbed ${result.checkedBlocks} blocks (${(result.checkedBytes / 1e6).toFixed(0)} MB):`
+        + ` ${result.damaged.length} damaged, ${repaired} repaired`);
+      if (result.damaged.length > repaired) {
+        showNotification('SPK Storage Damaged',
+          `${result.damaged.length - repaired} stored blocks are corrupt or missing and could not be repaired. `
+          + 'Challenges on that content will fail until it is re-pinned.');
+      }
+      this.emit('scrubbed', result);
+      return result;
+    } finally {
+      this.running = false;
+      this.progress = null;
+    }
+  }
+
+  private async scrubInstance(kubo: KuboManager, budget: number, result: ScrubResult): Promise<void> {
+    const instance = kubo.getInstanceId();
+    const response = await axios.post(`${kubo.getApiUrl()}/api/v0/pin/ls?type=recursive`);
+    const pins = Object.keys(response.data.Keys || {}).sort();
+    if (pins.length === 0) return;
+
+    const cursor = this.state.cursors[instance] ?? { pin: null, offset: 0 };
+    let index = cursor.pin === null ? 0 : pins.findIndex((pin) => pin >= cursor.pin!);
+    let offset = index >= 0 && pins[index] === cursor.pin ? cursor.offset : 0;
+    if (index < 0) index = 0;
+
+    let bytes = 0;
+    for (let covered = 0; covered < pins.length && bytes < budget && !this.stopping; covered++) {
+      const pin = pins[index];
+      const blocks = await this.listBlocks(kubo, pin);
+      for (; offset < blocks.length && bytes < budget && !this.stopping; offset++) {
+        while (this.activity.isBusy() && !this.stopping) await sleep(BUSY_WAIT_MS);
+        this.progress = { instance, pin, checkedBytes: bytes, budgetBytes: budget };
+        bytes += await this.checkBlock(kubo, pin, blocks[offset], result);
+        await sleep(BLOCK_PAUSE_MS);
+      }
+      this.emit('progress', this.progress);
+      if (offset < blocks.length) break;
+
+      offset = 0;
+      index++;
+      if (index === pins.length) {
+        index = 0;
+        this.state.cycleCompletedAt[instance] = new Date().toISOString();
+      }
+    }
+    this.state.cursors[instance] = { pin: pins[index], offset };
+    this.save();
+  }
+
+  private async listBlocks(kubo: KuboManager, pin: string): Promise<string[]> {
+    const blocks = [pin];
+    try {
+      const response = await axios.post(
+        `${kubo.getApiUrl()}/api/v0/refs?arg=${pin}&recursive=true&unique=true&offline=true`,
+        null,
+        { responseType: 'text', timeout: 10 * 60 * 1000 }
+      );
+      for (const line of String(response.data).split('\n')) {
+        try {
+          const ref = JSON.parse(line).Ref;
+          if (ref) blocks.push(ref);
+        } catch {
+          // Blank line
+        }
+      }
+    } catch {
+      // The walk stops at a missing block; reading the root, or a later run of the repairer, finds it
+    }
+    return blocks;
+  }
+
+  // Bytes read
+  private async checkBlock(kubo: KuboManager, pin: string, block: string, result: ScrubResult): Promise<number> {
+    const data = await this.readBlock(kubo, block);
+    if (data === null) {
+      result.damaged.push(await this.repair(kubo, pin, block, 'missing'));
+      return 0;
+    }
+    result.checkedBlocks++;
+    result.checkedBytes += data.length;
+
+    const multihash = cidMultihash(block);
+    const matches = multihash ? matchesCid(multihash.code, multihash.digest, data) : null;
+    if (matches === null) {
+      result.uncheckedBlocks++;
+    } else if (!matches) {
+      console.warn(`[Repair] Block ${block} of ${pin} does not match its CID`);
+      result.damaged.push(await this.repair(kubo, pin, block, 'corrupt'));
+    }
+    return data.length;
+  }
+
+  private async readBlock(kubo: KuboManager, block: string): Promise<Buffer | null> {
+    try {
+      const response = await axios.post(`${kubo.getApiUrl()}/api/v0/block/get?arg=${block}&offline=true`, null, {
+        responseType: 'arraybuffer',
+        timeout: 60 * 1000,
+      });
+      return Buffer.from(response.data);
+    } catch {
+      return null;
+    }
+  }
+
+  private async repair(kubo: KuboManager, pin: string, block: string, problem: DamagedBlock['problem']): Promise<DamagedBlock> {
+    const damaged: DamagedBlock = { block, pin, instance: kubo.getInstanceId(), problem, repaired: false };
+    try {
+      if (problem === 'corrupt') {
+        const blockPath = kubo.isExternal() || detectDatastore(kubo.getRepoPath()) !== 'flatfs'
+          ? null
+          : flatfsBlockPath(kubo.getRepoPath(), block);
+        if (!blockPath || !fs.existsSync(blockPath)) {
+          throw new Error('Only a flatfs repo managed by the agent can drop a corrupt block; re-pin the content');
+        }
+        fs.rmSync(blockPath);
+      }
+      await this.repairer.repairPin(kubo, { cid: pin, instance: damaged.instance, missingBlocks: [block], complete: true });
+
+      const data = await this.readBlock(kubo, block);
+      const multihash = cidMultihash(block);
+      damaged.repaired = data !== null && (!multihash || matchesCid(multihash.code, multihash.digest, data) !== false);
+      if (!damaged.repaired) damaged.error = 'No peer provided an intact copy';
+    } catch (error: any) {
+      damaged.error = error.message;
+    }
+    console.log(`[Repair] ${problem} block ${block} of ${pin}: ${damaged.repaired ? 'repaired' : damaged.error}`);
+    return damaged;
+  }
+
+  private load(): ScrubState {
+    try {
+      return { cursors: {}, cycleCompletedAt: {}, last: null, ...JSON.parse(fs.readFileSync(this.filePath, 'utf-8')) };
+    } catch {
+      return { cursors: {}, cycleCompletedAt: {}, last: null };
+    }
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.state));
+    } catch (error: any) {
+      console.error('[Repair] Failed to save scrub state:', error.message);
+    }
+  }
+}
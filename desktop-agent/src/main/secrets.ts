@@ -0,0 +1,223 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import { safeStorage } from 'electron';
+import { ConfigStore } from './config';
+
+// Blobs sealed by the vault carry this prefix; OS keyring blobs are bare base64
+const VAULT_PREFIX = 'vault:';
+// scrypt at N=2^17 takes ~128 MiB and a few hundred ms, once per unlock
+export const SCRYPT = { N: 2 ** 17, r: 8, p: 1, maxmem: 256 * 1024 * 1024 };
+const CHECK_PLAINTEXT = 'spk-desktop-agent-vault';
+export const MIN_PASSPHRASE = 8;
+
+export type SecretBackend = 'os-keyring' | 'vault';
+
+export interface VaultSettings {
+  // 'auto' uses the OS keyring when there is one, the vault otherwise
+  backend: 'auto' | 'vault';
+  // Locks again after this long without a secret being used; 0 keeps it open until quit
+  autoLockMinutes: number;
+}
+
+export const DEFAULT_VAULT_SETTINGS: VaultSettings = {
+  backend: 'auto',
+  autoLockMinutes: 15,
+};
+
+export function validateVaultSettings(settings: Partial<VaultSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.backend !== undefined && !['auto', 'vault'].includes(settings.backend)) {
+    errors.push('backend must be auto or vault');
+  }
+  if (settings.autoLockMinutes !== undefined
+    && (!Number.isInteger(settings.autoLockMinutes) || settings.autoLockMinutes < 0 || settings.autoLockMinutes > 24 * 60)) {
+    errors.push('autoLockMinutes must be between 0 and 1440');
+  }
+  return errors;
+}
+
+export class VaultLockedError extends Error {
+  constructor() {
+    super('The secrets vault is locked; unlock it from the dashboard');
+    this.name = 'VaultLockedError';
+  }
+}
+
+interface VaultFile {
+  version: 1;
+  kdf: 'scrypt';
+  salt: string;
+  N: number;
+  r: number;
+  p: number;
+  // CHECK_PLAINTEXT sealed with the key, to tell a wrong passphrase at unlock
+  check: string;
+}
+
+export interface VaultStatus {
+  // Where new secrets go; null when neither is usable yet
+  backend: SecretBackend | null;
+  keyringAvailable: boolean;
+  vaultCreated: boolean;
+  unlocked: boolean;
+  // When it locks itself unless used; null while locked or without auto-lock
+  locksAt: string | null;
+}
+
+// A 32-byte key for AES-256-GCM, as the vault and backup archives derive it
+export function deriveKey(passphrase: string, salt: Buffer, params: { N: number; r: number; p: number }): Promise<Buffer> {
+  return new Promise((resolve, reject) => {
+    crypto.scrypt(passphrase, salt, 32, { N: params.N, r: params.r, p: params.p, maxmem: SCRYPT.maxmem }, (error, key) => {
+      if (error) reject(error);
+      else resolve(key);
+    });
+  });
+}
+
+function seal(key: Buffer, plaintext: string): string {
+  const iv = crypto.randomBytes(12);
+  const cipher = crypto.createCipheriv('aes-256-gcm', key, iv);
+  const ciphertext = Buffer.concat([cipher.update(plaintext, 'utf-8'), cipher.final()]);
+  return Buffer.concat([iv, cipher.getAuthTag(), ciphertext]).toString('base64');
+}
+
+function unseal(key: Buffer, sealed: string): string {
+  const raw = Buffer.from(sealed, 'base64');
+  const decipher = crypto.createDecipheriv('aes-256-gcm', key, raw.subarray(0, 12));
+  decipher.setAuthTag(raw.subarray(12, 28));
+  return Buffer.concat([decipher.update(raw.subarray(28)), decipher.final()]).toString('utf-8');
+}
+
+/**
+ * Encrypts the agent's secrets at rest: stored Hive keys and the HAS auth
+ * key. The OS keyring (through Electron's safeStorage) is used when there
+ * is one. Where there isn't, as on Linux without a running secret service,
+ * secrets go in a vault instead: AES-256-GCM under a key derived from a
+ * passphrase with scrypt, described by vault.json. The vault is unlocked
+ * once per session; the key lives only in memory and is dropped after the
+ * configured time without use, or on quit.
+ *
+ * Emits `locked` and `unlocked`.
+ */
+export class SecretStore extends EventEmitter {
+  private filePath: string;
+  private config: ConfigStore;
+  private key: Buffer | null = null;
+  private lockTimer: NodeJS.Timeout | null = null;
+  private locksAt: number | null = null;
+
+  constructor(dataDir: string, config: ConfigStore) {
+    super();
+    this.filePath = path.join(dataDir, 'vault.json');
+    this.config = config;
+  }
+
+  getStatus(): VaultStatus {
+    return {
+      backend: this.backend(),
+      keyringAvailable: safeStorage.isEncryptionAvailable(),
+      vaultCreated: fs.existsSync(this.filePath),
+      unlocked: this.key !== null,
+      locksAt: this.locksAt ? new Date(this.locksAt).toISOString() : null,
+    };
+  }
+
+  // Whether encrypt() would work right now
+  isWritable(): boolean {
+    const backend = this.backend();
+    return backend === 'os-keyring' || (backend === 'vault' && this.key !== null);
+  }
+
+  encrypt(plaintext: string): string {
+    if (this.backend() === 'os-keyring') return safeStorage.encryptString(plaintext).toString('base64');
+    if (!fs.existsSync(this.filePath)) throw new Error('No OS keyring is available; set up the secrets vault first');
+    return VAULT_PREFIX + seal(this.useKey(), plaintext);
+  }
+
+  // Each blob goes back to whichever backend sealed it
+  decrypt(blob: string): string {
+    if (blob.startsWith(VAULT_PREFIX)) return unseal(this.useKey(), blob.slice(VAULT_PREFIX.length));
+    return safeStorage.decryptString(Buffer.from(blob, 'base64'));
+  }
+
+  async create(passphrase: string): Promise<VaultStatus> {
+    if (fs.existsSync(this.filePath)) throw new Error('A vault already exists; unlock it instead');
+    if (typeof passphrase !== 'string' || passphrase.length < MIN_PASSPHRASE) {
+      throw new Error(`The passphrase must be at least ${MIN_PASSPHRASE} characters`);
+    }
+    const salt = crypto.randomBytes(16);
+    const key = await deriveKey(passphrase, salt, SCRYPT);
+    const vault: VaultFile = {
+      version: 1,
+      kdf: 'scrypt',
+      salt: salt.toString('base64'),
+      N: SCRYPT.N,
+      r: SCRYPT.r,
+      p: SCRYPT.p,
+      check: seal(key, CHECK_PLAINTEXT),
+    };
+    fs.writeFileSync(this.filePath, JSON.stringify(vault, null, 2), { mode: 0o600 });
+    console.log('[Vault] Created the secrets vault');
+    this.setKey(key);
+    return this.getStatus();
+  }
+
+  async unlock(passphrase: string): Promise<VaultStatus> {
+    if (!fs.existsSync(this.filePath)) throw new Error('No vault has been set up');
+    const vault: VaultFile = JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+    const key = await deriveKey(String(passphrase ?? ''), Buffer.from(vault.salt, 'base64'), vault);
+    try {
+      if (unseal(key, vault.check) !== CHECK_PLAINTEXT) throw new Error('mismatch');
+    } catch {
+      throw new Error('Wrong passphrase');
+    }
+    console.log('[Vault] Unlocked');
+    this.setKey(key);
+    return this.getStatus();
+  }
+
+  lock(): VaultStatus {
+    if (this.lockTimer) clearTimeout(this.lockTimer);
+    this.lockTimer = null;
+    this.locksAt = null;
+    if (this.key) {
+      this.key.fill(0);
+      this.key = null;
+      console.log('[Vault] Locked');
+      this.emit('locked');
+    }
+    return this.getStatus();
+  }
+
+  private backend(): SecretBackend | null {
+    const { backend } = this.config.getConfig().vault;
+    if (backend === 'auto' && safeStorage.isEncryptionAvailable()) return 'os-keyring';
+    return fs.existsSync(this.filePath) ? 'vault' : null;
+  }
+
+  // Every use of the key pushes the auto-lock back
+  private useKey(): Buffer {
+    if (!this.key) throw new VaultLockedError();
+    this.scheduleLock();
+    return this.key;
+  }
+
+  private setKey(key: Buffer): void {
+    this.key?.fill(0);
+    this.key = key;
+    this.scheduleLock();
+    this.emit('unlocked');
+  }
+
+  private scheduleLock(): void {
+    if (this.lockTimer) clearTimeout(this.lockTimer);
+    this.lockTimer = null;
+    this.locksAt = null;
+    const { autoLockMinutes } = this.config.getConfig().vault;
+    if (autoLockMinutes === 0) return;
+    this.locksAt = Date.now() + autoLockMinutes * 60 * 1000;
+    this.lockTimer = setTimeout(() => this.lock(), autoLockMinutes * 60 * 1000);
+  }
+}
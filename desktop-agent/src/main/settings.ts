@@ -0,0 +1,153 @@
+/**
+ * Kubo daemon settings and the logic that maps them onto the repo config.
+ */
+
+export const KUBO_PROFILES = ['default', 'lowpower', 'server', 'randomports', 'local-discovery'] as const;
+export type KuboProfile = typeof KUBO_PROFILES[number];
+
+// flatfs stores one file per block; badger is a single LSM store that reads
+// much faster on spinning disks. Fixed when the repo is created.
+export const DATASTORE_TYPES = ['flatfs', 'badger'] as const;
+export type DatastoreType = typeof DATASTORE_TYPES[number];
+
+// Init profile that selects each datastore
+export const DATASTORE_PROFILES: Record<DatastoreType, string> = {
+  flatfs: 'flatfs',
+  badger: 'badgerds',
+};
+
+// How the DHT is told which CIDs we have. 'pinned' skips cached blocks, which
+// is usually what a storage node wants announced.
+export const REPROVIDER_STRATEGIES = ['all', 'pinned', 'roots'] as const;
+export type ReproviderStrategy = typeof REPROVIDER_STRATEGIES[number];
+
+/**
+ * Whether the agent spawns its own daemon or talks to an existing node.
+ */
+export type ConnectionMode =
+  | { type: 'embedded' }
+  | { type: 'external'; apiUrl: string; gatewayUrl?: string };
+
+export const DEFAULT_CONNECTION_MODE: ConnectionMode = { type: 'embedded' };
+
+function isHttpUrl(value: string): boolean {
+  try {
+    const parsed = new URL(value);
+    return parsed.protocol === 'http:' || parsed.protocol === 'https:';
+  } catch {
+    return false;
+  }
+}
+
+export function validateConnectionMode(mode: ConnectionMode): string[] {
+  if (mode.type === 'embedded') return [];
+
+  const errors: string[] = [];
+  if (!isHttpUrl(mode.apiUrl)) {
+    errors.push('apiUrl must be a valid http(s) URL');
+  }
+  if (mode.gatewayUrl !== undefined && !isHttpUrl(mode.gatewayUrl)) {
+    errors.push('gatewayUrl must be a valid http(s) URL');
+  }
+  return errors;
+}
+
+export interface KuboSettings {
+  apiPort: number;
+  gatewayPort: number;
+  swarmPort: number;
+  profile: KuboProfile;
+  storageMaxGb: number;
+  datastore: DatastoreType;
+  reproviderStrategy: ReproviderStrategy;
+  reproviderIntervalHours: number;
+}
+
+// Offset from the Kubo defaults (5001/8080/4001) so we don't collide with
+// IPFS Desktop or a system daemon on the same machine.
+export const DEFAULT_KUBO_SETTINGS: KuboSettings = {
+  apiPort: 5101,
+  gatewayPort: 8180,
+  swarmPort: 4101,
+  profile: 'default',
+  storageMaxGb: 50,
+  datastore: 'flatfs',
+  reproviderStrategy: 'all',
+  reproviderIntervalHours: 22,
+};
+
+export function validateKuboSettings(settings: Partial<KuboSettings>): string[] {
+  const errors: string[] = [];
+  const ports: Array<[string, number | undefined]> = [
+    ['apiPort', settings.apiPort],
+    ['gatewayPort', settings.gatewayPort],
+    ['swarmPort', settings.swarmPort],
+  ];
+
+  for (const [name, port] of ports) {
+    if (port === undefined) continue;
+    if (!Number.isInteger(port) || port < 1024 || port > 65535) {
+      errors.push(`${name} must be an integer between 1024 and 65535`);
+    }
+  }
+
+  const defined = ports.map(([, port]) => port).filter((port) => port !== undefined);
+  if (new Set(defined).size !== defined.length) {
+    errors.push('API, Gateway and Swarm ports must be different');
+  }
+
+  if (settings.storageMaxGb !== undefined && !(settings.storageMaxGb > 0)) {
+    errors.push('storageMaxGb must be greater than 0');
+  }
+
+  if (settings.profile !== undefined && !KUBO_PROFILES.includes(settings.profile)) {
+    errors.push(`profile must be one of: ${KUBO_PROFILES.join(', ')}`);
+  }
+
+  if (settings.datastore !== undefined && !DATASTORE_TYPES.includes(settings.datastore)) {
+    errors.push(`datastore must be one of: ${DATASTORE_TYPES.join(', ')}`);
+  }
+
+  if (settings.reproviderStrategy !== undefined && !REPROVIDER_STRATEGIES.includes(settings.reproviderStrategy)) {
+    errors.push(`reproviderStrategy must be one of: ${REPROVIDER_STRATEGIES.join(', ')}`);
+  }
+
+  const interval = settings.reproviderIntervalHours;
+  if (interval !== undefined && (!Number.isInteger(interval) || interval < 1 || interval > 168)) {
+    errors.push('reproviderIntervalHours must be an integer between 1 and 168');
+  }
+
+  return errors;
+}
+
+/**
+ * Rewrites the listen addresses, storage quota and reprovider schedule of a
+ * parsed Kubo repo config in place.
+ */
+export function applyKuboSettings(repoConfig: any, settings: KuboSettings): void {
+  const swarm = settings.swarmPort;
+  repoConfig.Addresses = {
+    ...repoConfig.Addresses,
+    API: `/ip4/127.0.0.1/tcp/${settings.apiPort}`,
+    Gateway: `/ip4/127.0.0.1/tcp/${settings.gatewayPort}`,
+    Swarm: [
+      `/ip4/0.0.0.0/tcp/${swarm}`,
+      `/ip6/::/tcp/${swarm}`,
+      `/ip4/0.0.0.0/udp/${swarm}/quic-v1`,
+      `/ip4/0.0.0.0/udp/${swarm}/quic-v1/webtransport`,
+      `/ip6/::/udp/${swarm}/quic-v1`,
+      `/ip6/::/udp/${swarm}/quic-v1/webtransport`,
+    ],
+  };
+
+  repoConfig.Datastore = {
+    ...repoConfig.Datastore,
+    StorageMax: `${settings.storageMaxGb}GB`,
+  };
+
+  repoConfig.Reprovider = {
+    ...repoConfig.Reprovider,
+    Strategy: settings.reproviderStrategy,
+    Interval: `${settings.reproviderIntervalHours}h`,
+  };
+}
@@ -0,0 +1,328 @@
+import * as crypto from 'crypto';
+import { Readable } from 'stream';
+import axios from 'axios';
+import { KuboPool } from './kubo-pool';
+import { PinIndex, MAX_LABELS } from './pin-index';
+import { ApiError } from './api-errors';
+import { addStream } from './uploads';
+
+export const SHARD_LABEL = 'shard';
+// GF(2^8) has 256 evaluation points, so no more shards than that
+export const MAX_SHARDS = 256;
+// Encoding and decoding hold the whole file in memory
+export const MAX_SHARDED_FILE_BYTES = 512 * 1024 * 1024;
+const MANIFEST_TIMEOUT_MS = 30 * 1000;
+const SHARD_FETCH_TIMEOUT_MS = 5 * 60 * 1000;
+
+/**
+ * Describes a file stored as Reed–Solomon shards: any `dataShards` of the
+ * `dataShards + parityShards` shards rebuild it. Data shards come first
+ * and are the file split in order, the last one zero-padded.
+ */
+export interface ShardManifest {
+  version: 1;
+  fileCid: string;
+  size: number;
+  sha256: string;
+  dataShards: number;
+  parityShards: number;
+  shardSize: number;
+  // Shard CIDs by index, data then parity
+  shards: string[];
+}
+
+export interface ShardEncodeResult {
+  manifestCid: string;
+  manifest: ShardManifest;
+}
+
+export interface ShardReconstructResult {
+  manifestCid: string;
+  fileCid: string;
+  // The CID the rebuilt file was added under; differs from fileCid when the file was added with other chunking
+  cid: string;
+  size: number;
+  shardsUsed: number[];
+  missingShards: number[];
+}
+
+// Log and antilog tables over the field with polynomial x^8 + x^4 + x^3 + x^2 + 1
+const EXP = new Uint8Array(512);
+const LOG = new Uint8Array(256);
+{
+  let x = 1;
+  for (let i = 0; i < 255; i++) {
+    EXP[i] = x;
+    LOG[x] = i;
+    x <<= 1;
+    if (x & 0x100) x ^= 0x11d;
+  }
+  for (let i = 255; i < 512; i++) EXP[i] = EXP[i - 255];
+}
+
+function gfMul(a: number, b: number): number {
+  return a === 0 || b === 0 ? 0 : EXP[LOG[a] + LOG[b]];
+}
+
+function gfInv(a: number): number {
+  if (a === 0) throw new Error('Zero has no inverse in GF(256)');
+  return EXP[255 - LOG[a]];
+}
+
+function gfPow(a: number, n: number): number {
+  if (n === 0) return 1;
+  return a === 0 ? 0 : EXP[(LOG[a] * n) % 255];
+}
+
+type Matrix = number[][];
+
+function multiply(a: Matrix, b: Matrix): Matrix {
+  return a.map((row) => b[0].map((_, c) => row.reduce((sum, value, i) => sum ^ gfMul(value, b[i][c]), 0)));
+}
+
+// Gauss-Jordan elimination; every square submatrix of the encoding matrix is invertible
+function invert(matrix: Matrix): Matrix {
+  const n = matrix.length;
+  const work = matrix.map((row, r) => [...row, ...Array.from({ length: n }, (_, c) => (c === r ? 1 : 0))]);
+  for (let col = 0; col < n; col++) {
+    const pivot = work.findIndex((row, r) => r >= col && row[col] !== 0);
+    if (pivot < 0) throw new Error('Shard matrix is singular');
+    [work[col], work[pivot]] = [work[pivot], work[col]];
+    const scale = gfInv(work[col][col]);
+    work[col] = work[col].map((value) => gfMul(value, scale));
+    for (let r = 0; r < n; r++) {
+      if (r === col || work[r][col] === 0) continue;
+      const factor = work[r][col];
+      work[r] = work[r].map((value, c) => value ^ gfMul(factor, work[col][c]));
+    }
+  }
+  return work.map((row) => row.slice(n));
+}
+
+/**
+ * The systematic encoding matrix: a Vandermonde matrix times the inverse
+ * of its top square, so the first `dataShards` rows are the identity and
+ * data shards are stored as they are.
+ */
+function encodingMatrix(dataShards: number, totalShards: number): Matrix {
+  const vandermonde = Array.from({ length: totalShards }, (_, r) =>
+    Array.from({ length: dataShards }, (_, c) => gfPow(r, c)));
+  return multiply(vandermonde, invert(vandermonde.slice(0, dataShards)));
+}
+
+// out ^= coefficient * input, byte by byte, through one row of the multiplication table
+function mulAdd(out: Buffer, input: Buffer, coefficient: number): void {
+  if (coefficient === 0) return;
+  const row = new Uint8Array(256);
+  for (let i = 1; i < 256; i++) row[i] = gfMul(coefficient, i);
+  for (let i = 0; i < input.length; i++) out[i] ^= row[input[i]];
+}
+
+export function validateShardCounts(dataShards: unknown, parityShards: unknown): string[] {
+  const errors: string[] = [];
+  if (!Number.isInteger(dataShards) || (dataShards as number) < 1) errors.push('dataShards must be a positive integer');
+  if (!Number.isInteger(parityShards) || (parityShards as number) < 1) errors.push('parityShards must be a positive integer');
+  if (errors.length === 0 && (dataShards as number) + (parityShards as number) > MAX_SHARDS) {
+    errors.push(`dataShards + parityShards may be at most ${MAX_SHARDS}`);
+  }
+  return errors;
+}
+
+/**
+ * Splits `data` into `dataShards` equal shards, padding the last, and
+ * computes `parityShards` parity shards from them.
+ */
+export function encodeShards(data: Buffer, dataShards: number, parityShards: number): { shardSize: number; shards: Buffer[] } {
+  const errors = validateShardCounts(dataShards, parityShards);
+  if (errors.length > 0) throw new Error(errors.join('; '));
+  const shardSize = Math.max(1, Math.ceil(data.length / dataShards));
+  const shards: Buffer[] = [];
+  for (let i = 0; i < dataShards; i++) {
+    const shard = Buffer.alloc(shardSize);
+    data.copy(shard, 0, i * shardSize, Math.min(data.length, (i + 1) * shardSize));
+    shards.push(shard);
+  }
+  const matrix = encodingMatrix(dataShards, dataShards + parityShards);
+  for (let p = 0; p < parityShards; p++) {
+    const parity = Buffer.alloc(shardSize);
+    for (let d = 0; d < dataShards; d++) mulAdd(parity, shards[d], matrix[dataShards + p][d]);
+    shards.push(parity);
+  }
+  return { shardSize, shards };
+}
+
+/**
+ * Rebuilds the original `size` bytes from any `dataShards` of the shards;
+ * missing ones are null. Only the data shards that are missing are
+ * computed.
+ */
+export function decodeShards(shards: Array<Buffer | null>, dataShards: number, parityShards: number, size: number): Buffer {
+  const total = dataShards + parityShards;
+  if (shards.length !== total) throw new Error(`Expected ${total} shard slots, got ${shards.length}`);
+  const present = shards.map((shard, index) => (shard ? index : -1)).filter((index) => index >= 0).slice(0, dataShards);
+  if (present.length < dataShards) {
+    throw new Error(`Only ${present.length} of the ${dataShards} shards needed are available`);
+  }
+  const shardSize = shards[present[0]]!.length;
+  if (present.some((index) => shards[index]!.length !== shardSize)) throw new Error('Shards differ in size');
+
+  const data: Buffer[] = shards.slice(0, dataShards) as Buffer[];
+  if (present.some((index) => index >= dataShards)) {
+    const matrix = encodingMatrix(dataShards, total);
+    const decode = invert(present.map((index) => matrix[index]));
+    for (let d = 0; d < dataShards; d++) {
+      if (shards[d]) continue;
+      const rebuilt = Buffer.alloc(shardSize);
+      present.forEach((index, j) => mulAdd(rebuilt, shards[index]!, decode[d][j]));
+      data[d] = rebuilt;
+    }
+  }
+  return Buffer.concat(data).subarray(0, size);
+}
+
+export function parseShardManifest(value: unknown): ShardManifest {
+  const manifest = value as ShardManifest;
+  const fail = (reason: string): never => {
+    throw new ApiError('INVALID_REQUEST', `Not a shard manifest: ${reason}`);
+  };
+  if (!manifest || typeof manifest !== 'object' || manifest.version !== 1) fail('unknown version');
+  if (typeof manifest.fileCid !== 'string' || !manifest.fileCid) fail('fileCid missing');
+  if (!Number.isInteger(manifest.size) || manifest.size < 0) fail('size must be a byte count');
+  if (typeof manifest.sha256 !== 'string' || !/^[0-9a-f]{64}$/.test(manifest.sha256)) fail('sha256 missing');
+  const errors = validateShardCounts(manifest.dataShards, manifest.parityShards);
+  if (errors.length > 0) fail(errors.join('; '));
+  if (!Number.isInteger(manifest.shardSize) || manifest.shardSize * manifest.dataShards < manifest.size) {
+    fail('shardSize too small for the file');
+  }
+  if (!Array.isArray(manifest.shards) || manifest.shards.length !== manifest.dataShards + manifest.parityShards
+    || manifest.shards.some((cid) => typeof cid !== 'string' || !cid)) {
+    fail('shards must list one CID per shard');
+  }
+  return manifest;
+}
+
+/**
+ * Erasure-coded storage, where a contract spreads a file over many nodes
+ * as Reed–Solomon shards and each node keeps only the shards it was
+ * assigned. Encoding splits a file this node stores into shards and adds
+ * them, with a manifest naming them all; reconstruction rebuilds the file
+ * from whichever shards can be fetched, checked against the manifest's
+ * SHA-256. Pinning assigned shards is the contract pinner's job.
+ */
+export class ShardManager {
+  private pool: KuboPool;
+  private pins: PinIndex;
+
+  constructor(pool: KuboPool, pins: PinIndex) {
+    this.pool = pool;
+    this.pins = pins;
+  }
+
+  async getManifest(manifestCid: string): Promise<ShardManifest> {
+    const buffer = await this.cat(manifestCid, MANIFEST_TIMEOUT_MS, 1024 * 1024);
+    try {
+      return parseShardManifest(JSON.parse(buffer.toString('utf8')));
+    } catch (error: any) {
+      if (error instanceof ApiError) throw error;
+      throw new ApiError('INVALID_REQUEST', `${manifestCid} is not a shard manifest: ${error.message}`);
+    }
+  }
+
+  async encode(fileCid: string, dataShards: number, parityShards: number): Promise<ShardEncodeResult> {
+    const errors = validateShardCounts(dataShards, parityShards);
+    if (errors.length > 0) throw new ApiError('INVALID_REQUEST', errors.join('; '));
+    // Only content already here, so encoding never starts a large download
+    const holder = await this.pool.findHolder(fileCid);
+    if (!holder) throw new ApiError('NOT_FOUND', `${fileCid} is not pinned on this node`);
+    const data = await this.cat(fileCid, SHARD_FETCH_TIMEOUT_MS, MAX_SHARDED_FILE_BYTES, holder.getApiUrl());
+
+    const { shardSize, shards } = encodeShards(data, dataShards, parityShards);
+    const target = await this.pool.selectForNewContent();
+    const shardCids: string[] = [];
+    for (const [index, shard] of shards.entries()) {
+      const { cid } = await addStream(target.getApiUrl(), Readable.from([shard]), `${fileCid}.shard-${index}`, () => undefined);
+      this.label(cid, `${fileCid}.shard-${index}`);
+      shardCids.push(cid);
+    }
+
+    const manifest: ShardManifest = {
+      version: 1,
+      fileCid,
+      size: data.length,
+      sha256: crypto.createHash('sha256').update(data).digest('hex'),
+      dataShards,
+      parityShards,
+      shardSize,
+      shards: shardCids,
+    };
+    const { cid: manifestCid } = await addStream(target.getApiUrl(), Readable.from([Buffer.from(JSON.stringify(manifest))]),
+      `${fileCid}.shards.json`, () => undefined);
+    this.label(manifestCid, `${fileCid}.shards.json`);
+    console.log(`[Storage] Encoded ${fileCid} into ${dataShards}+${parityShards} shards of ${shardSize} bytes (${manifestCid})`);
+    return { manifestCid, manifest };
+  }
+
+  /**
+   * Fetches shards in index order until `dataShards` have arrived, then
+   * decodes, verifies and adds the file.
+   */
+  async reconstruct(manifestCid: string): Promise<ShardReconstructResult> {
+    const manifest = await this.getManifest(manifestCid);
+    if (manifest.size > MAX_SHARDED_FILE_BYTES) {
+      throw new ApiError('INVALID_REQUEST', `Files over ${MAX_SHARDED_FILE_BYTES} bytes can't be rebuilt in memory`);
+    }
+    const shards: Array<Buffer | null> = new Array(manifest.shards.length).fill(null);
+    const missingShards: number[] = [];
+    let available = 0;
+    for (let index = 0; index < manifest.shards.length && available < manifest.dataShards; index++) {
+      try {
+        const shard = await this.cat(manifest.shards[index], SHARD_FETCH_TIMEOUT_MS, manifest.shardSize);
+        if (shard.length !== manifest.shardSize) throw new Error(`is ${shard.length} bytes, not ${manifest.shardSize}`);
+        shards[index] = shard;
+        available++;
+      } catch (error: any) {
+        console.error(`[Storage] Shard ${index} of ${manifestCid} unavailable:`, error.message);
+        missingShards.push(index);
+      }
+    }
+
+    let data: Buffer;
+    try {
+      data = decodeShards(shards, manifest.dataShards, manifest.parityShards, manifest.size);
+    } catch (error: any) {
+      throw new ApiError('CONFLICT', error.message);
+    }
+    if (crypto.createHash('sha256').update(data).digest('hex') !== manifest.sha256) {
+      throw new ApiError('CONFLICT', 'The rebuilt file does not match the manifest checksum; a shard is corrupt');
+    }
+
+    const target = await this.pool.selectForNewContent();
+    const { cid } = await addStream(target.getApiUrl(), Readable.from([data]), manifest.fileCid, () => undefined);
+    return {
+      manifestCid,
+      fileCid: manifest.fileCid,
+      cid,
+      size: data.length,
+      shardsUsed: shards.map((shard, index) => (shard ? index : -1)).filter((index) => index >= 0),
+      missingShards,
+    };
+  }
+
+  private label(cid: string, name: string): void {
+    const labels = this.pins.get(cid)?.labels ?? [];
+    this.pins.set(cid, { name, labels: Array.from(new Set([...labels, SHARD_LABEL])).slice(0, MAX_LABELS) });
+  }
+
+  private async cat(cid: string, timeoutMs: number, maxBytes: number, apiUrl = this.pool.getPrimary().getApiUrl()): Promise<Buffer> {
+    try {
+      const response = await axios.post(`${apiUrl}/api/v0/cat?arg=${cid}&length=${maxBytes + 1}`, null,
+        { timeout: timeoutMs, responseType: 'arraybuffer', maxContentLength: maxBytes + 1 });
+      const buffer = Buffer.from(response.data);
+      if (buffer.length > maxBytes) throw new ApiError('INVALID_REQUEST', `${cid} is larger than ${maxBytes} bytes`);
+      return buffer;
+    } catch (error: any) {
+      if (error instanceof ApiError) throw error;
+      throw new ApiError('IPFS_ERROR', `Could not read ${cid}: ${error.message}`);
+    }
+  }
+}
@@ -0,0 +1,129 @@
+import { Request, Response } from 'express';
+import { EventBus, EventTopic, EVENT_TOPICS, StreamEvent, parseTopics } from './event-bus';
+
+// Proxies close connections that look idle, usually after 30-60 seconds
+const HEARTBEAT_MS = 15 * 1000;
+const RETRY_MS = 3000;
+const MAX_BUFFERED_BYTES = 1024 * 1024;
+const DISCONNECT_BUFFERED_BYTES = 8 * 1024 * 1024;
+
+interface SseClient {
+  res: Response;
+  topics: Set<EventTopic>;
+  dropped: number;
+}
+
+/**
+ * Server-Sent Events transport for the event bus, for networks where a
+ * proxy blocks WebSocket upgrades but passes plain HTTP. It carries the
+ * same events as `/ws/events`. Event ids let a reconnecting EventSource
+ * pick up where it left off via `Last-Event-ID`; when that isn't possible
+ * the client gets a `resync` event and should refetch over REST.
+ */
+export class SseStream {
+  private bus: EventBus;
+  private clients = new Set<SseClient>();
+  private heartbeat: NodeJS.Timeout | null = null;
+  private onEvent = (message: StreamEvent) => this.forward(message);
+
+  constructor(bus: EventBus) {
+    this.bus = bus;
+    this.bus.on('event', this.onEvent);
+  }
+
+  handler() {
+    return (req: Request, res: Response) => {
+      const requested = parseTopics(req.query.topics);
+      const client: SseClient = {
+        res,
+        topics: new Set(requested.length > 0 ? requested : EVENT_TOPICS),
+        dropped: 0,
+      };
+
+      res.status(200).set({
+        'Content-Type': 'text/event-stream; charset=utf-8',
+        // no-transform keeps compressing proxies from buffering the stream
+        'Cache-Control': 'no-cache, no-transform',
+        'Connection': 'keep-alive',
+        'X-Accel-Buffering': 'no',
+      });
+      res.flushHeaders();
+      res.write(`retry: ${RETRY_MS}\n\n`);
+
+      const lastEventId = req.header('Last-Event-ID') ?? req.query.lastEventId;
+      if (lastEventId !== undefined) {
+        const missed = this.bus.since(Number(lastEventId));
+        if (missed === null) {
+          res.write('event: resync\ndata: {}\n\n');
+        } else {
+          missed.filter((message) => client.topics.has(message.topic)).forEach((message) => this.send(client, message));
+        }
+      }
+
+      this.clients.add(client);
+      req.on('close', () => this.clients.delete(client));
+      if (!this.heartbeat) {
+        this.heartbeat = setInterval(() => this.ping(), HEARTBEAT_MS);
+      }
+    };
+  }
+
+  getClientCount(): number {
+    return this.clients.size;
+  }
+
+  /**
+   * Ends every stream. Open responses keep server.close() from finishing,
+   * so this runs whenever a listener is torn down; EventSource reconnects
+   * on its own and resumes from its last event id.
+   */
+  disconnectAll(): void {
+    for (const client of this.clients) {
+      client.res.end();
+    }
+    this.clients.clear();
+  }
+
+  close(): void {
+    this.bus.off('event', this.onEvent);
+    if (this.heartbeat) {
+      clearInterval(this.heartbeat);
+      this.heartbeat = null;
+    }
+    this.disconnectAll();
+  }
+
+  private forward(message: StreamEvent): void {
+    for (const client of this.clients) {
+      if (client.topics.has(message.topic)) {
+        this.send(client, message);
+      }
+    }
+  }
+
+  private send(client: SseClient, message: StreamEvent): void {
+    const { res } = client;
+    if (res.writableLength > DISCONNECT_BUFFERED_BYTES) {
+      console.warn('[Events] Disconnecting SSE client that stopped reading');
+      res.end();
+      this.clients.delete(client);
+      return;
+    }
+    if (res.writableLength > MAX_BUFFERED_BYTES) {
+      client.dropped++;
+      return;
+    }
+
+    if (client.dropped > 0) {
+      res.write(`event: resync\ndata: ${JSON.stringify({ dropped: client.dropped })}\n\n`);
+      client.dropped = 0;
+    }
+    res.write(`id: ${message.id}\ndata: ${JSON.stringify(message)}\n\n`);
+  }
+
+  private ping(): void {
+    for (const client of this.clients) {
+      client.res.write(': ping\n\n');
+    }
+  }
+}
@@ -0,0 +1,79 @@
+import { EventEmitter } from 'events';
+
+/**
+ * Lifecycle of one daemon start. The happy path only moves forward:
+ * Starting → RepoReady → DaemonUp → Online. Failed and Stopped end a run;
+ * the next start() begins again at Starting.
+ */
+export type StartupState = 'Starting' | 'RepoReady' | 'DaemonUp' | 'Online' | 'Failed' | 'Stopped';
+
+const FORWARD_ORDER: StartupState[] = ['Starting', 'RepoReady', 'DaemonUp', 'Online'];
+
+export interface StartupStateChange {
+  state: StartupState;
+  previous: StartupState;
+  at: string;
+  error?: string;
+}
+
+export class StartupStateMachine extends EventEmitter {
+  private label: string;
+  private state: StartupState = 'Stopped';
+  private run = 0;
+
+  constructor(label: string) {
+    super();
+    this.label = label;
+  }
+
+  getState(): StartupState {
+    return this.state;
+  }
+
+  /**
+   * Identifies the current run so async steps from an earlier start can
+   * tell they've been superseded.
+   */
+  getRun(): number {
+    return this.run;
+  }
+
+  begin(): number {
+    this.run++;
+    this.set('Starting');
+    return this.run;
+  }
+
+  /**
+   * Moves forward to `state`. Ignored if it belongs to an earlier run or
+   * would move backwards, so late async steps can't regress the state.
+   */
+  advance(state: StartupState, run: number = this.run): void {
+    if (run !== this.run) return;
+    const from = FORWARD_ORDER.indexOf(this.state);
+    const to = FORWARD_ORDER.indexOf(state);
+    if (from === -1 || to <= from) return;
+    this.set(state);
+  }
+
+  fail(error: Error, run: number = this.run): void {
+    if (run !== this.run) return;
+    this.set('Failed', error.message);
+  }
+
+  stopped(): void {
+    this.run++;
+    this.set('Stopped');
+  }
+
+  private set(state: StartupState, error?: string): void {
+    const previous = this.state;
+    if (previous === state && !error) return;
+    this.state = state;
+
+    const change: StartupStateChange = { state, previous, at: new Date().toISOString() };
+    if (error) change.error = error;
+    console.log(`[Startup] ${this.label}: ${previous} -> ${state}${error ? ` (${error})` : ''}`);
+    this.emit('state-changed', change);
+  }
+}
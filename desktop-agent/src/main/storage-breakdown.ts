@@ -0,0 +1,190 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { ContractPinner } from './contracts';
+import { StorageMonitor } from './storage-monitor';
+
+const INDEX_INTERVAL_MS = 60 * 60 * 1000;
+// Each size is a files/stat call on the daemon; enough at once to finish, few enough not to crowd challenges
+const STAT_CONCURRENCY = 4;
+const TOP_CIDS = 200;
+
+export interface StorageBreakdownOperations {
+  // Recursively pinned CIDs across every Kubo instance, with the name the user gave, if any
+  listPinned(): Promise<Array<{ cid: string; name: string | null }>>;
+  // The DAG's cumulative size, from files stat on the instance holding it
+  sizeOf(cid: string): Promise<number | null>;
+}
+
+export interface CidUsage {
+  cid: string;
+  // Null when the daemon couldn't stat it
+  bytes: number | null;
+  name: string | null;
+  contractIds: string[];
+}
+
+export interface ContractUsage {
+  contractId: string;
+  account: string | null;
+  cids: number;
+  bytes: number;
+  // Only counting CIDs no other contract or user pin also holds; what dropping it frees
+  exclusiveBytes: number;
+}
+
+export interface StorageBreakdown {
+  indexedAt: string | null;
+  indexing: boolean;
+  // The repo as Kubo reports it, and its quota
+  usedBytes: number | null;
+  maxBytes: number | null;
+  pinnedBytes: number;
+  contractBytes: number;
+  // Pins that belong to no contract: uploads, names, pins made by hand
+  otherBytes: number;
+  // Repo use no pin accounts for: unpinned blocks awaiting GC, MFS, datastore overhead
+  unattributedBytes: number | null;
+  unsizedCids: number;
+  contracts: ContractUsage[];
+  // Largest first
+  cids: CidUsage[];
+}
+
+/**
+ * Attributes repo disk use to the contracts and CIDs that take it up. Every
+ * recursive pin is sized once with `files stat` (a CID's DAG never changes,
+ * so sizes are kept in storage-sizes.json) and matched to the contracts it
+ * is pinned for. A contract's bytes count every CID it holds; its exclusive
+ * bytes only those nothing else needs, which is what dropping it would
+ * free. Sizes are per DAG, so blocks two DAGs share count for both and the
+ * pinned total can exceed what the repo uses.
+ */
+export class StorageBreakdownIndexer {
+  private filePath: string;
+  private ops: StorageBreakdownOperations;
+  private contracts: ContractPinner;
+  private storage: StorageMonitor;
+  private sizes: Record<string, number>;
+  private pinned: Array<{ cid: string; name: string | null }> = [];
+  private indexedAt: string | null = null;
+  private indexing: Promise<StorageBreakdown> | null = null;
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(dataDir: string, ops: StorageBreakdownOperations, contracts: ContractPinner, storage: StorageMonitor) {
+    this.filePath = path.join(dataDir, 'storage-sizes.json');
+    this.ops = ops;
+    this.contracts = contracts;
+    this.storage = storage;
+    this.sizes = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.index().catch((error) => console.error('[Storage] Indexing disk usage failed:', error.message));
+    }, INDEX_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  // From the last index; contracts are matched afresh, so it follows pins and removals in between
+  getBreakdown(): StorageBreakdown {
+    const usage = this.storage.getUsage();
+    const cids: CidUsage[] = this.pinned.map(({ cid, name }) => ({
+      cid,
+      bytes: this.sizes[cid] ?? null,
+      name,
+      contractIds: this.contracts.contractsFor(cid),
+    }));
+    const byCid = new Map(cids.map((entry) => [entry.cid, entry]));
+
+    const contracts: ContractUsage[] = this.contracts.list().map((record) => {
+      const held = Object.keys(record.cids).map((cid) => byCid.get(cid)).filter((entry): entry is CidUsage => !!entry);
+      return {
+        contractId: record.id,
+        account: record.account,
+        cids: held.length,
+        bytes: held.reduce((sum, entry) => sum + (entry.bytes ?? 0), 0),
+        exclusiveBytes: held
+          .filter((entry) => entry.contractIds.length === 1 && entry.name === null)
+          .reduce((sum, entry) => sum + (entry.bytes ?? 0), 0),
+      };
+    }).sort((a, b) => b.bytes - a.bytes);
+
+    const pinnedBytes = cids.reduce((sum, entry) => sum + (entry.bytes ?? 0), 0);
+    const contractBytes = cids
+      .filter((entry) => entry.contractIds.length > 0)
+      .reduce((sum, entry) => sum + (entry.bytes ?? 0), 0);
+    return {
+      indexedAt: this.indexedAt,
+      indexing: this.indexing !== null,
+      usedBytes: usage?.usedBytes ?? null,
+      maxBytes: usage?.maxBytes ?? null,
+      pinnedBytes,
+      contractBytes,
+      otherBytes: pinnedBytes - contractBytes,
+      unattributedBytes: usage ? Math.max(0, usage.usedBytes - pinnedBytes) : null,
+      unsizedCids: cids.filter((entry) => entry.bytes === null).length,
+      contracts,
+      cids: cids.sort((a, b) => (b.bytes ?? -1) - (a.bytes ?? -1)).slice(0, TOP_CIDS),
+    };
+  }
+
+  // Concurrent callers share one run
+  index(): Promise<StorageBreakdown> {
+    if (!this.indexing) {
+      this.indexing = this.indexAll().finally(() => {
+        this.indexing = null;
+      });
+    }
+    return this.indexing;
+  }
+
+  private async indexAll(): Promise<StorageBreakdown> {
+    const pinned = await this.ops.listPinned();
+    const unsized = pinned.map((pin) => pin.cid).filter((cid) => this.sizes[cid] === undefined);
+    let next = 0;
+    const worker = async () => {
+      while (next < unsized.length) {
+        const cid = unsized[next++];
+        const size = await this.ops.sizeOf(cid);
+        if (size !== null) this.sizes[cid] = size;
+      }
+    };
+    await Promise.all(Array.from({ length: STAT_CONCURRENCY }, worker));
+
+    // Sizes of CIDs no longer pinned are dropped, so the file tracks the pinset
+    const current = new Set(pinned.map((pin) => pin.cid));
+    for (const cid of Object.keys(this.sizes)) {
+      if (!current.has(cid)) delete this.sizes[cid];
+    }
+    this.pinned = pinned;
+    this.indexedAt = new Date().toISOString();
+    this.save();
+    if (unsized.length > 0) console.log(`[Storage] Sized ${unsized.length} new pins of ${pinned.length}`);
+    await this.storage.check().catch(() => null);
+    return this.getBreakdown();
+  }
+
+  private load(): Record<string, number> {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Storage] Failed to read pin sizes:', error);
+    }
+    return {};
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.sizes));
+    } catch (error) {
+      console.error('[Storage] Failed to save pin sizes:', error);
+    }
+  }
+}
@@ -0,0 +1,89 @@
+import { EventEmitter } from 'events';
+import { KuboManager } from './kubo';
+import { ConfigStore } from './config';
+import { showNotification } from './notifications';
+
+const CHECK_INTERVAL_MS = 5 * 60 * 1000;
+const WARN_THRESHOLDS = [0.8, 0.95];
+
+export interface StorageUsage {
+  usedBytes: number;
+  maxBytes: number;
+  ratio: number;
+  full: boolean;
+  acceptingContracts: boolean;
+}
+
+/**
+ * Watches repo usage against the StorageMax quota, notifies when usage
+ * crosses 80% / 95%, and optionally stops accepting new content when full.
+ */
+export class StorageMonitor extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private lastThreshold = 0;
+  private usage: StorageUsage | null = null;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => this.check(), CHECK_INTERVAL_MS);
+    void this.check();
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getUsage(): StorageUsage | null {
+    return this.usage;
+  }
+
+  /**
+   * False once the repo is full and the user opted to pause new contracts.
+   */
+  isAcceptingContracts(): boolean {
+    return this.usage?.acceptingContracts ?? true;
+  }
+
+  async check(): Promise<StorageUsage | null> {
+    const stats = await this.kubo.getStats();
+    if (!stats) return this.usage;
+
+    const maxBytes = stats.storageMax || this.config.getConfig().kubo.storageMaxGb * 1e9;
+    const ratio = maxBytes > 0 ? stats.repoSize / maxBytes : 0;
+    const full = ratio >= WARN_THRESHOLDS[WARN_THRESHOLDS.length - 1];
+
+    this.usage = {
+      usedBytes: stats.repoSize,
+      maxBytes,
+      ratio,
+      full,
+      acceptingContracts: !(full && this.config.getConfig().pauseContractsWhenFull),
+    };
+
+    const crossed = WARN_THRESHOLDS.filter((threshold) => ratio >= threshold).pop() || 0;
+    if (crossed > this.lastThreshold) {
+      const percent = Math.round(ratio * 100);
+      showNotification(
+        'SPK Storage Almost Full',
+        this.usage.acceptingContracts
+          ? `Your IPFS repo is ${percent}% of its storage limit. Increase the limit or free up space.`
+          : `Your IPFS repo is ${percent}% full. New storage contracts are paused until space is freed.`
+      );
+      this.emit('storage-warning', this.usage);
+    }
+    this.lastThreshold = crossed;
+
+    return this.usage;
+  }
+}
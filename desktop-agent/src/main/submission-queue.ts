@@ -0,0 +1,154 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import { ChallengeRecord } from './poa';
+
+const INITIAL_RETRY_MS = 250;
+const MAX_RETRY_MS = 5000;
+
+export interface PendingSubmission {
+  id: string;
+  validatorUrl: string;
+  // The answer exactly as it would have gone out
+  message: object;
+  // Booked once the answer is delivered or given up on
+  record: ChallengeRecord;
+  deadlineAt: string;
+  attempts: number;
+  lastError: string | null;
+}
+
+export type SubmissionSender = (validatorUrl: string, message: object) => Promise<void>;
+
+/**
+ * Answers that were computed but couldn't be sent, kept in
+ * pending-proofs.json and retried with jittered backoff until their
+ * deadline. Emits `settled` with the final challenge record, delivered or
+ * not, and `changed` with the number still at risk.
+ */
+export class SubmissionQueue extends EventEmitter {
+  private filePath: string;
+  private send: SubmissionSender;
+  private pending = new Map<string, PendingSubmission>();
+  private timers = new Map<string, NodeJS.Timeout>();
+
+  constructor(dataDir: string, send: SubmissionSender) {
+    super();
+    this.filePath = path.join(dataDir, 'pending-proofs.json');
+    this.send = send;
+  }
+
+  /**
+   * Picks up answers left from the last run. Ones past their deadline are
+   * settled as failed straight away, so they still show in the history.
+   */
+  start(): void {
+    for (const entry of this.load()) {
+      this.pending.set(entry.id, entry);
+      this.schedule(entry, 0);
+    }
+    if (this.pending.size > 0) {
+      console.log(`[PoA] Retrying ${this.pending.size} unsent challenge answers`);
+      this.emit('changed', this.pending.size);
+    }
+  }
+
+  stop(): void {
+    for (const timer of this.timers.values()) clearTimeout(timer);
+    this.timers.clear();
+    this.save();
+  }
+
+  getPending(): PendingSubmission[] {
+    return Array.from(this.pending.values());
+  }
+
+  enqueue(validatorUrl: string, message: object, record: ChallengeRecord, deadlineAt: number, error: string): void {
+    const entry: PendingSubmission = {
+      id: crypto.randomUUID(),
+      validatorUrl,
+      message,
+      record,
+      deadlineAt: new Date(deadlineAt).toISOString(),
+      attempts: 1,
+      lastError: error,
+    };
+    this.pending.set(entry.id, entry);
+    this.save();
+    console.warn(`[PoA] Could not send proof for ${record.cid} to ${validatorUrl}, retrying: ${error}`);
+    this.emit('changed', this.pending.size);
+    this.schedule(entry, this.backoff(entry.attempts));
+  }
+
+  // A validator came back; its answers go now rather than at the next backoff
+  flush(validatorUrl: string): void {
+    for (const entry of this.pending.values()) {
+      if (entry.validatorUrl === validatorUrl) this.schedule(entry, 0);
+    }
+  }
+
+  private schedule(entry: PendingSubmission, delayMs: number): void {
+    const existing = this.timers.get(entry.id);
+    if (existing) clearTimeout(existing);
+    const remaining = new Date(entry.deadlineAt).getTime() - Date.now();
+    this.timers.set(entry.id, setTimeout(() => {
+      this.timers.delete(entry.id);
+      this.attempt(entry).catch(() => undefined);
+    }, Math.max(0, Math.min(delayMs, remaining))));
+  }
+
+  private async attempt(entry: PendingSubmission): Promise<void> {
+    if (!this.pending.has(entry.id)) return;
+    if (Date.now() >= new Date(entry.deadlineAt).getTime()) {
+      return this.settle(entry, `Could not send the proof before the deadline: ${entry.lastError}`);
+    }
+    try {
+      await this.send(entry.validatorUrl, entry.message);
+    } catch (error: any) {
+      entry.attempts++;
+      entry.lastError = error.message;
+      return this.schedule(entry, this.backoff(entry.attempts));
+    }
+    console.log(`[PoA] Sent proof for ${entry.record.cid} after ${entry.attempts} attempts`);
+    this.settle(entry, null);
+  }
+
+  private settle(entry: PendingSubmission, error: string | null): void {
+    this.pending.delete(entry.id);
+    this.save();
+    const receivedAt = new Date(entry.record.at).getTime();
+    this.emit('settled', {
+      ...entry.record,
+      passed: error === null,
+      responseTime: Date.now() - receivedAt,
+      error,
+    });
+    this.emit('changed', this.pending.size);
+  }
+
+  // Half of each wait is random, so answers queued in the same outage don't retry in lockstep
+  private backoff(attempts: number): number {
+    const ceiling = Math.min(MAX_RETRY_MS, INITIAL_RETRY_MS * 2 ** (attempts - 1));
+    return Math.round(ceiling / 2 + Math.random() * ceiling / 2);
+  }
+
+  private load(): PendingSubmission[] {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[PoA] Failed to read pending proofs:', error);
+    }
+    return [];
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.getPending(), null, 2));
+    } catch (error) {
+      console.error('[PoA] Failed to save pending proofs:', error);
+    }
+  }
+}
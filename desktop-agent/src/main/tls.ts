@@ -0,0 +1,188 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { execFile } from 'child_process';
+import { promisify } from 'util';
+import forge from 'node-forge';
+
+const execFileAsync = promisify(execFile);
+
+export interface TlsSettings {
+  enabled: boolean;
+  // HTTPS listens here; plain HTTP stays on apiPort for existing clients
+  port: number;
+}
+
+export const DEFAULT_TLS_SETTINGS: TlsSettings = {
+  enabled: false,
+  port: 5443,
+};
+
+const CA_VALIDITY_YEARS = 10;
+// Apple rejects server certificates valid for longer than 825 days
+const LEAF_VALIDITY_DAYS = 825;
+const RENEW_BEFORE_DAYS = 30;
+
+export interface TlsCredentials {
+  key: string;
+  cert: string;
+}
+
+export interface TrustResult {
+  trusted: boolean;
+  // Set when the CA couldn't be installed automatically
+  manualSteps?: string;
+}
+
+export function validateTlsSettings(settings: Partial<TlsSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.port !== undefined && (!Number.isInteger(settings.port) || settings.port < 1024 || settings.port > 65535)) {
+    errors.push('port must be between 1024 and 65535');
+  }
+  return errors;
+}
+
+function serialNumber(): string {
+  // Positive, 16 bytes; a leading 0 nibble keeps it from being read as negative
+  return '0' + crypto.randomBytes(15).toString('hex');
+}
+
+/**
+ * mkcert-style local certificates for the HTTPS API. A private CA is
+ * created once per install and, with the user's consent, added to the
+ * OS trust store; it then issues a certificate for localhost/127.0.0.1.
+ *
+ * The CA key never leaves ~/.spk-ipfs/tls and is only readable by the
+ * user, but anyone who can read it can mint certificates this machine
+ * trusts, which is why trusting it is a separate, explicit step.
+ */
+export class CertificateManager {
+  private dir: string;
+
+  constructor(dataDir: string) {
+    this.dir = path.join(dataDir, 'tls');
+  }
+
+  getCaPath(): string {
+    return path.join(this.dir, 'ca.pem');
+  }
+
+  /**
+   * Returns the server key and certificate, creating the CA and reissuing
+   * the leaf when it's missing or about to expire.
+   */
+  ensureCredentials(): TlsCredentials {
+    fs.mkdirSync(this.dir, { recursive: true, mode: 0o700 });
+    const ca = this.loadOrCreateCa();
+
+    const keyPath = path.join(this.dir, 'localhost.key');
+    const certPath = path.join(this.dir, 'localhost.pem');
+    if (fs.existsSync(keyPath) && fs.existsSync(certPath)) {
+      const cert = forge.pki.certificateFromPem(fs.readFileSync(certPath, 'utf-8'));
+      const renewAt = cert.validity.notAfter.getTime() - RENEW_BEFORE_DAYS * 24 * 60 * 60 * 1000;
+      if (Date.now() < renewAt && ca.cert.verify(cert)) {
+        return { key: fs.readFileSync(keyPath, 'utf-8'), cert: fs.readFileSync(certPath, 'utf-8') };
+      }
+    }
+
+    console.log('[TLS] Issuing localhost certificate');
+    const keys = forge.pki.rsa.generateKeyPair(2048);
+    const cert = forge.pki.createCertificate();
+    cert.publicKey = keys.publicKey;
+    cert.serialNumber = serialNumber();
+    cert.validity.notBefore = new Date(Date.now() - 60 * 60 * 1000);
+    cert.validity.notAfter = new Date(Date.now() + LEAF_VALIDITY_DAYS * 24 * 60 * 60 * 1000);
+    cert.setSubject([{ name: 'commonName', value: 'localhost' }, { name: 'organizationName', value: 'SPK Desktop Agent' }]);
+    cert.setIssuer(ca.cert.subject.attributes);
+    cert.setExtensions([
+      { name: 'basicConstraints', cA: false },
+      { name: 'keyUsage', digitalSignature: true, keyEncipherment: true },
+      { name: 'extKeyUsage', serverAuth: true },
+      {
+        name: 'subjectAltName',
+        altNames: [
+          { type: 2, value: 'localhost' },
+          { type: 7, ip: '127.0.0.1' },
+          { type: 7, ip: '::1' },
+        ],
+      },
+    ]);
+    cert.sign(ca.key, forge.md.sha256.create());
+
+    const key = forge.pki.privateKeyToPem(keys.privateKey);
+    const certPem = forge.pki.certificateToPem(cert);
+    fs.writeFileSync(keyPath, key, { mode: 0o600 });
+    fs.writeFileSync(certPath, certPem);
+    return { key, cert: certPem };
+  }
+
+  getCaFingerprint(): string | null {
+    if (!fs.existsSync(this.getCaPath())) return null;
+    const pem = fs.readFileSync(this.getCaPath(), 'utf-8');
+    return new crypto.X509Certificate(pem).fingerprint256;
+  }
+
+  /**
+   * Adds the CA to the current user's trust store. The OS shows its own
+   * confirmation on macOS and Windows; Linux has no single store, so we
+   * return instructions instead.
+   */
+  async trustCa(): Promise<TrustResult> {
+    this.ensureCredentials();
+    const caPath = this.getCaPath();
+
+    try {
+      if (process.platform === 'darwin') {
+        const keychain = path.join(process.env.HOME || '', 'Library/Keychains/login.keychain-db');
+        await execFileAsync('security', ['add-trusted-cert', '-r', 'trustRoot', '-k', keychain, caPath]);
+      } else if (process.platform === 'win32') {
+        await execFileAsync('certutil', ['-user', '-addstore', 'Root', caPath]);
+      } else {
+        return {
+          trusted: false,
+          manualSteps: `Import ${caPath} as a trusted authority in your browser, or run:\n`
+            + `  sudo cp ${caPath} /usr/local/share/ca-certificates/spk-desktop-agent.crt && sudo update-ca-certificates`,
+        };
+      }
+      console.log('[TLS] Installed local CA into the user trust store');
+      return { trusted: true };
+    } catch (error: any) {
+      console.error('[TLS] Failed to install local CA:', error.message);
+      return { trusted: false, manualSteps: `Import ${caPath} as a trusted root certificate.` };
+    }
+  }
+
+  private loadOrCreateCa(): { key: forge.pki.rsa.PrivateKey; cert: forge.pki.Certificate } {
+    const keyPath = path.join(this.dir, 'ca.key');
+    const certPath = this.getCaPath();
+    if (fs.existsSync(keyPath) && fs.existsSync(certPath)) {
+      return {
+        key: forge.pki.privateKeyFromPem(fs.readFileSync(keyPath, 'utf-8')) as forge.pki.rsa.PrivateKey,
+        cert: forge.pki.certificateFromPem(fs.readFileSync(certPath, 'utf-8')),
+      };
+    }
+
+    console.log('[TLS] Creating local certificate authority');
+    const keys = forge.pki.rsa.generateKeyPair(2048);
+    const cert = forge.pki.createCertificate();
+    cert.publicKey = keys.publicKey;
+    cert.serialNumber = serialNumber();
+    cert.validity.notBefore = new Date(Date.now() - 60 * 60 * 1000);
+    cert.validity.notAfter = new Date(Date.now() + CA_VALIDITY_YEARS * 365 * 24 * 60 * 60 * 1000);
+    const subject = [
+      { name: 'commonName', value: `SPK Desktop Agent Local CA ${crypto.randomBytes(4).toString('hex')}` },
+      { name: 'organizationName', value: 'SPK Desktop Agent' },
+    ];
+    cert.setSubject(subject);
+    cert.setIssuer(subject);
+    cert.setExtensions([
+      { name: 'basicConstraints', cA: true, pathLenConstraint: 0, critical: true },
+      { name: 'keyUsage', keyCertSign: true, cRLSign: true, critical: true },
+    ]);
+    cert.sign(keys.privateKey, forge.md.sha256.create());
+
+    fs.writeFileSync(keyPath, forge.pki.privateKeyToPem(keys.privateKey), { mode: 0o600 });
+    fs.writeFileSync(certPath, forge.pki.certificateToPem(cert));
+    return { key: keys.privateKey, cert };
+  }
+}
@@ -0,0 +1,155 @@
+import { ConfigStore } from './config';
+import { ContractPinner, TrashedContract } from './contracts';
+import { EventBus } from './event-bus';
+import { ApiError } from './api-errors';
+
+const CHECK_INTERVAL_MS = 60 * 60 * 1000;
+const HOUR_MS = 60 * 60 * 1000;
+
+export interface TrashSettings {
+  // How long a trashed contract's content stays pinned; 0 unpins it straight away
+  retentionHours: number;
+}
+
+export const DEFAULT_TRASH_SETTINGS: TrashSettings = {
+  retentionHours: 7 * 24,
+};
+
+export function validateTrashSettings(settings: Partial<TrashSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.retentionHours !== undefined
+    && (!Number.isInteger(settings.retentionHours) || settings.retentionHours < 0 || settings.retentionHours > 24 * 365)) {
+    errors.push('retentionHours must be between 0 and 8760');
+  }
+  return errors;
+}
+
+export interface TrashEntry extends TrashedContract {
+  purgeAt: string;
+  // CIDs the chain still lists under one of the node's accounts; the entry isn't purged while there are any
+  liableCids: string[];
+}
+
+export interface TrashPurge {
+  contractId: string;
+  unpinned: number;
+  // CIDs still held for a live or other trashed contract, or by the user
+  kept: number;
+  errors: string[];
+}
+
+export interface TrashOperations {
+  unpin(cid: string): Promise<void>;
+  heldOutsideContracts(cid: string): boolean;
+  // CIDs on-chain contracts still assign this node; null until the chain state has synced
+  obligations(): Set<string> | null;
+}
+
+/**
+ * Empties the contract trash once each entry's retention window has
+ * passed. Expired, orphaned or removed contracts land there rather than
+ * being unpinned on the spot, so a mistaken removal, or an expiry the
+ * chain hasn't caught up with, can be undone with a restore. Before
+ * unpinning, every CID is checked against the on-chain contracts; an
+ * entry is kept while any of its CIDs is still owed, and while the chain
+ * state is unknown.
+ */
+export class TrashCollector {
+  private config: ConfigStore;
+  private contracts: ContractPinner;
+  private ops: TrashOperations;
+  private events: EventBus;
+  private timer: NodeJS.Timeout | null = null;
+  private purging = false;
+
+  constructor(config: ConfigStore, contracts: ContractPinner, ops: TrashOperations, events: EventBus) {
+    this.config = config;
+    this.contracts = contracts;
+    this.ops = ops;
+    this.events = events;
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.collect().catch((error) => console.error('[Contracts] Emptying the trash failed:', error.message));
+    }, CHECK_INTERVAL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  // True when contracts go to the trash rather than being unpinned directly
+  isEnabled(): boolean {
+    return this.config.getConfig().trash.retentionHours > 0;
+  }
+
+  list(): TrashEntry[] {
+    const retention = this.config.getConfig().trash.retentionHours * HOUR_MS;
+    const obligations = this.ops.obligations();
+    return this.contracts.listTrash().map((entry) => ({
+      ...entry,
+      purgeAt: new Date(new Date(entry.trashedAt).getTime() + retention).toISOString(),
+      liableCids: obligations ? Object.keys(entry.record.cids).filter((cid) => obligations.has(cid)) : [],
+    }));
+  }
+
+  // Purges every entry past its retention window
+  async collect(): Promise<TrashPurge[]> {
+    const due = this.list().filter((entry) => new Date(entry.purgeAt).getTime() <= Date.now());
+    const purges: TrashPurge[] = [];
+    for (const entry of due) {
+      try {
+        purges.push(await this.purge(entry.record.id));
+      } catch (error: any) {
+        console.log(`[Contracts] Keeping ${entry.record.id} in the trash: ${error.message}`);
+      }
+    }
+    return purges;
+  }
+
+  /**
+   * Unpins a trashed contract's content now and forgets it, unless the
+   * chain still lists any of it for this node.
+   */
+  async purge(contractId: string): Promise<TrashPurge> {
+    if (this.purging) throw new ApiError('CONFLICT', 'The trash is already being emptied');
+    const entry = this.contracts.listTrash().find(({ record }) => record.id === contractId);
+    if (!entry) throw new ApiError('NOT_FOUND', 'No such contract in the trash');
+    const obligations = this.ops.obligations();
+    if (!obligations) throw new ApiError('CONFLICT', 'SPK contract state has not synced yet, so liability cannot be checked');
+    const liable = Object.keys(entry.record.cids).filter((cid) => obligations.has(cid));
+    if (liable.length > 0) {
+      throw new ApiError('CONFLICT', `The chain still assigns ${liable.length} of its CIDs to this node; restore it instead`);
+    }
+
+    this.purging = true;
+    try {
+      const purge: TrashPurge = { contractId, unpinned: 0, kept: 0, errors: [] };
+      for (const cid of Object.keys(entry.record.cids)) {
+        const stillWanted = this.contracts.contractsFor(cid).length > 0
+          || this.contracts.trashedFor(cid).some((id) => id !== contractId)
+          || this.ops.heldOutsideContracts(cid);
+        if (stillWanted) {
+          purge.kept++;
+          continue;
+        }
+        try {
+          await this.ops.unpin(cid);
+          purge.unpinned++;
+        } catch (error: any) {
+          purge.errors.push(`${cid}: ${error.message}`);
+        }
+      }
+      // Kept for the next run to retry what failed
+      if (purge.errors.length === 0) this.contracts.purge(contractId);
+      console.log(`[Contracts] Emptied ${contractId} from the trash: unpinned ${purge.unpinned} CIDs, kept ${purge.kept}`);
+      this.events.publish('pins', 'trash-purged', purge);
+      return purge;
+    } finally {
+      this.purging = false;
+    }
+  }
+}
@@ -0,0 +1,334 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as crypto from 'crypto';
+import { EventEmitter } from 'events';
+import { Operation, SignedTransaction, cryptoUtils } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+import { HiveAccount, HiveKeyRole } from './hive';
+import { HiveSigner, HiveSigningMethod, TX_EXPIRATION_MS } from './hive-signing';
+import { showNotification } from './notifications';
+
+const POLL_MS = 3000;
+const MAX_ATTEMPTS = 5;
+const RETRY_BASE_MS = 15 * 1000;
+const RETRY_MAX_MS = 5 * 60 * 1000;
+// A witness may include a transaction a little past its expiration's wall-clock time
+const EXPIRATION_GRACE_MS = 30 * 1000;
+// Past this, a transaction still not on chain stops being retried
+const QUEUE_DEADLINE_MS = 30 * 60 * 1000;
+const MAX_FINISHED = 200;
+
+export type QueuedTransactionStatus = 'pending' | 'broadcast' | 'included' | 'failed' | 'cancelled';
+
+export interface QueuedTransaction {
+  id: string;
+  // What queued it, e.g. 'registration' or 'rewards'
+  source: string;
+  description: string;
+  operations: Operation[];
+  role: HiveKeyRole;
+  // Signed for this account; it must still be the active one to retry
+  account: string;
+  status: QueuedTransactionStatus;
+  method: HiveSigningMethod | null;
+  attempts: number;
+  txId: string | null;
+  // Kept when signed with stored keys, so a lost broadcast resends the same transaction
+  signed: SignedTransaction | null;
+  // When the current transaction can no longer be included
+  expiresAt: string | null;
+  blockNum: number | null;
+  error: string | null;
+  createdAt: string;
+  updatedAt: string;
+  nextAttemptAt: string;
+  deadline: string;
+}
+
+export interface QueuedTransactionResult {
+  txId: string;
+  method: HiveSigningMethod;
+  blockNum: number | null;
+}
+
+export interface TransactionRequest {
+  source: string;
+  description: string;
+  operations: Operation[];
+  role: HiveKeyRole;
+}
+
+interface Waiter {
+  resolve: (result: QueuedTransactionResult) => void;
+  reject: (error: Error) => void;
+  onBroadcast?: (txId: string) => void;
+}
+
+function isFinished(entry: QueuedTransaction): boolean {
+  return entry.status === 'included' || entry.status === 'failed' || entry.status === 'cancelled';
+}
+
+/**
+ * Every transaction the agent puts on Hive goes through here rather than
+ * being signed and forgotten. Entries are kept in tx-queue.json, so one
+ * cut short by a restart carries on. After a broadcast the queue watches
+ * for inclusion; a transaction that expires unincluded (missed blocks, a
+ * node that dropped it) is signed again with a fresh expiration and sent,
+ * with backoff, up to five times or for half an hour. A broadcast that
+ * never reached a node resends the same signed transaction, so it can't
+ * land twice. Errors the chain returns fail the entry straight away.
+ *
+ * Each change is emitted as `update`; a final failure also as `failed`.
+ */
+export class TransactionQueue extends EventEmitter {
+  private filePath: string;
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private signer: HiveSigner;
+  private entries: QueuedTransaction[];
+  private waiters = new Map<string, Waiter>();
+  private timer: NodeJS.Timeout | null = null;
+  private processing = false;
+
+  constructor(dataDir: string, config: ConfigStore, account: HiveAccount, signer: HiveSigner) {
+    super();
+    this.filePath = path.join(dataDir, 'tx-queue.json');
+    this.config = config;
+    this.account = account;
+    this.signer = signer;
+    this.entries = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.process().catch((error) => console.error('[Hive] Transaction queue failed:', error.message));
+    }, POLL_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+  }
+
+  // Newest first
+  list(): QueuedTransaction[] {
+    return [...this.entries].reverse();
+  }
+
+  /**
+   * Queues the transaction and resolves once Hive includes it, or rejects
+   * when it finally fails. `onBroadcast` hears each broadcast's ID.
+   */
+  submit(request: TransactionRequest, onBroadcast?: (txId: string) => void): Promise<QueuedTransactionResult> {
+    const account = this.config.getConfig().hiveUsername;
+    if (!account) return Promise.reject(new Error('Log in with a Hive account first'));
+    const now = new Date();
+    const entry: QueuedTransaction = {
+      id: crypto.randomUUID(),
+      ...request,
+      account,
+      status: 'pending',
+      method: null,
+      attempts: 0,
+      txId: null,
+      signed: null,
+      expiresAt: null,
+      blockNum: null,
+      error: null,
+      createdAt: now.toISOString(),
+      updatedAt: now.toISOString(),
+      nextAttemptAt: now.toISOString(),
+      deadline: new Date(now.getTime() + QUEUE_DEADLINE_MS).toISOString(),
+    };
+    this.entries.push(entry);
+    const result = new Promise<QueuedTransactionResult>((resolve, reject) => {
+      this.waiters.set(entry.id, { resolve, reject, onBroadcast });
+    });
+    this.changed(entry);
+    this.process().catch(() => undefined);
+    return result;
+  }
+
+  // Queues a failed or cancelled transaction again, with a fresh set of attempts
+  retry(id: string): QueuedTransaction {
+    const entry = this.find(id);
+    if (entry.status !== 'failed' && entry.status !== 'cancelled') {
+      throw new Error('Only failed or cancelled transactions can be retried');
+    }
+    const now = Date.now();
+    Object.assign(entry, {
+      status: 'pending',
+      attempts: 0,
+      signed: null,
+      txId: null,
+      expiresAt: null,
+      error: null,
+      nextAttemptAt: new Date(now).toISOString(),
+      deadline: new Date(now + QUEUE_DEADLINE_MS).toISOString(),
+    });
+    this.changed(entry);
+    this.process().catch(() => undefined);
+    return entry;
+  }
+
+  // Only before it is broadcast; once on the wire it may still be included
+  cancel(id: string): QueuedTransaction {
+    const entry = this.find(id);
+    if (entry.status !== 'pending') throw new Error('Only transactions waiting to be broadcast can be cancelled');
+    this.finish(entry, 'cancelled', 'Cancelled');
+    return entry;
+  }
+
+  private async process(): Promise<void> {
+    // One at a time: Keychain and HAS each need the user, and an account's transactions stay in order
+    if (this.processing) return;
+    this.processing = true;
+    try {
+      for (const entry of this.entries.filter((candidate) => !isFinished(candidate))) {
+        if (entry.status === 'broadcast') await this.checkInclusion(entry);
+        else if (Date.now() >= new Date(entry.nextAttemptAt).getTime()) await this.attempt(entry);
+      }
+    } finally {
+      this.processing = false;
+    }
+  }
+
+  private async attempt(entry: QueuedTransaction): Promise<void> {
+    if (Date.now() > new Date(entry.deadline).getTime()) {
+      this.finish(entry, 'failed', entry.error ?? 'Not included before the queue gave up');
+      return;
+    }
+    if (this.config.getConfig().hiveUsername !== entry.account) {
+      this.finish(entry, 'failed', `@${entry.account} is no longer the active account; switch back to retry`);
+      return;
+    }
+
+    const method = this.config.getConfig().hiveSigning.method;
+    entry.method = method;
+    entry.attempts++;
+    try {
+      if (method === 'keys') {
+        // A still-valid signed transaction is resent as is, so it can't be included twice
+        if (!entry.signed || Date.now() > new Date(`${entry.signed.expiration}Z`).getTime()) {
+          entry.signed = await this.signer.sign(entry.operations, entry.role);
+          entry.txId = cryptoUtils.generateTrxId(entry.signed);
+          entry.expiresAt = `${entry.signed.expiration}Z`;
+          this.changed(entry);
+        }
+        await this.account.getClient().broadcast.send(entry.signed).catch((error) => {
+          // Already accepted by an earlier send whose answer was lost
+          if (/duplicate/i.test(error.message)) return;
+          throw error;
+        });
+      } else {
+        entry.txId = (await this.signer.broadcast(entry.description, entry.operations, entry.role)).txId;
+        entry.expiresAt = new Date(Date.now() + TX_EXPIRATION_MS).toISOString();
+      }
+    } catch (error: any) {
+      // The chain rejected it, or the user declined: trying again won't help
+      if (error.name === 'RPCError' || error.message === 'Cancelled') {
+        this.finish(entry, 'failed', error.message);
+      } else {
+        this.scheduleRetry(entry, error.message);
+      }
+      return;
+    }
+
+    entry.status = 'broadcast';
+    entry.error = null;
+    console.log(`[Hive] Broadcast ${entry.description} (attempt ${entry.attempts}): ${entry.txId}`);
+    this.waiters.get(entry.id)?.onBroadcast?.(entry.txId!);
+    this.changed(entry);
+  }
+
+  private async checkInclusion(entry: QueuedTransaction): Promise<void> {
+    const client = this.account.getClient();
+    // The expiration lets the node tell an expired transaction from one it never saw
+    const { status } = await client.transaction.findTransaction(entry.txId!, entry.expiresAt?.slice(0, -5))
+      .catch(() => ({ status: 'unknown' }));
+    if (status === 'within_reversible_block' || status === 'within_irreversible_block') {
+      const transaction = await client.database.getTransaction(entry.txId!).catch(() => null);
+      entry.blockNum = transaction?.block_num ?? null;
+      this.finish(entry, 'included', null);
+      return;
+    }
+    const expired = status === 'expired_reversible' || status === 'expired_irreversible' || status === 'too_old'
+      || (status === 'unknown' && Date.now() > new Date(entry.expiresAt!).getTime() + EXPIRATION_GRACE_MS);
+    if (expired) {
+      entry.signed = null;
+      this.scheduleRetry(entry, 'Expired before a block included it');
+    }
+  }
+
+  private scheduleRetry(entry: QueuedTransaction, error: string): void {
+    if (entry.attempts >= MAX_ATTEMPTS) {
+      this.finish(entry, 'failed', `${error} (gave up after ${entry.attempts} attempts)`);
+      return;
+    }
+    const delay = Math.min(RETRY_MAX_MS, RETRY_BASE_MS * 2 ** (entry.attempts - 1));
+    console.warn(`[Hive] ${entry.description} failed (${error}); retrying in ${Math.round(delay / 1000)}s`);
+    entry.status = 'pending';
+    entry.error = error;
+    entry.nextAttemptAt = new Date(Date.now() + delay).toISOString();
+    this.changed(entry);
+  }
+
+  private finish(entry: QueuedTransaction, status: 'included' | 'failed' | 'cancelled', error: string | null): void {
+    entry.status = status;
+    entry.error = error;
+    entry.signed = null;
+    this.changed(entry);
+
+    const waiter = this.waiters.get(entry.id);
+    this.waiters.delete(entry.id);
+    if (status === 'included') {
+      console.log(`[Hive] ${entry.description} included in block ${entry.blockNum ?? 'unknown'} (${entry.txId})`);
+      waiter?.resolve({ txId: entry.txId!, method: entry.method!, blockNum: entry.blockNum });
+      return;
+    }
+    waiter?.reject(new Error(error ?? status));
+    if (status === 'failed') {
+      console.error(`[Hive] ${entry.description} failed: ${error}`);
+      this.emit('failed', entry);
+      // Whoever queued it reports its own failure; this covers entries resumed after a restart
+      if (!waiter) showNotification('Transaction failed', `${entry.description}: ${error}. Retry it from the dashboard.`);
+    }
+  }
+
+  private find(id: string): QueuedTransaction {
+    const entry = this.entries.find((candidate) => candidate.id === id);
+    if (!entry) throw new Error(`No queued transaction ${id}`);
+    return entry;
+  }
+
+  private changed(entry: QueuedTransaction): void {
+    entry.updatedAt = new Date().toISOString();
+    const finished = this.entries.filter(isFinished);
+    if (finished.length > MAX_FINISHED) {
+      const drop = new Set(finished.slice(0, finished.length - MAX_FINISHED));
+      this.entries = this.entries.filter((candidate) => !drop.has(candidate));
+    }
+    this.save();
+    this.emit('update', entry);
+  }
+
+  private load(): QueuedTransaction[] {
+    try {
+      if (fs.existsSync(this.filePath)) {
+        return JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      }
+    } catch (error) {
+      console.error('[Hive] Failed to read the transaction queue:', error);
+    }
+    return [];
+  }
+
+  private save(): void {
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.entries, null, 2));
+    } catch (error) {
+      console.error('[Hive] Failed to save the transaction queue:', error);
+    }
+  }
+}
@@ -0,0 +1,280 @@
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import * as http from 'http';
+import * as crypto from 'crypto';
+import { Readable } from 'stream';
+import axios from 'axios';
+
+// Partial uploads nobody resumed within a day are discarded
+const UPLOAD_TTL_MS = 24 * 60 * 60 * 1000;
+
+export interface AddResult {
+  cid: string;
+  name: string;
+  size: number;
+  carSize: number;
+}
+
+export interface AddProgress {
+  uploadId: string;
+  name: string;
+  bytes: number;
+  total: number | null;
+}
+
+export interface ResumableUpload {
+  id: string;
+  name: string;
+  length: number;
+  offset: number;
+  filePath: string;
+  createdAt: number;
+}
+
+export class UploadOffsetError extends Error {
+  expected: number;
+
+  constructor(expected: number) {
+    super(`Upload-Offset must be ${expected}`);
+    this.name = 'UploadOffsetError';
+    this.expected = expected;
+  }
+}
+
+function parseNdjson(body: string): any[] {
+  return body
+    .split('\n')
+    .filter((line) => line.trim().length > 0)
+    .map((line) => {
+      try {
+        return JSON.parse(line);
+      } catch {
+        return null;
+      }
+    })
+    .filter(Boolean);
+}
+
+/**
+ * Streams `source` into Kubo's `add` as a single-file multipart body, so
+ * large files never have to sit in memory. Kubo reports progress as NDJSON
+ * lines with `Bytes`; the final line carries the CID.
+ */
+export function addStream(
+  apiUrl: string,
+  source: Readable,
+  name: string,
+  onProgress: (bytes: number) => void
+): Promise<{ cid: string; size: number }> {
+  const boundary = `spk-${crypto.randomBytes(12).toString('hex')}`;
+  const safeName = encodeURIComponent(name);
+  const url = new URL(`${apiUrl}/api/v0/add?pin=true&cid-version=1&progress=true`);
+
+  return new Promise((resolve, reject) => {
+    const req = http.request(url, {
+      method: 'POST',
+      headers: { 'Content-Type': `multipart/form-data; boundary=${boundary}` },
+    }, (res) => {
+      let buffered = '';
+      let result: { cid: string; size: number } | null = null;
+
+      res.setEncoding('utf8');
+      res.on('data', (chunk: string) => {
+        buffered += chunk;
+        const lines = buffered.split('\n');
+        buffered = lines.pop() || '';
+        for (const entry of parseNdjson(lines.join('\n'))) {
+          if (entry.Hash) {
+            result = { cid: entry.Hash, size: Number(entry.Size) || 0 };
+          } else if (entry.Bytes !== undefined) {
+            onProgress(entry.Bytes);
+          }
+        }
+      });
+      res.on('end', () => {
+        for (const entry of parseNdjson(buffered)) {
+          if (entry.Hash) result = { cid: entry.Hash, size: Number(entry.Size) || 0 };
+        }
+        if (res.statusCode !== 200) {
+          reject(new Error(`ipfs add failed with HTTP ${res.statusCode}`));
+        } else if (!result) {
+          reject(new Error('ipfs add returned no CID'));
+        } else {
+          resolve(result);
+        }
+      });
+    });
+
+    req.on('error', reject);
+    source.on('error', (error) => req.destroy(error));
+
+    req.write(`--${boundary}\r\n`
+      + `Content-Disposition: form-data; name="file"; filename="${safeName}"\r\n`
+      + 'Content-Type: application/octet-stream\r\n\r\n');
+    source.on('end', () => req.end(`\r\n--${boundary}--\r\n`));
+    source.pipe(req, { end: false });
+  });
+}
+
+/**
+ * Size of the CAR export of a DAG: what a retrieval client or another node
+ * would actually transfer, which is larger than the file for chunked content.
+ */
+export async function getCarSize(apiUrl: string, cid: string): Promise<number> {
+  const response = await axios.post(`${apiUrl}/api/v0/dag/export?arg=${cid}`, null, {
+    responseType: 'stream',
+    timeout: 0,
+  });
+
+  return new Promise((resolve, reject) => {
+    let size = 0;
+    response.data.on('data', (chunk: Buffer) => { size += chunk.length; });
+    response.data.on('end', () => resolve(size));
+    response.data.on('error', reject);
+  });
+}
+
+/**
+ * Streams a CAR into Kubo's `dag import`, pinning its root. The multipart
+ * body is written as the export produces it, so a large DAG is never held
+ * in memory.
+ */
+export function importCar(apiUrl: string, source: Readable): Promise<{ roots: string[]; bytes: number }> {
+  const boundary = `spk-${crypto.randomBytes(12).toString('hex')}`;
+  const url = new URL(`${apiUrl}/api/v0/dag/import?pin-roots=true&stats=true`);
+
+  return new Promise((resolve, reject) => {
+    const req = http.request(url, {
+      method: 'POST',
+      headers: { 'Content-Type': `multipart/form-data; boundary=${boundary}` },
+    }, (res) => {
+      let body = '';
+      res.setEncoding('utf8');
+      res.on('data', (chunk: string) => { body += chunk; });
+      res.on('end', () => {
+        if (res.statusCode !== 200) {
+          reject(new Error(`dag import failed with HTTP ${res.statusCode}: ${body.trim().slice(0, 200)}`));
+          return;
+        }
+        const roots: string[] = [];
+        let bytes = 0;
+        for (const line of body.split('\n').filter((entry) => entry.trim())) {
+          try {
+            const entry = JSON.parse(line);
+            if (entry.Root?.PinErrorMsg) {
+              reject(new Error(entry.Root.PinErrorMsg));
+              return;
+            }
+            if (entry.Root?.Cid?.['/']) roots.push(entry.Root.Cid['/']);
+            if (entry.Stats?.BlockBytesCount) bytes = Number(entry.Stats.BlockBytesCount);
+          } catch {
+            // Not a result line
+          }
+        }
+        resolve({ roots, bytes });
+      });
+    });
+    req.on('error', reject);
+    source.on('error', (error) => req.destroy(error));
+
+    req.write(`--${boundary}\r\n`
+      + 'Content-Disposition: form-data; name="file"; filename="import.car"\r\n'
+      + 'Content-Type: application/vnd.ipld.car\r\n\r\n');
+    source.on('end', () => req.end(`\r\n--${boundary}--\r\n`));
+    source.pipe(req, { end: false });
+  });
+}
+
+/**
+ * TUS-style resumable uploads: the client declares the length, then sends
+ * the bytes in PATCH chunks, each starting at the current offset. A client
+ * that lost its connection asks for the offset and carries on from there.
+ * Chunks are staged on disk and handed to Kubo once the last one arrives.
+ */
+export class ResumableUploads {
+  private dir: string;
+  private uploads = new Map<string, ResumableUpload>();
+
+  constructor(dir: string = path.join(os.tmpdir(), 'spk-uploads')) {
+    this.dir = dir;
+  }
+
+  create(length: number, name: string): ResumableUpload {
+    this.sweep();
+    fs.mkdirSync(this.dir, { recursive: true });
+
+    const id = crypto.randomBytes(16).toString('hex');
+    const upload: ResumableUpload = {
+      id,
+      name,
+      length,
+      offset: 0,
+      filePath: path.join(this.dir, id),
+      createdAt: Date.now(),
+    };
+    fs.writeFileSync(upload.filePath, '');
+    this.uploads.set(id, upload);
+    return upload;
+  }
+
+  get(id: string): ResumableUpload | null {
+    return this.uploads.get(id) || null;
+  }
+
+  /**
+   * Appends a chunk at `offset`, which must match what we already have.
+   * Bytes beyond the declared length are rejected rather than truncated.
+   */
+  async append(id: string, offset: number, chunk: Readable): Promise<ResumableUpload> {
+    const upload = this.uploads.get(id);
+    if (!upload) throw new Error('Unknown upload');
+    if (offset !== upload.offset) throw new UploadOffsetError(upload.offset);
+
+    try {
+      await this.write(upload, chunk);
+    } catch (error) {
+      // Whatever reached the disk still counts, so the client can resume from it
+      upload.offset = fs.statSync(upload.filePath).size;
+      throw error;
+    }
+    return upload;
+  }
+
+  private write(upload: ResumableUpload, chunk: Readable): Promise<void> {
+    return new Promise<void>((resolve, reject) => {
+      const out = fs.createWriteStream(upload.filePath, { flags: 'a' });
+      chunk.on('data', (data: Buffer) => {
+        if (upload.offset + data.length > upload.length) {
+          chunk.destroy();
+          out.destroy();
+          reject(new Error('Chunk exceeds Upload-Length'));
+          return;
+        }
+        upload.offset += data.length;
+      });
+      chunk.on('error', reject);
+      out.on('error', reject);
+      out.on('finish', resolve);
+      chunk.pipe(out);
+    });
+  }
+
+  isComplete(upload: ResumableUpload): boolean {
+    return upload.offset === upload.length;
+  }
+
+  remove(id: string): void {
+    const upload = this.uploads.get(id);
+    if (!upload) return;
+    fs.rmSync(upload.filePath, { force: true });
+    this.uploads.delete(id);
+  }
+
+  private sweep(): void {
+    const cutoff = Date.now() - UPLOAD_TTL_MS;
+    for (const upload of this.uploads.values()) {
+      if (upload.createdAt < cutoff) this.remove(upload.id);
+    }
+  }
+}
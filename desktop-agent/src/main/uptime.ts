@@ -0,0 +1,148 @@
+import * as fs from 'fs';
+import * as path from 'path';
+
+const DAY_MS = 24 * 60 * 60 * 1000;
+// How often the agent notes it is still running; a crash loses at most this much
+const HEARTBEAT_MS = 60 * 1000;
+const RETENTION_MS = 30 * DAY_MS;
+export const UPTIME_WINDOWS_DAYS = [7, 30];
+
+interface OnlineInterval {
+  start: number;
+  // Null while still online
+  end: number | null;
+}
+
+interface UptimeFile {
+  trackingSince: number;
+  lastSeenAt: number;
+  intervals: OnlineInterval[];
+}
+
+export interface UptimeWindow {
+  days: number;
+  // Share of the window the daemon was online; the window starts when tracking did if that's later
+  availability: number;
+  onlineMs: number;
+  offlineMs: number;
+  // Times it went offline in the window
+  outages: number;
+}
+
+export interface UptimeStats {
+  online: boolean;
+  onlineSince: string | null;
+  // When the last online stretch ended; null while online or before it was first online
+  offlineSince: string | null;
+  trackingSince: string;
+  windows: UptimeWindow[];
+}
+
+/**
+ * Records when the IPFS daemon was online, in uptime.json, and turns it
+ * into availability over the last 7 and 30 days. Validators only reach a
+ * node that is up, so this is what challenge income follows. Time the
+ * agent wasn't running counts as offline: the agent notes it is alive
+ * every minute, and an interval left open by a crash is closed at the last
+ * note. Intervals older than 30 days are dropped.
+ */
+export class UptimeTracker {
+  private filePath: string;
+  private data: UptimeFile;
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(dataDir: string) {
+    this.filePath = path.join(dataDir, 'uptime.json');
+    this.data = this.load();
+  }
+
+  start(): void {
+    if (this.timer) return;
+    this.timer = setInterval(() => {
+      this.data.lastSeenAt = Date.now();
+      this.save();
+    }, HEARTBEAT_MS);
+  }
+
+  stop(): void {
+    if (this.timer) clearInterval(this.timer);
+    this.timer = null;
+    this.observe(false);
+  }
+
+  observe(online: boolean): void {
+    const now = Date.now();
+    const open = this.openInterval();
+    if (online && !open) {
+      this.data.intervals.push({ start: now, end: null });
+    } else if (!online && open) {
+      open.end = now;
+    } else {
+      return;
+    }
+    this.data.lastSeenAt = now;
+    this.save();
+  }
+
+  getStats(): UptimeStats {
+    const now = Date.now();
+    const open = this.openInterval();
+    const last = this.data.intervals[this.data.intervals.length - 1];
+    return {
+      online: !!open,
+      onlineSince: open ? new Date(open.start).toISOString() : null,
+      offlineSince: !open && last?.end ? new Date(last.end).toISOString() : null,
+      trackingSince: new Date(this.data.trackingSince).toISOString(),
+      windows: UPTIME_WINDOWS_DAYS.map((days) => this.window(days, now)),
+    };
+  }
+
+  private window(days: number, now: number): UptimeWindow {
+    const from = Math.max(now - days * DAY_MS, this.data.trackingSince);
+    let onlineMs = 0;
+    let outages = 0;
+    for (const interval of this.data.intervals) {
+      const end = interval.end ?? now;
+      if (end <= from) continue;
+      onlineMs += end - Math.max(interval.start, from);
+      if (interval.end !== null) outages++;
+    }
+    const spanMs = Math.max(0, now - from);
+    return {
+      days,
+      availability: spanMs > 0 ? Math.min(1, onlineMs / spanMs) : 1,
+      onlineMs,
+      offlineMs: Math.max(0, spanMs - onlineMs),
+      outages,
+    };
+  }
+
+  private openInterval(): OnlineInterval | null {
+    const last = this.data.intervals[this.data.intervals.length - 1];
+    return last && last.end === null ? last : null;
+  }
+
+  private load(): UptimeFile {
+    const now = Date.now();
+    try {
+      const data: UptimeFile = JSON.parse(fs.readFileSync(this.filePath, 'utf-8'));
+      // Left open by a crash or a kill: it ended when the agent was last seen
+      for (const interval of data.intervals) {
+        if (interval.end === null) interval.end = Math.max(interval.start, data.lastSeenAt);
+      }
+      return data;
+    } catch {
+      return { trackingSince: now, lastSeenAt: now, intervals: [] };
+    }
+  }
+
+  private save(): void {
+    const cutoff = Date.now() - RETENTION_MS;
+    this.data.intervals = this.data.intervals.filter((interval) => interval.end === null || interval.end > cutoff);
+    try {
+      fs.writeFileSync(this.filePath, JSON.stringify(this.data));
+    } catch (error: any) {
+      console.error('[SPK] Failed to save uptime:', error.message);
+    }
+  }
+}
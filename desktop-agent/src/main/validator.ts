@@ -0,0 +1,271 @@
+import * as crypto from 'crypto';
+import * as net from 'net';
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { KuboManager } from './kubo';
+import { EventBus } from './event-bus';
+import { POA_PROTOCOL, generateProof, listBlocks } from './poa';
+import { PoaSigner, publicKeyFromPeerId, verifyMessage } from './poa-signing';
+
+// Our own copy may have to come over bitswap, so give it longer than a challenge
+const VERIFY_TIMEOUT_MS = 60 * 1000;
+const CHALLENGE_TIMEOUT_MS = 15 * 1000;
+// Same deadline storage nodes are held to when we're the one answering
+const ANSWER_DEADLINE_MS = 2000;
+const MAX_RESULTS = 100;
+
+export interface ValidatorSettings {
+  enabled: boolean;
+  intervalSec: number;
+  challengesPerRound: number;
+  // Where results are POSTed; null keeps them local
+  reportUrl: string | null;
+}
+
+export const DEFAULT_VALIDATOR_SETTINGS: ValidatorSettings = {
+  enabled: false,
+  intervalSec: 300,
+  challengesPerRound: 3,
+  reportUrl: null,
+};
+
+export function validateValidatorSettings(settings: Partial<ValidatorSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.enabled !== undefined && typeof settings.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  if (settings.intervalSec !== undefined && (!Number.isInteger(settings.intervalSec) || settings.intervalSec < 60 || settings.intervalSec > 86400)) {
+    errors.push('intervalSec must be between 60 and 86400');
+  }
+  if (settings.challengesPerRound !== undefined
+    && (!Number.isInteger(settings.challengesPerRound) || settings.challengesPerRound < 1 || settings.challengesPerRound > 20)) {
+    errors.push('challengesPerRound must be between 1 and 20');
+  }
+  if (settings.reportUrl !== undefined && settings.reportUrl !== null) {
+    try {
+      if (!['http:', 'https:'].includes(new URL(settings.reportUrl).protocol)) throw new Error();
+    } catch {
+      errors.push('reportUrl must be an http(s) URL');
+    }
+  }
+  return errors;
+}
+
+export interface ValidationResult {
+  at: string;
+  contractId: string;
+  cid: string;
+  peerId: string;
+  blockIndex: number;
+  salt: string;
+  passed: boolean;
+  // Round trip through the libp2p stream, after the connection was warmed up
+  responseTime: number | null;
+  error: string | null;
+}
+
+export interface ValidatorStatus {
+  enabled: boolean;
+  running: boolean;
+  lastRoundAt: string | null;
+  nextRoundAt: string | null;
+  challenged: number;
+  passed: number;
+  results: ValidationResult[];
+}
+
+interface ActiveContract {
+  id: string;
+  fileCid: string;
+}
+
+function freePort(): Promise<number> {
+  return new Promise((resolve, reject) => {
+    const server = net.createServer();
+    server.once('error', reject);
+    server.listen(0, '127.0.0.1', () => {
+      const { port } = server.address() as net.AddressInfo;
+      server.close(() => resolve(port));
+    });
+  });
+}
+
+function pickRandom<T>(items: T[], count: number): T[] {
+  const pool = [...items];
+  const picked: T[] = [];
+  while (picked.length < count && pool.length > 0) {
+    picked.push(pool.splice(crypto.randomInt(pool.length), 1)[0]);
+  }
+  return picked;
+}
+
+/**
+ * Opt-in validator mode: every round, picks active contracts at random,
+ * finds another node that stores each one, and challenges it over a libp2p
+ * stream. The expected proof is computed on our own node first, so the
+ * answer can be checked the moment it arrives.
+ */
+export class PoaValidator {
+  private config: ConfigStore;
+  private kubo: KuboManager;
+  private events: EventBus;
+  private signer: PoaSigner;
+  private timer: NodeJS.Timeout | null = null;
+  private running = false;
+  private roundInProgress = false;
+  private lastRoundAt: string | null = null;
+  private nextRoundAt: string | null = null;
+  private challenged = 0;
+  private passed = 0;
+  private results: ValidationResult[] = [];
+
+  constructor(config: ConfigStore, kubo: KuboManager, events: EventBus, signer: PoaSigner) {
+    this.config = config;
+    this.kubo = kubo;
+    this.events = events;
+    this.signer = signer;
+  }
+
+  start(): void {
+    this.running = true;
+    this.reload();
+  }
+
+  stop(): void {
+    this.running = false;
+    this.reload();
+  }
+
+  // Reschedules to match the settings
+  reload(): void {
+    if (this.timer) clearTimeout(this.timer);
+    this.timer = null;
+    this.nextRoundAt = null;
+    const { enabled, intervalSec } = this.config.getConfig().validator;
+    if (!this.running || !enabled) return;
+    this.nextRoundAt = new Date(Date.now() + intervalSec * 1000).toISOString();
+    this.timer = setTimeout(() => {
+      this.runRound()
+        .catch((error) => console.error('[Validator] Round failed:', error.message))
+        .finally(() => this.reload());
+    }, intervalSec * 1000);
+  }
+
+  getStatus(): ValidatorStatus {
+    return {
+      enabled: this.config.getConfig().validator.enabled,
+      running: this.roundInProgress,
+      lastRoundAt: this.lastRoundAt,
+      nextRoundAt: this.nextRoundAt,
+      challenged: this.challenged,
+      passed: this.passed,
+      results: [...this.results].reverse(),
+    };
+  }
+
+  async runRound(): Promise<ValidationResult[]> {
+    if (this.roundInProgress) throw new Error('A validation round is already running');
+    this.roundInProgress = true;
+    try {
+      const { spkApiUrl, validator } = this.config.getConfig();
+      const response = await axios.get(`${spkApiUrl}/api/contracts/active`, { timeout: 10000 });
+      const contracts = (Array.isArray(response.data) ? response.data : [])
+        .filter((contract: any): contract is ActiveContract => typeof contract?.id === 'string' && typeof contract?.fileCid === 'string');
+
+      const results: ValidationResult[] = [];
+      for (const contract of pickRandom(contracts, validator.challengesPerRound)) {
+        const result = await this.validateContract(contract).catch((error: Error) => {
+          console.warn(`[Validator] Skipped ${contract.id}: ${error.message}`);
+          return null;
+        });
+        if (result) results.push(result);
+      }
+      this.lastRoundAt = new Date().toISOString();
+      console.log(`[Validator] Round done: ${results.filter((result) => result.passed).length}/${results.length} passed`);
+      return results;
+    } finally {
+      this.roundInProgress = false;
+    }
+  }
+
+  private async validateContract(contract: ActiveContract): Promise<ValidationResult> {
+    const providers = await this.kubo.findProviders(contract.fileCid);
+    if (providers.length === 0) throw new Error('no other node provides its content');
+    const peerId = providers[crypto.randomInt(providers.length)];
+
+    const blockCount = (await listBlocks(this.kubo, contract.fileCid, VERIFY_TIMEOUT_MS)).length;
+    if (blockCount === 0) throw new Error('content is a single block');
+    const blockIndex = crypto.randomInt(blockCount);
+    const salt = crypto.randomBytes(16).toString('hex');
+    const expected = await generateProof(this.kubo, contract.fileCid, blockIndex, salt, { timeoutMs: VERIFY_TIMEOUT_MS });
+
+    const result: ValidationResult = {
+      at: new Date().toISOString(),
+      contractId: contract.id,
+      cid: contract.fileCid,
+      peerId,
+      blockIndex,
+      salt,
+      passed: false,
+      responseTime: null,
+      error: null,
+    };
+
+    try {
+      const { proof, responseTime } = await this.challengePeer(peerId, contract.fileCid, blockIndex, salt);
+      result.responseTime = responseTime;
+      if (proof !== expected.proof) {
+        result.error = 'Proof does not match';
+      } else if (responseTime > ANSWER_DEADLINE_MS) {
+        result.error = `Answered in ${responseTime}ms, past the ${ANSWER_DEADLINE_MS}ms deadline`;
+      } else {
+        result.passed = true;
+      }
+    } catch (error: any) {
+      result.error = error.response?.data?.error?.message || error.message;
+    }
+
+    this.book(result);
+    return result;
+  }
+
+  private async challengePeer(peerId: string, cid: string, blockIndex: number, salt: string): Promise<{ proof: string; responseTime: number }> {
+    const port = await freePort();
+    await this.kubo.p2pForward(POA_PROTOCOL, port, peerId);
+    try {
+      const base = `http://127.0.0.1:${port}/api/v1`;
+      // Dials the peer, so the timed request below measures only the answer
+      await axios.get(`${base}/ping`, { timeout: CHALLENGE_TIMEOUT_MS });
+      const validatorId = this.config.getConfig().hiveUsername || (await this.kubo.getPeerId());
+      const started = Date.now();
+      const challenge = { cid, blockIndex, salt, validatorId };
+      const body = this.config.getConfig().poa.signMessages ? this.signer.sign(challenge) : challenge;
+      const response = await axios.post(`${base}/challenge`, body, { timeout: CHALLENGE_TIMEOUT_MS });
+      const responseTime = Date.now() - started;
+      // An answer claiming the peer's own libp2p key must really be signed by it;
+      // unsigned answers and other keys can't be tied to the peer either way
+      const peerKey = publicKeyFromPeerId(peerId);
+      if (peerKey && response.data?.signer === peerKey) {
+        const rejection = verifyMessage(response.data, peerKey);
+        if (rejection) throw new Error(`Answer signature rejected: ${rejection}`);
+      }
+      return { proof: String(response.data?.proof ?? ''), responseTime };
+    } finally {
+      await this.kubo.p2pCloseForward(port).catch(() => undefined);
+    }
+  }
+
+  private book(result: ValidationResult): void {
+    this.challenged++;
+    if (result.passed) this.passed++;
+    this.results.push(result);
+    if (this.results.length > MAX_RESULTS) this.results.shift();
+    this.events.publish('challenge', 'validated', result);
+    if (!result.passed) console.warn(`[Validator] ${result.peerId} failed on ${result.cid}: ${result.error}`);
+
+    const { reportUrl } = this.config.getConfig().validator;
+    if (!reportUrl) return;
+    axios.post(reportUrl, { ...result, validator: this.config.getConfig().hiveUsername }, { timeout: 10000 })
+      .catch((error) => console.warn(`[Validator] Could not report to ${reportUrl}: ${error.message}`));
+  }
+}
@@ -0,0 +1,157 @@
+import { Asset, utils } from '@hiveio/dhive';
+import { ConfigStore } from './config';
+import { HiveAccount } from './hive';
+import { BrocaTracker } from './broca';
+import { HoneycombClient } from './honeycomb';
+import { fromMilli } from './eligibility';
+
+const TRANSFER_LIMIT = 20;
+// Account history is read in pages; the filter keeps them to these ops
+const HISTORY_OPS = [
+  utils.operationOrders.transfer,
+  utils.operationOrders.transfer_to_savings,
+  utils.operationOrders.claim_reward_balance,
+];
+
+export interface WalletSettings {
+  // How long an overview is reused before the public APIs are asked again
+  refreshMinutes: number;
+}
+
+export const DEFAULT_WALLET_SETTINGS: WalletSettings = {
+  refreshMinutes: 5,
+};
+
+export function validateWalletSettings(settings: Partial<WalletSettings>): string[] {
+  const errors: string[] = [];
+  if (settings.refreshMinutes !== undefined
+    && (!Number.isInteger(settings.refreshMinutes) || settings.refreshMinutes < 1 || settings.refreshMinutes > 24 * 60)) {
+    errors.push('refreshMinutes must be between 1 and 1440');
+  }
+  return errors;
+}
+
+export interface WalletBalances {
+  hive: number;
+  hbd: number;
+  savingsHbd: number;
+  hivePower: number;
+  spk: number;
+  spkPower: number;
+  larynx: number;
+  broca: number;
+  brocaMax: number;
+}
+
+export interface WalletTransfer {
+  at: string;
+  type: 'transfer' | 'transfer_to_savings' | 'claim_reward_balance';
+  from: string;
+  to: string;
+  // "1.000 HBD"; rewards list every asset claimed
+  amount: string;
+  memo: string;
+  txId: string;
+}
+
+export interface WalletOverview {
+  username: string;
+  balances: WalletBalances;
+  transfers: WalletTransfer[];
+  fetchedAt: string;
+  // Set when the latest refresh failed and this is the last good overview
+  stale: string | null;
+}
+
+/**
+ * The account's balances across Hive and the SPK chain, and its recent
+ * transfers and claims, for the dashboard's wallet panel. Everything comes
+ * from public APIs, so an overview is reused for the configured refresh
+ * time, and the last good one is served (marked stale) when they're down.
+ */
+export class WalletProvider {
+  private config: ConfigStore;
+  private account: HiveAccount;
+  private broca: BrocaTracker;
+  private honeycomb: HoneycombClient;
+  private last: WalletOverview | null = null;
+
+  constructor(config: ConfigStore, account: HiveAccount, broca: BrocaTracker, honeycomb: HoneycombClient) {
+    this.config = config;
+    this.account = account;
+    this.broca = broca;
+    this.honeycomb = honeycomb;
+  }
+
+  async getOverview(force = false): Promise<WalletOverview> {
+    const { hiveUsername, wallet } = this.config.getConfig();
+    if (!hiveUsername) throw new Error('Log in with a Hive account first');
+    const cached = this.last?.username === hiveUsername ? this.last : null;
+    if (!force && cached && !cached.stale
+      && Date.now() - new Date(cached.fetchedAt).getTime() < wallet.refreshMinutes * 60 * 1000) {
+      return cached;
+    }
+
+    try {
+      this.last = await this.fetch(hiveUsername);
+    } catch (error: any) {
+      console.error('[Hive] Wallet refresh failed:', error.message);
+      if (!cached) throw error;
+      this.last = { ...cached, stale: error.message };
+    }
+    return this.last;
+  }
+
+  private async fetch(username: string): Promise<WalletOverview> {
+    const client = this.account.getClient();
+    const [[hiveAccount], props, spk, history, broca] = await Promise.all([
+      client.database.getAccounts([username]),
+      client.database.getDynamicGlobalProperties(),
+      this.honeycomb.getAccount(username),
+      client.database.getAccountHistory(username, -1, 100, utils.makeBitMaskFilter(HISTORY_OPS) as [number, number]),
+      this.broca.getStatus(),
+    ]);
+    if (!hiveAccount) throw new Error(`@${username} does not exist on Hive`);
+
+    // Hive Power is vesting shares at the chain's current VESTS-to-HIVE rate
+    const vests = Asset.from(hiveAccount.vesting_shares).amount;
+    const hivePerVest = Asset.from(props.total_vesting_fund_hive).amount / Asset.from(props.total_vesting_shares).amount;
+
+    const transfers: WalletTransfer[] = history
+      .reverse()
+      .slice(0, TRANSFER_LIMIT)
+      .map(([, entry]) => {
+        const [type, op] = entry.op as [WalletTransfer['type'], any];
+        const amount = type === 'claim_reward_balance'
+          ? [op.reward_hbd, op.reward_hive, op.reward_vests].filter((asset) => Asset.from(asset).amount > 0).join(', ')
+          : String(op.amount);
+        return {
+          at: `${entry.timestamp}Z`,
+          type,
+          from: op.from ?? op.account,
+          to: op.to ?? op.account,
+          amount,
+          memo: op.memo ?? '',
+          txId: entry.trx_id,
+        };
+      });
+
+    return {
+      username,
+      balances: {
+        hive: Asset.from(hiveAccount.balance).amount,
+        hbd: Asset.from(hiveAccount.hbd_balance).amount,
+        savingsHbd: Asset.from(hiveAccount.savings_hbd_balance).amount,
+        hivePower: vests * hivePerVest,
+        spk: fromMilli(spk.spk),
+        spkPower: fromMilli(spk.spk_power),
+        larynx: fromMilli(spk.balance),
+        broca: broca.current,
+        brocaMax: broca.max,
+      },
+      transfers,
+      fetchedAt: new Date().toISOString(),
+      stale: null,
+    };
+  }
+}
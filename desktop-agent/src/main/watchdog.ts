@@ -0,0 +1,123 @@
+import { EventEmitter } from 'events';
+import { KuboManager } from './kubo';
+import { ConfigStore } from './config';
+
+const INITIAL_BACKOFF_MS = 2000;
+const MAX_BACKOFF_MS = 5 * 60 * 1000;
+// Consecutive failed health checks before we consider the daemon hung
+const FAILURES_BEFORE_RESTART = 2;
+
+export type WatchdogStatus = 'Running' | 'Unresponsive' | 'Restarting' | 'Error';
+
+export interface DaemonRestartEvent {
+  reason: 'crashed' | 'unresponsive';
+  attempt: number;
+  success: boolean;
+  error?: string;
+  timestamp: string;
+}
+
+/**
+ * Health-checks the daemon via /api/v0/id and restarts it with exponential
+ * backoff when the process dies or the API stops answering.
+ */
+export class DaemonWatchdog extends EventEmitter {
+  private kubo: KuboManager;
+  private config: ConfigStore;
+  private timer: NodeJS.Timeout | null = null;
+  private consecutiveFailures = 0;
+  private restartAttempt = 0;
+  private nextRestartAt = 0;
+  private restarting = false;
+  private restartCount = 0;
+
+  constructor(kubo: KuboManager, config: ConfigStore) {
+    super();
+    this.kubo = kubo;
+    this.config = config;
+
+    this.kubo.on('crashed', () => {
+      console.log('[Watchdog] Daemon exited unexpectedly');
+      this.scheduleRestart('crashed');
+    });
+  }
+
+  start(): void {
+    if (this.timer) return;
+    const intervalMs = this.config.getConfig().watchdogIntervalSec * 1000;
+    this.timer = setInterval(() => this.check(), intervalMs);
+    console.log(`[Watchdog] Health checks every ${intervalMs / 1000}s`);
+  }
+
+  stop(): void {
+    if (this.timer) {
+      clearInterval(this.timer);
+      this.timer = null;
+    }
+  }
+
+  getRestartCount(): number {
+    return this.restartCount;
+  }
+
+  private async check(): Promise<void> {
+    if (this.restarting || this.kubo.isSuspended()) return;
+
+    const peerId = await this.kubo.getPeerId();
+    if (peerId) {
+      if (this.consecutiveFailures > 0 || this.restartAttempt > 0) {
+        this.emit('status', 'Running');
+      }
+      this.consecutiveFailures = 0;
+      this.restartAttempt = 0;
+      return;
+    }
+
+    this.consecutiveFailures++;
+    console.log(`[Watchdog] Health check failed (${this.consecutiveFailures})`);
+    this.emit('status', 'Unresponsive');
+
+    if (this.consecutiveFailures >= FAILURES_BEFORE_RESTART) {
+      this.scheduleRestart('unresponsive');
+    }
+  }
+
+  private scheduleRestart(reason: DaemonRestartEvent['reason']): void {
+    if (this.restarting || Date.now() < this.nextRestartAt) return;
+    void this.restart(reason);
+  }
+
+  private async restart(reason: DaemonRestartEvent['reason']): Promise<void> {
+    this.restarting = true;
+    this.restartAttempt++;
+    this.emit('status', 'Restarting');
+
+    const backoff = Math.min(INITIAL_BACKOFF_MS * Math.pow(2, this.restartAttempt - 1), MAX_BACKOFF_MS);
+    console.log(`[Watchdog] Restarting daemon in ${backoff}ms (attempt ${this.restartAttempt}, ${reason})`);
+    await new Promise((resolve) => setTimeout(resolve, backoff));
+
+    const event: DaemonRestartEvent = {
+      reason,
+      attempt: this.restartAttempt,
+      success: false,
+      timestamp: new Date().toISOString(),
+    };
+
+    try {
+      await this.kubo.restart();
+      event.success = true;
+      this.consecutiveFailures = 0;
+      this.restartCount++;
+      this.emit('status', 'Running');
+    } catch (error: any) {
+      console.error('[Watchdog] Restart failed:', error.message);
+      event.error = error.message;
+      this.nextRestartAt = Date.now() + backoff;
+      this.emit('status', 'Error');
+    } finally {
+      this.restarting = false;
+    }
+
+    this.emit('daemon-restarted', event);
+  }
+}
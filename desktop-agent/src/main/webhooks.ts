@@ -0,0 +1,309 @@
+import * as crypto from 'crypto';
+import axios from 'axios';
+import { ConfigStore } from './config';
+import { EventBus, StreamEvent } from './event-bus';
+import { SecretStore } from './secrets';
+
+export const WEBHOOK_EVENTS = ['challenge.passed', 'challenge.failed', 'daemon.down', 'milestone.reached'] as const;
+export type WebhookEvent = typeof WEBHOOK_EVENTS[number];
+
+// The signing secret is sealed and kept apart, so it isn't in agent-config.json or /api/config
+export interface Webhook {
+  id: string;
+  url: string;
+  events: WebhookEvent[];
+  enabled: boolean;
+}
+
+export interface WebhookDelivery {
+  id: string;
+  webhookId: string;
+  event: WebhookEvent | 'ping';
+  status: 'pending' | 'delivered' | 'failed';
+  attempts: number;
+  responseStatus: number | null;
+  error: string | null;
+  createdAt: string;
+  updatedAt: string;
+}
+
+const DELIVERY_TIMEOUT_MS = 10 * 1000;
+// Delay before each retry; a delivery is dropped after the last one fails
+const RETRY_DELAYS_MS = [10 * 1000, 60 * 1000, 5 * 60 * 1000, 30 * 60 * 1000];
+const MAX_RECENT_DELIVERIES = 50;
+const MAX_WEBHOOKS = 20;
+
+export function validateWebhook(webhook: Partial<Webhook>): string[] {
+  const errors: string[] = [];
+  if (webhook.url !== undefined) {
+    let url: URL | null = null;
+    try {
+      url = new URL(webhook.url);
+    } catch {
+      errors.push('url must be a valid URL');
+    }
+    if (url && !['http:', 'https:'].includes(url.protocol)) {
+      errors.push('url must be http or https');
+    }
+  }
+  if (webhook.events !== undefined) {
+    if (!Array.isArray(webhook.events) || webhook.events.length === 0) {
+      errors.push('events must list at least one event');
+    } else {
+      const unknown = webhook.events.filter((event) => !(WEBHOOK_EVENTS as readonly string[]).includes(event));
+      if (unknown.length > 0) errors.push(`Unknown events: ${unknown.join(', ')}`);
+    }
+  }
+  if (webhook.enabled !== undefined && typeof webhook.enabled !== 'boolean') {
+    errors.push('enabled must be a boolean');
+  }
+  return errors;
+}
+
+/**
+ * Signature over the timestamp and body, so a captured delivery can't be
+ * replayed later or with another payload. Receivers recompute it with the
+ * webhook's secret and compare against `X-SPK-Signature`.
+ */
+export function signPayload(secret: string, timestamp: number, body: string): string {
+  return `sha256=${crypto.createHmac('sha256', secret).update(`${timestamp}.${body}`).digest('hex')}`;
+}
+
+function isRetryable(status: number | null): boolean {
+  return status === null || status === 429 || status >= 500;
+}
+
+/**
+ * Posts agent events to the user's webhook URLs: challenge results, the
+ * daemon going down and earnings milestones. Deliveries are retried with
+ * backoff while the agent runs; they aren't persisted across restarts.
+ * Signing secrets are sealed with the secret store, like Hive keys, so a
+ * webhook can only be added while the keyring or an unlocked vault is
+ * there to seal its secret.
+ */
+export class WebhookDispatcher {
+  private config: ConfigStore;
+  private bus: EventBus;
+  private secrets: SecretStore;
+  private daemonDown = false;
+  private recent: WebhookDelivery[] = [];
+  private timers = new Set<NodeJS.Timeout>();
+  private onEvent = (message: StreamEvent) => this.handle(message);
+  private onUnlocked = () => this.sealLegacySecrets();
+
+  constructor(config: ConfigStore, bus: EventBus, secrets: SecretStore) {
+    this.config = config;
+    this.bus = bus;
+    this.secrets = secrets;
+  }
+
+  start(): void {
+    this.bus.on('event', this.onEvent);
+    this.secrets.on('unlocked', this.onUnlocked);
+    this.sealLegacySecrets();
+  }
+
+  stop(): void {
+    this.bus.off('event', this.onEvent);
+    this.secrets.off('unlocked', this.onUnlocked);
+    this.timers.forEach((timer) => clearTimeout(timer));
+    this.timers.clear();
+  }
+
+  list(): Webhook[] {
+    return this.config.getConfig().webhooks;
+  }
+
+  // The secret is only handed back here and by getSecret, never with the list
+  add(update: Partial<Webhook>): { success: boolean; errors?: string[]; webhook?: Webhook; secret?: string } {
+    const errors = validateWebhook(update);
+    if (!update.url) errors.push('url is required');
+    if (this.list().length >= MAX_WEBHOOKS) errors.push(`At most ${MAX_WEBHOOKS} webhooks`);
+    if (!this.secrets.isWritable()) {
+      errors.push(this.secrets.getStatus().backend === 'vault'
+        ? 'Unlock the secrets vault to add a webhook'
+        : 'No OS keyring is available; set up the secrets vault to add a webhook');
+    }
+    if (errors.length > 0) return { success: false, errors };
+
+    const webhook: Webhook = {
+      id: crypto.randomUUID(),
+      url: update.url!,
+      events: update.events || [...WEBHOOK_EVENTS],
+      enabled: update.enabled ?? true,
+    };
+    const secret = crypto.randomBytes(32).toString('hex');
+    this.config.setWebhookSecrets({ ...this.config.getWebhookSecrets(), [webhook.id]: this.secrets.encrypt(secret) });
+    this.config.setConfig({ webhooks: [...this.list(), webhook] });
+    return { success: true, webhook, secret };
+  }
+
+  // Null for an unknown webhook; throws while the vault is locked
+  getSecret(id: string): string | null {
+    const sealed = this.config.getWebhookSecrets()[id];
+    return sealed ? this.secrets.decrypt(sealed) : null;
+  }
+
+  update(id: string, update: Partial<Webhook>): { success: boolean; errors?: string[]; webhook?: Webhook } {
+    const errors = validateWebhook(update);
+    const existing = this.list().find((webhook) => webhook.id === id);
+    if (!existing) errors.push('Unknown webhook');
+    if (errors.length > 0) return { success: false, errors };
+
+    const webhook: Webhook = {
+      ...existing!,
+      ...(update.url !== undefined ? { url: update.url } : {}),
+      ...(update.events !== undefined ? { events: update.events } : {}),
+      ...(update.enabled !== undefined ? { enabled: update.enabled } : {}),
+    };
+    this.config.setConfig({ webhooks: this.list().map((entry) => (entry.id === id ? webhook : entry)) });
+    return { success: true, webhook };
+  }
+
+  remove(id: string): Webhook[] {
+    const secrets = { ...this.config.getWebhookSecrets() };
+    delete secrets[id];
+    this.config.setWebhookSecrets(secrets);
+    this.config.setConfig({ webhooks: this.list().filter((webhook) => webhook.id !== id) });
+    return this.list();
+  }
+
+  getDeliveries(): WebhookDelivery[] {
+    return [...this.recent].reverse();
+  }
+
+  // Sends a `ping` to one webhook right away, so the user can check the receiver
+  async test(id: string): Promise<WebhookDelivery | null> {
+    const webhook = this.list().find((entry) => entry.id === id);
+    if (!webhook) return null;
+    const delivery = this.createDelivery(webhook, 'ping');
+    await this.attempt(webhook, delivery, { message: 'Test delivery from the SPK Desktop Agent' });
+    return delivery;
+  }
+
+  private handle(message: StreamEvent): void {
+    const data = message.data as any;
+    if (message.topic === 'challenge' && message.event === 'passed') {
+      this.dispatch('challenge.passed', data);
+    } else if (message.topic === 'challenge' && message.event === 'failed') {
+      this.dispatch('challenge.failed', data);
+    } else if (message.topic === 'earnings' && message.event === 'milestone') {
+      this.dispatch('milestone.reached', data);
+    } else if (message.topic === 'status' && message.event === 'watchdog') {
+      this.trackDaemon(data.status === 'Running' ? 'up' : 'down', `Watchdog: ${data.status}`);
+    } else if (message.topic === 'status' && message.event === 'startup-state') {
+      if (data.state === 'Online') this.trackDaemon('up', data.state);
+      if (data.state === 'Failed') this.trackDaemon('down', data.error || 'Startup failed');
+    }
+  }
+
+  // One daemon.down per outage, however many states it goes through
+  private trackDaemon(state: 'up' | 'down', reason: string): void {
+    if (state === 'up') {
+      this.daemonDown = false;
+      return;
+    }
+    if (this.daemonDown) return;
+    this.daemonDown = true;
+    this.dispatch('daemon.down', { reason });
+  }
+
+  private dispatch(event: WebhookEvent, data: unknown): void {
+    for (const webhook of this.list()) {
+      if (!webhook.enabled || !webhook.events.includes(event)) continue;
+      const delivery = this.createDelivery(webhook, event);
+      this.attempt(webhook, delivery, data).catch(() => undefined);
+    }
+  }
+
+  private createDelivery(webhook: Webhook, event: WebhookDelivery['event']): WebhookDelivery {
+    const now = new Date().toISOString();
+    const delivery: WebhookDelivery = {
+      id: crypto.randomUUID(),
+      webhookId: webhook.id,
+      event,
+      status: 'pending',
+      attempts: 0,
+      responseStatus: null,
+      error: null,
+      createdAt: now,
+      updatedAt: now,
+    };
+    this.recent.push(delivery);
+    if (this.recent.length > MAX_RECENT_DELIVERIES) this.recent.shift();
+    return delivery;
+  }
+
+  // Secrets from before they were sealed are moved out of the config once the store can seal them
+  private sealLegacySecrets(): void {
+    const legacy = this.config.getLegacyWebhookSecrets();
+    const ids = Object.keys(legacy);
+    if (ids.length === 0 || !this.secrets.isWritable()) return;
+    const sealed = Object.fromEntries(ids.map((id) => [id, this.secrets.encrypt(legacy[id])]));
+    this.config.setWebhookSecrets({ ...this.config.getWebhookSecrets(), ...sealed });
+    this.config.clearLegacyWebhookSecrets();
+    console.log(`[Webhooks] Sealed the signing secrets of ${ids.length} webhook(s)`);
+  }
+
+  // A secret still waiting to be sealed is used as it is
+  private secretFor(id: string): string | null {
+    return this.getSecret(id) ?? this.config.getLegacyWebhookSecrets()[id] ?? null;
+  }
+
+  private async attempt(webhook: Webhook, delivery: WebhookDelivery, data: unknown): Promise<void> {
+    // Same id and body on every retry, so receivers can drop duplicates
+    const body = JSON.stringify({ id: delivery.id, event: delivery.event, at: delivery.createdAt, data });
+    const timestamp = Math.floor(Date.now() / 1000);
+    delivery.attempts++;
+
+    try {
+      const secret = this.secretFor(webhook.id);
+      if (!secret) throw new Error('No signing secret; remove the webhook and add it again');
+      const response = await axios.post(webhook.url, body, {
+        timeout: DELIVERY_TIMEOUT_MS,
+        headers: {
+          'Content-Type': 'application/json',
+          'User-Agent': 'spk-desktop-agent',
+          'X-SPK-Event': delivery.event,
+          'X-SPK-Delivery': delivery.id,
+          'X-SPK-Timestamp': String(timestamp),
+          'X-SPK-Signature': signPayload(secret, timestamp, body),
+        },
+        validateStatus: () => true,
+        maxRedirects: 0,
+      });
+      delivery.responseStatus = response.status;
+      delivery.error = response.status < 300 ? null : `HTTP ${response.status}`;
+    } catch (error: any) {
+      delivery.responseStatus = null;
+      delivery.error = error.message;
+    }
+    delivery.updatedAt = new Date().toISOString();
+
+    if (delivery.error === null) {
+      delivery.status = 'delivered';
+      return;
+    }
+
+    const delay = RETRY_DELAYS_MS[delivery.attempts - 1];
+    if (delay === undefined || !isRetryable(delivery.responseStatus)) {
+      delivery.status = 'failed';
+      console.warn(`[Webhooks] Giving up on ${delivery.event} to ${webhook.url}: ${delivery.error}`);
+      return;
+    }
+
+    console.log(`[Webhooks] ${delivery.event} to ${webhook.url} failed (${delivery.error}), retrying in ${delay / 1000}s`);
+    const timer = setTimeout(() => {
+      this.timers.delete(timer);
+      // Picks up edits and removal since the first attempt
+      const current = this.list().find((entry) => entry.id === webhook.id);
+      if (!current || !current.enabled) {
+        delivery.status = 'failed';
+        delivery.error = 'Webhook was removed or disabled';
+        return;
+      }
+      this.attempt(current, delivery, data).catch(() => undefined);
+    }, delay);
+    this.timers.add(timer);
+  }
+}
@@ -1,54 +1,2383 @@
+import { ipcRenderer } from 'electron';
+
 const API_URL = 'http://127.0.0.1:5111';
 
-interface Status {
-  running: boolean;
-  peerId: string | null;
-  stats: {
-    repoSize: number;
-    numObjects: number;
-  } | null;
-  hiveUsername: string | null;
-  earnings: {
-    totalHbd: number;
-    challengesPassed: number;
-    consecutivePasses: number;
+interface Status {
+  running: boolean;
+  peerId: string | null;
+  stats: {
+    repoSize: number;
+    numObjects: number;
+  } | null;
+  hiveUsername: string | null;
+  connectivity: {
+    reachability: 'Reachable' | 'Relayed' | 'Unreachable' | 'Unknown';
+    hint: string | null;
+  } | null;
+  earnings: {
+    totalHbd: number;
+    challengesPassed: number;
+    consecutivePasses: number;
+    provisionalHbd: number;
+  };
+}
+
+// Daemon lifecycle pushed from the main process
+const STARTUP_LABELS: Record<string, string> = {
+  Starting: 'Preparing IPFS...',
+  RepoReady: 'Starting IPFS daemon...',
+  DaemonUp: 'IPFS Running (connecting to peers)',
+  Failed: 'IPFS failed to start',
+  Stopped: 'IPFS Stopped',
+};
+let startupState = 'Starting';
+
+// The dashboard is loaded from file://, so the API sees an opaque origin and
+// only answers when the request carries the token
+async function apiFetch(path: string, init: RequestInit = {}): Promise<Response> {
+  const token = await ipcRenderer.invoke('get-api-token');
+  return fetch(`${API_URL}${path}`, {
+    ...init,
+    headers: { ...(init.headers as Record<string, string>), Authorization: `Bearer ${token}` },
+  });
+}
+
+async function fetchStatus(): Promise<Status | null> {
+  try {
+    const response = await apiFetch('/api/v1/status');
+    return await response.json();
+  } catch (error) {
+    console.error('Failed to fetch status:', error);
+    return null;
+  }
+}
+
+async function fetchPinCount(): Promise<number> {
+  try {
+    const response = await apiFetch('/api/v1/pins?limit=1');
+    const data = await response.json();
+    return data.total || 0;
+  } catch {
+    return 0;
+  }
+}
+
+async function saveConfig(): Promise<void> {
+  const usernameInput = document.getElementById('hiveUsername') as HTMLInputElement;
+  const username = usernameInput.value.trim();
+
+  try {
+    const response = await apiFetch('/api/v1/config', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ hiveUsername: username }),
+    });
+    if (!response.ok) throw new Error(`HTTP ${response.status}`);
+    alert('Configuration saved!');
+  } catch (error) {
+    alert('Failed to save configuration');
+  }
+}
+
+async function loadBandwidthLimits(): Promise<void> {
+  const limits = await ipcRenderer.invoke('get-bandwidth-limits');
+  (document.getElementById('uploadKbps') as HTMLInputElement).value = String(limits.uploadKbps);
+  (document.getElementById('downloadKbps') as HTMLInputElement).value = String(limits.downloadKbps);
+}
+
+async function saveBandwidthLimits(): Promise<void> {
+  const uploadKbps = Number((document.getElementById('uploadKbps') as HTMLInputElement).value);
+  const downloadKbps = Number((document.getElementById('downloadKbps') as HTMLInputElement).value);
+
+  const result = await ipcRenderer.invoke('set-bandwidth-limits', { uploadKbps, downloadKbps });
+  if (result.success) {
+    alert('Bandwidth limits applied. The IPFS daemon was restarted.');
+  } else {
+    alert(`Failed to apply limits: ${result.errors.join(', ')}`);
+  }
+}
+
+async function loadPeering(): Promise<void> {
+  const peers = await ipcRenderer.invoke('get-peering-peers');
+  (document.getElementById('peeringDnsaddrs') as HTMLTextAreaElement).value = peers.dnsaddrs.join('\n');
+  const statusEl = document.getElementById('peeringStatus');
+  if (statusEl) {
+    statusEl.textContent = peers.dnsaddrs.length === 0
+      ? 'No SPK peer list is published yet, so none is set. Add /dnsaddr/ addresses you trust.'
+      : `${peers.spk.length} SPK peers resolved, ${peers.custom.length} added by hand`;
+  }
+}
+
+async function savePeering(): Promise<void> {
+  const dnsaddrs = (document.getElementById('peeringDnsaddrs') as HTMLTextAreaElement).value
+    .split('\n')
+    .map((line) => line.trim())
+    .filter((line) => line !== '');
+  const result = await ipcRenderer.invoke('set-peering-dnsaddrs', dnsaddrs);
+  if (!result.success) {
+    alert(`Failed to apply peering: ${result.errors.join(', ')}`);
+  }
+  await loadPeering();
+}
+
+const RESOURCE_LIMIT_FIELDS = ['maxConnections', 'maxInboundConnections', 'maxMemoryMb', 'maxFileDescriptors'];
+
+async function loadResourceLimits(): Promise<void> {
+  const limits = await ipcRenderer.invoke('get-resource-limits');
+  RESOURCE_LIMIT_FIELDS.forEach((field) => {
+    (document.getElementById(field) as HTMLInputElement).value = String(limits[field]);
+  });
+}
+
+async function saveResourceLimits(): Promise<void> {
+  const update: Record<string, number> = {};
+  RESOURCE_LIMIT_FIELDS.forEach((field) => {
+    update[field] = Number((document.getElementById(field) as HTMLInputElement).value);
+  });
+
+  const result = await ipcRenderer.invoke('set-resource-limits', update);
+  if (result.success) {
+    alert('Resource limits applied. The IPFS daemon was restarted.');
+  } else {
+    alert(`Failed to apply limits: ${result.errors.join(', ')}`);
+  }
+}
+
+async function loadTlsSettings(): Promise<void> {
+  const tls = await ipcRenderer.invoke('get-tls-settings');
+  (document.getElementById('tlsEnabled') as HTMLInputElement).checked = tls.enabled;
+  (document.getElementById('tlsPort') as HTMLInputElement).value = String(tls.port);
+
+  const statusEl = document.getElementById('tlsStatus');
+  if (statusEl) {
+    statusEl.textContent = tls.listening
+      ? `Listening on https://127.0.0.1:${tls.port}${tls.caFingerprint ? ` · CA ${tls.caFingerprint.slice(0, 23)}…` : ''}`
+      : 'HTTPS is off';
+  }
+}
+
+async function saveTlsSettings(): Promise<void> {
+  const enabled = (document.getElementById('tlsEnabled') as HTMLInputElement).checked;
+  const port = Number((document.getElementById('tlsPort') as HTMLInputElement).value);
+
+  const result = await ipcRenderer.invoke('set-tls-settings', { enabled, port });
+  if (!result.success) {
+    alert(`Failed to apply HTTPS settings: ${result.errors.join(', ')}`);
+  }
+  loadTlsSettings();
+}
+
+async function loadGrpcSettings(): Promise<void> {
+  const grpc = await ipcRenderer.invoke('get-grpc-settings');
+  (document.getElementById('grpcEnabled') as HTMLInputElement).checked = grpc.enabled;
+  (document.getElementById('grpcPort') as HTMLInputElement).value = String(grpc.port);
+
+  const statusEl = document.getElementById('grpcStatus');
+  if (statusEl) {
+    statusEl.textContent = grpc.listening
+      ? `Listening on port ${grpc.port}${grpc.tls ? ' (TLS)' : ''}`
+      : 'gRPC is off';
+  }
+}
+
+async function saveGrpcSettings(): Promise<void> {
+  const enabled = (document.getElementById('grpcEnabled') as HTMLInputElement).checked;
+  const port = Number((document.getElementById('grpcPort') as HTMLInputElement).value);
+
+  const result = await ipcRenderer.invoke('set-grpc-settings', { enabled, port });
+  if (!result.success) {
+    alert(`Failed to apply gRPC settings: ${result.errors.join(', ')}`);
+  }
+  loadGrpcSettings();
+}
+
+async function trustTlsCertificate(): Promise<void> {
+  const result = await ipcRenderer.invoke('trust-tls-certificate');
+  alert(result.trusted ? 'The local certificate is now trusted.' : result.manualSteps);
+  loadTlsSettings();
+}
+
+async function loadRemoteAccess(): Promise<void> {
+  const remote = await ipcRenderer.invoke('get-remote-access');
+  (document.getElementById('remoteEnabled') as HTMLInputElement).checked = remote.enabled;
+  (document.getElementById('remoteBindAddress') as HTMLInputElement).value = remote.bindAddress;
+  (document.getElementById('remoteAllowedIps') as HTMLInputElement).value = remote.allowedIps.join(', ');
+  (document.getElementById('remoteTrustProxy') as HTMLInputElement).checked = remote.trustProxy;
+}
+
+async function saveRemoteAccess(): Promise<void> {
+  const allowedIps = (document.getElementById('remoteAllowedIps') as HTMLInputElement).value
+    .split(',')
+    .map((entry) => entry.trim())
+    .filter(Boolean);
+
+  const result = await ipcRenderer.invoke('set-remote-access', {
+    enabled: (document.getElementById('remoteEnabled') as HTMLInputElement).checked,
+    bindAddress: (document.getElementById('remoteBindAddress') as HTMLInputElement).value.trim() || '0.0.0.0',
+    allowedIps,
+    trustProxy: (document.getElementById('remoteTrustProxy') as HTMLInputElement).checked,
+  });
+  if (!result.success) {
+    alert(`Failed to apply remote access: ${result.errors.join(', ')}`);
+  } else if (result.warnings.length > 0) {
+    alert(`Remote access applied.\n\n${result.warnings.join('\n')}`);
+  }
+  loadRemoteAccess();
+}
+
+async function loadDiscoverySettings(): Promise<void> {
+  const discovery = await ipcRenderer.invoke('get-discovery-settings');
+  (document.getElementById('discoveryEnabled') as HTMLInputElement).checked = discovery.enabled;
+}
+
+async function saveDiscoverySettings(): Promise<void> {
+  const enabled = (document.getElementById('discoveryEnabled') as HTMLInputElement).checked;
+  await ipcRenderer.invoke('set-discovery-settings', { enabled });
+}
+
+async function loadPoaSettings(): Promise<void> {
+  const poa = await ipcRenderer.invoke('get-poa-settings');
+  (document.getElementById('poaEnabled') as HTMLInputElement).checked = poa.enabled;
+  (document.getElementById('poaValidators') as HTMLInputElement).value = poa.validators.join(', ');
+  (document.getElementById('poaActive') as HTMLInputElement).value = String(poa.activeValidators);
+  (document.getElementById('poaPreferred') as HTMLInputElement).value = poa.preferredValidator || '';
+
+  const statusEl = document.getElementById('poaStatus');
+  if (statusEl) {
+    const connected = poa.connections.filter((connection: { state: string }) => connection.state === 'connected').length;
+    statusEl.textContent = poa.enabled && poa.validators.length > 0
+      ? `Connected to ${connected} of ${poa.validators.length} validators`
+        + (poa.atRisk > 0 ? ` · ${poa.atRisk} proofs unsent, retrying` : '')
+        + (poa.signMessages ? ` · answers signed with the ${poa.signer.source} key ${poa.signer.publicKey}` : '')
+      : 'Not listening for challenges';
+  }
+
+  const listEl = document.getElementById('poaConnections');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const connection of poa.connections) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${connection.preferred ? '★ ' : ''}${connection.url}: ${connection.state}, score ${connection.score}`
+      + (connection.latencyMs !== null ? `, ${connection.latencyMs}ms` : '')
+      + (connection.recentFailures > 0 ? `, ${connection.recentFailures} drops in the last hour` : '');
+    listEl.appendChild(row);
+  }
+}
+
+async function savePoaSettings(): Promise<void> {
+  const validators = (document.getElementById('poaValidators') as HTMLInputElement).value
+    .split(',')
+    .map((entry) => entry.trim())
+    .filter(Boolean);
+
+  const preferred = (document.getElementById('poaPreferred') as HTMLInputElement).value.trim();
+  const result = await ipcRenderer.invoke('set-poa-settings', {
+    enabled: (document.getElementById('poaEnabled') as HTMLInputElement).checked,
+    validators,
+    activeValidators: Number((document.getElementById('poaActive') as HTMLInputElement).value),
+    preferredValidator: preferred || null,
+  });
+  if (!result.success) {
+    alert(`Failed to apply PoA settings: ${result.errors.join(', ')}`);
+  }
+  loadPoaSettings();
+}
+
+async function loadChallengeDiagnostics(): Promise<void> {
+  const report = await ipcRenderer.invoke('get-challenge-diagnostics');
+  const tableEl = document.getElementById('diagnosticsStages');
+  const warningsEl = document.getElementById('diagnosticsWarnings');
+  if (!tableEl || !warningsEl) return;
+
+  if (report.samples === 0) {
+    tableEl.textContent = 'No challenges answered yet';
+    warningsEl.textContent = '';
+    return;
+  }
+
+  tableEl.innerHTML = '';
+  const rows: Array<[string, { p50: number; p95: number; budgetMs: number; overBudget: number }]> = [
+    ...Object.entries<any>(report.stages),
+    ['total', report.total],
+  ];
+  for (const [stage, summary] of rows) {
+    const row = document.createElement('div');
+    row.className = summary.p95 > summary.budgetMs ? 'warn' : '';
+    row.textContent = `${stage}: p50 ${summary.p50}ms · p95 ${summary.p95}ms · budget ${summary.budgetMs}ms · over ${summary.overBudget}/${report.samples}`;
+    tableEl.appendChild(row);
+  }
+  warningsEl.textContent = report.warnings.join('\n');
+}
+
+async function loadDiagnosticsBudget(): Promise<void> {
+  const poa = await ipcRenderer.invoke('get-poa-settings');
+  (document.getElementById('budgetTotal') as HTMLInputElement).value = String(poa.budget.totalMs);
+  (document.getElementById('budgetFetch') as HTMLInputElement).value = String(poa.budget.stages.fetch);
+  (document.getElementById('budgetSubmit') as HTMLInputElement).value = String(poa.budget.stages.submit);
+}
+
+async function saveDiagnosticsBudget(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-poa-settings', {
+    budget: {
+      totalMs: Number((document.getElementById('budgetTotal') as HTMLInputElement).value),
+      stages: {
+        fetch: Number((document.getElementById('budgetFetch') as HTMLInputElement).value),
+        submit: Number((document.getElementById('budgetSubmit') as HTMLInputElement).value),
+      },
+    },
+  });
+  if (!result.success) {
+    alert(`Failed to apply latency budget: ${result.errors.join(', ')}`);
+  }
+  loadDiagnosticsBudget();
+  loadChallengeDiagnostics();
+}
+
+async function loadEligibility(refresh = false): Promise<void> {
+  const report = await ipcRenderer.invoke('get-eligibility', refresh);
+  const summaryEl = document.getElementById('eligibilitySummary');
+  const itemsEl = document.getElementById('eligibilityItems');
+  if (!summaryEl || !itemsEl) return;
+
+  summaryEl.textContent = report.eligible
+    ? 'This node is eligible for challenges'
+    : 'This node is not receiving challenges:';
+  itemsEl.innerHTML = '';
+  for (const item of report.items) {
+    const row = document.createElement('div');
+    row.className = item.status === 'ok' ? 'network-status' : 'network-status warn';
+    const mark = item.status === 'ok' ? '✓' : item.status === 'warning' ? '!' : '✗';
+    row.textContent = `${mark} ${item.message}${item.fix ? ` — ${item.fix}` : ''}`;
+    itemsEl.appendChild(row);
+  }
+}
+
+async function loadRegistrationStatus(): Promise<void> {
+  const status = await ipcRenderer.invoke('get-registration-status');
+  const statusEl = document.getElementById('registrationStatus');
+  if (!statusEl) return;
+  if (status.lastError) {
+    statusEl.textContent = `Registration failed: ${status.lastError}`;
+  } else if (status.pendingReason) {
+    statusEl.textContent = `${status.pendingReason}; registering shortly`;
+  } else if (status.registeredAt) {
+    statusEl.textContent = `Registered with the SPK network ${new Date(status.registeredAt).toLocaleString()}`;
+  } else {
+    statusEl.textContent = 'Not registered; save a Hive username first';
+  }
+}
+
+async function loadHiveIdentity(): Promise<void> {
+  const identity = await ipcRenderer.invoke('hive-whoami');
+  const identityEl = document.getElementById('hiveIdentity');
+  if (!identityEl) return;
+  const keys = (['posting', 'active'] as const).filter((role) => identity.keys[role]);
+  identityEl.textContent = !identity.username
+    ? 'Not logged in'
+    : `Logged in as @${identity.username}`
+      + (keys.length > 0 ? ` · ${keys.join(' and ')} keys stored` : ' · no keys stored')
+      + (identity.keyStorage === 'vault' ? ` · keys in the vault${identity.locked ? ' (locked)' : ''}` : '')
+      + (identity.keyStorage ? '' : ' · no OS keyring or vault; set up the vault to store keys')
+      + (identity.payoutAccount ? ` · rewards go to @${identity.payoutAccount}` : '');
+  const payoutInput = document.getElementById('payoutAccount') as HTMLInputElement;
+  if (document.activeElement !== payoutInput) payoutInput.value = identity.payoutAccount ?? '';
+}
+
+async function savePayoutAccount(): Promise<void> {
+  const value = (document.getElementById('payoutAccount') as HTMLInputElement).value.trim();
+  const result = await ipcRenderer.invoke('set-payout-account', value || null);
+  if (!result.success) {
+    alert(`Failed to set the payout account: ${result.errors.join(', ')}`);
+  }
+  loadHiveIdentity();
+  loadRegistrationStatus();
+  loadChainRegistration();
+}
+
+async function hiveLogin(): Promise<void> {
+  const postingInput = document.getElementById('hivePostingKey') as HTMLInputElement;
+  const activeInput = document.getElementById('hiveActiveKey') as HTMLInputElement;
+  const result = await ipcRenderer.invoke('hive-login', {
+    username: (document.getElementById('hiveUsername') as HTMLInputElement).value,
+    postingKey: postingInput.value || undefined,
+    activeKey: activeInput.value || undefined,
+  }, (document.getElementById('accountLabel') as HTMLInputElement).value.trim() || undefined);
+  // Keys don't linger in the form once handed over
+  postingInput.value = '';
+  activeInput.value = '';
+  if (!result.success) {
+    alert(`Login failed: ${result.errors.join(', ')}`);
+  }
+  (document.getElementById('accountLabel') as HTMLInputElement).value = '';
+  loadHiveIdentity();
+  loadRegistrationStatus();
+  loadAccounts();
+}
+
+async function hiveLogout(): Promise<void> {
+  if (!confirm('Log out and remove the stored Hive keys?')) return;
+  await ipcRenderer.invoke('hive-logout');
+  (document.getElementById('hiveUsername') as HTMLInputElement).value = '';
+  loadHiveIdentity();
+  loadVault();
+  loadAccounts();
+}
+
+function renderHiveNodes(nodes: any[]): void {
+  const listEl = document.getElementById('hiveNodeList');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  nodes.forEach((node, index) => {
+    const row = document.createElement('div');
+    row.className = node.healthy ? 'network-status' : 'network-status warn';
+    row.textContent = `${index === 0 ? '★ ' : ''}${node.url}${node.custom ? ' (custom)' : ''}`
+      + (node.latencyMs !== null ? ` · ${node.latencyMs} ms` : ' · not checked yet')
+      + (node.lagBlocks ? ` · ${node.lagBlocks} blocks behind` : '')
+      + (node.lastError ? ` · ${node.lastError}` : '');
+    listEl.appendChild(row);
+  });
+}
+
+async function loadHiveNodes(): Promise<void> {
+  const { settings, nodes } = await ipcRenderer.invoke('get-hive-nodes');
+  const customEl = document.getElementById('customHiveNodes') as HTMLTextAreaElement | null;
+  if (!customEl) return;
+  // Don't clobber an edit in progress
+  if (document.activeElement !== customEl) customEl.value = settings.customNodes.join('\n');
+  (document.getElementById('includeDefaultNodes') as HTMLInputElement).checked = settings.includeDefaults;
+  (document.getElementById('hiveNodeCheck') as HTMLInputElement).value = String(settings.checkMinutes);
+  renderHiveNodes(nodes);
+}
+
+async function saveHiveNodes(): Promise<void> {
+  const customNodes = (document.getElementById('customHiveNodes') as HTMLTextAreaElement).value
+    .split('\n').map((line) => line.trim()).filter(Boolean);
+  const result = await ipcRenderer.invoke('set-hive-node-settings', {
+    customNodes,
+    includeDefaults: (document.getElementById('includeDefaultNodes') as HTMLInputElement).checked,
+    checkMinutes: parseInt((document.getElementById('hiveNodeCheck') as HTMLInputElement).value, 10),
+  });
+  if (!result.success) {
+    alert(`Failed to save Hive nodes: ${result.errors.join(', ')}`);
+    return;
+  }
+  renderHiveNodes(result.nodes);
+}
+
+async function checkHiveNodes(): Promise<void> {
+  const result = await ipcRenderer.invoke('check-hive-nodes');
+  renderHiveNodes(result.nodes);
+}
+
+async function loadDelegations(force = false): Promise<void> {
+  const listEl = document.getElementById('delegationList');
+  if (!listEl) return;
+  const result = await ipcRenderer.invoke('get-delegations', force);
+  listEl.innerHTML = '';
+  if (!result.success) {
+    listEl.textContent = result.errors.join(', ');
+    return;
+  }
+  for (const token of result.tokens) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const out = token.outgoing.map((d: any) => `@${d.account} ${d.amount.toFixed(3)}`).join(', ') || 'none';
+    const incoming = token.incoming.map((d: any) => `@${d.account} ${d.amount.toFixed(3)}`).join(', ') || 'none';
+    row.textContent = `${token.token} power ${token.power.toFixed(3)} · ${token.available.toFixed(3)} free\n`
+      + `Delegated to: ${out}\nReceived from: ${incoming}`;
+    listEl.appendChild(row);
+  }
+}
+
+function readDelegationForm(): Record<string, unknown> {
+  return {
+    token: (document.getElementById('delegationToken') as HTMLSelectElement).value,
+    to: (document.getElementById('delegationTo') as HTMLInputElement).value,
+    amount: parseFloat((document.getElementById('delegationAmount') as HTMLInputElement).value),
+  };
+}
+
+async function reviewDelegation(): Promise<void> {
+  const previewEl = document.getElementById('delegationPreview')!;
+  const confirmBtn = document.getElementById('confirmDelegation') as HTMLButtonElement;
+  const { operation, issues } = await ipcRenderer.invoke('preview-delegation', readDelegationForm());
+  previewEl.textContent = issues.length > 0
+    ? issues.join('\n')
+    : JSON.stringify(operation, null, 2);
+  confirmBtn.disabled = issues.length > 0;
+}
+
+async function confirmDelegation(): Promise<void> {
+  const previewEl = document.getElementById('delegationPreview')!;
+  const confirmBtn = document.getElementById('confirmDelegation') as HTMLButtonElement;
+  confirmBtn.disabled = true;
+  previewEl.textContent = 'Waiting for the signature and a block…';
+  const result = await ipcRenderer.invoke('delegate-power', readDelegationForm());
+  previewEl.textContent = result.success
+    ? `Included in block ${result.result.blockNum ?? 'unknown'} (${result.result.txId}); the SPK chain applies it within a few blocks.`
+    : `Delegation failed: ${result.errors.join(', ')}`;
+  loadDelegations(true);
+  loadEligibility(true);
+}
+
+async function loadHoneycomb(): Promise<void> {
+  const datasetsEl = document.getElementById('honeycombDatasets');
+  const rankingsEl = document.getElementById('nodeRankings');
+  if (!datasetsEl || !rankingsEl) return;
+  const { settings, datasets, rankings, validators } = await ipcRenderer.invoke('get-honeycomb-status');
+  const syncInput = document.getElementById('honeycombSync') as HTMLInputElement;
+  if (document.activeElement !== syncInput) syncInput.value = String(settings.syncMinutes);
+
+  datasetsEl.innerHTML = '';
+  for (const dataset of datasets) {
+    const row = document.createElement('div');
+    row.className = dataset.lastError ? 'network-status warn' : 'network-status';
+    row.textContent = `${dataset.dataset}: ${dataset.entries} entries`
+      + (dataset.headBlock ? ` at block ${dataset.headBlock}` : '')
+      + (dataset.syncedAt ? ` · synced ${new Date(dataset.syncedAt).toLocaleTimeString()}` : ' · not synced yet')
+      + (dataset.lastError ? ` · ${dataset.lastError}` : '');
+    datasetsEl.appendChild(row);
+  }
+  const validatorsRow = document.createElement('div');
+  validatorsRow.className = 'network-status';
+  validatorsRow.textContent = `${validators} validators in the current set`;
+  datasetsEl.appendChild(validatorsRow);
+
+  rankingsEl.innerHTML = '';
+  const username = (document.getElementById('hiveUsername') as HTMLInputElement).value.trim().toLowerCase();
+  const own = rankings.find((node: any) => node.account === username);
+  for (const node of [...rankings.slice(0, 5), ...(own && own.rank > 5 ? [own] : [])]) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `#${node.rank} @${node.account}${node.account === username ? ' (you)' : ''}`
+      + ` · agreed with consensus ${node.yays} of ${node.attempts} times`;
+    rankingsEl.appendChild(row);
+  }
+}
+
+async function saveHoneycomb(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-honeycomb-settings', {
+    syncMinutes: parseInt((document.getElementById('honeycombSync') as HTMLInputElement).value, 10),
+  });
+  if (!result.success) alert(`Failed to save: ${result.errors.join(', ')}`);
+  loadHoneycomb();
+}
+
+async function syncHoneycomb(): Promise<void> {
+  await ipcRenderer.invoke('sync-honeycomb');
+  loadHoneycomb();
+}
+
+const TX_STATUS_LABELS: Record<string, string> = {
+  pending: 'waiting to broadcast',
+  broadcast: 'waiting for a block',
+  included: 'included',
+  failed: 'failed',
+  cancelled: 'cancelled',
+};
+
+async function loadTxQueue(): Promise<void> {
+  const listEl = document.getElementById('txQueue');
+  if (!listEl) return;
+  const entries = await ipcRenderer.invoke('get-tx-queue');
+  listEl.innerHTML = '';
+  if (entries.length === 0) {
+    listEl.textContent = 'Nothing broadcast yet';
+    return;
+  }
+  for (const entry of entries.slice(0, 15)) {
+    const row = document.createElement('div');
+    row.className = entry.status === 'failed' ? 'network-status warn' : 'network-status';
+    row.textContent = `${new Date(entry.createdAt).toLocaleString()} · ${entry.description} · ${TX_STATUS_LABELS[entry.status]}`
+      + (entry.attempts > 1 ? ` (attempt ${entry.attempts})` : '')
+      + (entry.status === 'included' && entry.blockNum ? ` in block ${entry.blockNum}` : '')
+      + (entry.error ? ` · ${entry.error}` : '');
+    if (entry.status === 'failed' || entry.status === 'cancelled') {
+      const retryBtn = document.createElement('button');
+      retryBtn.textContent = 'Retry';
+      retryBtn.addEventListener('click', () => updateTransaction('retry-transaction', entry.id));
+      row.appendChild(retryBtn);
+    } else if (entry.status === 'pending') {
+      const cancelBtn = document.createElement('button');
+      cancelBtn.textContent = 'Cancel';
+      cancelBtn.addEventListener('click', () => updateTransaction('cancel-transaction', entry.id));
+      row.appendChild(cancelBtn);
+    }
+    listEl.appendChild(row);
+  }
+}
+
+async function updateTransaction(channel: 'retry-transaction' | 'cancel-transaction', id: string): Promise<void> {
+  const result = await ipcRenderer.invoke(channel, id);
+  if (!result.success) alert(result.errors.join(', '));
+  loadTxQueue();
+}
+
+function renderVaultStatus(status: any): void {
+  const statusEl = document.getElementById('vaultStatus');
+  if (!statusEl) return;
+  statusEl.textContent = !status.vaultCreated
+    ? (status.keyringAvailable ? 'Not needed: secrets use the OS keyring' : 'No OS keyring; create a vault to store keys')
+    : status.unlocked
+      ? `Unlocked${status.locksAt ? `; locks at ${new Date(status.locksAt).toLocaleTimeString()} unless used` : ''}`
+      : 'Locked';
+  (document.getElementById('createVault') as HTMLButtonElement).disabled = status.vaultCreated;
+  (document.getElementById('unlockVault') as HTMLButtonElement).disabled = !status.vaultCreated || status.unlocked;
+  (document.getElementById('lockVault') as HTMLButtonElement).disabled = !status.unlocked;
+}
+
+async function loadVault(): Promise<void> {
+  const status = await ipcRenderer.invoke('get-vault-status');
+  (document.getElementById('vaultBackend') as HTMLSelectElement).value = status.settings.backend;
+  const autoLockInput = document.getElementById('vaultAutoLock') as HTMLInputElement;
+  if (document.activeElement !== autoLockInput) autoLockInput.value = String(status.settings.autoLockMinutes);
+  renderVaultStatus(status);
+}
+
+async function vaultAction(channel: 'create-vault' | 'unlock-vault' | 'lock-vault'): Promise<void> {
+  const passphraseInput = document.getElementById('vaultPassphrase') as HTMLInputElement;
+  if (channel === 'create-vault' && !confirm('The passphrase cannot be recovered; keys in the vault are lost without it. Continue?')) return;
+  const result = await ipcRenderer.invoke(channel, passphraseInput.value);
+  passphraseInput.value = '';
+  if (!result.success) alert(result.errors.join(', '));
+  loadVault();
+  loadHiveIdentity();
+  loadHiveSigning();
+}
+
+async function saveVaultSettings(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-vault-settings', {
+    backend: (document.getElementById('vaultBackend') as HTMLSelectElement).value,
+    autoLockMinutes: parseInt((document.getElementById('vaultAutoLock') as HTMLInputElement).value, 10),
+  });
+  if (!result.success) alert(`Failed to save vault settings: ${result.errors.join(', ')}`);
+  loadVault();
+  loadHiveIdentity();
+}
+
+const NOTIFICATION_KINDS = ['challenges', 'rewards', 'contracts'] as const;
+
+async function loadAccounts(): Promise<void> {
+  const listEl = document.getElementById('accountList');
+  if (!listEl) return;
+  const { active, accounts, earnings } = await ipcRenderer.invoke('list-hive-accounts');
+  listEl.innerHTML = '';
+  if (accounts.length === 0) {
+    listEl.textContent = 'No accounts linked; log in above';
+    return;
+  }
+  for (const account of accounts) {
+    const totals = earnings[account.username];
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const summary = document.createElement('div');
+    summary.textContent = `@${account.username}${account.label ? ` (${account.label})` : ''}`
+      + (account.username === active ? ' · active' : '')
+      + (totals ? ` · ${totals.totalHbd.toFixed(3)} HBD ${formatFiat(totals.totalHbd)} from ${totals.challengesPassed} challenges passed, ${totals.challengesFailed} failed` : ' · no earnings yet');
+    row.appendChild(summary);
+
+    for (const kind of NOTIFICATION_KINDS) {
+      const label = document.createElement('label');
+      const checkbox = document.createElement('input');
+      checkbox.type = 'checkbox';
+      checkbox.checked = account.notifications[kind];
+      checkbox.addEventListener('change', async () => {
+        const result = await ipcRenderer.invoke('set-account-notifications', account.username, { [kind]: checkbox.checked });
+        if (!result.success) alert(`Failed to save notification settings: ${result.errors.join(', ')}`);
+      });
+      label.append(checkbox, ` ${kind} notifications `);
+      row.appendChild(label);
+    }
+
+    if (account.username !== active) {
+      const switchBtn = document.createElement('button');
+      switchBtn.textContent = 'Make Active';
+      switchBtn.addEventListener('click', () => switchAccount(account.username));
+      row.appendChild(switchBtn);
+    }
+    const removeBtn = document.createElement('button');
+    removeBtn.textContent = 'Remove';
+    removeBtn.addEventListener('click', () => unlinkAccount(account.username));
+    row.appendChild(removeBtn);
+    listEl.appendChild(row);
+  }
+}
+
+async function switchAccount(username: string): Promise<void> {
+  const result = await ipcRenderer.invoke('switch-hive-account', username);
+  if (!result.success) {
+    alert(`Failed to switch account: ${result.errors.join(', ')}`);
+    return;
+  }
+  (document.getElementById('hiveUsername') as HTMLInputElement).value = username;
+  // Everything account-bound follows the active account
+  loadAccounts();
+  loadHiveIdentity();
+  loadHiveSigning();
+  loadRegistrationStatus();
+  loadRewardClaims();
+  loadBroca();
+  loadWallet(true);
+  updateUI();
+}
+
+async function unlinkAccount(username: string): Promise<void> {
+  if (!confirm(`Remove @${username} and its stored keys? Its contracts and earnings history are kept.`)) return;
+  const { identity } = await ipcRenderer.invoke('unlink-hive-account', username);
+  (document.getElementById('hiveUsername') as HTMLInputElement).value = identity.username ?? '';
+  loadAccounts();
+  loadHiveIdentity();
+}
+
+async function refreshRegistration(): Promise<void> {
+  await ipcRenderer.invoke('refresh-registration');
+  loadRegistrationStatus();
+}
+
+let historyCursor: string | null = null;
+
+function readHistoryFilters(): Record<string, unknown> {
+  const from = (document.getElementById('historyFrom') as HTMLInputElement).value;
+  const to = (document.getElementById('historyTo') as HTMLInputElement).value;
+  const result = (document.getElementById('historyResult') as HTMLSelectElement).value;
+  const validator = (document.getElementById('historyValidator') as HTMLInputElement).value.trim();
+  const filters: Record<string, unknown> = {};
+  if (from) filters.from = from;
+  // The query's end is exclusive; the picker's is not
+  if (to) filters.to = new Date(Date.parse(to) + 24 * 3600 * 1000).toISOString();
+  if (result) filters.passed = result === 'passed';
+  if (validator) filters.validator = validator;
+  return filters;
+}
+
+async function loadChallengeHistory(more = false): Promise<void> {
+  const listEl = document.getElementById('historyEntries');
+  const daysEl = document.getElementById('historyDays');
+  const moreButton = document.getElementById('moreHistory');
+  if (!listEl || !daysEl || !moreButton) return;
+
+  const page = await ipcRenderer.invoke('get-challenge-history', {
+    ...readHistoryFilters(),
+    limit: 25,
+    ...(more && historyCursor ? { cursor: historyCursor } : {}),
+  });
+  if (!page.success) {
+    alert(`Failed to search challenge history: ${page.errors.join(', ')}`);
+    return;
+  }
+
+  if (!more) {
+    listEl.innerHTML = '';
+    daysEl.textContent = page.days.slice(0, 7)
+      .map((day: { day: string; challenges: number; passed: number; rewardHbd: number; avgLatencyMs: number }) =>
+        `${day.day}: ${day.passed}/${day.challenges} passed · ${day.rewardHbd.toFixed(3)} HBD · avg ${day.avgLatencyMs}ms`)
+      .join('\n') || 'No challenges in this range';
+  }
+  for (const entry of page.entries) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${entry.passed ? '✓' : '✗'} ${new Date(entry.at).toLocaleString()} ${entry.cid.slice(0, 12)}… `
+      + `${entry.latencyMs}ms${entry.validator ? ` · ${entry.validator}` : ''}${entry.error ? ` — ${entry.error}` : ''}`;
+    listEl.appendChild(row);
+  }
+  historyCursor = page.nextCursor;
+  moreButton.style.display = historyCursor ? '' : 'none';
+}
+
+async function loadValidatorSettings(): Promise<void> {
+  const validator = await ipcRenderer.invoke('get-validator-settings');
+  (document.getElementById('validatorEnabled') as HTMLInputElement).checked = validator.enabled;
+  (document.getElementById('validatorInterval') as HTMLInputElement).value = String(validator.intervalSec);
+  (document.getElementById('validatorPerRound') as HTMLInputElement).value = String(validator.challengesPerRound);
+  (document.getElementById('validatorReportUrl') as HTMLInputElement).value = validator.reportUrl || '';
+
+  const { status } = validator;
+  const statusEl = document.getElementById('validatorStatus');
+  if (statusEl) {
+    statusEl.textContent = status.running
+      ? 'Round in progress…'
+      : `${status.passed} of ${status.challenged} challenges passed`
+        + (status.nextRoundAt ? ` · next round ${new Date(status.nextRoundAt).toLocaleTimeString()}` : '');
+  }
+
+  const listEl = document.getElementById('validatorResults');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const result of status.results.slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${result.passed ? '✓' : '✗'} ${result.peerId.slice(0, 12)}… ${result.cid.slice(0, 12)}…`
+      + (result.responseTime !== null ? ` ${result.responseTime}ms` : '')
+      + (result.error ? ` — ${result.error}` : '');
+    listEl.appendChild(row);
+  }
+}
+
+async function saveValidatorSettings(): Promise<void> {
+  const reportUrl = (document.getElementById('validatorReportUrl') as HTMLInputElement).value.trim();
+  const result = await ipcRenderer.invoke('set-validator-settings', {
+    enabled: (document.getElementById('validatorEnabled') as HTMLInputElement).checked,
+    intervalSec: Number((document.getElementById('validatorInterval') as HTMLInputElement).value),
+    challengesPerRound: Number((document.getElementById('validatorPerRound') as HTMLInputElement).value),
+    reportUrl: reportUrl || null,
+  });
+  if (!result.success) {
+    alert(`Failed to apply validator settings: ${result.errors.join(', ')}`);
+  }
+  loadValidatorSettings();
+}
+
+async function runValidationRound(): Promise<void> {
+  const pending = ipcRenderer.invoke('run-validation-round');
+  loadValidatorSettings();
+  const result = await pending;
+  if (!result.success) {
+    alert(`Validation round failed: ${result.error}`);
+  }
+  loadValidatorSettings();
+}
+
+function readPolicyForm(): Record<string, unknown> {
+  const optionalNumber = (id: string) => {
+    const value = (document.getElementById(id) as HTMLInputElement).value.trim();
+    return value === '' ? null : Number(value);
+  };
+  return {
+    enabled: (document.getElementById('policyEnabled') as HTMLInputElement).checked,
+    dryRun: (document.getElementById('policyDryRun') as HTMLInputElement).checked,
+    maxContractGb: optionalNumber('policyMaxGb'),
+    minHbdPerGbMonth: optionalNumber('policyMinRate'),
+    maxCommittedGb: optionalNumber('policyMaxCommitted'),
+    dedupAware: (document.getElementById('policyDedup') as HTMLInputElement).checked,
+    blockedAccounts: (document.getElementById('policyBlocked') as HTMLInputElement).value
+      .split(',')
+      .map((account) => account.trim())
+      .filter(Boolean),
+    tier: (document.getElementById('policyTier') as HTMLSelectElement).value,
+    hotMinHbdPerGbMonth: optionalNumber('policyHotRate'),
+    coldMinGb: optionalNumber('policyColdGb'),
+  };
+}
+
+function renderPolicyDecisions(decisions: any[]): void {
+  const listEl = document.getElementById('policyDecisions');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const decision of decisions.slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const size = (decision.sizeBytes !== null ? ` ${(decision.sizeBytes / 1e9).toFixed(2)} GB` : '')
+      + (decision.newBytes != null && decision.newBytes < decision.sizeBytes ? ` (${(decision.newBytes / 1e9).toFixed(2)} GB new)` : '');
+    row.textContent = `${decision.decision === 'accept' ? '✓' : '✗'} ${decision.contractId} @${decision.uploader}${size}`
+      + (decision.decision === 'accept' && decision.tier ? ` · ${decision.tier}` : '')
+      + (decision.reasons.length > 0 ? ` — ${decision.reasons.join('; ')}` : '')
+      + (decision.dryRun ? ' (dry run)' : '')
+      + (decision.error ? ` — pin failed: ${decision.error}` : '');
+    listEl.appendChild(row);
+  }
+}
+
+async function loadStorageBreakdown(refresh = false): Promise<void> {
+  const summaryEl = document.getElementById('breakdownSummary');
+  const contractsEl = document.getElementById('breakdownContracts');
+  const cidsEl = document.getElementById('breakdownCids');
+  if (!summaryEl || !contractsEl || !cidsEl) return;
+  if (refresh) summaryEl.textContent = 'Sizing pins…';
+  const result = await ipcRenderer.invoke('get-storage-breakdown', refresh);
+  if (!result.success) {
+    summaryEl.textContent = `Could not size pins: ${result.errors.join(', ')}`;
+    return;
+  }
+  const breakdown = result.breakdown;
+  summaryEl.textContent = (breakdown.usedBytes !== null
+    ? `${formatBytes(breakdown.usedBytes)} of ${formatBytes(breakdown.maxBytes)} used: ` : '')
+    + `${formatBytes(breakdown.contractBytes)} for contracts, ${formatBytes(breakdown.otherBytes)} other pins`
+    + (breakdown.unattributedBytes !== null ? `, ${formatBytes(breakdown.unattributedBytes)} unpinned or overhead` : '')
+    + (breakdown.unsizedCids > 0 ? ` · ${breakdown.unsizedCids} pins not sized` : '');
+
+  contractsEl.innerHTML = '';
+  for (const contract of breakdown.contracts.slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${contract.contractId}${contract.account ? ` @${contract.account}` : ''} · ${formatBytes(contract.bytes)}`
+      + ` in ${contract.cids} CIDs` + (contract.exclusiveBytes !== contract.bytes ? ` (${formatBytes(contract.exclusiveBytes)} freed if dropped)` : '');
+    contractsEl.appendChild(row);
+  }
+  if (breakdown.contracts.length === 0) contractsEl.textContent = 'No contracts stored';
+
+  cidsEl.innerHTML = '';
+  for (const entry of breakdown.cids.slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${entry.name ?? entry.cid} · ${entry.bytes !== null ? formatBytes(entry.bytes) : 'size unknown'}`
+      + (entry.contractIds.length > 0 ? ` · ${entry.contractIds.join(', ')}` : ' · no contract');
+    const preview = document.createElement('button');
+    preview.textContent = 'Preview';
+    preview.addEventListener('click', () => previewPin(entry.cid));
+    row.appendChild(preview);
+    cidsEl.appendChild(row);
+  }
+}
+
+// Plays or shows a stored pin, streamed through the agent API
+async function previewPin(cid: string): Promise<void> {
+  const previewEl = document.getElementById('pinPreview');
+  if (!previewEl) return;
+  const url: string = await ipcRenderer.invoke('get-preview-url', cid);
+  previewEl.innerHTML = '';
+  const response = await fetch(url, { method: 'HEAD' }).catch(() => null);
+  if (!response || !response.ok) {
+    previewEl.className = 'network-status warn';
+    previewEl.textContent = response?.status === 404 ? `${cid} is not stored on this node` : `Could not load ${cid}`;
+    return;
+  }
+  const type = response.headers.get('content-type') || '';
+  previewEl.className = '';
+  let media: HTMLElement;
+  if (type.startsWith('video/') || type.startsWith('audio/')) {
+    const player = document.createElement(type.startsWith('video/') ? 'video' : 'audio');
+    player.controls = true;
+    player.src = url;
+    media = player;
+  } else if (type.startsWith('image/') && type !== 'image/svg+xml') {
+    const image = document.createElement('img');
+    image.src = url;
+    media = image;
+  } else {
+    previewEl.className = 'network-status';
+    previewEl.textContent = `${cid} is ${type || 'of unknown type'}; nothing to preview`;
+    return;
+  }
+  media.style.maxWidth = '100%';
+  previewEl.appendChild(media);
+}
+
+async function loadContractMarket(): Promise<void> {
+  const statusEl = document.getElementById('marketStatus');
+  const listEl = document.getElementById('marketOffers');
+  if (!statusEl || !listEl) return;
+  statusEl.textContent = 'Checking open offers…';
+  const result = await ipcRenderer.invoke('get-contract-market');
+  listEl.innerHTML = '';
+  if (!result.success) {
+    statusEl.textContent = `Could not load offers: ${result.errors.join(', ')}`;
+    return;
+  }
+  (document.getElementById('marketStoreOp') as HTMLInputElement).value = result.settings.storeOpId;
+  statusEl.textContent = result.offers.length === 0 ? 'No open offers right now' : `${result.offers.length} open offers`;
+  for (const offer of result.offers.slice(0, 20)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const size = offer.sizeBytes !== null ? `${(offer.sizeBytes / 1e9).toFixed(2)} GB` : 'size unknown';
+    const rate = offer.hbdPerGbMonth !== null ? ` (${offer.hbdPerGbMonth.toFixed(4)} HBD/GB/month)` : '';
+    row.textContent = `${offer.id} @${offer.uploader} · ${size} · ${offer.rewardHbd.toFixed(3)} HBD${rate}`
+      + ` · ${Math.round(offer.durationDays)} days · ${offer.openSlots} open`
+      + (offer.accepted ? ' · accepted' : offer.held ? ' · pinned, not accepted on-chain' : '');
+    if (!offer.accepted) {
+      const acceptBtn = document.createElement('button');
+      acceptBtn.textContent = offer.held ? 'Accept On-Chain' : 'Accept';
+      acceptBtn.addEventListener('click', () => acceptContract(offer.id, acceptBtn));
+      row.appendChild(acceptBtn);
+    }
+    listEl.appendChild(row);
+  }
+}
+
+async function acceptContract(contractId: string, button: HTMLButtonElement): Promise<void> {
+  if (!confirm(`Pin contract ${contractId} and accept it on-chain?`)) return;
+  button.disabled = true;
+  button.textContent = 'Accepting…';
+  const result = await ipcRenderer.invoke('accept-contract', contractId);
+  if (!result.success) alert(result.errors.join(', '));
+  loadContractMarket();
+  loadTxQueue();
+}
+
+async function saveContractMarket(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-contract-market-settings', {
+    storeOpId: (document.getElementById('marketStoreOp') as HTMLInputElement).value.trim(),
+  });
+  if (!result.success) alert(`Failed to save market settings: ${result.errors.join(', ')}`);
+  loadContractMarket();
+}
+
+async function loadContractPolicy(): Promise<void> {
+  const policy = await ipcRenderer.invoke('get-contract-policy');
+  (document.getElementById('policyEnabled') as HTMLInputElement).checked = policy.enabled;
+  (document.getElementById('policyDryRun') as HTMLInputElement).checked = policy.dryRun;
+  (document.getElementById('policyMaxGb') as HTMLInputElement).value = policy.maxContractGb ?? '';
+  (document.getElementById('policyMinRate') as HTMLInputElement).value = policy.minHbdPerGbMonth ?? '';
+  (document.getElementById('policyMaxCommitted') as HTMLInputElement).value = policy.maxCommittedGb ?? '';
+  (document.getElementById('policyDedup') as HTMLInputElement).checked = policy.dedupAware;
+  (document.getElementById('policyBlocked') as HTMLInputElement).value = policy.blockedAccounts.join(', ');
+  (document.getElementById('policyTier') as HTMLSelectElement).value = policy.tier;
+  (document.getElementById('policyHotRate') as HTMLInputElement).value = policy.hotMinHbdPerGbMonth ?? '';
+  (document.getElementById('policyColdGb') as HTMLInputElement).value = policy.coldMinGb ?? '';
+
+  const statusEl = document.getElementById('policyStatus');
+  if (statusEl) {
+    statusEl.textContent = !policy.enabled
+      ? 'Off; offers are only accepted by hand'
+      : policy.dryRun ? 'Dry run; decisions are logged but nothing is pinned' : 'Accepting offers that match';
+  }
+  renderPolicyDecisions(policy.decisions);
+}
+
+async function saveContractPolicy(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-contract-policy', readPolicyForm());
+  if (!result.success) {
+    alert(`Failed to apply contract policy: ${result.errors.join(', ')}`);
+  }
+  loadContractPolicy();
+  loadContractExpiry();
+}
+
+async function previewContractPolicy(): Promise<void> {
+  const statusEl = document.getElementById('policyStatus');
+  if (statusEl) statusEl.textContent = 'Checking open offers…';
+  const result = await ipcRenderer.invoke('preview-contract-policy', readPolicyForm());
+  if (!result.success) {
+    if (statusEl) statusEl.textContent = `Preview failed: ${result.errors.join(', ')}`;
+    return;
+  }
+  const accepted = result.decisions.filter((decision: any) => decision.decision === 'accept').length;
+  if (statusEl) statusEl.textContent = `These rules would accept ${accepted} of ${result.decisions.length} open offers`;
+  renderPolicyDecisions(result.decisions);
+}
+
+async function loadContractBandwidth(): Promise<void> {
+  const days = parseInt((document.getElementById('bandwidthDays') as HTMLSelectElement).value, 10);
+  const report = await ipcRenderer.invoke('get-contract-bandwidth', days);
+  const statusEl = document.getElementById('bandwidthStatus');
+  const listEl = document.getElementById('bandwidthContracts');
+  if (!statusEl || !listEl) return;
+  const share = report.sentBytes > 0 ? Math.round((report.attributedBytes / report.sentBytes) * 100) : 0;
+  statusEl.textContent = `${formatBytes(report.sentBytes)} sent over bitswap, ${share}% of it placed with a contract`;
+  listEl.innerHTML = '';
+  for (const contract of report.contracts.filter((entry: any) => entry.requests > 0).slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const top = contract.cids[0];
+    row.textContent = `${contract.contractId} · ${formatBytes(contract.bytes)}, ${contract.requests} blocks wanted`
+      + (contract.cids.length > 1 && top ? ` · most fetched ${top.cid}` : '');
+    listEl.appendChild(row);
+  }
+  if (listEl.childElementCount === 0) listEl.textContent = 'No contract content fetched by peers yet';
+}
+
+async function loadContractExpiry(): Promise<void> {
+  const { settings, upcoming, lastSweep } = await ipcRenderer.invoke('get-contract-expiry');
+  (document.getElementById('expiryEnabled') as HTMLInputElement).checked = settings.enabled;
+  (document.getElementById('expiryGrace') as HTMLInputElement).value = String(settings.graceHours);
+
+  const statusEl = document.getElementById('expiryStatus');
+  if (statusEl) {
+    statusEl.textContent = lastSweep
+      ? `Last removed ${lastSweep.contractIds.length} contracts, about ${(lastSweep.reclaimedBytes / 1e9).toFixed(2)} GB,`
+        + ` ${new Date(lastSweep.at).toLocaleString()}`
+      : 'No expired contracts removed since the agent started';
+  }
+
+  const listEl = document.getElementById('expiryUpcoming');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const contract of upcoming.slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const expired = new Date(contract.expiresAt).getTime() < Date.now();
+    row.textContent = `${contract.contractId} ${expired ? 'expired' : 'expires'} ${new Date(contract.expiresAt).toLocaleDateString()}`
+      + (settings.enabled ? `, unpinned after ${new Date(contract.unpinAt).toLocaleString()}` : '');
+    listEl.appendChild(row);
+  }
+}
+
+async function saveContractExpiry(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-contract-expiry-settings', {
+    enabled: (document.getElementById('expiryEnabled') as HTMLInputElement).checked,
+    graceHours: Number((document.getElementById('expiryGrace') as HTMLInputElement).value),
+  });
+  if (!result.success) {
+    alert(`Failed to apply expiry settings: ${result.errors.join(', ')}`);
+  }
+  loadContractExpiry();
+}
+
+async function sweepExpiredContracts(): Promise<void> {
+  const result = await ipcRenderer.invoke('sweep-expired-contracts');
+  if (!result.success) {
+    alert(`Expiry check failed: ${result.errors.join(', ')}`);
+  }
+  loadContractExpiry();
+  loadContractTrash();
+}
+
+async function loadContractTrash(): Promise<void> {
+  const { settings, contracts } = await ipcRenderer.invoke('get-contract-trash');
+  (document.getElementById('trashRetention') as HTMLInputElement).value = String(settings.retentionHours);
+
+  const statusEl = document.getElementById('trashStatus');
+  if (statusEl) {
+    statusEl.textContent = contracts.length > 0
+      ? `${contracts.length} contracts in the trash, still pinned`
+      : 'The trash is empty';
+  }
+
+  const listEl = document.getElementById('trashContracts');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const entry of contracts) {
+    const row = document.createElement('div');
+    row.className = entry.liableCids.length > 0 ? 'network-status warn' : 'network-status';
+    row.textContent = `${entry.record.id} (${entry.reason} ${new Date(entry.trashedAt).toLocaleString()})`
+      + (entry.liableCids.length > 0
+        ? ` · the chain still assigns ${entry.liableCids.length} of its CIDs to this node`
+        : ` · unpinned after ${new Date(entry.purgeAt).toLocaleString()}`);
+    const restore = document.createElement('button');
+    restore.textContent = 'Restore';
+    restore.addEventListener('click', () => restoreContract(entry.record.id));
+    const purge = document.createElement('button');
+    purge.textContent = 'Unpin Now';
+    purge.disabled = entry.liableCids.length > 0;
+    purge.addEventListener('click', () => purgeContract(entry.record.id));
+    row.appendChild(restore);
+    row.appendChild(purge);
+    listEl.appendChild(row);
+  }
+}
+
+async function saveContractTrash(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-trash-settings', {
+    retentionHours: Number((document.getElementById('trashRetention') as HTMLInputElement).value),
+  });
+  if (!result.success) {
+    alert(`Failed to apply trash settings: ${result.errors.join(', ')}`);
+  }
+  loadContractTrash();
+}
+
+async function restoreContract(contractId: string): Promise<void> {
+  const result = await ipcRenderer.invoke('restore-contract', contractId);
+  if (!result.success) {
+    alert(`Restore failed: ${result.errors.join(', ')}`);
+  }
+  loadContractTrash();
+}
+
+async function purgeContract(contractId: string): Promise<void> {
+  if (!confirm(`Unpin ${contractId} now? It can't be restored afterwards.`)) return;
+  const result = await ipcRenderer.invoke('purge-contract', contractId);
+  if (!result.success) {
+    alert(`Unpin failed: ${result.errors.join(', ')}`);
+  } else if (result.purge.errors.length > 0) {
+    alert(`Some CIDs could not be unpinned: ${result.purge.errors.join(', ')}`);
+  }
+  loadContractTrash();
+}
+
+async function loadPinReconciliation(): Promise<void> {
+  const { settings, last, nextRunAt } = await ipcRenderer.invoke('get-pin-reconciliation');
+  (document.getElementById('reconcileEnabled') as HTMLInputElement).checked = settings.enabled;
+  (document.getElementById('reconcileAutoPin') as HTMLInputElement).checked = settings.autoPin;
+  (document.getElementById('reconcileMirror') as HTMLInputElement).checked = settings.mirrorMfs;
+  (document.getElementById('reconcileInterval') as HTMLInputElement).value = String(settings.intervalHours);
+
+  const statusEl = document.getElementById('reconcileStatus');
+  if (statusEl) {
+    statusEl.textContent = (last
+      ? `${new Date(last.at).toLocaleString()}: ${last.obligated} CIDs under contract, ${last.missing.length} missing`
+        + ` (${last.queued} queued), ${last.orphans.length} orphaned`
+        + (last.errors.length > 0 ? ` · ${last.errors.join('; ')}` : '')
+      : 'Not checked since the agent started')
+      + (nextRunAt ? ` · next ${new Date(nextRunAt).toLocaleString()}` : '');
+  }
+  (document.getElementById('cleanOrphans') as HTMLButtonElement).disabled = !last || last.orphans.length === 0;
+
+  const listEl = document.getElementById('reconcileOrphans');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const orphan of (last?.orphans ?? []).slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status warn';
+    row.textContent = `${orphan.cid} · pinned for ${orphan.contractIds.join(', ')}, no longer on chain`;
+    listEl.appendChild(row);
+  }
+  loadMfsMirror(settings.mirrorMfs);
+}
+
+async function loadMfsMirror(enabled: boolean): Promise<void> {
+  const statusEl = document.getElementById('mirrorStatus');
+  const browseButton = document.getElementById('browseMirror') as HTMLButtonElement | null;
+  if (!statusEl || !browseButton) return;
+  const { last, roots } = await ipcRenderer.invoke('get-mfs-mirror');
+  browseButton.disabled = roots.length === 0;
+  if (!enabled) {
+    statusEl.textContent = 'MFS mirror off';
+  } else if (!last) {
+    statusEl.textContent = 'MFS mirror not synced since the agent started';
+  } else {
+    statusEl.className = last.errors.length > 0 ? 'network-status warn' : 'network-status';
+    statusEl.textContent = `MFS /spk: ${last.entries} entries in ${last.contracts} contracts`
+      + ` (${last.added} added, ${last.removed} removed ${new Date(last.at).toLocaleString()})`
+      + (last.errors.length > 0 ? ` · ${last.errors.join('; ')}` : '');
+  }
+}
+
+async function browseMfsMirror(): Promise<void> {
+  const result = await ipcRenderer.invoke('open-mfs-mirror');
+  if (!result.success) alert(result.errors.join(', '));
+}
+
+async function savePinReconciliation(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-pin-reconcile-settings', {
+    enabled: (document.getElementById('reconcileEnabled') as HTMLInputElement).checked,
+    autoPin: (document.getElementById('reconcileAutoPin') as HTMLInputElement).checked,
+    mirrorMfs: (document.getElementById('reconcileMirror') as HTMLInputElement).checked,
+    intervalHours: Number((document.getElementById('reconcileInterval') as HTMLInputElement).value),
+  });
+  if (!result.success) {
+    alert(`Failed to apply reconciliation settings: ${result.errors.join(', ')}`);
+  }
+  loadPinReconciliation();
+}
+
+async function runPinReconciliation(): Promise<void> {
+  const statusEl = document.getElementById('reconcileStatus');
+  if (statusEl) statusEl.textContent = 'Comparing pins with on-chain contracts…';
+  const result = await ipcRenderer.invoke('reconcile-pins');
+  if (!result.success) {
+    alert(`Reconciliation failed: ${result.errors.join(', ')}`);
+  }
+  loadPinReconciliation();
+}
+
+async function cleanOrphanPins(): Promise<void> {
+  if (!confirm('Unpin every orphaned CID? Contracts made up only of orphans go to the trash first; other content is freed at the next garbage collection.')) return;
+  const result = await ipcRenderer.invoke('clean-orphan-pins');
+  if (!result.success) {
+    alert(`Cleanup failed: ${result.errors.join(', ')}`);
+  } else if (result.cleanup.errors.length > 0) {
+    alert(`Some pins could not be removed: ${result.cleanup.errors.join(', ')}`);
+  }
+  loadPinReconciliation();
+  loadContractTrash();
+}
+
+const REPLICATION_TIERS = ['hot', 'warm', 'cold'];
+
+async function loadReplicationTiers(): Promise<void> {
+  const settingsEl = document.getElementById('tierSettings');
+  const contractsEl = document.getElementById('tierContracts');
+  if (!settingsEl || !contractsEl) return;
+  const { tiers, contracts } = await ipcRenderer.invoke('get-replication-tiers');
+
+  settingsEl.innerHTML = '';
+  for (const status of tiers) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${status.tier}: ${status.contracts} contracts, ${status.cids} CIDs`
+      + (status.nextReprovideAt ? ` · next announced ${new Date(status.nextReprovideAt).toLocaleString()}` : '');
+    const hours = document.createElement('input');
+    hours.type = 'number';
+    hours.min = '0';
+    hours.max = '168';
+    hours.id = `tierHours-${status.tier}`;
+    hours.title = 'Hours between announcements, 0 to leave them to Kubo';
+    hours.value = String(status.settings.reprovideHours);
+    const target = document.createElement('input');
+    target.type = 'number';
+    target.min = '500';
+    target.max = '60000';
+    target.id = `tierTarget-${status.tier}`;
+    target.title = 'Challenge deadline in ms, blank for the PoA latency budget';
+    target.value = status.settings.challengeTargetMs ?? '';
+    row.append(hours, target);
+    settingsEl.appendChild(row);
+  }
+
+  contractsEl.innerHTML = '';
+  for (const contract of contracts.slice(0, 20)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${contract.id}${contract.account ? ` @${contract.account}` : ''} · ${contract.cids} CIDs `;
+    const select = document.createElement('select');
+    for (const tier of REPLICATION_TIERS) {
+      const option = document.createElement('option');
+      option.value = tier;
+      option.textContent = tier;
+      select.appendChild(option);
+    }
+    select.value = contract.tier;
+    select.addEventListener('change', () => setContractTier(contract.id, select.value));
+    row.appendChild(select);
+    contractsEl.appendChild(row);
+  }
+  if (contracts.length === 0) contractsEl.textContent = 'No contracts stored';
+}
+
+async function setContractTier(contractId: string, tier: string): Promise<void> {
+  const result = await ipcRenderer.invoke('set-contract-tier', contractId, tier);
+  if (!result.success) alert(`Failed to change tier: ${result.errors.join(', ')}`);
+  loadReplicationTiers();
+}
+
+async function saveReplicationTiers(): Promise<void> {
+  const update: Record<string, { reprovideHours: number; challengeTargetMs: number | null }> = {};
+  for (const tier of REPLICATION_TIERS) {
+    const target = (document.getElementById(`tierTarget-${tier}`) as HTMLInputElement).value.trim();
+    update[tier] = {
+      reprovideHours: Number((document.getElementById(`tierHours-${tier}`) as HTMLInputElement).value),
+      challengeTargetMs: target === '' ? null : Number(target),
+    };
+  }
+  const result = await ipcRenderer.invoke('set-tier-settings', update);
+  if (!result.success) alert(`Failed to apply tier settings: ${result.errors.join(', ')}`);
+  loadReplicationTiers();
+}
+
+function selectedImportSource(): { kind: 'api' | 'repo'; location: string } | null {
+  const typed = (document.getElementById('importLocation') as HTMLInputElement).value.trim();
+  if (typed) return { kind: /^https?:\/\//.test(typed) ? 'api' : 'repo', location: typed };
+  const selected = (document.getElementById('importSource') as HTMLSelectElement).value;
+  return selected ? JSON.parse(selected) : null;
+}
+
+function renderPinImportResult(last: any): string {
+  return `Last import ${new Date(last.finishedAt).toLocaleString()}: ${last.imported.length} pins,`
+    + ` ${formatBytes(last.bytes)} copied, ${last.skipped} already here, ${last.failed.length} failed,`
+    + ` ${last.registered} matched to contracts` + (last.stoppedReason ? ` · stopped: ${last.stoppedReason}` : '');
+}
+
+async function loadPinImport(): Promise<void> {
+  const selectEl = document.getElementById('importSource') as HTMLSelectElement | null;
+  const statusEl = document.getElementById('importStatus');
+  if (!selectEl || !statusEl) return;
+  const { running, source, progress, last, candidates } = await ipcRenderer.invoke('get-pin-import');
+  selectEl.innerHTML = '';
+  for (const candidate of candidates) {
+    const option = document.createElement('option');
+    option.value = JSON.stringify({ kind: candidate.kind, location: candidate.location });
+    option.textContent = candidate.label;
+    selectEl.appendChild(option);
+  }
+  if (candidates.length === 0) {
+    const option = document.createElement('option');
+    option.value = '';
+    option.textContent = 'No other IPFS node found';
+    selectEl.appendChild(option);
+  }
+  (document.getElementById('cancelImport') as HTMLButtonElement).disabled = !running;
+  statusEl.textContent = running
+    ? `Importing from ${source.location}${progress ? `: ${progress.done} of ${progress.total}` : ''}`
+    : last ? renderPinImportResult(last) : '';
+}
+
+async function listImportPins(): Promise<void> {
+  const statusEl = document.getElementById('importStatus');
+  const listEl = document.getElementById('importPins');
+  const source = selectedImportSource();
+  if (!statusEl || !listEl || !source) return;
+  statusEl.textContent = 'Reading pins…';
+  listEl.innerHTML = '';
+  const result = await ipcRenderer.invoke('list-import-pins', source);
+  if (!result.success) {
+    statusEl.textContent = `Could not read pins: ${result.errors.join(', ')}`;
+    return;
+  }
+  const present = result.pins.filter((pin: any) => pin.present).length;
+  statusEl.textContent = `${result.pins.length} pins, ${present} already on this node`;
+  (document.getElementById('startImport') as HTMLButtonElement).disabled = result.pins.length === present;
+  for (const pin of result.pins.filter((entry: any) => !entry.present).slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = pin.name ? `${pin.name} · ${pin.cid}` : pin.cid;
+    listEl.appendChild(row);
+  }
+}
+
+async function startPinImport(): Promise<void> {
+  const source = selectedImportSource();
+  if (!source || !confirm(`Copy every pin from ${source.location} into this node?`)) return;
+  const startBtn = document.getElementById('startImport') as HTMLButtonElement;
+  startBtn.disabled = true;
+  (document.getElementById('cancelImport') as HTMLButtonElement).disabled = false;
+  const result = await ipcRenderer.invoke('import-pins', source);
+  if (!result.success) alert(`Import failed: ${result.errors.join(', ')}`);
+  (document.getElementById('importPins') as HTMLElement).innerHTML = '';
+  loadPinImport();
+  loadStorageBreakdown();
+}
+
+async function loadScrub(): Promise<void> {
+  const status = await ipcRenderer.invoke('get-scrub-status');
+  (document.getElementById('scrubEnabled') as HTMLInputElement).checked = status.settings.enabled;
+  (document.getElementById('scrubInterval') as HTMLInputElement).value = String(status.settings.intervalHours);
+  (document.getElementById('scrubBudget') as HTMLInputElement).value = String(status.settings.megabytesPerRun);
+
+  const statusEl = document.getElementById('scrubStatus');
+  const listEl = document.getElementById('scrubDamaged');
+  if (!statusEl || !listEl) return;
+  const { last } = status;
+  const cycles = Object.entries(status.cycleCompletedAt)
+    .map(([instance, at]) => `${instance} fully covered ${new Date(at as string).toLocaleDateString()}`);
+  statusEl.textContent = status.running ? 'Scrubbing…'
+    : last ? `Last run ${new Date(last.finishedAt).toLocaleString()}: ${last.checkedBlocks} blocks,`
+      + ` ${formatBytes(last.checkedBytes)}, ${last.damaged.length} damaged`
+      + (cycles.length > 0 ? ` · ${cycles.join(', ')}` : '')
+      : 'Not run yet';
+  listEl.innerHTML = '';
+  for (const block of last?.damaged || []) {
+    const row = document.createElement('div');
+    row.className = block.repaired ? 'network-status' : 'network-status warn';
+    row.textContent = `${block.block} (${block.problem}, in ${block.pin}) · ${block.repaired ? 'repaired' : block.error}`;
+    listEl.appendChild(row);
+  }
+}
+
+async function saveScrub(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-scrub-settings', {
+    enabled: (document.getElementById('scrubEnabled') as HTMLInputElement).checked,
+    intervalHours: parseInt((document.getElementById('scrubInterval') as HTMLInputElement).value, 10),
+    megabytesPerRun: parseInt((document.getElementById('scrubBudget') as HTMLInputElement).value, 10),
+  });
+  if (!result.success) alert(`Invalid settings: ${result.errors.join(', ')}`);
+  loadScrub();
+}
+
+async function runScrub(): Promise<void> {
+  const statusEl = document.getElementById('scrubStatus');
+  if (statusEl) statusEl.textContent = 'Scrubbing…';
+  const result = await ipcRenderer.invoke('run-scrub');
+  if (!result.success) alert(`Scrub failed: ${result.errors.join(', ')}`);
+  loadScrub();
+}
+
+async function loadDiskHealth(refresh = false): Promise<void> {
+  const { settings, disks } = await ipcRenderer.invoke('get-disk-health', refresh);
+  (document.getElementById('diskHealthEnabled') as HTMLInputElement).checked = settings.enabled;
+  (document.getElementById('diskHealthInterval') as HTMLInputElement).value = String(settings.intervalHours);
+  renderDiskHealth(disks);
+}
+
+function renderDiskHealth(disks: any[]): void {
+  const listEl = document.getElementById('diskHealthDisks');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  if (disks.length === 0) {
+    listEl.textContent = 'Not checked yet';
+    return;
+  }
+  for (const disk of disks) {
+    const row = document.createElement('div');
+    row.className = disk.state === 'prefail' ? 'network-status warn' : 'network-status';
+    const name = disk.model ? `${disk.model} (${disk.device})` : disk.device || 'Unknown disk';
+    row.textContent = `${disk.instance} · ${name} · `
+      + (disk.state === 'prefail' ? `pre-failure signs: ${disk.indicators.join(', ')}`
+        : disk.state === 'healthy' ? `healthy, checked ${new Date(disk.checkedAt).toLocaleString()}` : disk.reason);
+    if (disk.state === 'prefail') {
+      const button = document.createElement('button');
+      button.textContent = 'Migrate Repo';
+      button.addEventListener('click', () => moveRepo(disk.instance));
+      row.appendChild(button);
+    }
+    listEl.appendChild(row);
+  }
+}
+
+async function saveDiskHealth(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-disk-health-settings', {
+    enabled: (document.getElementById('diskHealthEnabled') as HTMLInputElement).checked,
+    intervalHours: parseInt((document.getElementById('diskHealthInterval') as HTMLInputElement).value, 10),
+  });
+  if (!result.success) alert(`Invalid settings: ${result.errors.join(', ')}`);
+  loadDiskHealth();
+}
+
+async function moveRepo(instance: string): Promise<void> {
+  if (!confirm(`Copy the ${instance} repo to another drive? The daemon stops until the copy is done; the old repo is left in place.`)) return;
+  const statusEl = document.getElementById('repoMoveStatus');
+  const response = await ipcRenderer.invoke('move-repo', instance);
+  if (!response.success) {
+    if (statusEl) statusEl.textContent = response.errors.length > 0 ? `Move failed: ${response.errors.join(', ')}` : '';
+    return;
+  }
+  const { result } = response;
+  if (statusEl) {
+    statusEl.textContent = `Moved ${formatBytes(result.copiedBytes)} to ${result.to}. Delete ${result.from} once the node is answering challenges again.`;
+  }
+  loadDiskHealth();
+}
+
+async function exportContractCar(): Promise<void> {
+  const statusEl = document.getElementById('carStatus');
+  const contractId = (document.getElementById('carContract') as HTMLInputElement).value.trim();
+  if (!statusEl) return;
+  if (!contractId) {
+    statusEl.textContent = 'Enter the contract to export';
+    return;
+  }
+  statusEl.textContent = `Exporting ${contractId}…`;
+  const response = await ipcRenderer.invoke('export-contract-car', contractId);
+  if (!response.success) {
+    statusEl.textContent = response.errors.length > 0 ? `Export failed: ${response.errors.join(', ')}` : '';
+    return;
+  }
+  const { result } = response;
+  const bytes = result.files.reduce((sum: number, file: any) => sum + file.bytes, 0);
+  statusEl.textContent = `Wrote ${result.files.length} CARs (${formatBytes(bytes)}) to ${result.dir}`
+    + (result.skipped.length > 0 ? ` · ${result.skipped.length} CIDs not pinned, left out` : '');
+}
+
+async function importCarArchives(folder: boolean): Promise<void> {
+  const statusEl = document.getElementById('carStatus');
+  const listEl = document.getElementById('carFiles');
+  if (!statusEl || !listEl) return;
+  const paths: string[] = await ipcRenderer.invoke('choose-car-files', folder);
+  if (paths.length === 0) return;
+  const contractId = (document.getElementById('carContract') as HTMLInputElement).value.trim();
+  statusEl.textContent = 'Verifying and importing…';
+  listEl.innerHTML = '';
+  const response = await ipcRenderer.invoke('import-car-files', { paths, contractId: contractId || undefined });
+  if (!response.success) {
+    statusEl.textContent = `Import failed: ${response.errors.join(', ')}`;
+    return;
+  }
+  const { result } = response;
+  statusEl.textContent = `${result.imported.length} roots imported`
+    + (result.registered ? ` and registered under ${result.contractId}` : '')
+    + (result.missingRoots.length > 0 ? ` · ${result.missingRoots.length} expected roots missing` : '')
+    + (result.unexpectedRoots.length > 0 ? ` · ${result.unexpectedRoots.length} unexpected roots dropped` : '');
+  for (const file of result.files) {
+    const row = document.createElement('div');
+    row.className = file.error ? 'network-status warn' : 'network-status';
+    row.textContent = `${file.path} · ${file.blocks} blocks, ${formatBytes(file.bytes)}`
+      + (file.uncheckedBlocks > 0 ? ` (${file.uncheckedBlocks} not hash-checked)` : '')
+      + (file.error ? ` — ${file.error}` : ' — verified');
+    listEl.appendChild(row);
+  }
+  loadStorageBreakdown();
+}
+
+interface SigningRequest {
+  id: string;
+  description: string;
+  method: string;
+  handoffUrl: string | null;
+  hasUri: string | null;
+  qrCode: string | null;
+}
+
+const signingRequests = new Map<string, SigningRequest>();
+
+async function loadHiveSigning(): Promise<void> {
+  const { settings, hasSession, pending } = await ipcRenderer.invoke('get-hive-signing');
+  (document.getElementById('signingMethod') as HTMLSelectElement).value = settings.method;
+  (document.getElementById('hasServer') as HTMLInputElement).value = settings.hasServer;
+  const statusEl = document.getElementById('signingStatus');
+  if (statusEl) {
+    statusEl.textContent = hasSession
+      ? `Wallet app paired for @${hasSession.username} until ${new Date(hasSession.expiresAt).toLocaleString()}`
+      : 'No wallet app paired';
+  }
+  signingRequests.clear();
+  for (const request of pending) signingRequests.set(request.id, request);
+  renderSigningRequests();
+}
+
+function renderSigningRequests(): void {
+  const listEl = document.getElementById('signingRequests');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const request of signingRequests.values()) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = request.method === 'keychain'
+      ? `${request.description}: approve it in Hive Keychain in your browser (${request.handoffUrl})`
+      : `${request.description}: ${request.qrCode ? 'scan with your wallet app' : 'approve it in your wallet app'}`;
+    if (request.qrCode) {
+      const image = document.createElement('img');
+      image.src = request.qrCode;
+      image.alt = request.hasUri || '';
+      image.style.display = 'block';
+      row.appendChild(image);
+    }
+    const cancel = document.createElement('button');
+    cancel.textContent = 'Cancel';
+    cancel.addEventListener('click', () => ipcRenderer.invoke('cancel-hive-signing', request.id));
+    row.appendChild(cancel);
+    listEl.appendChild(row);
+  }
+}
+
+async function saveHiveSigning(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-hive-signing-settings', {
+    method: (document.getElementById('signingMethod') as HTMLSelectElement).value,
+    hasServer: (document.getElementById('hasServer') as HTMLInputElement).value.trim(),
+  });
+  if (!result.success) {
+    alert(`Failed to apply signing settings: ${result.errors.join(', ')}`);
+  }
+  loadHiveSigning();
+}
+
+async function pairHiveAuth(): Promise<void> {
+  const result = await ipcRenderer.invoke('pair-hive-auth');
+  if (!result.success) {
+    alert(`Pairing failed: ${result.errors.join(', ')}`);
+  }
+  loadHiveSigning();
+}
+
+async function forgetHiveAuth(): Promise<void> {
+  await ipcRenderer.invoke('forget-hive-auth');
+  loadHiveSigning();
+}
+
+function chainRegistrationForm(): { endpoint: string; domain: string; autoRenew: boolean } {
+  return {
+    endpoint: (document.getElementById('chainEndpoint') as HTMLInputElement).value.trim(),
+    domain: (document.getElementById('chainDomain') as HTMLInputElement).value.trim(),
+    autoRenew: (document.getElementById('chainAutoRenew') as HTMLInputElement).checked,
   };
 }
 
-async function fetchStatus(): Promise<Status | null> {
-  try {
-    const response = await fetch(`${API_URL}/api/status`);
-    return await response.json();
-  } catch (error) {
-    console.error('Failed to fetch status:', error);
-    return null;
+function showChainStep(step: 1 | 2): void {
+  (document.getElementById('chainStep1') as HTMLElement).style.display = step === 1 ? 'block' : 'none';
+  (document.getElementById('chainStep2') as HTMLElement).style.display = step === 2 ? 'block' : 'none';
+}
+
+function renderChainRegistration(state: any, stage?: string): void {
+  const statusEl = document.getElementById('chainRegStatus');
+  if (!statusEl) return;
+  if (stage === 'broadcasting') {
+    statusEl.textContent = 'Waiting for the transaction to be signed…';
+  } else if (stage === 'confirming') {
+    statusEl.textContent = `Broadcast ${state.txId}; waiting for a block to include it…`;
+  } else if (state.lastError) {
+    statusEl.textContent = `Registration failed: ${state.lastError}`;
+  } else if (state.includedAt) {
+    statusEl.textContent = `Registered ${state.peerId} at ${state.endpoint}`
+      + (state.blockNum ? ` in block ${state.blockNum}` : '')
+      + (state.listedAt ? ` · listed on the SPK chain ${new Date(state.listedAt).toLocaleString()}` : ' · not yet seen on the SPK chain');
+  } else {
+    statusEl.textContent = 'Not registered on-chain';
   }
 }
 
-async function fetchPins(): Promise<string[]> {
-  try {
-    const response = await fetch(`${API_URL}/api/pins`);
-    const data = await response.json();
-    return data.pins || [];
-  } catch {
-    return [];
+async function loadChainRegistration(): Promise<void> {
+  const { settings, state } = await ipcRenderer.invoke('get-chain-registration');
+  (document.getElementById('chainEndpoint') as HTMLInputElement).value = settings.endpoint;
+  (document.getElementById('chainDomain') as HTMLInputElement).value = settings.domain;
+  (document.getElementById('chainAutoRenew') as HTMLInputElement).checked = settings.autoRenew;
+  renderChainRegistration(state);
+}
+
+async function reviewChainRegistration(): Promise<void> {
+  const result = await ipcRenderer.invoke('preview-chain-registration', chainRegistrationForm());
+  if (!result.success) {
+    alert(`Invalid registration: ${result.errors.join(', ')}`);
+    return;
   }
+  const { operation, issues } = result.preview;
+  (document.getElementById('chainPreview') as HTMLElement).textContent = JSON.stringify(
+    { ...operation[1], json: JSON.parse(operation[1].json) }, null, 2);
+  (document.getElementById('chainIssues') as HTMLElement).textContent = issues.join('\n');
+  (document.getElementById('chainBroadcast') as HTMLButtonElement).disabled = issues.length > 0;
+  showChainStep(2);
 }
 
-async function saveConfig(): Promise<void> {
-  const usernameInput = document.getElementById('hiveUsername') as HTMLInputElement;
-  const username = usernameInput.value.trim();
+async function broadcastChainRegistration(): Promise<void> {
+  (document.getElementById('chainBroadcast') as HTMLButtonElement).disabled = true;
+  const result = await ipcRenderer.invoke('broadcast-chain-registration', chainRegistrationForm());
+  if (!result.success) {
+    alert(`Registration failed: ${result.errors.join(', ')}`);
+  }
+  showChainStep(1);
+  loadChainRegistration();
+}
 
-  try {
-    await fetch(`${API_URL}/api/config`, {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({ hiveUsername: username }),
+async function verifyChainRegistration(): Promise<void> {
+  const result = await ipcRenderer.invoke('verify-chain-registration');
+  if (!result.success) {
+    alert(`Check failed: ${result.errors.join(', ')}`);
+  }
+  loadChainRegistration();
+}
+
+interface PriceQuote {
+  currency: string;
+  hbd: number;
+  hive: number;
+  source: string;
+  fetchedAt: string;
+}
+
+let priceQuote: PriceQuote | null = null;
+
+// "~$0.012"; empty until a price is known
+function formatFiat(amount: number, token: 'hbd' | 'hive' = 'hbd'): string {
+  if (!priceQuote) return '';
+  const fiat = amount * priceQuote[token];
+  return `~${new Intl.NumberFormat(undefined, {
+    style: 'currency',
+    currency: priceQuote.currency.toUpperCase(),
+    ...(fiat !== 0 && Math.abs(fiat) < 1 ? { maximumSignificantDigits: 2 } : {}),
+  }).format(fiat)}`;
+}
+
+async function loadEarningsHistory(): Promise<void> {
+  const granularity = (document.getElementById('earningsGranularity') as HTMLSelectElement).value;
+  const result = await ipcRenderer.invoke('get-earnings-history', { granularity });
+  const statusEl = document.getElementById('earningsSeriesStatus');
+  const listEl = document.getElementById('earningsSeries');
+  if (!statusEl || !listEl) return;
+  listEl.innerHTML = '';
+  if (!result.success) {
+    statusEl.textContent = `Earnings history unavailable: ${result.errors.join(', ')}`;
+    return;
+  }
+  const { periods } = result.series;
+  const challenges = periods.reduce((sum: number, period: any) => sum + period.challenges, 0);
+  const passed = periods.reduce((sum: number, period: any) => sum + period.passed, 0);
+  const hbd = periods.reduce((sum: number, period: any) => sum + (period.amounts.HBD ?? 0), 0);
+  statusEl.textContent = challenges > 0
+    ? `${challenges} challenges, ${Math.round((passed / challenges) * 100)}% passed, ${hbd.toFixed(3)} HBD ${formatFiat(hbd)}`
+    : 'No challenges in this range';
+  // Newest first, busy periods only
+  for (const period of [...periods].reverse().filter((entry: any) => entry.challenges > 0)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const amounts = Object.entries(period.amounts)
+      .filter(([, amount]) => (amount as number) > 0)
+      .map(([token, amount]) => `${(amount as number).toFixed(3)} ${token}`);
+    row.textContent = `${period.start} · ${period.challenges} challenges, ${Math.round(period.passRate * 100)}% passed`
+      + (amounts.length > 0 ? ` · ${amounts.join(', ')}` : '');
+    listEl.appendChild(row);
+  }
+}
+
+function describeScenario(scenario: any): string {
+  return `${scenario.monthly.totalHbd.toFixed(3)} HBD a month ${formatFiat(scenario.monthly.totalHbd)}`
+    + ` (${scenario.daily.totalHbd.toFixed(4)} a day) storing ${scenario.storedGb.toFixed(1)} GB`
+    + (scenario.monthly.contractHbd > 0 ? `, ${scenario.monthly.contractHbd.toFixed(3)} of it from new contracts` : '');
+}
+
+async function loadEarningsProjection(): Promise<void> {
+  const input = (document.getElementById('projectionCapacity') as HTMLInputElement).value;
+  const result = await ipcRenderer.invoke('get-earnings-projection', input === '' ? undefined : Number(input));
+  const currentEl = document.getElementById('projectionCurrent');
+  const requestedEl = document.getElementById('projectionRequested');
+  const basisEl = document.getElementById('projectionBasis');
+  if (!currentEl || !requestedEl || !basisEl) return;
+  if (!result.success) {
+    requestedEl.textContent = result.errors.join(', ');
+    return;
+  }
+  const { basis, current, quota, requested } = result.projection;
+  currentEl.textContent = `Now: ${describeScenario(current)}`
+    + (quota && quota.storedGb > current.storedGb ? ` · with the quota filled: ${describeScenario(quota)}` : '');
+  requestedEl.textContent = requested ? `At ${requested.capacityGb} GB: ${describeScenario(requested)}` : '';
+  basisEl.textContent = basis.challengesPerGbDay === null
+    ? 'No challenge history yet; only new contracts are counted'
+    : `From the last ${basis.windowDays} days: ${basis.challengesPerDay.toFixed(1)} challenges a day, `
+      + `${Math.round((basis.passRate ?? 0) * 100)}% passed`
+      + (basis.marketHbdPerGbMonth !== null
+        ? ` · open offers pay about ${basis.marketHbdPerGbMonth.toFixed(4)} HBD per GB a month for ${basis.marketGb.toFixed(1)} GB`
+        : ' · the contract market could not be read');
+}
+
+async function exportEarnings(format: 'csv' | 'json'): Promise<void> {
+  const result = await ipcRenderer.invoke('export-earnings', { format });
+  if (result.success) {
+    alert(`Exported ${result.result.rows} entries to ${result.result.path}`);
+  } else if (result.errors.length > 0) {
+    alert(`Export failed: ${result.errors.join(', ')}`);
+  }
+}
+
+async function loadPriceFeed(): Promise<void> {
+  const { settings, currencies, quote, stale, lastError } = await ipcRenderer.invoke('get-price-feed');
+  priceQuote = quote;
+  const select = document.getElementById('priceCurrency') as HTMLSelectElement;
+  if (select.options.length === 0) {
+    for (const currency of currencies) select.add(new Option(currency.toUpperCase(), currency));
+  }
+  select.value = settings.currency;
+  const statusEl = document.getElementById('priceStatus');
+  if (statusEl) {
+    statusEl.textContent = quote
+      ? `1 HBD ${formatFiat(1)} · 1 HIVE ${formatFiat(1, 'hive')} · from ${quote.source === 'coingecko' ? 'CoinGecko' : 'the Hive internal market'}`
+        + ` ${new Date(quote.fetchedAt).toLocaleString()}${stale ? ' (out of date)' : ''}`
+      : `No price yet${lastError ? `: ${lastError}` : ''}`;
+  }
+}
+
+async function savePriceFeed(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-price-feed-settings', {
+    currency: (document.getElementById('priceCurrency') as HTMLSelectElement).value,
+  });
+  if (!result.success) {
+    alert(`Failed to apply price settings: ${result.errors.join(', ')}`);
+  }
+  await loadPriceFeed();
+  updateUI();
+}
+
+async function loadDailySummary(): Promise<void> {
+  const { settings, nextAt, last } = await ipcRenderer.invoke('get-daily-summary');
+  (document.getElementById('dailySummaryEnabled') as HTMLInputElement).checked = settings.enabled;
+  (document.getElementById('dailySummaryTime') as HTMLInputElement).value = settings.time;
+  const statusEl = document.getElementById('dailySummaryStatus');
+  if (statusEl) {
+    const parts = [nextAt ? `Next ${new Date(nextAt).toLocaleString()}` : 'Off'];
+    if (last) parts.push(`last sent ${new Date(last.sentAt).toLocaleString()}: ${last.passed}/${last.challenges} passed`);
+    statusEl.textContent = parts.join(' · ');
+  }
+}
+
+async function saveDailySummary(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-daily-summary-settings', {
+    enabled: (document.getElementById('dailySummaryEnabled') as HTMLInputElement).checked,
+    time: (document.getElementById('dailySummaryTime') as HTMLInputElement).value,
+  });
+  if (!result.success) {
+    alert(`Failed to apply daily summary settings: ${result.errors.join(', ')}`);
+  }
+  await loadDailySummary();
+}
+
+async function loadAnalytics(): Promise<void> {
+  const kind = (document.getElementById('analyticsKind') as HTMLSelectElement).value;
+  const result = await ipcRenderer.invoke('get-analytics', { kind: kind || undefined, limit: 50 });
+  if (!result.success) return;
+  const { status, events } = result;
+  (document.getElementById('analyticsEnabled') as HTMLInputElement).checked = status.settings.enabled;
+  (document.getElementById('analyticsRetention') as HTMLInputElement).value = String(status.settings.retentionDays);
+  (document.getElementById('analyticsAllowExport') as HTMLInputElement).checked = status.settings.allowExport;
+  (document.getElementById('exportAnalytics') as HTMLButtonElement).disabled = !status.settings.allowExport;
+  const statusEl = document.getElementById('analyticsStatus');
+  if (statusEl) {
+    statusEl.textContent = status.total > 0
+      ? `${status.total} events since ${new Date(status.oldest).toLocaleString()}`
+        + ` · ${status.counts['agent.crash'] ?? 0} agent crashes, ${status.counts['daemon.crash'] ?? 0} daemon crashes`
+      : 'No events recorded';
+  }
+  const eventsEl = document.getElementById('analyticsEvents');
+  if (eventsEl) {
+    eventsEl.textContent = events
+      .map((event: any) => `${new Date(event.at).toLocaleString()} ${event.kind} ${JSON.stringify(event.data)}`)
+      .join('\n');
+  }
+}
+
+async function saveAnalytics(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-analytics-settings', {
+    enabled: (document.getElementById('analyticsEnabled') as HTMLInputElement).checked,
+    retentionDays: Number((document.getElementById('analyticsRetention') as HTMLInputElement).value),
+    allowExport: (document.getElementById('analyticsAllowExport') as HTMLInputElement).checked,
+  });
+  if (!result.success) {
+    alert(`Failed to apply analytics settings: ${result.errors.join(', ')}`);
+  }
+  await loadAnalytics();
+}
+
+async function exportAnalytics(): Promise<void> {
+  const result = await ipcRenderer.invoke('export-analytics');
+  if (result.success) {
+    alert(`Exported ${result.result.events} anonymized events to ${result.result.path}`);
+  } else if (result.errors.length > 0) {
+    alert(`Export failed: ${result.errors.join(', ')}`);
+  }
+}
+
+async function purgeAnalytics(): Promise<void> {
+  if (!confirm('Delete every recorded agent event? This cannot be undone.')) return;
+  await ipcRenderer.invoke('purge-analytics');
+  await loadAnalytics();
+}
+
+async function loadBackup(): Promise<void> {
+  const status = await ipcRenderer.invoke('get-backup-status');
+  (document.getElementById('backupAutoIpfs') as HTMLInputElement).checked = status.settings.autoIpfs;
+  (document.getElementById('backupInterval') as HTMLInputElement).value = String(status.settings.intervalHours);
+  (document.getElementById('backupKeep') as HTMLInputElement).value = String(status.settings.keep);
+  const statusEl = document.getElementById('backupStatus');
+  if (statusEl) {
+    const latest = status.ipfs[0];
+    statusEl.textContent = status.restorePending
+      ? 'A restored backup is waiting; restart the agent to finish'
+      : status.lastError
+        ? `Last backup failed: ${status.lastError}`
+        : latest
+          ? `Last IPFS backup ${new Date(latest.at).toLocaleString()}`
+          : status.settings.autoIpfs && !status.passphraseSet
+            ? 'Set a passphrase for automatic backups'
+            : 'No IPFS backups yet';
+    statusEl.classList.toggle('warn', status.restorePending || !!status.lastError);
+  }
+  const listEl = document.getElementById('backupList');
+  if (listEl) {
+    listEl.textContent = status.ipfs
+      .map((backup: any) => `${new Date(backup.at).toLocaleString()} ${formatBytes(backup.bytes)} ${backup.cid}`)
+      .join('\n');
+  }
+}
+
+function backupPassphrase(): string {
+  return (document.getElementById('backupPassphrase') as HTMLInputElement).value;
+}
+
+async function saveBackup(): Promise<void> {
+  const passphrase = backupPassphrase();
+  const result = await ipcRenderer.invoke('set-backup-settings', {
+    autoIpfs: (document.getElementById('backupAutoIpfs') as HTMLInputElement).checked,
+    intervalHours: Number((document.getElementById('backupInterval') as HTMLInputElement).value),
+    keep: Number((document.getElementById('backupKeep') as HTMLInputElement).value),
+  }, passphrase || undefined);
+  if (!result.success) {
+    alert(`Failed to apply backup settings: ${result.errors.join(', ')}`);
+  }
+  await loadBackup();
+}
+
+async function exportBackup(): Promise<void> {
+  const result = await ipcRenderer.invoke('export-backup', backupPassphrase());
+  if (result.success) {
+    alert(`Saved an encrypted backup (${formatBytes(result.result.bytes)}) to ${result.result.path}`);
+  } else if (result.errors.length > 0) {
+    alert(`Backup failed: ${result.errors.join(', ')}`);
+  }
+}
+
+async function finishRestore(result: any): Promise<void> {
+  if (!result.success) {
+    if (result.errors.length > 0) alert(`Restore failed: ${result.errors.join(', ')}`);
+    return;
+  }
+  await loadBackup();
+  if (confirm(`Restored the backup of ${new Date(result.result.createdAt).toLocaleString()}. `
+    + 'Earnings history is swapped in when the agent restarts. Restart now?')) {
+    await ipcRenderer.invoke('restart-after-restore');
+  }
+}
+
+async function restoreBackup(): Promise<void> {
+  if (!confirm('Restoring replaces this machine\'s earnings history and settings with the backup. Continue?')) return;
+  await finishRestore(await ipcRenderer.invoke('restore-backup', backupPassphrase()));
+}
+
+async function restoreBackupCid(): Promise<void> {
+  const cid = (document.getElementById('backupCid') as HTMLInputElement).value;
+  if (!confirm('Restoring replaces this machine\'s earnings history and settings with the backup. Continue?')) return;
+  await finishRestore(await ipcRenderer.invoke('restore-backup-cid', cid, backupPassphrase()));
+}
+
+async function backupNow(): Promise<void> {
+  const result = await ipcRenderer.invoke('backup-to-ipfs-now');
+  if (!result.success) {
+    alert(`Backup failed: ${result.errors.join(', ')}`);
+  }
+  await loadBackup();
+}
+
+async function loadEarningsAnomaly(): Promise<void> {
+  const { settings, anomaly } = await ipcRenderer.invoke('get-earnings-anomaly');
+  (document.getElementById('anomalyEnabled') as HTMLInputElement).checked = settings.enabled;
+  (document.getElementById('anomalyDrop') as HTMLInputElement).value = String(settings.dropPercent);
+  (document.getElementById('anomalyPassRate') as HTMLInputElement).value = String(settings.passRateDropPoints);
+  const statusEl = document.getElementById('anomalyStatus');
+  if (!statusEl) return;
+  statusEl.className = anomaly.anomalous ? 'network-status warn' : 'network-status';
+  if (anomaly.anomalous) {
+    statusEl.textContent = [anomaly.message, ...anomaly.causes.slice(1)].join(' · ');
+  } else if (!anomaly.baseline) {
+    statusEl.textContent = 'Not enough challenge history yet to compare against';
+  } else {
+    statusEl.textContent = `Last 7 days: ${anomaly.recent.hbd.toFixed(3)} HBD against ${anomaly.baseline.hbd.toFixed(3)} a week before`;
+  }
+}
+
+async function saveEarningsAnomaly(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-anomaly-settings', {
+    enabled: (document.getElementById('anomalyEnabled') as HTMLInputElement).checked,
+    dropPercent: Number((document.getElementById('anomalyDrop') as HTMLInputElement).value),
+    passRateDropPoints: Number((document.getElementById('anomalyPassRate') as HTMLInputElement).value),
+  });
+  if (!result.success) {
+    alert(`Failed to apply earnings alert settings: ${result.errors.join(', ')}`);
+  }
+  await loadEarningsAnomaly();
+}
+
+// From the earnings drop notification: check reachability again and show where it stands
+async function showConnectivityDiagnostics(): Promise<void> {
+  const networkStatusEl = document.getElementById('networkStatus');
+  networkStatusEl?.scrollIntoView({ behavior: 'smooth', block: 'center' });
+  const { reachability, hint } = await ipcRenderer.invoke('check-connectivity');
+  if (networkStatusEl) {
+    networkStatusEl.textContent = `Network: ${reachability}${hint ? ` - ${hint}` : ''}`;
+    networkStatusEl.classList.toggle('unreachable', reachability === 'Unreachable');
+  }
+}
+
+const RANK_LABELS: Record<string, string> = { earnings: 'By earnings', reliability: 'By proofs passed', reputation: 'By reputation' };
+
+async function loadNetworkRank(refresh = false): Promise<void> {
+  const { settings, rank, lastError } = await ipcRenderer.invoke('get-network-rank', refresh);
+  (document.getElementById('networkRankNotify') as HTMLInputElement).checked = settings.notifyWeekly;
+  const statusEl = document.getElementById('networkRankStatus');
+  if (statusEl) {
+    statusEl.className = lastError || (rank && !rank.found) ? 'network-status warn' : 'network-status';
+    statusEl.textContent = !rank
+      ? `No rank yet${lastError ? `: ${lastError}` : ''}`
+      : rank.found
+        ? `Among ${rank.totalNodes} storage nodes, as of ${new Date(rank.fetchedAt).toLocaleString()}`
+        : `This node isn't among the ${rank.totalNodes} storage nodes the network lists`;
+  }
+  const listEl = document.getElementById('networkRanks');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const [criterion, entry] of Object.entries(rank?.ranks ?? {}) as Array<[string, any]>) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    const value = criterion === 'earnings' ? `${entry.value.toFixed(3)} HBD`
+      : criterion === 'reliability' ? `${(entry.value * 100).toFixed(1)}%` : String(entry.value);
+    row.textContent = `${RANK_LABELS[criterion] ?? criterion}: #${entry.rank} (${value})`;
+    listEl.appendChild(row);
+  }
+}
+
+async function saveNetworkRank(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-network-rank-settings', {
+    notifyWeekly: (document.getElementById('networkRankNotify') as HTMLInputElement).checked,
+  });
+  if (!result.success) {
+    alert(`Failed to apply network rank settings: ${result.errors.join(', ')}`);
+  }
+}
+
+const GOAL_PERIOD_LABELS: Record<string, string> = { total: 'in total', day: 'today', week: 'this week', month: 'this month' };
+let milestoneGoals: any[] = [];
+
+function parseThresholds(id: string): number[] {
+  return (document.getElementById(id) as HTMLInputElement).value
+    .split(',').map((value) => value.trim()).filter((value) => value !== '').map(Number);
+}
+
+async function loadMilestones(): Promise<void> {
+  const { thresholds, goals } = await ipcRenderer.invoke('get-milestones');
+  (document.getElementById('milestonePassed') as HTMLInputElement).value = thresholds.challengesPassed.join(', ');
+  (document.getElementById('milestoneStreak') as HTMLInputElement).value = thresholds.consecutivePasses.join(', ');
+  (document.getElementById('milestoneHbd') as HTMLInputElement).value = thresholds.totalHbd.join(', ');
+  milestoneGoals = goals.map((progress: any) => progress.goal);
+
+  const listEl = document.getElementById('goalList');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const { goal, current, reached } of goals) {
+    const unit = goal.metric === 'amount' ? ` ${goal.token || 'HBD'}` : ' challenges passed';
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${goal.target}${unit} ${GOAL_PERIOD_LABELS[goal.period]}: `
+      + `${goal.metric === 'amount' ? current.toFixed(3) : current} so far${reached ? ', reached' : ''}`;
+    const remove = document.createElement('button');
+    remove.textContent = 'Remove';
+    remove.addEventListener('click', () => saveGoals(milestoneGoals.filter((entry) => entry.id !== goal.id)));
+    row.appendChild(remove);
+    listEl.appendChild(row);
+  }
+}
+
+async function saveMilestones(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-milestone-settings', {
+    thresholds: {
+      challengesPassed: parseThresholds('milestonePassed'),
+      consecutivePasses: parseThresholds('milestoneStreak'),
+      totalHbd: parseThresholds('milestoneHbd'),
+    },
+  });
+  if (!result.success) {
+    alert(`Failed to apply milestones: ${result.errors.join(', ')}`);
+  }
+  await loadMilestones();
+}
+
+async function saveGoals(goals: any[]): Promise<void> {
+  const result = await ipcRenderer.invoke('set-milestone-settings', { goals });
+  if (!result.success) {
+    alert(`Failed to save goals: ${result.errors.join(', ')}`);
+  }
+  await loadMilestones();
+}
+
+function addGoal(): void {
+  const metric = (document.getElementById('goalMetric') as HTMLSelectElement).value;
+  saveGoals([...milestoneGoals, {
+    metric: metric === 'challengesPassed' ? 'challengesPassed' : 'amount',
+    token: metric === 'challengesPassed' ? undefined : metric,
+    target: Number((document.getElementById('goalTarget') as HTMLInputElement).value),
+    period: (document.getElementById('goalPeriod') as HTMLSelectElement).value,
+  }]);
+}
+
+async function loadWallet(force = false): Promise<void> {
+  const balancesEl = document.getElementById('walletBalances');
+  const transfersEl = document.getElementById('walletTransfers');
+  if (!balancesEl || !transfersEl) return;
+  const result = await ipcRenderer.invoke('get-wallet-overview', force);
+  (document.getElementById('walletRefresh') as HTMLInputElement).value = String(result.settings.refreshMinutes);
+  if (!result.success) {
+    balancesEl.textContent = result.errors.join(', ');
+    transfersEl.innerHTML = '';
+    return;
+  }
+  const { balances, transfers, fetchedAt, stale } = result.overview;
+  balancesEl.textContent = [
+    `${balances.hive.toFixed(3)} HIVE · ${balances.hivePower.toFixed(3)} HP`,
+    `${balances.hbd.toFixed(3)} HBD · ${balances.savingsHbd.toFixed(3)} HBD in savings`,
+    `${balances.spk.toFixed(3)} SPK · ${balances.spkPower.toFixed(3)} SPK power · ${balances.larynx.toFixed(3)} LARYNX`,
+    `${balances.broca.toFixed(0)} of ${balances.brocaMax.toFixed(0)} BROCA`,
+    stale ? `As of ${new Date(fetchedAt).toLocaleString()}; refresh failed: ${stale}` : `Updated ${new Date(fetchedAt).toLocaleTimeString()}`,
+  ].join('\n');
+
+  transfersEl.innerHTML = '';
+  for (const transfer of transfers.slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = transfer.type === 'claim_reward_balance'
+      ? `${new Date(transfer.at).toLocaleString()} · claimed ${transfer.amount}`
+      : `${new Date(transfer.at).toLocaleString()} · ${transfer.amount} ${transfer.from} → ${transfer.to}`
+        + (transfer.type === 'transfer_to_savings' ? ' (savings)' : '')
+        + (transfer.memo ? ` · ${transfer.memo}` : '');
+    transfersEl.appendChild(row);
+  }
+}
+
+async function saveWalletSettings(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-wallet-settings', {
+    refreshMinutes: Number((document.getElementById('walletRefresh') as HTMLInputElement).value),
+  });
+  if (!result.success) {
+    alert(`Failed to apply wallet settings: ${result.errors.join(', ')}`);
+  }
+}
+
+async function loadBroca(force = false): Promise<void> {
+  const statusEl = document.getElementById('brocaStatus');
+  const curveEl = document.getElementById('brocaCurve');
+  if (!statusEl || !curveEl) return;
+  const result = await ipcRenderer.invoke('get-broca-status', force);
+  if (!result.success) {
+    statusEl.textContent = result.errors.join(', ');
+    curveEl.innerHTML = '';
+    return;
+  }
+  const { current, max, fullAt, low, curve } = result.status;
+  statusEl.textContent = `${current.toFixed(0)} of ${max.toFixed(0)} BROCA`
+    + (fullAt ? ` · full ${new Date(fullAt).toLocaleString()}` : ' · full')
+    + (low ? ' · low: storage operations may fail' : '');
+
+  // Regeneration from now until full, scaled to the cap
+  const points: Array<{ broca: number }> = curve.length > 0 ? curve : [{ broca: current }, { broca: current }];
+  const coords = points.map((point, i) => {
+    const x = (i / (points.length - 1)) * 240;
+    const y = max > 0 ? 78 - (point.broca / max) * 76 : 78;
+    return `${x.toFixed(1)},${y.toFixed(1)}`;
+  });
+  curveEl.innerHTML = `<polyline fill="none" stroke="${low ? '#e67e22' : '#3498db'}" stroke-width="2" points="${coords.join(' ')}" />`;
+}
+
+function formatClaimAmounts(amounts: Record<string, number>): string {
+  const parts = Object.entries(amounts).filter(([, amount]) => amount > 0).map(([token, amount]) => `${amount} ${token}`);
+  return parts.length > 0 ? parts.join(', ') : 'nothing';
+}
+
+async function loadRewardClaims(): Promise<void> {
+  const { settings, lastClaimAt, nextScheduledAt, history } = await ipcRenderer.invoke('get-reward-claims');
+  (document.getElementById('claimMode') as HTMLSelectElement).value = settings.mode;
+  (document.getElementById('claimInterval') as HTMLInputElement).value = String(settings.intervalHours);
+  (document.getElementById('claimThresholdHbd') as HTMLInputElement).value = String(settings.thresholds.HBD);
+  (document.getElementById('claimThresholdLarynx') as HTMLInputElement).value = String(settings.thresholds.LARYNX);
+  (document.getElementById('claimThresholdSpk') as HTMLInputElement).value = String(settings.thresholds.SPK);
+
+  const statusEl = document.getElementById('claimStatus');
+  if (statusEl) {
+    statusEl.textContent = (lastClaimAt ? `Last claimed ${new Date(lastClaimAt).toLocaleString()}` : 'Nothing claimed yet')
+      + (nextScheduledAt ? ` · next ${new Date(nextScheduledAt).toLocaleString()}` : '');
+  }
+
+  const listEl = document.getElementById('claimHistory');
+  if (!listEl) return;
+  listEl.innerHTML = '';
+  for (const record of history.slice(0, 10)) {
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${new Date(record.at).toLocaleString()} · ${record.trigger} · ${formatClaimAmounts(record.amounts)}`
+      + (record.error ? ` · failed: ${record.error}` : ` · ${record.txId}`);
+    listEl.appendChild(row);
+  }
+}
+
+async function saveRewardClaims(): Promise<void> {
+  const result = await ipcRenderer.invoke('set-reward-claim-settings', {
+    mode: (document.getElementById('claimMode') as HTMLSelectElement).value,
+    intervalHours: Number((document.getElementById('claimInterval') as HTMLInputElement).value),
+    thresholds: {
+      HBD: Number((document.getElementById('claimThresholdHbd') as HTMLInputElement).value),
+      LARYNX: Number((document.getElementById('claimThresholdLarynx') as HTMLInputElement).value),
+      SPK: Number((document.getElementById('claimThresholdSpk') as HTMLInputElement).value),
+    },
+  });
+  if (!result.success) {
+    alert(`Failed to apply claim settings: ${result.errors.join(', ')}`);
+  }
+  loadRewardClaims();
+}
+
+async function estimateRewardClaim(): Promise<void> {
+  const statusEl = document.getElementById('claimStatus');
+  const result = await ipcRenderer.invoke('estimate-reward-claim');
+  if (!statusEl) return;
+  statusEl.textContent = result.success
+    ? `Accrued: ${formatClaimAmounts(result.estimate.accrued)}. Claim Now would take `
+      + `${result.estimate.claiming.length > 0 ? result.estimate.claiming.join(', ') : 'nothing'}.`
+    : `Estimate failed: ${result.errors.join(', ')}`;
+}
+
+async function claimRewardsNow(): Promise<void> {
+  const result = await ipcRenderer.invoke('claim-rewards-now');
+  if (!result.success) {
+    alert(`Claim failed: ${result.errors.join(', ')}`);
+  } else if (!result.claim) {
+    alert('Nothing has accrued to claim');
+  }
+  loadRewardClaims();
+}
+
+function renderRiskBanner(risks: Array<{ level: string; message: string }>): void {
+  const bannerEl = document.getElementById('riskBanner');
+  if (!bannerEl) return;
+  bannerEl.style.display = risks.length > 0 ? 'block' : 'none';
+  bannerEl.classList.toggle('warning', risks.every((risk) => risk.level === 'warning'));
+  bannerEl.textContent = risks
+    .map((risk) => `${risk.level === 'critical' ? 'Penalty risk' : 'Warning'}: ${risk.message}`)
+    .join('\n');
+}
+
+async function runBenchmark(): Promise<void> {
+  const resultEl = document.getElementById('benchmarkResult');
+  if (!resultEl) return;
+  resultEl.textContent = 'Running…';
+  const result = await ipcRenderer.invoke('run-poa-benchmark', {
+    count: Number((document.getElementById('benchmarkCount') as HTMLInputElement).value),
+    ratePerSec: Number((document.getElementById('benchmarkRate') as HTMLInputElement).value),
+  });
+  if (!result.success) {
+    resultEl.textContent = `Benchmark failed: ${result.errors.join(', ')}`;
+    return;
+  }
+  const { report } = result;
+  const failures = Object.entries<number>(report.failures).map(([cause, count]) => `${count}× ${cause}`);
+  resultEl.textContent = [
+    `${report.passed}/${report.completed} within ${report.deadlineMs}ms · p50 ${report.p50}ms · p95 ${report.p95}ms · max ${report.max}ms`,
+    ...failures,
+  ].join('\n');
+}
+
+async function loadWebhooks(): Promise<void> {
+  const { webhooks, deliveries } = await ipcRenderer.invoke('get-webhooks');
+  const listEl = document.getElementById('webhookList');
+  if (!listEl) return;
+
+  listEl.innerHTML = '';
+  for (const webhook of webhooks) {
+    const last = deliveries.find((delivery: { webhookId: string }) => delivery.webhookId === webhook.id);
+    const row = document.createElement('div');
+    row.className = 'network-status';
+    row.textContent = `${webhook.url} · ${webhook.events.join(', ')}${last ? ` · last: ${last.event} ${last.status}` : ''}`;
+
+    const secret = document.createElement('button');
+    secret.textContent = 'Show Secret';
+    secret.addEventListener('click', async () => {
+      const result = await ipcRenderer.invoke('get-webhook-secret', webhook.id);
+      alert(result.success ? `Signing secret:\n\n${result.secret}` : `Could not read the secret: ${result.errors.join(', ')}`);
     });
-    alert('Configuration saved!');
-  } catch (error) {
-    alert('Failed to save configuration');
+    const test = document.createElement('button');
+    test.textContent = 'Test';
+    test.addEventListener('click', async () => {
+      const delivery = await ipcRenderer.invoke('test-webhook', webhook.id);
+      alert(delivery?.status === 'delivered' ? 'Test delivered.' : `Test failed: ${delivery?.error || 'unknown webhook'}`);
+      loadWebhooks();
+    });
+    const remove = document.createElement('button');
+    remove.textContent = 'Remove';
+    remove.addEventListener('click', async () => {
+      await ipcRenderer.invoke('remove-webhook', webhook.id);
+      loadWebhooks();
+    });
+    row.append(' ', secret, test, remove);
+    listEl.appendChild(row);
+  }
+}
+
+async function addWebhook(): Promise<void> {
+  const urlEl = document.getElementById('webhookUrl') as HTMLInputElement;
+  const events = Array.from(document.querySelectorAll<HTMLInputElement>('.webhook-event:checked')).map((el) => el.value);
+
+  const result = await ipcRenderer.invoke('add-webhook', { url: urlEl.value.trim(), events });
+  if (!result.success) {
+    alert(`Failed to add webhook: ${result.errors.join(', ')}`);
+    return;
+  }
+  alert(`Webhook added. Verify deliveries with this signing secret:\n\n${result.secret}`);
+  urlEl.value = '';
+  loadWebhooks();
+}
+
+async function updateResourceUsage(): Promise<void> {
+  const usageEl = document.getElementById('resourceUsage');
+  if (!usageEl) return;
+
+  const usage: Array<{ name: string; usage: number; limit: number | string | null }> =
+    await ipcRenderer.invoke('get-resource-usage');
+  const shown = usage.filter((entry) => ['Conns', 'ConnsInbound', 'FD', 'Memory'].includes(entry.name));
+  usageEl.textContent = shown
+    .map((entry) => {
+      const value = entry.name === 'Memory' ? formatBytes(entry.usage) : String(entry.usage);
+      const limit = typeof entry.limit === 'number'
+        ? (entry.name === 'Memory' ? formatBytes(entry.limit) : String(entry.limit))
+        : entry.limit || '?';
+      return `${entry.name}: ${value} / ${limit}`;
+    })
+    .join(' · ');
+}
+
+const MAX_LOG_LINES = 500;
+
+function appendLogLine(entry: { timestamp: string; level: string; message: string }): void {
+  const logEl = document.getElementById('daemonLog');
+  if (!logEl) return;
+
+  const atBottom = logEl.scrollTop + logEl.clientHeight >= logEl.scrollHeight - 5;
+  const line = document.createElement('div');
+  line.className = entry.level;
+  line.textContent = `${entry.timestamp.slice(11, 19)} ${entry.message}`;
+  logEl.appendChild(line);
+  while (logEl.childElementCount > MAX_LOG_LINES) {
+    logEl.firstElementChild?.remove();
   }
+  if (atBottom) logEl.scrollTop = logEl.scrollHeight;
+}
+
+async function loadDaemonLogs(): Promise<void> {
+  const level = (document.getElementById('logLevel') as HTMLSelectElement).value;
+  await ipcRenderer.invoke('set-daemon-log-level', level);
+  const entries = await ipcRenderer.invoke('get-daemon-logs', level, MAX_LOG_LINES);
+
+  const logEl = document.getElementById('daemonLog');
+  if (logEl) logEl.innerHTML = '';
+  entries.forEach(appendLogLine);
 }
 
 function formatBytes(bytes: number): string {
@@ -59,13 +2388,24 @@ function formatBytes(bytes: number): string {
   return parseFloat((bytes / Math.pow(k, i)).toFixed(2)) + ' ' + sizes[i];
 }
 
+async function loadUptime(): Promise<void> {
+  const stats = await ipcRenderer.invoke('get-uptime-stats');
+  const el = document.getElementById('uptimeStatus');
+  if (!el) return;
+  const windows = stats.windows.map((window: any) => `${(window.availability * 100).toFixed(1)}% over ${window.days} days`
+    + (window.outages > 0 ? ` (${window.outages} outages)` : ''));
+  el.textContent = `Availability ${windows.join(' · ')}`;
+  el.classList.toggle('warn', stats.windows[0].availability < 0.95);
+}
+
 async function updateUI(): Promise<void> {
   const status = await fetchStatus();
-  const pins = await fetchPins();
+  const pinCount = await fetchPinCount();
 
   const statusDot = document.getElementById('statusDot');
   const statusText = document.getElementById('statusText');
   const peerIdEl = document.getElementById('peerId');
+  const networkStatusEl = document.getElementById('networkStatus');
   const totalHbdEl = document.getElementById('totalHbd');
   const challengesPassedEl = document.getElementById('challengesPassed');
   const streakEl = document.getElementById('streak');
@@ -75,10 +2415,10 @@ async function updateUI(): Promise<void> {
   if (status) {
     if (status.running) {
       statusDot?.classList.add('running');
-      if (statusText) statusText.textContent = 'IPFS Running';
+      if (statusText) statusText.textContent = startupState === 'DaemonUp' ? STARTUP_LABELS.DaemonUp : 'IPFS Running';
     } else {
       statusDot?.classList.remove('running');
-      if (statusText) statusText.textContent = 'IPFS Stopped';
+      if (statusText) statusText.textContent = STARTUP_LABELS[startupState] || 'IPFS Stopped';
     }
 
     if (peerIdEl) {
@@ -87,9 +2427,21 @@ async function updateUI(): Promise<void> {
         : 'Peer ID: Not available';
     }
 
+    if (networkStatusEl && status.connectivity) {
+      const { reachability, hint } = status.connectivity;
+      networkStatusEl.textContent = `Network: ${reachability}${hint ? ` - ${hint}` : ''}`;
+      networkStatusEl.classList.toggle('unreachable', reachability === 'Unreachable');
+    }
+
     if (totalHbdEl) {
       totalHbdEl.textContent = status.earnings.totalHbd.toFixed(3);
     }
+    const totalFiatEl = document.getElementById('totalHbdFiat');
+    if (totalFiatEl) {
+      const { provisionalHbd } = status.earnings;
+      totalFiatEl.textContent = formatFiat(status.earnings.totalHbd)
+        + (provisionalHbd > 0 ? ` (${provisionalHbd.toFixed(3)} not yet final)` : '');
+    }
 
     if (challengesPassedEl) {
       challengesPassedEl.textContent = status.earnings.challengesPassed.toString();
@@ -109,14 +2461,234 @@ async function updateUI(): Promise<void> {
   }
 
   if (pinnedFilesEl) {
-    pinnedFilesEl.textContent = pins.length.toString();
+    pinnedFilesEl.textContent = pinCount.toString();
   }
 }
 
 document.addEventListener('DOMContentLoaded', () => {
   const saveButton = document.getElementById('saveConfig');
   saveButton?.addEventListener('click', saveConfig);
+  document.getElementById('saveBandwidth')?.addEventListener('click', saveBandwidthLimits);
+  document.getElementById('savePeering')?.addEventListener('click', savePeering);
+
+  document.getElementById('saveResourceLimits')?.addEventListener('click', saveResourceLimits);
+  document.getElementById('saveTls')?.addEventListener('click', saveTlsSettings);
+  document.getElementById('trustTls')?.addEventListener('click', trustTlsCertificate);
+  document.getElementById('saveGrpc')?.addEventListener('click', saveGrpcSettings);
+  document.getElementById('discoveryEnabled')?.addEventListener('change', saveDiscoverySettings);
+  document.getElementById('saveRemoteAccess')?.addEventListener('click', saveRemoteAccess);
+  document.getElementById('addWebhook')?.addEventListener('click', addWebhook);
+  document.getElementById('savePoa')?.addEventListener('click', savePoaSettings);
+  document.getElementById('saveBudget')?.addEventListener('click', saveDiagnosticsBudget);
+  document.getElementById('runBenchmark')?.addEventListener('click', runBenchmark);
+  document.getElementById('saveValidator')?.addEventListener('click', saveValidatorSettings);
+  document.getElementById('refreshRegistration')?.addEventListener('click', refreshRegistration);
+  document.getElementById('hiveLogin')?.addEventListener('click', hiveLogin);
+  document.getElementById('hiveLogout')?.addEventListener('click', hiveLogout);
+  document.getElementById('savePayout')?.addEventListener('click', savePayoutAccount);
+  document.getElementById('createVault')?.addEventListener('click', () => vaultAction('create-vault'));
+  document.getElementById('unlockVault')?.addEventListener('click', () => vaultAction('unlock-vault'));
+  document.getElementById('lockVault')?.addEventListener('click', () => vaultAction('lock-vault'));
+  document.getElementById('saveVault')?.addEventListener('click', saveVaultSettings);
+  ipcRenderer.on('vault-state', (_event, status) => {
+    renderVaultStatus(status);
+    loadHiveIdentity();
+  });
+  document.getElementById('saveHiveNodes')?.addEventListener('click', saveHiveNodes);
+  document.getElementById('checkHiveNodes')?.addEventListener('click', checkHiveNodes);
+  document.getElementById('saveSigning')?.addEventListener('click', saveHiveSigning);
+  document.getElementById('pairHas')?.addEventListener('click', pairHiveAuth);
+  document.getElementById('forgetHas')?.addEventListener('click', forgetHiveAuth);
+  document.getElementById('chainReview')?.addEventListener('click', reviewChainRegistration);
+  document.getElementById('chainBack')?.addEventListener('click', () => showChainStep(1));
+  document.getElementById('chainBroadcast')?.addEventListener('click', broadcastChainRegistration);
+  document.getElementById('chainVerify')?.addEventListener('click', verifyChainRegistration);
+  ipcRenderer.on('chain-registration-progress', (_event, progress) => renderChainRegistration(progress.state, progress.stage));
+  document.getElementById('savePrices')?.addEventListener('click', savePriceFeed);
+  document.getElementById('saveDailySummary')?.addEventListener('click', saveDailySummary);
+  document.getElementById('saveMilestones')?.addEventListener('click', saveMilestones);
+  document.getElementById('networkRankNotify')?.addEventListener('change', saveNetworkRank);
+  document.getElementById('saveAnomaly')?.addEventListener('click', saveEarningsAnomaly);
+  document.getElementById('saveAnalytics')?.addEventListener('click', saveAnalytics);
+  document.getElementById('analyticsKind')?.addEventListener('change', loadAnalytics);
+  document.getElementById('exportAnalytics')?.addEventListener('click', exportAnalytics);
+  document.getElementById('purgeAnalytics')?.addEventListener('click', purgeAnalytics);
+  document.getElementById('saveBackup')?.addEventListener('click', saveBackup);
+  document.getElementById('exportBackup')?.addEventListener('click', exportBackup);
+  document.getElementById('restoreBackup')?.addEventListener('click', restoreBackup);
+  document.getElementById('restoreBackupCid')?.addEventListener('click', restoreBackupCid);
+  document.getElementById('backupNow')?.addEventListener('click', backupNow);
+  document.getElementById('refreshNetworkRank')?.addEventListener('click', () => loadNetworkRank(true));
+  document.getElementById('addGoal')?.addEventListener('click', addGoal);
+  document.getElementById('earningsGranularity')?.addEventListener('change', loadEarningsHistory);
+  document.getElementById('runProjection')?.addEventListener('click', loadEarningsProjection);
+  document.getElementById('exportEarningsCsv')?.addEventListener('click', () => exportEarnings('csv'));
+  document.getElementById('exportEarningsJson')?.addEventListener('click', () => exportEarnings('json'));
+  document.getElementById('saveHoneycomb')?.addEventListener('click', saveHoneycomb);
+  document.getElementById('reviewDelegation')?.addEventListener('click', reviewDelegation);
+  document.getElementById('confirmDelegation')?.addEventListener('click', confirmDelegation);
+  ['delegationToken', 'delegationTo', 'delegationAmount'].forEach((id) => {
+    // An edited form needs reviewing again
+    document.getElementById(id)?.addEventListener('input', () => {
+      (document.getElementById('confirmDelegation') as HTMLButtonElement).disabled = true;
+    });
+  });
+  document.getElementById('syncHoneycomb')?.addEventListener('click', syncHoneycomb);
+  document.getElementById('saveWallet')?.addEventListener('click', saveWalletSettings);
+  document.getElementById('refreshWallet')?.addEventListener('click', () => loadWallet(true));
+  document.getElementById('refreshBroca')?.addEventListener('click', () => loadBroca(true));
+  document.getElementById('saveClaims')?.addEventListener('click', saveRewardClaims);
+  document.getElementById('estimateClaim')?.addEventListener('click', estimateRewardClaim);
+  document.getElementById('claimNow')?.addEventListener('click', claimRewardsNow);
+  ipcRenderer.on('reward-claimed', () => loadRewardClaims());
+  ipcRenderer.on('show-connectivity-diagnostics', () => showConnectivityDiagnostics());
+  ipcRenderer.on('tx-queue-update', () => loadTxQueue());
+  ipcRenderer.on('hive-signing-request', (_event, request: SigningRequest) => {
+    signingRequests.set(request.id, request);
+    renderSigningRequests();
+  });
+  ipcRenderer.on('hive-signing-resolved', (_event, outcome: { id: string }) => {
+    signingRequests.delete(outcome.id);
+    loadHiveSigning();
+  });
+  document.getElementById('refreshEligibility')?.addEventListener('click', () => loadEligibility(true));
+  document.getElementById('searchHistory')?.addEventListener('click', () => loadChallengeHistory());
+  document.getElementById('moreHistory')?.addEventListener('click', () => loadChallengeHistory(true));
+  document.getElementById('runValidator')?.addEventListener('click', runValidationRound);
+  document.getElementById('refreshBreakdown')?.addEventListener('click', () => loadStorageBreakdown(true));
+  document.getElementById('refreshMarket')?.addEventListener('click', loadContractMarket);
+  document.getElementById('saveMarket')?.addEventListener('click', saveContractMarket);
+  document.getElementById('savePolicy')?.addEventListener('click', saveContractPolicy);
+  document.getElementById('previewPolicy')?.addEventListener('click', previewContractPolicy);
+  document.getElementById('bandwidthDays')?.addEventListener('change', loadContractBandwidth);
+  document.getElementById('saveExpiry')?.addEventListener('click', saveContractExpiry);
+  document.getElementById('sweepExpiry')?.addEventListener('click', sweepExpiredContracts);
+  document.getElementById('saveTrash')?.addEventListener('click', saveContractTrash);
+  document.getElementById('saveReconcile')?.addEventListener('click', savePinReconciliation);
+  document.getElementById('runReconcile')?.addEventListener('click', runPinReconciliation);
+  document.getElementById('cleanOrphans')?.addEventListener('click', cleanOrphanPins);
+  document.getElementById('browseMirror')?.addEventListener('click', browseMfsMirror);
+  document.getElementById('saveTiers')?.addEventListener('click', saveReplicationTiers);
+  document.getElementById('listImportPins')?.addEventListener('click', listImportPins);
+  document.getElementById('startImport')?.addEventListener('click', startPinImport);
+  document.getElementById('cancelImport')?.addEventListener('click', () => ipcRenderer.invoke('cancel-pin-import'));
+  document.getElementById('saveScrub')?.addEventListener('click', saveScrub);
+  document.getElementById('runScrub')?.addEventListener('click', runScrub);
+  ipcRenderer.on('scrub-progress', (_event, progress) => {
+    const statusEl = document.getElementById('scrubStatus');
+    if (statusEl) {
+      statusEl.textContent = `Scrubbing ${progress.instance}: ${formatBytes(progress.checkedBytes)}`
+        + ` of ${formatBytes(progress.budgetBytes)} · ${progress.pin}`;
+    }
+  });
+  ipcRenderer.on('scrubbed', () => loadScrub());
+  document.getElementById('saveDiskHealth')?.addEventListener('click', saveDiskHealth);
+  document.getElementById('checkDiskHealth')?.addEventListener('click', () => loadDiskHealth(true));
+  ipcRenderer.on('disk-health', (_event, disks) => renderDiskHealth(disks));
+  ipcRenderer.on('repo-move-status', (_event, message) => {
+    const statusEl = document.getElementById('repoMoveStatus');
+    if (statusEl) statusEl.textContent = message;
+  });
+  ipcRenderer.on('repo-move-progress', (_event, progress) => {
+    const statusEl = document.getElementById('repoMoveStatus');
+    if (statusEl && progress.doneBytes < progress.totalBytes) {
+      statusEl.textContent = `Copied ${formatBytes(progress.doneBytes)} of ${formatBytes(progress.totalBytes)}`;
+    }
+  });
+  document.getElementById('exportCar')?.addEventListener('click', exportContractCar);
+  document.getElementById('importCarFiles')?.addEventListener('click', () => importCarArchives(false));
+  document.getElementById('importCarFolder')?.addEventListener('click', () => importCarArchives(true));
+  ipcRenderer.on('car-import-progress', (_event, progress) => {
+    const statusEl = document.getElementById('carStatus');
+    if (statusEl && progress.done < progress.total) {
+      statusEl.textContent = `Verifying ${progress.done + 1} of ${progress.total}: ${progress.file}`;
+    }
+  });
+  ipcRenderer.on('pin-import-progress', (_event, progress) => {
+    const statusEl = document.getElementById('importStatus');
+    if (statusEl) {
+      statusEl.textContent = `Imported ${progress.done} of ${progress.total} pins, ${formatBytes(progress.bytes)}`
+        + (progress.cid ? ` · ${progress.cid}` : '');
+    }
+  });
+  document.getElementById('logLevel')?.addEventListener('change', loadDaemonLogs);
+  ipcRenderer.on('daemon-log', (_event, entry) => appendLogLine(entry));
+  ipcRenderer.on('slashing-risk', (_event, risks) => renderRiskBanner(risks));
+  ipcRenderer.invoke('get-slashing-risks').then(renderRiskBanner);
+  ipcRenderer.on('startup-state-changed', (_event, change) => {
+    startupState = change.state;
+    updateUI();
+  });
+  ipcRenderer.invoke('get-startup-state').then((state: string) => { startupState = state; });
+
+  loadBandwidthLimits();
+  loadPeering();
+  loadResourceLimits();
+  loadTlsSettings();
+  loadGrpcSettings();
+  loadDiscoverySettings();
+  loadRemoteAccess();
+  loadWebhooks();
+  loadPoaSettings();
+  loadDiagnosticsBudget();
+  loadChallengeDiagnostics();
+  loadValidatorSettings();
+  loadStorageBreakdown();
+  loadContractMarket();
+  loadContractPolicy();
+  loadContractBandwidth();
+  loadContractTrash();
+  loadPinReconciliation();
+  loadReplicationTiers();
+  loadPinImport();
+  loadScrub();
+  loadDiskHealth();
+  loadChallengeHistory();
+  loadRegistrationStatus();
+  loadHiveIdentity();
+  loadAccounts();
+  loadHiveNodes();
+  loadHiveSigning();
+  loadTxQueue();
+  loadChainRegistration();
+  loadRewardClaims();
+  loadBroca();
+  loadWallet();
+  loadPriceFeed();
+  loadEarningsHistory();
+  loadEarningsProjection();
+  loadUptime();
+  loadDailySummary();
+  loadMilestones();
+  loadNetworkRank();
+  loadEarningsAnomaly();
+  loadAnalytics();
+  loadBackup();
+  loadHoneycomb();
+  loadDelegations();
+  loadEligibility();
+  loadDaemonLogs();
 
   updateUI();
   setInterval(updateUI, 5000);
+  updateResourceUsage();
+  setInterval(updateResourceUsage, 15000);
+  setInterval(loadPoaSettings, 15000);
+  setInterval(loadChallengeDiagnostics, 15000);
+  setInterval(loadValidatorSettings, 15000);
+  setInterval(loadRegistrationStatus, 15000);
+  setInterval(loadEligibility, 60000);
+  setInterval(loadPriceFeed, 60000);
+  setInterval(loadEarningsHistory, 60000);
+  setInterval(loadUptime, 60000);
+  setInterval(loadDailySummary, 60000);
+  setInterval(loadMilestones, 60000);
+  setInterval(loadNetworkRank, 15 * 60 * 1000);
+  setInterval(loadEarningsAnomaly, 60 * 60 * 1000);
+  setInterval(loadAnalytics, 60000);
+  setInterval(loadBackup, 60000);
+  setInterval(loadContractBandwidth, 60000);
+  setInterval(loadAccounts, 60000);
+  setInterval(loadHiveNodes, 60000);
+  setInterval(loadHoneycomb, 60000);
 });
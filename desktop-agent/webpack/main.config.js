@@ -26,5 +26,10 @@ module.exports = {
   },
   externals: {
     'electron-store': 'commonjs electron-store',
+    // Native addon, loaded from node_modules at runtime
+    'better-sqlite3': 'commonjs better-sqlite3',
+    // Optional native addons ws uses when present
+    bufferutil: 'commonjs bufferutil',
+    'utf-8-validate': 'commonjs utf-8-validate',
   },
 };